@@ -1,17 +1,50 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use crate::error::{Result, TuicrError};
+use crate::input::keymap::{ModeKeymap, effective_keymap};
+use crate::input::keymap_file::Keymap;
 use crate::model::{
-    Comment, CommentType, DiffFile, DiffLine, LineRange, LineSide, ReviewSession, SessionDiffSource,
+    CodeOwners, Comment, CommentReply, CommentSort, CommentType, DiffFile, DiffHunk, DiffLine,
+    FileReview, FileStatus, HistoryAction, IssueLinker, LineOrigin, LineRange, LineSide, Rename,
+    ReviewPosition, ReviewSession, ReviewState, SearchPattern, SessionDiffSource, apply_suggestion,
+    capture_context, collapse_renames, find_duplicate_blocks, find_matching_bracket, format_size,
+    hide_moved_hunks, new_todo_markers, review_stats, structural_diff,
 };
-use crate::persistence::load_latest_session_for_context;
-use crate::theme::Theme;
+use crate::output::ExportOptions;
+use crate::output::webhook::{WebhookConfig, WebhookEvent, WebhookEventKind, WebhookSender};
+use crate::persistence::{
+    archive_session, autosave_session, discard_autosave, load_autosave,
+    load_latest_session_for_context, load_session, save_session,
+};
+use crate::text_edit::TextEditor;
+use crate::theme::{Theme, ThemeArg, resolve_theme};
 use crate::vcs::git::calculate_gap;
-use crate::vcs::{CommitInfo, VcsBackend, VcsInfo, detect_vcs};
+use crate::vcs::{
+    BlameLine, CommitInfo, FileWatcher, RefInfo, VcsBackend, VcsInfo, VcsType, WhitespaceMode,
+    detect_vcs,
+};
 
 const VISIBLE_COMMIT_COUNT: usize = 10;
+/// Lines fetched per request when reading past the last hunk of a file
+const TAIL_FETCH_CHUNK: u32 = 1000;
 const COMMIT_PAGE_SIZE: usize = 10;
+/// Default time to wait for the rest of a multi-key sequence (vim's timeoutlen)
+pub const DEFAULT_KEY_TIMEOUT_MS: u64 = 1000;
+/// Default window for the second press of the force quit key
+pub const DEFAULT_QUIT_TIMEOUT_MS: u64 = 2000;
+/// Default interval between autosaves of unsaved changes, in seconds
+pub const DEFAULT_AUTOSAVE_SECS: u64 = 30;
+/// Default length limit for intraline highlighting, in characters
+const DEFAULT_INTRALINE_LIMIT: usize = 500;
+/// Default number of diff lines above which a file is rendered without
+/// syntax highlighting
+const DEFAULT_HIGHLIGHT_MAX_LINES: usize = 10_000;
+/// Default columns per indent level for indent guides
+const DEFAULT_INDENT_WIDTH: usize = 4;
+/// Time without input after which the review timer stops counting
+const REVIEW_IDLE_LIMIT: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Debug, Clone)]
 pub enum FileTreeItem {
@@ -23,9 +56,108 @@ pub enum FileTreeItem {
     File {
         file_idx: usize,
         depth: usize,
+        /// Entry in the pinned section at the top of the list
+        pinned: bool,
     },
 }
 
+/// A per-file flag set on all the files of a visual selection at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMark {
+    Reviewed,
+    Skipped,
+    Pinned,
+    Filtered,
+}
+
+/// Predefined filters for the file list, shown as tabs above it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileFilter {
+    #[default]
+    All,
+    Unreviewed,
+    Commented,
+    Added,
+    Deleted,
+    /// Files whose CODEOWNERS include the reviewer
+    Mine,
+    /// Files put aside with a visual selection, which no other tab lists
+    Filtered,
+}
+
+impl FileFilter {
+    /// Tab order; tab `n` is selected with the number key `n + 1`
+    pub const ALL: [FileFilter; 7] = [
+        FileFilter::All,
+        FileFilter::Unreviewed,
+        FileFilter::Commented,
+        FileFilter::Added,
+        FileFilter::Deleted,
+        FileFilter::Mine,
+        FileFilter::Filtered,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::All => "All",
+            Self::Unreviewed => "Unreviewed",
+            Self::Commented => "Commented",
+            Self::Added => "Added",
+            Self::Deleted => "Deleted",
+            Self::Mine => "Owned by me",
+            Self::Filtered => "Filtered",
+        }
+    }
+
+    /// Compact label for the tab row, which has to fit a narrow panel
+    pub fn tab_label(&self) -> &'static str {
+        match self {
+            Self::All => "All",
+            Self::Unreviewed => "Unrev",
+            Self::Commented => "Cmnt",
+            Self::Added => "Add",
+            Self::Deleted => "Del",
+            Self::Mine => "Mine",
+            Self::Filtered => "Filt",
+        }
+    }
+
+    pub fn matches(&self, file: &DiffFile, session: &ReviewSession, owners: &CodeOwners) -> bool {
+        let path = file.display_path();
+        if session.is_file_filtered(path) {
+            return *self == Self::Filtered;
+        }
+        match self {
+            Self::All => true,
+            Self::Unreviewed => !session.is_file_reviewed(path) && !session.is_file_skipped(path),
+            Self::Commented => session
+                .files
+                .get(path)
+                .is_some_and(|review| review.comment_count() > 0),
+            Self::Added => file.status == FileStatus::Added,
+            Self::Deleted => file.status == FileStatus::Deleted,
+            Self::Mine => owners.is_mine(path),
+            Self::Filtered => false,
+        }
+    }
+}
+
+/// What the diff filters hid from the files as loaded
+#[derive(Debug, Default)]
+struct HiddenChanges {
+    line_endings: usize,
+    renames: Vec<Rename>,
+    hunks: usize,
+}
+
+/// Aggregated change counts for every file below a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectoryRollup {
+    pub additions: usize,
+    pub deletions: usize,
+    pub unreviewed: usize,
+}
+
 /// Identifies a gap between hunks in a file (for context expansion)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GapId {
@@ -78,23 +210,120 @@ pub enum InputMode {
     Confirm,
     CommitSelect,
     VisualSelect,
+    /// Read-only scrollable popup (see `TextPopup`)
+    Popup,
+    ThemePicker,
+    /// Picking a branch, tag or remote branch to diff against
+    RefSelect,
+    /// An operation failed (see `ErrorReport`)
+    Error,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DiffSource {
     WorkingTree,
     CommitRange(Vec<String>),
+    /// Working tree compared against a named ref (branch, tag, ...)
+    Ref(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfirmAction {
     CopyAndQuit,
+    /// Offer to jump back to where the previous session left off
+    ResumeReview,
+    /// Ask whether to copy an oversized export in chunks or write it to a file
+    LargeExport,
+    /// Ask to copy the next chunk of an oversized export
+    NextExportChunk,
+    /// Ask before `:export <format> <path>` replaces an existing file
+    OverwriteExport,
+    /// Ask whether to open, diff against or archive the review the branch
+    /// had before it moved on
+    PreviousReview,
+    /// Offer to recover unsaved changes autosaved by a run that crashed
+    RecoverAutosave,
+}
+
+/// A saved review of the current branch made at an older commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviousReview {
+    pub path: PathBuf,
+    /// The commit the review was made at
+    pub base_commit: String,
+}
+
+/// An export too large for the clipboard, waiting to be delivered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargeExport {
+    pub content: String,
+    /// Extension used if the export is written to a file
+    pub extension: &'static str,
+    pub chunks: Vec<String>,
+    /// Index of the next chunk to copy
+    pub next_chunk: usize,
+    /// Quit once the export is delivered or cancelled (`:x`)
+    pub quit_after: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FocusedPanel {
     FileList,
     Diff,
+    Comments,
+}
+
+/// What the inline comment editor is being used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentInputKind {
+    Add,
+    Edit,
+    Reply,
+}
+
+impl CommentInputKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            CommentInputKind::Add => "Add",
+            CommentInputKind::Edit => "Edit",
+            CommentInputKind::Reply => "Reply",
+        }
+    }
+}
+
+/// A suggestion written to the working tree, kept so it can be undone
+#[derive(Debug, Clone)]
+pub struct AppliedSuggestion {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    pub comment_id: String,
+    /// File content before and after the suggestion was applied
+    pub before: String,
+    pub after: String,
+    /// Whether the comment was resolved before applying marked it so
+    pub was_resolved: bool,
+}
+
+/// Most review edits kept for undo
+const UNDO_LIMIT: usize = 100;
+
+/// A review edit that can be undone or redone: the state of the review of
+/// each file it changed to put back, and where the edit was made
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub action: HistoryAction,
+    pub path: PathBuf,
+    pub line: Option<u32>,
+    states: Vec<(PathBuf, ReviewState)>,
+}
+
+/// One row in the comments panel
+#[derive(Debug, Clone)]
+pub struct CommentPanelEntry {
+    pub path: PathBuf,
+    /// None for file-level comments
+    pub line: Option<u32>,
+    pub comment: Comment,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -127,24 +356,44 @@ pub struct App {
     pub input_mode: InputMode,
     pub focused_panel: FocusedPanel,
     pub diff_view_mode: DiffViewMode,
+    /// Screen reader mode: words instead of symbols and colors for diff and
+    /// comment markers, and a single column to read top to bottom
+    pub screen_reader: bool,
 
     pub file_list_state: FileListState,
     pub diff_state: DiffState,
     pub help_state: HelpState,
+    pub text_popup: Option<TextPopup>,
+    pub theme_picker: Option<ThemePickerState>,
     pub command_buffer: String,
     pub search_buffer: String,
-    pub last_search_pattern: Option<String>,
-    pub comment_buffer: String,
-    pub comment_cursor: usize,
+    pub last_search_pattern: Option<SearchPattern>,
+    /// Whether matches of the last search are highlighted in the diff
+    pub search_highlight: bool,
+    /// Whether the last search, and so `n`/`N`, only looks at comments
+    pub comment_search: bool,
+    pub comment_editor: TextEditor,
     pub comment_type: CommentType,
     pub comment_is_file_level: bool,
     pub comment_line: Option<(u32, LineSide)>,
     pub editing_comment_id: Option<String>,
+    /// Id of the comment being replied to while in Comment mode
+    pub reply_to_comment_id: Option<String>,
 
     /// Visual selection anchor point (starting line, side)
     pub visual_anchor: Option<(u32, LineSide)>,
+    /// File list row where a visual selection of files started
+    pub file_visual_anchor: Option<usize>,
     /// Line range for range comments (used when creating comments from visual selection)
     pub comment_line_range: Option<(LineRange, LineSide)>,
+    /// Selected lines a suggestion being written replaces
+    pub comment_suggestion_base: Option<String>,
+    /// Suggestions applied to the working tree, most recent last
+    pub applied_suggestions: Vec<AppliedSuggestion>,
+    /// Comment and reviewed state edits, most recent last
+    pub undo_stack: Vec<UndoEntry>,
+    /// Undone edits, most recently undone last; cleared by a new edit
+    pub redo_stack: Vec<UndoEntry>,
 
     // Commit selection state
     pub commit_list: Vec<CommitInfo>,
@@ -154,21 +403,53 @@ pub struct App {
     /// Selected commit range as (start_idx, end_idx) inclusive, where start <= end.
     /// Indices refer to positions in commit_list (0 = newest/HEAD, higher = older).
     pub commit_selection_range: Option<(usize, usize)>,
+    /// Commit selection was reopened from a commit range review; Esc returns
+    /// to that review instead of the working tree
+    reselecting_commits: bool,
     /// State describing how many commits are currently shown and how pagination behaves.
     pub visible_commit_count: usize,
     pub commit_page_size: usize,
     pub has_more_commit: bool,
 
+    // Ref selection state
+    pub ref_list: Vec<RefInfo>,
+    pub ref_list_cursor: usize,
+    pub ref_list_scroll_offset: usize,
+    pub ref_list_viewport_height: usize,
+
     pub should_quit: bool,
+    /// The comment being written is to be opened in `$EDITOR`, which the
+    /// main loop does as it owns the terminal
+    pub external_edit_requested: bool,
     pub dirty: bool,
     pub quit_warned: bool,
     pub message: Option<Message>,
+    /// The failure shown in the error popup
+    pub error_report: Option<ErrorReport>,
+    /// Errors of this run with when they happened, shown by `:errors`
+    pub error_log: Vec<(chrono::DateTime<chrono::Local>, String)>,
     pub pending_confirm: Option<ConfirmAction>,
+    /// The older review of the branch the session was loaded from, until
+    /// the user picks what to do with it
+    pub previous_review: Option<PreviousReview>,
+    /// Export being delivered through the large-export dialog
+    pub large_export: Option<LargeExport>,
+    /// Target file and content of an export waiting for overwrite confirmation
+    pub export_overwrite: Option<(PathBuf, String)>,
+    /// Key bindings, including the user's keymap file
+    pub keymap: Keymap,
     pub supports_keyboard_enhancement: bool,
     pub show_file_list: bool,
+    pub show_comments_panel: bool,
+    pub comments_panel_state: CommentsPanelState,
     pub file_list_area: Option<ratatui::layout::Rect>,
     pub diff_area: Option<ratatui::layout::Rect>,
+    pub comments_area: Option<ratatui::layout::Rect>,
     pub expanded_dirs: HashSet<String>,
+    /// Active file list filter tab
+    pub file_filter: FileFilter,
+    /// CODEOWNERS of the repository, for owner hints and the "Mine" filter
+    pub code_owners: CodeOwners,
     /// Tracks which hunk gaps have been expanded to show more context
     pub expanded_gaps: HashSet<GapId>,
     /// Stores the expanded context lines for each gap
@@ -177,6 +458,96 @@ pub struct App {
     pub line_annotations: Vec<AnnotatedLine>,
     /// Output to stdout instead of clipboard when exporting
     pub output_to_stdout: bool,
+    /// Lines of diff context embedded around each comment in exports
+    pub export_context_lines: usize,
+    /// Whether changes that only switch line endings are hidden
+    pub hide_line_endings: bool,
+    /// Hide hunks of moved or pre-existing code (`:set newcode`)
+    pub new_code_only: bool,
+    /// Collapse lines that only apply identifiers renamed throughout their
+    /// file (`:set hiderenames`)
+    pub hide_renames: bool,
+    /// Whitespace changes the diff leaves out (`:set iw`)
+    pub whitespace_mode: WhitespaceMode,
+    /// Files with changes staged for the next commit, when reviewing the
+    /// working tree
+    pub staged_paths: HashSet<PathBuf>,
+    /// Emphasize the changed words within changed lines
+    pub intraline: bool,
+    /// Lines longer than this many characters get no intraline emphasis
+    pub intraline_limit: usize,
+    /// Files with more diff lines than this are rendered without syntax
+    /// highlighting, 0 for no limit
+    pub highlight_max_lines: usize,
+    /// Files highlighted with `:highlight!` despite `highlight_max_lines`
+    pub forced_highlight: HashSet<PathBuf>,
+    /// Draw indent guides in the leading whitespace of code lines
+    pub indent_guides: bool,
+    /// Columns per indent level, also the width leading tabs expand to
+    pub indent_width: usize,
+    /// Highlight the line the diff cursor is on
+    pub cursor_line_highlight: bool,
+    /// Highlight the column where the cursor line's code starts
+    pub cursor_column_highlight: bool,
+    /// Dim the diff lines the cursor has passed over and the hunks marked
+    /// reviewed (`:set dimviewed`)
+    pub dim_viewed: bool,
+    /// Cursor line and file whose diff lines were last marked viewed
+    viewed_cursor: Option<(usize, usize)>,
+    /// Number diff lines relative to the cursor line (`:set rnu`)
+    pub relative_line_numbers: bool,
+    /// Show who last changed each line in a gutter (`:set blame`)
+    pub blame_gutter: bool,
+    /// Blame of new-side file lines, loaded as files scroll into view
+    blame: HashMap<PathBuf, Vec<Option<BlameLine>>>,
+    /// Diff files as loaded, kept while line-ending changes are hidden
+    unfiltered_diff_files: Option<Vec<DiffFile>>,
+    /// Repository fingerprint taken when the diff was last loaded
+    diff_fingerprint: Option<u64>,
+    /// The review the last reload switched to after the working copy moved
+    /// to another branch, to tell the user
+    branch_switch: Option<String>,
+    /// The repository changed since the diff was loaded
+    pub diff_stale: bool,
+    /// Set while watching the working tree to reload the diff when files
+    /// change (`--watch`, `:set autoreload`)
+    watcher: Option<FileWatcher>,
+    /// Posts new and updated comments to the configured webhook
+    webhook: Option<WebhookSender>,
+    /// Whether the terminal window has focus (tracked via focus events)
+    pub terminal_focused: bool,
+    /// Dim the whole UI while the terminal is unfocused
+    pub dim_when_unfocused: bool,
+    /// Browse-only mode (`--read-only`): the session cannot be edited or saved
+    pub read_only: bool,
+    /// Only list comments assigned to this person in the comments panel
+    pub comments_assignee_filter: Option<String>,
+    /// URL templates for issue references, from `TUICR_ISSUE_URLS`
+    pub issue_linker: IssueLinker,
+    /// Keys typed so far of a pending multi-key sequence (e.g. "d"), shown in
+    /// the status bar until the sequence completes or times out
+    pub pending_keys: Option<String>,
+    /// Milliseconds before a pending multi-key sequence is dropped (0 = never)
+    pub key_timeout_ms: u64,
+    /// Milliseconds within which the force quit key must be pressed again to
+    /// quit (0 = the first press quits)
+    pub quit_timeout_ms: u64,
+    /// When the force quit key was pressed, until it is pressed again or
+    /// the timeout passes
+    pub pending_quit: Option<Instant>,
+    /// Seconds between autosaves of unsaved changes to the journal, which
+    /// also happen on mode changes (0 = no autosave)
+    pub autosave_secs: u64,
+    last_autosave: Instant,
+    /// A newer autosave of this review left by a run that did not exit
+    /// normally, while the user decides whether to recover it
+    autosave_recovery: Option<ReviewSession>,
+    /// Redraw only after input or a change of state, never on the idle tick
+    pub reduce_motion: bool,
+    /// When review time was last added to the current file
+    review_clock: Option<Instant>,
+    /// Last key press or mouse event, to stop the review timer when idle
+    last_activity: Instant,
     /// Pending output to print to stdout after TUI exits
     pub pending_stdout_output: Option<String>,
     /// Calculated screen position for comment input cursor (col, row) for IME positioning.
@@ -191,6 +562,12 @@ pub struct FileListState {
     pub viewport_width: usize,    // Set during render
     pub viewport_height: usize,   // Set during render
     pub max_content_width: usize, // Set during render
+    pub top: u16,                 // Set during render
+    /// Screen row of the mouse while it is over the list
+    pub hovered_row: Option<u16>,
+    /// Full path of the hovered or selected entry when its row is cut off.
+    /// Set during render.
+    pub path_echo: Option<String>,
 }
 
 impl FileListState {
@@ -210,6 +587,30 @@ impl FileListState {
         let max_scroll_x = self.max_content_width.saturating_sub(self.viewport_width);
         self.scroll_x = (self.scroll_x.saturating_add(cols)).min(max_scroll_x);
     }
+
+    /// Tree index of the entry under the mouse
+    pub fn hovered(&self) -> Option<usize> {
+        let offset = usize::from(self.hovered_row?.checked_sub(self.top)?);
+        (offset < self.viewport_height).then(|| self.list_state.offset() + offset)
+    }
+}
+
+#[derive(Default)]
+pub struct CommentsPanelState {
+    /// Index of the selected comment in `App::comment_panel_entries`
+    selected: usize,
+    /// Rows as rendered, file group headers included
+    pub list_state: ratatui::widgets::ListState,
+}
+
+impl CommentsPanelState {
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index;
+    }
 }
 
 #[derive(Debug)]
@@ -243,6 +644,36 @@ impl Default for DiffState {
     }
 }
 
+/// Theme picker popup; the highlighted theme is previewed live
+pub struct ThemePickerState {
+    pub selected: usize,
+    /// Theme active when the picker opened, restored on cancel
+    pub original: &'static str,
+}
+
+/// A failed operation shown in the error popup, with how to recover
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    /// What failed, e.g. "Reload failed"
+    pub context: String,
+    /// The error and its causes, outermost first
+    pub chain: Vec<String>,
+    /// Command to run again on retry, without the leading `:`
+    pub retry: Option<String>,
+}
+
+/// Generated read-only content shown in a scrollable popup
+pub struct TextPopup {
+    pub title: String,
+    pub lines: Vec<ratatui::text::Line<'static>>,
+    /// Soft-wrap long lines; when false, lines scroll horizontally instead
+    pub wrap: bool,
+    pub scroll_offset: usize,
+    pub scroll_x: usize,
+    pub viewport_height: usize, // Set during render
+    pub total_lines: usize,     // Set during render
+}
+
 #[derive(Debug, Default)]
 pub struct HelpState {
     pub scroll_offset: usize,
@@ -250,6 +681,9 @@ pub struct HelpState {
     pub total_lines: usize, // Set during render
 }
 
+/// Path, comment id and (for line comments) line of a comment
+type CommentTarget = (PathBuf, String, Option<(u32, LineSide)>);
+
 /// Represents a comment location for deletion
 enum CommentLocation {
     FileComment {
@@ -265,24 +699,70 @@ enum CommentLocation {
 }
 
 impl App {
-    pub fn new(theme: Theme, output_to_stdout: bool) -> Result<Self> {
-        let vcs = detect_vcs()?;
+    /// `read_only` is set before any session is looked up, so a read-only
+    /// review never writes to the session store or asks about it
+    pub fn new(theme: Theme, output_to_stdout: bool, read_only: bool) -> Result<Self> {
+        Self::with_backend(detect_vcs()?, theme, output_to_stdout, read_only)
+    }
+
+    /// Start straight on the commits of a range expression (`--range`),
+    /// without looking at the working tree or offering the commit selector
+    pub fn with_commit_range(
+        theme: Theme,
+        output_to_stdout: bool,
+        read_only: bool,
+        range: &str,
+    ) -> Result<Self> {
+        Self::start(
+            detect_vcs()?,
+            theme,
+            output_to_stdout,
+            read_only,
+            Some(range),
+        )
+    }
+
+    /// Start on a given backend instead of detecting the repository, e.g. for
+    /// a diff read from stdin
+    pub fn with_backend(
+        vcs: Box<dyn VcsBackend>,
+        theme: Theme,
+        output_to_stdout: bool,
+        read_only: bool,
+    ) -> Result<Self> {
+        Self::start(vcs, theme, output_to_stdout, read_only, None)
+    }
+
+    fn start(
+        vcs: Box<dyn VcsBackend>,
+        theme: Theme,
+        output_to_stdout: bool,
+        read_only: bool,
+        range: Option<&str>,
+    ) -> Result<Self> {
         let vcs_info = vcs.info().clone();
+        let diff_fingerprint = vcs.state_fingerprint();
         let highlighter = theme.syntax_highlighter();
 
         // Try to get working tree diff first
-        let diff_result = vcs.get_working_tree_diff(highlighter);
+        let diff_result = match range {
+            // Start as if the tree was clean, then load the range in place
+            // of the commit selector
+            Some(_) => Err(TuicrError::NoChanges),
+            None => vcs.get_working_tree_diff(highlighter),
+        };
 
         match diff_result {
             Ok(diff_files) => {
                 // We have unstaged changes - normal flow
-                let mut session = Self::load_or_create_session(&vcs_info);
+                let (mut session, previous_review) = Self::load_or_create_session(&vcs_info)?;
+                // Archiving or moving on from the previous review would
+                // write to the session store
+                let previous_review = previous_review.filter(|_| !read_only);
 
                 // Ensure all current diff files are in the session
-                for file in &diff_files {
-                    let path = file.display_path().clone();
-                    session.add_file(path, file.status);
-                }
+                let invalidated =
+                    Self::sync_session_files(&mut session, &diff_files, WhitespaceMode::Show);
 
                 let mut app = Self {
                     theme,
@@ -294,48 +774,130 @@ impl App {
                     input_mode: InputMode::Normal,
                     focused_panel: FocusedPanel::Diff,
                     diff_view_mode: DiffViewMode::Unified,
+                    screen_reader: false,
                     file_list_state: FileListState::default(),
                     diff_state: DiffState::default(),
                     help_state: HelpState::default(),
+                    text_popup: None,
+                    theme_picker: None,
                     command_buffer: String::new(),
                     search_buffer: String::new(),
                     last_search_pattern: None,
-                    comment_buffer: String::new(),
-                    comment_cursor: 0,
+                    search_highlight: false,
+                    comment_search: false,
+                    comment_editor: TextEditor::default(),
                     comment_type: CommentType::Note,
                     comment_is_file_level: true,
                     comment_line: None,
                     editing_comment_id: None,
+                    reply_to_comment_id: None,
                     visual_anchor: None,
+                    file_visual_anchor: None,
                     comment_line_range: None,
+                    comment_suggestion_base: None,
+                    applied_suggestions: Vec::new(),
+                    undo_stack: Vec::new(),
+                    redo_stack: Vec::new(),
                     commit_list: Vec::new(),
                     commit_list_cursor: 0,
                     commit_list_scroll_offset: 0,
                     commit_list_viewport_height: 0,
                     commit_selection_range: None,
+                    reselecting_commits: false,
+                    ref_list: Vec::new(),
+                    ref_list_cursor: 0,
+                    ref_list_scroll_offset: 0,
+                    ref_list_viewport_height: 0,
                     visible_commit_count: VISIBLE_COMMIT_COUNT,
                     commit_page_size: COMMIT_PAGE_SIZE,
                     has_more_commit: true,
                     should_quit: false,
+                    error_report: None,
+                    error_log: Vec::new(),
+                    external_edit_requested: false,
                     dirty: false,
                     quit_warned: false,
                     message: None,
                     pending_confirm: None,
+                    previous_review,
                     supports_keyboard_enhancement: false,
                     show_file_list: true,
+                    show_comments_panel: false,
+                    comments_panel_state: CommentsPanelState::default(),
                     file_list_area: None,
                     diff_area: None,
+                    comments_area: None,
                     expanded_dirs: HashSet::new(),
+                    file_filter: FileFilter::All,
+                    code_owners: CodeOwners::default(),
                     expanded_gaps: HashSet::new(),
                     expanded_content: HashMap::new(),
                     line_annotations: Vec::new(),
                     output_to_stdout,
+                    export_context_lines: 0,
+                    hide_line_endings: false,
+                    new_code_only: false,
+                    hide_renames: false,
+                    whitespace_mode: WhitespaceMode::Show,
+                    staged_paths: HashSet::new(),
+                    intraline: true,
+                    intraline_limit: DEFAULT_INTRALINE_LIMIT,
+                    highlight_max_lines: DEFAULT_HIGHLIGHT_MAX_LINES,
+                    forced_highlight: HashSet::new(),
+                    indent_guides: true,
+                    indent_width: DEFAULT_INDENT_WIDTH,
+                    cursor_line_highlight: false,
+                    dim_viewed: false,
+                    viewed_cursor: None,
+                    cursor_column_highlight: false,
+                    relative_line_numbers: false,
+                    blame_gutter: false,
+                    blame: HashMap::new(),
+                    unfiltered_diff_files: None,
+                    diff_fingerprint,
+                    branch_switch: None,
+                    diff_stale: false,
+                    watcher: None,
+                    webhook: None,
+                    terminal_focused: true,
+                    dim_when_unfocused: false,
+                    read_only,
+                    comments_assignee_filter: None,
+                    issue_linker: IssueLinker::from_env(),
+                    large_export: None,
+                    export_overwrite: None,
+                    keymap: Keymap::default(),
+                    pending_keys: None,
+                    key_timeout_ms: DEFAULT_KEY_TIMEOUT_MS,
+                    quit_timeout_ms: DEFAULT_QUIT_TIMEOUT_MS,
+                    pending_quit: None,
+                    autosave_secs: DEFAULT_AUTOSAVE_SECS,
+                    last_autosave: Instant::now(),
+                    autosave_recovery: None,
+                    reduce_motion: false,
+                    review_clock: None,
+                    last_activity: Instant::now(),
                     pending_stdout_output: None,
                     comment_cursor_screen_pos: None,
                 };
+                app.refresh_staged_paths();
+                app.record_anchor();
+                app.apply_highlight_overrides();
                 app.sort_files_by_directory(true);
                 app.expand_all_dirs();
                 app.rebuild_annotations();
+                app.restore_view_state();
+                if invalidated > 0 {
+                    app.dirty |= !app.read_only;
+                }
+                if let Some(problems) = app.reload_problems(invalidated) {
+                    app.set_warning(problems);
+                }
+                if app.previous_review.is_some() {
+                    app.enter_confirm_mode(ConfirmAction::PreviousReview);
+                } else if app.session.has_progress() {
+                    app.enter_confirm_mode(ConfirmAction::ResumeReview);
+                }
                 Ok(app)
             }
             Err(TuicrError::NoChanges) => {
@@ -352,11 +914,11 @@ impl App {
                 let session = ReviewSession::new(
                     vcs_info.root_path.clone(),
                     vcs_info.head_commit.clone(),
-                    vcs_info.branch_name.clone(),
+                    vcs_info.session_branch().map(str::to_string),
                     SessionDiffSource::WorkingTree,
                 );
 
-                Ok(Self {
+                let mut app = Self {
                     theme,
                     vcs,
                     vcs_info,
@@ -366,81 +928,191 @@ impl App {
                     input_mode: InputMode::CommitSelect,
                     focused_panel: FocusedPanel::Diff,
                     diff_view_mode: DiffViewMode::Unified,
+                    screen_reader: false,
                     file_list_state: FileListState::default(),
                     diff_state: DiffState::default(),
                     help_state: HelpState::default(),
+                    text_popup: None,
+                    theme_picker: None,
                     command_buffer: String::new(),
                     search_buffer: String::new(),
                     last_search_pattern: None,
-                    comment_buffer: String::new(),
-                    comment_cursor: 0,
+                    search_highlight: false,
+                    comment_search: false,
+                    comment_editor: TextEditor::default(),
                     comment_type: CommentType::Note,
                     comment_is_file_level: true,
                     comment_line: None,
                     editing_comment_id: None,
+                    reply_to_comment_id: None,
                     visual_anchor: None,
+                    file_visual_anchor: None,
                     comment_line_range: None,
+                    comment_suggestion_base: None,
+                    applied_suggestions: Vec::new(),
+                    undo_stack: Vec::new(),
+                    redo_stack: Vec::new(),
                     commit_list: commits,
                     commit_list_cursor: 0,
                     commit_list_scroll_offset: 0,
                     commit_list_viewport_height: 0,
                     commit_selection_range: None,
+                    reselecting_commits: false,
+                    ref_list: Vec::new(),
+                    ref_list_cursor: 0,
+                    ref_list_scroll_offset: 0,
+                    ref_list_viewport_height: 0,
                     visible_commit_count: commit_count,
                     commit_page_size: COMMIT_PAGE_SIZE,
                     has_more_commit,
                     should_quit: false,
+                    error_report: None,
+                    error_log: Vec::new(),
+                    external_edit_requested: false,
                     dirty: false,
                     quit_warned: false,
                     message: None,
                     pending_confirm: None,
+                    previous_review: None,
                     supports_keyboard_enhancement: false,
                     show_file_list: true,
+                    show_comments_panel: false,
+                    comments_panel_state: CommentsPanelState::default(),
                     file_list_area: None,
                     diff_area: None,
+                    comments_area: None,
                     expanded_dirs: HashSet::new(),
+                    file_filter: FileFilter::All,
+                    code_owners: CodeOwners::default(),
                     expanded_gaps: HashSet::new(),
                     expanded_content: HashMap::new(),
                     line_annotations: Vec::new(),
                     output_to_stdout,
+                    export_context_lines: 0,
+                    hide_line_endings: false,
+                    new_code_only: false,
+                    hide_renames: false,
+                    whitespace_mode: WhitespaceMode::Show,
+                    staged_paths: HashSet::new(),
+                    intraline: true,
+                    intraline_limit: DEFAULT_INTRALINE_LIMIT,
+                    highlight_max_lines: DEFAULT_HIGHLIGHT_MAX_LINES,
+                    forced_highlight: HashSet::new(),
+                    indent_guides: true,
+                    indent_width: DEFAULT_INDENT_WIDTH,
+                    cursor_line_highlight: false,
+                    dim_viewed: false,
+                    viewed_cursor: None,
+                    cursor_column_highlight: false,
+                    relative_line_numbers: false,
+                    blame_gutter: false,
+                    blame: HashMap::new(),
+                    unfiltered_diff_files: None,
+                    diff_fingerprint,
+                    branch_switch: None,
+                    diff_stale: false,
+                    watcher: None,
+                    webhook: None,
+                    terminal_focused: true,
+                    dim_when_unfocused: false,
+                    read_only,
+                    comments_assignee_filter: None,
+                    issue_linker: IssueLinker::from_env(),
+                    large_export: None,
+                    export_overwrite: None,
+                    keymap: Keymap::default(),
+                    pending_keys: None,
+                    key_timeout_ms: DEFAULT_KEY_TIMEOUT_MS,
+                    quit_timeout_ms: DEFAULT_QUIT_TIMEOUT_MS,
+                    pending_quit: None,
+                    autosave_secs: DEFAULT_AUTOSAVE_SECS,
+                    last_autosave: Instant::now(),
+                    autosave_recovery: None,
+                    reduce_motion: false,
+                    review_clock: None,
+                    last_activity: Instant::now(),
                     pending_stdout_output: None,
                     comment_cursor_screen_pos: None,
-                })
+                };
+                if let Some(range) = range {
+                    app.review_commit_range(range)?;
+                }
+                Ok(app)
             }
             Err(e) => Err(e),
         }
     }
 
-    fn load_or_create_session(vcs_info: &VcsInfo) -> ReviewSession {
-        let new_session = || {
-            ReviewSession::new(
-                vcs_info.root_path.clone(),
-                vcs_info.head_commit.clone(),
-                vcs_info.branch_name.clone(),
+    fn new_working_tree_session(vcs_info: &VcsInfo) -> ReviewSession {
+        ReviewSession::new(
+            vcs_info.root_path.clone(),
+            vcs_info.head_commit.clone(),
+            vcs_info.session_branch().map(str::to_string),
+            SessionDiffSource::WorkingTree,
+        )
+    }
+
+    /// The latest working tree session of the branch. A jj change without
+    /// one takes over the review saved under its bookmark, from before jj
+    /// reviews went by change id.
+    pub(crate) fn load_latest_working_tree_session(
+        vcs_info: &VcsInfo,
+    ) -> Result<Option<(PathBuf, ReviewSession)>> {
+        let load = |branch| {
+            load_latest_session_for_context(
+                &vcs_info.root_path,
+                branch,
+                &vcs_info.head_commit,
                 SessionDiffSource::WorkingTree,
+                None,
             )
         };
+        let found = load(vcs_info.session_branch())?;
+        let Some(bookmark) = vcs_info.legacy_session_branch().filter(|_| found.is_none()) else {
+            return Ok(found);
+        };
+        Ok(load(Some(bookmark))?.map(|(path, mut session)| {
+            session.branch_name = None;
+            session.base_commit = vcs_info.head_commit.clone();
+            (path, session)
+        }))
+    }
 
-        let Ok(found) = load_latest_session_for_context(
-            &vcs_info.root_path,
-            vcs_info.branch_name.as_deref(),
-            &vcs_info.head_commit,
-            SessionDiffSource::WorkingTree,
-            None,
-        ) else {
-            return new_session();
+    /// The latest working tree session of the branch, or a new one. A session
+    /// with progress made before the branch moved to another commit is also
+    /// returned as the `PreviousReview`, so the user can decide about it.
+    /// Fails when the latest session was saved by a newer tuicr.
+    fn load_or_create_session(
+        vcs_info: &VcsInfo,
+    ) -> Result<(ReviewSession, Option<PreviousReview>)> {
+        let new_session = || Ok((Self::new_working_tree_session(vcs_info), None));
+
+        let branch = vcs_info.session_branch();
+        let found = match Self::load_latest_working_tree_session(vcs_info) {
+            Ok(found) => found,
+            Err(e @ TuicrError::SessionTooNew(_)) => return Err(e),
+            Err(_) => return new_session(),
         };
 
-        let Some((_path, mut session)) = found else {
+        let Some((path, mut session)) = found else {
             return new_session();
         };
 
+        let previous_review = (branch.is_some()
+            && session.base_commit != vcs_info.head_commit
+            && session.has_progress())
+        .then(|| PreviousReview {
+            path,
+            base_commit: session.base_commit.clone(),
+        });
+
         let mut updated = false;
-        if session.branch_name.is_none() && vcs_info.branch_name.is_some() {
-            session.branch_name = vcs_info.branch_name.clone();
+        if session.branch_name.is_none() && branch.is_some() {
+            session.branch_name = branch.map(str::to_string);
             updated = true;
         }
 
-        if vcs_info.branch_name.is_some() && session.base_commit != vcs_info.head_commit {
+        if branch.is_some() && session.base_commit != vcs_info.head_commit {
             session.base_commit = vcs_info.head_commit.clone();
             updated = true;
         }
@@ -449,10 +1121,270 @@ impl App {
             session.updated_at = chrono::Utc::now();
         }
 
-        session
+        Ok((session, previous_review))
+    }
+
+    /// Keep reviewing in the previous review of the branch, from where it
+    /// left off
+    pub fn open_previous_review(&mut self) {
+        self.exit_confirm_mode();
+        self.previous_review = None;
+        self.resume_last_position();
+    }
+
+    /// Keep the previous review of the branch, but only show what changed
+    /// since the commit it was made at (an interdiff)
+    pub fn diff_since_previous_review(&mut self) {
+        self.exit_confirm_mode();
+        let Some(previous) = self.previous_review.take() else {
+            return;
+        };
+        if let Err(e) = self.compare_against_ref(&previous.base_commit) {
+            self.set_error(format!("Cannot diff against the reviewed commit: {e}"));
+        }
+    }
+
+    /// Move the previous review of the branch to the archive and start over
+    pub fn archive_previous_review(&mut self) {
+        self.exit_confirm_mode();
+        if self.deny_if_read_only() {
+            return;
+        }
+        let Some(previous) = self.previous_review.take() else {
+            return;
+        };
+        match archive_session(&previous.path) {
+            Ok(archived) => {
+                self.start_over(Self::new_working_tree_session(&self.vcs_info));
+                self.set_message(format!(
+                    "Archived the previous review to {}",
+                    archived.display()
+                ));
+            }
+            Err(e) => self.set_error(format!("Archive failed: {e}")),
+        }
+    }
+
+    /// Save the open review, compress it into the archive and start a new
+    /// review of the same diff. Reviews with files left to review are only
+    /// archived when `force` is set.
+    pub fn archive_current_session(&mut self, force: bool) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let unreviewed = self.session.files.len() - self.session.reviewed_count();
+        if unreviewed > 0 && !force {
+            self.set_error(format!(
+                "{unreviewed} files are not reviewed yet; use :archive! to archive anyway"
+            ));
+            return;
+        }
+
+        self.record_position();
+        self.record_view_state();
+        match save_session(&self.session).and_then(|path| archive_session(&path)) {
+            Ok(archived) => {
+                let mut session = ReviewSession::new(
+                    self.session.repo_path.clone(),
+                    self.session.base_commit.clone(),
+                    self.session.branch_name.clone(),
+                    self.session.diff_source,
+                );
+                session.commit_range = self.session.commit_range.clone();
+                session.range = self.session.range.clone();
+                self.start_over(session);
+                self.set_message(format!("Archived the review to {}", archived.display()));
+            }
+            Err(e) => self.set_error(format!("Archive failed: {e}")),
+        }
     }
 
-    pub fn reload_diff_files(&mut self) -> Result<usize> {
+    /// Replace the session with a new, empty one for the current diff
+    fn start_over(&mut self, mut session: ReviewSession) {
+        Self::sync_session_files(&mut session, &self.diff_files, self.whitespace_mode);
+        // Forced languages are a view setting, not part of the review
+        session.languages = std::mem::take(&mut self.session.languages);
+        self.session = session;
+        self.dirty = false;
+        self.clear_expanded_gaps();
+        self.rebuild_annotations();
+        self.jump_to_file(0);
+    }
+
+    /// Replace the session with one loaded from a file, e.g. a teammate's review
+    pub fn open_session_file(&mut self, path: &PathBuf) -> Result<()> {
+        self.replace_session(load_session(path)?);
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Review with `session` in place of the open one, keeping the diff
+    fn replace_session(&mut self, mut session: ReviewSession) {
+        Self::sync_session_files(&mut session, &self.diff_files, self.whitespace_mode);
+        self.session = session;
+        self.record_anchor();
+        self.apply_highlight_overrides();
+        self.clear_expanded_gaps();
+        self.expand_all_dirs();
+        self.rebuild_annotations();
+        self.restore_view_state();
+    }
+
+    /// Ask whether to recover the autosave of this review that a crashed or
+    /// killed run left behind, when it is newer than the last `:w`. The
+    /// question about a previous review of the branch comes first.
+    pub fn offer_autosave_recovery(&mut self) {
+        if self.read_only || self.pending_confirm == Some(ConfirmAction::PreviousReview) {
+            return;
+        }
+        match load_autosave(&self.session) {
+            Ok(Some(journal)) => {
+                self.autosave_recovery = Some(journal);
+                self.enter_confirm_mode(ConfirmAction::RecoverAutosave);
+            }
+            Ok(None) => {}
+            Err(e) => self.set_warning(format!("Cannot read the autosave: {e}")),
+        }
+    }
+
+    /// Continue the review from its autosave, unsaved until the next `:w`
+    pub fn recover_autosave(&mut self) {
+        self.exit_confirm_mode();
+        if let Some(journal) = self.autosave_recovery.take() {
+            self.replace_session(journal);
+            self.dirty = true;
+            self.set_message("Recovered unsaved changes from the autosave");
+        }
+    }
+
+    /// Keep the saved review and delete the autosave
+    pub fn discard_autosave_recovery(&mut self) {
+        self.exit_confirm_mode();
+        self.autosave_recovery = None;
+        if let Err(e) = discard_autosave(&self.session) {
+            self.set_warning(format!("Cannot delete the autosave: {e}"));
+        }
+    }
+
+    /// Write unsaved changes to the autosave journal when the autosave
+    /// interval has passed or `mode_changed`, so a crash loses little
+    pub fn poll_autosave(&mut self, mode_changed: bool) {
+        if self.autosave_secs == 0 || !self.dirty || self.read_only {
+            return;
+        }
+        let due = self.last_autosave.elapsed() >= Duration::from_secs(self.autosave_secs);
+        if !due && !mode_changed {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        self.record_position();
+        self.record_view_state();
+        if let Err(e) = autosave_session(&self.session) {
+            self.set_warning(format!("Autosave failed: {e}"));
+        }
+    }
+
+    /// Adds every diff file to the session, refreshes their content hashes
+    /// and moves line comments to where their lines are now. Returns how many
+    /// reviewed files were reset because their diff changed since they were
+    /// marked as reviewed.
+    ///
+    /// Hashes and comments are only updated from full diffs: a diff with
+    /// whitespace changes left out would otherwise reset files as soon as
+    /// whitespace is shown again, and orphan comments on hidden lines.
+    fn sync_session_files(
+        session: &mut ReviewSession,
+        diff_files: &[DiffFile],
+        whitespace: WhitespaceMode,
+    ) -> usize {
+        let mut invalidated = 0;
+        for file in diff_files {
+            let path = file.display_path().clone();
+            session.add_file(path.clone(), file.status);
+            if whitespace != WhitespaceMode::Show {
+                continue;
+            }
+            if session.update_content_hash(&path, file.content_hash()) {
+                invalidated += 1;
+            }
+            let hunks: Vec<String> = file.hunks.iter().map(DiffHunk::fingerprint).collect();
+            session.retain_reviewed_hunks(&path, &hunks);
+            session.reanchor_comments(&path, file);
+        }
+        invalidated
+    }
+
+    pub fn invalidated_message(count: usize) -> String {
+        if count == 1 {
+            "1 reviewed file changed since review and was marked unreviewed".to_string()
+        } else {
+            format!("{count} reviewed files changed since review and were marked unreviewed")
+        }
+    }
+
+    /// What a reload left for the user to look at: a switch to the review of
+    /// another branch, reviewed files reset by the reload, and comments whose
+    /// lines are gone
+    pub fn reload_problems(&self, invalidated: usize) -> Option<String> {
+        let mut problems = Vec::new();
+        if let Some(review) = &self.branch_switch {
+            problems.push(format!("switched to the review of {review}"));
+        }
+        if invalidated > 0 {
+            problems.push(Self::invalidated_message(invalidated));
+        }
+        match self.session.orphaned_comment_count() {
+            0 => {}
+            1 => problems.push("1 comment lost its lines and is marked orphaned".to_string()),
+            n => problems.push(format!(
+                "{n} comments lost their lines and are marked orphaned"
+            )),
+        }
+        (!problems.is_empty()).then(|| problems.join("; "))
+    }
+
+    /// Continue with the review of the branch the working copy is on now,
+    /// when it moved to another branch (or, without one, another commit; for
+    /// jj, another change) since the diff was loaded, so reviews of different
+    /// branches never mix. The open review is saved first if it has unsaved
+    /// changes.
+    fn follow_branch_switch(&mut self) -> Result<()> {
+        self.branch_switch = None;
+        if self.diff_source != DiffSource::WorkingTree {
+            return Ok(());
+        }
+        let Ok(info) = self.vcs.current_info() else {
+            return Ok(());
+        };
+        if info.same_review(&self.vcs_info) {
+            self.vcs_info = info;
+            return Ok(());
+        }
+
+        if self.dirty && !self.read_only {
+            self.record_position();
+            self.record_view_state();
+            save_session(&self.session)?;
+            let _ = discard_autosave(&self.session);
+            self.dirty = false;
+        }
+        let (session, _) = Self::load_or_create_session(&info)?;
+        let short_head: String = info.head_commit.chars().take(8).collect();
+        self.branch_switch = Some(match (info.session_branch(), info.vcs_type) {
+            (Some(branch), _) => format!("branch {branch}"),
+            (None, VcsType::Jujutsu) => format!("change {short_head}"),
+            (None, _) => format!("commit {short_head}"),
+        });
+        self.vcs_info = info;
+        self.session = session;
+        self.apply_highlight_overrides();
+        Ok(())
+    }
+
+    /// Re-reads the diff being reviewed. Returns the number of files and the
+    /// number of reviewed files that were invalidated by the reload.
+    pub fn reload_diff_files(&mut self) -> Result<(usize, usize)> {
+        self.follow_branch_switch()?;
         let current_path = self.current_file_path().cloned();
         let prev_file_idx = self.diff_state.current_file_idx;
         let prev_cursor_line = self.diff_state.cursor_line;
@@ -468,15 +1400,22 @@ impl App {
         };
 
         let highlighter = self.theme.syntax_highlighter();
-        let diff_files = self.vcs.get_working_tree_diff(highlighter)?;
+        let diff_files = match &self.diff_source {
+            DiffSource::Ref(base) => self.vcs.get_ref_diff(base, highlighter)?,
+            DiffSource::CommitRange(ids) => self.vcs.get_commit_range_diff(ids, highlighter)?,
+            DiffSource::WorkingTree => self.vcs.get_working_tree_diff(highlighter)?,
+        };
 
-        for file in &diff_files {
-            let path = file.display_path().clone();
-            self.session.add_file(path, file.status);
+        let invalidated =
+            Self::sync_session_files(&mut self.session, &diff_files, self.whitespace_mode);
+        if invalidated > 0 {
+            self.dirty |= !self.read_only;
         }
 
         self.diff_files = diff_files;
+        self.apply_diff_filters();
         self.clear_expanded_gaps();
+        self.mark_diff_fresh();
 
         self.sort_files_by_directory(false);
         self.expand_all_dirs();
@@ -523,7 +1462,7 @@ impl App {
         }
 
         self.rebuild_annotations();
-        Ok(self.diff_files.len())
+        Ok((self.diff_files.len(), invalidated))
     }
 
     pub fn current_file(&self) -> Option<&DiffFile> {
@@ -540,6 +1479,9 @@ impl App {
     }
 
     pub fn toggle_reviewed_for_file_idx(&mut self, file_idx: usize, adjust_cursor: bool) {
+        if self.deny_if_read_only() {
+            return;
+        }
         let Some(path) = self
             .diff_files
             .get(file_idx)
@@ -549,7 +1491,16 @@ impl App {
         };
 
         if let Some(review) = self.session.get_file_mut(&path) {
+            let before = review.review_state();
             review.reviewed = !review.reviewed;
+            review.reviewed_hunks.clear();
+            let action = if review.reviewed {
+                HistoryAction::Reviewed
+            } else {
+                HistoryAction::Unreviewed
+            };
+            self.session.record(action, &path, None);
+            self.push_undo(action, &path, None, before);
             self.dirty = true;
             self.rebuild_annotations();
 
@@ -563,40 +1514,237 @@ impl App {
         }
     }
 
-    pub fn file_count(&self) -> usize {
-        self.diff_files.len()
-    }
+    /// Mark the current file reviewed and move on to the next file still to
+    /// review, wrapping around to earlier ones
+    pub fn mark_reviewed_and_advance(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let file_idx = self.diff_state.current_file_idx;
+        let Some(path) = self
+            .diff_files
+            .get(file_idx)
+            .map(|file| file.display_path().clone())
+        else {
+            return;
+        };
+        if !self.session.is_file_reviewed(&path) {
+            self.toggle_reviewed_for_file_idx(file_idx, false);
+        }
 
-    pub fn reviewed_count(&self) -> usize {
-        self.session.reviewed_count()
+        match next_unreviewed_file(&self.diff_files, &self.session, file_idx) {
+            Some(next) => {
+                self.jump_to_file(next);
+                let left = self.reviewable_count() - self.reviewed_count();
+                self.set_message(format!(
+                    "Reviewed {}; {left} file{} left",
+                    path.display(),
+                    if left == 1 { "" } else { "s" }
+                ));
+            }
+            None => {
+                self.rebuild_annotations();
+                self.ensure_cursor_visible();
+                self.set_message(format!("All {} files reviewed", self.reviewable_count()));
+            }
+        }
     }
 
-    pub fn set_message(&mut self, msg: impl Into<String>) {
-        self.message = Some(Message {
-            content: msg.into(),
-            message_type: MessageType::Info,
-        });
+    /// File and hunk index of the hunk header or diff line under the cursor
+    pub fn hunk_at_cursor(&self) -> Option<(usize, usize)> {
+        match self.line_annotations.get(self.diff_state.cursor_line)? {
+            AnnotatedLine::HunkHeader { file_idx, hunk_idx }
+            | AnnotatedLine::DiffLine {
+                file_idx, hunk_idx, ..
+            } => Some((*file_idx, *hunk_idx)),
+            _ => None,
+        }
     }
 
-    pub fn set_warning(&mut self, msg: impl Into<String>) {
-        self.message = Some(Message {
-            content: msg.into(),
-            message_type: MessageType::Warning,
-        });
-    }
+    /// Toggle the reviewed state of the hunk under the cursor; the file is
+    /// marked reviewed once all of its hunks are
+    pub fn toggle_hunk_reviewed(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let Some((file_idx, hunk_idx)) = self.hunk_at_cursor() else {
+            self.set_warning("Move the cursor onto a hunk to mark it reviewed");
+            return;
+        };
+        let Some(file) = self.diff_files.get(file_idx) else {
+            return;
+        };
+        let path = file.display_path().clone();
+        let hunks: Vec<String> = file.hunks.iter().map(DiffHunk::fingerprint).collect();
+        let new_start = file.hunks[hunk_idx].new_start;
+        let fingerprint = &hunks[hunk_idx];
+
+        let reviewed = !self.session.is_hunk_reviewed(&path, fingerprint);
+        let before = self.session.files.get(&path).map(|r| r.review_state());
+        let file_changed = self
+            .session
+            .set_hunk_reviewed(&path, fingerprint, reviewed, &hunks);
+        let action = if reviewed {
+            HistoryAction::Reviewed
+        } else {
+            HistoryAction::Unreviewed
+        };
+        self.session.record(action, &path, Some(new_start));
+        if let Some(before) = before {
+            self.push_undo(action, &path, Some(new_start), before);
+        }
+        self.dirty = true;
+        self.rebuild_annotations();
 
-    pub fn set_error(&mut self, msg: impl Into<String>) {
-        self.message = Some(Message {
-            content: msg.into(),
-            message_type: MessageType::Error,
-        });
+        let done = hunks
+            .iter()
+            .filter(|hunk| self.session.is_hunk_reviewed(&path, hunk))
+            .count();
+        if file_changed && reviewed {
+            self.set_message(format!("All hunks reviewed; {} reviewed", path.display()));
+        } else {
+            self.set_message(format!(
+                "Hunk {}; {done}/{} reviewed in {}",
+                if reviewed { "reviewed" } else { "unreviewed" },
+                hunks.len(),
+                path.display()
+            ));
+        }
     }
 
-    pub fn cursor_down(&mut self, lines: usize) {
-        let max_line = self.total_lines().saturating_sub(1);
-        self.diff_state.cursor_line = (self.diff_state.cursor_line + lines).min(max_line);
-        self.ensure_cursor_visible();
-        self.update_current_file_from_cursor();
+    pub fn toggle_pinned_for_file_idx(&mut self, file_idx: usize) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let Some(path) = self
+            .diff_files
+            .get(file_idx)
+            .map(|file| file.display_path().clone())
+        else {
+            return;
+        };
+
+        if let Some(review) = self.session.get_file_mut(&path) {
+            review.pinned = !review.pinned;
+            let pinned = review.pinned;
+            self.dirty = true;
+            self.set_message(if pinned {
+                format!("Pinned {}", path.display())
+            } else {
+                format!("Unpinned {}", path.display())
+            });
+        }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.diff_files.len()
+    }
+
+    /// Reviewed files in the current diff, not counting skipped ones
+    pub fn reviewed_count(&self) -> usize {
+        self.diff_files
+            .iter()
+            .map(|file| file.display_path())
+            .filter(|path| {
+                self.session.is_file_reviewed(path) && !self.session.is_file_skipped(path)
+            })
+            .count()
+    }
+
+    /// Files in the current diff that count towards review progress
+    pub fn reviewable_count(&self) -> usize {
+        self.diff_files
+            .iter()
+            .filter(|file| !self.session.is_file_skipped(file.display_path()))
+            .count()
+    }
+
+    pub fn toggle_skipped_for_file_idx(&mut self, file_idx: usize) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let Some(path) = self
+            .diff_files
+            .get(file_idx)
+            .map(|file| file.display_path().clone())
+        else {
+            return;
+        };
+
+        if let Some(review) = self.session.get_file_mut(&path) {
+            review.skipped = !review.skipped;
+            let skipped = review.skipped;
+            self.dirty = true;
+            self.set_message(if skipped {
+                format!("Skipped {}", path.display())
+            } else {
+                format!("Unskipped {}", path.display())
+            });
+        }
+    }
+
+    pub fn set_message(&mut self, msg: impl Into<String>) {
+        self.message = Some(Message {
+            content: msg.into(),
+            message_type: MessageType::Info,
+        });
+    }
+
+    /// In read-only mode, warn and return true so the caller skips the edit
+    pub fn deny_if_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.set_warning("Read-only session: editing is disabled");
+        }
+        self.read_only
+    }
+
+    pub fn set_warning(&mut self, msg: impl Into<String>) {
+        self.message = Some(Message {
+            content: msg.into(),
+            message_type: MessageType::Warning,
+        });
+    }
+
+    pub fn set_error(&mut self, msg: impl Into<String>) {
+        let content = msg.into();
+        self.error_log.push((chrono::Local::now(), content.clone()));
+        self.message = Some(Message {
+            content,
+            message_type: MessageType::Error,
+        });
+    }
+
+    /// Show `error` in the error popup, offering to run the `retry` command
+    /// again, look at the error log or continue
+    pub fn report_error(&mut self, context: &str, error: &TuicrError, retry: Option<String>) {
+        let chain = error.chain();
+        self.error_log.push((
+            chrono::Local::now(),
+            format!("{context}: {}", chain.join(": ")),
+        ));
+        self.error_report = Some(ErrorReport {
+            context: context.to_string(),
+            chain,
+            retry,
+        });
+        self.input_mode = InputMode::Error;
+    }
+
+    /// Close the error popup, returning the command to run again if retrying
+    pub fn close_error_report(&mut self, retry: bool) -> Option<String> {
+        let report = self.error_report.take()?;
+        self.input_mode = InputMode::Normal;
+        if !retry {
+            self.set_warning(format!("{}: {}", report.context, report.chain[0]));
+        }
+        report.retry.filter(|_| retry)
+    }
+
+    pub fn cursor_down(&mut self, lines: usize) {
+        let max_line = self.total_lines().saturating_sub(1);
+        self.diff_state.cursor_line = (self.diff_state.cursor_line + lines).min(max_line);
+        self.ensure_cursor_visible();
+        self.update_current_file_from_cursor();
     }
 
     pub fn cursor_up(&mut self, lines: usize) {
@@ -654,15 +1802,21 @@ impl App {
         }
     }
 
+    /// Whether diff lines wrap. Wrapping is forced on while a minified file
+    /// is selected, since its lines cannot be read by scrolling sideways.
+    pub fn wrap_enabled(&self) -> bool {
+        self.diff_state.wrap_lines || self.current_file().is_some_and(DiffFile::is_minified)
+    }
+
     pub fn scroll_left(&mut self, cols: usize) {
-        if self.diff_state.wrap_lines {
+        if self.wrap_enabled() {
             return;
         }
         self.diff_state.scroll_x = self.diff_state.scroll_x.saturating_sub(cols);
     }
 
     pub fn scroll_right(&mut self, cols: usize) {
-        if self.diff_state.wrap_lines {
+        if self.wrap_enabled() {
             return;
         }
         let max_scroll_x = self
@@ -678,1013 +1832,3600 @@ impl App {
         self.set_diff_wrap(enabled);
     }
 
-    pub fn set_diff_wrap(&mut self, enabled: bool) {
-        self.diff_state.wrap_lines = enabled;
-        if enabled {
-            self.diff_state.scroll_x = 0;
+    pub fn set_hide_line_endings(&mut self, hide: bool) {
+        if hide == self.hide_line_endings {
+            return;
         }
-        let status = if self.diff_state.wrap_lines {
-            "on"
+        self.hide_line_endings = hide;
+        let hidden = self.refilter_diff().line_endings;
+        if hide {
+            self.set_message(format!("Hiding {hidden} line-ending-only changes"));
         } else {
-            "off"
-        };
-        self.set_message(format!("Diff wrapping: {status}"));
+            self.set_message("Showing line-ending changes");
+        }
     }
 
-    fn ensure_cursor_visible(&mut self) {
-        // Use visible_line_count which is computed during render based on actual line widths.
-        // Fall back to viewport_height if not yet set (before first render).
-        let visible_lines = if self.diff_state.visible_line_count > 0 {
-            self.diff_state.visible_line_count
-        } else {
-            self.diff_state.viewport_height.max(1)
-        };
-        let max_scroll = self.max_scroll_offset();
-        if self.diff_state.cursor_line < self.diff_state.scroll_offset {
-            self.diff_state.scroll_offset = self.diff_state.cursor_line;
-        }
-        if self.diff_state.cursor_line >= self.diff_state.scroll_offset + visible_lines {
-            self.diff_state.scroll_offset =
-                (self.diff_state.cursor_line - visible_lines + 1).min(max_scroll);
-        }
+    pub fn toggle_hide_line_endings(&mut self) {
+        self.set_hide_line_endings(!self.hide_line_endings);
     }
 
-    pub fn search_in_diff_from_cursor(&mut self) -> bool {
-        let pattern = self.search_buffer.clone();
-        if pattern.trim().is_empty() {
-            self.set_message("Search pattern is empty");
-            return false;
+    pub fn set_new_code_only(&mut self, enabled: bool) {
+        if enabled == self.new_code_only {
+            return;
+        }
+        self.new_code_only = enabled;
+        // Expanded gaps are keyed by hunk index, which the filter shifts
+        self.clear_expanded_gaps();
+        let hidden = self.refilter_diff().hunks;
+        if enabled {
+            self.set_message(format!(
+                "Hiding {hidden} hunks of moved or pre-existing code"
+            ));
+        } else {
+            self.set_message("Showing all hunks");
         }
-
-        self.last_search_pattern = Some(pattern.clone());
-        self.search_in_diff(&pattern, self.diff_state.cursor_line, true, true)
     }
 
-    pub fn search_next_in_diff(&mut self) -> bool {
-        let Some(pattern) = self.last_search_pattern.clone() else {
-            self.set_message("No previous search");
-            return false;
-        };
-        self.search_in_diff(&pattern, self.diff_state.cursor_line, true, false)
+    pub fn set_hide_renames(&mut self, hide: bool) {
+        if hide == self.hide_renames {
+            return;
+        }
+        self.hide_renames = hide;
+        let renames = self.refilter_diff().renames;
+        if !hide {
+            self.set_message("Showing renamed lines");
+            return;
+        }
+        if renames.is_empty() {
+            self.set_message("No identifiers renamed throughout a file");
+            return;
+        }
+        let lines: usize = renames.iter().map(|rename| rename.lines).sum();
+        let mut names: Vec<String> = renames
+            .iter()
+            .take(3)
+            .map(|rename| format!("{} → {}", rename.old, rename.new))
+            .collect();
+        if renames.len() > names.len() {
+            names.push(format!("{} more", renames.len() - names.len()));
+        }
+        self.set_message(format!(
+            "Hiding {lines} lines that only rename: {}",
+            names.join(", ")
+        ));
     }
 
-    pub fn search_prev_in_diff(&mut self) -> bool {
-        let Some(pattern) = self.last_search_pattern.clone() else {
-            self.set_message("No previous search");
-            return false;
-        };
-        self.search_in_diff(&pattern, self.diff_state.cursor_line, false, false)
+    pub fn toggle_hide_renames(&mut self) {
+        self.set_hide_renames(!self.hide_renames);
     }
 
-    fn search_in_diff(
-        &mut self,
-        pattern: &str,
-        start_idx: usize,
-        forward: bool,
-        include_current: bool,
-    ) -> bool {
-        let total_lines = self.total_lines();
-        if total_lines == 0 {
-            self.set_message("No diff content to search");
-            return false;
+    /// Re-request the diff with the whitespace changes of `mode` left out.
+    /// Comments keep their anchors: they are keyed by line numbers of the
+    /// files, which ignoring whitespace does not change.
+    pub fn set_whitespace_mode(&mut self, mode: WhitespaceMode) -> Result<()> {
+        let previous = self.whitespace_mode;
+        self.vcs.set_whitespace_mode(mode)?;
+        self.whitespace_mode = mode;
+        if let Err(e) = self.reload_diff_files() {
+            let _ = self.vcs.set_whitespace_mode(previous);
+            self.whitespace_mode = previous;
+            return Err(e);
         }
+        Ok(())
+    }
 
-        if forward {
-            let mut idx = start_idx.min(total_lines.saturating_sub(1));
-            if !include_current {
-                idx = idx.saturating_add(1);
-            }
-            for line_idx in idx..total_lines {
-                if let Some(text) = self.line_text_for_search(line_idx)
-                    && text.contains(pattern)
-                {
-                    self.diff_state.cursor_line = line_idx;
-                    self.ensure_cursor_visible();
-                    self.center_cursor();
-                    self.update_current_file_from_cursor();
-                    return true;
+    /// Switch to the next whitespace mode the VCS supports and that leaves
+    /// changes to review
+    pub fn cycle_whitespace_mode(&mut self) {
+        let mut mode = self.whitespace_mode.next();
+        loop {
+            match self.set_whitespace_mode(mode) {
+                Ok(()) => {
+                    self.set_message(format!("Diff {}", mode.label()));
+                    return;
                 }
-            }
-        } else {
-            let mut idx = start_idx.min(total_lines.saturating_sub(1));
-            if !include_current {
-                idx = idx.saturating_sub(1);
-            }
-            let mut line_idx = idx;
-            loop {
-                if let Some(text) = self.line_text_for_search(line_idx)
-                    && text.contains(pattern)
+                Err(TuicrError::UnsupportedOperation(_) | TuicrError::NoChanges)
+                    if mode != WhitespaceMode::Show =>
                 {
-                    self.diff_state.cursor_line = line_idx;
-                    self.ensure_cursor_visible();
-                    self.center_cursor();
-                    self.update_current_file_from_cursor();
-                    return true;
+                    mode = mode.next();
                 }
-                if line_idx == 0 {
-                    break;
+                Err(e) => {
+                    self.set_error(format!("{e}"));
+                    return;
                 }
-                line_idx = line_idx.saturating_sub(1);
             }
         }
-
-        self.set_message(format!("No matches for \"{pattern}\""));
-        false
     }
 
-    fn line_text_for_search(&self, line_idx: usize) -> Option<String> {
-        match self.line_annotations.get(line_idx)? {
-            AnnotatedLine::FileHeader { file_idx } => {
-                let file = self.diff_files.get(*file_idx)?;
-                Some(format!(
-                    "{} [{}]",
-                    file.display_path().display(),
-                    file.status.as_char()
-                ))
-            }
-            AnnotatedLine::FileComment {
-                file_idx,
-                comment_idx,
-            } => {
-                let path = self.diff_files.get(*file_idx)?.display_path();
-                let review = self.session.files.get(path)?;
-                let comment = review.file_comments.get(*comment_idx)?;
-                Some(comment.content.clone())
-            }
-            AnnotatedLine::LineComment {
-                file_idx,
-                line,
-                comment_idx,
-                ..
-            } => {
-                let path = self.diff_files.get(*file_idx)?.display_path();
-                let review = self.session.files.get(path)?;
-                let comments = review.line_comments.get(line)?;
-                let comment = comments.get(*comment_idx)?;
-                Some(comment.content.clone())
-            }
-            AnnotatedLine::Expander { gap_id } => {
-                let gap = self.gap_size(gap_id)?;
-                Some(format!("... expand ({gap} lines) ..."))
-            }
-            AnnotatedLine::ExpandedContext {
-                gap_id,
-                line_idx: context_idx,
+    /// Stage the changed lines of the visual selection, the hunk under the
+    /// cursor, or the whole file selected in the file list
+    pub fn stage_at_cursor(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        if self.diff_source != DiffSource::WorkingTree {
+            self.set_warning("Only working tree changes can be staged");
+            return;
+        }
+        if self.whitespace_mode != WhitespaceMode::Show {
+            self.set_warning("Show whitespace changes (:set noiw) to stage");
+            return;
+        }
+
+        let mut picked: HashSet<(usize, Option<usize>)> = HashSet::new();
+        let mut file_idx = None;
+        let mut pick = |annotation: &AnnotatedLine| match annotation {
+            AnnotatedLine::HunkHeader {
+                file_idx: idx,
+                hunk_idx,
             } => {
-                let content = self.expanded_content.get(gap_id)?.get(*context_idx)?;
-                Some(content.content.clone())
-            }
-            AnnotatedLine::HunkHeader { file_idx, hunk_idx } => {
-                let file = self.diff_files.get(*file_idx)?;
-                let hunk = file.hunks.get(*hunk_idx)?;
-                Some(hunk.header.clone())
+                file_idx = Some(*idx);
+                picked.insert((*hunk_idx, None));
             }
             AnnotatedLine::DiffLine {
-                file_idx,
+                file_idx: idx,
                 hunk_idx,
-                line_idx: diff_idx,
+                line_idx,
                 ..
             } => {
-                let file = self.diff_files.get(*file_idx)?;
-                let hunk = file.hunks.get(*hunk_idx)?;
-                let line = hunk.lines.get(*diff_idx)?;
-                Some(line.content.clone())
+                file_idx = Some(*idx);
+                picked.insert((*hunk_idx, Some(*line_idx)));
             }
-            AnnotatedLine::BinaryOrEmpty { file_idx } => {
-                let file = self.diff_files.get(*file_idx)?;
-                if file.is_binary {
-                    Some("(binary file)".to_string())
-                } else {
-                    Some("(no changes)".to_string())
+            _ => {}
+        };
+        let whole_file = self.focused_panel == FocusedPanel::FileList;
+        let what = if whole_file {
+            file_idx = Some(self.diff_state.current_file_idx);
+            "file"
+        } else if self.input_mode == InputMode::VisualSelect {
+            let Some(rows) = self.visual_selection_rows() else {
+                self.set_warning("Invalid visual selection");
+                return;
+            };
+            for annotation in &self.line_annotations[rows] {
+                pick(annotation);
+            }
+            "selected lines"
+        } else {
+            if let Some(annotation) = self.line_annotations.get(self.diff_state.cursor_line) {
+                pick(annotation);
+            }
+            // The cursor picks its whole hunk
+            picked = picked
+                .into_iter()
+                .map(|(hunk_idx, _)| (hunk_idx, None))
+                .collect();
+            "hunk"
+        };
+
+        let Some(file) = file_idx.and_then(|idx| self.diff_files.get(idx)) else {
+            self.set_warning("Move the cursor onto a hunk to stage it");
+            return;
+        };
+        let patch = crate::output::selection_patch(file, |hunk_idx, line_idx| {
+            whole_file
+                || picked.contains(&(hunk_idx, None))
+                || picked.contains(&(hunk_idx, Some(line_idx)))
+        });
+        let Some(patch) = patch else {
+            self.set_warning("No changes to stage here");
+            return;
+        };
+        let path = file.display_path().clone();
+
+        match self.vcs.stage_patch(&patch) {
+            Ok(()) => {
+                // The index changed, not the reviewed diff
+                if !self.diff_stale {
+                    self.diff_fingerprint = self.vcs.state_fingerprint();
+                }
+                self.refresh_staged_paths();
+                if self.input_mode == InputMode::VisualSelect {
+                    self.exit_visual_mode();
                 }
+                self.set_message(format!("Staged {what} of {}", path.display()));
             }
-            AnnotatedLine::Spacing => None,
+            Err(TuicrError::UnsupportedOperation(why)) => self.set_warning(why),
+            Err(e) => self.set_error(format!("{e}")),
         }
     }
 
-    fn gap_size(&self, gap_id: &GapId) -> Option<u32> {
-        let file = self.diff_files.get(gap_id.file_idx)?;
-        let hunk = file.hunks.get(gap_id.hunk_idx)?;
-        let prev_hunk = if gap_id.hunk_idx > 0 {
-            file.hunks.get(gap_id.hunk_idx - 1)
+    pub fn set_intraline(&mut self, enabled: bool) {
+        self.intraline = enabled;
+        if enabled {
+            self.set_message(format!(
+                "Highlighting changed words in lines up to {} characters",
+                self.intraline_limit
+            ));
         } else {
-            None
-        };
-        Some(calculate_gap(
-            prev_hunk.map(|h| (&h.new_start, &h.new_count)),
-            hunk.new_start,
-        ))
+            self.set_message("Intraline highlighting off");
+        }
     }
 
-    pub fn center_cursor(&mut self) {
-        let viewport = self.diff_state.viewport_height.max(1);
-        let half_viewport = viewport / 2;
-        let max_scroll = self.max_scroll_offset();
-        self.diff_state.scroll_offset = self
-            .diff_state
-            .cursor_line
-            .saturating_sub(half_viewport)
-            .min(max_scroll);
+    pub fn set_intraline_limit(&mut self, limit: usize) {
+        self.intraline_limit = limit;
+        self.set_intraline(self.intraline);
     }
 
-    pub fn file_list_down(&mut self, n: usize) {
-        let visible_items = self.build_visible_items();
-        let max_idx = visible_items.len().saturating_sub(1);
-        let new_idx = (self.file_list_state.selected() + n).min(max_idx);
-        self.file_list_state.select(new_idx);
+    pub fn set_indent_guides(&mut self, enabled: bool) {
+        self.indent_guides = enabled;
+        if enabled {
+            self.set_message(format!("Indent guides every {} columns", self.indent_width));
+        } else {
+            self.set_message("Indent guides off");
+        }
     }
 
-    pub fn file_list_up(&mut self, n: usize) {
-        let new_idx = self.file_list_state.selected().saturating_sub(n);
-        self.file_list_state.select(new_idx);
+    pub fn set_indent_width(&mut self, width: usize) {
+        if width == 0 {
+            self.set_error("Indent width must be at least 1");
+            return;
+        }
+        self.indent_width = width;
+        self.set_indent_guides(self.indent_guides);
     }
 
-    pub fn file_list_viewport_scroll_down(&mut self, lines: usize) {
-        let visible_items = self.build_visible_items();
-        let total = visible_items.len();
-        let viewport = self.file_list_state.viewport_height.max(1);
-        let selected = self.file_list_state.selected();
+    /// Columns between indent guides, 0 when they are turned off
+    pub fn indent_guide_width(&self) -> usize {
+        if self.indent_guides {
+            self.indent_width
+        } else {
+            0
+        }
+    }
 
-        // Get current offset
-        let current_offset = self.file_list_state.list_state.offset();
-        let max_offset = total.saturating_sub(viewport);
+    pub fn set_cursor_line_highlight(&mut self, enabled: bool) {
+        self.cursor_line_highlight = enabled;
+        self.set_message(if enabled {
+            "Highlighting the cursor line"
+        } else {
+            "Cursor line highlight off"
+        });
+    }
 
-        // Move viewport down
-        let new_offset = (current_offset + lines).min(max_offset);
-        *self.file_list_state.list_state.offset_mut() = new_offset;
+    pub fn set_dim_viewed(&mut self, enabled: bool) {
+        self.dim_viewed = enabled;
+        self.viewed_cursor = None;
+        self.track_viewed_lines();
+        self.set_message(if enabled {
+            "Dimming lines already viewed"
+        } else {
+            "Dimming viewed lines off"
+        });
+    }
 
-        // Clamp cursor to stay within viewport bounds
-        // If cursor is now above the visible area, move it to the top visible line
-        if selected < new_offset {
-            self.file_list_state.select(new_offset);
+    /// Mark the diff lines the cursor moved over since the last call as
+    /// viewed. Moves longer than a screen, or into another file, are jumps
+    /// that only view the line they land on. Side-by-side rows do not map
+    /// to single diff lines, so only the unified view tracks them.
+    pub fn track_viewed_lines(&mut self) {
+        if !self.dim_viewed || self.diff_view_mode != DiffViewMode::Unified {
+            return;
+        }
+        let cursor = self.diff_state.cursor_line;
+        let file = self.diff_state.current_file_idx;
+        let from = match self.viewed_cursor.replace((cursor, file)) {
+            Some(last) if last == (cursor, file) => return,
+            Some((from, from_file))
+                if from_file == file
+                    && from.abs_diff(cursor) <= self.diff_state.visible_line_count =>
+            {
+                from
+            }
+            _ => cursor,
+        };
+        for idx in from.min(cursor)..=from.max(cursor) {
+            let Some(AnnotatedLine::DiffLine {
+                file_idx,
+                old_lineno,
+                new_lineno,
+                ..
+            }) = self.line_annotations.get(idx)
+            else {
+                continue;
+            };
+            let Some(file) = self.diff_files.get(*file_idx) else {
+                continue;
+            };
+            if let Some(review) = self.session.files.get_mut(file.display_path()) {
+                review.mark_line_viewed(*old_lineno, *new_lineno);
+            }
         }
     }
 
-    pub fn file_list_viewport_scroll_up(&mut self, lines: usize) {
-        let viewport = self.file_list_state.viewport_height.max(1);
-        let selected = self.file_list_state.selected();
+    pub fn set_cursor_column_highlight(&mut self, enabled: bool) {
+        self.cursor_column_highlight = enabled;
+        self.set_message(if enabled {
+            "Highlighting the cursor line's code column"
+        } else {
+            "Cursor column highlight off"
+        });
+    }
 
-        // Get current offset
-        let current_offset = self.file_list_state.list_state.offset();
+    pub fn set_relative_line_numbers(&mut self, enabled: bool) {
+        self.relative_line_numbers = enabled;
+        self.set_message(if enabled {
+            "Relative line numbers"
+        } else {
+            "Absolute line numbers"
+        });
+    }
 
-        // Move viewport up
-        let new_offset = current_offset.saturating_sub(lines);
-        *self.file_list_state.list_state.offset_mut() = new_offset;
+    pub fn set_reduce_motion(&mut self, enabled: bool) {
+        self.reduce_motion = enabled;
+        self.set_message(if enabled {
+            "Reduced motion: the screen only redraws after input or changes"
+        } else {
+            "Reduced motion off"
+        });
+    }
 
-        // Clamp cursor to stay within viewport bounds
-        // If cursor is now below the visible area, move it to the bottom visible line
-        let max_visible = new_offset + viewport - 1;
-        if selected > max_visible {
-            self.file_list_state.select(max_visible);
-        }
+    pub fn set_blame_gutter(&mut self, enabled: bool) {
+        self.blame_gutter = enabled;
+        self.set_message(if enabled {
+            "Showing blame"
+        } else {
+            "Blame gutter off"
+        });
     }
 
-    pub fn jump_to_file(&mut self, idx: usize) {
-        use std::path::Path;
+    /// Blame the files with lines in the viewport that have not been blamed
+    /// yet, so only files the reviewer looks at pay for it
+    pub fn load_visible_blame(&mut self) {
+        let start = self.diff_state.scroll_offset;
+        let end = (start + self.diff_state.viewport_height).min(self.line_annotations.len());
+        let mut file_indices: Vec<usize> = self.line_annotations[start.min(end)..end]
+            .iter()
+            .filter_map(|annotation| match annotation {
+                AnnotatedLine::DiffLine { file_idx, .. } => Some(*file_idx),
+                AnnotatedLine::ExpandedContext { gap_id, .. } => Some(gap_id.file_idx),
+                _ => None,
+            })
+            .collect();
+        file_indices.dedup();
 
-        if idx < self.diff_files.len() {
-            self.diff_state.current_file_idx = idx;
-            self.diff_state.cursor_line = self.calculate_file_scroll_offset(idx);
-            let max_scroll = self.max_scroll_offset();
-            self.diff_state.scroll_offset = self.diff_state.cursor_line.min(max_scroll);
+        if let Err(e) = self.load_blame(file_indices) {
+            self.blame_gutter = false;
+            self.set_error(e.to_string());
+        }
+    }
 
-            let file_path = self.diff_files[idx].display_path().clone();
-            let mut current = file_path.parent();
-            while let Some(parent) = current {
-                if parent != Path::new("") {
-                    self.expanded_dirs
-                        .insert(parent.to_string_lossy().to_string());
-                }
-                current = parent.parent();
+    /// Blame the given files unless already blamed. Only a VCS without blame
+    /// support is an error: files that fail to blame are left blank rather
+    /// than retried.
+    fn load_blame(&mut self, file_indices: impl IntoIterator<Item = usize>) -> Result<()> {
+        let revision = match &self.diff_source {
+            DiffSource::CommitRange(ids) => ids.last().cloned(),
+            DiffSource::WorkingTree | DiffSource::Ref(_) => None,
+        };
+        for file_idx in file_indices {
+            let Some(file) = self.diff_files.get(file_idx) else {
+                continue;
+            };
+            if file.status == FileStatus::Deleted || self.blame.contains_key(file.display_path()) {
+                continue;
             }
-
-            if let Some(tree_idx) = self.file_idx_to_tree_idx(idx) {
-                self.file_list_state.select(tree_idx);
+            let path = file.display_path().clone();
+            match self.vcs.annotate_file(&path, revision.as_deref()) {
+                Ok(lines) => {
+                    self.blame.insert(path, lines);
+                }
+                Err(e @ TuicrError::UnsupportedOperation(_)) => return Err(e),
+                Err(_) => {
+                    self.blame.insert(path, Vec::new());
+                }
             }
         }
+        Ok(())
     }
 
-    pub fn next_file(&mut self) {
-        let visible_items = self.build_visible_items();
-        let current_file_idx = self.diff_state.current_file_idx;
+    /// Blame of a new-side line: None when the file has not been blamed or
+    /// the line is past what was blamed, `Some(None)` when it is uncommitted
+    pub fn blame_line(
+        &self,
+        path: &std::path::Path,
+        new_lineno: u32,
+    ) -> Option<Option<&BlameLine>> {
+        let lines = self.blame.get(path)?;
+        let line = lines.get((new_lineno as usize).checked_sub(1)?)?;
+        Some(line.as_ref())
+    }
 
-        for item in &visible_items {
-            if let FileTreeItem::File { file_idx, .. } = item
-                && *file_idx > current_file_idx
-            {
-                self.jump_to_file(*file_idx);
-                return;
+    /// Display column where the code of the cursor line starts, with tabs
+    /// expanded to the indent width, and the line's origin. None when the
+    /// cursor is not on code.
+    pub fn cursor_indent_column(&self) -> Option<(usize, LineOrigin)> {
+        let (_, origin, text) = self.code_line_at(self.diff_state.cursor_line)?;
+        let width = self.indent_width.max(1);
+        let mut col = 0;
+        for ch in text.chars() {
+            match ch {
+                ' ' => col += 1,
+                '\t' => col += width - col % width,
+                _ => return Some((col, origin)),
             }
         }
+        None
     }
 
-    pub fn prev_file(&mut self) {
-        let visible_items = self.build_visible_items();
-        let current_file_idx = self.diff_state.current_file_idx;
-
-        for item in visible_items.iter().rev() {
-            if let FileTreeItem::File { file_idx, .. } = item
-                && *file_idx < current_file_idx
-            {
-                self.jump_to_file(*file_idx);
-                return;
-            }
+    /// Longest line that gets intraline emphasis, 0 when it is turned off
+    pub fn intraline_max_len(&self) -> usize {
+        if self.intraline {
+            self.intraline_limit
+        } else {
+            0
         }
     }
 
-    fn file_idx_to_tree_idx(&self, target_file_idx: usize) -> Option<usize> {
-        let visible_items = self.build_visible_items();
-        for (tree_idx, item) in visible_items.iter().enumerate() {
-            if let FileTreeItem::File { file_idx, .. } = item
-                && *file_idx == target_file_idx
-            {
-                return Some(tree_idx);
-            }
+    /// Apply the diff filters (`:set hideeol`, `:set hiderenames`,
+    /// `:set newcode`) to freshly loaded diff files, keeping them as loaded
+    /// to undo the filters. Returns what was hidden.
+    fn apply_diff_filters(&mut self) -> HiddenChanges {
+        self.apply_highlight_overrides();
+        if !self.hide_line_endings && !self.hide_renames && !self.new_code_only {
+            self.unfiltered_diff_files = None;
+            return HiddenChanges::default();
+        }
+        self.unfiltered_diff_files = Some(self.diff_files.clone());
+        let line_endings = if self.hide_line_endings {
+            self.diff_files
+                .iter_mut()
+                .map(DiffFile::collapse_line_ending_changes)
+                .sum()
+        } else {
+            0
+        };
+        let renames = if self.hide_renames {
+            self.diff_files
+                .iter_mut()
+                .flat_map(collapse_renames)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let hunks = if self.new_code_only {
+            self.hide_old_code_hunks()
+        } else {
+            0
+        };
+        HiddenChanges {
+            line_endings,
+            renames,
+            hunks,
         }
-        None
     }
 
-    pub fn next_hunk(&mut self) {
-        // Find the next hunk header position after current cursor
-        let mut cumulative = 0;
-        for file in &self.diff_files {
-            let path = file.display_path();
+    /// Line limit for highlighting `path`, `None` when it is highlighted
+    /// whatever its size
+    fn highlight_limit(&self, path: &PathBuf) -> Option<usize> {
+        (self.highlight_max_lines > 0 && !self.forced_highlight.contains(path))
+            .then_some(self.highlight_max_lines)
+    }
 
-            // File header
-            cumulative += 1;
+    /// Re-highlight the loaded files whose language was forced with `:lang`,
+    /// and render files over the highlighting line limit plain
+    fn apply_highlight_overrides(&mut self) {
+        self.rehighlight_files(|file, language, limit| {
+            language.is_some() || limit.is_some_and(|max| file.line_count() > max)
+        });
+    }
 
-            // If file is reviewed, skip all content
-            if self.session.is_file_reviewed(path) {
-                continue;
-            }
+    /// Highlight the current file even when it is over the line limit
+    pub fn force_highlight(&mut self) {
+        let Some(path) = self
+            .diff_files
+            .get(self.diff_state.current_file_idx)
+            .map(|file| file.display_path().clone())
+        else {
+            self.set_warning("No file to highlight");
+            return;
+        };
+        self.forced_highlight.insert(path.clone());
+        self.rehighlight_files(|file, _, _| *file.display_path() == path);
+        self.set_message(format!("Highlighting {}", path.display()));
+    }
 
-            // File comments
-            if let Some(review) = self.session.files.get(path) {
-                cumulative += review.file_comments.len();
-            }
+    /// Set the line limit for highlighting a file (0 for none) and re-render
+    /// the files it changes
+    pub fn set_highlight_max_lines(&mut self, max_lines: usize) {
+        let old = self.highlight_max_lines;
+        self.highlight_max_lines = max_lines;
+        let over = |limit: usize, file: &DiffFile| limit > 0 && file.line_count() > limit;
+        self.rehighlight_files(|file, _, _| over(old, file) != over(max_lines, file));
+        self.set_message(if max_lines == 0 {
+            "Highlighting files of any size".to_string()
+        } else {
+            format!("Highlighting files up to {max_lines} diff lines")
+        });
+    }
 
-            if file.is_binary || file.hunks.is_empty() {
-                cumulative += 1; // "(binary file)" or "(no changes)"
-            } else {
-                for hunk in &file.hunks {
-                    // This is a hunk header position
-                    if cumulative > self.diff_state.cursor_line {
-                        self.diff_state.cursor_line = cumulative;
-                        self.ensure_cursor_visible();
-                        self.update_current_file_from_cursor();
-                        return;
-                    }
-                    cumulative += 1; // hunk header
-                    cumulative += hunk.lines.len(); // diff lines
-                }
+    /// Recompute the highlighting of the loaded files for which `filter`,
+    /// given the file, its forced language and its line limit, holds
+    fn rehighlight_files(
+        &mut self,
+        filter: impl Fn(&DiffFile, Option<&str>, Option<usize>) -> bool,
+    ) {
+        let highlighter = self.theme.syntax_highlighter();
+        let limits: Vec<Option<usize>> = self
+            .diff_files
+            .iter()
+            .chain(self.unfiltered_diff_files.iter().flatten())
+            .map(|file| self.highlight_limit(file.display_path()))
+            .collect();
+        for (file, limit) in self
+            .diff_files
+            .iter_mut()
+            .chain(self.unfiltered_diff_files.iter_mut().flatten())
+            .zip(limits)
+        {
+            let language = self.session.languages.get(file.display_path());
+            let language = language.map(String::as_str);
+            if filter(file, language, limit) {
+                highlighter.rehighlight_file(file, language, limit);
             }
-            cumulative += 1; // spacing
         }
     }
 
-    pub fn prev_hunk(&mut self) {
-        // Find the previous hunk header position before current cursor
-        let mut hunk_positions: Vec<usize> = Vec::new();
-        let mut cumulative = 0;
-
-        for file in &self.diff_files {
-            let path = file.display_path();
+    /// Force the syntax highlighting language of the current file, or go
+    /// back to detecting it from the path with `None`. Only remembered in the
+    /// session outside read-only mode.
+    pub fn set_file_language(&mut self, language: Option<&str>) {
+        let Some(path) = self
+            .diff_files
+            .get(self.diff_state.current_file_idx)
+            .map(|file| file.display_path().clone())
+        else {
+            self.set_warning("No file to set the language of");
+            return;
+        };
+        let highlighter = self.theme.syntax_highlighter();
+        let name = match language {
+            Some(language) => match highlighter.language_name(language) {
+                Some(name) => Some(name.to_string()),
+                None => {
+                    self.set_error(format!("Unknown language '{language}'"));
+                    return;
+                }
+            },
+            None => None,
+        };
+        match &name {
+            Some(name) => self.session.languages.insert(path.clone(), name.clone()),
+            None => self.session.languages.remove(&path),
+        };
+        self.dirty |= !self.read_only;
+        self.rehighlight_files(|file, _, _| *file.display_path() == path);
+        self.set_message(match name {
+            Some(name) => format!("Highlighting {} as {name}", path.display()),
+            None => format!("Detecting the language of {} from its name", path.display()),
+        });
+    }
 
-            cumulative += 1; // File header
+    /// Re-apply the diff filters to the files as loaded after a filter was
+    /// switched, keeping the cursor in range
+    fn refilter_diff(&mut self) -> HiddenChanges {
+        if let Some(files) = self.unfiltered_diff_files.take() {
+            self.diff_files = files;
+        }
+        let hidden = self.apply_diff_filters();
+        self.rebuild_annotations();
+        let max_line = self.total_lines().saturating_sub(1);
+        self.diff_state.cursor_line = self.diff_state.cursor_line.min(max_line);
+        self.diff_state.scroll_offset = self.diff_state.scroll_offset.min(self.max_scroll_offset());
+        hidden
+    }
 
-            // If file is reviewed, skip all content
-            if self.session.is_file_reviewed(path) {
-                continue;
-            }
+    /// Drop hunks of moved code, and within a commit range hunks whose added
+    /// lines blame to commits outside of it. Returns how many were dropped.
+    fn hide_old_code_hunks(&mut self) -> usize {
+        // Outside a commit range every added line is new to blame
+        let range = match &self.diff_source {
+            DiffSource::CommitRange(ids) => Some(ids.clone()),
+            DiffSource::WorkingTree | DiffSource::Ref(_) => None,
+        };
+        if range.is_some() {
+            // Without blame, moved-line detection still applies
+            let _ = self.load_blame(0..self.diff_files.len());
+        }
 
-            if let Some(review) = self.session.files.get(path) {
-                cumulative += review.file_comments.len();
-            }
+        let blame = &self.blame;
+        hide_moved_hunks(&mut self.diff_files, |path, line| {
+            let Some(ids) = &range else {
+                return false;
+            };
+            line.new_lineno
+                .and_then(|lineno| blame.get(path)?.get(lineno as usize - 1)?.as_ref())
+                .is_some_and(|blame| !ids.iter().any(|id| id.starts_with(&blame.short_id)))
+        })
+    }
 
-            if file.is_binary || file.hunks.is_empty() {
-                cumulative += 1;
-            } else {
-                for hunk in &file.hunks {
-                    hunk_positions.push(cumulative);
-                    cumulative += 1;
-                    cumulative += hunk.lines.len();
-                }
-            }
-            cumulative += 1;
+    pub fn set_export_context_lines(&mut self, lines: usize) {
+        self.export_context_lines = lines;
+        if lines == 0 {
+            self.set_message("Export context snippets: off");
+        } else {
+            self.set_message(format!("Export context snippets: {lines} lines"));
         }
+    }
 
-        // Find the last hunk position before current cursor
-        for &pos in hunk_positions.iter().rev() {
-            if pos < self.diff_state.cursor_line {
-                self.diff_state.cursor_line = pos;
+    pub fn set_key_timeout(&mut self, ms: u64) {
+        self.key_timeout_ms = ms;
+        if ms == 0 {
+            self.set_message("Multi-key timeout: off");
+        } else {
+            self.set_message(format!("Multi-key timeout: {ms}ms"));
+        }
+    }
+
+    pub fn set_quit_timeout(&mut self, ms: u64) {
+        self.quit_timeout_ms = ms;
+        if ms == 0 {
+            self.set_message("Force quit: on first press");
+        } else {
+            self.set_message(format!("Force quit: press twice within {ms}ms"));
+        }
+    }
+
+    pub fn export_options(&self) -> ExportOptions<'_> {
+        ExportOptions {
+            diff_files: &self.diff_files,
+            context_lines: self.export_context_lines,
+            issue_links: Some(&self.issue_linker),
+        }
+    }
+
+    pub fn set_diff_wrap(&mut self, enabled: bool) {
+        self.diff_state.wrap_lines = enabled;
+        if enabled {
+            self.diff_state.scroll_x = 0;
+        }
+        let status = if self.diff_state.wrap_lines {
+            "on"
+        } else {
+            "off"
+        };
+        self.set_message(format!("Diff wrapping: {status}"));
+    }
+
+    fn ensure_cursor_visible(&mut self) {
+        // Use visible_line_count which is computed during render based on actual line widths.
+        // Fall back to viewport_height if not yet set (before first render).
+        let visible_lines = if self.diff_state.visible_line_count > 0 {
+            self.diff_state.visible_line_count
+        } else {
+            self.diff_state.viewport_height.max(1)
+        };
+        let max_scroll = self.max_scroll_offset();
+        if self.diff_state.cursor_line < self.diff_state.scroll_offset {
+            self.diff_state.scroll_offset = self.diff_state.cursor_line;
+        }
+        if self.diff_state.cursor_line >= self.diff_state.scroll_offset + visible_lines {
+            self.diff_state.scroll_offset =
+                (self.diff_state.cursor_line - visible_lines + 1).min(max_scroll);
+        }
+    }
+
+    pub fn search_in_diff_from_cursor(&mut self) -> bool {
+        if self.search_buffer.trim().is_empty() {
+            self.set_message("Search pattern is empty");
+            return false;
+        }
+
+        let pattern = SearchPattern::new(&self.search_buffer);
+        self.last_search_pattern = Some(pattern.clone());
+        self.search_highlight = true;
+        self.comment_search = false;
+        self.search_in_diff(&pattern, self.diff_state.cursor_line, true, true)
+    }
+
+    /// Move the cursor to the next comment whose text or replies match
+    /// `query`, for `:ccomments /pattern`; `n`/`N` then step through the
+    /// matching comments only
+    pub fn search_comments(&mut self, query: &str) -> bool {
+        let query = query.strip_prefix('/').unwrap_or(query);
+        let query = query.strip_suffix('/').unwrap_or(query);
+        if query.trim().is_empty() {
+            self.set_message("Usage: :ccomments /pattern");
+            return false;
+        }
+
+        let pattern = SearchPattern::new(query);
+        self.last_search_pattern = Some(pattern.clone());
+        self.search_highlight = true;
+        self.comment_search = true;
+        self.search_in_diff(&pattern, self.diff_state.cursor_line, true, true)
+    }
+
+    pub fn search_next_in_diff(&mut self) -> bool {
+        let Some(pattern) = self.last_search_pattern.clone() else {
+            self.set_message("No previous search");
+            return false;
+        };
+        self.search_highlight = true;
+        self.search_in_diff(&pattern, self.diff_state.cursor_line, true, false)
+    }
+
+    pub fn search_prev_in_diff(&mut self) -> bool {
+        let Some(pattern) = self.last_search_pattern.clone() else {
+            self.set_message("No previous search");
+            return false;
+        };
+        self.search_highlight = true;
+        self.search_in_diff(&pattern, self.diff_state.cursor_line, false, false)
+    }
+
+    /// The pattern whose matches the diff highlights, if any
+    pub fn highlighted_search(&self) -> Option<&SearchPattern> {
+        self.last_search_pattern
+            .as_ref()
+            .filter(|_| self.search_highlight)
+    }
+
+    pub fn clear_search_highlight(&mut self) {
+        self.search_highlight = false;
+    }
+
+    /// Move the cursor to the line holding the bracket that matches one on
+    /// the cursor line. Only code of the same file and side is searched, so
+    /// brackets on deleted lines match old code and the rest new code.
+    pub fn jump_to_matching_bracket(&mut self) -> bool {
+        let cursor = self.diff_state.cursor_line;
+        let Some((file_idx, origin, _)) = self.code_line_at(cursor) else {
+            self.set_message("Move cursor to a diff line to match brackets");
+            return false;
+        };
+        let same_side = |line_origin: LineOrigin| match origin {
+            LineOrigin::Deletion => line_origin != LineOrigin::Addition,
+            _ => line_origin != LineOrigin::Deletion,
+        };
+        let (rows, texts): (Vec<usize>, Vec<&str>) = (0..self.line_annotations.len())
+            .filter_map(|idx| {
+                let (file, line_origin, text) = self.code_line_at(idx)?;
+                (file == file_idx && same_side(line_origin)).then_some((idx, text))
+            })
+            .unzip();
+        let matched = rows
+            .iter()
+            .position(|row| *row == cursor)
+            .and_then(|pos| find_matching_bracket(&texts, pos))
+            .map(|pos| rows[pos]);
+
+        match matched {
+            Some(row) => {
+                self.diff_state.cursor_line = row;
                 self.ensure_cursor_visible();
                 self.update_current_file_from_cursor();
-                return;
+                true
+            }
+            None => {
+                self.set_message("No matching bracket");
+                false
+            }
+        }
+    }
+
+    /// File index, origin and content of a diff or expanded context row
+    fn code_line_at(&self, line_idx: usize) -> Option<(usize, LineOrigin, &str)> {
+        match self.line_annotations.get(line_idx)? {
+            AnnotatedLine::DiffLine {
+                file_idx,
+                hunk_idx,
+                line_idx,
+                ..
+            } => {
+                let line = self
+                    .diff_files
+                    .get(*file_idx)?
+                    .hunks
+                    .get(*hunk_idx)?
+                    .lines
+                    .get(*line_idx)?;
+                Some((*file_idx, line.origin, line.content.as_str()))
+            }
+            AnnotatedLine::ExpandedContext { gap_id, line_idx } => {
+                let line = self.expanded_content.get(gap_id)?.get(*line_idx)?;
+                Some((gap_id.file_idx, LineOrigin::Context, line.content.as_str()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Move the cursor to the next line matching `pattern`, across files,
+    /// wrapping around the end (or start) of the diff
+    fn search_in_diff(
+        &mut self,
+        pattern: &SearchPattern,
+        start_idx: usize,
+        forward: bool,
+        include_current: bool,
+    ) -> bool {
+        let total_lines = self.total_lines();
+        if total_lines == 0 {
+            self.set_message("No diff content to search");
+            return false;
+        }
+
+        let start = start_idx.min(total_lines - 1);
+        let first_step = usize::from(!include_current);
+        for step in first_step..first_step + total_lines {
+            let line_idx = if forward {
+                (start + step) % total_lines
+            } else {
+                (start + total_lines * 2 - step) % total_lines
+            };
+            let text = if self.comment_search {
+                self.comment_text_for_search(line_idx)
+            } else {
+                self.line_text_for_search(line_idx)
+            };
+            if text.is_some_and(|text| pattern.is_match(&text)) {
+                self.diff_state.cursor_line = line_idx;
+                self.ensure_cursor_visible();
+                self.center_cursor();
+                self.update_current_file_from_cursor();
+                let wrapped = if forward {
+                    line_idx < start
+                } else {
+                    line_idx > start
+                };
+                if wrapped {
+                    self.set_message(if forward {
+                        "Search hit bottom, continuing at top"
+                    } else {
+                        "Search hit top, continuing at bottom"
+                    });
+                }
+                return true;
+            }
+        }
+
+        if self.comment_search {
+            self.set_message(format!("No comments match \"{}\"", pattern.as_str()));
+        } else {
+            self.set_message(format!("No matches for \"{}\"", pattern.as_str()));
+        }
+        false
+    }
+
+    fn line_text_for_search(&self, line_idx: usize) -> Option<String> {
+        self.annotation_text(self.line_annotations.get(line_idx)?)
+    }
+
+    /// Text and replies of the comment whose box starts at `line_idx`; `None`
+    /// for other rows, so each comment matches once
+    fn comment_text_for_search(&self, line_idx: usize) -> Option<String> {
+        let annotation = self.line_annotations.get(line_idx)?;
+        if line_idx > 0 && same_comment(&self.line_annotations[line_idx - 1], annotation) {
+            return None;
+        }
+        let comment = match annotation {
+            AnnotatedLine::FileComment {
+                file_idx,
+                comment_idx,
+            } => {
+                let path = self.diff_files.get(*file_idx)?.display_path();
+                let review = self.session.files.get(path)?;
+                review.file_comments.get(*comment_idx)?
+            }
+            AnnotatedLine::LineComment {
+                file_idx,
+                line,
+                comment_idx,
+                ..
+            } => {
+                let path = self.diff_files.get(*file_idx)?.display_path();
+                let review = self.session.files.get(path)?;
+                review.line_comments.get(line)?.get(*comment_idx)?
+            }
+            _ => return None,
+        };
+        let mut text = comment.content.clone();
+        for reply in &comment.replies {
+            text.push('\n');
+            text.push_str(&reply.content);
+        }
+        Some(text)
+    }
+
+    fn annotation_text(&self, annotation: &AnnotatedLine) -> Option<String> {
+        match annotation {
+            AnnotatedLine::FileHeader { file_idx } => {
+                let file = self.diff_files.get(*file_idx)?;
+                Some(format!("{} [{}]", file.path_label(), file.status.as_char()))
+            }
+            AnnotatedLine::FileComment {
+                file_idx,
+                comment_idx,
+            } => {
+                let path = self.diff_files.get(*file_idx)?.display_path();
+                let review = self.session.files.get(path)?;
+                let comment = review.file_comments.get(*comment_idx)?;
+                Some(comment.content.clone())
+            }
+            AnnotatedLine::LineComment {
+                file_idx,
+                line,
+                comment_idx,
+                ..
+            } => {
+                let path = self.diff_files.get(*file_idx)?.display_path();
+                let review = self.session.files.get(path)?;
+                let comments = review.line_comments.get(line)?;
+                let comment = comments.get(*comment_idx)?;
+                Some(comment.content.clone())
+            }
+            AnnotatedLine::Expander { gap_id } => {
+                let gap = self.gap_size(gap_id)?;
+                Some(format!("... expand ({gap} lines) ..."))
+            }
+            AnnotatedLine::ExpandedContext {
+                gap_id,
+                line_idx: context_idx,
+            } => {
+                let content = self.expanded_content.get(gap_id)?.get(*context_idx)?;
+                Some(content.content.clone())
+            }
+            AnnotatedLine::HunkHeader { file_idx, hunk_idx } => {
+                let file = self.diff_files.get(*file_idx)?;
+                let hunk = file.hunks.get(*hunk_idx)?;
+                Some(hunk.header.clone())
+            }
+            AnnotatedLine::DiffLine {
+                file_idx,
+                hunk_idx,
+                line_idx: diff_idx,
+                ..
+            } => {
+                let file = self.diff_files.get(*file_idx)?;
+                let hunk = file.hunks.get(*hunk_idx)?;
+                let line = hunk.lines.get(*diff_idx)?;
+                Some(line.content.clone())
+            }
+            AnnotatedLine::BinaryOrEmpty { file_idx } => {
+                let file = self.diff_files.get(*file_idx)?;
+                if file.is_binary {
+                    Some(file.binary_sizes.placeholder())
+                } else {
+                    Some("(no changes)".to_string())
+                }
+            }
+            AnnotatedLine::Spacing => None,
+        }
+    }
+
+    /// Plain text of the diff rows currently on screen, or of the visual
+    /// selection when one is active. Gutters, styling and comment boxes are
+    /// stripped so the result pastes cleanly.
+    pub fn visible_region_text(&self) -> String {
+        let range = match self.visual_selection_rows() {
+            Some(range) => range,
+            None => {
+                let start = self.diff_state.scroll_offset;
+                let rows = self.diff_state.visible_line_count.max(1);
+                start..(start + rows).min(self.line_annotations.len())
+            }
+        };
+        self.rows_text(range)
+    }
+
+    /// Plain text of every row of the diff view, top to bottom
+    pub fn view_text(&self) -> String {
+        self.rows_text(0..self.line_annotations.len())
+    }
+
+    fn rows_text(&self, range: std::ops::Range<usize>) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut prev: Option<&AnnotatedLine> = None;
+        for annotation in self.line_annotations.get(range).unwrap_or_default() {
+            // Comment boxes span several rows; emit each comment once
+            let repeated = prev.is_some_and(|prev| same_comment(prev, annotation));
+            prev = Some(annotation);
+            if repeated {
+                continue;
+            }
+            lines.extend(self.plain_line_text(annotation));
+        }
+
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        let start = lines.iter().position(|l| !l.is_empty()).unwrap_or(0);
+        lines[start..].join("\n")
+    }
+
+    /// Annotation rows covered by the visual selection, if active
+    fn visual_selection_rows(&self) -> Option<std::ops::Range<usize>> {
+        let (_, side) = self.get_visual_selection()?;
+        let (anchor_line, _) = self.visual_anchor?;
+        let cursor = self.diff_state.cursor_line;
+        let cursor_file = match self.line_annotations.get(cursor)? {
+            AnnotatedLine::DiffLine { file_idx, .. } => *file_idx,
+            _ => return None,
+        };
+        let anchor = self.line_annotations.iter().position(|annotation| {
+            matches!(
+                annotation,
+                AnnotatedLine::DiffLine { file_idx, old_lineno, new_lineno, .. }
+                    if *file_idx == cursor_file
+                        && match side {
+                            LineSide::New => *new_lineno == Some(anchor_line),
+                            LineSide::Old => new_lineno.is_none() && *old_lineno == Some(anchor_line),
+                        }
+            )
+        })?;
+        Some(anchor.min(cursor)..anchor.max(cursor) + 1)
+    }
+
+    fn plain_line_text(&self, annotation: &AnnotatedLine) -> Option<String> {
+        match annotation {
+            AnnotatedLine::FileHeader { file_idx } if self.screen_reader => {
+                let file = self.diff_files.get(*file_idx)?;
+                Some(format!(
+                    "file {} {}",
+                    file.status.label(),
+                    file.path_label()
+                ))
+            }
+            AnnotatedLine::FileHeader { file_idx } => {
+                let file = self.diff_files.get(*file_idx)?;
+                Some(file.path_label())
+            }
+            AnnotatedLine::FileComment { .. } | AnnotatedLine::LineComment { .. } => {
+                let quote = if self.screen_reader {
+                    "comment: "
+                } else {
+                    "> "
+                };
+                self.annotation_text(annotation)
+                    .map(|text| format!("{quote}{}", text.replace('\n', &format!("\n{quote}"))))
+            }
+            AnnotatedLine::Expander { .. } => Some("...".to_string()),
+            AnnotatedLine::ExpandedContext { .. } => self
+                .annotation_text(annotation)
+                .map(|text| format!(" {text}")),
+            AnnotatedLine::DiffLine {
+                file_idx,
+                hunk_idx,
+                line_idx,
+                ..
+            } => {
+                let line = self
+                    .diff_files
+                    .get(*file_idx)?
+                    .hunks
+                    .get(*hunk_idx)?
+                    .lines
+                    .get(*line_idx)?;
+                let prefix = match (line.origin, self.screen_reader) {
+                    (LineOrigin::Addition, false) => "+",
+                    (LineOrigin::Deletion, false) => "-",
+                    (LineOrigin::Context, false) => " ",
+                    (LineOrigin::Addition, true) => "added: ",
+                    (LineOrigin::Deletion, true) => "removed: ",
+                    (LineOrigin::Context, true) => "",
+                };
+                Some(format!("{prefix}{}", line.content))
+            }
+            AnnotatedLine::HunkHeader { .. } | AnnotatedLine::BinaryOrEmpty { .. } => {
+                self.annotation_text(annotation)
+            }
+            AnnotatedLine::Spacing => Some(String::new()),
+        }
+    }
+
+    fn gap_size(&self, gap_id: &GapId) -> Option<u32> {
+        let file = self.diff_files.get(gap_id.file_idx)?;
+        let hunk = file.hunks.get(gap_id.hunk_idx)?;
+        let prev_hunk = if gap_id.hunk_idx > 0 {
+            file.hunks.get(gap_id.hunk_idx - 1)
+        } else {
+            None
+        };
+        Some(calculate_gap(
+            prev_hunk.map(|h| (&h.new_start, &h.new_count)),
+            hunk.new_start,
+        ))
+    }
+
+    pub fn center_cursor(&mut self) {
+        let viewport = self.diff_state.viewport_height.max(1);
+        let half_viewport = viewport / 2;
+        let max_scroll = self.max_scroll_offset();
+        self.diff_state.scroll_offset = self
+            .diff_state
+            .cursor_line
+            .saturating_sub(half_viewport)
+            .min(max_scroll);
+    }
+
+    pub fn file_list_down(&mut self, n: usize) {
+        let visible_items = self.build_visible_items();
+        let max_idx = visible_items.len().saturating_sub(1);
+        let new_idx = (self.file_list_state.selected() + n).min(max_idx);
+        self.file_list_state.select(new_idx);
+    }
+
+    pub fn file_list_up(&mut self, n: usize) {
+        let new_idx = self.file_list_state.selected().saturating_sub(n);
+        self.file_list_state.select(new_idx);
+    }
+
+    pub fn file_list_viewport_scroll_down(&mut self, lines: usize) {
+        let visible_items = self.build_visible_items();
+        let total = visible_items.len();
+        let viewport = self.file_list_state.viewport_height.max(1);
+        let selected = self.file_list_state.selected();
+
+        // Get current offset
+        let current_offset = self.file_list_state.list_state.offset();
+        let max_offset = total.saturating_sub(viewport);
+
+        // Move viewport down
+        let new_offset = (current_offset + lines).min(max_offset);
+        *self.file_list_state.list_state.offset_mut() = new_offset;
+
+        // Clamp cursor to stay within viewport bounds
+        // If cursor is now above the visible area, move it to the top visible line
+        if selected < new_offset {
+            self.file_list_state.select(new_offset);
+        }
+    }
+
+    pub fn file_list_viewport_scroll_up(&mut self, lines: usize) {
+        let viewport = self.file_list_state.viewport_height.max(1);
+        let selected = self.file_list_state.selected();
+
+        // Get current offset
+        let current_offset = self.file_list_state.list_state.offset();
+
+        // Move viewport up
+        let new_offset = current_offset.saturating_sub(lines);
+        *self.file_list_state.list_state.offset_mut() = new_offset;
+
+        // Clamp cursor to stay within viewport bounds
+        // If cursor is now below the visible area, move it to the bottom visible line
+        let max_visible = new_offset + viewport - 1;
+        if selected > max_visible {
+            self.file_list_state.select(max_visible);
+        }
+    }
+
+    pub fn jump_to_file(&mut self, idx: usize) {
+        use std::path::Path;
+
+        if idx < self.diff_files.len() {
+            self.diff_state.current_file_idx = idx;
+            self.diff_state.cursor_line = self.calculate_file_scroll_offset(idx);
+            let max_scroll = self.max_scroll_offset();
+            self.diff_state.scroll_offset = self.diff_state.cursor_line.min(max_scroll);
+
+            let file_path = self.diff_files[idx].display_path().clone();
+            let mut current = file_path.parent();
+            while let Some(parent) = current {
+                if parent != Path::new("") {
+                    self.expanded_dirs
+                        .insert(parent.to_string_lossy().to_string());
+                }
+                current = parent.parent();
+            }
+
+            if let Some(tree_idx) = self.file_idx_to_tree_idx(idx) {
+                self.file_list_state.select(tree_idx);
+            }
+        }
+    }
+
+    pub fn next_file(&mut self) {
+        let visible_items = self.build_visible_items();
+        let current_file_idx = self.diff_state.current_file_idx;
+
+        for item in &visible_items {
+            if let FileTreeItem::File {
+                file_idx,
+                pinned: false,
+                ..
+            } = item
+                && *file_idx > current_file_idx
+            {
+                self.jump_to_file(*file_idx);
+                return;
+            }
+        }
+    }
+
+    pub fn prev_file(&mut self) {
+        let visible_items = self.build_visible_items();
+        let current_file_idx = self.diff_state.current_file_idx;
+
+        for item in visible_items.iter().rev() {
+            if let FileTreeItem::File {
+                file_idx,
+                pinned: false,
+                ..
+            } = item
+                && *file_idx < current_file_idx
+            {
+                self.jump_to_file(*file_idx);
+                return;
+            }
+        }
+    }
+
+    fn file_idx_to_tree_idx(&self, target_file_idx: usize) -> Option<usize> {
+        let visible_items = self.build_visible_items();
+        for (tree_idx, item) in visible_items.iter().enumerate() {
+            if let FileTreeItem::File { file_idx, .. } = item
+                && *file_idx == target_file_idx
+            {
+                return Some(tree_idx);
+            }
+        }
+        None
+    }
+
+    pub fn next_hunk(&mut self) {
+        // Find the next hunk header position after current cursor
+        let mut cumulative = 0;
+        for file in &self.diff_files {
+            let path = file.display_path();
+
+            // File header
+            cumulative += 1;
+
+            // If file is reviewed, skip all content
+            if self.session.is_file_reviewed(path) {
+                continue;
+            }
+
+            // File comments
+            if let Some(review) = self.session.files.get(path) {
+                cumulative += review.file_comments.len();
+            }
+
+            if file.is_binary || file.hunks.is_empty() {
+                cumulative += 1; // "(binary file)" or "(no changes)"
+            } else {
+                for hunk in &file.hunks {
+                    // This is a hunk header position
+                    if cumulative > self.diff_state.cursor_line {
+                        self.diff_state.cursor_line = cumulative;
+                        self.ensure_cursor_visible();
+                        self.update_current_file_from_cursor();
+                        return;
+                    }
+                    cumulative += 1; // hunk header
+                    cumulative += hunk.lines.len(); // diff lines
+                }
+            }
+            cumulative += 1; // spacing
+        }
+    }
+
+    pub fn prev_hunk(&mut self) {
+        // Find the previous hunk header position before current cursor
+        let mut hunk_positions: Vec<usize> = Vec::new();
+        let mut cumulative = 0;
+
+        for file in &self.diff_files {
+            let path = file.display_path();
+
+            cumulative += 1; // File header
+
+            // If file is reviewed, skip all content
+            if self.session.is_file_reviewed(path) {
+                continue;
+            }
+
+            if let Some(review) = self.session.files.get(path) {
+                cumulative += review.file_comments.len();
+            }
+
+            if file.is_binary || file.hunks.is_empty() {
+                cumulative += 1;
+            } else {
+                for hunk in &file.hunks {
+                    hunk_positions.push(cumulative);
+                    cumulative += 1;
+                    cumulative += hunk.lines.len();
+                }
+            }
+            cumulative += 1;
+        }
+
+        // Find the last hunk position before current cursor
+        for &pos in hunk_positions.iter().rev() {
+            if pos < self.diff_state.cursor_line {
+                self.diff_state.cursor_line = pos;
+                self.ensure_cursor_visible();
+                self.update_current_file_from_cursor();
+                return;
+            }
+        }
+
+        // If no previous hunk, go to start
+        self.diff_state.cursor_line = 0;
+        self.ensure_cursor_visible();
+        self.update_current_file_from_cursor();
+    }
+
+    fn calculate_file_scroll_offset(&self, file_idx: usize) -> usize {
+        let mut offset = 0;
+        for (i, file) in self.diff_files.iter().enumerate() {
+            if i == file_idx {
+                break;
+            }
+            offset += self.file_render_height(i, file);
+        }
+        offset
+    }
+
+    fn file_render_height(&self, file_idx: usize, file: &DiffFile) -> usize {
+        let path = file.display_path();
+
+        // If reviewed, only show header (1 line total)
+        if self.session.is_file_reviewed(path) {
+            return 1;
+        }
+
+        let header_lines = 1; // File header
+        let spacing_lines = 1; // Blank line between files
+        let mut content_lines = 0;
+        let mut comment_lines = 0;
+
+        if let Some(review) = self.session.files.get(path) {
+            for comment in &review.file_comments {
+                comment_lines += Self::comment_display_lines(comment);
+            }
+        }
+
+        if file.is_binary || file.hunks.is_empty() {
+            content_lines = 1;
+        } else {
+            let line_comments = self.session.files.get(path).map(|r| &r.line_comments);
+
+            for (hunk_idx, hunk) in file.hunks.iter().enumerate() {
+                // Calculate gap before this hunk
+                let prev_hunk = if hunk_idx > 0 {
+                    file.hunks.get(hunk_idx - 1)
+                } else {
+                    None
+                };
+                let gap = calculate_gap(
+                    prev_hunk.map(|h| (&h.new_start, &h.new_count)),
+                    hunk.new_start,
+                );
+
+                let gap_id = GapId { file_idx, hunk_idx };
+
+                if gap > 0 {
+                    if self.expanded_gaps.contains(&gap_id) {
+                        // Expanded content lines
+                        if let Some(expanded) = self.expanded_content.get(&gap_id) {
+                            content_lines += expanded.len();
+                        }
+                    } else {
+                        // Expander line
+                        content_lines += 1;
+                    }
+                }
+
+                // Hunk header + diff lines
+                content_lines += 1; // Hunk header
+
+                for diff_line in &hunk.lines {
+                    content_lines += 1;
+
+                    if let Some(line_comments) = line_comments {
+                        if let Some(old_ln) = diff_line.old_lineno
+                            && let Some(comments) = line_comments.get(&old_ln)
+                        {
+                            for comment in comments {
+                                if comment.side == Some(LineSide::Old) {
+                                    comment_lines += Self::comment_display_lines(comment);
+                                }
+                            }
+                        }
+
+                        if let Some(new_ln) = diff_line.new_lineno
+                            && let Some(comments) = line_comments.get(&new_ln)
+                        {
+                            for comment in comments {
+                                if comment.side != Some(LineSide::Old) {
+                                    comment_lines += Self::comment_display_lines(comment);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        header_lines + comment_lines + content_lines + spacing_lines
+    }
+
+    fn update_current_file_from_cursor(&mut self) {
+        let mut cumulative = 0;
+        for (i, file) in self.diff_files.iter().enumerate() {
+            let height = self.file_render_height(i, file);
+            if cumulative + height > self.diff_state.cursor_line {
+                self.diff_state.current_file_idx = i;
+                self.file_list_state.select(i);
+                return;
+            }
+            cumulative += height;
+        }
+        if !self.diff_files.is_empty() {
+            self.diff_state.current_file_idx = self.diff_files.len() - 1;
+            self.file_list_state.select(self.diff_files.len() - 1);
+        }
+    }
+
+    pub fn total_lines(&self) -> usize {
+        self.diff_files
+            .iter()
+            .enumerate()
+            .map(|(i, f)| self.file_render_height(i, f))
+            .sum()
+    }
+
+    /// Calculate the maximum scroll offset.
+    ///
+    /// When line wrapping is enabled, logical lines may expand to multiple visual rows.
+    /// This means we need to allow scrolling further to ensure all content is reachable.
+    /// We allow scrolling to `total - 1` so the last logical line can be at the top.
+    ///
+    /// When wrapping is disabled, each logical line is one visual row, so we use
+    /// `total - viewport` which stops when the last line reaches the bottom.
+    pub fn max_scroll_offset(&self) -> usize {
+        let total = self.total_lines();
+        let viewport = self.diff_state.viewport_height.max(1);
+        if self.wrap_enabled() {
+            // With wrapping, allow scrolling to show the last line at the top
+            total.saturating_sub(1)
+        } else {
+            // Without wrapping, stop when last line is at the bottom
+            total.saturating_sub(viewport)
+        }
+    }
+
+    /// Calculate the number of display lines a comment takes (header + content + footer)
+    fn comment_display_lines(comment: &Comment) -> usize {
+        2 + comment.display_line_count() // header + content and reply lines + footer
+    }
+
+    /// Returns the source line number and side at the current cursor position, if on a diff line
+    pub fn get_line_at_cursor(&self) -> Option<(u32, LineSide)> {
+        let target = self.diff_state.cursor_line;
+        match self.line_annotations.get(target) {
+            Some(AnnotatedLine::DiffLine {
+                old_lineno,
+                new_lineno,
+                ..
+            }) => {
+                // Prefer new line number (for added/context lines), fall back to old (for deleted)
+                new_lineno
+                    .map(|ln| (ln, LineSide::New))
+                    .or_else(|| old_lineno.map(|ln| (ln, LineSide::Old)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Find the comment at the current cursor position
+    fn find_comment_at_cursor(&self) -> Option<CommentLocation> {
+        let target = self.diff_state.cursor_line;
+        match self.line_annotations.get(target) {
+            Some(AnnotatedLine::FileComment {
+                file_idx,
+                comment_idx,
+            }) => {
+                let path = self.diff_files.get(*file_idx)?.display_path().clone();
+                Some(CommentLocation::FileComment {
+                    path,
+                    index: *comment_idx,
+                })
+            }
+            Some(AnnotatedLine::LineComment {
+                file_idx,
+                line,
+                side,
+                comment_idx,
+            }) => {
+                let path = self.diff_files.get(*file_idx)?.display_path().clone();
+                Some(CommentLocation::LineComment {
+                    path,
+                    line: *line,
+                    side: *side,
+                    index: *comment_idx,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Open a reply editor for the comment under the diff cursor.
+    /// Returns false if the cursor is not on a comment.
+    pub fn reply_to_comment_at_cursor(&mut self) -> bool {
+        match self.comment_id_at_cursor() {
+            Some((_, id, line)) => {
+                self.enter_reply_mode(id, line);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Path, id and line of the comment under the diff cursor
+    fn comment_id_at_cursor(&self) -> Option<CommentTarget> {
+        match self.find_comment_at_cursor() {
+            Some(CommentLocation::FileComment { path, index }) => self
+                .session
+                .files
+                .get(&path)
+                .and_then(|review| review.file_comments.get(index))
+                .map(|comment| (path.clone(), comment.id.clone(), None)),
+            Some(CommentLocation::LineComment {
+                path,
+                line,
+                side,
+                index,
+            }) => self
+                .session
+                .files
+                .get(&path)
+                .and_then(|review| review.line_comments.get(&line))
+                .and_then(|comments| {
+                    comments
+                        .iter()
+                        .filter(|c| c.side.unwrap_or(LineSide::New) == side)
+                        .nth(index)
+                })
+                .map(|comment| (path.clone(), comment.id.clone(), Some((line, side)))),
+            None => None,
+        }
+    }
+
+    /// People a comment can be assigned to: the comma-separated `TUICR_TEAM`
+    /// environment variable, or else the repository's commit authors
+    pub fn team_members(&self) -> Vec<String> {
+        if let Ok(team) = std::env::var("TUICR_TEAM") {
+            let members: Vec<String> = team
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect();
+            if !members.is_empty() {
+                return members;
+            }
+        }
+        self.vcs.list_authors().unwrap_or_default()
+    }
+
+    /// Assign (or with `None`, unassign) the selected comment in the comments
+    /// panel, or else the comment under the diff cursor. A unique
+    /// case-insensitive prefix of a team member expands to their full name.
+    pub fn assign_comment(&mut self, name: Option<&str>) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let target = if self.focused_panel == FocusedPanel::Comments {
+            self.selected_panel_comment()
+                .map(|entry| (entry.path, entry.comment.id))
+        } else {
+            self.comment_id_at_cursor().map(|(path, id, _)| (path, id))
+        };
+        let Some((path, id)) = target else {
+            self.set_message("No comment at cursor");
+            return;
+        };
+        let assignee = name.map(|name| {
+            let prefix = name.to_lowercase();
+            let matches: Vec<String> = self
+                .team_members()
+                .into_iter()
+                .filter(|member| member.to_lowercase().starts_with(&prefix))
+                .collect();
+            match matches.as_slice() {
+                [member] => member.clone(),
+                _ => name.to_string(),
+            }
+        });
+        if let Some(comment) = self
+            .session
+            .get_file_mut(&path)
+            .and_then(|review| review.find_comment_mut(&id))
+        {
+            comment.assignee = assignee.clone();
+            self.dirty = true;
+            self.set_message(match assignee {
+                Some(name) => format!("Assigned to @{name}"),
+                None => "Comment unassigned".to_string(),
+            });
+            self.rebuild_annotations();
+        }
+    }
+
+    /// Order the comments panel and exports; saved with the session
+    pub fn set_comment_sort(&mut self, sort: CommentSort) {
+        if self.session.comment_sort != sort {
+            self.session.comment_sort = sort;
+            self.dirty |= !self.read_only;
+        }
+        self.comments_panel_state.select(0);
+        self.set_message(format!("Sorting comments by {}", sort.name()));
+    }
+
+    /// Limit the comments panel to one assignee, or show all with `None`
+    pub fn set_comments_assignee_filter(&mut self, name: Option<String>) {
+        self.set_message(match &name {
+            Some(name) => format!("Showing comments assigned to @{name}"),
+            None => "Showing all comments".to_string(),
+        });
+        self.comments_assignee_filter = name;
+        self.comments_panel_state.select(0);
+    }
+
+    /// Keep `before`, the state of the review of `path` ahead of `action`,
+    /// so the action can be undone
+    fn push_undo(
+        &mut self,
+        action: HistoryAction,
+        path: &std::path::Path,
+        line: Option<u32>,
+        before: ReviewState,
+    ) {
+        self.push_undo_entry(UndoEntry {
+            action,
+            path: path.to_path_buf(),
+            line,
+            states: vec![(path.to_path_buf(), before)],
+        });
+    }
+
+    /// Keep the states of several files ahead of one `action` on all of
+    /// them, so a single undo puts them all back
+    fn push_batch_undo(&mut self, action: HistoryAction, states: Vec<(PathBuf, ReviewState)>) {
+        let Some((path, _)) = states.first() else {
+            return;
+        };
+        self.push_undo_entry(UndoEntry {
+            action,
+            path: path.clone(),
+            line: None,
+            states,
+        });
+    }
+
+    fn push_undo_entry(&mut self, entry: UndoEntry) {
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(entry);
+        self.redo_stack.clear();
+    }
+
+    /// Undo the last comment or reviewed state edit, jumping to where it was
+    pub fn undo(&mut self) {
+        self.step_history(true);
+    }
+
+    /// Redo the last undone edit
+    pub fn redo(&mut self) {
+        self.step_history(false);
+    }
+
+    fn step_history(&mut self, undo: bool) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let (from, verb) = if undo {
+            (&mut self.undo_stack, "Undid")
+        } else {
+            (&mut self.redo_stack, "Redid")
+        };
+        let Some(mut entry) = from.pop() else {
+            self.set_message(if undo {
+                "Already at oldest change"
+            } else {
+                "Already at newest change"
+            });
+            return;
+        };
+        if let Some((path, _)) = entry
+            .states
+            .iter()
+            .find(|(path, _)| self.session.get_file_mut(path).is_none())
+        {
+            self.set_warning(format!(
+                "Cannot restore {}: no longer in the review",
+                path.display()
+            ));
+            return;
+        }
+        entry.states = std::mem::take(&mut entry.states)
+            .into_iter()
+            .map(|(path, state)| match self.session.get_file_mut(&path) {
+                Some(review) => {
+                    let replaced = review.restore(state);
+                    (path, replaced)
+                }
+                None => (path, state),
+            })
+            .collect();
+        let message = match entry.states.len() {
+            1 => format!(
+                "{verb} {} in {}",
+                entry.action.label(),
+                entry.path.display()
+            ),
+            count => format!("{verb} {} in {count} files", entry.action.label()),
+        };
+        let (path, line) = (entry.path.clone(), entry.line);
+        if undo {
+            self.redo_stack.push(entry);
+        } else {
+            self.undo_stack.push(entry);
+        }
+        self.dirty = true;
+        self.rebuild_annotations();
+        self.clamp_comments_panel_selection();
+        self.jump_to_location(&path, line);
+        self.set_message(message);
+    }
+
+    /// Delete the comment at the current cursor position, if any
+    /// Returns true if a comment was deleted
+    pub fn delete_comment_at_cursor(&mut self) -> bool {
+        if self.deny_if_read_only() {
+            return true;
+        }
+        let location = self.find_comment_at_cursor();
+
+        match location {
+            Some(CommentLocation::FileComment { path, index }) => {
+                if let Some(review) = self.session.get_file_mut(&path) {
+                    let before = review.review_state();
+                    review.file_comments.remove(index);
+                    self.session
+                        .record(HistoryAction::CommentDeleted, &path, None);
+                    self.push_undo(HistoryAction::CommentDeleted, &path, None, before);
+                    self.dirty = true;
+                    self.set_message("Comment deleted");
+                    self.rebuild_annotations();
+                    return true;
+                }
+            }
+            Some(CommentLocation::LineComment {
+                path,
+                line,
+                side,
+                index,
+            }) => {
+                let before = self.session.files.get(&path).map(|r| r.review_state());
+                if let Some(review) = self.session.get_file_mut(&path)
+                    && let Some(before) = before
+                    && let Some(comments) = review.line_comments.get_mut(&line)
+                {
+                    // Find the actual index by counting comments with matching side
+                    let mut side_idx = 0;
+                    let mut actual_idx = None;
+                    for (i, comment) in comments.iter().enumerate() {
+                        let comment_side = comment.side.unwrap_or(LineSide::New);
+                        if comment_side == side {
+                            if side_idx == index {
+                                actual_idx = Some(i);
+                                break;
+                            }
+                            side_idx += 1;
+                        }
+                    }
+                    if let Some(idx) = actual_idx {
+                        comments.remove(idx);
+                        if comments.is_empty() {
+                            review.line_comments.remove(&line);
+                        }
+                        self.session
+                            .record(HistoryAction::CommentDeleted, &path, Some(line));
+                        self.push_undo(HistoryAction::CommentDeleted, &path, Some(line), before);
+                        self.dirty = true;
+                        self.set_message(format!("Comment on line {line} deleted"));
+                        self.rebuild_annotations();
+                        return true;
+                    }
+                }
+            }
+            None => {}
+        }
+
+        false
+    }
+
+    /// All comments in the session for files in the current diff, in the
+    /// session's `comment_sort` order. File order puts file-level comments
+    /// first and line comments by line; the other orders keep it among equals.
+    pub fn comment_panel_entries(&self) -> Vec<CommentPanelEntry> {
+        let mut entries = Vec::new();
+        for file in &self.diff_files {
+            let path = file.display_path();
+            let Some(review) = self.session.files.get(path) else {
+                continue;
+            };
+            let shown = |comment: &Comment| match &self.comments_assignee_filter {
+                Some(name) => comment.assignee.as_ref() == Some(name),
+                None => true,
+            };
+            for comment in review.file_comments.iter().filter(|c| shown(c)) {
+                entries.push(CommentPanelEntry {
+                    path: path.clone(),
+                    line: None,
+                    comment: comment.clone(),
+                });
+            }
+            let mut lines: Vec<_> = review.line_comments.keys().copied().collect();
+            lines.sort_unstable();
+            for line in lines {
+                for comment in review.line_comments[&line].iter().filter(|c| shown(c)) {
+                    entries.push(CommentPanelEntry {
+                        path: path.clone(),
+                        line: Some(line),
+                        comment: comment.clone(),
+                    });
+                }
+            }
+        }
+        let sort = self.session.comment_sort;
+        entries.sort_by(|a, b| sort.compare(&a.comment, &b.comment));
+        entries
+    }
+
+    /// Show and focus the comments panel, or hide it when it already has focus
+    pub fn toggle_comments_panel(&mut self) {
+        if self.show_comments_panel && self.focused_panel == FocusedPanel::Comments {
+            self.show_comments_panel = false;
+            self.focused_panel = FocusedPanel::Diff;
+        } else {
+            self.show_comments_panel = true;
+            self.focused_panel = FocusedPanel::Comments;
+            self.clamp_comments_panel_selection();
+        }
+    }
+
+    fn clamp_comments_panel_selection(&mut self) {
+        let count = self.comment_panel_entries().len();
+        let selected = self.comments_panel_state.selected();
+        self.comments_panel_state
+            .select(selected.min(count.saturating_sub(1)));
+    }
+
+    pub fn comments_panel_down(&mut self, n: usize) {
+        let count = self.comment_panel_entries().len();
+        let selected = self.comments_panel_state.selected();
+        self.comments_panel_state
+            .select((selected + n).min(count.saturating_sub(1)));
+    }
+
+    pub fn comments_panel_up(&mut self, n: usize) {
+        let selected = self.comments_panel_state.selected();
+        self.comments_panel_state.select(selected.saturating_sub(n));
+    }
+
+    pub fn selected_panel_comment(&self) -> Option<CommentPanelEntry> {
+        self.comment_panel_entries()
+            .into_iter()
+            .nth(self.comments_panel_state.selected())
+    }
+
+    /// Move the diff cursor to the comment selected in the comments panel
+    pub fn jump_to_selected_comment(&mut self) -> bool {
+        let Some(entry) = self.selected_panel_comment() else {
+            return false;
+        };
+        self.jump_to_location(&entry.path, entry.line)
+    }
+
+    pub fn toggle_selected_comment_resolved(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let Some(entry) = self.selected_panel_comment() else {
+            self.set_message("No comment selected");
+            return;
+        };
+        if let Some(review) = self.session.get_file_mut(&entry.path)
+            && let Some(comment) = review.find_comment_mut(&entry.comment.id)
+        {
+            comment.resolved = !comment.resolved;
+            let resolved = comment.resolved;
+            self.dirty = true;
+            self.notify_webhook(&entry.path, &entry.comment.id, WebhookEventKind::Updated);
+            self.set_message(if resolved {
+                "Comment resolved"
+            } else {
+                "Comment reopened"
+            });
+        }
+    }
+
+    pub fn delete_selected_panel_comment(&mut self) -> bool {
+        if self.deny_if_read_only() {
+            return true;
+        }
+        let Some(entry) = self.selected_panel_comment() else {
+            return false;
+        };
+        let before = self
+            .session
+            .files
+            .get(&entry.path)
+            .map(|r| r.review_state());
+        let removed = self
+            .session
+            .get_file_mut(&entry.path)
+            .is_some_and(|review| review.remove_comment(&entry.comment.id));
+        if removed && let Some(before) = before {
+            self.session
+                .record(HistoryAction::CommentDeleted, &entry.path, entry.line);
+            self.push_undo(
+                HistoryAction::CommentDeleted,
+                &entry.path,
+                entry.line,
+                before,
+            );
+            self.dirty = true;
+            self.set_message("Comment deleted");
+            self.rebuild_annotations();
+            self.clamp_comments_panel_selection();
+        }
+        removed
+    }
+
+    /// Jump to the selected panel comment and open a reply editor for it
+    pub fn reply_to_selected_comment(&mut self) {
+        let Some(entry) = self.selected_panel_comment() else {
+            self.set_message("No comment selected");
+            return;
+        };
+        if !self.jump_to_location(&entry.path, entry.line) {
+            return;
+        }
+        let line = entry
+            .line
+            .map(|line| (line, entry.comment.side.unwrap_or(LineSide::New)));
+        self.enter_reply_mode(entry.comment.id, line);
+    }
+
+    pub fn clear_all_comments(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let cleared = self.session.clear_comments();
+        if cleared == 0 {
+            self.set_message("No comments to clear");
+            return;
+        }
+
+        self.dirty = true;
+        self.rebuild_annotations();
+        self.set_message(format!("Cleared {cleared} comments"));
+    }
+
+    /// Enter edit mode for the comment at the current cursor position
+    /// Returns true if a comment was found and edit mode entered
+    pub fn enter_edit_mode(&mut self) -> bool {
+        if self.deny_if_read_only() {
+            return true;
+        }
+        let location = self.find_comment_at_cursor();
+
+        match location {
+            Some(CommentLocation::FileComment { path, index }) => {
+                if let Some(review) = self.session.files.get(&path)
+                    && let Some(comment) = review.file_comments.get(index)
+                {
+                    self.input_mode = InputMode::Comment;
+                    self.comment_editor.set_text(comment.content.clone());
+                    self.comment_type = comment.comment_type;
+                    self.comment_is_file_level = true;
+                    self.comment_line = None;
+                    self.editing_comment_id = Some(comment.id.clone());
+                    return true;
+                }
+            }
+            Some(CommentLocation::LineComment {
+                path,
+                line,
+                side,
+                index,
+            }) => {
+                if let Some(review) = self.session.files.get(&path)
+                    && let Some(comments) = review.line_comments.get(&line)
+                {
+                    // Find the actual comment by counting comments with matching side
+                    let mut side_idx = 0;
+                    for comment in comments.iter() {
+                        let comment_side = comment.side.unwrap_or(LineSide::New);
+                        if comment_side == side {
+                            if side_idx == index {
+                                self.input_mode = InputMode::Comment;
+                                self.comment_editor.set_text(comment.content.clone());
+                                self.comment_type = comment.comment_type;
+                                self.comment_is_file_level = false;
+                                self.comment_line = Some((line, side));
+                                self.editing_comment_id = Some(comment.id.clone());
+                                return true;
+                            }
+                            side_idx += 1;
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+
+        false
+    }
+
+    pub fn enter_command_mode(&mut self) {
+        self.input_mode = InputMode::Command;
+        self.command_buffer.clear();
+    }
+
+    /// Leave command mode, unless the command opened another mode
+    pub fn exit_command_mode(&mut self) {
+        if self.input_mode == InputMode::Command {
+            self.input_mode = InputMode::Normal;
+        }
+        self.command_buffer.clear();
+    }
+
+    pub fn enter_search_mode(&mut self) {
+        self.input_mode = InputMode::Search;
+        self.search_buffer.clear();
+    }
+
+    pub fn exit_search_mode(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.search_buffer.clear();
+    }
+
+    pub fn enter_comment_mode(&mut self, file_level: bool, line: Option<(u32, LineSide)>) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        self.input_mode = InputMode::Comment;
+        self.comment_editor.clear();
+        self.comment_type = CommentType::Note;
+        self.comment_is_file_level = file_level;
+        self.comment_line = line;
+    }
+
+    pub fn exit_comment_mode(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.comment_editor.clear();
+        self.editing_comment_id = None;
+        self.reply_to_comment_id = None;
+        self.comment_line_range = None;
+        self.comment_suggestion_base = None;
+    }
+
+    pub fn comment_input_kind(&self) -> CommentInputKind {
+        if self.reply_to_comment_id.is_some() {
+            CommentInputKind::Reply
+        } else if self.editing_comment_id.is_some() {
+            CommentInputKind::Edit
+        } else {
+            CommentInputKind::Add
+        }
+    }
+
+    /// Open the comment editor as a reply to the comment with `comment_id`.
+    /// The cursor must already be on the comment's file.
+    pub fn enter_reply_mode(&mut self, comment_id: String, line: Option<(u32, LineSide)>) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        self.enter_comment_mode(line.is_none(), line);
+        self.reply_to_comment_id = Some(comment_id);
+    }
+
+    /// Enter visual selection mode, anchoring at the current cursor position
+    pub fn enter_visual_mode(&mut self, line: u32, side: LineSide) {
+        self.input_mode = InputMode::VisualSelect;
+        self.visual_anchor = Some((line, side));
+    }
+
+    /// Exit visual selection mode and return to normal mode
+    pub fn exit_visual_mode(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.visual_anchor = None;
+        self.file_visual_anchor = None;
+    }
+
+    /// Enter visual selection mode in the file list, anchoring at the
+    /// selected row
+    pub fn enter_file_visual_mode(&mut self) {
+        self.input_mode = InputMode::VisualSelect;
+        self.file_visual_anchor = Some(self.file_list_state.selected());
+    }
+
+    /// Whether file list row `tree_idx` is within the visual selection
+    pub fn is_tree_item_in_visual_selection(&self, tree_idx: usize) -> bool {
+        let Some(anchor) = self.file_visual_anchor else {
+            return false;
+        };
+        let selected = self.file_list_state.selected();
+        (anchor.min(selected)..=anchor.max(selected)).contains(&tree_idx)
+    }
+
+    /// Files shown in the visual selection of the file list, as indices
+    /// into `diff_files`
+    fn visual_selected_files(&self) -> Vec<usize> {
+        self.build_visible_items()
+            .into_iter()
+            .enumerate()
+            .filter(|(tree_idx, _)| self.is_tree_item_in_visual_selection(*tree_idx))
+            .filter_map(|(_, item)| match item {
+                FileTreeItem::File { file_idx, .. } => Some(file_idx),
+                FileTreeItem::Directory { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Set `mark` on every file of the visual selection, or clear it when
+    /// they all have it already, then leave visual mode. One undo puts back
+    /// the whole selection.
+    pub fn mark_selected_files(&mut self, mark: FileMark) {
+        let mut files = self.visual_selected_files();
+        // Pinned files are listed twice, in their section and in the tree
+        files.sort_unstable();
+        files.dedup();
+        self.exit_visual_mode();
+        if files.is_empty() {
+            self.set_warning("Select files to mark");
+            return;
+        }
+        if self.deny_if_read_only() {
+            return;
+        }
+        let paths: Vec<PathBuf> = files
+            .iter()
+            .map(|&file_idx| self.diff_files[file_idx].display_path().clone())
+            .collect();
+        let has_mark = |review: &FileReview| match mark {
+            FileMark::Reviewed => review.reviewed,
+            FileMark::Skipped => review.skipped,
+            FileMark::Pinned => review.pinned,
+            FileMark::Filtered => review.filtered,
+        };
+        let set = !paths
+            .iter()
+            .all(|path| self.session.files.get(path).is_some_and(has_mark));
+        let action = match (mark, set) {
+            (FileMark::Reviewed, true) => HistoryAction::Reviewed,
+            (FileMark::Reviewed, false) => HistoryAction::Unreviewed,
+            (FileMark::Skipped, true) => HistoryAction::Skipped,
+            (FileMark::Skipped, false) => HistoryAction::Unskipped,
+            (FileMark::Pinned, true) => HistoryAction::Pinned,
+            (FileMark::Pinned, false) => HistoryAction::Unpinned,
+            (FileMark::Filtered, true) => HistoryAction::Filtered,
+            (FileMark::Filtered, false) => HistoryAction::Unfiltered,
+        };
+
+        let mut before = Vec::with_capacity(paths.len());
+        for path in paths {
+            let Some(review) = self.session.get_file_mut(&path) else {
+                continue;
+            };
+            let changed = has_mark(review) != set;
+            before.push((path.clone(), review.marked_state()));
+            match mark {
+                FileMark::Reviewed => {
+                    review.reviewed = set;
+                    review.reviewed_hunks.clear();
+                }
+                FileMark::Skipped => review.skipped = set,
+                FileMark::Pinned => review.pinned = set,
+                FileMark::Filtered => review.filtered = set,
+            }
+            if changed {
+                self.session.record(action, &path, None);
+            }
+        }
+        self.push_batch_undo(action, before);
+        self.dirty = true;
+        self.rebuild_annotations();
+        self.ensure_valid_tree_selection();
+
+        let count = files.len();
+        let files = if count == 1 { "file" } else { "files" };
+        self.set_message(match (mark, set) {
+            (FileMark::Reviewed, true) => format!("Marked {count} {files} reviewed"),
+            (FileMark::Reviewed, false) => format!("Marked {count} {files} unreviewed"),
+            (FileMark::Skipped, true) => format!("Skipped {count} {files}"),
+            (FileMark::Skipped, false) => format!("Unskipped {count} {files}"),
+            (FileMark::Pinned, true) => format!("Pinned {count} {files}"),
+            (FileMark::Pinned, false) => format!("Unpinned {count} {files}"),
+            (FileMark::Filtered, true) => format!("Filtered out {count} {files}"),
+            (FileMark::Filtered, false) => format!("Unfiltered {count} {files}"),
+        });
+    }
+
+    /// Get the current visual selection range (if in visual mode)
+    /// Returns None if not in visual mode or if there's no valid selection
+    pub fn get_visual_selection(&self) -> Option<(LineRange, LineSide)> {
+        if self.input_mode != InputMode::VisualSelect {
+            return None;
+        }
+
+        let (anchor_line, anchor_side) = self.visual_anchor?;
+        let (current_line, current_side) = self.get_line_at_cursor()?;
+
+        // Don't allow selection across sides (old vs new)
+        if anchor_side != current_side {
+            return None;
+        }
+
+        let range = LineRange::new(anchor_line, current_line);
+        Some((range, anchor_side))
+    }
+
+    /// Check if a given line is within the current visual selection
+    pub fn is_line_in_visual_selection(&self, line: u32, side: LineSide) -> bool {
+        if let Some((range, sel_side)) = self.get_visual_selection() {
+            sel_side == side && range.contains(line)
+        } else {
+            false
+        }
+    }
+
+    /// Enter comment mode from visual selection
+    pub fn enter_comment_from_visual(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        if let Some((range, side)) = self.get_visual_selection() {
+            self.comment_line_range = Some((range, side));
+            self.comment_line = Some((range.end, side)); // Key by end line
+            self.input_mode = InputMode::Comment;
+            self.comment_editor.clear();
+            self.comment_type = CommentType::Note;
+            self.comment_is_file_level = false;
+            self.visual_anchor = None;
+        } else {
+            self.set_warning("Invalid visual selection");
+            self.exit_visual_mode();
+        }
+    }
+
+    pub fn save_comment(&mut self) {
+        if self.comment_editor.text().trim().is_empty() {
+            self.set_message("Comment cannot be empty");
+            return;
+        }
+
+        let content = self.comment_editor.text().trim().to_string();
+        // The lines around a new line comment, to follow it when the diff changes
+        let line_context = self
+            .comment_line_range
+            .or(self
+                .comment_line
+                .map(|(line, side)| (LineRange::single(line), side)))
+            .zip(self.current_file())
+            .and_then(|((range, side), file)| capture_context(file, range, side));
+
+        if let Some(path) = self.current_file_path().cloned()
+            && let Some(review) = self.session.get_file_mut(&path)
+        {
+            let before = review.review_state();
+            let message: String;
+            let mut recorded: Option<(HistoryAction, Option<u32>)> = None;
+            let mut notified: Option<(String, WebhookEventKind)> = None;
+
+            if let Some(parent_id) = &self.reply_to_comment_id {
+                // Append a reply to an existing thread
+                if let Some(parent) = review.find_comment_mut(parent_id) {
+                    parent.replies.push(CommentReply::new(content));
+                    notified = Some((parent_id.clone(), WebhookEventKind::Updated));
+                    message = "Reply added".to_string();
+                    recorded = Some((HistoryAction::ReplyAdded, self.comment_line.map(|(l, _)| l)));
+                } else {
+                    message = "Error: Comment to reply to not found".to_string();
+                }
+            } else if let Some(editing_id) = &self.editing_comment_id {
+                // Update existing comment
+                // Search in file comments
+                if let Some(comment) = review
+                    .file_comments
+                    .iter_mut()
+                    .find(|c| &c.id == editing_id)
+                {
+                    comment.content = content.clone();
+                    comment.comment_type = self.comment_type;
+                    notified = Some((editing_id.clone(), WebhookEventKind::Updated));
+                    message = "Comment updated".to_string();
+                    recorded = Some((HistoryAction::CommentEdited, None));
+                } else {
+                    // If not found in file comments, search in line comments
+                    let mut found_comment = None;
+                    for comments in review.line_comments.values_mut() {
+                        if let Some(comment) = comments.iter_mut().find(|c| &c.id == editing_id) {
+                            found_comment = Some(comment);
+                            break;
+                        }
+                    }
+
+                    if let Some(comment) = found_comment {
+                        comment.content = content.clone();
+                        comment.comment_type = self.comment_type;
+                        notified = Some((editing_id.clone(), WebhookEventKind::Updated));
+                        recorded = Some((
+                            HistoryAction::CommentEdited,
+                            self.comment_line.map(|(l, _)| l),
+                        ));
+                        message = if let Some((line, _)) = self.comment_line {
+                            format!("Comment on line {line} updated")
+                        } else {
+                            "Comment updated".to_string()
+                        };
+                    } else {
+                        message = "Error: Comment to edit not found".to_string();
+                    }
+                }
+            } else {
+                // Create new comment
+                let line = if self.comment_is_file_level {
+                    None
+                } else {
+                    self.comment_line_range
+                        .map(|(range, _)| range.end)
+                        .or(self.comment_line.map(|(l, _)| l))
+                };
+                recorded = Some((HistoryAction::CommentAdded, line));
+                if self.comment_is_file_level {
+                    let comment = Comment::new(content, self.comment_type, None);
+                    notified = Some((comment.id.clone(), WebhookEventKind::Created));
+                    review.add_file_comment(comment);
+                    message = "File comment added".to_string();
+                } else if let Some((range, side)) = self.comment_line_range {
+                    // Range comment from visual selection
+                    let mut comment =
+                        Comment::new_with_range(content, self.comment_type, Some(side), range);
+                    comment.suggestion_base = self.comment_suggestion_base.clone();
+                    comment.line_context = line_context;
+                    notified = Some((comment.id.clone(), WebhookEventKind::Created));
+                    // Store by end line of the range
+                    review.add_line_comment(range.end, comment);
+                    if range.is_single() {
+                        message = format!("Comment added to line {}", range.end);
+                    } else {
+                        message = format!("Comment added to lines {}-{}", range.start, range.end);
+                    }
+                } else if let Some((line, side)) = self.comment_line {
+                    let mut comment = Comment::new(content, self.comment_type, Some(side));
+                    comment.line_context = line_context;
+                    notified = Some((comment.id.clone(), WebhookEventKind::Created));
+                    review.add_line_comment(line, comment);
+                    message = format!("Comment added to line {line}");
+                } else {
+                    // Fallback to file comment if no line specified
+                    let comment = Comment::new(content, self.comment_type, None);
+                    notified = Some((comment.id.clone(), WebhookEventKind::Created));
+                    review.add_file_comment(comment);
+                    message = "File comment added".to_string();
+                }
+            }
+
+            if let Some((action, line)) = recorded {
+                self.session.record(action, &path, line);
+                self.push_undo(action, &path, line, before);
+            }
+            if let Some((id, kind)) = notified {
+                self.notify_webhook(&path, &id, kind);
             }
+            self.dirty = true;
+            self.set_message(message);
+            self.rebuild_annotations();
         }
 
-        // If no previous hunk, go to start
-        self.diff_state.cursor_line = 0;
-        self.ensure_cursor_visible();
-        self.update_current_file_from_cursor();
+        self.exit_comment_mode();
     }
 
-    fn calculate_file_scroll_offset(&self, file_idx: usize) -> usize {
-        let mut offset = 0;
-        for (i, file) in self.diff_files.iter().enumerate() {
-            if i == file_idx {
-                break;
+    /// Start a suggestion for the selected new-side lines, pre-filled with
+    /// their current text to edit into the proposed replacement
+    pub fn enter_suggestion_from_visual(&mut self) {
+        let Some((range, side)) = self.get_visual_selection() else {
+            self.set_warning("Invalid visual selection");
+            self.exit_visual_mode();
+            return;
+        };
+        if side == LineSide::Old {
+            self.set_warning("Suggestions replace new lines; select added or context lines");
+            return;
+        }
+        let Some(base) = self.new_side_text(self.diff_state.current_file_idx, range) else {
+            self.set_warning("Expand the hidden context inside the selection to suggest on it");
+            return;
+        };
+
+        self.enter_comment_from_visual();
+        if self.input_mode != InputMode::Comment {
+            return;
+        }
+        self.comment_type = CommentType::Suggestion;
+        self.comment_editor
+            .set_text(format!("```suggestion\n{base}\n```"));
+        // Leave the cursor at the end of the replacement
+        let cursor = self.comment_editor.text().len() - "\n```".len();
+        self.comment_editor.set_cursor(cursor);
+        self.comment_suggestion_base = Some(base);
+    }
+
+    /// Write the suggestion at the cursor (or selected in the comments
+    /// panel) into the working tree file and resolve its comment
+    pub fn apply_suggestion_at_cursor(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let target = if self.focused_panel == FocusedPanel::Comments {
+            self.selected_panel_comment()
+                .map(|entry| (entry.path, entry.comment.id))
+        } else {
+            self.comment_id_at_cursor().map(|(path, id, _)| (path, id))
+        };
+        let Some((path, id)) = target else {
+            self.set_message("No comment at cursor");
+            return;
+        };
+
+        let line = match self.write_suggestion(&path, &id) {
+            Ok(line) => line,
+            Err(e) => {
+                self.set_error(format!("Failed to apply suggestion: {e}"));
+                return;
             }
-            offset += self.file_render_height(i, file);
+        };
+        self.dirty = true;
+        if self.reload_after_edit() {
+            self.set_message(format!(
+                "Suggestion applied to {}:{line} (:unapply to undo)",
+                path.display()
+            ));
         }
-        offset
     }
 
-    fn file_render_height(&self, file_idx: usize, file: &DiffFile) -> usize {
-        let path = file.display_path();
+    fn write_suggestion(&mut self, path: &PathBuf, id: &str) -> Result<u32> {
+        let not_applicable = |why: &str| TuicrError::UnsupportedOperation(why.to_string());
+        let full_path = self.vcs_info.working_tree_path(path)?;
+        let (range, comment) = self
+            .session
+            .files
+            .get(path)
+            .into_iter()
+            .flat_map(|review| &review.line_comments)
+            .flat_map(|(line, comments)| comments.iter().map(move |c| (*line, c)))
+            .find(|(_, comment)| comment.id == id)
+            .map(|(line, comment)| {
+                let range = comment.line_range.unwrap_or(LineRange::single(line));
+                (range, comment)
+            })
+            .ok_or_else(|| not_applicable("only line comments carry suggestions"))?;
+        let replacement = comment
+            .suggestion()
+            .ok_or_else(|| not_applicable("not a suggestion; make one with s in visual mode"))?;
+        if comment.side == Some(LineSide::Old) {
+            return Err(not_applicable(
+                "suggestions on deleted lines cannot be applied",
+            ));
+        }
+        let base = match &comment.suggestion_base {
+            Some(base) => base.clone(),
+            None => self
+                .diff_files
+                .iter()
+                .position(|file| file.display_path() == path)
+                .and_then(|file_idx| self.new_side_text(file_idx, range))
+                .ok_or_else(|| not_applicable("the suggested lines are not in the diff"))?,
+        };
 
-        // If reviewed, only show header (1 line total)
-        if self.session.is_file_reviewed(path) {
-            return 1;
+        let before = std::fs::read_to_string(&full_path)?;
+        let (after, line) = apply_suggestion(&before, range, &base, &replacement)?;
+        std::fs::write(&full_path, &after)?;
+
+        let comment = self
+            .session
+            .get_file_mut(path)
+            .and_then(|review| review.find_comment_mut(id))
+            .ok_or_else(|| not_applicable("comment not found"))?;
+        self.applied_suggestions.push(AppliedSuggestion {
+            path: path.to_path_buf(),
+            comment_id: id.to_string(),
+            before,
+            after,
+            was_resolved: comment.resolved,
+        });
+        comment.resolved = true;
+        Ok(line)
+    }
+
+    /// Restore the file the last applied suggestion changed, unless it was
+    /// edited again since
+    pub fn undo_applied_suggestion(&mut self) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        let Some(applied) = self.applied_suggestions.pop() else {
+            self.set_message("No applied suggestion to undo");
+            return;
+        };
+        let restored = self
+            .vcs_info
+            .working_tree_path(&applied.path)
+            .and_then(|full_path| {
+                let current = std::fs::read_to_string(&full_path)?;
+                if current != applied.after {
+                    return Err(TuicrError::SuggestionConflict(format!(
+                        "{} changed after the suggestion was applied",
+                        applied.path.display()
+                    )));
+                }
+                Ok(std::fs::write(&full_path, &applied.before)?)
+            });
+        if let Err(e) = restored {
+            self.set_error(format!("Failed to undo suggestion: {e}"));
+            return;
         }
 
-        let header_lines = 1; // File header
-        let spacing_lines = 1; // Blank line between files
-        let mut content_lines = 0;
-        let mut comment_lines = 0;
+        if let Some(comment) = self
+            .session
+            .get_file_mut(&applied.path)
+            .and_then(|review| review.find_comment_mut(&applied.comment_id))
+        {
+            comment.resolved = applied.was_resolved;
+        }
+        self.dirty = true;
+        if self.reload_after_edit() {
+            self.set_message(format!("Suggestion undone in {}", applied.path.display()));
+        }
+    }
 
-        if let Some(review) = self.session.files.get(path) {
-            for comment in &review.file_comments {
-                comment_lines += Self::comment_display_lines(comment);
-            }
+    /// Show a change tuicr made to the working tree. Diffs of commits do
+    /// not include it, so only the annotations are refreshed for them.
+    fn reload_after_edit(&mut self) -> bool {
+        if matches!(
+            self.diff_source,
+            DiffSource::WorkingTree | DiffSource::Ref(_)
+        ) && let Err(e) = self.reload_diff_files()
+        {
+            self.set_error(format!("Failed to reload: {e}"));
+            return false;
         }
+        self.rebuild_annotations();
+        true
+    }
 
-        if file.is_binary || file.hunks.is_empty() {
-            content_lines = 1;
+    /// Text of new-side lines `range` of a file, from the diff and expanded
+    /// context. None when some of the lines are not loaded.
+    fn new_side_text(&self, file_idx: usize, range: LineRange) -> Option<String> {
+        let file = self.diff_files.get(file_idx)?;
+        let expanded = self
+            .expanded_content
+            .iter()
+            .filter(|(gap_id, _)| gap_id.file_idx == file_idx)
+            .flat_map(|(_, lines)| lines);
+        let lines: BTreeMap<u32, &str> = file
+            .hunks
+            .iter()
+            .flat_map(|hunk| &hunk.lines)
+            .filter(|line| line.origin != LineOrigin::Deletion)
+            .chain(expanded)
+            .filter_map(|line| Some((line.new_lineno?, line.content.as_str())))
+            .filter(|(lineno, _)| range.contains(*lineno))
+            .collect();
+        if lines.len() != (range.end - range.start + 1) as usize {
+            return None;
+        }
+        Some(lines.into_values().collect::<Vec<_>>().join("\n"))
+    }
+
+    pub fn cycle_comment_type(&mut self) {
+        self.comment_type = match self.comment_type {
+            CommentType::Note => CommentType::Suggestion,
+            CommentType::Suggestion => CommentType::Issue,
+            CommentType::Issue => CommentType::Praise,
+            CommentType::Praise => CommentType::Note,
+        };
+    }
+
+    pub fn toggle_help(&mut self) {
+        if self.input_mode == InputMode::Help {
+            self.input_mode = InputMode::Normal;
         } else {
-            let line_comments = self.session.files.get(path).map(|r| &r.line_comments);
+            self.input_mode = InputMode::Help;
+            self.help_state.scroll_offset = 0;
+        }
+    }
 
-            for (hunk_idx, hunk) in file.hunks.iter().enumerate() {
-                // Calculate gap before this hunk
-                let prev_hunk = if hunk_idx > 0 {
-                    file.hunks.get(hunk_idx - 1)
-                } else {
-                    None
-                };
-                let gap = calculate_gap(
-                    prev_hunk.map(|h| (&h.new_start, &h.new_count)),
-                    hunk.new_start,
-                );
+    pub fn help_scroll_down(&mut self, lines: usize) {
+        let max_offset = self
+            .help_state
+            .total_lines
+            .saturating_sub(self.help_state.viewport_height);
+        self.help_state.scroll_offset = (self.help_state.scroll_offset + lines).min(max_offset);
+    }
 
-                let gap_id = GapId { file_idx, hunk_idx };
+    pub fn help_scroll_up(&mut self, lines: usize) {
+        self.help_state.scroll_offset = self.help_state.scroll_offset.saturating_sub(lines);
+    }
 
-                if gap > 0 {
-                    if self.expanded_gaps.contains(&gap_id) {
-                        // Expanded content lines
-                        if let Some(expanded) = self.expanded_content.get(&gap_id) {
-                            content_lines += expanded.len();
-                        }
-                    } else {
-                        // Expander line
-                        content_lines += 1;
-                    }
-                }
+    pub fn help_scroll_to_top(&mut self) {
+        self.help_state.scroll_offset = 0;
+    }
 
-                // Hunk header + diff lines
-                content_lines += 1; // Hunk header
+    pub fn help_scroll_to_bottom(&mut self) {
+        let max_offset = self
+            .help_state
+            .total_lines
+            .saturating_sub(self.help_state.viewport_height);
+        self.help_state.scroll_offset = max_offset;
+    }
 
-                for diff_line in &hunk.lines {
-                    content_lines += 1;
+    pub fn open_popup(
+        &mut self,
+        title: impl Into<String>,
+        lines: Vec<ratatui::text::Line<'static>>,
+        wrap: bool,
+    ) {
+        self.text_popup = Some(TextPopup {
+            title: title.into(),
+            lines,
+            wrap,
+            scroll_offset: 0,
+            scroll_x: 0,
+            viewport_height: 0,
+            total_lines: 0,
+        });
+        self.input_mode = InputMode::Popup;
+    }
 
-                    if let Some(line_comments) = line_comments {
-                        if let Some(old_ln) = diff_line.old_lineno
-                            && let Some(comments) = line_comments.get(&old_ln)
-                        {
-                            for comment in comments {
-                                if comment.side == Some(LineSide::Old) {
-                                    comment_lines += Self::comment_display_lines(comment);
-                                }
-                            }
-                        }
+    pub fn close_popup(&mut self) {
+        self.text_popup = None;
+        self.input_mode = InputMode::Normal;
+    }
 
-                        if let Some(new_ln) = diff_line.new_lineno
-                            && let Some(comments) = line_comments.get(&new_ln)
-                        {
-                            for comment in comments {
-                                if comment.side != Some(LineSide::Old) {
-                                    comment_lines += Self::comment_display_lines(comment);
-                                }
-                            }
-                        }
-                    }
-                }
+    pub fn popup_scroll_down(&mut self, lines: usize) {
+        if let Some(popup) = self.text_popup.as_mut() {
+            let max_offset = popup.total_lines.saturating_sub(popup.viewport_height);
+            popup.scroll_offset = (popup.scroll_offset + lines).min(max_offset);
+        }
+    }
+
+    pub fn popup_scroll_up(&mut self, lines: usize) {
+        if let Some(popup) = self.text_popup.as_mut() {
+            popup.scroll_offset = popup.scroll_offset.saturating_sub(lines);
+        }
+    }
+
+    pub fn popup_scroll_left(&mut self, cols: usize) {
+        if let Some(popup) = self.text_popup.as_mut() {
+            popup.scroll_x = popup.scroll_x.saturating_sub(cols);
+        }
+    }
+
+    pub fn popup_scroll_right(&mut self, cols: usize) {
+        if let Some(popup) = self.text_popup.as_mut()
+            && !popup.wrap
+        {
+            popup.scroll_x = popup.scroll_x.saturating_add(cols);
+        }
+    }
+
+    pub fn popup_viewport_height(&self) -> usize {
+        self.text_popup
+            .as_ref()
+            .map(|popup| popup.viewport_height)
+            .unwrap_or(0)
+    }
+
+    /// Switch to the named theme and re-highlight the loaded diff with it
+    pub fn switch_theme(&mut self, name: &str) -> bool {
+        let Some(arg) = ThemeArg::from_str(name) else {
+            return false;
+        };
+        if self.theme.name != arg.name() {
+            self.theme = resolve_theme(arg);
+            self.rehighlight_files(|_, _, _| true);
+        }
+        true
+    }
+
+    pub fn open_theme_picker(&mut self) {
+        let selected = ThemeArg::ALL
+            .iter()
+            .position(|arg| arg.name() == self.theme.name)
+            .unwrap_or(0);
+        self.theme_picker = Some(ThemePickerState {
+            selected,
+            original: self.theme.name,
+        });
+        self.input_mode = InputMode::ThemePicker;
+    }
+
+    /// Move the picker selection and preview the newly selected theme
+    pub fn theme_picker_move(&mut self, delta: isize) {
+        let Some(picker) = self.theme_picker.as_mut() else {
+            return;
+        };
+        let count = ThemeArg::ALL.len() as isize;
+        picker.selected = (picker.selected as isize + delta).rem_euclid(count) as usize;
+        let name = ThemeArg::ALL[picker.selected].name();
+        self.switch_theme(name);
+    }
+
+    pub fn close_theme_picker(&mut self, keep: bool) {
+        if let Some(picker) = self.theme_picker.take() {
+            if keep {
+                self.set_message(format!("Theme: {}", self.theme.name));
+            } else {
+                self.switch_theme(picker.original);
+            }
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Open a reflowed word diff of the current file in a popup
+    pub fn show_word_diff(&mut self) {
+        let Some(file) = self.current_file() else {
+            self.set_message("No file selected");
+            return;
+        };
+        if file.is_binary || file.hunks.is_empty() {
+            self.set_message("Nothing to word-diff in this file");
+            return;
+        }
+        let title = format!("Word diff: {}", file.display_path().display());
+        let lines = crate::ui::word_diff_view::word_diff_lines(&self.theme, file);
+        if !file.is_prose() {
+            self.set_warning("Word diff works best on prose files");
+        }
+        self.open_popup(title, lines, true);
+    }
+
+    /// Open a column-aligned rendering of the current CSV/TSV file in a popup
+    pub fn show_table_diff(&mut self) {
+        let Some(file) = self.current_file() else {
+            self.set_message("No file selected");
+            return;
+        };
+        let Some(delimiter) = file.tabular_delimiter() else {
+            self.set_warning("Column alignment is only available for CSV/TSV files");
+            return;
+        };
+        if file.is_binary || file.hunks.is_empty() {
+            self.set_message("Nothing to align in this file");
+            return;
+        }
+        let title = format!("Columns: {}", file.display_path().display());
+        let lines = crate::ui::table_view::table_diff_lines(&self.theme, file, delimiter);
+        self.open_popup(title, lines, false);
+    }
+
+    /// Open a key-path diff of the current JSON/YAML file in a popup
+    pub fn show_structural_diff(&mut self) {
+        let Some(file) = self.current_file() else {
+            self.set_message("No file selected");
+            return;
+        };
+        let Some(format) = file.structured_format() else {
+            self.set_warning("Structural diff is only available for JSON/YAML files");
+            return;
+        };
+        let title = format!("Structural diff: {}", file.display_path().display());
+        let result = self
+            .file_versions(file)
+            .and_then(|(old, new)| structural_diff(format, &old, &new));
+        match result {
+            Ok(changes) => {
+                let lines =
+                    crate::ui::structural_view::structural_diff_lines(&self.theme, &changes);
+                self.open_popup(title, lines, true);
             }
+            Err(e) => self.set_error(format!("Structural diff failed: {e}")),
         }
+    }
 
-        header_lines + comment_lines + content_lines + spacing_lines
+    /// Turn recording of review actions into the session history on or off
+    pub fn set_record_history(&mut self, enabled: bool) {
+        if self.deny_if_read_only() {
+            return;
+        }
+        if self.session.record_history != enabled {
+            self.session.record_history = enabled;
+            self.dirty = true;
+        }
+        if enabled {
+            self.set_message("Recording review actions (:history to view)");
+        } else {
+            self.set_message("No longer recording review actions");
+        }
     }
 
-    fn update_current_file_from_cursor(&mut self) {
-        let mut cumulative = 0;
-        for (i, file) in self.diff_files.iter().enumerate() {
-            let height = self.file_render_height(i, file);
-            if cumulative + height > self.diff_state.cursor_line {
-                self.diff_state.current_file_idx = i;
-                self.file_list_state.select(i);
-                return;
+    /// Open the session's audit trail in a popup
+    pub fn show_history(&mut self) {
+        if self.session.history.is_empty() {
+            if self.session.record_history {
+                self.set_message("No review actions recorded yet");
+            } else {
+                self.set_message("History is off. Enable it with :set history");
             }
-            cumulative += height;
+            return;
         }
-        if !self.diff_files.is_empty() {
-            self.diff_state.current_file_idx = self.diff_files.len() - 1;
-            self.file_list_state.select(self.diff_files.len() - 1);
+        let lines = crate::ui::history_view::history_lines(&self.theme, &self.session.history);
+        self.open_popup("History", lines, false);
+    }
+
+    /// Add the time since the last tick to the file under the cursor. Time
+    /// only counts while the terminal has focus and the reviewer was active
+    /// within the last few minutes.
+    pub fn tick_review_timer(&mut self) {
+        let now = Instant::now();
+        let Some(since) = self.review_clock.replace(now) else {
+            return;
+        };
+        if !self.terminal_focused || now.duration_since(self.last_activity) > REVIEW_IDLE_LIMIT {
+            return;
+        }
+        let Some(path) = self.current_file_path().cloned() else {
+            return;
+        };
+        if let Some(review) = self.session.files.get_mut(&path) {
+            review.time_spent_ms += now.duration_since(since).as_millis() as u64;
         }
     }
 
-    pub fn total_lines(&self) -> usize {
-        self.diff_files
-            .iter()
-            .enumerate()
-            .map(|(i, f)| self.file_render_height(i, f))
-            .sum()
+    pub fn note_activity(&mut self) {
+        self.last_activity = Instant::now();
     }
 
-    /// Calculate the maximum scroll offset.
-    ///
-    /// When line wrapping is enabled, logical lines may expand to multiple visual rows.
-    /// This means we need to allow scrolling further to ensure all content is reachable.
-    /// We allow scrolling to `total - 1` so the last logical line can be at the top.
-    ///
-    /// When wrapping is disabled, each logical line is one visual row, so we use
-    /// `total - viewport` which stops when the last line reaches the bottom.
-    pub fn max_scroll_offset(&self) -> usize {
-        let total = self.total_lines();
-        let viewport = self.diff_state.viewport_height.max(1);
-        if self.diff_state.wrap_lines {
-            // With wrapping, allow scrolling to show the last line at the top
-            total.saturating_sub(1)
-        } else {
-            // Without wrapping, stop when last line is at the bottom
-            total.saturating_sub(viewport)
+    /// Open review progress, diff size, comment counts and time per file
+    pub fn show_stats(&mut self) {
+        let stats = review_stats(&self.diff_files, &self.session);
+        let lines = crate::ui::stats_view::stats_lines(&self.theme, &stats);
+        self.open_popup("Review stats", lines, false);
+    }
+
+    /// Open the TODO/FIXME/XXX markers added by the diff in a popup
+    pub fn show_todos(&mut self) {
+        let markers = new_todo_markers(&self.diff_files);
+        if markers.is_empty() {
+            self.set_message("The diff adds no TODO, FIXME or XXX markers");
+            return;
         }
+        let title = format!("New TODOs ({})", markers.len());
+        let lines = crate::ui::todo_view::todo_lines(&self.theme, &markers);
+        self.open_popup(title, lines, false);
     }
 
-    /// Calculate the number of display lines a comment takes (header + content + footer)
-    fn comment_display_lines(comment: &Comment) -> usize {
-        let content_lines = comment.content.split('\n').count();
-        2 + content_lines // header + content lines + footer
+    /// Open the errors of this run in a popup, newest first
+    pub fn show_error_log(&mut self) {
+        if self.error_log.is_empty() {
+            self.set_message("No errors so far");
+            return;
+        }
+        let title = format!("Errors ({})", self.error_log.len());
+        let lines = crate::ui::error_view::error_log_lines(&self.theme, &self.error_log);
+        self.open_popup(title, lines, true);
     }
 
-    /// Returns the source line number and side at the current cursor position, if on a diff line
-    pub fn get_line_at_cursor(&self) -> Option<(u32, LineSide)> {
-        let target = self.diff_state.cursor_line;
-        match self.line_annotations.get(target) {
-            Some(AnnotatedLine::DiffLine {
-                old_lineno,
-                new_lineno,
-                ..
-            }) => {
-                // Prefer new line number (for added/context lines), fall back to old (for deleted)
-                new_lineno
-                    .map(|ln| (ln, LineSide::New))
-                    .or_else(|| old_lineno.map(|ln| (ln, LineSide::Old)))
-            }
-            _ => None,
+    /// Open the added blocks that look copied between files in a popup
+    pub fn show_duplicates(&mut self) {
+        let duplications = find_duplicate_blocks(&self.diff_files);
+        if duplications.is_empty() {
+            self.set_message("No possible duplication across the changed files");
+            return;
         }
+        let title = format!("Possible duplication ({})", duplications.len());
+        let lines = crate::ui::duplicates_view::duplicate_lines(&self.theme, &duplications);
+        self.open_popup(title, lines, false);
     }
 
-    /// Find the comment at the current cursor position
-    fn find_comment_at_cursor(&self) -> Option<CommentLocation> {
-        let target = self.diff_state.cursor_line;
-        match self.line_annotations.get(target) {
-            Some(AnnotatedLine::FileComment {
-                file_idx,
-                comment_idx,
-            }) => {
-                let path = self.diff_files.get(*file_idx)?.display_path().clone();
-                Some(CommentLocation::FileComment {
-                    path,
-                    index: *comment_idx,
-                })
+    /// Open a block-art preview of the current file in a popup, when it is an
+    /// image: the new version, or the old one for a deleted file
+    pub fn show_image_preview(&mut self) {
+        let Some(file) = self.current_file() else {
+            return;
+        };
+        let path = file.display_path().clone();
+        if !file.is_image() {
+            self.set_message(format!("Not an image: {}", path.display()));
+            return;
+        }
+        let revision = match (&file.status, &self.diff_source, &self.session.anchor) {
+            (FileStatus::Deleted, _, Some(anchor)) => Some(anchor.base.clone()),
+            (_, DiffSource::CommitRange(_), Some(anchor)) => anchor.head.clone(),
+            _ => None,
+        };
+        let bytes = match revision {
+            Some(rev) => self.vcs.file_at_revision(&rev, &path),
+            None if file.status == FileStatus::Deleted => {
+                self.set_message("The deleted image is not available to preview");
+                return;
             }
-            Some(AnnotatedLine::LineComment {
-                file_idx,
-                line,
-                side,
-                comment_idx,
-            }) => {
-                let path = self.diff_files.get(*file_idx)?.display_path().clone();
-                Some(CommentLocation::LineComment {
-                    path,
-                    line: *line,
-                    side: *side,
-                    index: *comment_idx,
-                })
+            None => self
+                .vcs_info
+                .working_tree_path(&path)
+                .and_then(|full_path| Ok(std::fs::read(full_path)?)),
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.set_error(format!("Cannot read {}: {e}", path.display()));
+                return;
             }
-            _ => None,
+        };
+        match image::load_from_memory(&bytes) {
+            Ok(image) => {
+                let title = format!(
+                    "{} ({}×{}, {})",
+                    path.display(),
+                    image.width(),
+                    image.height(),
+                    format_size(bytes.len() as u64)
+                );
+                let lines = crate::ui::image_view::image_lines(&image, 64, 32);
+                self.open_popup(title, lines, false);
+            }
+            Err(e) => self.set_error(format!("Cannot preview {}: {e}", path.display())),
         }
     }
 
-    /// Delete the comment at the current cursor position, if any
-    /// Returns true if a comment was deleted
-    pub fn delete_comment_at_cursor(&mut self) -> bool {
-        let location = self.find_comment_at_cursor();
+    /// Open the effective keymap in a popup, optionally filtered by `query`
+    pub fn show_keymap(&mut self, query: &str) {
+        let keymap: Vec<ModeKeymap> = effective_keymap(&self.keymap)
+            .iter()
+            .map(|mode| mode.filtered(query))
+            .collect();
+        if keymap.iter().all(|mode| mode.bindings.is_empty()) {
+            self.set_message(format!("No bindings match '{query}'"));
+            return;
+        }
+        let title = if query.is_empty() {
+            "Keymap".to_string()
+        } else {
+            format!("Keymap: {query}")
+        };
+        let lines = crate::ui::keymap_view::keymap_lines(&self.theme, &keymap);
+        self.open_popup(title, lines, false);
+    }
 
-        match location {
-            Some(CommentLocation::FileComment { path, index }) => {
-                if let Some(review) = self.session.get_file_mut(&path) {
-                    review.file_comments.remove(index);
-                    self.dirty = true;
-                    self.set_message("Comment deleted");
-                    self.rebuild_annotations();
-                    return true;
+    /// Reconstruct the full old and new contents of a file from its hunks.
+    ///
+    /// Unchanged stretches between and after hunks are read through the VCS
+    /// backend, the same way gap expansion does.
+    fn file_versions(&self, file: &DiffFile) -> Result<(String, String)> {
+        let mut old_lines: Vec<String> = Vec::new();
+        let mut new_lines: Vec<String> = Vec::new();
+        let path = file.display_path();
+        // Added and deleted files carry their whole content in the hunks
+        let complete = matches!(file.status, FileStatus::Added | FileStatus::Deleted);
+
+        let mut prev_hunk: Option<&DiffHunk> = None;
+        for hunk in &file.hunks {
+            if !complete {
+                let gap = calculate_gap(
+                    prev_hunk.map(|h| (&h.new_start, &h.new_count)),
+                    hunk.new_start,
+                );
+                if gap > 0 {
+                    let start = hunk.new_start - gap;
+                    for line in self.vcs.fetch_context_lines(
+                        path,
+                        file.status,
+                        start,
+                        hunk.new_start - 1,
+                    )? {
+                        old_lines.push(line.content.clone());
+                        new_lines.push(line.content);
+                    }
                 }
             }
-            Some(CommentLocation::LineComment {
-                path,
-                line,
-                side,
-                index,
-            }) => {
-                if let Some(review) = self.session.get_file_mut(&path)
-                    && let Some(comments) = review.line_comments.get_mut(&line)
-                {
-                    // Find the actual index by counting comments with matching side
-                    let mut side_idx = 0;
-                    let mut actual_idx = None;
-                    for (i, comment) in comments.iter().enumerate() {
-                        let comment_side = comment.side.unwrap_or(LineSide::New);
-                        if comment_side == side {
-                            if side_idx == index {
-                                actual_idx = Some(i);
-                                break;
-                            }
-                            side_idx += 1;
-                        }
-                    }
-                    if let Some(idx) = actual_idx {
-                        comments.remove(idx);
-                        if comments.is_empty() {
-                            review.line_comments.remove(&line);
-                        }
-                        self.dirty = true;
-                        self.set_message(format!("Comment on line {line} deleted"));
-                        self.rebuild_annotations();
-                        return true;
+            for line in &hunk.lines {
+                match line.origin {
+                    LineOrigin::Context => {
+                        old_lines.push(line.content.clone());
+                        new_lines.push(line.content.clone());
                     }
+                    LineOrigin::Deletion => old_lines.push(line.content.clone()),
+                    LineOrigin::Addition => new_lines.push(line.content.clone()),
                 }
             }
-            None => {}
+            prev_hunk = Some(hunk);
         }
 
-        false
-    }
-
-    pub fn clear_all_comments(&mut self) {
-        let cleared = self.session.clear_comments();
-        if cleared == 0 {
-            self.set_message("No comments to clear");
-            return;
+        if !complete && let Some(last) = prev_hunk {
+            let mut start = last.new_start + last.new_count;
+            loop {
+                let chunk = self.vcs.fetch_context_lines(
+                    path,
+                    file.status,
+                    start,
+                    start + TAIL_FETCH_CHUNK - 1,
+                )?;
+                let fetched = chunk.len();
+                for line in chunk {
+                    old_lines.push(line.content.clone());
+                    new_lines.push(line.content);
+                }
+                if fetched < TAIL_FETCH_CHUNK as usize {
+                    break;
+                }
+                start += TAIL_FETCH_CHUNK;
+            }
         }
 
-        self.dirty = true;
-        self.rebuild_annotations();
-        self.set_message(format!("Cleared {cleared} comments"));
+        Ok((old_lines.join("\n"), new_lines.join("\n")))
     }
 
-    /// Enter edit mode for the comment at the current cursor position
-    /// Returns true if a comment was found and edit mode entered
-    pub fn enter_edit_mode(&mut self) -> bool {
-        let location = self.find_comment_at_cursor();
+    pub fn enter_confirm_mode(&mut self, action: ConfirmAction) {
+        self.input_mode = InputMode::Confirm;
+        self.pending_confirm = Some(action);
+    }
 
-        match location {
-            Some(CommentLocation::FileComment { path, index }) => {
-                if let Some(review) = self.session.files.get(&path)
-                    && let Some(comment) = review.file_comments.get(index)
-                {
-                    self.input_mode = InputMode::Comment;
-                    self.comment_buffer = comment.content.clone();
-                    self.comment_cursor = self.comment_buffer.len();
-                    self.comment_type = comment.comment_type;
-                    self.comment_is_file_level = true;
-                    self.comment_line = None;
-                    self.editing_comment_id = Some(comment.id.clone());
-                    return true;
-                }
+    pub fn exit_confirm_mode(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.pending_confirm = None;
+    }
+
+    /// Text shown in the confirm dialog for the pending action
+    pub fn confirm_message(&self) -> String {
+        match self.pending_confirm {
+            Some(ConfirmAction::ResumeReview) => self.resume_summary(),
+            Some(ConfirmAction::PreviousReview) => {
+                let base = self
+                    .previous_review
+                    .as_ref()
+                    .map_or("", |previous| &previous.base_commit);
+                format!(
+                    "The branch moved since its review at {} ({}/{} files reviewed, {} comments). Open it, diff the changes since, or archive it?",
+                    &base[..base.len().min(8)],
+                    self.reviewed_count(),
+                    self.reviewable_count(),
+                    self.session.comment_count()
+                )
             }
-            Some(CommentLocation::LineComment {
-                path,
-                line,
-                side,
-                index,
-            }) => {
-                if let Some(review) = self.session.files.get(&path)
-                    && let Some(comments) = review.line_comments.get(&line)
-                {
-                    // Find the actual comment by counting comments with matching side
-                    let mut side_idx = 0;
-                    for comment in comments.iter() {
-                        let comment_side = comment.side.unwrap_or(LineSide::New);
-                        if comment_side == side {
-                            if side_idx == index {
-                                self.input_mode = InputMode::Comment;
-                                self.comment_buffer = comment.content.clone();
-                                self.comment_cursor = self.comment_buffer.len();
-                                self.comment_type = comment.comment_type;
-                                self.comment_is_file_level = false;
-                                self.comment_line = Some((line, side));
-                                self.editing_comment_id = Some(comment.id.clone());
-                                return true;
-                            }
-                            side_idx += 1;
-                        }
-                    }
-                }
+            Some(ConfirmAction::LargeExport) => {
+                let (size, chunks) = self
+                    .large_export
+                    .as_ref()
+                    .map_or((0, 0), |e| (e.content.len(), e.chunks.len()));
+                format!(
+                    "The review is {} KB, too large to copy reliably. Copy it in {chunks} chunks or write it to a file?",
+                    size.div_ceil(1024)
+                )
             }
-            None => {}
+            Some(ConfirmAction::NextExportChunk) => match &self.large_export {
+                Some(e) => format!(
+                    "Chunk {}/{} copied. Copy chunk {}/{}?",
+                    e.next_chunk,
+                    e.chunks.len(),
+                    e.next_chunk + 1,
+                    e.chunks.len()
+                ),
+                None => String::new(),
+            },
+            Some(ConfirmAction::OverwriteExport) => match &self.export_overwrite {
+                Some((path, _)) => format!("{} already exists. Overwrite it?", path.display()),
+                None => String::new(),
+            },
+            Some(ConfirmAction::RecoverAutosave) => match &self.autosave_recovery {
+                Some(journal) => format!(
+                    "Found unsaved changes from {} ({} comments) that were not written before tuicr exited. Recover them?",
+                    journal
+                        .updated_at
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M"),
+                    journal.comment_count()
+                ),
+                None => String::new(),
+            },
+            Some(ConfirmAction::CopyAndQuit) | None => "Copy review to clipboard?".to_string(),
         }
-
-        false
     }
 
-    pub fn enter_command_mode(&mut self) {
-        self.input_mode = InputMode::Command;
-        self.command_buffer.clear();
+    /// Keys offered by the confirm dialog, as (key, rest of the label) pairs
+    pub fn confirm_choices(&self) -> &'static [(&'static str, &'static str)] {
+        match self.pending_confirm {
+            Some(ConfirmAction::LargeExport) => &[
+                ("[C]", "hunks    "),
+                ("[F]", "ile    "),
+                ("[Esc]", " cancel"),
+            ],
+            Some(ConfirmAction::PreviousReview) => &[
+                ("[O]", "pen    "),
+                ("[D]", "iff since    "),
+                ("[A]", "rchive"),
+            ],
+            _ => &[("[Y]", "es    "), ("[N]", "o")],
+        }
     }
 
-    pub fn exit_command_mode(&mut self) {
-        self.input_mode = InputMode::Normal;
-        self.command_buffer.clear();
+    /// Summary of a resumed session, e.g.
+    /// "Resuming: 14/30 files reviewed, 9 comments, last at src/app.rs:210"
+    pub fn resume_summary(&self) -> String {
+        let mut summary = format!(
+            "Resuming: {}/{} files reviewed, {} comments",
+            self.reviewed_count(),
+            self.reviewable_count(),
+            self.session.comment_count()
+        );
+        if let Some(position) = &self.session.last_position {
+            summary.push_str(&format!(", last at {position}. Jump back?"));
+        } else {
+            summary.push_str(". Jump to first unreviewed file?");
+        }
+        summary
     }
 
-    pub fn enter_search_mode(&mut self) {
-        self.input_mode = InputMode::Search;
-        self.search_buffer.clear();
+    /// Jump to the last saved position, or the first unreviewed file when the
+    /// session has no recorded position (or it is no longer in the diff).
+    pub fn resume_last_position(&mut self) {
+        if let Some(position) = self.session.last_position.clone()
+            && self.jump_to_location(&position.path, position.line)
+        {
+            return;
+        }
+        if let Some(idx) = self
+            .diff_files
+            .iter()
+            .position(|file| !self.session.is_file_reviewed(file.display_path()))
+        {
+            self.jump_to_file(idx);
+        }
     }
 
-    pub fn exit_search_mode(&mut self) {
-        self.input_mode = InputMode::Normal;
-        self.search_buffer.clear();
-    }
+    /// Remember expanded context and collapsed directories in the session
+    pub fn record_view_state(&mut self) {
+        for review in self.session.files.values_mut() {
+            review.expanded_context.clear();
+        }
+        for gap in &self.expanded_gaps {
+            let Some(file) = self.diff_files.get(gap.file_idx) else {
+                continue;
+            };
+            let Some(hunk) = file.hunks.get(gap.hunk_idx) else {
+                continue;
+            };
+            if let Some(review) = self.session.files.get_mut(file.display_path()) {
+                review.expanded_context.push(hunk.new_start);
+            }
+        }
+        for review in self.session.files.values_mut() {
+            review.expanded_context.sort_unstable();
+        }
 
-    pub fn enter_comment_mode(&mut self, file_level: bool, line: Option<(u32, LineSide)>) {
-        self.input_mode = InputMode::Comment;
-        self.comment_buffer.clear();
-        self.comment_cursor = 0;
-        self.comment_type = CommentType::Note;
-        self.comment_is_file_level = file_level;
-        self.comment_line = line;
+        let mut collapsed: Vec<String> = self
+            .all_dir_paths()
+            .into_iter()
+            .filter(|dir| !self.expanded_dirs.contains(dir))
+            .collect();
+        collapsed.sort();
+        self.session.collapsed_dirs = collapsed;
     }
 
-    pub fn exit_comment_mode(&mut self) {
-        self.input_mode = InputMode::Normal;
-        self.comment_buffer.clear();
-        self.comment_cursor = 0;
-        self.editing_comment_id = None;
-        self.comment_line_range = None;
+    /// Re-apply the expanded context and collapsed directories saved in the
+    /// session. Hunks that no longer exist are ignored.
+    pub fn restore_view_state(&mut self) {
+        let mut gaps = Vec::new();
+        for (file_idx, file) in self.diff_files.iter().enumerate() {
+            let Some(review) = self.session.files.get(file.display_path()) else {
+                continue;
+            };
+            for (hunk_idx, hunk) in file.hunks.iter().enumerate() {
+                if review.expanded_context.contains(&hunk.new_start) {
+                    gaps.push(GapId { file_idx, hunk_idx });
+                }
+            }
+        }
+        for gap in gaps {
+            // Context that cannot be fetched any more just stays collapsed
+            let _ = self.expand_gap(gap);
+        }
+        for dir in &self.session.collapsed_dirs {
+            self.expanded_dirs.remove(dir);
+        }
+        self.ensure_valid_tree_selection();
     }
 
-    /// Enter visual selection mode, anchoring at the current cursor position
-    pub fn enter_visual_mode(&mut self, line: u32, side: LineSide) {
-        self.input_mode = InputMode::VisualSelect;
-        self.visual_anchor = Some((line, side));
+    /// Remember the current cursor location so the next session can resume it
+    pub fn record_position(&mut self) {
+        let Some(path) = self.current_file_path().cloned() else {
+            return;
+        };
+        let line = self.get_line_at_cursor().map(|(line, _)| line);
+        self.session.last_position = Some(ReviewPosition { path, line });
     }
 
-    /// Exit visual selection mode and return to normal mode
-    pub fn exit_visual_mode(&mut self) {
-        self.input_mode = InputMode::Normal;
-        self.visual_anchor = None;
+    /// Move the cursor to a file and, optionally, a line within it.
+    /// Returns false if the file is not part of the current diff.
+    pub fn jump_to_location(&mut self, path: &std::path::Path, line: Option<u32>) -> bool {
+        let Some(file_idx) = self
+            .diff_files
+            .iter()
+            .position(|file| file.display_path() == path)
+        else {
+            return false;
+        };
+        self.jump_to_file(file_idx);
+
+        if let Some(target) = line {
+            let found = self.line_annotations.iter().position(|annotation| {
+                matches!(
+                    annotation,
+                    AnnotatedLine::DiffLine { file_idx: idx, new_lineno, old_lineno, .. }
+                        if *idx == file_idx
+                            && (*new_lineno == Some(target)
+                                || (new_lineno.is_none() && *old_lineno == Some(target)))
+                )
+            });
+            if let Some(cursor) = found {
+                self.diff_state.cursor_line = cursor;
+                self.center_cursor();
+            }
+        }
+        true
     }
 
-    /// Get the current visual selection range (if in visual mode)
-    /// Returns None if not in visual mode or if there's no valid selection
-    pub fn get_visual_selection(&self) -> Option<(LineRange, LineSide)> {
-        if self.input_mode != InputMode::VisualSelect {
-            return None;
+    pub fn enter_commit_select_mode(&mut self) -> Result<()> {
+        let commits = self.vcs.get_recent_commits(0, VISIBLE_COMMIT_COUNT)?;
+        if commits.is_empty() {
+            self.set_message("No commits found");
+            return Ok(());
         }
 
-        let (anchor_line, anchor_side) = self.visual_anchor?;
-        let (current_line, current_side) = self.get_line_at_cursor()?;
+        // Check if there might be more commits
+        self.has_more_commit = commits.len() >= VISIBLE_COMMIT_COUNT;
+        self.commit_list = commits;
+        self.commit_list_cursor = 0;
+        self.commit_list_scroll_offset = 0;
+        self.commit_selection_range = None;
+        self.visible_commit_count = self.commit_list.len();
+        self.reselecting_commits = false;
 
-        // Don't allow selection across sides (old vs new)
-        if anchor_side != current_side {
-            return None;
+        // Reopening from a commit range review: restore its checkboxes
+        if let DiffSource::CommitRange(ids) = self.diff_source.clone()
+            && let Some((start, end)) = self.find_commit_range(&ids)?
+        {
+            self.commit_selection_range = Some((start, end));
+            self.commit_list_cursor = start;
+            if self.commit_list_viewport_height > 0 && start >= self.commit_list_viewport_height {
+                self.commit_list_scroll_offset = start + 1 - self.commit_list_viewport_height;
+            }
+            self.reselecting_commits = true;
         }
 
-        let range = LineRange::new(anchor_line, current_line);
-        Some((range, anchor_side))
+        self.input_mode = InputMode::CommitSelect;
+        Ok(())
     }
 
-    /// Check if a given line is within the current visual selection
-    pub fn is_line_in_visual_selection(&self, line: u32, side: LineSide) -> bool {
-        if let Some((range, sel_side)) = self.get_visual_selection() {
-            sel_side == side && range.contains(line)
-        } else {
-            false
-        }
-    }
+    /// Locate the given commits in the commit list, loading older pages as
+    /// needed. Returns the (newest, oldest) index range covering them.
+    fn find_commit_range(&mut self, ids: &[String]) -> Result<Option<(usize, usize)>> {
+        const MAX_PAGES: usize = 20;
 
-    /// Enter comment mode from visual selection
-    pub fn enter_comment_from_visual(&mut self) {
-        if let Some((range, side)) = self.get_visual_selection() {
-            self.comment_line_range = Some((range, side));
-            self.comment_line = Some((range.end, side)); // Key by end line
-            self.input_mode = InputMode::Comment;
-            self.comment_buffer.clear();
-            self.comment_cursor = 0;
-            self.comment_type = CommentType::Note;
-            self.comment_is_file_level = false;
-            self.visual_anchor = None;
-        } else {
-            self.set_warning("Invalid visual selection");
-            self.exit_visual_mode();
+        for _ in 0..MAX_PAGES {
+            let positions: Vec<usize> = ids
+                .iter()
+                .filter_map(|id| self.commit_list.iter().position(|c| c.id == *id))
+                .collect();
+            if positions.len() == ids.len() {
+                let start = positions.iter().copied().min();
+                let end = positions.iter().copied().max();
+                return Ok(start.zip(end));
+            }
+            if !self.has_more_commit {
+                break;
+            }
+            let offset = self.commit_list.len();
+            let more = self.vcs.get_recent_commits(offset, self.commit_page_size)?;
+            self.has_more_commit = more.len() >= self.commit_page_size;
+            self.commit_list.extend(more);
+            self.visible_commit_count = self.commit_list.len();
         }
+        Ok(None)
     }
 
-    pub fn save_comment(&mut self) {
-        if self.comment_buffer.trim().is_empty() {
-            self.set_message("Comment cannot be empty");
-            return;
-        }
+    pub fn exit_commit_select_mode(&mut self) -> Result<()> {
+        self.input_mode = InputMode::Normal;
 
-        let content = self.comment_buffer.trim().to_string();
+        // Cancelled a reselection: keep reviewing the current commit range
+        if std::mem::take(&mut self.reselecting_commits) {
+            return Ok(());
+        }
 
-        if let Some(path) = self.current_file_path().cloned()
-            && let Some(review) = self.session.get_file_mut(&path)
-        {
-            let message: String;
+        // If we were viewing commits, try to go back to working tree
+        if matches!(self.diff_source, DiffSource::CommitRange(_)) {
+            let highlighter = self.theme.syntax_highlighter();
+            match self.vcs.get_working_tree_diff(highlighter) {
+                Ok(diff_files) => {
+                    self.diff_files = diff_files;
+                    self.diff_source = DiffSource::WorkingTree;
+                    self.mark_diff_fresh();
 
-            // Check if we're editing an existing comment
-            if let Some(editing_id) = &self.editing_comment_id {
-                // Update existing comment
-                // Search in file comments
-                if let Some(comment) = review
-                    .file_comments
-                    .iter_mut()
-                    .find(|c| &c.id == editing_id)
-                {
-                    comment.content = content.clone();
-                    comment.comment_type = self.comment_type;
-                    message = "Comment updated".to_string();
-                } else {
-                    // If not found in file comments, search in line comments
-                    let mut found_comment = None;
-                    for comments in review.line_comments.values_mut() {
-                        if let Some(comment) = comments.iter_mut().find(|c| &c.id == editing_id) {
-                            found_comment = Some(comment);
-                            break;
-                        }
+                    // Update session for new files
+                    if Self::sync_session_files(
+                        &mut self.session,
+                        &self.diff_files,
+                        self.whitespace_mode,
+                    ) > 0
+                    {
+                        self.dirty |= !self.read_only;
                     }
+                    self.apply_diff_filters();
 
-                    if let Some(comment) = found_comment {
-                        comment.content = content.clone();
-                        comment.comment_type = self.comment_type;
-                        message = if let Some((line, _)) = self.comment_line {
-                            format!("Comment on line {line} updated")
-                        } else {
-                            "Comment updated".to_string()
-                        };
-                    } else {
-                        message = "Error: Comment to edit not found".to_string();
-                    }
+                    self.sort_files_by_directory(true);
+                    self.expand_all_dirs();
                 }
-            } else {
-                // Create new comment
-                if self.comment_is_file_level {
-                    let comment = Comment::new(content, self.comment_type, None);
-                    review.add_file_comment(comment);
-                    message = "File comment added".to_string();
-                } else if let Some((range, side)) = self.comment_line_range {
-                    // Range comment from visual selection
-                    let comment =
-                        Comment::new_with_range(content, self.comment_type, Some(side), range);
-                    // Store by end line of the range
-                    review.add_line_comment(range.end, comment);
-                    if range.is_single() {
-                        message = format!("Comment added to line {}", range.end);
-                    } else {
-                        message = format!("Comment added to lines {}-{}", range.start, range.end);
-                    }
-                } else if let Some((line, side)) = self.comment_line {
-                    let comment = Comment::new(content, self.comment_type, Some(side));
-                    review.add_line_comment(line, comment);
-                    message = format!("Comment added to line {line}");
-                } else {
-                    // Fallback to file comment if no line specified
-                    let comment = Comment::new(content, self.comment_type, None);
-                    review.add_file_comment(comment);
-                    message = "File comment added".to_string();
+                Err(_) => {
+                    self.set_message("No working tree changes");
                 }
             }
+        }
 
-            self.dirty = true;
-            self.set_message(message);
-            self.rebuild_annotations();
+        Ok(())
+    }
+
+    /// Compare the repository state with the one the diff was loaded from
+    /// and flag the diff as stale when they differ. Commit range reviews are
+    /// never stale since commits do not change.
+    pub fn check_diff_stale(&mut self) {
+        if self.diff_stale || matches!(self.diff_source, DiffSource::CommitRange(_)) {
+            return;
+        }
+        if let Some(loaded) = self.diff_fingerprint
+            && let Some(current) = self.vcs.state_fingerprint()
+            && current != loaded
+        {
+            self.diff_stale = true;
         }
+    }
 
-        self.exit_comment_mode();
+    pub fn autoreload(&self) -> bool {
+        self.watcher.is_some()
     }
 
-    pub fn cycle_comment_type(&mut self) {
-        self.comment_type = match self.comment_type {
-            CommentType::Note => CommentType::Suggestion,
-            CommentType::Suggestion => CommentType::Issue,
-            CommentType::Issue => CommentType::Praise,
-            CommentType::Praise => CommentType::Note,
-        };
+    /// Start posting comment events if a webhook is configured for the
+    /// repository (see `WebhookConfig`)
+    /// Read the repository's CODEOWNERS, if it has one
+    pub fn load_code_owners(&mut self) {
+        if let Some(owners) = CodeOwners::load(&self.vcs_info.root_path) {
+            self.code_owners = owners;
+        }
     }
 
-    pub fn toggle_help(&mut self) {
-        if self.input_mode == InputMode::Help {
-            self.input_mode = InputMode::Normal;
-        } else {
-            self.input_mode = InputMode::Help;
-            self.help_state.scroll_offset = 0;
+    pub fn start_webhook(&mut self) {
+        if let Some(config) = WebhookConfig::load(&self.vcs_info.root_path) {
+            self.webhook = Some(WebhookSender::spawn(
+                config,
+                self.vcs_info.root_path.display().to_string(),
+                self.vcs_info.branch_name.clone(),
+            ));
         }
     }
 
-    pub fn help_scroll_down(&mut self, lines: usize) {
-        let max_offset = self
-            .help_state
-            .total_lines
-            .saturating_sub(self.help_state.viewport_height);
-        self.help_state.scroll_offset = (self.help_state.scroll_offset + lines).min(max_offset);
+    /// Queue comment `id` of `path` for the webhook
+    fn notify_webhook(&self, path: &PathBuf, id: &str, kind: WebhookEventKind) {
+        if let Some(webhook) = &self.webhook
+            && let Some(event) = WebhookEvent::find(&self.session, path, id, kind)
+        {
+            webhook.send(event);
+        }
     }
 
-    pub fn help_scroll_up(&mut self, lines: usize) {
-        self.help_state.scroll_offset = self.help_state.scroll_offset.saturating_sub(lines);
+    /// Show a warning when posting to the webhook failed. Returns whether
+    /// it did, i.e. the screen may change.
+    pub fn poll_webhook(&mut self) -> bool {
+        let Some(error) = self.webhook.as_ref().and_then(WebhookSender::last_failure) else {
+            return false;
+        };
+        self.set_warning(format!("Webhook: {error}"));
+        true
+    }
+
+    /// Start or stop reloading the diff whenever working tree files change.
+    /// Only working tree reviews of a repository can be watched.
+    pub fn set_autoreload(&mut self, enabled: bool) {
+        if !enabled {
+            self.watcher = None;
+            self.set_message("Auto-reload off");
+            return;
+        }
+        if matches!(self.diff_source, DiffSource::CommitRange(_))
+            || self.vcs.state_fingerprint().is_none()
+        {
+            self.set_warning("Auto-reload needs a working tree review of a repository");
+            return;
+        }
+        if self.watcher.is_none() {
+            self.watcher = Some(FileWatcher::spawn(self.vcs_info.root_path.clone()));
+        }
+        self.set_message("Auto-reload on: the diff follows file changes");
+    }
+
+    /// Reload the diff when the watcher saw a change that the repository
+    /// state confirms. Waits for normal mode so a reload never pulls the
+    /// lines out from under a comment being written or a selection.
+    /// Returns whether the watcher saw anything, i.e. the screen may change.
+    pub fn poll_autoreload(&mut self) -> bool {
+        let Some(watcher) = &self.watcher else {
+            return false;
+        };
+        let changed = watcher.changed();
+        if changed {
+            self.check_diff_stale();
+        }
+        if !self.diff_stale || self.input_mode != InputMode::Normal {
+            return changed;
+        }
+        match self.reload_diff_files() {
+            Ok((count, invalidated)) => match self.reload_problems(invalidated) {
+                Some(problems) => {
+                    self.set_warning(format!("Auto-reloaded {count} files; {problems}"))
+                }
+                None => self.set_message(format!("Auto-reloaded {count} files")),
+            },
+            Err(TuicrError::NoChanges) => {
+                // Everything was committed or reverted; keep the last diff
+                self.mark_diff_fresh();
+                self.set_message("No changes left to review");
+            }
+            Err(e) => {
+                self.mark_diff_fresh();
+                self.set_error(format!("Auto-reload failed: {e}"));
+            }
+        }
+        true
     }
 
-    pub fn help_scroll_to_top(&mut self) {
-        self.help_state.scroll_offset = 0;
+    /// Record the repository state the current diff was loaded from
+    fn mark_diff_fresh(&mut self) {
+        self.blame.clear();
+        self.diff_fingerprint = self.vcs.state_fingerprint();
+        self.diff_stale = false;
+        self.refresh_staged_paths();
+        self.record_anchor();
     }
 
-    pub fn help_scroll_to_bottom(&mut self) {
-        let max_offset = self
-            .help_state
-            .total_lines
-            .saturating_sub(self.help_state.viewport_height);
-        self.help_state.scroll_offset = max_offset;
+    /// Record in the session the revisions the diff compares, for exports to
+    /// name. Left unset when the VCS cannot tell.
+    fn record_anchor(&mut self) {
+        let anchor = match &self.diff_source {
+            DiffSource::WorkingTree => self.vcs.working_tree_anchor(),
+            DiffSource::CommitRange(ids) => self.vcs.commit_range_anchor(ids),
+            DiffSource::Ref(base) => self.vcs.ref_anchor(base),
+        };
+        self.session.anchor = anchor.ok();
     }
 
-    pub fn enter_confirm_mode(&mut self, action: ConfirmAction) {
-        self.input_mode = InputMode::Confirm;
-        self.pending_confirm = Some(action);
+    fn refresh_staged_paths(&mut self) {
+        self.staged_paths = if self.diff_source == DiffSource::WorkingTree {
+            self.vcs
+                .staged_paths()
+                .unwrap_or_default()
+                .into_iter()
+                .collect()
+        } else {
+            HashSet::new()
+        };
     }
 
-    pub fn exit_confirm_mode(&mut self) {
-        self.input_mode = InputMode::Normal;
-        self.pending_confirm = None;
-    }
+    // Ref selection methods
 
-    pub fn enter_commit_select_mode(&mut self) -> Result<()> {
-        let commits = self.vcs.get_recent_commits(0, VISIBLE_COMMIT_COUNT)?;
-        if commits.is_empty() {
-            self.set_message("No commits found");
+    pub fn enter_ref_select_mode(&mut self) -> Result<()> {
+        let refs = self.vcs.list_refs()?;
+        if refs.is_empty() {
+            self.set_message("No branches or tags found");
             return Ok(());
         }
 
-        // Check if there might be more commits
-        self.has_more_commit = commits.len() >= VISIBLE_COMMIT_COUNT;
-        self.commit_list = commits;
-        self.commit_list_cursor = 0;
-        self.commit_list_scroll_offset = 0;
-        self.commit_selection_range = None;
-        self.visible_commit_count = self.commit_list.len();
-        self.input_mode = InputMode::CommitSelect;
+        // Start on the current base when already comparing against a ref
+        let current = match &self.diff_source {
+            DiffSource::Ref(base) => refs.iter().position(|r| r.name == *base),
+            _ => None,
+        };
+        self.ref_list = refs;
+        self.ref_list_cursor = current.unwrap_or(0);
+        self.ref_list_scroll_offset = 0;
+        if self.ref_list_viewport_height > 0
+            && self.ref_list_cursor >= self.ref_list_viewport_height
+        {
+            self.ref_list_scroll_offset = self.ref_list_cursor + 1 - self.ref_list_viewport_height;
+        }
+        self.input_mode = InputMode::RefSelect;
         Ok(())
     }
 
-    pub fn exit_commit_select_mode(&mut self) -> Result<()> {
+    pub fn exit_ref_select_mode(&mut self) {
         self.input_mode = InputMode::Normal;
+    }
 
-        // If we were viewing commits, try to go back to working tree
-        if matches!(self.diff_source, DiffSource::CommitRange(_)) {
-            let highlighter = self.theme.syntax_highlighter();
-            match self.vcs.get_working_tree_diff(highlighter) {
-                Ok(diff_files) => {
-                    self.diff_files = diff_files;
-                    self.diff_source = DiffSource::WorkingTree;
+    pub fn ref_select_up(&mut self) {
+        if self.ref_list_cursor > 0 {
+            self.ref_list_cursor -= 1;
+            if self.ref_list_cursor < self.ref_list_scroll_offset {
+                self.ref_list_scroll_offset = self.ref_list_cursor;
+            }
+        }
+    }
 
-                    // Update session for new files
-                    for file in &self.diff_files {
-                        let path = file.display_path().clone();
-                        self.session.add_file(path, file.status);
-                    }
+    pub fn ref_select_down(&mut self) {
+        if self.ref_list_cursor + 1 < self.ref_list.len() {
+            self.ref_list_cursor += 1;
+            if self.ref_list_viewport_height > 0
+                && self.ref_list_cursor
+                    >= self.ref_list_scroll_offset + self.ref_list_viewport_height
+            {
+                self.ref_list_scroll_offset =
+                    self.ref_list_cursor - self.ref_list_viewport_height + 1;
+            }
+        }
+    }
 
-                    self.sort_files_by_directory(true);
-                    self.expand_all_dirs();
-                }
-                Err(_) => {
-                    self.set_message("No working tree changes");
-                }
+    pub fn confirm_ref_selection(&mut self) -> Result<()> {
+        let Some(base) = self
+            .ref_list
+            .get(self.ref_list_cursor)
+            .map(|r| r.name.clone())
+        else {
+            return Ok(());
+        };
+        self.compare_against_ref(&base)
+    }
+
+    /// Replace the diff with the working tree compared against `base`
+    pub fn compare_against_ref(&mut self, base: &str) -> Result<()> {
+        let highlighter = self.theme.syntax_highlighter();
+        let diff_files = match self.vcs.get_ref_diff(base, highlighter) {
+            Ok(files) => files,
+            Err(TuicrError::NoChanges) => {
+                self.set_message(format!("No changes against {base}"));
+                return Ok(());
             }
+            Err(e) => return Err(e),
+        };
+
+        let invalidated =
+            Self::sync_session_files(&mut self.session, &diff_files, self.whitespace_mode);
+        if invalidated > 0 {
+            self.dirty |= !self.read_only;
         }
 
+        self.diff_files = diff_files;
+        self.apply_diff_filters();
+        self.diff_source = DiffSource::Ref(base.to_string());
+        self.input_mode = InputMode::Normal;
+        self.clear_expanded_gaps();
+        self.mark_diff_fresh();
+
+        self.diff_state = DiffState::default();
+        self.file_list_state = FileListState::default();
+
+        self.sort_files_by_directory(true);
+        self.expand_all_dirs();
+        self.rebuild_annotations();
+
+        if invalidated > 0 {
+            self.set_warning(Self::invalidated_message(invalidated));
+        } else {
+            self.set_message(format!("Comparing against {base}"));
+        }
         Ok(())
     }
 
     pub fn toggle_diff_view_mode(&mut self) {
+        if self.screen_reader {
+            self.set_warning("Side-by-side view is off in screen reader mode");
+            return;
+        }
         self.diff_view_mode = match self.diff_view_mode {
             DiffViewMode::Unified => DiffViewMode::SideBySide,
             DiffViewMode::SideBySide => DiffViewMode::Unified,
@@ -1696,6 +5437,23 @@ impl App {
         self.set_message(format!("Diff view mode: {mode_name}"));
     }
 
+    /// Turn screen reader mode on or off. It shows the diff alone in the
+    /// unified view, so every row reads top to bottom.
+    pub fn set_screen_reader(&mut self, enabled: bool) {
+        self.screen_reader = enabled;
+        if enabled {
+            self.diff_view_mode = DiffViewMode::Unified;
+            self.show_comments_panel = false;
+            self.focused_panel = FocusedPanel::Diff;
+        }
+        self.show_file_list = !enabled;
+        self.set_message(if enabled {
+            "Screen reader mode on"
+        } else {
+            "Screen reader mode off"
+        });
+    }
+
     pub fn toggle_file_list(&mut self) {
         self.show_file_list = !self.show_file_list;
         let status = if self.show_file_list {
@@ -1846,6 +5604,31 @@ impl App {
             return Ok(());
         }
 
+        self.load_commit_range(selected_ids, None)
+    }
+
+    /// Review the commits of a range expression (`tuicr --range main..feature`)
+    pub fn review_commit_range(&mut self, range: &str) -> Result<()> {
+        let ids = self.vcs.resolve_commit_range(range)?;
+        self.load_commit_range(ids, Some(range.to_string()))?;
+        if self.input_mode == InputMode::CommitSelect {
+            return Err(TuicrError::NoChanges);
+        }
+        // Reopening the same range picks the review up where it was left
+        self.restore_view_state();
+        if self.session.has_progress() {
+            self.enter_confirm_mode(ConfirmAction::ResumeReview);
+        }
+        Ok(())
+    }
+
+    /// Load the diff of commits (oldest first) and the session reviewing
+    /// them. `range` is the expression they were resolved from, if any.
+    fn load_commit_range(
+        &mut self,
+        selected_ids: Vec<String>,
+        range: Option<String>,
+    ) -> Result<()> {
         // Get the diff for the selected commits
         let highlighter = self.theme.syntax_highlighter();
         let diff_files = self.vcs.get_commit_range_diff(&selected_ids, highlighter)?;
@@ -1859,22 +5642,26 @@ impl App {
         let newest_commit_id = selected_ids.last().unwrap().clone();
         let loaded_session = load_latest_session_for_context(
             &self.vcs_info.root_path,
-            self.vcs_info.branch_name.as_deref(),
+            self.vcs_info.session_branch(),
             &newest_commit_id,
             SessionDiffSource::CommitRange,
             Some(selected_ids.as_slice()),
-        )
-        .ok()
-        .and_then(|found| found.map(|(_path, session)| session));
+        );
+        let loaded_session = match loaded_session {
+            Ok(found) => found.map(|(_path, session)| session),
+            Err(e @ TuicrError::SessionTooNew(_)) => return Err(e),
+            Err(_) => None,
+        };
 
         let mut session = loaded_session.unwrap_or_else(|| {
             let mut session = ReviewSession::new(
                 self.vcs_info.root_path.clone(),
                 newest_commit_id,
-                self.vcs_info.branch_name.clone(),
+                self.vcs_info.session_branch().map(str::to_string),
                 SessionDiffSource::CommitRange,
             );
             session.commit_range = Some(selected_ids.clone());
+            session.range = range.clone();
             session
         });
 
@@ -1882,18 +5669,54 @@ impl App {
             session.commit_range = Some(selected_ids.clone());
             session.updated_at = chrono::Utc::now();
         }
+        if range.is_some() && session.range != range {
+            session.range = range;
+            session.updated_at = chrono::Utc::now();
+        }
 
-        self.session = session;
+        let previous_dirty = self.dirty;
+        let previous = std::mem::replace(&mut self.session, session);
 
         // Add files to session
-        for file in &diff_files {
-            let path = file.display_path().clone();
-            self.session.add_file(path, file.status);
+        let invalidated =
+            Self::sync_session_files(&mut self.session, &diff_files, self.whitespace_mode);
+        if invalidated > 0 {
+            self.dirty |= !self.read_only;
+            self.set_warning(Self::invalidated_message(invalidated));
+        }
+
+        // Reselecting commits: bring along comments that still anchor. The
+        // previous session is saved first so dropped comments are not lost.
+        if std::mem::take(&mut self.reselecting_commits) && previous.has_comments() {
+            if previous_dirty && !self.read_only {
+                save_session(&previous)?;
+            }
+            let (carried, dropped) =
+                self.session
+                    .carry_over_comments(&previous, |path, line, side| {
+                        diff_files
+                            .iter()
+                            .find(|file| file.display_path() == path)
+                            .is_some_and(|file| file.contains_line(line, side))
+                    });
+            if carried > 0 {
+                self.dirty |= !self.read_only;
+            }
+            if dropped > 0 {
+                self.set_warning(format!(
+                    "Kept {carried} comments; {dropped} no longer anchor to the selected commits"
+                ));
+            } else if carried > 0 {
+                self.set_message(format!("Kept {carried} comments"));
+            }
         }
 
         // Update app state
         self.diff_files = diff_files;
         self.diff_source = DiffSource::CommitRange(selected_ids);
+        self.record_anchor();
+        self.blame.clear();
+        self.apply_diff_filters();
         self.input_mode = InputMode::Normal;
 
         // Reset navigation state
@@ -1952,21 +5775,26 @@ impl App {
     }
 
     pub fn expand_all_dirs(&mut self) {
+        self.expanded_dirs = self.all_dir_paths();
+        self.ensure_valid_tree_selection();
+    }
+
+    /// Every directory containing a diff file, at any depth
+    fn all_dir_paths(&self) -> HashSet<String> {
         use std::path::Path;
 
-        self.expanded_dirs.clear();
+        let mut dirs = HashSet::new();
         for file in &self.diff_files {
             let path = file.display_path();
             let mut current = path.parent();
             while let Some(parent) = current {
                 if parent != Path::new("") {
-                    self.expanded_dirs
-                        .insert(parent.to_string_lossy().to_string());
+                    dirs.insert(parent.to_string_lossy().to_string());
                 }
                 current = parent.parent();
             }
         }
-        self.ensure_valid_tree_selection();
+        dirs
     }
 
     pub fn collapse_all_dirs(&mut self) {
@@ -1983,6 +5811,29 @@ impl App {
         }
     }
 
+    /// Switch the file list filter tab
+    pub fn set_file_filter(&mut self, filter: FileFilter) {
+        if self.file_filter == filter {
+            return;
+        }
+        if filter == FileFilter::Mine && self.code_owners.is_empty() {
+            self.set_warning("No CODEOWNERS file to tell which files are yours");
+            return;
+        }
+        self.file_filter = filter;
+        self.ensure_valid_tree_selection();
+        let shown = self
+            .diff_files
+            .iter()
+            .filter(|file| filter.matches(file, &self.session, &self.code_owners))
+            .count();
+        self.set_message(format!(
+            "Files: {} ({shown}/{})",
+            filter.label(),
+            self.diff_files.len()
+        ));
+    }
+
     /// Check if a hunk gap has been expanded
     pub fn is_gap_expanded(&self, gap_id: &GapId) -> bool {
         self.expanded_gaps.contains(gap_id)
@@ -2246,7 +6097,29 @@ impl App {
         let mut items = Vec::new();
         let mut seen_dirs: HashSet<String> = HashSet::new();
 
+        // Pinned files get their own section above the tree
+        for (file_idx, file) in self.diff_files.iter().enumerate() {
+            if self.session.is_file_pinned(file.display_path())
+                && self
+                    .file_filter
+                    .matches(file, &self.session, &self.code_owners)
+            {
+                items.push(FileTreeItem::File {
+                    file_idx,
+                    depth: 0,
+                    pinned: true,
+                });
+            }
+        }
+
         for (file_idx, file) in self.diff_files.iter().enumerate() {
+            // Directories are only emitted for files that pass the filter
+            if !self
+                .file_filter
+                .matches(file, &self.session, &self.code_owners)
+            {
+                continue;
+            }
             let path = file.display_path();
 
             let mut ancestors: Vec<String> = Vec::new();
@@ -2280,6 +6153,7 @@ impl App {
                 items.push(FileTreeItem::File {
                     file_idx,
                     depth: ancestors.len(),
+                    pinned: false,
                 });
             }
         }
@@ -2287,6 +6161,25 @@ impl App {
         items
     }
 
+    /// Sum the +/- counts and unreviewed files of every diff file under `dir`
+    pub fn directory_rollup(&self, dir: &str) -> DirectoryRollup {
+        let dir = std::path::Path::new(dir);
+        let mut rollup = DirectoryRollup::default();
+        for file in &self.diff_files {
+            let path = file.display_path();
+            if !path.starts_with(dir) {
+                continue;
+            }
+            let (additions, deletions) = file.line_stats();
+            rollup.additions += additions;
+            rollup.deletions += deletions;
+            if !self.session.is_file_reviewed(path) && !self.session.is_file_skipped(path) {
+                rollup.unreviewed += 1;
+            }
+        }
+        rollup
+    }
+
     pub fn get_selected_tree_item(&self) -> Option<FileTreeItem> {
         let visible_items = self.build_visible_items();
         let selected_idx = self.file_list_state.selected();
@@ -2297,17 +6190,8 @@ impl App {
 #[cfg(test)]
 mod tree_tests {
     use super::*;
-    use crate::model::{DiffFile, FileStatus};
-
-    fn make_file(path: &str) -> DiffFile {
-        DiffFile {
-            old_path: None,
-            new_path: Some(PathBuf::from(path)),
-            status: FileStatus::Modified,
-            hunks: vec![],
-            is_binary: false,
-        }
-    }
+    use crate::model::DiffFile;
+    use crate::model::diff_types::test_diffs::modified_file;
 
     struct TreeTestHarness {
         diff_files: Vec<DiffFile>,
@@ -2317,7 +6201,7 @@ mod tree_tests {
     impl TreeTestHarness {
         fn new(paths: &[&str]) -> Self {
             Self {
-                diff_files: paths.iter().map(|p| make_file(p)).collect(),
+                diff_files: paths.iter().map(|p| modified_file(p, Vec::new())).collect(),
                 expanded_dirs: HashSet::new(),
             }
         }
@@ -2386,6 +6270,7 @@ mod tree_tests {
                     items.push(FileTreeItem::File {
                         file_idx,
                         depth: ancestors.len(),
+                        pinned: false,
                     });
                 }
             }
@@ -2487,13 +6372,148 @@ mod tree_tests {
     }
 }
 
+/// Whether `a` and `b` are rows of the same comment box
+fn same_comment(a: &AnnotatedLine, b: &AnnotatedLine) -> bool {
+    match (a, b) {
+        (
+            AnnotatedLine::FileComment {
+                file_idx: a_file,
+                comment_idx: a_idx,
+            },
+            AnnotatedLine::FileComment {
+                file_idx,
+                comment_idx,
+            },
+        ) => a_file == file_idx && a_idx == comment_idx,
+        (
+            AnnotatedLine::LineComment {
+                file_idx: a_file,
+                line: a_line,
+                side: a_side,
+                comment_idx: a_idx,
+            },
+            AnnotatedLine::LineComment {
+                file_idx,
+                line,
+                side,
+                comment_idx,
+            },
+        ) => a_file == file_idx && a_line == line && a_side == side && a_idx == comment_idx,
+        _ => false,
+    }
+}
+
+/// Index of the first file after `current` that is neither reviewed nor
+/// skipped, wrapping around to the files before it
+fn next_unreviewed_file(
+    files: &[DiffFile],
+    session: &ReviewSession,
+    current: usize,
+) -> Option<usize> {
+    (1..files.len())
+        .map(|step| (current + step) % files.len())
+        .find(|&idx| {
+            let path = files[idx].display_path();
+            !session.is_file_reviewed(path) && !session.is_file_skipped(path)
+        })
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::modified_file;
+    use crate::model::{Comment, SessionDiffSource};
+
+    fn make_file(path: &str, status: FileStatus) -> DiffFile {
+        DiffFile {
+            status,
+            ..modified_file(path, Vec::new())
+        }
+    }
+
+    fn make_session(files: &[&DiffFile]) -> ReviewSession {
+        let mut session = ReviewSession::new(
+            PathBuf::from("/repo"),
+            "abc".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        for file in files {
+            session.add_file(file.display_path().clone(), file.status);
+        }
+        session
+    }
+
+    #[test]
+    fn should_filter_by_review_state_and_comments() {
+        // given
+        let reviewed = make_file("a.rs", FileStatus::Modified);
+        let commented = make_file("b.rs", FileStatus::Modified);
+        let mut session = make_session(&[&reviewed, &commented]);
+        session
+            .get_file_mut(reviewed.display_path())
+            .unwrap()
+            .reviewed = true;
+        session
+            .get_file_mut(commented.display_path())
+            .unwrap()
+            .add_file_comment(Comment::new("why?".to_string(), CommentType::Note, None));
+
+        // then
+        assert!(!FileFilter::Unreviewed.matches(&reviewed, &session, &CodeOwners::default()));
+        assert!(FileFilter::Unreviewed.matches(&commented, &session, &CodeOwners::default()));
+        assert!(!FileFilter::Commented.matches(&reviewed, &session, &CodeOwners::default()));
+        assert!(FileFilter::Commented.matches(&commented, &session, &CodeOwners::default()));
+    }
+
+    #[test]
+    fn should_find_the_next_file_to_review() {
+        // given
+        let files: Vec<DiffFile> = ["a.rs", "b.rs", "c.rs", "d.rs"]
+            .iter()
+            .map(|path| make_file(path, FileStatus::Modified))
+            .collect();
+        let mut session = make_session(&files.iter().collect::<Vec<_>>());
+        session
+            .get_file_mut(files[1].display_path())
+            .unwrap()
+            .reviewed = true;
+        session
+            .get_file_mut(files[2].display_path())
+            .unwrap()
+            .skipped = true;
+
+        // then
+        assert_eq!(next_unreviewed_file(&files, &session, 0), Some(3));
+        assert_eq!(next_unreviewed_file(&files, &session, 3), Some(0));
+        session
+            .get_file_mut(files[0].display_path())
+            .unwrap()
+            .reviewed = true;
+        assert_eq!(next_unreviewed_file(&files, &session, 3), None);
+    }
+
+    #[test]
+    fn should_filter_by_file_status() {
+        // given
+        let added = make_file("new.rs", FileStatus::Added);
+        let deleted = make_file("old.rs", FileStatus::Deleted);
+        let session = make_session(&[&added, &deleted]);
+
+        // then
+        assert!(FileFilter::All.matches(&added, &session, &CodeOwners::default()));
+        assert!(FileFilter::Added.matches(&added, &session, &CodeOwners::default()));
+        assert!(!FileFilter::Added.matches(&deleted, &session, &CodeOwners::default()));
+        assert!(FileFilter::Deleted.matches(&deleted, &session, &CodeOwners::default()));
+    }
+}
+
 #[cfg(test)]
 mod scroll_tests {
     use super::*;
 
     /// Test the max_scroll_offset calculation logic directly using DiffState
     /// This tests the core algorithm without needing full App setup
-
     fn calc_max_scroll(total_lines: usize, viewport_height: usize, wrap_lines: bool) -> usize {
         let viewport = viewport_height.max(1);
         if wrap_lines {
@@ -2631,3 +6651,233 @@ mod scroll_tests {
         assert_eq!(diff_state_wrap.viewport_height, 20);
     }
 }
+
+#[cfg(test)]
+mod test_backend {
+    use super::*;
+    use crate::syntax::SyntaxHighlighter;
+    use crate::vcs::StdinBackend;
+
+    pub(super) const DIFF: &str = concat!(
+        "diff --git a/src/lib.rs b/src/lib.rs\n",
+        "index 1111111..2222222 100644\n",
+        "--- a/src/lib.rs\n",
+        "+++ b/src/lib.rs\n",
+        "@@ -1,2 +1,2 @@\n",
+        " fn main() {\n",
+        "-    old();\n",
+        "+    new();\n",
+    );
+
+    pub(super) type Staged = std::sync::Arc<std::sync::Mutex<Vec<String>>>;
+
+    /// Serves the same diff for the working tree and any commit range, and
+    /// records what is staged
+    struct FakeBackend {
+        info: VcsInfo,
+        diff: StdinBackend,
+        staged: Staged,
+    }
+
+    impl VcsBackend for FakeBackend {
+        fn info(&self) -> &VcsInfo {
+            &self.info
+        }
+
+        fn get_working_tree_diff(&self, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
+            self.diff.get_working_tree_diff(highlighter)
+        }
+
+        fn get_commit_range_diff(
+            &self,
+            _commit_ids: &[String],
+            highlighter: &SyntaxHighlighter,
+        ) -> Result<Vec<DiffFile>> {
+            self.diff.get_working_tree_diff(highlighter)
+        }
+
+        fn fetch_context_lines(
+            &self,
+            file_path: &std::path::Path,
+            file_status: FileStatus,
+            start_line: u32,
+            end_line: u32,
+        ) -> Result<Vec<DiffLine>> {
+            self.diff
+                .fetch_context_lines(file_path, file_status, start_line, end_line)
+        }
+
+        fn stage_patch(&self, patch: &str) -> Result<()> {
+            self.staged.lock().unwrap().push(patch.to_string());
+            Ok(())
+        }
+    }
+
+    /// An app reviewing `diff` in a repository that does not exist on disk
+    pub(super) fn app_reviewing(diff: &str, read_only: bool, staged: Staged) -> App {
+        let backend = FakeBackend {
+            info: VcsInfo {
+                root_path: std::env::temp_dir()
+                    .join(format!("tuicr-repo-{}", uuid::Uuid::new_v4())),
+                head_commit: "c2".to_string(),
+                branch_name: Some("main".to_string()),
+                vcs_type: VcsType::Git,
+            },
+            diff: StdinBackend::new(diff.to_string()),
+            staged,
+        };
+        App::with_backend(Box::new(backend), Theme::dark(), false, read_only).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod read_only_tests {
+    use super::test_backend::{DIFF, Staged, app_reviewing};
+    use super::*;
+    use crate::model::CommentType;
+    use crate::persistence::storage::with_test_reviews_dir;
+
+    fn read_only_app() -> App {
+        read_only_app_staging_to(Default::default())
+    }
+
+    fn read_only_app_staging_to(staged: Staged) -> App {
+        app_reviewing(DIFF, true, staged)
+    }
+
+    #[test]
+    fn should_not_save_the_session_when_reselecting_commits() {
+        // given
+        let guard = with_test_reviews_dir();
+        let mut app = read_only_app();
+        app.session
+            .get_file_mut(&PathBuf::from("src/lib.rs"))
+            .unwrap()
+            .add_line_comment(1, Comment::new("why?".to_string(), CommentType::Note, None));
+        app.dirty = true;
+        app.reselecting_commits = true;
+
+        // when
+        app.load_commit_range(vec!["c1".to_string(), "c2".to_string()], None)
+            .unwrap();
+
+        // then
+        assert_eq!(std::fs::read_dir(&guard.path).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn should_not_stage_in_read_only_mode() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let staged = std::sync::Arc::default();
+        let mut app = read_only_app_staging_to(std::sync::Arc::clone(&staged));
+        app.focused_panel = FocusedPanel::FileList;
+
+        // when
+        app.stage_at_cursor();
+
+        // then
+        assert!(staged.lock().unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod file_mark_tests {
+    use super::test_backend::app_reviewing;
+    use super::*;
+    use crate::persistence::storage::with_test_reviews_dir;
+
+    /// Three files at the root, so the file list has no directory rows
+    fn three_file_app() -> App {
+        let diff: String = ["a.rs", "b.rs", "c.rs"]
+            .iter()
+            .map(|name| {
+                format!(
+                    "diff --git a/{name} b/{name}\n\
+                     --- a/{name}\n\
+                     +++ b/{name}\n\
+                     @@ -1 +1 @@\n\
+                     -old\n\
+                     +new\n"
+                )
+            })
+            .collect();
+        app_reviewing(&diff, false, Default::default())
+    }
+
+    fn select_rows(app: &mut App, first: usize, last: usize) {
+        app.focused_panel = FocusedPanel::FileList;
+        app.file_list_state.select(first);
+        app.enter_file_visual_mode();
+        app.file_list_state.select(last);
+    }
+
+    fn listed_files(app: &App) -> Vec<String> {
+        app.build_visible_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                FileTreeItem::File { file_idx, .. } => Some(
+                    app.diff_files[file_idx]
+                        .display_path()
+                        .display()
+                        .to_string(),
+                ),
+                FileTreeItem::Directory { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_set_the_mark_on_every_selected_file() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let mut app = three_file_app();
+        app.session
+            .get_file_mut(&PathBuf::from("a.rs"))
+            .unwrap()
+            .reviewed = true;
+        select_rows(&mut app, 0, 1);
+
+        // when
+        app.mark_selected_files(FileMark::Reviewed);
+
+        // then
+        assert!(app.session.is_file_reviewed(&PathBuf::from("a.rs")));
+        assert!(app.session.is_file_reviewed(&PathBuf::from("b.rs")));
+        assert!(!app.session.is_file_reviewed(&PathBuf::from("c.rs")));
+    }
+
+    #[test]
+    fn should_undo_a_batch_mark_in_one_step() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let mut app = three_file_app();
+        select_rows(&mut app, 0, 2);
+        app.mark_selected_files(FileMark::Skipped);
+
+        // when
+        app.undo();
+
+        // then
+        assert_eq!(app.undo_stack.len(), 0);
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            assert!(!app.session.is_file_skipped(&PathBuf::from(name)));
+        }
+    }
+
+    #[test]
+    fn should_list_filtered_files_under_the_filtered_tab_only() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let mut app = three_file_app();
+        select_rows(&mut app, 0, 1);
+
+        // when
+        app.mark_selected_files(FileMark::Filtered);
+
+        // then
+        assert_eq!(listed_files(&app), ["c.rs"]);
+        app.set_file_filter(FileFilter::Filtered);
+        assert_eq!(listed_files(&app), ["a.rs", "b.rs"]);
+    }
+}
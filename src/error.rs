@@ -20,17 +20,92 @@ pub enum TuicrError {
     #[error("No comments to export - skipping copy")]
     NoComments,
 
+    #[error("No saved review for this repository")]
+    NoSavedReview,
+
     #[error("Review session corrupted: {0}")]
     CorruptedSession(String),
 
+    #[error(
+        "Review session saved by a newer tuicr (format {0}, this version reads up to {supported}) - upgrade tuicr to open it",
+        supported = crate::model::review::SESSION_VERSION
+    )]
+    SessionTooNew(String),
+
     #[error("Clipboard error: {0}")]
     Clipboard(String),
 
+    #[error("Too large for the clipboard ({0} bytes)")]
+    ClipboardTooLarge(usize),
+
     #[error("VCS command failed: {0}")]
     VcsCommand(String),
 
+    #[error("Parse error: {0}")]
+    Parse(String),
+
     #[error("Unsupported operation: {0}")]
     UnsupportedOperation(String),
+
+    #[error("Forge request failed: {0}")]
+    Forge(String),
+
+    #[error("Forge authentication failed: {0}")]
+    ForgeAuth(String),
+
+    #[error("Forge unreachable: {0}")]
+    ForgeUnreachable(String),
+
+    #[error("Suggestion conflicts with the file: {0}")]
+    SuggestionConflict(String),
+
+    #[error("Editor failed: {0}")]
+    Editor(String),
+}
+
+impl TuicrError {
+    /// The error followed by its causes, outermost first. Causes whose text
+    /// the message before already includes are left out.
+    pub fn chain(&self) -> Vec<String> {
+        let mut chain = vec![self.to_string()];
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            let text = cause.to_string();
+            if !chain.last().is_some_and(|last| last.contains(&text)) {
+                chain.push(text);
+            }
+            source = cause.source();
+        }
+        chain
+    }
 }
 
 pub type Result<T> = std::result::Result<T, TuicrError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Error)]
+    #[error("connection reset")]
+    struct Reset;
+
+    #[derive(Debug, Error)]
+    #[error("handshake failed")]
+    struct Handshake(#[source] Reset);
+
+    #[test]
+    fn should_list_the_causes_of_an_error() {
+        // given
+        let error = TuicrError::from(std::io::Error::other(Handshake(Reset)));
+
+        // when
+        let chain = error.chain();
+
+        // then
+        assert_eq!(
+            chain,
+            vec!["IO error: handshake failed", "connection reset"]
+        );
+    }
+}
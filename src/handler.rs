@@ -1,16 +1,28 @@
-use crate::app::{self, App, FileTreeItem, FocusedPanel};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::app::{self, App, FileMark, FileTreeItem, FocusedPanel};
+use crate::error::TuicrError;
 use crate::input::Action;
-use crate::output::{export_to_clipboard, generate_export_content};
-use crate::persistence::save_session;
-use crate::text_edit::{
-    delete_char_before, delete_word_before, next_char_boundary, prev_char_boundary,
+use crate::model::{CommentSort, PendingExport};
+use crate::output::bitbucket::{self, BitbucketTarget};
+use crate::output::delivery;
+use crate::output::github::{self, GitHubTarget};
+use crate::output::gitlab::{self, GitLabTarget};
+use crate::output::{
+    CLIPBOARD_LIMIT_BYTES, ExportFormat, copy_to_clipboard, generate_export_content,
+    generate_gerrit_json, generate_json_export, generate_phabricator_json, generate_rdjson_export,
+    generate_sarif_export, split_into_chunks, write_export_file,
 };
+use crate::persistence::{discard_autosave, save_session};
+use crate::theme::ThemeArg;
+use crate::vcs::WhitespaceMode;
 
 /// Export review: either to clipboard or set pending stdout output based on app.output_to_stdout.
 /// When output_to_stdout is true, stores the content and sets should_quit.
 fn handle_export(app: &mut App) {
     if app.output_to_stdout {
-        match generate_export_content(&app.session, &app.diff_source) {
+        match generate_export_content(&app.session, &app.diff_source, &app.export_options()) {
             Ok(content) => {
                 app.pending_stdout_output = Some(content);
                 app.should_quit = true;
@@ -18,96 +30,284 @@ fn handle_export(app: &mut App) {
             Err(e) => app.set_warning(format!("{e}")),
         }
     } else {
-        match export_to_clipboard(&app.session, &app.diff_source) {
-            Ok(msg) => app.set_message(msg),
+        match generate_export_content(&app.session, &app.diff_source, &app.export_options()) {
+            Ok(content) => copy_export(app, content, ExportFormat::Markdown, false),
             Err(e) => app.set_warning(format!("{e}")),
         }
     }
 }
 
-fn comment_line_start(buffer: &str, cursor: usize) -> usize {
-    let cursor = cursor.min(buffer.len());
-    match buffer[..cursor].rfind('\n') {
-        Some(pos) => pos + 1,
-        None => 0,
+/// Copy an export to the clipboard. Exports too large for the clipboard open
+/// a dialog offering to copy them in chunks or write them to a file; with
+/// `quit_after`, the app quits once that dialog is done.
+fn copy_export(app: &mut App, content: String, format: ExportFormat, quit_after: bool) {
+    match copy_to_clipboard(&content, "Review") {
+        Err(TuicrError::ClipboardTooLarge(_)) => {
+            app.large_export = Some(app::LargeExport {
+                chunks: split_into_chunks(&content, CLIPBOARD_LIMIT_BYTES),
+                content,
+                extension: format.extension(),
+                next_chunk: 0,
+                quit_after,
+            });
+            app.enter_confirm_mode(app::ConfirmAction::LargeExport);
+            return;
+        }
+        Ok(msg) => app.set_message(msg),
+        Err(e) => app.set_warning(format!("{e}")),
     }
+    app.should_quit |= quit_after;
 }
 
-fn comment_line_end(buffer: &str, cursor: usize) -> usize {
-    let cursor = cursor.min(buffer.len());
-    match buffer[cursor..].find('\n') {
-        Some(pos) => cursor + pos,
-        None => buffer.len(),
+/// Copy the next chunk of a large export, asking before each further chunk
+fn copy_next_export_chunk(app: &mut App) {
+    let Some(export) = app.large_export.as_mut() else {
+        return;
+    };
+    let total = export.chunks.len();
+    let idx = export.next_chunk;
+    export.next_chunk += 1;
+    match copy_to_clipboard(&export.chunks[idx], &format!("Chunk {}/{total}", idx + 1)) {
+        Ok(_) if idx + 1 < total => app.enter_confirm_mode(app::ConfirmAction::NextExportChunk),
+        Ok(msg) => {
+            app.set_message(msg);
+            finish_large_export(app);
+        }
+        Err(e) => {
+            app.set_warning(format!("{e}"));
+            finish_large_export(app);
+        }
     }
 }
 
-fn comment_word_left(buffer: &str, cursor: usize) -> usize {
-    let cursor = cursor.min(buffer.len());
-    if cursor == 0 {
-        return 0;
+/// Write a large export to a file instead of the clipboard
+fn write_large_export(app: &mut App) {
+    let Some(export) = app.large_export.as_ref() else {
+        return;
+    };
+    match write_export_file(&export.content, export.extension) {
+        Ok(path) => app.set_message(format!("Review written to {}", path.display())),
+        Err(e) => app.set_error(format!("Failed to write review: {e}")),
     }
-    let before = &buffer[..cursor];
-    let mut idx = 0;
-    let mut found_word = false;
-    for (pos, ch) in before.char_indices().rev() {
-        if !ch.is_whitespace() {
-            idx = pos;
-            found_word = true;
-            break;
+    finish_large_export(app);
+}
+
+fn finish_large_export(app: &mut App) {
+    let quit_after = app.large_export.take().is_some_and(|e| e.quit_after);
+    app.exit_confirm_mode();
+    app.should_quit |= quit_after;
+}
+
+/// Export the review in a specific format, to a file if `path` is given,
+/// else to stdout or the clipboard
+fn handle_export_as(app: &mut App, format: ExportFormat, path: Option<&str>) {
+    let content = match format {
+        ExportFormat::Markdown => {
+            generate_export_content(&app.session, &app.diff_source, &app.export_options())
         }
+        ExportFormat::Json => generate_json_export(&app.session, &app.export_options()),
+        ExportFormat::Phabricator => generate_phabricator_json(&app.session, &app.export_options()),
+        ExportFormat::Gerrit => generate_gerrit_json(&app.session, &app.export_options()),
+        ExportFormat::Sarif => generate_sarif_export(&app.session, &app.export_options()),
+        ExportFormat::Rdjson => generate_rdjson_export(&app.session, &app.export_options()),
+    };
+    match (content, path) {
+        (Ok(content), Some(path)) => export_to_path(app, content, path),
+        (Ok(content), None) if app.output_to_stdout => {
+            app.pending_stdout_output = Some(content);
+            app.should_quit = true;
+        }
+        (Ok(content), None) => copy_export(app, content, format, false),
+        (Err(e), _) => app.set_warning(format!("{e}")),
     }
+}
 
-    if !found_word {
-        return 0;
+/// Write an export to `path`, asking before overwriting an existing file
+fn export_to_path(app: &mut App, content: String, path: &str) {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => match directories::BaseDirs::new() {
+            Some(dirs) => dirs.home_dir().join(rest),
+            None => PathBuf::from(path),
+        },
+        None => PathBuf::from(path),
+    };
+    if path.exists() {
+        app.export_overwrite = Some((path, content));
+        app.enter_confirm_mode(app::ConfirmAction::OverwriteExport);
+        return;
     }
+    write_export(app, &path, &content);
+}
 
-    for (pos, ch) in before[..idx].char_indices().rev() {
-        if ch.is_whitespace() {
-            return pos + ch.len_utf8();
-        }
-        idx = pos;
+fn write_export(app: &mut App, path: &Path, content: &str) {
+    match std::fs::write(path, content) {
+        Ok(()) => app.set_message(format!("Review written to {}", path.display())),
+        Err(e) => app.set_error(format!("Failed to write {}: {e}", path.display())),
     }
-
-    idx
 }
 
-fn comment_word_right(buffer: &str, cursor: usize) -> usize {
-    let cursor = cursor.min(buffer.len());
-    if cursor >= buffer.len() {
-        return buffer.len();
+/// Post the review as inline comments on a Bitbucket pull request
+fn handle_bitbucket_export(app: &mut App, url: &str) {
+    let export = BitbucketTarget::from_pr_url(url).and_then(|target| {
+        bitbucket::bitbucket_export(&app.session, &target, &app.export_options())
+    });
+    match export {
+        Ok(export) => {
+            deliver_or_queue(app, export, format!("export bitbucket {url}"));
+        }
+        Err(e) => app.report_error(
+            "Bitbucket export failed",
+            &e,
+            Some(format!("export bitbucket {url}")),
+        ),
     }
+}
 
-    let mut chars = buffer[cursor..].char_indices();
-    if let Some((_, ch)) = chars.next()
-        && ch.is_whitespace()
-    {
-        for (pos, ch) in buffer[cursor..].char_indices() {
-            if !ch.is_whitespace() {
-                return cursor + pos;
+/// Post the review as a GitHub pull request review, reporting line comments
+/// that are not on the pull request diff
+fn handle_github_export(app: &mut App, url: &str) {
+    let export = GitHubTarget::from_pr_url(url)
+        .and_then(|target| github::github_export(&app.session, &target, &app.export_options()));
+    match export {
+        Ok((export, unmapped)) => {
+            if deliver_or_queue(app, export, format!("export github {url}")) && !unmapped.is_empty()
+            {
+                app.set_warning(format!(
+                    "Posted review; {} comment(s) not on the pull request diff went into the \
+                     review body: {}",
+                    unmapped.len(),
+                    unmapped.join(", ")
+                ));
             }
         }
-        return buffer.len();
+        Err(e) => app.report_error(
+            "GitHub export failed",
+            &e,
+            Some(format!("export github {url}")),
+        ),
     }
+}
 
-    let mut word_end = buffer.len();
-    for (pos, ch) in buffer[cursor..].char_indices() {
-        if ch.is_whitespace() {
-            word_end = cursor + pos;
-            break;
+fn handle_gitlab_export(app: &mut App, url: &str) {
+    let export = GitLabTarget::from_mr_url(url)
+        .and_then(|target| gitlab::gitlab_export(&app.session, &target, &app.export_options()));
+    match export {
+        Ok((export, unmapped)) => {
+            if deliver_or_queue(app, export, format!("export gitlab {url}")) && !unmapped.is_empty()
+            {
+                app.set_warning(format!(
+                    "Posted discussions; {} comment(s) not on the merge request diff were posted \
+                     unpositioned: {}",
+                    unmapped.len(),
+                    unmapped.join(", ")
+                ));
+            }
         }
+        Err(e) => app.report_error(
+            "GitLab export failed",
+            &e,
+            Some(format!("export gitlab {url}")),
+        ),
     }
+}
 
-    if word_end >= buffer.len() {
-        return buffer.len();
+/// Send a forge export; if the forge cannot be reached, keep the undelivered
+/// part in the session for `:retry-export`. Other failures offer to run the
+/// `retry` command again. Returns whether everything was posted.
+fn deliver_or_queue(app: &mut App, mut export: PendingExport, retry: String) -> bool {
+    if export.payloads.is_empty() {
+        app.set_message(format!("Nothing new to post to {}", export.host));
+        return false;
+    }
+    // A fresh export covers everything still unpublished, queued or not
+    app.session
+        .pending_exports
+        .retain(|queued| queued.endpoint != export.endpoint);
+    let result = delivery::deliver(&mut export, &mut app.session);
+    app.dirty |= !app.read_only;
+    match result {
+        Ok(count) => {
+            app.set_message(format!("Posted {count} comment(s) to {}", export.host));
+            return true;
+        }
+        Err(TuicrError::ForgeUnreachable(msg)) => {
+            let left: usize = export.payloads.iter().map(|p| p.publishes.len()).sum();
+            app.session.pending_exports.push(export);
+            app.set_warning(format!(
+                "Offline? {msg}. Queued {left} comment(s); :retry-export to send them later"
+            ));
+        }
+        Err(e) => app.report_error("Export failed", &e, Some(retry)),
     }
+    false
+}
 
-    for (pos, ch) in buffer[word_end..].char_indices() {
-        if !ch.is_whitespace() {
-            return word_end + pos;
+/// Retry every queued forge export, keeping the ones that fail again
+fn handle_retry_export(app: &mut App) {
+    if app.session.pending_exports.is_empty() {
+        app.set_message("No queued exports");
+        return;
+    }
+    let mut posted = 0;
+    let mut last_error = None;
+    let queued = std::mem::take(&mut app.session.pending_exports);
+    for mut export in queued {
+        match delivery::deliver(&mut export, &mut app.session) {
+            Ok(count) => posted += count,
+            Err(e) => {
+                last_error = Some(e);
+                app.session.pending_exports.push(export);
+            }
         }
     }
+    app.dirty |= !app.read_only;
+    match last_error {
+        None => app.set_message(format!("Posted {posted} queued comment(s)")),
+        Some(e) => {
+            let context = format!(
+                "Posted {posted} queued comment(s); {} export(s) still queued",
+                app.session.pending_exports.len()
+            );
+            app.report_error(&context, &e, Some("retry-export".to_string()));
+        }
+    }
+}
 
-    buffer.len()
+/// Copy the whole diff view as plain text, or write it to `path`
+fn handle_dump(app: &mut App, path: Option<&str>) {
+    let text = app.view_text();
+    if text.is_empty() {
+        app.set_message("Nothing to dump");
+        return;
+    }
+    if let Some(path) = path {
+        export_to_path(app, text + "\n", path);
+        return;
+    }
+    let what = format!("{} lines", text.lines().count());
+    match copy_to_clipboard(&text, &what) {
+        Ok(msg) => app.set_message(msg),
+        Err(e) => app.set_warning(format!("{e}")),
+    }
+}
+
+/// Copy the diff rows on screen (or the visual selection) as plain text
+fn handle_copy_visible(app: &mut App) {
+    let text = app.visible_region_text();
+    if text.is_empty() {
+        app.set_message("Nothing to copy");
+        return;
+    }
+    let line_count = text.lines().count();
+    let what = if line_count == 1 {
+        "1 line".to_string()
+    } else {
+        format!("{line_count} lines")
+    };
+    match copy_to_clipboard(&text, &what) {
+        Ok(msg) => app.set_message(msg),
+        Err(e) => app.set_warning(format!("{e}")),
+    }
 }
 
 /// Handle actions in Help mode (scrolling only)
@@ -129,6 +329,108 @@ pub fn handle_help_action(app: &mut App, action: Action) {
     }
 }
 
+/// Handle actions in Popup mode (read-only scrollable popups)
+pub fn handle_popup_action(app: &mut App, action: Action) {
+    let viewport = app.popup_viewport_height();
+    match action {
+        Action::CursorDown(n) | Action::MouseScrollDown(n) => app.popup_scroll_down(n),
+        Action::CursorUp(n) | Action::MouseScrollUp(n) => app.popup_scroll_up(n),
+        Action::ScrollLeft(n) => app.popup_scroll_left(n),
+        Action::ScrollRight(n) => app.popup_scroll_right(n),
+        Action::HalfPageDown => app.popup_scroll_down(viewport / 2),
+        Action::HalfPageUp => app.popup_scroll_up(viewport / 2),
+        Action::PageDown => app.popup_scroll_down(viewport),
+        Action::PageUp => app.popup_scroll_up(viewport),
+        Action::GoToTop => app.popup_scroll_up(usize::MAX),
+        Action::GoToBottom => app.popup_scroll_down(usize::MAX),
+        Action::ExitMode => app.close_popup(),
+        _ => {}
+    }
+}
+
+/// Handle the force quit key (Ctrl+C by default) in any mode. The first
+/// press cancels a comment being written and asks to press `key` again
+/// within `quit_timeout_ms`, which quits; with a zero timeout it quits at once.
+pub fn handle_force_quit(app: &mut App, key: &str) {
+    if app.input_mode == app::InputMode::Comment {
+        app.exit_comment_mode();
+    }
+    let timeout = Duration::from_millis(app.quit_timeout_ms);
+    if timeout.is_zero() || app.pending_quit.is_some_and(|at| at.elapsed() < timeout) {
+        app.should_quit = true;
+        return;
+    }
+    app.pending_quit = Some(Instant::now());
+    app.set_message(format!("Press {key} again to exit"));
+}
+
+/// Forget a first press of the force quit key once another key is pressed
+pub fn cancel_force_quit(app: &mut App) {
+    if app.pending_quit.take().is_some() {
+        app.message = None;
+    }
+}
+
+/// Forget a first press of the force quit key once its timeout passed.
+/// Returns whether the prompt was cleared.
+pub fn expire_force_quit(app: &mut App) -> bool {
+    let expired = app
+        .pending_quit
+        .is_some_and(|at| at.elapsed() >= Duration::from_millis(app.quit_timeout_ms));
+    if expired {
+        cancel_force_quit(app);
+    }
+    expired
+}
+
+/// Handle actions in the error popup: retry runs the failed command again
+pub fn handle_error_action(app: &mut App, action: Action) {
+    match action {
+        Action::RetryFailed => {
+            if let Some(cmd) = app.close_error_report(true) {
+                app.enter_command_mode();
+                app.command_buffer = cmd;
+                handle_command_action(app, Action::SubmitInput);
+            } else {
+                app.set_message("Nothing to retry");
+            }
+        }
+        Action::ShowErrorLog => {
+            app.close_error_report(false);
+            app.show_error_log();
+        }
+        Action::ExitMode => {
+            app.close_error_report(false);
+        }
+        _ => {}
+    }
+}
+
+/// Handle actions in the theme picker popup
+pub fn handle_ref_select_action(app: &mut App, action: Action) {
+    match action {
+        Action::CursorDown(_) | Action::MouseScrollDown(_) => app.ref_select_down(),
+        Action::CursorUp(_) | Action::MouseScrollUp(_) => app.ref_select_up(),
+        Action::SubmitInput => {
+            if let Err(e) = app.confirm_ref_selection() {
+                app.set_error(format!("Failed to diff against ref: {e}"));
+            }
+        }
+        Action::ExitMode => app.exit_ref_select_mode(),
+        _ => {}
+    }
+}
+
+pub fn handle_theme_picker_action(app: &mut App, action: Action) {
+    match action {
+        Action::CursorDown(n) | Action::MouseScrollDown(n) => app.theme_picker_move(n as isize),
+        Action::CursorUp(n) | Action::MouseScrollUp(n) => app.theme_picker_move(-(n as isize)),
+        Action::SubmitInput => app.close_theme_picker(true),
+        Action::ExitMode => app.close_theme_picker(false),
+        _ => {}
+    }
+}
+
 /// Handle actions in Command mode (text input for :commands)
 pub fn handle_command_action(app: &mut App, action: Action) {
     match action {
@@ -141,57 +443,360 @@ pub fn handle_command_action(app: &mut App, action: Action) {
             let cmd = app.command_buffer.trim().to_string();
             match cmd.as_str() {
                 "q" | "quit" => {
-                    if app.dirty {
+                    if app.dirty && !app.read_only {
                         app.set_error("No write since last change (add ! to override)");
                     } else {
                         app.should_quit = true;
                     }
                 }
                 "q!" | "quit!" => app.should_quit = true,
-                "w" | "write" => match save_session(&app.session) {
-                    Ok(path) => {
-                        app.dirty = false;
-                        app.set_message(format!("Saved to {}", path.display()));
+                "w" | "write" if app.read_only => {
+                    app.set_warning("Read-only session: not saving");
+                }
+                "x" | "wq" if app.read_only => app.should_quit = true,
+                "w" | "write" => {
+                    app.record_position();
+                    app.record_view_state();
+                    match save_session(&app.session) {
+                        Ok(path) => {
+                            app.dirty = false;
+                            // A journal left behind is older than the save,
+                            // so it would not be offered anyway
+                            let _ = discard_autosave(&app.session);
+                            app.set_message(format!("Saved to {}", path.display()));
+                        }
+                        Err(e) => app.set_error(format!("Save failed: {e}")),
                     }
-                    Err(e) => app.set_error(format!("Save failed: {e}")),
-                },
-                "x" | "wq" => match save_session(&app.session) {
-                    Ok(_) => {
-                        app.dirty = false;
-                        if app.session.has_comments() {
-                            if app.output_to_stdout {
-                                // Skip confirmation dialog, export directly
-                                handle_export(app);
+                }
+                "x" | "wq" => {
+                    app.record_position();
+                    app.record_view_state();
+                    match save_session(&app.session) {
+                        Ok(_) => {
+                            app.dirty = false;
+                            let _ = discard_autosave(&app.session);
+                            if app.session.has_comments() {
+                                if app.output_to_stdout {
+                                    // Skip confirmation dialog, export directly
+                                    handle_export(app);
+                                    return;
+                                }
+                                app.exit_command_mode();
+                                app.enter_confirm_mode(app::ConfirmAction::CopyAndQuit);
                                 return;
+                            } else {
+                                app.should_quit = true;
                             }
-                            app.exit_command_mode();
-                            app.enter_confirm_mode(app::ConfirmAction::CopyAndQuit);
-                            return;
-                        } else {
-                            app.should_quit = true;
                         }
+                        Err(e) => app.set_error(format!("Save failed: {e}")),
                     }
-                    Err(e) => app.set_error(format!("Save failed: {e}")),
-                },
+                }
                 "e" | "reload" => match app.reload_diff_files() {
-                    Ok(count) => app.set_message(format!("Reloaded {count} files")),
-                    Err(e) => app.set_error(format!("Reload failed: {e}")),
+                    Ok((count, invalidated)) => match app.reload_problems(invalidated) {
+                        Some(problems) => {
+                            app.set_warning(format!("Reloaded {count} files; {problems}"))
+                        }
+                        None => app.set_message(format!("Reloaded {count} files")),
+                    },
+                    Err(e) => app.report_error("Reload failed", &e, Some(cmd.clone())),
                 },
                 "clip" | "export" => handle_export(app),
+                "retry-export" => handle_retry_export(app),
+                "errors" => {
+                    app.show_error_log();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                _ if cmd.starts_with("export bitbucket") => {
+                    let url = cmd["export bitbucket".len()..].trim();
+                    if url.is_empty() {
+                        app.set_error("Usage: :export bitbucket <pull request URL>");
+                    } else {
+                        handle_bitbucket_export(app, url);
+                    }
+                }
+                _ if cmd.starts_with("export github") => {
+                    let url = cmd["export github".len()..].trim();
+                    if url.is_empty() {
+                        app.set_error("Usage: :export github <pull request URL>");
+                    } else {
+                        handle_github_export(app, url);
+                    }
+                }
+                _ if cmd.starts_with("export gitlab") => {
+                    let url = cmd["export gitlab".len()..].trim();
+                    if url.is_empty() {
+                        app.set_error("Usage: :export gitlab <merge request URL>");
+                    } else {
+                        handle_gitlab_export(app, url);
+                    }
+                }
+                _ if cmd.starts_with("export ") => {
+                    let arg = cmd["export ".len()..].trim();
+                    let (name, path) = match arg.split_once(char::is_whitespace) {
+                        Some((name, path)) => (name, Some(path.trim())),
+                        None => (arg, None),
+                    };
+                    match ExportFormat::from_name(name) {
+                        Some(format) => handle_export_as(app, format, path),
+                        None => app.set_error(format!(
+                            "Unknown export format '{name}'. Valid options: md, json, phab, gerrit, sarif, rdjson"
+                        )),
+                    }
+                }
+                "dump" => handle_dump(app, None),
+                _ if cmd.starts_with("dump ") => {
+                    handle_dump(app, Some(cmd["dump ".len()..].trim()))
+                }
                 "clear" => app.clear_all_comments(),
+                "archive" => app.archive_current_session(false),
+                "archive!" => app.archive_current_session(true),
                 "version" => {
                     app.set_message(format!("tuicr v{}", env!("CARGO_PKG_VERSION")));
                 }
+                "theme" => {
+                    app.open_theme_picker();
+                    return;
+                }
+                _ if cmd.starts_with("theme ") => {
+                    let name = cmd.trim_start_matches("theme ").trim();
+                    if app.switch_theme(name) {
+                        app.set_message(format!("Theme: {}", app.theme.name));
+                    } else {
+                        let names: Vec<&str> = ThemeArg::ALL.iter().map(|t| t.name()).collect();
+                        app.set_error(format!(
+                            "Unknown theme '{name}'. Valid options: {}",
+                            names.join(", ")
+                        ));
+                    }
+                }
+                "noh" | "nohlsearch" => app.clear_search_highlight(),
                 "set wrap" => app.set_diff_wrap(true),
                 "set wrap!" => app.toggle_diff_wrap(),
+                "set newcode" => app.set_new_code_only(true),
+                "set nonewcode" => app.set_new_code_only(false),
+                "set newcode!" => app.set_new_code_only(!app.new_code_only),
+                "set hideeol" => app.set_hide_line_endings(true),
+                "set nohideeol" => app.set_hide_line_endings(false),
+                "set hideeol!" => app.toggle_hide_line_endings(),
+                "set hiderenames" => app.set_hide_renames(true),
+                "set nohiderenames" => app.set_hide_renames(false),
+                "set hiderenames!" => app.toggle_hide_renames(),
+                "set intraline" => app.set_intraline(true),
+                "set nointraline" => app.set_intraline(false),
+                "set intraline!" => app.set_intraline(!app.intraline),
+                "set indentguides" => app.set_indent_guides(true),
+                "set noindentguides" => app.set_indent_guides(false),
+                "set indentguides!" => app.set_indent_guides(!app.indent_guides),
+                "set cursorline" => app.set_cursor_line_highlight(true),
+                "set nocursorline" => app.set_cursor_line_highlight(false),
+                "set cursorline!" => app.set_cursor_line_highlight(!app.cursor_line_highlight),
+                "set dimviewed" => app.set_dim_viewed(true),
+                "set nodimviewed" => app.set_dim_viewed(false),
+                "set dimviewed!" => app.set_dim_viewed(!app.dim_viewed),
+                "set rnu" | "set relativenumber" => app.set_relative_line_numbers(true),
+                "set nornu" | "set norelativenumber" => app.set_relative_line_numbers(false),
+                "set rnu!" | "set relativenumber!" => {
+                    app.set_relative_line_numbers(!app.relative_line_numbers)
+                }
+                "set screenreader" => app.set_screen_reader(true),
+                "set noscreenreader" => app.set_screen_reader(false),
+                "set screenreader!" => app.set_screen_reader(!app.screen_reader),
+                "set reducemotion" => app.set_reduce_motion(true),
+                "set noreducemotion" => app.set_reduce_motion(false),
+                "set reducemotion!" => app.set_reduce_motion(!app.reduce_motion),
+                "set autoreload" => app.set_autoreload(true),
+                "set noautoreload" => app.set_autoreload(false),
+                "set autoreload!" => app.set_autoreload(!app.autoreload()),
+                "set iw" => app.cycle_whitespace_mode(),
+                "set noiw" => match app.set_whitespace_mode(WhitespaceMode::Show) {
+                    Ok(()) => app.set_message(format!("Diff {}", WhitespaceMode::Show.label())),
+                    Err(e) => app.set_error(format!("{e}")),
+                },
+                "set blame" => app.set_blame_gutter(true),
+                "set noblame" => app.set_blame_gutter(false),
+                "set blame!" => app.set_blame_gutter(!app.blame_gutter),
+                "set cursorcolumn" => app.set_cursor_column_highlight(true),
+                "set nocursorcolumn" => app.set_cursor_column_highlight(false),
+                "set cursorcolumn!" => {
+                    app.set_cursor_column_highlight(!app.cursor_column_highlight)
+                }
+                "set dimunfocused" => {
+                    app.dim_when_unfocused = true;
+                    app.set_message("Dimming the UI while the terminal is unfocused");
+                }
+                "set nodimunfocused" => {
+                    app.dim_when_unfocused = false;
+                    app.set_message("No longer dimming the UI when unfocused");
+                }
                 "diff" => app.toggle_diff_view_mode(),
+                "worddiff" | "wd" => {
+                    app.show_word_diff();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "assign" => {
+                    let team = app.team_members();
+                    if team.is_empty() {
+                        app.set_message("Usage: :assign <name> (set TUICR_TEAM to list your team)");
+                    } else {
+                        app.set_message(format!("Team: {}", team.join(", ")));
+                    }
+                }
+                _ if cmd.starts_with("assign ") => {
+                    app.assign_comment(Some(cmd["assign ".len()..].trim()));
+                }
+                "unassign" => app.assign_comment(None),
+                "apply" => app.apply_suggestion_at_cursor(),
+                "unapply" => app.undo_applied_suggestion(),
+                "assignee" => app.set_comments_assignee_filter(None),
+                _ if cmd.starts_with("assignee ") => {
+                    let name = cmd["assignee ".len()..].trim().trim_start_matches('@');
+                    app.set_comments_assignee_filter(Some(name.to_string()));
+                }
+                "history" => {
+                    app.show_history();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "dups" | "duplicates" => {
+                    app.show_duplicates();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "sort" => app.set_message(format!(
+                    "Sorting comments by {} (:sort file|severity|created|resolved)",
+                    app.session.comment_sort.name()
+                )),
+                _ if cmd.starts_with("sort ") => {
+                    let name = cmd["sort ".len()..].trim();
+                    match CommentSort::from_name(name) {
+                        Some(sort) => app.set_comment_sort(sort),
+                        None => app.set_error(format!(
+                            "Unknown sort '{name}'. Valid options: file, severity, created, resolved"
+                        )),
+                    }
+                }
+                "lang" => app.set_error("Usage: :lang <language> or :lang auto"),
+                "lang auto" => app.set_file_language(None),
+                "highlight!" => app.force_highlight(),
+                _ if cmd.starts_with("lang ") => {
+                    app.set_file_language(Some(cmd["lang ".len()..].trim()));
+                }
+                "todos" => {
+                    app.show_todos();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "stats" => {
+                    app.show_stats();
+                    return;
+                }
+                "set history" => app.set_record_history(true),
+                "set nohistory" => app.set_record_history(false),
+                "map" => {
+                    app.show_keymap("");
+                    return;
+                }
+                _ if cmd.starts_with("map ") => {
+                    app.show_keymap(cmd["map ".len()..].trim());
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "structdiff" | "sd" => {
+                    app.show_structural_diff();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "copy" => handle_copy_visible(app),
+                "columns" | "cols" => {
+                    app.show_table_diff();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                _ if cmd == "ccomments" || cmd.starts_with("ccomments ") => {
+                    app.search_comments(cmd["ccomments".len()..].trim());
+                }
+                "image" | "img" => {
+                    app.show_image_preview();
+                    if app.input_mode == app::InputMode::Popup {
+                        return;
+                    }
+                }
+                "base" | "refs" => {
+                    if let Err(e) = app.enter_ref_select_mode() {
+                        app.report_error("Failed to list refs", &e, Some(cmd.clone()));
+                    } else if app.input_mode == app::InputMode::RefSelect {
+                        return;
+                    }
+                }
+                _ if cmd.starts_with("base ") => {
+                    let base = cmd["base ".len()..].trim();
+                    if let Err(e) = app.compare_against_ref(base) {
+                        let context = format!("Failed to diff against {base}");
+                        app.report_error(&context, &e, Some(cmd.clone()));
+                    }
+                }
+                "edit" => {
+                    app.exit_command_mode();
+                    if !app.enter_edit_mode() {
+                        app.set_message("No comment at cursor");
+                    } else if app.input_mode == app::InputMode::Comment {
+                        app.external_edit_requested = true;
+                    }
+                    return;
+                }
                 "commits" => {
                     if let Err(e) = app.enter_commit_select_mode() {
-                        app.set_error(format!("Failed to load commits: {e}"));
+                        app.report_error("Failed to load commits", &e, Some(cmd.clone()));
                     } else {
                         return;
                     }
                 }
+                _ if cmd.starts_with("set quittimeout=") => {
+                    match cmd["set quittimeout=".len()..].parse::<u64>() {
+                        Ok(ms) => app.set_quit_timeout(ms),
+                        Err(_) => app.set_error("Usage: :set quittimeout=<milliseconds>"),
+                    }
+                }
+                _ if cmd.starts_with("set context=") => {
+                    match cmd["set context=".len()..].parse::<usize>() {
+                        Ok(lines) => app.set_export_context_lines(lines),
+                        Err(_) => app.set_error("Usage: :set context=<lines>"),
+                    }
+                }
+                _ if cmd.starts_with("set intralinemax=") => {
+                    match cmd["set intralinemax=".len()..].parse::<usize>() {
+                        Ok(limit) => app.set_intraline_limit(limit),
+                        Err(_) => app.set_error("Usage: :set intralinemax=<characters>"),
+                    }
+                }
+                _ if cmd.starts_with("set highlightmax=") => {
+                    match cmd["set highlightmax=".len()..].parse::<usize>() {
+                        Ok(lines) => app.set_highlight_max_lines(lines),
+                        Err(_) => app.set_error("Usage: :set highlightmax=<lines>"),
+                    }
+                }
+                _ if cmd.starts_with("set indentwidth=") => {
+                    match cmd["set indentwidth=".len()..].parse::<usize>() {
+                        Ok(width) => app.set_indent_width(width),
+                        Err(_) => app.set_error("Usage: :set indentwidth=<columns>"),
+                    }
+                }
+                _ if cmd.starts_with("set timeoutlen=") => {
+                    match cmd["set timeoutlen=".len()..].parse::<u64>() {
+                        Ok(ms) => app.set_key_timeout(ms),
+                        Err(_) => app.set_error("Usage: :set timeoutlen=<milliseconds>"),
+                    }
+                }
                 _ => app.set_message(format!("Unknown command: {cmd}")),
             }
             app.exit_command_mode();
@@ -245,42 +850,24 @@ pub fn handle_search_action(app: &mut App, action: Action) {
 
 /// Handle actions in Comment mode (text input for comments)
 pub fn handle_comment_action(app: &mut App, action: Action) {
+    let editor = &mut app.comment_editor;
     match action {
-        Action::InsertChar(c) => {
-            app.comment_buffer.insert(app.comment_cursor, c);
-            app.comment_cursor += c.len_utf8();
-        }
-        Action::DeleteChar => {
-            app.comment_cursor = delete_char_before(&mut app.comment_buffer, app.comment_cursor);
-        }
+        Action::InsertChar(c) => editor.insert(c),
+        Action::DeleteChar => editor.delete_char(),
         Action::ExitMode => app.exit_comment_mode(),
         Action::SubmitInput => app.save_comment(),
         Action::CycleCommentType => app.cycle_comment_type(),
-        Action::TextCursorLeft => {
-            app.comment_cursor = prev_char_boundary(&app.comment_buffer, app.comment_cursor);
-        }
-        Action::TextCursorRight => {
-            app.comment_cursor = next_char_boundary(&app.comment_buffer, app.comment_cursor);
-        }
-        Action::TextCursorLineStart => {
-            app.comment_cursor = comment_line_start(&app.comment_buffer, app.comment_cursor);
-        }
-        Action::TextCursorLineEnd => {
-            app.comment_cursor = comment_line_end(&app.comment_buffer, app.comment_cursor);
-        }
-        Action::TextCursorWordLeft => {
-            app.comment_cursor = comment_word_left(&app.comment_buffer, app.comment_cursor);
-        }
-        Action::TextCursorWordRight => {
-            app.comment_cursor = comment_word_right(&app.comment_buffer, app.comment_cursor);
-        }
-        Action::DeleteWord => {
-            app.comment_cursor = delete_word_before(&mut app.comment_buffer, app.comment_cursor);
-        }
-        Action::ClearLine => {
-            app.comment_buffer.clear();
-            app.comment_cursor = 0;
-        }
+        Action::TextCursorLeft => editor.move_left(),
+        Action::TextCursorRight => editor.move_right(),
+        Action::TextCursorUp => editor.move_up(),
+        Action::TextCursorDown => editor.move_down(),
+        Action::TextCursorLineStart => editor.move_line_start(),
+        Action::TextCursorLineEnd => editor.move_line_end(),
+        Action::TextCursorWordLeft => editor.move_word_left(),
+        Action::TextCursorWordRight => editor.move_word_right(),
+        Action::DeleteWord => editor.delete_word(),
+        Action::ClearLine => editor.clear(),
+        Action::EditInExternalEditor => app.external_edit_requested = true,
         Action::Quit => app.should_quit = true,
         _ => {}
     }
@@ -289,18 +876,94 @@ pub fn handle_comment_action(app: &mut App, action: Action) {
 /// Handle actions in Confirm mode (Y/N prompts)
 pub fn handle_confirm_action(app: &mut App, action: Action) {
     match action {
+        Action::ConfirmYes if app.pending_confirm == Some(app::ConfirmAction::ResumeReview) => {
+            app.exit_confirm_mode();
+            app.resume_last_position();
+        }
+        Action::ConfirmNo if app.pending_confirm == Some(app::ConfirmAction::ResumeReview) => {
+            app.exit_confirm_mode();
+        }
+        Action::OpenPreviousReview | Action::ConfirmYes
+            if app.pending_confirm == Some(app::ConfirmAction::PreviousReview) =>
+        {
+            app.open_previous_review();
+        }
+        Action::DiffSincePreviousReview
+            if app.pending_confirm == Some(app::ConfirmAction::PreviousReview) =>
+        {
+            app.diff_since_previous_review();
+        }
+        Action::ArchivePreviousReview
+            if app.pending_confirm == Some(app::ConfirmAction::PreviousReview) =>
+        {
+            app.archive_previous_review();
+        }
+        Action::ConfirmNo if app.pending_confirm == Some(app::ConfirmAction::PreviousReview) => {
+            // Keep the review without jumping anywhere
+            app.exit_confirm_mode();
+            app.previous_review = None;
+        }
+        Action::ConfirmYes if app.pending_confirm == Some(app::ConfirmAction::RecoverAutosave) => {
+            app.recover_autosave();
+        }
+        Action::ConfirmNo if app.pending_confirm == Some(app::ConfirmAction::RecoverAutosave) => {
+            app.discard_autosave_recovery();
+        }
+        Action::ExportInChunks if app.pending_confirm == Some(app::ConfirmAction::LargeExport) => {
+            copy_next_export_chunk(app);
+        }
+        Action::ExportToFile if app.pending_confirm == Some(app::ConfirmAction::LargeExport) => {
+            write_large_export(app);
+        }
+        Action::ConfirmYes if app.pending_confirm == Some(app::ConfirmAction::NextExportChunk) => {
+            copy_next_export_chunk(app);
+        }
+        Action::ConfirmNo
+            if matches!(
+                app.pending_confirm,
+                Some(app::ConfirmAction::LargeExport | app::ConfirmAction::NextExportChunk)
+            ) =>
+        {
+            app.set_message("Export cancelled");
+            finish_large_export(app);
+        }
+        Action::ConfirmYes if app.pending_confirm == Some(app::ConfirmAction::LargeExport) => {}
+        Action::ConfirmYes if app.pending_confirm == Some(app::ConfirmAction::OverwriteExport) => {
+            app.exit_confirm_mode();
+            if let Some((path, content)) = app.export_overwrite.take() {
+                write_export(app, &path, &content);
+            }
+        }
+        Action::ConfirmNo if app.pending_confirm == Some(app::ConfirmAction::OverwriteExport) => {
+            app.exit_confirm_mode();
+            app.export_overwrite = None;
+            app.set_message("Export cancelled");
+        }
         Action::ConfirmYes => {
             if let Some(app::ConfirmAction::CopyAndQuit) = app.pending_confirm {
                 if app.output_to_stdout {
-                    match generate_export_content(&app.session, &app.diff_source) {
+                    match generate_export_content(
+                        &app.session,
+                        &app.diff_source,
+                        &app.export_options(),
+                    ) {
                         Ok(content) => app.pending_stdout_output = Some(content),
                         Err(e) => app.set_warning(format!("{e}")),
                     }
                 } else {
-                    match export_to_clipboard(&app.session, &app.diff_source) {
-                        Ok(msg) => app.set_message(msg),
-                        Err(e) => app.set_warning(format!("{e}")),
+                    app.exit_confirm_mode();
+                    match generate_export_content(
+                        &app.session,
+                        &app.diff_source,
+                        &app.export_options(),
+                    ) {
+                        Ok(content) => copy_export(app, content, ExportFormat::Markdown, true),
+                        Err(e) => {
+                            app.set_warning(format!("{e}"));
+                            app.should_quit = true;
+                        }
                     }
+                    return;
                 }
             }
             app.exit_confirm_mode();
@@ -352,6 +1015,10 @@ pub fn handle_commit_select_action(app: &mut App, action: Action) {
 
 /// Handle actions in VisualSelect mode
 pub fn handle_visual_action(app: &mut App, action: Action) {
+    if app.file_visual_anchor.is_some() {
+        handle_file_visual_action(app, action);
+        return;
+    }
     match action {
         Action::CursorDown(n) => {
             app.cursor_down(n);
@@ -381,6 +1048,27 @@ pub fn handle_visual_action(app: &mut App, action: Action) {
                 app.exit_visual_mode();
             }
         }
+        Action::AddSuggestion => app.enter_suggestion_from_visual(),
+        Action::Stage => app.stage_at_cursor(),
+        Action::CopyVisibleText => {
+            handle_copy_visible(app);
+            app.exit_visual_mode();
+        }
+        Action::ExitMode => app.exit_visual_mode(),
+        Action::Quit => app.should_quit = true,
+        _ => {}
+    }
+}
+
+/// Handle actions while selecting a range of files in the file list
+fn handle_file_visual_action(app: &mut App, action: Action) {
+    match action {
+        Action::CursorDown(n) => app.file_list_down(n),
+        Action::CursorUp(n) => app.file_list_up(n),
+        Action::ToggleReviewed => app.mark_selected_files(FileMark::Reviewed),
+        Action::ToggleSkipped => app.mark_selected_files(FileMark::Skipped),
+        Action::TogglePin => app.mark_selected_files(FileMark::Pinned),
+        Action::ToggleFiltered => app.mark_selected_files(FileMark::Filtered),
         Action::ExitMode => app.exit_visual_mode(),
         Action::Quit => app.should_quit = true,
         _ => {}
@@ -411,6 +1099,21 @@ pub fn handle_file_list_action(app: &mut App, action: Action) {
                 app.set_warning("Select a file to toggle reviewed");
             }
         }
+        Action::TogglePin => {
+            if let Some(FileTreeItem::File { file_idx, .. }) = app.get_selected_tree_item() {
+                app.toggle_pinned_for_file_idx(file_idx);
+            } else {
+                app.set_warning("Select a file to pin");
+            }
+        }
+        Action::ToggleSkipped => {
+            if let Some(FileTreeItem::File { file_idx, .. }) = app.get_selected_tree_item() {
+                app.toggle_skipped_for_file_idx(file_idx);
+            } else {
+                app.set_warning("Select a file to skip");
+            }
+        }
+        Action::EnterVisualMode => app.enter_file_visual_mode(),
         _ => handle_shared_normal_action(app, action),
     }
 }
@@ -424,6 +1127,10 @@ pub fn handle_diff_action(app: &mut App, action: Action) {
         Action::ScrollRight(n) => app.scroll_right(n),
         Action::MouseScrollDown(n) => app.viewport_scroll_down(n),
         Action::MouseScrollUp(n) => app.viewport_scroll_up(n),
+        Action::MatchBracket => {
+            app.jump_to_matching_bracket();
+        }
+        Action::ToggleHunkReviewed => app.toggle_hunk_reviewed(),
         Action::SelectFile => {
             // Check if cursor is on an expander line or expanded content
             if let Some((gap_id, is_expanded)) = app.get_gap_at_cursor() {
@@ -442,6 +1149,27 @@ pub fn handle_diff_action(app: &mut App, action: Action) {
     }
 }
 
+/// Handle actions when the comments panel is focused
+pub fn handle_comments_panel_action(app: &mut App, action: Action) {
+    match action {
+        Action::CursorDown(n) | Action::MouseScrollDown(n) => app.comments_panel_down(n),
+        Action::CursorUp(n) | Action::MouseScrollUp(n) => app.comments_panel_up(n),
+        Action::SelectFile => {
+            if app.jump_to_selected_comment() {
+                app.focused_panel = FocusedPanel::Diff;
+            } else {
+                app.set_message("No comment selected");
+            }
+        }
+        Action::ToggleResolved => app.toggle_selected_comment_resolved(),
+        Action::AddLineComment | Action::ToggleReviewed | Action::ReplyToComment => {
+            app.reply_to_selected_comment()
+        }
+        Action::ExitMode => app.focused_panel = FocusedPanel::Diff,
+        _ => handle_shared_normal_action(app, action),
+    }
+}
+
 /// Handle actions shared between file list and diff panels in Normal mode
 fn handle_shared_normal_action(app: &mut App, action: Action) {
     // Reset quit_warned on any non-quit action
@@ -451,7 +1179,7 @@ fn handle_shared_normal_action(app: &mut App, action: Action) {
 
     match action {
         Action::Quit => {
-            if app.dirty && !app.quit_warned {
+            if app.dirty && !app.read_only && !app.quit_warned {
                 app.set_warning("Unsaved changes. Press q again to quit.");
                 app.quit_warned = true;
             } else {
@@ -471,13 +1199,58 @@ fn handle_shared_normal_action(app: &mut App, action: Action) {
         Action::PrevFile => app.prev_file(),
         Action::NextHunk => app.next_hunk(),
         Action::PrevHunk => app.prev_hunk(),
+        Action::CenterCursor => app.center_cursor(),
+        Action::DeleteComment => {
+            let deleted = if app.focused_panel == FocusedPanel::Comments {
+                app.delete_selected_panel_comment()
+            } else {
+                app.delete_comment_at_cursor()
+            };
+            if !deleted {
+                app.set_message("No comment at cursor");
+            }
+        }
+        Action::Undo => app.undo(),
+        Action::Redo => app.redo(),
+        Action::ToggleFileList => app.toggle_file_list(),
+        Action::FocusFileList => app.focused_panel = FocusedPanel::FileList,
+        Action::FocusDiff => app.focused_panel = FocusedPanel::Diff,
+        Action::ToggleCommentsPanel => app.toggle_comments_panel(),
+        Action::CycleWhitespace => app.cycle_whitespace_mode(),
+        Action::Stage => app.stage_at_cursor(),
+        Action::ReviewAndNext => app.mark_reviewed_and_advance(),
+        Action::ShowStats => app.show_stats(),
+        Action::ReselectCommits => {
+            if let Err(e) = app.enter_commit_select_mode() {
+                app.report_error("Failed to load commits", &e, Some("commits".to_string()));
+            }
+        }
         Action::ToggleReviewed => app.toggle_reviewed(),
+        Action::ReplyToComment => {
+            if !app.reply_to_comment_at_cursor() {
+                app.set_message("Move cursor to a comment to reply to it");
+            }
+        }
+        Action::TogglePin => {
+            let file_idx = app.diff_state.current_file_idx;
+            app.toggle_pinned_for_file_idx(file_idx);
+        }
+        Action::ToggleSkipped => {
+            let file_idx = app.diff_state.current_file_idx;
+            app.toggle_skipped_for_file_idx(file_idx);
+        }
         Action::ToggleFocus => {
             app.focused_panel = match app.focused_panel {
                 FocusedPanel::FileList => FocusedPanel::Diff,
-                FocusedPanel::Diff => FocusedPanel::FileList,
+                FocusedPanel::Diff if app.show_comments_panel => FocusedPanel::Comments,
+                FocusedPanel::Diff | FocusedPanel::Comments => FocusedPanel::FileList,
             };
         }
+        Action::SelectFileFilter(idx) => {
+            if let Some(filter) = app::FileFilter::ALL.get(idx) {
+                app.set_file_filter(*filter);
+            }
+        }
         Action::ExpandAll => {
             app.expand_all_dirs();
             app.set_message("All directories expanded");
@@ -504,6 +1277,7 @@ fn handle_shared_normal_action(app: &mut App, action: Action) {
             }
         }
         Action::ExportToClipboard => handle_export(app),
+        Action::CopyVisibleText => handle_copy_visible(app),
         Action::SearchNext => {
             app.search_next_in_diff();
         }
@@ -0,0 +1,34 @@
+//! FNV-1a hashing for fingerprints that must stay stable across runs and
+//! platforms, e.g. in session file names and saved comments, which
+//! `std::hash` does not promise.
+
+/// 64-bit FNV-1a hash of `bytes`
+pub(crate) fn fnv1a_64(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a64::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Incremental form of [`fnv1a_64`], for input that is not in one buffer
+pub(crate) struct Fnv1a64(u64);
+
+impl Default for Fnv1a64 {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv1a64 {
+    const PRIME: u64 = 0x100000001b3;
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -1,4 +1,4 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
 use crate::app::InputMode;
 
@@ -17,8 +17,8 @@ pub enum Action {
     PrevFile,
     NextHunk,
     PrevHunk,
-    PendingZCommand,
-    PendingSemicolonCommand,
+    CenterCursor,
+    MatchBracket,
     ScrollLeft(usize),
     ScrollRight(usize),
     MouseScrollUp(usize),
@@ -27,23 +27,54 @@ pub enum Action {
     // Panel focus
     ToggleFocus,
     SelectFile,
+    /// Switch the file list filter tab (0-based index into `FileFilter::ALL`)
+    SelectFileFilter(usize),
+    ToggleFileList,
+    FocusFileList,
+    FocusDiff,
+    ToggleCommentsPanel,
+    ReselectCommits,
+    /// Cycle which whitespace changes the diff leaves out
+    CycleWhitespace,
 
     // Review actions
     ToggleReviewed,
+    /// Mark the file reviewed and jump to the next unreviewed one
+    ReviewAndNext,
+    /// Show review progress, comment counts and time spent
+    ShowStats,
+    /// Mark the hunk under the cursor reviewed or not
+    ToggleHunkReviewed,
+    TogglePin,
+    ToggleSkipped,
+    /// List files under the `Filtered` file list tab only
+    ToggleFiltered,
+    ToggleResolved,
+    /// Stage the hunk, file or selected lines for the next commit
+    Stage,
     AddLineComment,
     AddFileComment,
     EditComment,
-    PendingDCommand,
+    /// Reply to the thread of the comment under the cursor
+    ReplyToComment,
+    DeleteComment,
+    /// Undo the last comment or reviewed state edit
+    Undo,
+    Redo,
     SearchNext,
     SearchPrev,
 
     // Visual selection mode
     EnterVisualMode,
     AddRangeComment,
+    AddSuggestion,
 
     // Session
     Quit,
+    /// Quit without saving once pressed twice in a row, in any mode
+    ForceQuit,
     ExportToClipboard,
+    CopyVisibleText,
 
     // Mode changes
     EnterCommandMode,
@@ -59,6 +90,10 @@ pub enum Action {
     SubmitInput,
     TextCursorLeft,
     TextCursorRight,
+    TextCursorUp,
+    TextCursorDown,
+    /// Edit the comment being written in `$EDITOR`
+    EditInExternalEditor,
     TextCursorLineStart,
     TextCursorLineEnd,
     TextCursorWordLeft,
@@ -70,6 +105,21 @@ pub enum Action {
     // Confirm dialog
     ConfirmYes,
     ConfirmNo,
+    /// Copy an oversized export to the clipboard chunk by chunk
+    ExportInChunks,
+    /// Write an oversized export to a file
+    ExportToFile,
+    /// Keep reviewing in the older review of the branch
+    OpenPreviousReview,
+    /// Review what changed since the older review of the branch
+    DiffSincePreviousReview,
+    /// Archive the older review of the branch and start a new one
+    ArchivePreviousReview,
+
+    // Error popup
+    /// Run the failed operation again
+    RetryFailed,
+    ShowErrorLog,
 
     // Commit selection
     CommitSelectUp,
@@ -85,7 +135,34 @@ pub enum Action {
     None,
 }
 
+/// Filter the key events worth mapping to actions.
+///
+/// With the Kitty keyboard protocol's report-all-keys and event-type flags,
+/// terminals also report key releases and lone presses of modifier and lock
+/// keys. Those are dropped, and auto-repeats count as presses.
+pub fn normalize_key_event(key: KeyEvent) -> Option<KeyEvent> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    match key.code {
+        KeyCode::Modifier(_)
+        | KeyCode::CapsLock
+        | KeyCode::NumLock
+        | KeyCode::ScrollLock
+        | KeyCode::Media(_) => None,
+        _ => Some(KeyEvent::new_with_kind_and_state(
+            key.code,
+            key.modifiers,
+            KeyEventKind::Press,
+            key.state,
+        )),
+    }
+}
+
 pub fn map_key_to_action(key: KeyEvent, mode: InputMode) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::ForceQuit;
+    }
     match mode {
         InputMode::Normal => map_normal_mode(key),
         InputMode::Command => map_command_mode(key),
@@ -95,6 +172,10 @@ pub fn map_key_to_action(key: KeyEvent, mode: InputMode) -> Action {
         InputMode::Confirm => map_confirm_mode(key),
         InputMode::CommitSelect => map_commit_select_mode(key),
         InputMode::VisualSelect => map_visual_mode(key),
+        InputMode::Popup => map_popup_mode(key),
+        InputMode::ThemePicker => map_theme_picker_mode(key),
+        InputMode::RefSelect => map_ref_select_mode(key),
+        InputMode::Error => map_error_mode(key),
     }
 }
 
@@ -111,8 +192,6 @@ fn map_normal_mode(key: KeyEvent) -> Action {
         (KeyCode::PageUp, KeyModifiers::NONE) => Action::PageUp,
         (KeyCode::Char('g'), KeyModifiers::NONE) => Action::GoToTop,
         (KeyCode::Char('G'), _) => Action::GoToBottom,
-        (KeyCode::Char('z'), KeyModifiers::NONE) => Action::PendingZCommand,
-        (KeyCode::Char(';'), _) => Action::PendingSemicolonCommand,
 
         // File navigation (use _ for modifiers since shift is implicit in the character)
         (KeyCode::Char('}'), _) => Action::NextFile,
@@ -124,20 +203,35 @@ fn map_normal_mode(key: KeyEvent) -> Action {
         (KeyCode::Tab, KeyModifiers::NONE) => Action::ToggleFocus,
         (KeyCode::Enter, KeyModifiers::NONE) => Action::SelectFile,
 
+        // File list filter tabs
+        (KeyCode::Char(c @ '1'..='7'), KeyModifiers::NONE) => {
+            Action::SelectFileFilter(c as usize - '1' as usize)
+        }
+
         // Horizontal scrolling
         (KeyCode::Char('h') | KeyCode::Left, KeyModifiers::NONE) => Action::ScrollLeft(4),
         (KeyCode::Char('l') | KeyCode::Right, KeyModifiers::NONE) => Action::ScrollRight(4),
 
         // Review actions
         (KeyCode::Char('r'), KeyModifiers::NONE) => Action::ToggleReviewed,
+        (KeyCode::Char('a'), KeyModifiers::NONE) => Action::ReviewAndNext,
+        (KeyCode::Char('m'), KeyModifiers::NONE) => Action::ToggleHunkReviewed,
+        (KeyCode::Char('p'), KeyModifiers::NONE) => Action::TogglePin,
+        (KeyCode::Char('x'), KeyModifiers::NONE) => Action::ToggleSkipped,
+        (KeyCode::Char('R'), _) => Action::ToggleResolved,
+        (KeyCode::Char('s'), KeyModifiers::NONE) => Action::Stage,
         (KeyCode::Char('c'), KeyModifiers::NONE) => Action::AddLineComment,
         (KeyCode::Char('C'), _) => Action::AddFileComment,
         (KeyCode::Char('i'), KeyModifiers::NONE) => Action::EditComment,
-        (KeyCode::Char('d'), KeyModifiers::NONE) => Action::PendingDCommand,
+        (KeyCode::Char('A'), _) => Action::ReplyToComment,
+        (KeyCode::Char('u'), KeyModifiers::NONE) => Action::Undo,
+        (KeyCode::Char('r'), KeyModifiers::CONTROL) => Action::Redo,
         (KeyCode::Char('v') | KeyCode::Char('V'), _) => Action::EnterVisualMode,
         (KeyCode::Char('y'), KeyModifiers::NONE) => Action::ExportToClipboard,
+        (KeyCode::Char('Y'), _) => Action::CopyVisibleText,
         (KeyCode::Char('n'), KeyModifiers::NONE) => Action::SearchNext,
         (KeyCode::Char('N'), _) => Action::SearchPrev,
+        (KeyCode::Char('%'), _) => Action::MatchBracket,
 
         // Mode changes (use _ for shifted characters like : and ?)
         (KeyCode::Char(':'), _) => Action::EnterCommandMode,
@@ -182,21 +276,20 @@ fn map_search_mode(key: KeyEvent) -> Action {
 
 fn map_comment_mode(key: KeyEvent) -> Action {
     match (key.code, key.modifiers) {
-        // Cancel: Esc, Ctrl+C
+        // Cancel: Esc (Ctrl+C goes through ForceQuit)
         (KeyCode::Esc, KeyModifiers::NONE) => Action::ExitMode,
-        (KeyCode::Char('c'), KeyModifiers::CONTROL) => Action::ExitMode,
-        // Submit: Enter without shift (Ctrl+Enter and Ctrl+S also work)
-        (KeyCode::Enter, KeyModifiers::NONE) => Action::SubmitInput,
+        // Submit: Ctrl+S, or Ctrl+Enter in terminals that report it
         (KeyCode::Enter, KeyModifiers::CONTROL) => Action::SubmitInput,
         (KeyCode::Char('s'), KeyModifiers::CONTROL) => Action::SubmitInput,
-        // Newline: Shift+Enter (modern terminals) or Ctrl+J (universal fallback)
+        // Newline: Enter (Shift+Enter and Ctrl+J also work)
+        (KeyCode::Enter, KeyModifiers::NONE) => Action::InsertChar('\n'),
         (KeyCode::Enter, mods) if mods.contains(KeyModifiers::SHIFT) => Action::InsertChar('\n'),
         (KeyCode::Char('j'), KeyModifiers::CONTROL) => Action::InsertChar('\n'),
         // Comment type: Tab to cycle
         (KeyCode::Tab, KeyModifiers::NONE) => Action::CycleCommentType,
         // Cursor movement
         (KeyCode::Char('a'), KeyModifiers::CONTROL) => Action::TextCursorLineStart,
-        (KeyCode::Char('e'), KeyModifiers::CONTROL) => Action::TextCursorLineEnd,
+        (KeyCode::Char('e'), KeyModifiers::CONTROL) => Action::EditInExternalEditor,
         (KeyCode::Left, mods)
             if mods.contains(KeyModifiers::ALT) || mods.contains(KeyModifiers::CONTROL) =>
         {
@@ -221,6 +314,8 @@ fn map_comment_mode(key: KeyEvent) -> Action {
         }
         (KeyCode::Left, KeyModifiers::NONE) => Action::TextCursorLeft,
         (KeyCode::Right, KeyModifiers::NONE) => Action::TextCursorRight,
+        (KeyCode::Up, KeyModifiers::NONE) => Action::TextCursorUp,
+        (KeyCode::Down, KeyModifiers::NONE) => Action::TextCursorDown,
         // Editing
         (KeyCode::Backspace, mods)
             if mods.contains(KeyModifiers::SUPER) || mods.contains(KeyModifiers::META) =>
@@ -235,6 +330,47 @@ fn map_comment_mode(key: KeyEvent) -> Action {
     }
 }
 
+fn map_popup_mode(key: KeyEvent) -> Action {
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, KeyModifiers::NONE) | (KeyCode::Char('q'), KeyModifiers::NONE) => {
+            Action::ExitMode
+        }
+        (KeyCode::Char('j') | KeyCode::Down, KeyModifiers::NONE) => Action::CursorDown(1),
+        (KeyCode::Char('k') | KeyCode::Up, KeyModifiers::NONE) => Action::CursorUp(1),
+        (KeyCode::Char('h') | KeyCode::Left, KeyModifiers::NONE) => Action::ScrollLeft(4),
+        (KeyCode::Char('l') | KeyCode::Right, KeyModifiers::NONE) => Action::ScrollRight(4),
+        (KeyCode::Char('d'), KeyModifiers::CONTROL) => Action::HalfPageDown,
+        (KeyCode::Char('u'), KeyModifiers::CONTROL) => Action::HalfPageUp,
+        (KeyCode::Char('f'), KeyModifiers::CONTROL) => Action::PageDown,
+        (KeyCode::Char('b'), KeyModifiers::CONTROL) => Action::PageUp,
+        (KeyCode::PageDown, KeyModifiers::NONE) => Action::PageDown,
+        (KeyCode::PageUp, KeyModifiers::NONE) => Action::PageUp,
+        (KeyCode::Char('g'), KeyModifiers::NONE) => Action::GoToTop,
+        (KeyCode::Char('G'), _) => Action::GoToBottom,
+        _ => Action::None,
+    }
+}
+
+fn map_theme_picker_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Action::CursorDown(1),
+        KeyCode::Char('k') | KeyCode::Up => Action::CursorUp(1),
+        KeyCode::Enter => Action::SubmitInput,
+        KeyCode::Esc | KeyCode::Char('q') => Action::ExitMode,
+        _ => Action::None,
+    }
+}
+
+fn map_ref_select_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Action::CursorDown(1),
+        KeyCode::Char('k') | KeyCode::Up => Action::CursorUp(1),
+        KeyCode::Enter => Action::SubmitInput,
+        KeyCode::Esc | KeyCode::Char('q') => Action::ExitMode,
+        _ => Action::None,
+    }
+}
+
 fn map_help_mode(key: KeyEvent) -> Action {
     match (key.code, key.modifiers) {
         // Close help
@@ -260,6 +396,20 @@ fn map_confirm_mode(key: KeyEvent) -> Action {
     match key.code {
         KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Action::ConfirmYes,
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Action::ConfirmNo,
+        KeyCode::Char('c') | KeyCode::Char('C') => Action::ExportInChunks,
+        KeyCode::Char('f') | KeyCode::Char('F') => Action::ExportToFile,
+        KeyCode::Char('o') | KeyCode::Char('O') => Action::OpenPreviousReview,
+        KeyCode::Char('d') | KeyCode::Char('D') => Action::DiffSincePreviousReview,
+        KeyCode::Char('a') | KeyCode::Char('A') => Action::ArchivePreviousReview,
+        _ => Action::None,
+    }
+}
+
+fn map_error_mode(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('r') | KeyCode::Char('R') => Action::RetryFailed,
+        KeyCode::Char('l') | KeyCode::Char('L') => Action::ShowErrorLog,
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => Action::ExitMode,
         _ => Action::None,
     }
 }
@@ -284,6 +434,17 @@ fn map_visual_mode(key: KeyEvent) -> Action {
         // Create range comment
         (KeyCode::Char('c'), KeyModifiers::NONE) => Action::AddRangeComment,
         (KeyCode::Enter, KeyModifiers::NONE) => Action::AddRangeComment,
+        // Suggest a replacement for the selected lines
+        (KeyCode::Char('s'), KeyModifiers::NONE) => Action::AddSuggestion,
+        // Stage the selected changed lines
+        (KeyCode::Char('S'), _) => Action::Stage,
+        // Copy selected lines as plain text
+        (KeyCode::Char('y'), KeyModifiers::NONE) => Action::CopyVisibleText,
+        // Mark the selected files in the file list
+        (KeyCode::Char('r'), KeyModifiers::NONE) => Action::ToggleReviewed,
+        (KeyCode::Char('x'), KeyModifiers::NONE) => Action::ToggleSkipped,
+        (KeyCode::Char('p'), KeyModifiers::NONE) => Action::TogglePin,
+        (KeyCode::Char('f'), KeyModifiers::NONE) => Action::ToggleFiltered,
         // Cancel selection
         (KeyCode::Esc, KeyModifiers::NONE) => Action::ExitMode,
         (KeyCode::Char('v') | KeyCode::Char('V'), _) => Action::ExitMode,
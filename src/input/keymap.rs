@@ -0,0 +1,263 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::InputMode;
+
+use super::keybindings::Action;
+use super::keymap_file::{Keymap, action_name, format_keys, mode_name};
+
+/// Modes listed in the keymap, in display order
+pub(super) const MODES: &[(InputMode, &str)] = &[
+    (InputMode::Normal, "Normal"),
+    (InputMode::VisualSelect, "Visual"),
+    (InputMode::Comment, "Comment"),
+    (InputMode::Command, "Command"),
+    (InputMode::Search, "Search"),
+    (InputMode::CommitSelect, "Commit select"),
+    (InputMode::RefSelect, "Ref select"),
+    (InputMode::ThemePicker, "Theme picker"),
+    (InputMode::Popup, "Popup"),
+    (InputMode::Help, "Help"),
+    (InputMode::Confirm, "Confirm"),
+    (InputMode::Error, "Error"),
+];
+
+const SPECIAL_KEYS: &[(KeyCode, &str)] = &[
+    (KeyCode::Esc, "Esc"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::BackTab, "Shift-Tab"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+];
+
+/// All keys that trigger one action in a mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub keys: Vec<String>,
+    pub action: String,
+    /// Name of the action in the keymap file
+    pub name: String,
+}
+
+/// The bindings active in one input mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeKeymap {
+    pub mode: &'static str,
+    pub bindings: Vec<KeyBinding>,
+}
+
+impl ModeKeymap {
+    /// Keep only bindings whose keys or action contain `query` (case-insensitive)
+    pub fn filtered(&self, query: &str) -> Self {
+        let query = query.to_lowercase();
+        let bindings = self
+            .bindings
+            .iter()
+            .filter(|b| {
+                b.action.to_lowercase().contains(&query)
+                    || b.keys.iter().any(|k| k.to_lowercase().contains(&query))
+            })
+            .cloned()
+            .collect();
+        Self {
+            mode: self.mode,
+            bindings,
+        }
+    }
+}
+
+/// Build the keymap that is in effect, per mode.
+///
+/// Single-key bindings are discovered by feeding every candidate key through
+/// `keymap`, so the listing always matches what a key press does.
+pub fn effective_keymap(keymap: &Keymap) -> Vec<ModeKeymap> {
+    MODES
+        .iter()
+        .map(|&(mode, name)| {
+            let mut bindings: Vec<KeyBinding> = Vec::new();
+            for (key, label) in candidate_keys() {
+                let Some(action) = keymap.action_for(mode, key) else {
+                    continue;
+                };
+                // Patterns that ignore modifiers would list every modified variant
+                let shifted_char =
+                    matches!(key.code, KeyCode::Char(_)) && key.modifiers == KeyModifiers::SHIFT;
+                if !key.modifiers.is_empty()
+                    && !shifted_char
+                    && keymap.action_for(mode, KeyEvent::new(key.code, KeyModifiers::NONE))
+                        == Some(action.clone())
+                {
+                    continue;
+                }
+                add_binding(&mut bindings, &action, label);
+            }
+            for (keys, action) in keymap.sequences(mode) {
+                add_binding(&mut bindings, action, format_keys(keys));
+            }
+            ModeKeymap {
+                mode: name,
+                bindings,
+            }
+        })
+        .collect()
+}
+
+fn add_binding(bindings: &mut Vec<KeyBinding>, action: &Action, label: String) {
+    let Some(action_text) = action_label(action) else {
+        return;
+    };
+    match bindings.iter_mut().find(|b| b.action == action_text) {
+        Some(binding) => {
+            if !binding.keys.contains(&label) {
+                binding.keys.push(label);
+            }
+        }
+        None => bindings.push(KeyBinding {
+            keys: vec![label],
+            action: action_text,
+            name: action_name(action),
+        }),
+    }
+}
+
+/// Render the keymap as Markdown tables, one per mode
+pub fn keymap_markdown(keymap: &[ModeKeymap]) -> String {
+    let mut out = String::from("# tuicr keymap\n");
+    for mode in keymap.iter().filter(|m| !m.bindings.is_empty()) {
+        out.push_str(&format!(
+            "\n## {} (`[{}]`)\n\n| Keys | Action | Name |\n|------|--------|------|\n",
+            mode.mode,
+            mode_name(mode.mode)
+        ));
+        for binding in &mode.bindings {
+            let keys: Vec<String> = binding.keys.iter().map(|k| markdown_code(k)).collect();
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                keys.join(", "),
+                binding.action,
+                markdown_code(&binding.name)
+            ));
+        }
+    }
+    out
+}
+
+fn markdown_code(key: &str) -> String {
+    let key = key.replace('|', "\\|");
+    if key.contains('`') {
+        format!("`` {key} ``")
+    } else {
+        format!("`{key}`")
+    }
+}
+
+fn candidate_keys() -> Vec<(KeyEvent, String)> {
+    let mut keys = Vec::new();
+    // Shifted characters arrive as uppercase, so Shift needs no label of its own
+    for ch in ' '..='~' {
+        let label = if ch == ' ' {
+            "Space".to_string()
+        } else {
+            ch.to_string()
+        };
+        for modifiers in [KeyModifiers::NONE, KeyModifiers::SHIFT] {
+            keys.push((KeyEvent::new(KeyCode::Char(ch), modifiers), label.clone()));
+        }
+    }
+    for (prefix, modifiers) in [
+        ("Ctrl-", KeyModifiers::CONTROL),
+        ("Alt-", KeyModifiers::ALT),
+    ] {
+        for ch in ('a'..='z').chain(['[', ']']) {
+            keys.push((
+                KeyEvent::new(KeyCode::Char(ch), modifiers),
+                format!("{prefix}{ch}"),
+            ));
+        }
+    }
+    for (prefix, modifiers) in [
+        ("", KeyModifiers::NONE),
+        ("Shift-", KeyModifiers::SHIFT),
+        ("Ctrl-", KeyModifiers::CONTROL),
+        ("Alt-", KeyModifiers::ALT),
+    ] {
+        for &(code, name) in SPECIAL_KEYS {
+            keys.push((KeyEvent::new(code, modifiers), format!("{prefix}{name}")));
+        }
+    }
+    keys
+}
+
+/// Human-readable name of an action, or `None` for actions not worth listing
+/// (typing text and unbound keys)
+fn action_label(action: &Action) -> Option<String> {
+    match action {
+        Action::None | Action::InsertChar(_) => return None,
+        _ => {}
+    }
+    // "CursorDown(1)" -> "Cursor down (1)"
+    let debug = format!("{action:?}");
+    let mut label = String::new();
+    for (idx, ch) in debug.chars().enumerate() {
+        if idx > 0 && ch.is_ascii_uppercase() {
+            label.push(' ');
+            label.push(ch.to_ascii_lowercase());
+        } else if ch == '(' {
+            label.push_str(" (");
+        } else {
+            label.push(ch);
+        }
+    }
+    Some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normal_binding<'a>(keymap: &'a [ModeKeymap], action: &str) -> Option<&'a KeyBinding> {
+        keymap
+            .iter()
+            .find(|m| m.mode == "Normal")
+            .and_then(|m| m.bindings.iter().find(|b| b.action == action))
+    }
+
+    #[test]
+    fn should_group_keys_bound_to_the_same_action() {
+        // when
+        let keymap = effective_keymap(&Keymap::default());
+
+        // then
+        let binding = normal_binding(&keymap, "Cursor down (1)").unwrap();
+        assert!(binding.keys.contains(&"j".to_string()));
+        assert!(binding.keys.contains(&"Down".to_string()));
+        assert!(normal_binding(&keymap, "Center cursor").is_some());
+    }
+
+    #[test]
+    fn should_filter_bindings_by_key_or_action() {
+        // given
+        let normal = effective_keymap(&Keymap::default()).remove(0);
+
+        // when
+        let filtered = normal.filtered("CENTER");
+
+        // then
+        assert_eq!(filtered.bindings.len(), 1);
+        assert_eq!(filtered.bindings[0].keys, vec!["zz".to_string()]);
+    }
+
+    #[test]
+    fn should_escape_pipes_and_backticks_in_markdown() {
+        assert_eq!(markdown_code("|"), "`\\|`");
+        assert_eq!(markdown_code("`"), "`` ` ``");
+    }
+}
@@ -0,0 +1,695 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use toml::Spanned;
+
+use crate::app::InputMode;
+use crate::error::{Result, TuicrError};
+
+use super::keybindings::{Action, map_key_to_action};
+use super::keymap::MODES;
+
+/// Multi-key sequences bound out of the box
+const DEFAULT_SEQUENCES: &[(InputMode, &str, Action)] = &[
+    (InputMode::Normal, "zz", Action::CenterCursor),
+    (InputMode::Normal, "dd", Action::DeleteComment),
+    (InputMode::Normal, ";e", Action::ToggleFileList),
+    (InputMode::Normal, ";h", Action::FocusFileList),
+    (InputMode::Normal, ";l", Action::FocusDiff),
+    (InputMode::Normal, ";c", Action::ToggleCommentsPanel),
+    (InputMode::Normal, ";s", Action::ReselectCommits),
+    (InputMode::Normal, ";w", Action::CycleWhitespace),
+    (InputMode::Normal, ";p", Action::ShowStats),
+];
+
+/// Named keys accepted inside `<...>`, matched case-insensitively
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("esc", KeyCode::Esc),
+    ("enter", KeyCode::Enter),
+    ("cr", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("bs", KeyCode::Backspace),
+    ("backspace", KeyCode::Backspace),
+    ("del", KeyCode::Delete),
+    ("delete", KeyCode::Delete),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+];
+
+/// The keymap file: a table per mode, mapping key notation to action names
+type KeymapTables = HashMap<Spanned<String>, HashMap<Spanned<String>, Spanned<String>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Binding {
+    mode: InputMode,
+    keys: Vec<KeyEvent>,
+    action: Action,
+}
+
+/// Key bindings in effect: the built-in ones of `map_key_to_action`, the
+/// default multi-key sequences, and the user's keymap file on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    /// Sequences and single-key overrides; single keys bound to
+    /// `Action::None` are unbound
+    bindings: Vec<Binding>,
+}
+
+enum Resolution {
+    Action(Action),
+    /// The keys are the start of a longer sequence
+    Pending,
+    Unmatched,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+        for (mode, keys, action) in DEFAULT_SEQUENCES {
+            let keys = parse_keys(keys).expect("default sequences are valid");
+            keymap.bind(*mode, keys, action.clone());
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Load `keymap.toml` from the config directory on top of the defaults.
+    /// A missing file is not an error; an invalid one reports every problem.
+    pub fn load() -> Result<Self> {
+        match keymap_path() {
+            Some(path) if path.exists() => Self::load_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn load_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let (keymap, errors) = Self::parse(&text);
+        if errors.is_empty() {
+            return Ok(keymap);
+        }
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|e| format!("{}:{e}", path.display()))
+            .collect();
+        Err(TuicrError::Parse(format!(
+            "invalid keymap\n{}",
+            errors.join("\n")
+        )))
+    }
+
+    /// Parse a keymap file: one `[mode]` table per input mode, mapping key
+    /// notation to action names, e.g.
+    ///
+    /// ```toml
+    /// [normal]
+    /// "<C-n>" = "next_file"
+    /// gg = "go_to_top"
+    /// zz = "none"        # unbind
+    /// J = "cursor_down(5)"
+    /// ```
+    ///
+    /// Invalid entries are skipped and reported as `line: message`; a file
+    /// that is not valid TOML is reported as a whole.
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let line_of = |offset: usize| text[..offset].matches('\n').count() + 1;
+        let tables: KeymapTables = match toml::from_str(text) {
+            Ok(tables) => tables,
+            Err(e) => {
+                let line = e.span().map_or(1, |span| line_of(span.start));
+                return (keymap, vec![format!("{line}: {}", e.message())]);
+            }
+        };
+
+        // In file order, so problems are reported the way they are read
+        let mut tables: Vec<_> = tables.into_iter().collect();
+        tables.sort_by_key(|(name, _)| name.span().start);
+        let mut errors = Vec::new();
+        let mut user_keys: Vec<(InputMode, Vec<KeyEvent>, usize)> = Vec::new();
+        for (name, entries) in tables {
+            let Some(mode) = mode_from_name(name.get_ref()) else {
+                errors.push(format!(
+                    "{}: unknown mode [{}]",
+                    line_of(name.span().start),
+                    name.get_ref()
+                ));
+                continue;
+            };
+            let mut entries: Vec<_> = entries.into_iter().collect();
+            entries.sort_by_key(|(notation, _)| notation.span().start);
+            for (notation, action_name) in entries {
+                let line_no = line_of(notation.span().start);
+                let entry = parse_keys(notation.get_ref()).and_then(|keys| {
+                    let action = parse_action(action_name.get_ref())
+                        .ok_or_else(|| format!("unknown action '{}'", action_name.get_ref()))?;
+                    Ok((keys, action))
+                });
+                let (keys, action) = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        errors.push(format!("{line_no}: {e}"));
+                        continue;
+                    }
+                };
+                if user_keys.iter().any(|(m, k, _)| *m == mode && *k == keys) {
+                    errors.push(format!("{line_no}: {} is bound twice", format_keys(&keys)));
+                    continue;
+                }
+                user_keys.push((mode, keys.clone(), line_no));
+                keymap.bind(mode, keys, action);
+            }
+        }
+
+        // A key that is bound and also starts a sequence could never fire
+        for (mode, keys, line_no) in &user_keys {
+            let bound = keymap
+                .bindings
+                .iter()
+                .any(|b| b.mode == *mode && b.keys == *keys && b.action != Action::None);
+            let longer = keymap
+                .bindings
+                .iter()
+                .find(|b| b.mode == *mode && b.keys.len() > keys.len() && b.keys.starts_with(keys));
+            if bound && let Some(longer) = longer {
+                errors.push(format!(
+                    "{line_no}: {} is also the start of {}; unbind that sequence first",
+                    format_keys(keys),
+                    format_keys(&longer.keys)
+                ));
+            }
+        }
+        (keymap, errors)
+    }
+
+    /// Add or replace a binding
+    fn bind(&mut self, mode: InputMode, keys: Vec<KeyEvent>, action: Action) {
+        self.bindings
+            .retain(|b| !(b.mode == mode && b.keys == keys));
+        // Unbound sequences simply disappear; unbound single keys must
+        // shadow the built-in mapping
+        if keys.len() == 1 || action != Action::None {
+            self.bindings.push(Binding { mode, keys, action });
+        }
+    }
+
+    fn resolve(&self, mode: InputMode, keys: &[KeyEvent]) -> Resolution {
+        let normalized: Vec<KeyEvent> = keys.iter().map(|k| normalize(*k)).collect();
+        let in_mode = || self.bindings.iter().filter(|b| b.mode == mode);
+        if let Some(binding) = in_mode().find(|b| b.keys == normalized) {
+            return Resolution::Action(binding.action.clone());
+        }
+        if in_mode().any(|b| b.keys.len() > normalized.len() && b.keys.starts_with(&normalized)) {
+            return Resolution::Pending;
+        }
+        match keys {
+            [key] => Resolution::Action(map_key_to_action(*key, mode)),
+            _ => Resolution::Unmatched,
+        }
+    }
+
+    /// Add a key press to the keys typed so far (`pending`). Returns the
+    /// action once a binding is complete, or None while `pending` is the start
+    /// of a sequence. A prefix that does not continue into a sequence is
+    /// dropped and the key handled on its own.
+    pub fn feed(
+        &self,
+        mode: InputMode,
+        pending: &mut Vec<KeyEvent>,
+        key: KeyEvent,
+    ) -> Option<Action> {
+        pending.push(key);
+        loop {
+            match self.resolve(mode, pending) {
+                Resolution::Pending => return None,
+                Resolution::Action(action) => {
+                    pending.clear();
+                    return Some(action);
+                }
+                Resolution::Unmatched => {
+                    pending.clear();
+                    pending.push(key);
+                }
+            }
+        }
+    }
+
+    /// Action of a single key press, or None if the key starts a sequence
+    pub fn action_for(&self, mode: InputMode, key: KeyEvent) -> Option<Action> {
+        match self.resolve(mode, &[key]) {
+            Resolution::Action(action) => Some(action),
+            Resolution::Pending | Resolution::Unmatched => None,
+        }
+    }
+
+    /// Multi-key sequences bound in `mode`
+    pub fn sequences(&self, mode: InputMode) -> impl Iterator<Item = (&[KeyEvent], &Action)> {
+        self.bindings
+            .iter()
+            .filter(move |b| b.mode == mode && b.keys.len() > 1)
+            .map(|b| (b.keys.as_slice(), &b.action))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tuicr/keymap.toml`, defaulting to `~/.config`
+pub fn keymap_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".config")))?;
+    Some(config_dir.join("tuicr").join("keymap.toml"))
+}
+
+/// Section name of a mode in the keymap file, e.g. `commit_select`
+pub fn mode_name(label: &str) -> String {
+    label.to_lowercase().replace(' ', "_")
+}
+
+fn mode_from_name(name: &str) -> Option<InputMode> {
+    MODES
+        .iter()
+        .find(|(_, label)| mode_name(label) == name)
+        .map(|(mode, _)| *mode)
+}
+
+/// Parse vim-style key notation: characters stand for themselves and special
+/// or modified keys go in angle brackets, e.g. `zz`, `<C-d>`, `<S-Tab>` or
+/// `g<Enter>`. `<lt>` is a literal `<`.
+pub fn parse_keys(notation: &str) -> std::result::Result<Vec<KeyEvent>, String> {
+    let mut keys = Vec::new();
+    let mut rest = notation;
+    while let Some(ch) = rest.chars().next() {
+        if ch == '<'
+            && let Some(end) = rest.find('>')
+            && end > 1
+        {
+            keys.push(parse_bracketed_key(&rest[1..end])?);
+            rest = &rest[end + 1..];
+        } else {
+            keys.push(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    if keys.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(keys)
+}
+
+fn parse_bracketed_key(inner: &str) -> std::result::Result<KeyEvent, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = inner;
+    while let Some((prefix, rest)) = name.split_once('-')
+        && !rest.is_empty()
+    {
+        modifiers |= match prefix.to_ascii_lowercase().as_str() {
+            "c" | "ctrl" => KeyModifiers::CONTROL,
+            "a" | "m" | "alt" => KeyModifiers::ALT,
+            "s" | "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!("unknown modifier '{prefix}' in <{inner}>")),
+        };
+        name = rest;
+    }
+    let lower = name.to_ascii_lowercase();
+    let code = if let Some((_, code)) = NAMED_KEYS.iter().find(|(n, _)| *n == lower) {
+        *code
+    } else {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) if modifiers.contains(KeyModifiers::CONTROL) => {
+                KeyCode::Char(ch.to_ascii_lowercase())
+            }
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => return Err(format!("unknown key <{inner}>")),
+        }
+    };
+    Ok(normalize(KeyEvent::new(code, modifiers)))
+}
+
+/// Shifted characters arrive as uppercase, with or without the Shift flag
+/// depending on the terminal: compare them without it
+fn normalize(key: KeyEvent) -> KeyEvent {
+    let modifiers = match key.code {
+        KeyCode::Char(_) => key.modifiers.difference(KeyModifiers::SHIFT),
+        _ => key.modifiers,
+    };
+    KeyEvent::new(key.code, modifiers)
+}
+
+/// Render keys back in the notation `parse_keys` accepts
+pub fn format_keys(keys: &[KeyEvent]) -> String {
+    keys.iter().map(|key| format_key(*key)).collect()
+}
+
+fn format_key(key: KeyEvent) -> String {
+    let name = match key.code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char('<') => "lt".to_string(),
+        KeyCode::Char(ch) if key.modifiers.is_empty() => return ch.to_string(),
+        KeyCode::Char(ch) => ch.to_string(),
+        KeyCode::Backspace => "BS".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        KeyCode::PageUp => "PageUp".to_string(),
+        KeyCode::PageDown => "PageDown".to_string(),
+        code => format!("{code:?}"),
+    };
+    let mut out = String::from("<");
+    for (flag, prefix) in [
+        (KeyModifiers::CONTROL, "C-"),
+        (KeyModifiers::ALT, "A-"),
+        (KeyModifiers::SHIFT, "S-"),
+    ] {
+        if key.modifiers.contains(flag) {
+            out.push_str(prefix);
+        }
+    }
+    out.push_str(&name);
+    out.push('>');
+    out
+}
+
+/// Name of an action in the keymap file, e.g. `cursor_down(1)`
+pub fn action_name(action: &Action) -> String {
+    let debug = format!("{action:?}");
+    let mut name = String::new();
+    for (idx, ch) in debug.char_indices() {
+        if ch == '(' {
+            name.push_str(&debug[idx..]);
+            break;
+        }
+        if ch.is_ascii_uppercase() {
+            if idx > 0 {
+                name.push('_');
+            }
+            name.push(ch.to_ascii_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+/// Parse an action name as written by `action_name`. Counts are optional and
+/// default to what the built-in keys use, e.g. `cursor_down` is
+/// `cursor_down(1)`.
+pub fn parse_action(name: &str) -> Option<Action> {
+    let (name, arg) = match name.trim().split_once('(') {
+        Some((name, rest)) => (name.trim(), Some(rest.strip_suffix(')')?.trim())),
+        None => (name.trim(), None),
+    };
+    let count = |default: usize| match arg {
+        Some(arg) => arg.parse().ok(),
+        None => Some(default),
+    };
+    let plain = |action: Action| arg.is_none().then_some(action);
+    match name {
+        "cursor_down" => count(1).map(Action::CursorDown),
+        "cursor_up" => count(1).map(Action::CursorUp),
+        "scroll_left" => count(4).map(Action::ScrollLeft),
+        "scroll_right" => count(4).map(Action::ScrollRight),
+        "mouse_scroll_up" => count(3).map(Action::MouseScrollUp),
+        "mouse_scroll_down" => count(3).map(Action::MouseScrollDown),
+        "select_file_filter" => count(0).map(Action::SelectFileFilter),
+        "insert_char" => {
+            let arg = arg?;
+            let arg = arg
+                .strip_prefix('\'')
+                .and_then(|a| a.strip_suffix('\''))
+                .unwrap_or(arg);
+            let arg = if arg == "\\n" { "\n" } else { arg };
+            let mut chars = arg.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Some(Action::InsertChar(ch)),
+                _ => None,
+            }
+        }
+        "half_page_down" => plain(Action::HalfPageDown),
+        "half_page_up" => plain(Action::HalfPageUp),
+        "page_down" => plain(Action::PageDown),
+        "page_up" => plain(Action::PageUp),
+        "go_to_top" => plain(Action::GoToTop),
+        "go_to_bottom" => plain(Action::GoToBottom),
+        "next_file" => plain(Action::NextFile),
+        "prev_file" => plain(Action::PrevFile),
+        "next_hunk" => plain(Action::NextHunk),
+        "prev_hunk" => plain(Action::PrevHunk),
+        "center_cursor" => plain(Action::CenterCursor),
+        "match_bracket" => plain(Action::MatchBracket),
+        "toggle_focus" => plain(Action::ToggleFocus),
+        "select_file" => plain(Action::SelectFile),
+        "toggle_file_list" => plain(Action::ToggleFileList),
+        "focus_file_list" => plain(Action::FocusFileList),
+        "focus_diff" => plain(Action::FocusDiff),
+        "toggle_comments_panel" => plain(Action::ToggleCommentsPanel),
+        "reselect_commits" => plain(Action::ReselectCommits),
+        "cycle_whitespace" => plain(Action::CycleWhitespace),
+        "toggle_reviewed" => plain(Action::ToggleReviewed),
+        "review_and_next" => plain(Action::ReviewAndNext),
+        "show_stats" => plain(Action::ShowStats),
+        "toggle_hunk_reviewed" => plain(Action::ToggleHunkReviewed),
+        "toggle_pin" => plain(Action::TogglePin),
+        "toggle_skipped" => plain(Action::ToggleSkipped),
+        "toggle_filtered" => plain(Action::ToggleFiltered),
+        "toggle_resolved" => plain(Action::ToggleResolved),
+        "stage" => plain(Action::Stage),
+        "add_line_comment" => plain(Action::AddLineComment),
+        "add_file_comment" => plain(Action::AddFileComment),
+        "edit_comment" => plain(Action::EditComment),
+        "reply_to_comment" => plain(Action::ReplyToComment),
+        "delete_comment" => plain(Action::DeleteComment),
+        "undo" => plain(Action::Undo),
+        "redo" => plain(Action::Redo),
+        "search_next" => plain(Action::SearchNext),
+        "search_prev" => plain(Action::SearchPrev),
+        "enter_visual_mode" => plain(Action::EnterVisualMode),
+        "add_range_comment" => plain(Action::AddRangeComment),
+        "add_suggestion" => plain(Action::AddSuggestion),
+        "quit" => plain(Action::Quit),
+        "force_quit" => plain(Action::ForceQuit),
+        "export_to_clipboard" => plain(Action::ExportToClipboard),
+        "copy_visible_text" => plain(Action::CopyVisibleText),
+        "enter_command_mode" => plain(Action::EnterCommandMode),
+        "enter_search_mode" => plain(Action::EnterSearchMode),
+        "exit_mode" => plain(Action::ExitMode),
+        "toggle_help" => plain(Action::ToggleHelp),
+        "delete_char" => plain(Action::DeleteChar),
+        "delete_word" => plain(Action::DeleteWord),
+        "clear_line" => plain(Action::ClearLine),
+        "submit_input" => plain(Action::SubmitInput),
+        "text_cursor_left" => plain(Action::TextCursorLeft),
+        "text_cursor_right" => plain(Action::TextCursorRight),
+        "text_cursor_up" => plain(Action::TextCursorUp),
+        "text_cursor_down" => plain(Action::TextCursorDown),
+        "edit_in_external_editor" => plain(Action::EditInExternalEditor),
+        "text_cursor_line_start" => plain(Action::TextCursorLineStart),
+        "text_cursor_line_end" => plain(Action::TextCursorLineEnd),
+        "text_cursor_word_left" => plain(Action::TextCursorWordLeft),
+        "text_cursor_word_right" => plain(Action::TextCursorWordRight),
+        "cycle_comment_type" => plain(Action::CycleCommentType),
+        "confirm_yes" => plain(Action::ConfirmYes),
+        "confirm_no" => plain(Action::ConfirmNo),
+        "export_in_chunks" => plain(Action::ExportInChunks),
+        "export_to_file" => plain(Action::ExportToFile),
+        "open_previous_review" => plain(Action::OpenPreviousReview),
+        "diff_since_previous_review" => plain(Action::DiffSincePreviousReview),
+        "archive_previous_review" => plain(Action::ArchivePreviousReview),
+        "retry_failed" => plain(Action::RetryFailed),
+        "show_error_log" => plain(Action::ShowErrorLog),
+        "commit_select_up" => plain(Action::CommitSelectUp),
+        "commit_select_down" => plain(Action::CommitSelectDown),
+        "toggle_commit_select" => plain(Action::ToggleCommitSelect),
+        "confirm_commit_select" => plain(Action::ConfirmCommitSelect),
+        "toggle_expand" => plain(Action::ToggleExpand),
+        "expand_all" => plain(Action::ExpandAll),
+        "collapse_all" => plain(Action::CollapseAll),
+        "none" => plain(Action::None),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::keymap::effective_keymap;
+
+    fn key(ch: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)
+    }
+
+    fn feed_all(keymap: &Keymap, keys: &str) -> Vec<Action> {
+        let mut pending = Vec::new();
+        keys.chars()
+            .filter_map(|ch| keymap.feed(InputMode::Normal, &mut pending, key(ch)))
+            .collect()
+    }
+
+    #[test]
+    fn should_resolve_default_sequences_and_fall_back_after_a_prefix() {
+        // given
+        let keymap = Keymap::default();
+
+        // when
+        let actions = feed_all(&keymap, "zzdj");
+
+        // then
+        assert_eq!(actions, vec![Action::CenterCursor, Action::CursorDown(1)]);
+    }
+
+    #[test]
+    fn should_remap_keys_and_sequences() {
+        // given
+        let (keymap, errors) = Keymap::parse(
+            "# my keys\n[normal]\n\"<C-n>\" = \"next_file\"\ngg = \"go_to_top\" # top\nJ = 'cursor_down(5)'\nzz = \"none\"\n",
+        );
+
+        // when
+        let actions = feed_all(&keymap, "ggJz");
+
+        // then
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            actions,
+            vec![Action::GoToTop, Action::CursorDown(5), Action::None]
+        );
+        assert_eq!(
+            keymap.action_for(
+                InputMode::Normal,
+                KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL)
+            ),
+            Some(Action::NextFile)
+        );
+    }
+
+    #[test]
+    fn should_move_force_quit_to_another_key_per_mode() {
+        // given
+        let (keymap, errors) =
+            Keymap::parse("[comment]\n\"<C-c>\" = \"none\"\n\"<C-q>\" = \"force_quit\"\n");
+        let action = |mode, ch| {
+            keymap.action_for(
+                mode,
+                KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL),
+            )
+        };
+
+        // then
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(action(InputMode::Comment, 'c'), Some(Action::None));
+        assert_eq!(action(InputMode::Comment, 'q'), Some(Action::ForceQuit));
+        assert_eq!(action(InputMode::Normal, 'c'), Some(Action::ForceQuit));
+    }
+
+    #[test]
+    fn should_keep_keys_apart_that_legacy_terminals_merge() {
+        // given
+        let (keymap, errors) = Keymap::parse(
+            "[normal]\n\"<C-i>\" = \"next_file\"\n\"<S-CR>\" = \"next_hunk\"\n\"<C-CR>\" = \"prev_hunk\"\n",
+        );
+        let action =
+            |code, modifiers| keymap.action_for(InputMode::Normal, KeyEvent::new(code, modifiers));
+
+        // then
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            action(KeyCode::Char('i'), KeyModifiers::CONTROL),
+            Some(Action::NextFile)
+        );
+        assert_eq!(
+            action(KeyCode::Tab, KeyModifiers::NONE),
+            Some(Action::ToggleFocus)
+        );
+        assert_eq!(
+            action(KeyCode::Enter, KeyModifiers::SHIFT),
+            Some(Action::NextHunk)
+        );
+        assert_eq!(
+            action(KeyCode::Enter, KeyModifiers::CONTROL),
+            Some(Action::PrevHunk)
+        );
+        assert_eq!(
+            action(KeyCode::Enter, KeyModifiers::NONE),
+            Some(Action::SelectFile)
+        );
+    }
+
+    #[test]
+    fn should_report_invalid_entries_with_line_numbers() {
+        // when
+        let (_, errors) = Keymap::parse(
+            "[normal]\nx = \"fly\"\n\"<Hyper-x>\" = \"quit\"\nz = \"quit\"\n\"<C-n>\" = \"quit\"\n\"<Ctrl-n>\" = \"quit\"\n[nowhere]\n",
+        );
+
+        // then
+        assert_eq!(
+            errors,
+            vec![
+                "2: unknown action 'fly'",
+                "3: unknown modifier 'Hyper' in <Hyper-x>",
+                "6: <C-n> is bound twice",
+                "7: unknown mode [nowhere]",
+                "4: z is also the start of zz; unbind that sequence first",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_invalid_toml_with_its_line_number() {
+        // when
+        let (keymap, errors) = Keymap::parse("[normal]\nx = \"quit\"\ny = quit\n");
+
+        // then
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("3: "), "{errors:?}");
+        assert_eq!(keymap, Keymap::default());
+    }
+
+    #[test]
+    fn should_accept_any_toml_spelling_of_a_binding() {
+        // given
+        let (keymap, errors) = Keymap::parse(
+            "normal.q = \"go_to_top\"\ncomment = { \"<C-s>\" = 'submit_input' }\n[commit_select]\n\"\\u0078\" = \"confirm_commit_select\"\n",
+        );
+        let action = |mode, ch, modifiers| {
+            keymap.action_for(mode, KeyEvent::new(KeyCode::Char(ch), modifiers))
+        };
+
+        // then
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            action(InputMode::Normal, 'q', KeyModifiers::NONE),
+            Some(Action::GoToTop)
+        );
+        assert_eq!(
+            action(InputMode::Comment, 's', KeyModifiers::CONTROL),
+            Some(Action::SubmitInput)
+        );
+        assert_eq!(
+            action(InputMode::CommitSelect, 'x', KeyModifiers::NONE),
+            Some(Action::ConfirmCommitSelect)
+        );
+    }
+
+    #[test]
+    fn should_name_every_built_in_action() {
+        for mode in effective_keymap(&Keymap::default()) {
+            for binding in mode.bindings {
+                assert_eq!(
+                    parse_action(&binding.name).map(|a| action_name(&a)),
+                    Some(binding.name.clone())
+                );
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 pub mod handler;
 pub mod keybindings;
+pub mod keymap;
+pub mod keymap_file;
 pub mod mode;
 
-pub use keybindings::{Action, map_key_to_action};
+pub use keybindings::Action;
@@ -1,6 +1,7 @@
 mod app;
 mod error;
 mod handler;
+mod hash;
 mod input;
 mod model;
 mod output;
@@ -17,8 +18,9 @@ use std::time::{Duration, Instant};
 
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind,
-        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
     },
     execute,
     terminal::{
@@ -30,192 +32,281 @@ use ratatui::{Terminal, backend::CrosstermBackend};
 
 use app::{App, FocusedPanel, InputMode};
 use handler::{
-    handle_command_action, handle_comment_action, handle_commit_select_action,
-    handle_confirm_action, handle_diff_action, handle_file_list_action, handle_help_action,
-    handle_search_action, handle_visual_action,
+    cancel_force_quit, expire_force_quit, handle_command_action, handle_comment_action,
+    handle_comments_panel_action, handle_commit_select_action, handle_confirm_action,
+    handle_diff_action, handle_error_action, handle_file_list_action, handle_force_quit,
+    handle_help_action, handle_popup_action, handle_ref_select_action, handle_search_action,
+    handle_theme_picker_action, handle_visual_action,
 };
-use input::{Action, map_key_to_action};
+use input::Action;
+use input::keybindings::normalize_key_event;
+use input::keymap_file::{Keymap, format_keys};
 use theme::{parse_cli_args, resolve_theme};
+use vcs::StdinBackend;
 
-/// Timeout for the "press Ctrl+C again to exit" feature
-const CTRL_C_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often to check whether the repository changed under the loaded diff
+const STALE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 fn main() -> anyhow::Result<()> {
     // Setup panic hook to restore terminal on panic
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
         let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
-        let _ = execute!(io::stdout(), DisableMouseCapture);
+        let _ = execute!(io::stdout(), DisableMouseCapture, DisableFocusChange);
         let _ = disable_raw_mode();
         let _ = execute!(io::stdout(), LeaveAlternateScreen);
         original_hook(panic_info);
     }));
 
-    // Check keyboard enhancement support before enabling raw mode
-    let keyboard_enhancement_supported = matches!(supports_keyboard_enhancement(), Ok(true));
-
     // Parse CLI arguments and resolve theme
     // This also configures syntax highlighting colors before diff parsing
     let cli_args = parse_cli_args();
+    let keymap = match Keymap::load() {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(2);
+        }
+    };
+    if cli_args.print_keymap {
+        print!(
+            "{}",
+            input::keymap::keymap_markdown(&input::keymap::effective_keymap(&keymap))
+        );
+        return Ok(());
+    }
+    if let Some(age) = cli_args.prune_older_than {
+        match persistence::prune_sessions(age) {
+            Ok(count) => {
+                println!("Pruned {count} sessions");
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if cli_args.check {
+        match output::check_saved_review(
+            cli_args.range.as_deref(),
+            cli_args.session_path.as_deref(),
+        ) {
+            Ok(problems) if problems.is_empty() => {
+                println!("Review complete");
+                return Ok(());
+            }
+            Ok(problems) => {
+                eprintln!("Review incomplete:");
+                for problem in problems {
+                    eprintln!("  {problem}");
+                }
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(format) = cli_args.export_format {
+        match output::export_saved_review(
+            format,
+            cli_args.range.as_deref(),
+            cli_args.session_path.as_deref(),
+        ) {
+            Ok(content) => {
+                print!("{content}");
+                return Ok(());
+            }
+            Err(error::TuicrError::NoComments) => {
+                eprintln!("Error: The saved review has no comments to export");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Check keyboard enhancement support before enabling raw mode. This
+    // queries the terminal, so the commands above that only print skip it.
+    let keyboard_enhancement_supported = matches!(supports_keyboard_enhancement(), Ok(true));
+
     let theme = resolve_theme(cli_args.theme);
 
     // Initialize app
-    let mut app = match App::new(theme, cli_args.output_to_stdout) {
+    if let Some(timeout) = cli_args.vcs_timeout {
+        vcs::set_command_timeout(timeout);
+    }
+    let app = if cli_args.stdin_diff {
+        StdinBackend::from_stdin().and_then(|backend| {
+            App::with_backend(
+                Box::new(backend),
+                theme,
+                cli_args.output_to_stdout,
+                cli_args.read_only,
+            )
+        })
+    } else if let Some(range) = &cli_args.range {
+        App::with_commit_range(theme, cli_args.output_to_stdout, cli_args.read_only, range)
+    } else {
+        App::new(theme, cli_args.output_to_stdout, cli_args.read_only)
+    };
+    let mut app = match app {
         Ok(mut app) => {
             app.supports_keyboard_enhancement = keyboard_enhancement_supported;
+            app.keymap = keymap;
             app
         }
         Err(e) => {
             eprintln!("Error: {e}");
-            eprintln!(
-                "\nMake sure you're in a git, jujutsu, or mercurial repository with uncommitted changes."
-            );
+            // The error already says what to do about a newer session
+            if matches!(e, error::TuicrError::SessionTooNew(_)) {
+                std::process::exit(1);
+            }
+            if cli_args.stdin_diff {
+                eprintln!("\nPipe a unified diff in, e.g. `git diff | tuicr -`.");
+            } else if let Some(range) = &cli_args.range {
+                eprintln!("\nMake sure `{range}` names commits with changes in this repository.");
+            } else {
+                eprintln!(
+                    "\nMake sure you're in a git, jujutsu, or mercurial repository with uncommitted changes."
+                );
+            }
             std::process::exit(1);
         }
     };
 
+    if let Some(path) = &cli_args.session_path
+        && let Err(e) = app.open_session_file(path)
+    {
+        eprintln!("Error: Failed to load session {}: {e}", path.display());
+        std::process::exit(1);
+    }
+    if cli_args.watch {
+        app.set_autoreload(true);
+    }
+    app.load_code_owners();
+    app.start_webhook();
+    if cli_args.screen_reader {
+        app.set_screen_reader(true);
+    }
+    if cli_args.reduce_motion {
+        app.set_reduce_motion(true);
+    }
+    if let Some(lines) = cli_args.export_context_lines {
+        app.export_context_lines = lines;
+    }
+    if let Some(lines) = cli_args.highlight_max_lines {
+        app.set_highlight_max_lines(lines);
+    }
+    if let Some(ms) = cli_args.quit_timeout_ms {
+        app.quit_timeout_ms = ms;
+    }
+    if let Some(secs) = cli_args.autosave_secs {
+        app.autosave_secs = secs;
+    }
+    if cli_args.session_path.is_none() {
+        app.offer_autosave_recovery();
+    }
+
     // Setup terminal
     // When --stdout is used, render TUI to /dev/tty so stdout is free for export output
     enable_raw_mode()?;
+    vcs::set_cancel_check(vcs_cancel_requested);
     let mut tty_output: Box<dyn Write> = if cli_args.output_to_stdout {
         Box::new(File::options().write(true).open("/dev/tty")?)
     } else {
         Box::new(io::stdout())
     };
-    execute!(tty_output, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        tty_output,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableFocusChange
+    )?;
 
-    // Enable keyboard enhancement for better modifier key detection (e.g., Alt+Enter)
-    // This is supported by modern terminals like Kitty, iTerm2, WezTerm, etc.
     if keyboard_enhancement_supported {
-        let _ = execute!(
-            tty_output,
-            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
-        );
+        push_keyboard_enhancement(&mut tty_output);
     }
     let backend = CrosstermBackend::new(tty_output);
     let mut terminal = Terminal::new(backend)?;
 
-    // Track pending z command for zz centering
-    let mut pending_z = false;
-    // Track pending d command for dd delete
-    let mut pending_d = false;
-    // Track pending ; command for ;e toggle file list
-    let mut pending_semicolon = false;
-    // Track pending Ctrl+C for "press twice to exit" (with timestamp for 2s timeout)
-    let mut pending_ctrl_c: Option<Instant> = None;
+    // Keys typed so far of a multi-key sequence such as `zz`
+    let mut pending_keys: Vec<KeyEvent> = Vec::new();
+    // When the pending prefix was typed, for the multi-key timeout
+    let mut pending_since: Option<Instant> = None;
+    let mut last_stale_check = Instant::now();
+    // Whether anything changed since the last frame, for reduced motion
+    let mut needs_redraw = true;
+    // Mode at the last autosave check, to autosave on mode changes
+    let mut last_mode = app.input_mode;
 
     // Main loop
     loop {
-        // Render
-        terminal.draw(|frame| {
-            ui::render(frame, &mut app);
-        })?;
-
-        // Auto-clear expired pending Ctrl+C state and message
-        if let Some(first_press) = pending_ctrl_c
-            && first_press.elapsed() >= CTRL_C_EXIT_TIMEOUT
+        // Render (with reduced motion, only when something changed)
+        if needs_redraw || !app.reduce_motion {
+            terminal.draw(|frame| {
+                ui::render(frame, &mut app);
+            })?;
+            needs_redraw = false;
+        }
+
+        needs_redraw |= expire_force_quit(&mut app);
+
+        // Drop a pending multi-key prefix nobody completed
+        if let Some(since) = pending_since
+            && app.key_timeout_ms > 0
+            && since.elapsed() >= Duration::from_millis(app.key_timeout_ms)
         {
-            pending_ctrl_c = None;
-            app.message = None;
+            pending_keys.clear();
+            pending_since = None;
+            app.pending_keys = None;
+            needs_redraw = true;
         }
 
+        // Timers are paused while the terminal is in the background
+        if app.terminal_focused && last_stale_check.elapsed() >= STALE_CHECK_INTERVAL {
+            last_stale_check = Instant::now();
+            app.check_diff_stale();
+            needs_redraw = true;
+        }
+
+        needs_redraw |= app.poll_autoreload();
+        needs_redraw |= app.poll_webhook();
+        app.tick_review_timer();
+        app.poll_autosave(app.input_mode != last_mode);
+        last_mode = app.input_mode;
+
         // Handle events
         if event::poll(Duration::from_millis(100))? {
+            needs_redraw = true;
             let event = event::read()?;
             match event {
-                Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    // Handle Ctrl+C twice to exit (works across all input modes)
-                    // In Comment mode, first Ctrl+C also cancels the comment
-                    if key.code == crossterm::event::KeyCode::Char('c')
-                        && key
-                            .modifiers
-                            .contains(crossterm::event::KeyModifiers::CONTROL)
-                    {
-                        // If in comment mode, cancel the comment first
-                        if app.input_mode == InputMode::Comment {
-                            app.exit_comment_mode();
-                        }
-
-                        if let Some(first_press) = pending_ctrl_c
-                            && first_press.elapsed() < CTRL_C_EXIT_TIMEOUT
-                        {
-                            // Second Ctrl+C within timeout - exit immediately
-                            app.should_quit = true;
-                            continue;
-                        }
-                        // First Ctrl+C (or timeout expired) - show warning and start timer
-                        pending_ctrl_c = Some(Instant::now());
-                        app.set_message("Press Ctrl+C again to exit");
+                Event::Key(key) => {
+                    app.note_activity();
+                    let Some(key) = normalize_key_event(key) else {
                         continue;
-                    }
-
-                    // Any other key clears the pending Ctrl+C state and message
-                    if pending_ctrl_c.is_some() {
-                        pending_ctrl_c = None;
-                        app.message = None;
-                    }
+                    };
+                    // The next key completes or cancels any pending prefix
+                    pending_since = None;
+                    app.pending_keys = None;
 
-                    // Handle pending z command for zz centering
-                    if pending_z {
-                        pending_z = false;
-                        if key.code == crossterm::event::KeyCode::Char('z') {
-                            app.center_cursor();
-                            continue;
-                        }
-                        // Otherwise fall through to normal handling
-                    }
-
-                    // Handle pending d command for dd delete comment
-                    if pending_d {
-                        pending_d = false;
-                        if key.code == crossterm::event::KeyCode::Char('d') {
-                            if !app.delete_comment_at_cursor() {
-                                app.set_message("No comment at cursor");
-                            }
-                            continue;
-                        }
-                        // Otherwise fall through to normal handling
-                    }
-
-                    // Handle pending ; command for ;e toggle file list, ;h/;l panel focus
-                    if pending_semicolon {
-                        pending_semicolon = false;
-                        match key.code {
-                            crossterm::event::KeyCode::Char('e') => {
-                                app.toggle_file_list();
-                                continue;
-                            }
-                            crossterm::event::KeyCode::Char('h') => {
-                                app.focused_panel = app::FocusedPanel::FileList;
-                                continue;
-                            }
-                            crossterm::event::KeyCode::Char('l') => {
-                                app.focused_panel = app::FocusedPanel::Diff;
-                                continue;
-                            }
-                            _ => {}
-                        }
-                        // Otherwise fall through to normal handling
-                    }
-
-                    let action = map_key_to_action(key, app.input_mode);
+                    let Some(action) = app.keymap.feed(app.input_mode, &mut pending_keys, key)
+                    else {
+                        pending_since = Some(Instant::now());
+                        app.pending_keys = Some(format_keys(&pending_keys));
+                        continue;
+                    };
 
-                    // Handle pending command setters (these work in any mode)
-                    match action {
-                        Action::PendingZCommand => {
-                            pending_z = true;
-                            continue;
-                        }
-                        Action::PendingDCommand => {
-                            pending_d = true;
-                            continue;
-                        }
-                        Action::PendingSemicolonCommand => {
-                            pending_semicolon = true;
-                            continue;
-                        }
-                        _ => {}
+                    // The force quit key (Ctrl+C by default) works in every mode
+                    if action == Action::ForceQuit {
+                        handle_force_quit(&mut app, &format_keys(&[key]));
+                        continue;
                     }
+                    cancel_force_quit(&mut app);
 
                     // Dispatch by input mode
                     match app.input_mode {
@@ -226,13 +317,28 @@ fn main() -> anyhow::Result<()> {
                         InputMode::Confirm => handle_confirm_action(&mut app, action),
                         InputMode::CommitSelect => handle_commit_select_action(&mut app, action),
                         InputMode::VisualSelect => handle_visual_action(&mut app, action),
+                        InputMode::Popup => handle_popup_action(&mut app, action),
+                        InputMode::ThemePicker => handle_theme_picker_action(&mut app, action),
+                        InputMode::RefSelect => handle_ref_select_action(&mut app, action),
+                        InputMode::Error => handle_error_action(&mut app, action),
                         InputMode::Normal => match app.focused_panel {
                             FocusedPanel::FileList => handle_file_list_action(&mut app, action),
                             FocusedPanel::Diff => handle_diff_action(&mut app, action),
+                            FocusedPanel::Comments => {
+                                handle_comments_panel_action(&mut app, action)
+                            }
                         },
                     }
                 }
+                Event::FocusLost => app.terminal_focused = false,
+                Event::FocusGained => {
+                    app.terminal_focused = true;
+                    // Coming back is the most likely moment for the diff to be stale
+                    last_stale_check = Instant::now();
+                    app.check_diff_stale();
+                }
                 Event::Mouse(mouse_event) => {
+                    app.note_activity();
                     use crossterm::event::MouseEventKind;
 
                     // Helper to determine which panel the mouse is over
@@ -248,6 +354,7 @@ fn main() -> anyhow::Result<()> {
                                 && mouse_row < area.y + area.height
                         })
                         .unwrap_or(false);
+                    app.file_list_state.hovered_row = over_file_list.then_some(mouse_row);
 
                     let over_diff = app
                         .diff_area
@@ -259,6 +366,16 @@ fn main() -> anyhow::Result<()> {
                         })
                         .unwrap_or(false);
 
+                    let over_comments = app
+                        .comments_area
+                        .map(|area| {
+                            mouse_col >= area.x
+                                && mouse_col < area.x + area.width
+                                && mouse_row >= area.y
+                                && mouse_row < area.y + area.height
+                        })
+                        .unwrap_or(false);
+
                     match mouse_event.kind {
                         MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
                             // Click to focus panel
@@ -267,6 +384,8 @@ fn main() -> anyhow::Result<()> {
                                     app.focused_panel = FocusedPanel::FileList;
                                 } else if over_diff {
                                     app.focused_panel = FocusedPanel::Diff;
+                                } else if over_comments {
+                                    app.focused_panel = FocusedPanel::Comments;
                                 }
                             }
                         }
@@ -280,11 +399,14 @@ fn main() -> anyhow::Result<()> {
                             // Dispatch action based on which panel the mouse is over
                             match app.input_mode {
                                 InputMode::Help => handle_help_action(&mut app, action),
+                                InputMode::Popup => handle_popup_action(&mut app, action),
                                 InputMode::Normal => {
                                     if over_file_list {
                                         handle_file_list_action(&mut app, action);
                                     } else if over_diff {
                                         handle_diff_action(&mut app, action);
+                                    } else if over_comments {
+                                        handle_comments_panel_action(&mut app, action);
                                     }
                                 }
                                 _ => {}
@@ -297,6 +419,14 @@ fn main() -> anyhow::Result<()> {
             }
         }
 
+        app.track_viewed_lines();
+
+        if app.external_edit_requested {
+            app.external_edit_requested = false;
+            edit_comment_externally(&mut terminal, &mut app, keyboard_enhancement_supported)?;
+            needs_redraw = true;
+        }
+
         if app.should_quit {
             break;
         }
@@ -304,10 +434,19 @@ fn main() -> anyhow::Result<()> {
 
     // Restore terminal
     let _ = execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags);
-    execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        DisableFocusChange
+    )?;
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
 
+    // Quitting normally, unsaved changes are dropped on purpose
+    if !app.read_only {
+        let _ = persistence::discard_autosave(&app.session);
+    }
+
     // Print pending stdout output if --stdout was used
     if let Some(output) = app.pending_stdout_output {
         print!("{output}");
@@ -315,3 +454,76 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Whether Esc or Ctrl+C was pressed while an hg or jj command runs, to
+/// abort a command that hangs (e.g. on a credential prompt). Other input
+/// arriving meanwhile is dropped.
+fn vcs_cancel_requested() -> bool {
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        if let Ok(Event::Key(key)) = event::read()
+            && key.kind == KeyEventKind::Press
+            && (key.code == KeyCode::Esc
+                || (key.code == KeyCode::Char('c')
+                    && key.modifiers.contains(KeyModifiers::CONTROL)))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Enable keyboard enhancement for better modifier key detection (e.g., Alt+Enter)
+/// This is supported by modern terminals like Kitty, iTerm2, WezTerm, etc.
+/// Reporting all keys as escape codes tells apart keys that legacy encoding
+/// merges, such as Ctrl+I and Tab or Shift+Enter and Enter.
+fn push_keyboard_enhancement(output: &mut impl Write) {
+    let _ = execute!(
+        output,
+        PushKeyboardEnhancementFlags(
+            KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+                | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+        )
+    );
+}
+
+/// Suspend the TUI while the comment being written is edited in `$EDITOR`,
+/// then load the edited text back into the comment input
+fn edit_comment_externally(
+    terminal: &mut Terminal<CrosstermBackend<Box<dyn Write>>>,
+    app: &mut App,
+    keyboard_enhancement_supported: bool,
+) -> anyhow::Result<()> {
+    let backend = terminal.backend_mut();
+    if keyboard_enhancement_supported {
+        let _ = execute!(backend, PopKeyboardEnhancementFlags);
+    }
+    execute!(
+        backend,
+        DisableMouseCapture,
+        DisableFocusChange,
+        LeaveAlternateScreen
+    )?;
+    disable_raw_mode()?;
+
+    let edited = text_edit::edit_externally(app.comment_editor.text());
+
+    enable_raw_mode()?;
+    execute!(
+        backend,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableFocusChange
+    )?;
+    if keyboard_enhancement_supported {
+        push_keyboard_enhancement(backend);
+    }
+    terminal.clear()?;
+
+    match edited {
+        Ok(text) => app.comment_editor.set_text(text),
+        Err(e) => app.set_warning(format!("{e}")),
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use super::comment::{LineContext, LineRange, LineSide};
+use super::diff_types::DiffFile;
+
+/// Lines kept above and below a comment to recognise where it moved
+const CONTEXT_LINES: usize = 3;
+
+/// The lines on `side` of `file`'s diff with their numbers, in diff order
+fn side_lines(file: &DiffFile, side: LineSide) -> Vec<(u32, &str)> {
+    file.hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter_map(|line| {
+            let lineno = match side {
+                LineSide::New => line.new_lineno,
+                LineSide::Old => line.old_lineno,
+            }?;
+            Some((lineno, line.content.as_str()))
+        })
+        .collect()
+}
+
+/// Whether `lines[idx]` directly follows `lines[idx - 1]` in the file
+fn follows(lines: &[(u32, &str)], idx: usize) -> bool {
+    idx > 0 && lines[idx].0 == lines[idx - 1].0 + 1
+}
+
+/// The lines `range` covers on `side` of `file`, with up to
+/// [`CONTEXT_LINES`] lines around them, to re-anchor a comment made on them
+pub fn capture_context(file: &DiffFile, range: LineRange, side: LineSide) -> Option<LineContext> {
+    let lines = side_lines(file, side);
+    let start = lines
+        .iter()
+        .position(|(lineno, _)| *lineno == range.start)?;
+    let end = start + (range.end - range.start) as usize;
+    if end >= lines.len() || (start + 1..=end).any(|idx| !follows(&lines, idx)) {
+        return None;
+    }
+
+    let mut first = start;
+    while start - first < CONTEXT_LINES && follows(&lines, first) {
+        first -= 1;
+    }
+    let mut last = end;
+    while last - end < CONTEXT_LINES && last + 1 < lines.len() && follows(&lines, last + 1) {
+        last += 1;
+    }
+    let text = |from: usize, to: usize| -> Vec<String> {
+        lines[from..to]
+            .iter()
+            .map(|(_, content)| content.to_string())
+            .collect()
+    };
+
+    let mut context = LineContext {
+        new_line: None,
+        old_line: None,
+        content: text(start, end + 1).join("\n"),
+        before: text(first, start),
+        after: text(end + 1, last + 1),
+    };
+    match side {
+        LineSide::New => context.new_line = Some(range.start),
+        LineSide::Old => context.old_line = Some(range.start),
+    }
+    Some(context)
+}
+
+/// Where the lines of `context`, last seen at `range`, are on `side` of
+/// `file`. Like `git apply`, a place matches when the lines read the same;
+/// the one with the most matching context lines wins, then the nearest. A
+/// place other than `range` needs at least one context line to match, when
+/// there is context. `None` when the lines are gone.
+pub fn relocate(
+    file: &DiffFile,
+    context: &LineContext,
+    range: LineRange,
+    side: LineSide,
+) -> Option<LineRange> {
+    let lines = side_lines(file, side);
+    let target: Vec<&str> = context.content.split('\n').collect();
+    if target.len() > lines.len() {
+        return None;
+    }
+
+    // Context lines matching outward from the lines at `start..=end`
+    let matching_context = |start: usize, end: usize| -> usize {
+        let above = context
+            .before
+            .iter()
+            .rev()
+            .zip((0..start).rev())
+            .take_while(|(text, idx)| follows(&lines, idx + 1) && lines[*idx].1 == text.as_str())
+            .count();
+        let below = context
+            .after
+            .iter()
+            .zip(end + 1..lines.len())
+            .take_while(|(text, idx)| follows(&lines, *idx) && lines[*idx].1 == text.as_str())
+            .count();
+        above + below
+    };
+
+    let has_context = !context.before.is_empty() || !context.after.is_empty();
+    (0..=lines.len() - target.len())
+        .filter(|&start| {
+            let end = start + target.len() - 1;
+            (start..=end).all(|idx| lines[idx].1 == target[idx - start])
+                && (start + 1..=end).all(|idx| follows(&lines, idx))
+        })
+        .map(|start| {
+            let end = start + target.len() - 1;
+            let moved = LineRange::new(lines[start].0, lines[end].0);
+            (moved, matching_context(start, end))
+        })
+        .filter(|(moved, matched)| *matched > 0 || !has_context || *moved == range)
+        .max_by_key(|(moved, matched)| {
+            (
+                *matched,
+                std::cmp::Reverse(moved.start.abs_diff(range.start)),
+            )
+        })
+        .map(|(moved, _)| moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::added_file;
+
+    /// A file whose diff adds `lines` from line 1 on
+    fn file(lines: &[&str]) -> DiffFile {
+        added_file("src/lib.rs", 1, lines)
+    }
+
+    #[test]
+    fn should_follow_lines_that_moved() {
+        // given
+        let before = file(&["fn a() {", "    b();", "}", "fn c() {", "    b();", "}"]);
+        let context = capture_context(&before, LineRange::single(5), LineSide::New).unwrap();
+        let after = file(&[
+            "use x;", "", "fn a() {", "    b();", "}", "fn c() {", "    b();", "}",
+        ]);
+
+        // when
+        let moved = relocate(&after, &context, LineRange::single(5), LineSide::New);
+
+        // then
+        assert_eq!(context.before, vec!["    b();", "}", "fn c() {"]);
+        assert_eq!(moved, Some(LineRange::single(7)));
+    }
+
+    #[test]
+    fn should_move_ranges_with_fewer_matching_context_lines() {
+        // given
+        let before = file(&["a", "b", "x", "y", "c", "d"]);
+        let context = capture_context(&before, LineRange::new(3, 4), LineSide::New).unwrap();
+        let after = file(&["a", "changed", "new", "x", "y", "c", "d"]);
+
+        // when
+        let moved = relocate(&after, &context, LineRange::new(3, 4), LineSide::New);
+
+        // then
+        assert_eq!(moved, Some(LineRange::new(4, 5)));
+    }
+
+    #[test]
+    fn should_not_find_lines_that_changed() {
+        // given
+        let before = file(&["a", "b", "c"]);
+        let context = capture_context(&before, LineRange::single(2), LineSide::New).unwrap();
+        let after = file(&["a", "B", "c", "}", "b"]);
+
+        // when
+        let moved = relocate(&after, &context, LineRange::single(2), LineSide::New);
+
+        // then
+        assert_eq!(moved, None);
+    }
+}
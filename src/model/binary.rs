@@ -0,0 +1,79 @@
+/// Sizes in bytes of a binary file on each side of the diff; `None` where
+/// the file does not exist or the VCS did not tell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BinarySizes {
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+}
+
+impl BinarySizes {
+    /// Placeholder shown in place of the diff of a binary file, with its size
+    /// change when known: "(binary file, 1.2 KiB → 3.4 KiB, +2.2 KiB)"
+    pub fn placeholder(&self) -> String {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) => {
+                let delta = if new >= old {
+                    format!("+{}", format_size(new - old))
+                } else {
+                    format!("-{}", format_size(old - new))
+                };
+                format!(
+                    "(binary file, {} → {}, {delta})",
+                    format_size(old),
+                    format_size(new)
+                )
+            }
+            (None, Some(new)) => format!("(binary file, {})", format_size(new)),
+            (Some(old), None) => format!("(binary file, {} removed)", format_size(old)),
+            (None, None) => "(binary file)".to_string(),
+        }
+    }
+}
+
+/// `bytes` in B, KiB, MiB or GiB, with one decimal above bytes
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_sizes_in_binary_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn should_show_the_size_change_of_binary_files() {
+        // given
+        let modified = BinarySizes {
+            old: Some(2048),
+            new: Some(1024),
+        };
+        let added = BinarySizes {
+            old: None,
+            new: Some(100),
+        };
+
+        // then
+        assert_eq!(
+            modified.placeholder(),
+            "(binary file, 2.0 KiB → 1.0 KiB, -1.0 KiB)"
+        );
+        assert_eq!(added.placeholder(), "(binary file, 100 B)");
+        assert_eq!(BinarySizes::default().placeholder(), "(binary file)");
+    }
+}
@@ -0,0 +1,104 @@
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Find the line holding the bracket that matches one on `lines[cursor]`,
+/// like vim's `%`: an opening bracket is matched forward, a closing one
+/// backward, counting only brackets of the same kind.
+///
+/// There is no cursor column, so the first bracket whose match is on another
+/// line wins: `fn main() {` jumps to the closing brace, not to `)`.
+pub fn find_matching_bracket(lines: &[&str], cursor: usize) -> Option<usize> {
+    let line = lines.get(cursor)?;
+    line.char_indices()
+        .filter(|(_, ch)| is_bracket(*ch))
+        .filter_map(|(col, bracket)| match_bracket(lines, cursor, col, bracket))
+        .find(|idx| *idx != cursor)
+}
+
+fn match_bracket(lines: &[&str], cursor: usize, col: usize, bracket: char) -> Option<usize> {
+    let line = lines[cursor];
+    let mut depth = 0usize;
+
+    if let Some(&(open, close)) = PAIRS.iter().find(|(open, _)| *open == bracket) {
+        let rest = std::iter::once((cursor, &line[col..])).chain(
+            lines
+                .iter()
+                .enumerate()
+                .skip(cursor + 1)
+                .map(|(i, l)| (i, *l)),
+        );
+        for (idx, text) in rest {
+            for ch in text.chars() {
+                if ch == open {
+                    depth += 1;
+                } else if ch == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(idx);
+                    }
+                }
+            }
+        }
+    } else {
+        let &(open, close) = PAIRS.iter().find(|(_, close)| *close == bracket)?;
+        let rest = std::iter::once((cursor, &line[..col + bracket.len_utf8()])).chain(
+            lines[..cursor]
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, l)| (i, *l)),
+        );
+        for (idx, text) in rest {
+            for ch in text.chars().rev() {
+                if ch == close {
+                    depth += 1;
+                } else if ch == open {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(idx);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn is_bracket(ch: char) -> bool {
+    PAIRS
+        .iter()
+        .any(|(open, close)| ch == *open || ch == *close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: [&str; 6] = [
+        "fn main() {",
+        "    if ready {",
+        "        run(vec![1, 2]);",
+        "    }",
+        "}",
+        "",
+    ];
+
+    #[test]
+    fn should_match_opening_bracket_forward() {
+        // `()` closes on the same line, so the brace is used
+        assert_eq!(find_matching_bracket(&CODE, 0), Some(4));
+        assert_eq!(find_matching_bracket(&CODE, 1), Some(3));
+    }
+
+    #[test]
+    fn should_match_closing_bracket_backward() {
+        assert_eq!(find_matching_bracket(&CODE, 4), Some(0));
+        assert_eq!(find_matching_bracket(&CODE, 3), Some(1));
+    }
+
+    #[test]
+    fn should_return_none_without_match_on_another_line() {
+        assert_eq!(find_matching_bracket(&CODE, 2), None);
+        assert_eq!(find_matching_bracket(&CODE, 5), None);
+        assert_eq!(find_matching_bracket(&["    foo(", "bar"], 0), None);
+    }
+}
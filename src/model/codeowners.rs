@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use regex::Regex;
+
+/// Where CODEOWNERS files are looked up when `tuicr.codeowners` is not set,
+/// in the order GitHub and GitLab use
+const CODEOWNERS_PATHS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+#[derive(Debug, Clone)]
+struct OwnerRule {
+    pattern: Regex,
+    owners: Vec<String>,
+}
+
+/// Ownership rules of a CODEOWNERS file, and the owner names (users, teams
+/// or emails) that count as the reviewer for "files I own"
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+    identities: Vec<String>,
+}
+
+impl CodeOwners {
+    /// Rules of CODEOWNERS `text`. GitLab section headers and lines with an
+    /// invalid pattern are ignored.
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|line| !line.starts_with('[') && !line.starts_with("^["))
+            .filter_map(|line| {
+                let line = line.split(" #").next().unwrap_or(line);
+                let mut fields = line.split_whitespace();
+                let pattern = pattern_regex(fields.next()?)?;
+                Some(OwnerRule {
+                    pattern,
+                    owners: fields.map(str::to_string).collect(),
+                })
+            })
+            .collect();
+        Self {
+            rules,
+            identities: Vec::new(),
+        }
+    }
+
+    /// The ownership file of the repository at `root`: `tuicr.codeowners`
+    /// from git config (relative to the root), else the first CODEOWNERS
+    /// file found where GitHub and GitLab look for it. The reviewer is
+    /// `tuicr.owner` (whitespace separated, e.g. `@alice @acme/platform`)
+    /// and `user.email`.
+    pub fn load(root: &Path) -> Option<Self> {
+        let config = git2::Repository::discover(root)
+            .and_then(|repo| repo.config())
+            .or_else(|_| git2::Config::open_default())
+            .ok();
+        let lookup = |key: &str| config.as_ref()?.get_string(key).ok();
+
+        let text = match lookup("tuicr.codeowners") {
+            Some(path) => std::fs::read_to_string(root.join(path)).ok()?,
+            None => CODEOWNERS_PATHS
+                .iter()
+                .find_map(|path| std::fs::read_to_string(root.join(path)).ok())?,
+        };
+        let mut owners = Self::parse(&text);
+        owners.identities = lookup("tuicr.owner")
+            .iter()
+            .flat_map(|names| names.split_whitespace())
+            .map(str::to_string)
+            .chain(lookup("user.email"))
+            .collect();
+        Some(owners)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Owners of `path`: those of the last matching rule, so later rules
+    /// override earlier ones. A matching rule without owners clears them.
+    pub fn owners(&self, path: &Path) -> &[String] {
+        let path = path.to_string_lossy();
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.pattern.is_match(&path))
+            .map_or(&[], |rule| &rule.owners)
+    }
+
+    /// Whether one of the owners of `path` is the reviewer
+    pub fn is_mine(&self, path: &Path) -> bool {
+        self.owners(path).iter().any(|owner| {
+            self.identities
+                .iter()
+                .any(|identity| identity.eq_ignore_ascii_case(owner))
+        })
+    }
+
+    /// Short owner hint for the file list: the first owner, and how many
+    /// more there are
+    pub fn label(&self, path: &Path) -> Option<String> {
+        match self.owners(path) {
+            [] => None,
+            [owner] => Some(owner.clone()),
+            [owner, rest @ ..] => Some(format!("{owner} +{}", rest.len())),
+        }
+    }
+}
+
+/// Regex of a gitignore-style CODEOWNERS pattern, matched against paths
+/// relative to the repository root. Patterns with a leading or inner `/`
+/// are anchored at the root, others match at any depth; a pattern matching
+/// a directory matches everything below it.
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let (pattern, directory) = match pattern.strip_suffix('/') {
+        Some(dir) => (dir, true),
+        None => (pattern, false),
+    };
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut rest = pattern;
+    while let Some(ch) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            regex.push_str("(?:.*/)?");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("**") {
+            regex.push_str(".*");
+            rest = after;
+        } else {
+            match ch {
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                _ => regex.push_str(&regex::escape(&ch.to_string())),
+            }
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    regex.push_str(if directory { "/.*$" } else { "(?:/.*)?$" });
+    Regex::new(&regex).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_take_owners_from_the_last_matching_rule() {
+        // given
+        let owners = CodeOwners::parse(
+            "# Default owners\n\
+             *                @acme/core\n\
+             *.md             @acme/docs @bob\n\
+             /src/ui/         @acme/frontend # terminal UI\n\
+             docs/**/api      @carol\n\
+             /vendor/\n",
+        );
+
+        // then
+        assert_eq!(owners.owners(Path::new("Cargo.toml")), ["@acme/core"]);
+        assert_eq!(
+            owners.owners(Path::new("src/ui/README.md")),
+            ["@acme/frontend"]
+        );
+        assert_eq!(
+            owners.label(Path::new("guide/intro.md")).as_deref(),
+            Some("@acme/docs +1")
+        );
+        assert_eq!(owners.owners(Path::new("docs/v1/api/list.md")), ["@carol"]);
+        assert_eq!(
+            owners.owners(Path::new("lib/src/ui/app.rs")),
+            ["@acme/core"]
+        );
+        assert!(owners.owners(Path::new("vendor/lib.rs")).is_empty());
+    }
+
+    #[test]
+    fn should_find_files_owned_by_the_reviewer() {
+        // given
+        let mut owners = CodeOwners::parse("*.rs @acme/backend alice@example.com\n*.ts @acme/web");
+
+        // when
+        owners.identities = vec!["Alice@example.com".to_string()];
+
+        // then
+        assert!(owners.is_mine(Path::new("src/main.rs")));
+        assert!(!owners.is_mine(Path::new("web/app.ts")));
+    }
+}
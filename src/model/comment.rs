@@ -1,6 +1,10 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::hash::fnv1a_64;
+
 /// Which side of the diff a line comment belongs to
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -67,11 +71,91 @@ impl CommentType {
     }
 }
 
+/// Order of the comments panel and exports, chosen with `:sort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentSort {
+    /// By file, file comments first, then by line
+    #[default]
+    File,
+    /// Issues first, then suggestions, notes and praise
+    Severity,
+    /// Oldest first
+    Created,
+    /// Unresolved first
+    Resolved,
+}
+
+impl CommentSort {
+    pub const ALL: &'static [CommentSort] = &[
+        CommentSort::File,
+        CommentSort::Severity,
+        CommentSort::Created,
+        CommentSort::Resolved,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Severity => "severity",
+            Self::Created => "created",
+            Self::Resolved => "resolved",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|sort| sort.name() == name)
+    }
+
+    /// Order two comments. Ties are left to a stable sort, so comments
+    /// listed in file order stay in file order within each group.
+    pub fn compare(self, a: &Comment, b: &Comment) -> std::cmp::Ordering {
+        let severity = |comment: &Comment| match comment.comment_type {
+            CommentType::Issue => 0,
+            CommentType::Suggestion => 1,
+            CommentType::Note => 2,
+            CommentType::Praise => 3,
+        };
+        match self {
+            Self::File => std::cmp::Ordering::Equal,
+            Self::Severity => severity(a).cmp(&severity(b)),
+            Self::Created => a.created_at.cmp(&b.created_at),
+            Self::Resolved => a.resolved.cmp(&b.resolved),
+        }
+    }
+}
+
+/// The lines a comment was made on, to find them again when the diff changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LineContext {
     pub new_line: Option<u32>,
     pub old_line: Option<u32>,
+    /// The commented lines, joined with newlines
     pub content: String,
+    /// Lines right above the commented ones
+    #[serde(default)]
+    pub before: Vec<String>,
+    /// Lines right below the commented ones
+    #[serde(default)]
+    pub after: Vec<String>,
+}
+
+/// A reply in a comment thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentReply {
+    pub id: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CommentReply {
+    pub fn new(content: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            content,
+            created_at: Utc::now(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +173,36 @@ pub struct Comment {
     /// None for file-level comments or single-line comments (backward compatibility)
     #[serde(default)]
     pub line_range: Option<LineRange>,
+    /// Whether the thread has been marked as resolved
+    #[serde(default)]
+    pub resolved: bool,
+    /// Replies to this comment, oldest first
+    #[serde(default)]
+    pub replies: Vec<CommentReply>,
+    /// Person responsible for following up on this comment
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Export target (e.g. a pull request's comments endpoint) mapped to the
+    /// fingerprint of the content that was published there
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub published: BTreeMap<String, String>,
+    /// For suggestions made from a visual selection, the selected lines as
+    /// they read when the suggestion was made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion_base: Option<String>,
+    /// The lines of a line comment were not found after the diff changed
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+/// A line of comment content as shown in the comment box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentLine<'a> {
+    Text(&'a str),
+    /// A selected line that a suggestion replaces
+    Removed(&'a str),
+    /// A line of a suggestion's replacement
+    Added(&'a str),
 }
 
 impl Comment {
@@ -101,6 +215,12 @@ impl Comment {
             line_context: None,
             side,
             line_range: None,
+            resolved: false,
+            replies: Vec::new(),
+            assignee: None,
+            published: BTreeMap::new(),
+            suggestion_base: None,
+            orphaned: false,
         }
     }
 
@@ -119,8 +239,104 @@ impl Comment {
             line_context: None,
             side,
             line_range: Some(line_range),
+            resolved: false,
+            replies: Vec::new(),
+            assignee: None,
+            published: BTreeMap::new(),
+            suggestion_base: None,
+            orphaned: false,
         }
     }
+
+    /// Identifies the exported content, so edits made after publishing are
+    /// sent again
+    pub fn fingerprint(&self) -> String {
+        content_fingerprint(&format!("{}\n{}", self.comment_type.as_str(), self.content))
+    }
+
+    pub fn is_published_to(&self, target: &str) -> bool {
+        self.published.get(target) == Some(&self.fingerprint())
+    }
+
+    /// Whether the current content has not been published to any target
+    pub fn is_draft(&self) -> bool {
+        let fingerprint = self.fingerprint();
+        !self.published.values().any(|f| *f == fingerprint)
+    }
+
+    /// Line indices of the opening and closing fence of the ```suggestion
+    /// block, for suggestion comments that have one
+    fn suggestion_fence(&self) -> Option<(usize, usize)> {
+        if self.comment_type != CommentType::Suggestion {
+            return None;
+        }
+        let lines: Vec<&str> = self.content.split('\n').collect();
+        let open = lines.iter().position(|l| l.trim() == "```suggestion")?;
+        let close = lines[open + 1..].iter().position(|l| l.trim() == "```")?;
+        Some((open, open + 1 + close))
+    }
+
+    /// Replacement text proposed by the ```suggestion block
+    pub fn suggestion(&self) -> Option<String> {
+        let (open, close) = self.suggestion_fence()?;
+        let lines: Vec<&str> = self.content.split('\n').collect();
+        Some(lines[open + 1..close].join("\n"))
+    }
+
+    /// Content with the ```suggestion block taken out
+    pub fn content_without_suggestion(&self) -> String {
+        match self.suggestion_fence() {
+            Some((open, close)) => {
+                let lines: Vec<&str> = self.content.split('\n').collect();
+                [&lines[..open], &lines[close + 1..]]
+                    .concat()
+                    .join("\n")
+                    .trim()
+                    .to_string()
+            }
+            None => self.content.clone(),
+        }
+    }
+
+    /// Content lines as shown in the comment box, where a suggestion block
+    /// becomes a mini-diff of the selected lines against the replacement
+    pub fn display_content(&self) -> Vec<CommentLine<'_>> {
+        let lines = self.content.split('\n').map(CommentLine::Text);
+        let Some((open, close)) = self.suggestion_fence() else {
+            return lines.collect();
+        };
+        let lines: Vec<CommentLine> = lines.collect();
+        let removed = self
+            .suggestion_base
+            .iter()
+            .flat_map(|base| base.split('\n'))
+            .map(CommentLine::Removed);
+        let added = lines[open + 1..close].iter().map(|line| match line {
+            CommentLine::Text(text) => CommentLine::Added(text),
+            other => *other,
+        });
+        lines[..open]
+            .iter()
+            .copied()
+            .chain(removed)
+            .chain(added)
+            .chain(lines[close + 1..].iter().copied())
+            .collect()
+    }
+
+    /// Number of content lines, including replies, shown in the comment box
+    pub fn display_line_count(&self) -> usize {
+        self.display_content().len()
+            + self
+                .replies
+                .iter()
+                .map(|reply| reply.content.split('\n').count())
+                .sum::<usize>()
+    }
+}
+
+pub fn content_fingerprint(text: &str) -> String {
+    format!("{:016x}", fnv1a_64(text.as_bytes()))
 }
 
 #[cfg(test)]
@@ -235,6 +451,65 @@ mod tests {
             assert_eq!(comment.side, Some(LineSide::New));
         }
 
+        #[test]
+        fn display_line_count_includes_replies() {
+            let mut comment = Comment::new(
+                "one\ntwo".to_string(),
+                CommentType::Note,
+                Some(LineSide::New),
+            );
+            comment.replies.push(CommentReply::new("reply".to_string()));
+            assert_eq!(comment.display_line_count(), 3);
+        }
+
+        #[test]
+        fn suggestion_block_shows_as_mini_diff() {
+            // given
+            let mut comment = Comment::new(
+                "Use a constant\n```suggestion\nconst X: u32 = 1;\n```".to_string(),
+                CommentType::Suggestion,
+                Some(LineSide::New),
+            );
+            comment.suggestion_base = Some("let x = 1;".to_string());
+
+            // then
+            assert_eq!(comment.suggestion().as_deref(), Some("const X: u32 = 1;"));
+            assert_eq!(comment.content_without_suggestion(), "Use a constant");
+            assert_eq!(
+                comment.display_content(),
+                vec![
+                    CommentLine::Text("Use a constant"),
+                    CommentLine::Removed("let x = 1;"),
+                    CommentLine::Added("const X: u32 = 1;"),
+                ]
+            );
+        }
+
+        #[test]
+        fn suggestion_block_is_plain_text_in_other_comment_types() {
+            let comment = Comment::new(
+                "```suggestion\nx\n```".to_string(),
+                CommentType::Note,
+                Some(LineSide::New),
+            );
+            assert_eq!(comment.suggestion(), None);
+            assert_eq!(comment.display_line_count(), 3);
+        }
+
+        #[test]
+        fn deserializes_legacy_comment_without_thread_fields() {
+            let json = r#"{
+                "id": "1",
+                "content": "Old",
+                "comment_type": "note",
+                "created_at": "2024-01-01T00:00:00Z",
+                "line_context": null
+            }"#;
+            let comment: Comment = serde_json::from_str(json).unwrap();
+            assert!(!comment.resolved);
+            assert!(comment.replies.is_empty());
+        }
+
         #[test]
         fn new_with_range_creates_comment_with_line_range() {
             let range = LineRange::new(10, 15);
@@ -300,4 +575,22 @@ mod tests {
             assert_eq!(range.end, 15);
         }
     }
+
+    #[test]
+    fn should_sort_by_severity_keeping_file_order_within_a_type() {
+        // given
+        let mut comments = [
+            Comment::new("a".to_string(), CommentType::Note, None),
+            Comment::new("b".to_string(), CommentType::Issue, None),
+            Comment::new("c".to_string(), CommentType::Praise, None),
+            Comment::new("d".to_string(), CommentType::Issue, None),
+        ];
+
+        // when
+        comments.sort_by(|a, b| CommentSort::Severity.compare(a, b));
+
+        // then
+        let order: Vec<&str> = comments.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(order, vec!["b", "d", "a", "c"]);
+    }
 }
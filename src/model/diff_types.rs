@@ -1,7 +1,12 @@
 use ratatui::style::Style;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
 use std::path::PathBuf;
 
+use crate::hash::fnv1a_64;
+use crate::model::{BinarySizes, LineSide, StructuredFormat};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FileStatus {
@@ -22,8 +27,21 @@ impl FileStatus {
             FileStatus::Copied => 'C',
         }
     }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileStatus::Added => "added",
+            FileStatus::Modified => "modified",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Renamed => "renamed",
+            FileStatus::Copied => "copied",
+        }
+    }
 }
 
+/// Lines longer than this (in bytes) mark a file as minified/bundled
+pub const MINIFIED_LINE_LEN: usize = 1000;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineOrigin {
     Context,
@@ -40,6 +58,47 @@ pub struct DiffLine {
     /// Optional syntax-highlighted spans for this line
     /// If None, use the default diff coloring
     pub highlighted_spans: Option<Vec<(Style, String)>>,
+    /// The line is the last in its file and has no trailing newline
+    /// ("\\ No newline at end of file" in the diff)
+    pub no_newline_at_eof: bool,
+    /// The line ended with CRLF rather than LF
+    pub crlf: bool,
+    /// Byte ranges of `content` that differ from the paired line on the
+    /// other side of the change. Empty for context and unpaired lines.
+    pub changed_ranges: Vec<Range<usize>>,
+}
+
+/// Line terminator style of one side of a change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+impl LineEnding {
+    fn of<'a>(mut lines: impl Iterator<Item = &'a DiffLine>) -> Self {
+        let first = lines.next().is_some_and(|l| l.crlf);
+        if lines.all(|l| l.crlf == first) {
+            if first {
+                LineEnding::Crlf
+            } else {
+                LineEnding::Lf
+            }
+        } else {
+            LineEnding::Mixed
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::Crlf => write!(f, "CRLF"),
+            LineEnding::Mixed => write!(f, "mixed"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,6 +117,182 @@ pub struct DiffHunk {
     pub new_count: u32,
 }
 
+/// File-level changes that are invisible in the line content itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileIndicator {
+    NoNewlineAtEof,
+    NewlineAtEofAdded,
+    NewlineAtEofRemoved,
+    BomAdded,
+    BomRemoved,
+    /// The changed lines switched between valid and invalid UTF-8
+    EncodingChanged,
+    /// The changed lines are not valid UTF-8
+    NotUtf8,
+    /// Lines whose only change is their terminator
+    LineEndings {
+        from: LineEnding,
+        to: LineEnding,
+        lines: usize,
+    },
+}
+
+impl std::fmt::Display for FileIndicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileIndicator::NoNewlineAtEof => write!(f, "no newline at EOF"),
+            FileIndicator::NewlineAtEofAdded => write!(f, "newline at EOF added"),
+            FileIndicator::NewlineAtEofRemoved => write!(f, "newline at EOF removed"),
+            FileIndicator::BomAdded => write!(f, "BOM added"),
+            FileIndicator::BomRemoved => write!(f, "BOM removed"),
+            FileIndicator::EncodingChanged => write!(f, "encoding changed"),
+            FileIndicator::NotUtf8 => write!(f, "not UTF-8"),
+            FileIndicator::LineEndings { from, to, lines } => {
+                write!(f, "line endings {from}→{to} ({lines} lines)")
+            }
+        }
+    }
+}
+
+const BOM: char = '\u{feff}';
+
+impl DiffHunk {
+    /// Fingerprint of the hunk's lines, which identifies it across reloads
+    /// even when changes above it move its line numbers
+    pub fn fingerprint(&self) -> String {
+        let mut bytes = Vec::new();
+        self.push_content(&mut bytes);
+        format!("{:016x}", fnv1a_64(&bytes))
+    }
+
+    /// Number of added and deleted lines in the hunk
+    pub fn line_stats(&self) -> (usize, usize) {
+        let mut additions = 0;
+        let mut deletions = 0;
+        for line in &self.lines {
+            match line.origin {
+                LineOrigin::Addition => additions += 1,
+                LineOrigin::Deletion => deletions += 1,
+                LineOrigin::Context => {}
+            }
+        }
+        (additions, deletions)
+    }
+
+    fn push_content(&self, bytes: &mut Vec<u8>) {
+        for line in &self.lines {
+            bytes.push(match line.origin {
+                LineOrigin::Context => b' ',
+                LineOrigin::Addition => b'+',
+                LineOrigin::Deletion => b'-',
+            });
+            bytes.extend_from_slice(line.content.as_bytes());
+            bytes.push(b'\n');
+        }
+    }
+
+    /// Index pairs of deletions and the additions that replace them.
+    ///
+    /// Within each run of deletions followed by a run of additions, the
+    /// i-th deletion is paired with the i-th addition.
+    pub fn change_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        let mut idx = 0;
+        while idx < self.lines.len() {
+            if self.lines[idx].origin != LineOrigin::Deletion {
+                idx += 1;
+                continue;
+            }
+            let del_start = idx;
+            while idx < self.lines.len() && self.lines[idx].origin == LineOrigin::Deletion {
+                idx += 1;
+            }
+            let add_start = idx;
+            while idx < self.lines.len() && self.lines[idx].origin == LineOrigin::Addition {
+                idx += 1;
+            }
+            let count = (add_start - del_start).min(idx - add_start);
+            pairs.extend((0..count).map(|i| (del_start + i, add_start + i)));
+        }
+        pairs
+    }
+
+    fn is_line_ending_only(&self, (del, add): (usize, usize)) -> bool {
+        let (del, add) = (&self.lines[del], &self.lines[add]);
+        del.content == add.content && del.crlf != add.crlf
+    }
+
+    /// Turn deletion/addition pairs that differ only in line ending into
+    /// context lines. Returns how many pairs were collapsed.
+    fn collapse_line_ending_changes(&mut self) -> usize {
+        self.collapse_pairs(|hunk, pair| hunk.is_line_ending_only(pair))
+    }
+
+    /// Turn the deletion/addition pairs `is_mechanical` picks into context
+    /// lines showing the addition. Returns how many pairs were collapsed.
+    pub fn collapse_pairs(
+        &mut self,
+        is_mechanical: impl Fn(&DiffHunk, (usize, usize)) -> bool,
+    ) -> usize {
+        let collapsed: HashMap<usize, usize> = self
+            .change_pairs()
+            .into_iter()
+            .filter(|&pair| is_mechanical(self, pair))
+            .collect();
+        if collapsed.is_empty() {
+            return 0;
+        }
+
+        let old_lines = std::mem::take(&mut self.lines);
+        let mut idx = 0;
+        while idx < old_lines.len() {
+            if old_lines[idx].origin != LineOrigin::Deletion {
+                self.lines.push(old_lines[idx].clone());
+                idx += 1;
+                continue;
+            }
+            let del_start = idx;
+            while idx < old_lines.len() && old_lines[idx].origin == LineOrigin::Deletion {
+                idx += 1;
+            }
+            let add_start = idx;
+            while idx < old_lines.len() && old_lines[idx].origin == LineOrigin::Addition {
+                idx += 1;
+            }
+            let deletions = &old_lines[del_start..add_start];
+            let additions = &old_lines[add_start..idx];
+
+            // Walk the run position by position so the remaining changes stay
+            // on the correct side of each new context line
+            let mut pending_dels: Vec<DiffLine> = Vec::new();
+            let mut pending_adds: Vec<DiffLine> = Vec::new();
+            for pos in 0..deletions.len().max(additions.len()) {
+                if collapsed.contains_key(&(del_start + pos)) {
+                    let (del, add) = (&deletions[pos], &additions[pos]);
+                    self.lines.append(&mut pending_dels);
+                    self.lines.append(&mut pending_adds);
+                    self.lines.push(DiffLine {
+                        origin: LineOrigin::Context,
+                        content: add.content.clone(),
+                        old_lineno: del.old_lineno,
+                        new_lineno: add.new_lineno,
+                        highlighted_spans: None,
+                        no_newline_at_eof: add.no_newline_at_eof,
+                        crlf: add.crlf,
+                        changed_ranges: Vec::new(),
+                    });
+                    continue;
+                }
+                pending_dels.extend(deletions.get(pos).cloned());
+                pending_adds.extend(additions.get(pos).cloned());
+            }
+            self.lines.append(&mut pending_dels);
+            self.lines.append(&mut pending_adds);
+        }
+        collapsed.len()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DiffFile {
     pub old_path: Option<PathBuf>,
@@ -65,6 +300,21 @@ pub struct DiffFile {
     pub status: FileStatus,
     pub hunks: Vec<DiffHunk>,
     pub is_binary: bool,
+    /// Sizes of a binary file, shown in place of its diff
+    pub binary_sizes: BinarySizes,
+    /// Whether the diff has minified or bundled lines, see
+    /// [`has_minified_lines`]
+    pub minified: bool,
+}
+
+/// Whether `hunks` contain minified or bundled lines that are too long to
+/// read without wrapping. Computed once when a diff is loaded, as the view
+/// asks on every frame.
+pub fn has_minified_lines(hunks: &[DiffHunk]) -> bool {
+    hunks
+        .iter()
+        .flat_map(|h| &h.lines)
+        .any(|l| l.content.len() > MINIFIED_LINE_LEN)
 }
 
 impl DiffFile {
@@ -74,4 +324,425 @@ impl DiffFile {
             .or(self.old_path.as_ref())
             .expect("DiffFile must have at least one path")
     }
+
+    /// The path a renamed or copied file came from
+    pub fn renamed_from(&self) -> Option<&PathBuf> {
+        match self.status {
+            FileStatus::Renamed | FileStatus::Copied => self
+                .old_path
+                .as_ref()
+                .filter(|old| self.new_path.as_ref() != Some(*old)),
+            _ => None,
+        }
+    }
+
+    /// The path to show for the file: "old → new" when renamed or copied
+    pub fn path_label(&self) -> String {
+        match self.renamed_from() {
+            Some(old) => format!("{} → {}", old.display(), self.display_path().display()),
+            None => self.display_path().display().to_string(),
+        }
+    }
+
+    /// Whether the file holds prose (documentation) rather than code
+    pub fn is_prose(&self) -> bool {
+        const PROSE_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc", "org", "tex"];
+        self.display_path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| PROSE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+    }
+
+    /// Whether the file is an image, judging by its extension
+    pub fn is_image(&self) -> bool {
+        const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "ico"];
+        self.display_path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+    }
+
+    /// Cell delimiter for tabular data files (CSV/TSV), if this is one
+    pub fn tabular_delimiter(&self) -> Option<char> {
+        let ext = self
+            .display_path()
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(','),
+            "tsv" | "tab" => Some('\t'),
+            _ => None,
+        }
+    }
+
+    /// Structured document format (JSON/YAML), if this is one
+    pub fn structured_format(&self) -> Option<StructuredFormat> {
+        StructuredFormat::from_extension(self.display_path().extension()?.to_str()?)
+    }
+
+    /// Whether the file contains minified or bundled lines that are too long
+    /// to read without wrapping
+    pub fn is_minified(&self) -> bool {
+        self.minified
+    }
+
+    /// EOF newline, BOM and encoding changes worth flagging on the file header
+    pub fn indicators(&self) -> Vec<FileIndicator> {
+        let lines: Vec<&DiffLine> = self.hunks.iter().flat_map(|h| &h.lines).collect();
+        let is_old = |l: &&DiffLine| l.origin != LineOrigin::Addition;
+        let is_new = |l: &&DiffLine| l.origin != LineOrigin::Deletion;
+        let mut indicators = Vec::new();
+
+        let old_missing = lines
+            .iter()
+            .copied()
+            .filter(is_old)
+            .any(|l| l.no_newline_at_eof);
+        let new_missing = lines
+            .iter()
+            .copied()
+            .filter(is_new)
+            .any(|l| l.no_newline_at_eof);
+        match (old_missing, new_missing) {
+            (true, true) => indicators.push(FileIndicator::NoNewlineAtEof),
+            (false, true) if self.status == FileStatus::Added => {
+                indicators.push(FileIndicator::NoNewlineAtEof)
+            }
+            (false, true) => indicators.push(FileIndicator::NewlineAtEofRemoved),
+            (true, false) if self.status == FileStatus::Deleted => {
+                indicators.push(FileIndicator::NoNewlineAtEof)
+            }
+            (true, false) => indicators.push(FileIndicator::NewlineAtEofAdded),
+            (false, false) => {}
+        }
+
+        let old_bom = lines
+            .iter()
+            .copied()
+            .filter(is_old)
+            .find(|l| l.old_lineno == Some(1))
+            .map(|l| l.content.starts_with(BOM));
+        let new_bom = lines
+            .iter()
+            .copied()
+            .filter(is_new)
+            .find(|l| l.new_lineno == Some(1))
+            .map(|l| l.content.starts_with(BOM));
+        match (old_bom, new_bom) {
+            (Some(false), Some(true)) => indicators.push(FileIndicator::BomAdded),
+            (Some(true), Some(false)) => indicators.push(FileIndicator::BomRemoved),
+            _ => {}
+        }
+
+        // Lossy decoding turns invalid UTF-8 into replacement characters
+        let invalid = |origin: LineOrigin| {
+            lines
+                .iter()
+                .filter(|l| l.origin == origin)
+                .any(|l| l.content.contains(char::REPLACEMENT_CHARACTER))
+        };
+        let has = |origin: LineOrigin| lines.iter().any(|l| l.origin == origin);
+        let (old_invalid, new_invalid) =
+            (invalid(LineOrigin::Deletion), invalid(LineOrigin::Addition));
+        if old_invalid != new_invalid && has(LineOrigin::Deletion) && has(LineOrigin::Addition) {
+            indicators.push(FileIndicator::EncodingChanged);
+        } else if old_invalid || new_invalid {
+            indicators.push(FileIndicator::NotUtf8);
+        }
+
+        if let Some(change) = self.line_ending_change() {
+            indicators.push(change);
+        }
+
+        indicators
+    }
+
+    /// Summary of lines whose only change is LF↔CRLF, if there are any
+    pub fn line_ending_change(&self) -> Option<FileIndicator> {
+        let mut deletions = Vec::new();
+        let mut additions = Vec::new();
+        for hunk in &self.hunks {
+            for pair in hunk.change_pairs() {
+                if hunk.is_line_ending_only(pair) {
+                    deletions.push(&hunk.lines[pair.0]);
+                    additions.push(&hunk.lines[pair.1]);
+                }
+            }
+        }
+        if deletions.is_empty() {
+            return None;
+        }
+        Some(FileIndicator::LineEndings {
+            lines: deletions.len(),
+            from: LineEnding::of(deletions.into_iter()),
+            to: LineEnding::of(additions.into_iter()),
+        })
+    }
+
+    /// Hide line-ending-only changes by turning them into context lines.
+    /// Returns how many changed lines were hidden.
+    pub fn collapse_line_ending_changes(&mut self) -> usize {
+        self.hunks
+            .iter_mut()
+            .map(DiffHunk::collapse_line_ending_changes)
+            .sum()
+    }
+
+    /// Whether `line` on the given side is part of the diff (and can anchor a
+    /// line comment)
+    pub fn contains_line(&self, line: u32, side: LineSide) -> bool {
+        self.hunks.iter().flat_map(|hunk| &hunk.lines).any(|l| {
+            let lineno = match side {
+                LineSide::Old => l.old_lineno,
+                LineSide::New => l.new_lineno,
+            };
+            lineno == Some(line)
+        })
+    }
+
+    /// Number of added and deleted lines across all hunks
+    pub fn line_stats(&self) -> (usize, usize) {
+        self.hunks.iter().map(DiffHunk::line_stats).fold(
+            (0, 0),
+            |(additions, deletions), (hunk_additions, hunk_deletions)| {
+                (additions + hunk_additions, deletions + hunk_deletions)
+            },
+        )
+    }
+
+    /// Number of diff lines (context, additions and deletions) across all hunks
+    pub fn line_count(&self) -> usize {
+        self.hunks.iter().map(|hunk| hunk.lines.len()).sum()
+    }
+
+    /// Fingerprint of the diff content, used to detect files that changed
+    /// after being marked as reviewed.
+    pub fn content_hash(&self) -> String {
+        let mut bytes = Vec::new();
+        bytes.push(self.status.as_char() as u8);
+        for hunk in &self.hunks {
+            hunk.push_content(&mut bytes);
+        }
+        format!("{:016x}", fnv1a_64(&bytes))
+    }
+}
+
+/// Diffs for tests, with everything a test is not about left empty
+#[cfg(test)]
+pub(crate) mod test_diffs {
+    use super::*;
+
+    /// A line of `content`, without line numbers
+    pub(crate) fn line(origin: LineOrigin, content: &str) -> DiffLine {
+        DiffLine {
+            origin,
+            content: content.to_string(),
+            old_lineno: None,
+            new_lineno: None,
+            highlighted_spans: None,
+            no_newline_at_eof: false,
+            crlf: false,
+            changed_ranges: Vec::new(),
+        }
+    }
+
+    /// A line of `content` at the given line numbers
+    pub(crate) fn numbered_line(
+        origin: LineOrigin,
+        content: &str,
+        old_lineno: Option<u32>,
+        new_lineno: Option<u32>,
+    ) -> DiffLine {
+        DiffLine {
+            old_lineno,
+            new_lineno,
+            ..line(origin, content)
+        }
+    }
+
+    /// A hunk of `lines` starting at line 1 on both sides
+    pub(crate) fn hunk(lines: Vec<DiffLine>) -> DiffHunk {
+        hunk_at(1, 1, lines)
+    }
+
+    /// A hunk of `lines` starting at `old_start` and `new_start`, with the
+    /// counts and header they add up to
+    pub(crate) fn hunk_at(old_start: u32, new_start: u32, lines: Vec<DiffLine>) -> DiffHunk {
+        let count = |skipped| lines.iter().filter(|l| l.origin != skipped).count() as u32;
+        let old_count = count(LineOrigin::Addition);
+        let new_count = count(LineOrigin::Deletion);
+        DiffHunk {
+            header: format!("@@ -{old_start},{old_count} +{new_start},{new_count} @@"),
+            lines,
+            old_start,
+            old_count,
+            new_start,
+            new_count,
+        }
+    }
+
+    /// A modified file at `path`
+    pub(crate) fn modified_file(path: &str, hunks: Vec<DiffHunk>) -> DiffFile {
+        DiffFile {
+            old_path: Some(PathBuf::from(path)),
+            new_path: Some(PathBuf::from(path)),
+            status: FileStatus::Modified,
+            hunks,
+            is_binary: false,
+            binary_sizes: Default::default(),
+            minified: false,
+        }
+    }
+
+    /// A new file at `path` whose diff adds `contents` from `first_line` on
+    pub(crate) fn added_file(path: &str, first_line: u32, contents: &[&str]) -> DiffFile {
+        let lines = contents
+            .iter()
+            .zip(first_line..)
+            .map(|(content, lineno)| {
+                numbered_line(LineOrigin::Addition, content, None, Some(lineno))
+            })
+            .collect();
+        DiffFile {
+            old_path: None,
+            status: FileStatus::Added,
+            ..modified_file(path, vec![hunk_at(0, first_line, lines)])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_diffs::{hunk, line, modified_file};
+    use super::*;
+
+    fn file_with_lines(lines: Vec<DiffLine>) -> DiffFile {
+        modified_file("src/lib.rs", vec![hunk(lines)])
+    }
+
+    #[test]
+    fn should_label_renamed_files_with_both_paths() {
+        // given
+        let mut file = file_with_lines(Vec::new());
+        let modified = file.path_label();
+        file.status = FileStatus::Renamed;
+        file.old_path = Some(PathBuf::from("src/old.rs"));
+
+        // then
+        assert_eq!(modified, "src/lib.rs");
+        assert_eq!(file.path_label(), "src/old.rs → src/lib.rs");
+    }
+
+    #[test]
+    fn should_count_added_and_deleted_lines() {
+        // given
+        let file = file_with_lines(vec![
+            line(LineOrigin::Context, "fn main() {"),
+            line(LineOrigin::Deletion, "    old();"),
+            line(LineOrigin::Addition, "    new();"),
+            line(LineOrigin::Addition, "    more();"),
+        ]);
+
+        // when
+        let stats = file.line_stats();
+
+        // then
+        assert_eq!(stats, (2, 1));
+    }
+
+    #[test]
+    fn should_change_content_hash_when_lines_change() {
+        // given
+        let before = file_with_lines(vec![line(LineOrigin::Addition, "a")]);
+        let after = file_with_lines(vec![line(LineOrigin::Addition, "b")]);
+
+        // when / then
+        assert_eq!(before.content_hash(), before.clone().content_hash());
+        assert_ne!(before.content_hash(), after.content_hash());
+    }
+
+    fn crlf_line(origin: LineOrigin, content: &str) -> DiffLine {
+        DiffLine {
+            crlf: true,
+            ..line(origin, content)
+        }
+    }
+
+    #[test]
+    fn should_summarize_line_ending_only_changes() {
+        // given
+        let file = file_with_lines(vec![
+            line(LineOrigin::Deletion, "a"),
+            line(LineOrigin::Deletion, "b"),
+            crlf_line(LineOrigin::Addition, "a"),
+            crlf_line(LineOrigin::Addition, "b2"),
+        ]);
+
+        // when
+        let change = file.line_ending_change();
+
+        // then
+        assert_eq!(
+            change,
+            Some(FileIndicator::LineEndings {
+                from: LineEnding::Lf,
+                to: LineEnding::Crlf,
+                lines: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn should_collapse_line_ending_changes_into_context() {
+        // given
+        let mut file = file_with_lines(vec![
+            line(LineOrigin::Deletion, "a"),
+            line(LineOrigin::Deletion, "b"),
+            line(LineOrigin::Deletion, "c"),
+            crlf_line(LineOrigin::Addition, "a2"),
+            crlf_line(LineOrigin::Addition, "b"),
+            crlf_line(LineOrigin::Addition, "c2"),
+        ]);
+
+        // when
+        let hidden = file.collapse_line_ending_changes();
+
+        // then
+        assert_eq!(hidden, 1);
+        let lines: Vec<(LineOrigin, &str)> = file.hunks[0]
+            .lines
+            .iter()
+            .map(|l| (l.origin, l.content.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (LineOrigin::Deletion, "a"),
+                (LineOrigin::Addition, "a2"),
+                (LineOrigin::Context, "b"),
+                (LineOrigin::Deletion, "c"),
+                (LineOrigin::Addition, "c2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_flag_bom_removal_and_encoding_change() {
+        // given
+        let mut old_first = line(LineOrigin::Deletion, "\u{feff}caf\u{fffd}");
+        old_first.old_lineno = Some(1);
+        let mut new_first = line(LineOrigin::Addition, "café");
+        new_first.new_lineno = Some(1);
+        let file = file_with_lines(vec![old_first, new_first]);
+
+        // when
+        let indicators = file.indicators();
+
+        // then
+        assert_eq!(
+            indicators,
+            vec![FileIndicator::BomRemoved, FileIndicator::EncodingChanged]
+        );
+    }
 }
@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use super::diff_types::{DiffFile, LineOrigin};
+
+/// Fewest lines of code two added blocks must share to count as duplicated
+pub const MIN_DUPLICATE_LINES: usize = 6;
+
+/// Where an added block sits in the new version of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockLocation {
+    pub path: PathBuf,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Two added blocks in different files that read the same
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplication {
+    pub first: BlockLocation,
+    pub second: BlockLocation,
+    /// Lines of code the blocks share (blank and punctuation-only lines
+    /// are not counted)
+    pub lines: usize,
+    /// First shared line, trimmed, as a preview
+    pub preview: String,
+}
+
+/// A line of code in a run of added lines
+struct CodeLine {
+    lineno: u32,
+    text: String,
+}
+
+/// Find added blocks that were likely copied between files of the diff.
+///
+/// Lines are compared with whitespace collapsed, so reindented copies still
+/// match, and lines without letters or digits (`}`, `});`) are skipped. Blocks
+/// sharing at least `MIN_DUPLICATE_LINES` such lines in a row are reported,
+/// largest first.
+pub fn find_duplicate_blocks(files: &[DiffFile]) -> Vec<Duplication> {
+    // Runs of consecutive added lines, as (file index, code lines)
+    let mut runs: Vec<(usize, Vec<CodeLine>)> = Vec::new();
+    for (file_idx, file) in files.iter().enumerate() {
+        for hunk in &file.hunks {
+            let mut run = Vec::new();
+            for line in &hunk.lines {
+                if line.origin != LineOrigin::Addition {
+                    if line.origin == LineOrigin::Context && !run.is_empty() {
+                        runs.push((file_idx, std::mem::take(&mut run)));
+                    }
+                    continue;
+                }
+                if let (Some(text), Some(lineno)) = (normalized(&line.content), line.new_lineno) {
+                    run.push(CodeLine { lineno, text });
+                }
+            }
+            if !run.is_empty() {
+                runs.push((file_idx, run));
+            }
+        }
+    }
+
+    let mut windows: HashMap<Vec<&str>, Vec<(usize, usize)>> = HashMap::new();
+    for (run_idx, (_, lines)) in runs.iter().enumerate() {
+        for pos in 0..lines.len().saturating_sub(MIN_DUPLICATE_LINES - 1) {
+            let key = lines[pos..pos + MIN_DUPLICATE_LINES]
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect();
+            windows.entry(key).or_default().push((run_idx, pos));
+        }
+    }
+
+    // Matching windows of two runs on the same diagonal belong to one block
+    let mut diagonals: BTreeMap<(usize, usize, isize), Vec<usize>> = BTreeMap::new();
+    for occurrences in windows.values() {
+        for (idx, &(run_a, pos_a)) in occurrences.iter().enumerate() {
+            for &(run_b, pos_b) in &occurrences[idx + 1..] {
+                if runs[run_a].0 == runs[run_b].0 {
+                    continue;
+                }
+                let ((run_a, pos_a), (run_b, pos_b)) = if run_a < run_b {
+                    ((run_a, pos_a), (run_b, pos_b))
+                } else {
+                    ((run_b, pos_b), (run_a, pos_a))
+                };
+                diagonals
+                    .entry((run_a, run_b, pos_b as isize - pos_a as isize))
+                    .or_default()
+                    .push(pos_a);
+            }
+        }
+    }
+
+    let mut duplications = Vec::new();
+    for ((run_a, run_b, offset), mut starts) in diagonals {
+        starts.sort_unstable();
+        starts.dedup();
+        let mut idx = 0;
+        while idx < starts.len() {
+            let first = starts[idx];
+            let mut last = first;
+            while idx + 1 < starts.len() && starts[idx + 1] == last + 1 {
+                idx += 1;
+                last = starts[idx];
+            }
+            idx += 1;
+
+            let (file_a, lines_a) = &runs[run_a];
+            let (file_b, lines_b) = &runs[run_b];
+            let end = last + MIN_DUPLICATE_LINES - 1;
+            let other = |pos: usize| (pos as isize + offset) as usize;
+            duplications.push(Duplication {
+                first: BlockLocation {
+                    path: files[*file_a].display_path().clone(),
+                    start: lines_a[first].lineno,
+                    end: lines_a[end].lineno,
+                },
+                second: BlockLocation {
+                    path: files[*file_b].display_path().clone(),
+                    start: lines_b[other(first)].lineno,
+                    end: lines_b[other(end)].lineno,
+                },
+                lines: end - first + 1,
+                preview: lines_a[first].text.clone(),
+            });
+        }
+    }
+    duplications.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| a.first.path.cmp(&b.first.path))
+            .then_with(|| a.first.start.cmp(&b.first.start))
+    });
+    duplications
+}
+
+fn normalized(content: &str) -> Option<String> {
+    if !content.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    Some(content.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::added_file;
+
+    const BLOCK: &[&str] = &[
+        "let config = load_config(path)?;",
+        "let client = Client::new(&config);",
+        "",
+        "for item in items {",
+        "    client.send(item)?;",
+        "}",
+        "client.flush()?;",
+        "log::info!(\"sent {}\", items.len());",
+        "Ok(client)",
+    ];
+
+    #[test]
+    fn should_report_blocks_copied_between_files() {
+        // given
+        let reindented: Vec<String> = BLOCK.iter().map(|line| format!("    {line}")).collect();
+        let mut copy: Vec<&str> = vec!["fn other() {"];
+        copy.extend(reindented.iter().map(String::as_str));
+        let files = vec![
+            added_file("src/a.rs", 1, BLOCK),
+            added_file("src/b.rs", 10, &copy),
+        ];
+
+        // when
+        let duplications = find_duplicate_blocks(&files);
+
+        // then
+        assert_eq!(
+            duplications,
+            vec![Duplication {
+                first: BlockLocation {
+                    path: PathBuf::from("src/a.rs"),
+                    start: 1,
+                    end: 9,
+                },
+                second: BlockLocation {
+                    path: PathBuf::from("src/b.rs"),
+                    start: 11,
+                    end: 19,
+                },
+                lines: 7,
+                preview: BLOCK[0].to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_ignore_short_and_same_file_repeats() {
+        let files = vec![
+            added_file("src/a.rs", 1, &[BLOCK, BLOCK].concat()),
+            added_file("src/b.rs", 1, &BLOCK[..5]),
+        ];
+        assert!(find_duplicate_blocks(&files).is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+/// An issue-tracker reference found in comment text, e.g. `PROJ-123` or `#456`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueRef {
+    /// Byte range of the reference in the searched text
+    pub start: usize,
+    pub end: usize,
+    /// Project key (`PROJ`), or `#` for bare numeric references
+    pub key: String,
+    pub id: String,
+}
+
+/// Find issue references in `text`.
+///
+/// Recognizes Jira-style keys (an uppercase word of at least two characters, a
+/// dash and a number) and `#` followed by a number. References must not be
+/// glued to surrounding letters or digits.
+pub fn find_issue_refs(text: &str) -> Vec<IssueRef> {
+    let bytes = text.as_bytes();
+    let is_word = |idx: usize| bytes.get(idx).is_some_and(|b| b.is_ascii_alphanumeric());
+    let digits_from = |idx: usize| {
+        let mut end = idx;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+        end
+    };
+
+    let mut refs = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let boundary_before = idx == 0 || !is_word(idx - 1);
+        if bytes[idx] == b'#' && (idx == 0 || !is_word(idx - 1) && bytes[idx - 1] != b'&') {
+            let end = digits_from(idx + 1);
+            if end > idx + 1 && !is_word(end) {
+                refs.push(IssueRef {
+                    start: idx,
+                    end,
+                    key: "#".to_string(),
+                    id: text[idx + 1..end].to_string(),
+                });
+                idx = end;
+                continue;
+            }
+        } else if boundary_before && bytes[idx].is_ascii_uppercase() {
+            let mut key_end = idx + 1;
+            while key_end < bytes.len()
+                && (bytes[key_end].is_ascii_uppercase() || bytes[key_end].is_ascii_digit())
+            {
+                key_end += 1;
+            }
+            if key_end - idx >= 2 && bytes.get(key_end) == Some(&b'-') {
+                let end = digits_from(key_end + 1);
+                if end > key_end + 1 && !is_word(end) {
+                    refs.push(IssueRef {
+                        start: idx,
+                        end,
+                        key: text[idx..key_end].to_string(),
+                        id: text[key_end + 1..end].to_string(),
+                    });
+                    idx = end;
+                    continue;
+                }
+            }
+            idx = key_end;
+            continue;
+        }
+        idx += 1;
+    }
+    refs
+}
+
+/// URL templates used to turn issue references into links
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IssueLinker {
+    /// (key, template) pairs; key `*` matches any project key
+    templates: Vec<(String, String)>,
+}
+
+impl IssueLinker {
+    /// Parse a comma-separated list of `KEY=URL` templates, where the URL may
+    /// contain `{key}` and `{id}`, e.g.
+    /// `#=https://github.com/org/repo/issues/{id},PROJ=https://jira.example.com/browse/{key}-{id}`
+    pub fn parse(spec: &str) -> Self {
+        let templates = spec
+            .split(',')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(key, url)| (key.trim().to_string(), url.trim().to_string()))
+            .filter(|(key, url)| !key.is_empty() && !url.is_empty())
+            .collect();
+        Self { templates }
+    }
+
+    /// Templates from the `TUICR_ISSUE_URLS` environment variable
+    pub fn from_env() -> Self {
+        std::env::var("TUICR_ISSUE_URLS")
+            .map(|spec| Self::parse(&spec))
+            .unwrap_or_default()
+    }
+
+    pub fn url_for(&self, issue: &IssueRef) -> Option<String> {
+        let (_, template) = self
+            .templates
+            .iter()
+            .find(|(key, _)| *key == issue.key)
+            .or_else(|| {
+                self.templates
+                    .iter()
+                    .find(|(key, _)| key == "*" && issue.key != "#")
+            })?;
+        Some(
+            template
+                .replace("{key}", &issue.key)
+                .replace("{id}", &issue.id),
+        )
+    }
+
+    /// Replace references that have a URL template with Markdown links
+    pub fn expand(&self, text: &str) -> String {
+        if self.templates.is_empty() {
+            return text.to_string();
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for issue in find_issue_refs(text) {
+            if let Some(url) = self.url_for(&issue) {
+                out.push_str(&text[last..issue.start]);
+                out.push_str(&format!("[{}]({url})", &text[issue.start..issue.end]));
+                last = issue.end;
+            }
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(text: &str) -> Vec<String> {
+        find_issue_refs(text)
+            .iter()
+            .map(|r| text[r.start..r.end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn should_find_jira_keys_and_numeric_refs() {
+        assert_eq!(
+            refs("Fixes PROJ-123 and #456 (see AB2-7)."),
+            vec!["PROJ-123", "#456", "AB2-7"]
+        );
+    }
+
+    #[test]
+    fn should_ignore_refs_glued_to_words() {
+        assert!(refs("abc#12 x-1 A-1 PROJ-12a &#38; PROJ-").is_empty());
+    }
+
+    #[test]
+    fn should_expand_refs_with_templates() {
+        // given
+        let linker = IssueLinker::parse(
+            "#=https://github.com/o/r/issues/{id}, *=https://jira.example.com/browse/{key}-{id}",
+        );
+
+        // when
+        let text = linker.expand("PROJ-1 and #2");
+
+        // then
+        assert_eq!(
+            text,
+            "[PROJ-1](https://jira.example.com/browse/PROJ-1) and [#2](https://github.com/o/r/issues/2)"
+        );
+    }
+
+    #[test]
+    fn should_leave_refs_without_template_untouched() {
+        // given
+        let linker = IssueLinker::parse("PROJ=https://jira.example.com/browse/PROJ-{id}");
+
+        // when
+        let text = linker.expand("OTHER-1 and #2");
+
+        // then
+        assert_eq!(text, "OTHER-1 and #2");
+    }
+}
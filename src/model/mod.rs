@@ -1,7 +1,43 @@
+pub mod anchoring;
+pub mod binary;
+pub mod brackets;
+pub mod codeowners;
 pub mod comment;
 pub mod diff_types;
+pub mod duplicates;
+pub mod issue_ref;
+pub mod moved_code;
+pub mod renames;
 pub mod review;
+pub mod search;
+pub mod stats;
+pub mod structural_diff;
+pub mod suggestion;
+pub mod todo_markers;
+pub mod word_diff;
 
-pub use comment::{Comment, CommentType, LineRange, LineSide};
-pub use diff_types::{DiffFile, DiffHunk, DiffLine, FileStatus, LineOrigin};
-pub use review::{ReviewSession, SessionDiffSource};
+pub use anchoring::capture_context;
+pub use binary::{BinarySizes, format_size};
+pub use brackets::find_matching_bracket;
+pub use codeowners::CodeOwners;
+pub use comment::{
+    Comment, CommentLine, CommentReply, CommentSort, CommentType, LineRange, LineSide,
+    content_fingerprint,
+};
+pub use diff_types::{DiffFile, DiffHunk, DiffLine, FileStatus, LineOrigin, has_minified_lines};
+pub use duplicates::find_duplicate_blocks;
+pub use issue_ref::{IssueLinker, find_issue_refs};
+pub use moved_code::hide_moved_hunks;
+pub use renames::{Rename, collapse_renames};
+pub use review::{
+    ExportPayload, FileReview, HistoryAction, PendingExport, PublishedContent, ReviewAnchor,
+    ReviewPosition, ReviewSession, ReviewState, SessionDiffSource,
+};
+pub use search::SearchPattern;
+pub use stats::{ReviewStats, review_stats};
+pub use structural_diff::{StructuralChange, StructuredFormat, structural_diff};
+pub use suggestion::apply_suggestion;
+pub use todo_markers::{TodoMarker, new_todo_markers};
+pub use word_diff::{
+    WordChange, WordSegment, diff_code_chunks, diff_words, mark_intraline_changes,
+};
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::diff_types::{DiffFile, DiffHunk, DiffLine, LineOrigin};
+
+/// Remove the hunks that hold no new code, to focus a large refactor on
+/// what it really adds. A hunk goes when each of its changed lines is
+///
+/// - an addition that is deleted elsewhere in the diff (moved code), or that
+///   `is_pre_existing` says predates the change (e.g. by blame);
+/// - a deletion that is added elsewhere in the diff (the other end of a move).
+///
+/// Lines are compared with surrounding whitespace trimmed, so reindented
+/// code counts as moved; blank lines are ignored. Returns how many hunks were
+/// removed.
+pub fn hide_moved_hunks(
+    files: &mut [DiffFile],
+    is_pre_existing: impl Fn(&Path, &DiffLine) -> bool,
+) -> usize {
+    let changed = |origin: LineOrigin| -> HashSet<String> {
+        files
+            .iter()
+            .flat_map(|file| &file.hunks)
+            .flat_map(|hunk| &hunk.lines)
+            .filter(|line| line.origin == origin)
+            .filter_map(|line| normalized(line).map(str::to_string))
+            .collect()
+    };
+    let deleted = changed(LineOrigin::Deletion);
+    let added = changed(LineOrigin::Addition);
+
+    let mut hidden = 0;
+    for file in files.iter_mut() {
+        let path = file.display_path().clone();
+        let before = file.hunks.len();
+        file.hunks.retain(|hunk| {
+            !is_old_code(hunk, |line| match line.origin {
+                LineOrigin::Addition => {
+                    normalized(line).is_some_and(|text| deleted.contains(text))
+                        || is_pre_existing(&path, line)
+                }
+                LineOrigin::Deletion => normalized(line).is_some_and(|text| added.contains(text)),
+                LineOrigin::Context => true,
+            })
+        });
+        hidden += before - file.hunks.len();
+    }
+    hidden
+}
+
+/// Whether a hunk changes at least one non-blank line and all of them are old
+fn is_old_code(hunk: &DiffHunk, is_old: impl Fn(&DiffLine) -> bool) -> bool {
+    let mut changes = hunk
+        .lines
+        .iter()
+        .filter(|line| line.origin != LineOrigin::Context && normalized(line).is_some())
+        .peekable();
+    changes.peek().is_some() && changes.all(is_old)
+}
+
+fn normalized(line: &DiffLine) -> Option<&str> {
+    Some(line.content.trim()).filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::{self, line, modified_file};
+
+    fn hunk(lines: &[(LineOrigin, &str)]) -> DiffHunk {
+        test_diffs::hunk(
+            lines
+                .iter()
+                .map(|(origin, content)| line(*origin, content))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn should_hide_both_ends_of_moved_code() {
+        // given
+        use LineOrigin::*;
+        let mut files = vec![
+            modified_file(
+                "src/old.rs",
+                vec![hunk(&[(Deletion, "fn helper() {}"), (Deletion, "")])],
+            ),
+            modified_file(
+                "src/new.rs",
+                vec![
+                    hunk(&[(Context, "mod a;"), (Addition, "    fn helper() {}")]),
+                    hunk(&[(Addition, "fn genuinely_new() {}")]),
+                ],
+            ),
+        ];
+
+        // when
+        let hidden = hide_moved_hunks(&mut files, |_, _| false);
+
+        // then
+        assert_eq!(hidden, 2);
+        assert!(files[0].hunks.is_empty());
+        assert_eq!(files[1].hunks.len(), 1);
+        assert_eq!(files[1].hunks[0].lines[0].content, "fn genuinely_new() {}");
+    }
+
+    #[test]
+    fn should_hide_pre_existing_additions() {
+        // given
+        use LineOrigin::*;
+        let mut files = vec![modified_file(
+            "src/lib.rs",
+            vec![
+                hunk(&[(Addition, "from base"), (Addition, "")]),
+                hunk(&[(Addition, "from base"), (Addition, "fresh")]),
+            ],
+        )];
+
+        // when
+        let hidden = hide_moved_hunks(&mut files, |_, line| line.content == "from base");
+
+        // then
+        assert_eq!(hidden, 1);
+        assert_eq!(files[0].hunks[0].lines[1].content, "fresh");
+    }
+
+    #[test]
+    fn should_keep_hunks_that_only_change_blank_lines() {
+        let mut files = vec![modified_file(
+            "src/lib.rs",
+            vec![hunk(&[(LineOrigin::Addition, "   ")])],
+        )];
+        assert_eq!(hide_moved_hunks(&mut files, |_, _| true), 0);
+    }
+}
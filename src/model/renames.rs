@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use super::diff_types::{DiffFile, DiffHunk};
+
+/// Lines of a file that must swap the same identifiers for it to count as a
+/// systematic rename rather than a one-off edit
+const MIN_RENAME_LINES: usize = 2;
+
+/// An identifier replaced consistently throughout a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub old: String,
+    pub new: String,
+    /// Changed lines that only apply this and other renames of the file
+    pub lines: usize,
+}
+
+/// Split `text` into identifiers and single other characters
+fn tokens(text: &str) -> Vec<&str> {
+    let is_ident = |ch: char| ch.is_alphanumeric() || ch == '_';
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for (idx, ch) in text.char_indices() {
+        if is_ident(ch) {
+            continue;
+        }
+        if start < idx {
+            tokens.push(&text[start..idx]);
+        }
+        tokens.push(&text[idx..idx + ch.len_utf8()]);
+        start = idx + ch.len_utf8();
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn is_identifier(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+}
+
+/// The identifiers `new` has in place of those of `old`, or `None` when the
+/// lines differ in anything else
+fn swapped_identifiers<'a>(old: &'a str, new: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let (old, new) = (tokens(old), tokens(new));
+    if old.len() != new.len() {
+        return None;
+    }
+    let mut swaps = Vec::new();
+    for (old, new) in old.into_iter().zip(new) {
+        if old == new {
+            continue;
+        }
+        if !is_identifier(old) || !is_identifier(new) {
+            return None;
+        }
+        if !swaps.contains(&(old, new)) {
+            swaps.push((old, new));
+        }
+    }
+    Some(swaps).filter(|swaps| !swaps.is_empty())
+}
+
+/// The identifier swaps of each changed line pair of `hunk`
+fn hunk_swaps(hunk: &DiffHunk) -> impl Iterator<Item = ((usize, usize), Vec<(&str, &str)>)> {
+    hunk.change_pairs().into_iter().filter_map(|(del, add)| {
+        let swaps = swapped_identifiers(&hunk.lines[del].content, &hunk.lines[add].content)?;
+        Some(((del, add), swaps))
+    })
+}
+
+/// Renames in `file`: identifiers that changed line pairs replace by the
+/// same new name every time, on at least [`MIN_RENAME_LINES`] lines, and
+/// whose new name replaces nothing else
+pub fn find_renames(file: &DiffFile) -> Vec<Rename> {
+    let mut replacements: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+    let mut sources: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+    for hunk in &file.hunks {
+        for (_, swaps) in hunk_swaps(hunk) {
+            for (old, new) in swaps {
+                *replacements.entry(old).or_default().entry(new).or_default() += 1;
+                *sources.entry(new).or_default().entry(old).or_default() += 1;
+            }
+        }
+    }
+
+    let mut renames: Vec<Rename> = replacements
+        .into_iter()
+        .filter_map(|(old, news)| {
+            let [(new, lines)] = news.into_iter().collect::<Vec<_>>()[..] else {
+                return None;
+            };
+            (lines >= MIN_RENAME_LINES && sources[new].len() == 1).then(|| Rename {
+                old: old.to_string(),
+                new: new.to_string(),
+                lines,
+            })
+        })
+        .collect();
+    renames.sort_by(|a, b| a.old.cmp(&b.old));
+    renames
+}
+
+/// Turn the changed lines of `file` that only apply its renames into context
+/// lines, to leave the structural changes. Returns the renames with the
+/// number of lines collapsed for each.
+pub fn collapse_renames(file: &mut DiffFile) -> Vec<Rename> {
+    let mut renames = find_renames(file);
+    if renames.is_empty() {
+        return renames;
+    }
+    let mapping: HashMap<String, String> = renames
+        .iter()
+        .map(|rename| (rename.old.clone(), rename.new.clone()))
+        .collect();
+
+    let mut collapsed: HashMap<String, usize> = HashMap::new();
+    for hunk in &mut file.hunks {
+        let pairs: HashMap<(usize, usize), Vec<String>> = hunk_swaps(hunk)
+            .filter(|(_, swaps)| {
+                swaps
+                    .iter()
+                    .all(|(old, new)| mapping.get(*old).is_some_and(|to| to == new))
+            })
+            .map(|(pair, swaps)| (pair, swaps.iter().map(|(old, _)| old.to_string()).collect()))
+            .collect();
+        for olds in pairs.values() {
+            for old in olds {
+                *collapsed.entry(old.clone()).or_default() += 1;
+            }
+        }
+        hunk.collapse_pairs(|_, pair| pairs.contains_key(&pair));
+    }
+
+    for rename in &mut renames {
+        rename.lines = collapsed.get(&rename.old).copied().unwrap_or(0);
+    }
+    renames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::LineOrigin;
+    use crate::model::diff_types::test_diffs::{hunk, modified_file, numbered_line};
+
+    fn file(lines: &[(LineOrigin, &str)]) -> DiffFile {
+        let lines = lines
+            .iter()
+            .zip(1..)
+            .map(|((origin, content), lineno)| {
+                numbered_line(*origin, content, Some(lineno), Some(lineno))
+            })
+            .collect();
+        modified_file("src/lib.rs", vec![hunk(lines)])
+    }
+
+    #[test]
+    fn should_find_identifiers_renamed_consistently() {
+        // given
+        use LineOrigin::*;
+        let file = file(&[
+            (Deletion, "let total = count + 1;"),
+            (Deletion, "print(total);"),
+            (Deletion, "let x = 1;"),
+            (Addition, "let sum = count + 1;"),
+            (Addition, "print(sum);"),
+            (Addition, "let y = 1;"),
+        ]);
+
+        // when
+        let renames = find_renames(&file);
+
+        // then
+        assert_eq!(
+            renames,
+            vec![Rename {
+                old: "total".to_string(),
+                new: "sum".to_string(),
+                lines: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn should_collapse_lines_that_only_rename() {
+        // given
+        use LineOrigin::*;
+        let mut file = file(&[
+            (Deletion, "fn load(cfg: Config) {"),
+            (Deletion, "    parse(cfg, 1)"),
+            (Addition, "fn load(config: Config) {"),
+            (Addition, "    parse(config, 2)"),
+            (Deletion, "    check(cfg);"),
+            (Addition, "    check(config);"),
+        ]);
+
+        // when
+        let renames = collapse_renames(&mut file);
+
+        // then
+        assert_eq!(renames[0].lines, 2);
+        let lines: Vec<(LineOrigin, &str)> = file.hunks[0]
+            .lines
+            .iter()
+            .map(|line| (line.origin, line.content.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (Context, "fn load(config: Config) {"),
+                (Deletion, "    parse(cfg, 1)"),
+                (Addition, "    parse(config, 2)"),
+                (Context, "    check(config);"),
+            ]
+        );
+    }
+}
@@ -1,10 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
-use super::comment::Comment;
-use super::diff_types::FileStatus;
+use super::anchoring;
+use super::comment::{Comment, CommentSort, CommentType, LineRange, LineSide, content_fingerprint};
+use super::diff_types::{DiffFile, FileStatus};
+
+/// Format version written to new sessions; see `persistence::migration`
+pub const SESSION_VERSION: &str = "1.6";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileReview {
@@ -13,6 +17,56 @@ pub struct FileReview {
     pub status: FileStatus,
     pub file_comments: Vec<Comment>,
     pub line_comments: HashMap<u32, Vec<Comment>>,
+    /// Hash of the diff content last seen for this file
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Pinned files are listed in a section at the top of the file list
+    #[serde(default)]
+    pub pinned: bool,
+    /// Skipped files are excluded from review progress
+    #[serde(default)]
+    pub skipped: bool,
+    /// Filtered files are only listed under the `Filtered` file list tab
+    #[serde(default)]
+    pub filtered: bool,
+    /// New-side start line of each hunk whose preceding context is expanded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expanded_context: Vec<u32>,
+    /// Time spent with the cursor in this file, in milliseconds
+    #[serde(default)]
+    pub time_spent_ms: u64,
+    /// Fingerprints of hunks marked reviewed one at a time; cleared once
+    /// the whole file is reviewed
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub reviewed_hunks: BTreeSet<String>,
+    /// Added and context lines the cursor passed over (`:set dimviewed`),
+    /// as sorted, disjoint ranges of new-side line numbers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub viewed_lines: Vec<LineRange>,
+    /// Deleted lines the cursor passed over, as ranges of old-side numbers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub viewed_deleted_lines: Vec<LineRange>,
+}
+
+/// The parts of a file review that undo puts back: whether it is reviewed
+/// and its comments, but not e.g. the time spent or expanded context
+#[derive(Debug, Clone)]
+pub struct ReviewState {
+    reviewed: bool,
+    reviewed_hunks: BTreeSet<String>,
+    file_comments: Vec<Comment>,
+    line_comments: HashMap<u32, Vec<Comment>>,
+    /// Only kept by `marked_state`, so that undoing e.g. a comment does not
+    /// also undo a later pin
+    flags: Option<FileFlags>,
+}
+
+/// The per-file flags set from the file list
+#[derive(Debug, Clone, Copy)]
+struct FileFlags {
+    pinned: bool,
+    skipped: bool,
+    filtered: bool,
 }
 
 impl FileReview {
@@ -23,6 +77,15 @@ impl FileReview {
             status,
             file_comments: Vec::new(),
             line_comments: HashMap::new(),
+            content_hash: None,
+            pinned: false,
+            skipped: false,
+            filtered: false,
+            expanded_context: Vec::new(),
+            time_spent_ms: 0,
+            reviewed_hunks: BTreeSet::new(),
+            viewed_lines: Vec::new(),
+            viewed_deleted_lines: Vec::new(),
         }
     }
 
@@ -37,6 +100,195 @@ impl FileReview {
     pub fn add_line_comment(&mut self, line: u32, comment: Comment) {
         self.line_comments.entry(line).or_default().push(comment);
     }
+
+    /// Move line comments to where the lines they were made on are in
+    /// `file`'s diff now, and flag those whose lines are gone as orphaned.
+    /// Comments made without their lines stay put. Returns whether any
+    /// comment moved or changed flag.
+    pub fn reanchor_comments(&mut self, file: &DiffFile) -> bool {
+        let mut changed = false;
+        let mut lines: Vec<u32> = self.line_comments.keys().copied().collect();
+        lines.sort_unstable();
+        let mut anchored: HashMap<u32, Vec<Comment>> = HashMap::new();
+        for line in lines {
+            for mut comment in self.line_comments.remove(&line).unwrap_or_default() {
+                let Some(context) = &comment.line_context else {
+                    anchored.entry(line).or_default().push(comment);
+                    continue;
+                };
+                let side = comment.side.unwrap_or(LineSide::New);
+                let range = comment.line_range.unwrap_or(LineRange::single(line));
+                let target = match anchoring::relocate(file, context, range, side) {
+                    Some(moved) => {
+                        changed |= moved != range || comment.orphaned;
+                        comment.orphaned = false;
+                        if comment.line_range.is_some() {
+                            comment.line_range = Some(moved);
+                        }
+                        if let Some(context) = &mut comment.line_context {
+                            match side {
+                                LineSide::New => context.new_line = Some(moved.start),
+                                LineSide::Old => context.old_line = Some(moved.start),
+                            }
+                        }
+                        moved.end
+                    }
+                    None => {
+                        changed |= !comment.orphaned;
+                        comment.orphaned = true;
+                        line
+                    }
+                };
+                anchored.entry(target).or_default().push(comment);
+            }
+        }
+        self.line_comments = anchored;
+        changed
+    }
+
+    pub fn find_comment_mut(&mut self, id: &str) -> Option<&mut Comment> {
+        self.file_comments
+            .iter_mut()
+            .chain(self.line_comments.values_mut().flatten())
+            .find(|c| c.id == id)
+    }
+
+    pub fn review_state(&self) -> ReviewState {
+        ReviewState {
+            reviewed: self.reviewed,
+            reviewed_hunks: self.reviewed_hunks.clone(),
+            file_comments: self.file_comments.clone(),
+            line_comments: self.line_comments.clone(),
+            flags: None,
+        }
+    }
+
+    /// Like `review_state`, but also keeping the pinned, skipped and filtered
+    /// flags, for edits that change them
+    pub fn marked_state(&self) -> ReviewState {
+        ReviewState {
+            flags: Some(self.flags()),
+            ..self.review_state()
+        }
+    }
+
+    fn flags(&self) -> FileFlags {
+        FileFlags {
+            pinned: self.pinned,
+            skipped: self.skipped,
+            filtered: self.filtered,
+        }
+    }
+
+    /// Put back an earlier `state`, returning the one it replaces
+    pub fn restore(&mut self, state: ReviewState) -> ReviewState {
+        let mut replaced = self.review_state();
+        if let Some(flags) = state.flags {
+            replaced.flags = Some(self.flags());
+            self.pinned = flags.pinned;
+            self.skipped = flags.skipped;
+            self.filtered = flags.filtered;
+        }
+        self.reviewed = state.reviewed;
+        self.reviewed_hunks = state.reviewed_hunks;
+        self.file_comments = state.file_comments;
+        self.line_comments = state.line_comments;
+        replaced
+    }
+
+    /// Remove a comment by id. Returns true if it was found.
+    pub fn remove_comment(&mut self, id: &str) -> bool {
+        if let Some(idx) = self.file_comments.iter().position(|c| c.id == id) {
+            self.file_comments.remove(idx);
+            return true;
+        }
+        let mut emptied = None;
+        let mut removed = false;
+        for (line, comments) in self.line_comments.iter_mut() {
+            if let Some(idx) = comments.iter().position(|c| c.id == id) {
+                comments.remove(idx);
+                removed = true;
+                if comments.is_empty() {
+                    emptied = Some(*line);
+                }
+                break;
+            }
+        }
+        if let Some(line) = emptied {
+            self.line_comments.remove(&line);
+        }
+        removed
+    }
+
+    /// Record that the cursor passed over the diff line with these line
+    /// numbers: its new-side number, or the old one of a deleted line
+    pub fn mark_line_viewed(&mut self, old_lineno: Option<u32>, new_lineno: Option<u32>) {
+        let (ranges, line) = match (new_lineno, old_lineno) {
+            (Some(line), _) => (&mut self.viewed_lines, line),
+            (None, Some(line)) => (&mut self.viewed_deleted_lines, line),
+            (None, None) => return,
+        };
+        let idx = ranges.partition_point(|range| range.end.saturating_add(1) < line);
+        match ranges.get_mut(idx) {
+            Some(range) if range.start <= line.saturating_add(1) => {
+                range.start = range.start.min(line);
+                range.end = range.end.max(line);
+                let end = range.end;
+                if ranges
+                    .get(idx + 1)
+                    .is_some_and(|next| next.start <= end.saturating_add(1))
+                {
+                    ranges[idx].end = ranges.remove(idx + 1).end;
+                }
+            }
+            _ => ranges.insert(idx, LineRange::single(line)),
+        }
+    }
+
+    pub fn is_line_viewed(&self, old_lineno: Option<u32>, new_lineno: Option<u32>) -> bool {
+        let (ranges, line) = match (new_lineno, old_lineno) {
+            (Some(line), _) => (&self.viewed_lines, line),
+            (None, Some(line)) => (&self.viewed_deleted_lines, line),
+            (None, None) => return false,
+        };
+        ranges.iter().any(|range| range.contains(line))
+    }
+}
+
+/// Where the reviewer was when the session was last saved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewPosition {
+    pub path: PathBuf,
+    pub line: Option<u32>,
+}
+
+impl std::fmt::Display for ReviewPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.path.display()),
+            None => write!(f, "{}", self.path.display()),
+        }
+    }
+}
+
+/// The revisions a review was performed against, which its line numbers
+/// refer to: full commit ids, or change ids for jj
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewAnchor {
+    /// Old side of the diff
+    pub base: String,
+    /// New side of the diff; `None` for uncommitted working tree changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+}
+
+impl std::fmt::Display for ReviewAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.head {
+            Some(head) => write!(f, "base {}, head {head}", self.base),
+            None => write!(f, "base {}, head working tree", self.base),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -48,6 +300,86 @@ pub enum SessionDiffSource {
     CommitRange,
 }
 
+/// A review action recorded in the session history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Reviewed,
+    Unreviewed,
+    Skipped,
+    Unskipped,
+    Pinned,
+    Unpinned,
+    Filtered,
+    Unfiltered,
+    CommentAdded,
+    CommentEdited,
+    CommentDeleted,
+    ReplyAdded,
+}
+
+impl HistoryAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Reviewed => "marked reviewed",
+            Self::Unreviewed => "marked unreviewed",
+            Self::Skipped => "skipped",
+            Self::Unskipped => "unskipped",
+            Self::Pinned => "pinned",
+            Self::Unpinned => "unpinned",
+            Self::Filtered => "filtered",
+            Self::Unfiltered => "unfiltered",
+            Self::CommentAdded => "comment added",
+            Self::CommentEdited => "comment edited",
+            Self::CommentDeleted => "comment deleted",
+            Self::ReplyAdded => "reply added",
+        }
+    }
+}
+
+/// One timestamped entry of the session's audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub at: DateTime<Utc>,
+    pub action: HistoryAction,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub line: Option<u32>,
+}
+
+/// A forge export that could not be delivered, kept for `:retry-export`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingExport {
+    /// Forge name, e.g. "bitbucket"
+    pub forge: String,
+    /// Host whose credentials are used
+    pub host: String,
+    /// URL the payloads are POSTed to, also the key under which delivered
+    /// comments are marked as published
+    pub endpoint: String,
+    /// Requests not yet accepted by the forge, in posting order
+    pub payloads: Vec<ExportPayload>,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// A comment (or the session notes) as sent in an export request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedContent {
+    /// None for the session notes
+    pub comment_id: Option<String>,
+    /// Fingerprint of the published content
+    pub fingerprint: String,
+}
+
+/// One request of a forge export and what it publishes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportPayload {
+    /// A single comment, or every comment of a review posted in one request
+    pub publishes: Vec<PublishedContent>,
+    /// JSON request body
+    pub body: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReviewSession {
     pub id: String,
@@ -60,10 +392,39 @@ pub struct ReviewSession {
     pub diff_source: SessionDiffSource,
     #[serde(default)]
     pub commit_range: Option<Vec<String>>,
+    /// The `--range` expression `commit_range` was resolved from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    /// Revisions of the diff last loaded for the review
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<ReviewAnchor>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub files: HashMap<PathBuf, FileReview>,
     pub session_notes: Option<String>,
+    #[serde(default)]
+    pub last_position: Option<ReviewPosition>,
+    /// Whether review actions are appended to `history`
+    #[serde(default)]
+    pub record_history: bool,
+    /// Audit trail of review actions, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<HistoryEntry>,
+    /// Forge exports that failed for network reasons
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_exports: Vec<PendingExport>,
+    /// Directories collapsed in the file list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collapsed_dirs: Vec<String>,
+    /// Like `Comment::published`, for the session notes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes_published: BTreeMap<String, String>,
+    /// Order of the comments panel and exports
+    #[serde(default)]
+    pub comment_sort: CommentSort,
+    /// Syntax highlighting language forced per file with `:lang`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub languages: BTreeMap<PathBuf, String>,
 }
 
 impl ReviewSession {
@@ -76,33 +437,176 @@ impl ReviewSession {
         let now = Utc::now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
-            version: "1.2".to_string(),
+            version: SESSION_VERSION.to_string(),
             repo_path,
             branch_name,
             base_commit,
             diff_source,
             commit_range: None,
+            range: None,
+            anchor: None,
             created_at: now,
             updated_at: now,
             files: HashMap::new(),
             session_notes: None,
+            last_position: None,
+            record_history: false,
+            history: Vec::new(),
+            pending_exports: Vec::new(),
+            collapsed_dirs: Vec::new(),
+            notes_published: BTreeMap::new(),
+            comment_sort: CommentSort::default(),
+            languages: BTreeMap::new(),
+        }
+    }
+
+    /// Append an action to the audit trail, if recording is enabled
+    pub fn record(&mut self, action: HistoryAction, path: &Path, line: Option<u32>) {
+        if !self.record_history {
+            return;
         }
+        self.history.push(HistoryEntry {
+            at: Utc::now(),
+            action,
+            path: path.to_path_buf(),
+            line,
+        });
+    }
+
+    /// Record that a comment (or the session notes, for `None`) was published
+    /// to `target` with the given content fingerprint
+    pub fn mark_published(&mut self, target: &str, comment_id: Option<&str>, fingerprint: &str) {
+        let published = match comment_id {
+            Some(id) => match self
+                .files
+                .values_mut()
+                .find_map(|review| review.find_comment_mut(id))
+            {
+                Some(comment) => &mut comment.published,
+                None => return,
+            },
+            None => &mut self.notes_published,
+        };
+        published.insert(target.to_string(), fingerprint.to_string());
+    }
+
+    /// Whether the current session notes were published to `target`
+    pub fn notes_published_to(&self, target: &str) -> bool {
+        self.session_notes.as_ref().is_some_and(|notes| {
+            self.notes_published.get(target) == Some(&content_fingerprint(notes))
+        })
+    }
+
+    /// Whether anything was ever published, so unpublished comments are
+    /// worth flagging as drafts
+    pub fn has_published(&self) -> bool {
+        !self.notes_published.is_empty()
+            || self.files.values().any(|review| {
+                review
+                    .file_comments
+                    .iter()
+                    .chain(review.line_comments.values().flatten())
+                    .any(|c| !c.published.is_empty())
+            })
     }
 
     pub fn reviewed_count(&self) -> usize {
         self.files.values().filter(|f| f.reviewed).count()
     }
 
+    /// Files neither reviewed nor skipped, sorted by path
+    pub fn unreviewed_files(&self) -> Vec<&PathBuf> {
+        let mut paths: Vec<&PathBuf> = self
+            .files
+            .iter()
+            .filter(|(_, review)| !review.reviewed && !review.skipped)
+            .map(|(path, _)| path)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Unresolved issue comments, the ones that block merging, as their
+    /// file and line (`None` for file comments) sorted by location
+    pub fn open_issues(&self) -> Vec<(&PathBuf, Option<u32>)> {
+        let is_open = |c: &Comment| c.comment_type == CommentType::Issue && !c.resolved;
+        let mut issues: Vec<(&PathBuf, Option<u32>)> = Vec::new();
+        for (path, review) in &self.files {
+            for _ in review.file_comments.iter().filter(|c| is_open(c)) {
+                issues.push((path, None));
+            }
+            for (line, comments) in &review.line_comments {
+                for _ in comments.iter().filter(|c| is_open(c)) {
+                    issues.push((path, Some(*line)));
+                }
+            }
+        }
+        issues.sort();
+        issues
+    }
+
     pub fn add_file(&mut self, path: PathBuf, status: FileStatus) {
         self.files
             .entry(path.clone())
             .or_insert_with(|| FileReview::new(path, status));
     }
 
+    /// Records the current diff hash for a file. If the file was reviewed and
+    /// its diff no longer matches the reviewed content, the reviewed flag is
+    /// cleared. Returns true when the reviewed state was invalidated.
+    pub fn update_content_hash(&mut self, path: &PathBuf, hash: String) -> bool {
+        let Some(review) = self.files.get_mut(path) else {
+            return false;
+        };
+        let changed = review
+            .content_hash
+            .as_ref()
+            .is_some_and(|previous| *previous != hash);
+        review.content_hash = Some(hash);
+        if changed {
+            // Line numbers of the old diff no longer match the new one
+            review.viewed_lines.clear();
+            review.viewed_deleted_lines.clear();
+        }
+        if changed && review.reviewed {
+            review.reviewed = false;
+            return true;
+        }
+        false
+    }
+
     pub fn get_file_mut(&mut self, path: &PathBuf) -> Option<&mut FileReview> {
         self.files.get_mut(path)
     }
 
+    pub fn comment_count(&self) -> usize {
+        self.files.values().map(|f| f.comment_count()).sum()
+    }
+
+    /// Line comments whose lines were not found after the diff changed
+    pub fn orphaned_comment_count(&self) -> usize {
+        self.files
+            .values()
+            .flat_map(|f| f.line_comments.values().flatten())
+            .filter(|c| c.orphaned)
+            .count()
+    }
+
+    /// Re-anchor the line comments of `path` to `file`'s diff; see
+    /// [`FileReview::reanchor_comments`]
+    pub fn reanchor_comments(&mut self, path: &PathBuf, file: &DiffFile) {
+        if let Some(review) = self.files.get_mut(path)
+            && review.reanchor_comments(file)
+        {
+            self.updated_at = Utc::now();
+        }
+    }
+
+    /// Whether the session carries any progress worth offering to resume
+    pub fn has_progress(&self) -> bool {
+        self.reviewed_count() > 0 || self.has_comments() || self.last_position.is_some()
+    }
+
     pub fn has_comments(&self) -> bool {
         self.files.values().any(|f| f.comment_count() > 0)
     }
@@ -117,7 +621,456 @@ impl ReviewSession {
         cleared
     }
 
+    pub fn is_file_pinned(&self, path: &PathBuf) -> bool {
+        self.files.get(path).map(|r| r.pinned).unwrap_or(false)
+    }
+
+    pub fn is_file_skipped(&self, path: &PathBuf) -> bool {
+        self.files.get(path).map(|r| r.skipped).unwrap_or(false)
+    }
+
+    pub fn is_file_filtered(&self, path: &PathBuf) -> bool {
+        self.files.get(path).map(|r| r.filtered).unwrap_or(false)
+    }
+
     pub fn is_file_reviewed(&self, path: &PathBuf) -> bool {
         self.files.get(path).map(|r| r.reviewed).unwrap_or(false)
     }
+
+    /// Whether a hunk is reviewed, on its own or as part of its file
+    pub fn is_hunk_reviewed(&self, path: &PathBuf, fingerprint: &str) -> bool {
+        self.files
+            .get(path)
+            .is_some_and(|r| r.reviewed || r.reviewed_hunks.contains(fingerprint))
+    }
+
+    /// Whether some but not all hunks of a file are reviewed
+    pub fn is_file_partly_reviewed(&self, path: &PathBuf) -> bool {
+        self.files
+            .get(path)
+            .is_some_and(|r| !r.reviewed && !r.reviewed_hunks.is_empty())
+    }
+
+    /// Mark one hunk of a file reviewed or not. `hunks` holds the
+    /// fingerprints of all the file's hunks: the file is reviewed exactly
+    /// when every one of them is. Returns true when that changed the file's
+    /// reviewed state.
+    pub fn set_hunk_reviewed(
+        &mut self,
+        path: &PathBuf,
+        fingerprint: &str,
+        reviewed: bool,
+        hunks: &[String],
+    ) -> bool {
+        let Some(review) = self.files.get_mut(path) else {
+            return false;
+        };
+        let was_reviewed = review.reviewed;
+        if was_reviewed {
+            review.reviewed_hunks = hunks.iter().cloned().collect();
+        }
+        if reviewed {
+            review.reviewed_hunks.insert(fingerprint.to_string());
+        } else {
+            review.reviewed_hunks.remove(fingerprint);
+        }
+        review.reviewed_hunks.retain(|hunk| hunks.contains(hunk));
+        review.reviewed = !hunks.is_empty() && review.reviewed_hunks.len() == hunks.len();
+        if review.reviewed {
+            review.reviewed_hunks.clear();
+        }
+        review.reviewed != was_reviewed
+    }
+
+    /// Forget hunks marked reviewed that are no longer in the file's diff
+    pub fn retain_reviewed_hunks(&mut self, path: &PathBuf, hunks: &[String]) {
+        if let Some(review) = self.files.get_mut(path) {
+            review.reviewed_hunks.retain(|hunk| hunks.contains(hunk));
+        }
+    }
+
+    /// Copy comments from `previous` into files of this session.
+    ///
+    /// File comments follow their file; line comments are only kept when
+    /// `anchors(path, line, side)` holds for every line they cover. Comments
+    /// already present (same id) are skipped. Returns `(carried, dropped)`.
+    pub fn carry_over_comments<F>(&mut self, previous: &ReviewSession, anchors: F) -> (usize, usize)
+    where
+        F: Fn(&PathBuf, u32, LineSide) -> bool,
+    {
+        let mut carried = 0;
+        let mut dropped = 0;
+        for (path, old_review) in &previous.files {
+            let Some(review) = self.files.get_mut(path) else {
+                dropped += old_review.comment_count();
+                continue;
+            };
+            for comment in &old_review.file_comments {
+                if review.find_comment_mut(&comment.id).is_none() {
+                    review.add_file_comment(comment.clone());
+                    carried += 1;
+                }
+            }
+            for (line, comments) in &old_review.line_comments {
+                for comment in comments {
+                    let side = comment.side.unwrap_or(LineSide::New);
+                    let start = comment.line_range.map_or(*line, |range| range.start);
+                    if !anchors(path, start, side) || !anchors(path, *line, side) {
+                        dropped += 1;
+                    } else if review.find_comment_mut(&comment.id).is_none() {
+                        review.add_line_comment(*line, comment.clone());
+                        carried += 1;
+                    }
+                }
+            }
+        }
+        if carried > 0 {
+            self.updated_at = Utc::now();
+        }
+        (carried, dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CommentType;
+
+    fn session_with_file(path: &Path) -> ReviewSession {
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            Some("main".to_string()),
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.to_path_buf(), FileStatus::Modified);
+        session
+    }
+
+    #[test]
+    fn should_list_unreviewed_files_and_open_issues() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = session_with_file(&path);
+        session.add_file(PathBuf::from("done.rs"), FileStatus::Modified);
+        session.add_file(PathBuf::from("generated.rs"), FileStatus::Added);
+        session
+            .get_file_mut(&PathBuf::from("done.rs"))
+            .unwrap()
+            .reviewed = true;
+        session
+            .get_file_mut(&PathBuf::from("generated.rs"))
+            .unwrap()
+            .skipped = true;
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_line_comment(
+            8,
+            Comment::new("open".to_string(), CommentType::Issue, Some(LineSide::New)),
+        );
+        let mut resolved = Comment::new("fixed".to_string(), CommentType::Issue, None);
+        resolved.resolved = true;
+        review.add_file_comment(resolved);
+        review.add_file_comment(Comment::new("fyi".to_string(), CommentType::Note, None));
+
+        // when
+        let unreviewed = session.unreviewed_files();
+        let issues = session.open_issues();
+
+        // then
+        assert_eq!(unreviewed, vec![&path]);
+        assert_eq!(issues, vec![(&path, Some(8))]);
+    }
+
+    #[test]
+    fn should_carry_over_only_anchored_comments() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut previous = session_with_file(&path);
+        let review = previous.get_file_mut(&path).unwrap();
+        review.add_file_comment(Comment::new("file".to_string(), CommentType::Note, None));
+        review.add_line_comment(
+            3,
+            Comment::new("kept".to_string(), CommentType::Note, Some(LineSide::New)),
+        );
+        review.add_line_comment(
+            9,
+            Comment::new("gone".to_string(), CommentType::Issue, Some(LineSide::New)),
+        );
+        let mut session = session_with_file(&path);
+
+        // when
+        let (carried, dropped) = session.carry_over_comments(&previous, |_, line, _| line < 5);
+
+        // then
+        assert_eq!((carried, dropped), (2, 1));
+        let review = &session.files[&path];
+        assert_eq!(review.file_comments.len(), 1);
+        assert_eq!(review.line_comments[&3][0].content, "kept");
+        assert!(!review.line_comments.contains_key(&9));
+    }
+
+    /// The diff of a new file with `lines`
+    fn added_file(lines: &[&str]) -> DiffFile {
+        crate::model::diff_types::test_diffs::added_file("src/lib.rs", 1, lines)
+    }
+
+    #[test]
+    fn should_move_comments_with_their_lines_and_orphan_the_rest() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = session_with_file(&path);
+        let before = added_file(&["a", "b", "c", "d"]);
+        let review = session.get_file_mut(&path).unwrap();
+        for (line, content) in [(2, "on b"), (3, "on c")] {
+            let mut comment = Comment::new(content.to_string(), CommentType::Note, None);
+            comment.side = Some(LineSide::New);
+            comment.line_context =
+                anchoring::capture_context(&before, LineRange::single(line), LineSide::New);
+            review.add_line_comment(line, comment);
+        }
+        let after = added_file(&["new", "a", "b", "C", "d"]);
+
+        // when
+        session.reanchor_comments(&path, &after);
+
+        // then
+        // "on b" follows b to line 3; "on c" stays on its line, orphaned
+        let comments: Vec<(&str, bool)> = session.files[&path].line_comments[&3]
+            .iter()
+            .map(|c| (c.content.as_str(), c.orphaned))
+            .collect();
+        assert_eq!(comments, vec![("on b", false), ("on c", true)]);
+        assert!(!session.files[&path].line_comments.contains_key(&2));
+        assert_eq!(session.orphaned_comment_count(), 1);
+    }
+
+    #[test]
+    fn should_not_duplicate_carried_comments() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut previous = session_with_file(&path);
+        previous
+            .get_file_mut(&path)
+            .unwrap()
+            .add_file_comment(Comment::new("once".to_string(), CommentType::Note, None));
+        let mut session = previous.clone();
+
+        // when
+        let (carried, _) = session.carry_over_comments(&previous, |_, _, _| true);
+
+        // then
+        assert_eq!(carried, 0);
+        assert_eq!(session.comment_count(), 1);
+    }
+
+    #[test]
+    fn should_record_history_only_when_enabled() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = session_with_file(&path);
+
+        // when
+        session.record(HistoryAction::Reviewed, &path, None);
+        session.record_history = true;
+        session.record(HistoryAction::CommentAdded, &path, Some(4));
+
+        // then
+        assert_eq!(session.history.len(), 1);
+        assert_eq!(session.history[0].action, HistoryAction::CommentAdded);
+        assert_eq!(session.history[0].line, Some(4));
+    }
+
+    #[test]
+    fn should_format_position_with_line() {
+        // given
+        let position = ReviewPosition {
+            path: PathBuf::from("src/app.rs"),
+            line: Some(210),
+        };
+
+        // when / then
+        assert_eq!(position.to_string(), "src/app.rs:210");
+    }
+
+    #[test]
+    fn should_report_progress_only_when_session_has_work() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        assert!(!session.has_progress());
+
+        // when
+        session.get_file_mut(&path).unwrap().reviewed = true;
+
+        // then
+        assert!(session.has_progress());
+    }
+
+    #[test]
+    fn should_default_pinned_and_hash_for_legacy_file_reviews() {
+        // given
+        let json = r#"{
+            "path": "src/main.rs",
+            "reviewed": true,
+            "status": "modified",
+            "file_comments": [],
+            "line_comments": {}
+        }"#;
+
+        // when
+        let review: FileReview = serde_json::from_str(json).unwrap();
+
+        // then
+        assert!(review.reviewed);
+        assert!(!review.pinned);
+        assert!(!review.skipped);
+        assert!(review.content_hash.is_none());
+        assert!(review.expanded_context.is_empty());
+    }
+
+    #[test]
+    fn should_remove_line_comment_by_id() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        let comment = Comment::new(
+            "Nit".to_string(),
+            crate::model::CommentType::Note,
+            Some(crate::model::LineSide::New),
+        );
+        let id = comment.id.clone();
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_line_comment(12, comment);
+
+        // when
+        let removed = review.remove_comment(&id);
+
+        // then
+        assert!(removed);
+        assert!(review.line_comments.is_empty());
+        assert!(!review.remove_comment(&id));
+    }
+
+    #[test]
+    fn should_restore_comments_and_reviewed_state() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_file_comment(Comment::new(
+            "Split this".to_string(),
+            CommentType::Issue,
+            None,
+        ));
+        let before = review.review_state();
+        review.file_comments.clear();
+        review.reviewed = true;
+        review.time_spent_ms = 500;
+
+        // when
+        let replaced = review.restore(before);
+
+        // then
+        assert_eq!(review.file_comments[0].content, "Split this");
+        assert!(!review.reviewed);
+        assert_eq!(review.time_spent_ms, 500);
+        review.restore(replaced);
+        assert!(review.file_comments.is_empty());
+        assert!(review.reviewed);
+    }
+
+    #[test]
+    fn should_merge_viewed_lines_into_ranges() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        let review = session.get_file_mut(&path).unwrap();
+
+        // when
+        for line in [5, 3, 10, 4, 11, 7, 6] {
+            review.mark_line_viewed(Some(line), Some(line));
+        }
+        review.mark_line_viewed(Some(4), None);
+
+        // then
+        assert_eq!(
+            review.viewed_lines,
+            vec![LineRange::new(3, 7), LineRange::new(10, 11)]
+        );
+        assert!(review.is_line_viewed(None, Some(6)));
+        assert!(!review.is_line_viewed(Some(8), Some(8)));
+        assert!(review.is_line_viewed(Some(4), None));
+        assert!(!review.is_line_viewed(Some(5), None));
+    }
+
+    #[test]
+    fn should_keep_reviewed_when_hash_unchanged() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        session.update_content_hash(&path, "aaaa".to_string());
+        session.get_file_mut(&path).unwrap().reviewed = true;
+
+        // when
+        let invalidated = session.update_content_hash(&path, "aaaa".to_string());
+
+        // then
+        assert!(!invalidated);
+        assert!(session.is_file_reviewed(&path));
+    }
+
+    #[test]
+    fn should_clear_reviewed_when_hash_changes() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        session.update_content_hash(&path, "aaaa".to_string());
+        session.get_file_mut(&path).unwrap().reviewed = true;
+
+        // when
+        let invalidated = session.update_content_hash(&path, "bbbb".to_string());
+
+        // then
+        assert!(invalidated);
+        assert!(!session.is_file_reviewed(&path));
+    }
+
+    #[test]
+    fn should_review_file_once_every_hunk_is_reviewed() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        let hunks = vec!["aaaa".to_string(), "bbbb".to_string()];
+
+        // when
+        let first = session.set_hunk_reviewed(&path, "aaaa", true, &hunks);
+        let partly = session.is_file_partly_reviewed(&path);
+        let second = session.set_hunk_reviewed(&path, "bbbb", true, &hunks);
+        let reviewed = session.is_file_reviewed(&path);
+        let unmarked = session.set_hunk_reviewed(&path, "aaaa", false, &hunks);
+
+        // then
+        assert!(!first && partly);
+        assert!(second && reviewed);
+        assert!(unmarked);
+        assert!(session.is_file_partly_reviewed(&path));
+        assert!(session.is_hunk_reviewed(&path, "bbbb"));
+        assert!(!session.is_hunk_reviewed(&path, "aaaa"));
+    }
+
+    #[test]
+    fn should_adopt_hash_for_legacy_sessions() {
+        // given
+        let path = PathBuf::from("src/main.rs");
+        let mut session = session_with_file(&path);
+        session.get_file_mut(&path).unwrap().reviewed = true;
+
+        // when
+        let invalidated = session.update_content_hash(&path, "aaaa".to_string());
+
+        // then
+        assert!(!invalidated);
+        assert!(session.is_file_reviewed(&path));
+        assert_eq!(session.files[&path].content_hash.as_deref(), Some("aaaa"));
+    }
 }
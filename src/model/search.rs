@@ -0,0 +1,86 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+/// A `/` search pattern. Patterns are regular expressions and match
+/// smart-case: without capitals they ignore case, with any capital they
+/// match it exactly. A `\c` anywhere forces ignoring case and `\C` forces
+/// matching it, as in vim. Text that is not a valid regex is searched for
+/// literally.
+#[derive(Debug, Clone)]
+pub struct SearchPattern {
+    /// The pattern as typed
+    source: String,
+    regex: Regex,
+}
+
+impl SearchPattern {
+    pub fn new(source: &str) -> Self {
+        let mut ignore_case = None;
+        if source.contains("\\C") {
+            ignore_case = Some(false);
+        } else if source.contains("\\c") {
+            ignore_case = Some(true);
+        }
+        let pattern = source.replace("\\C", "").replace("\\c", "");
+        let ignore_case = ignore_case.unwrap_or_else(|| !pattern.chars().any(char::is_uppercase));
+        let build = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+        };
+        let regex = build(&pattern)
+            .or_else(|_| build(&regex::escape(&pattern)))
+            .expect("escaped pattern is a valid regex");
+        Self {
+            source: source.to_string(),
+            regex,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Byte ranges of the non-empty matches in `text`
+    pub fn find_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.range())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_ignore_case_unless_the_pattern_has_capitals() {
+        assert!(SearchPattern::new("parse").is_match("fn Parse()"));
+        assert!(!SearchPattern::new("Parse").is_match("fn parse()"));
+        assert!(SearchPattern::new("Parse\\c").is_match("fn parse()"));
+        assert!(!SearchPattern::new("parse\\C").is_match("fn Parse()"));
+    }
+
+    #[test]
+    fn should_match_regexes_and_fall_back_to_literal_text() {
+        // given
+        let regex = SearchPattern::new("foo.*bar");
+        let invalid = SearchPattern::new("call(");
+
+        // when
+        let ranges = regex.find_ranges("let foo = bar; foo");
+        let literal = invalid.find_ranges("call(a); call(b)");
+
+        // then
+        assert_eq!(ranges, vec![4..13]);
+        assert_eq!(literal, vec![0..5, 9..14]);
+        assert!(SearchPattern::new("x*").find_ranges("abc").is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::comment::CommentType;
+use super::diff_types::DiffFile;
+use super::review::ReviewSession;
+
+/// Comment types from most to least severe
+const SEVERITY_ORDER: [CommentType; 4] = [
+    CommentType::Issue,
+    CommentType::Suggestion,
+    CommentType::Note,
+    CommentType::Praise,
+];
+
+/// Progress and effort of a review, for the `:stats` overlay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewStats {
+    pub files_total: usize,
+    pub files_reviewed: usize,
+    pub files_skipped: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// Comments and how many of them are resolved, by type from most to
+    /// least severe
+    pub comments: Vec<(CommentType, usize, usize)>,
+    /// Time spent in each diff file that has any, longest first
+    pub time_per_file: Vec<(PathBuf, Duration)>,
+}
+
+impl ReviewStats {
+    pub fn total_time(&self) -> Duration {
+        self.time_per_file.iter().map(|(_, time)| *time).sum()
+    }
+}
+
+/// Statistics of the review of `files` in `session`
+pub fn review_stats(files: &[DiffFile], session: &ReviewSession) -> ReviewStats {
+    let mut stats = ReviewStats {
+        files_total: files.len(),
+        files_reviewed: 0,
+        files_skipped: 0,
+        lines_added: 0,
+        lines_removed: 0,
+        comments: SEVERITY_ORDER.iter().map(|&kind| (kind, 0, 0)).collect(),
+        time_per_file: Vec::new(),
+    };
+    for file in files {
+        let (added, removed) = file.line_stats();
+        stats.lines_added += added;
+        stats.lines_removed += removed;
+
+        let Some(review) = session.files.get(file.display_path()) else {
+            continue;
+        };
+        stats.files_reviewed += usize::from(review.reviewed);
+        stats.files_skipped += usize::from(review.skipped);
+        let comments = review
+            .file_comments
+            .iter()
+            .chain(review.line_comments.values().flatten());
+        for comment in comments {
+            if let Some(entry) = stats
+                .comments
+                .iter_mut()
+                .find(|(kind, _, _)| *kind == comment.comment_type)
+            {
+                entry.1 += 1;
+                entry.2 += usize::from(comment.resolved);
+            }
+        }
+        if review.time_spent_ms > 0 {
+            stats.time_per_file.push((
+                file.display_path().clone(),
+                Duration::from_millis(review.time_spent_ms),
+            ));
+        }
+    }
+    stats
+        .time_per_file
+        .sort_by(|(a_path, a_time), (b_path, b_time)| b_time.cmp(a_time).then(a_path.cmp(b_path)));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::{hunk, line, modified_file};
+    use crate::model::review::SessionDiffSource;
+    use crate::model::{Comment, FileStatus, LineOrigin};
+
+    fn file(path: &str, origins: &[LineOrigin]) -> DiffFile {
+        let lines = origins.iter().map(|&origin| line(origin, "x")).collect();
+        modified_file(path, vec![hunk(lines)])
+    }
+
+    #[test]
+    fn should_count_progress_lines_comments_and_time() {
+        // given
+        use LineOrigin::*;
+        let files = vec![
+            file("a.rs", &[Addition, Addition, Deletion, Context]),
+            file("b.rs", &[Addition]),
+        ];
+        let mut session = ReviewSession::new(
+            PathBuf::from("/repo"),
+            "abc".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        for file in &files {
+            session.add_file(file.display_path().clone(), FileStatus::Modified);
+        }
+        let a = session.files.get_mut(&PathBuf::from("a.rs")).unwrap();
+        a.reviewed = true;
+        a.time_spent_ms = 90_000;
+        a.add_file_comment(Comment::new("x".to_string(), CommentType::Issue, None));
+        let mut resolved = Comment::new("y".to_string(), CommentType::Issue, None);
+        resolved.resolved = true;
+        a.add_line_comment(1, resolved);
+        let b = session.files.get_mut(&PathBuf::from("b.rs")).unwrap();
+        b.time_spent_ms = 120_000;
+        b.add_file_comment(Comment::new("z".to_string(), CommentType::Praise, None));
+
+        // when
+        let stats = review_stats(&files, &session);
+
+        // then
+        assert_eq!((stats.files_reviewed, stats.files_total), (1, 2));
+        assert_eq!((stats.lines_added, stats.lines_removed), (3, 1));
+        assert_eq!(stats.comments[0], (CommentType::Issue, 2, 1));
+        assert_eq!(stats.comments[3], (CommentType::Praise, 1, 0));
+        assert_eq!(stats.time_per_file[0].0, PathBuf::from("b.rs"));
+        assert_eq!(stats.total_time(), Duration::from_secs(210));
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::error::{Result, TuicrError};
+
+/// Document formats that support a structural (key-path) diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Yaml,
+}
+
+impl StructuredFormat {
+    /// Detect the format from a file extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A single leaf-level difference between two documents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuralChange {
+    Added {
+        path: String,
+        value: String,
+    },
+    Removed {
+        path: String,
+        value: String,
+    },
+    Changed {
+        path: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl StructuralChange {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Changed { path, .. } => {
+                path
+            }
+        }
+    }
+}
+
+/// Compare two documents by key path, ignoring key order and formatting.
+///
+/// Both documents are flattened into `path -> scalar` maps (e.g.
+/// `server.ports[0] -> 8080`) and the maps are compared. Changes are returned
+/// sorted by path.
+pub fn structural_diff(
+    format: StructuredFormat,
+    old: &str,
+    new: &str,
+) -> Result<Vec<StructuralChange>> {
+    let old_leaves = flatten_document(format, old)?;
+    let new_leaves = flatten_document(format, new)?;
+
+    let mut changes = Vec::new();
+    for (path, old_value) in &old_leaves {
+        match new_leaves.get(path) {
+            None => changes.push(StructuralChange::Removed {
+                path: path.clone(),
+                value: old_value.clone(),
+            }),
+            Some(new_value) if new_value != old_value => changes.push(StructuralChange::Changed {
+                path: path.clone(),
+                old: old_value.clone(),
+                new: new_value.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (path, new_value) in &new_leaves {
+        if !old_leaves.contains_key(path) {
+            changes.push(StructuralChange::Added {
+                path: path.clone(),
+                value: new_value.clone(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    Ok(changes)
+}
+
+fn flatten_document(format: StructuredFormat, text: &str) -> Result<BTreeMap<String, String>> {
+    let mut leaves = BTreeMap::new();
+    // A missing side (added or deleted file) has no leaves
+    if text.trim().is_empty() {
+        return Ok(leaves);
+    }
+    match format {
+        StructuredFormat::Json => {
+            let value: serde_json::Value = serde_json::from_str(text)
+                .map_err(|e| TuicrError::Parse(format!("invalid JSON: {e}")))?;
+            flatten_json(&value, String::new(), &mut leaves);
+        }
+        StructuredFormat::Yaml => {
+            let docs = YamlLoader::load_from_str(text)
+                .map_err(|e| TuicrError::Parse(format!("invalid YAML: {e}")))?;
+            for (idx, doc) in docs.iter().enumerate() {
+                let root = if docs.len() > 1 {
+                    format!("---{idx}")
+                } else {
+                    String::new()
+                };
+                flatten_yaml(doc, root, &mut leaves);
+            }
+        }
+    }
+    Ok(leaves)
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn leaf_path(path: String) -> String {
+    if path.is_empty() {
+        "(root)".to_string()
+    } else {
+        path
+    }
+}
+
+fn flatten_json(value: &serde_json::Value, path: String, leaves: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                flatten_json(child, join_key(&path, key), leaves);
+            }
+        }
+        serde_json::Value::Array(items) if !items.is_empty() => {
+            for (idx, child) in items.iter().enumerate() {
+                flatten_json(child, format!("{path}[{idx}]"), leaves);
+            }
+        }
+        other => {
+            leaves.insert(leaf_path(path), other.to_string());
+        }
+    }
+}
+
+fn flatten_yaml(value: &Yaml, path: String, leaves: &mut BTreeMap<String, String>) {
+    match value {
+        Yaml::Hash(map) if !map.is_empty() => {
+            for (key, child) in map {
+                flatten_yaml(child, join_key(&path, &yaml_key(key)), leaves);
+            }
+        }
+        Yaml::Array(items) if !items.is_empty() => {
+            for (idx, child) in items.iter().enumerate() {
+                flatten_yaml(child, format!("{path}[{idx}]"), leaves);
+            }
+        }
+        other => {
+            leaves.insert(leaf_path(path), yaml_scalar(other));
+        }
+    }
+}
+
+fn yaml_key(key: &Yaml) -> String {
+    match key {
+        Yaml::String(s) => s.clone(),
+        other => yaml_scalar(other),
+    }
+}
+
+fn yaml_scalar(value: &Yaml) -> String {
+    match value {
+        Yaml::String(s) => format!("{s:?}"),
+        Yaml::Real(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Null | Yaml::BadValue => "null".to_string(),
+        Yaml::Hash(_) => "{}".to_string(),
+        Yaml::Array(_) => "[]".to_string(),
+        Yaml::Alias(id) => format!("*{id}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_ignore_reordered_json_keys() {
+        // given
+        let old = r#"{"a": 1, "b": {"c": true}}"#;
+        let new = "{\n  \"b\": {\"c\": true},\n  \"a\": 1\n}";
+
+        // when
+        let changes = structural_diff(StructuredFormat::Json, old, new).unwrap();
+
+        // then
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn should_report_json_changes_by_key_path() {
+        // given
+        let old = r#"{"server": {"port": 80, "debug": true}, "tags": ["a"]}"#;
+        let new = r#"{"server": {"port": 8080}, "tags": ["a", "b"]}"#;
+
+        // when
+        let changes = structural_diff(StructuredFormat::Json, old, new).unwrap();
+
+        // then
+        assert_eq!(
+            changes,
+            vec![
+                StructuralChange::Removed {
+                    path: "server.debug".to_string(),
+                    value: "true".to_string(),
+                },
+                StructuralChange::Changed {
+                    path: "server.port".to_string(),
+                    old: "80".to_string(),
+                    new: "8080".to_string(),
+                },
+                StructuralChange::Added {
+                    path: "tags[1]".to_string(),
+                    value: "\"b\"".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_diff_yaml_documents() {
+        // given
+        let old = "name: app\nreplicas: 1\n";
+        let new = "replicas: 3\nname: app\n";
+
+        // when
+        let changes = structural_diff(StructuredFormat::Yaml, old, new).unwrap();
+
+        // then
+        assert_eq!(
+            changes,
+            vec![StructuralChange::Changed {
+                path: "replicas".to_string(),
+                old: "1".to_string(),
+                new: "3".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_fail_on_invalid_json() {
+        // when
+        let result = structural_diff(StructuredFormat::Json, "{", "{}");
+
+        // then
+        assert!(matches!(result, Err(TuicrError::Parse(_))));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::{Result, TuicrError};
+
+use super::comment::LineRange;
+
+/// Replace the lines a suggestion was made on with its replacement.
+///
+/// `range` is where `base`, the lines the suggestion replaces, sat in the
+/// diff. When the file changed since and they moved, `base` is used where it
+/// occurs exactly once; otherwise the suggestion conflicts with the file.
+/// Line endings of the replaced lines are kept.
+///
+/// Returns the new text and the first line the replacement starts on.
+pub fn apply_suggestion(
+    text: &str,
+    range: LineRange,
+    base: &str,
+    replacement: &str,
+) -> Result<(String, u32)> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let base_lines: Vec<&str> = base.split('\n').collect();
+    let count = base_lines.len();
+    let matches_at = |start: usize| {
+        lines.get(start..start + count).is_some_and(|window| {
+            window
+                .iter()
+                .zip(&base_lines)
+                .all(|(line, base)| line.trim_end_matches('\n').trim_end_matches('\r') == *base)
+        })
+    };
+
+    let anchored = range.start.saturating_sub(1) as usize;
+    let start = if matches_at(anchored) {
+        anchored
+    } else {
+        let found: Vec<usize> = (0..lines.len()).filter(|&idx| matches_at(idx)).collect();
+        match found[..] {
+            [start] => start,
+            [] => {
+                return Err(TuicrError::SuggestionConflict(format!(
+                    "lines {}-{} no longer read as they did when the suggestion was made",
+                    range.start, range.end
+                )));
+            }
+            _ => {
+                return Err(TuicrError::SuggestionConflict(format!(
+                    "lines {}-{} changed and their original text occurs {} times",
+                    range.start,
+                    range.end,
+                    found.len()
+                )));
+            }
+        }
+    };
+
+    let eol = if lines[start].ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut result: String = lines[..start].concat();
+    if !replacement.is_empty() {
+        result.push_str(&replacement.split('\n').collect::<Vec<_>>().join(eol));
+        if lines[start + count - 1].ends_with('\n') {
+            result.push_str(eol);
+        }
+    }
+    result.push_str(&lines[start + count..].concat());
+    Ok((result, start as u32 + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "fn main() {\r\n    let x = 1;\r\n    run(x);\r\n}";
+
+    #[test]
+    fn should_replace_lines_keeping_line_endings() {
+        // when
+        let (text, line) = apply_suggestion(
+            FILE,
+            LineRange::new(2, 3),
+            "    let x = 1;\n    run(x);",
+            "    run(1);",
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(text, "fn main() {\r\n    run(1);\r\n}");
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn should_find_moved_lines() {
+        // given
+        let moved = format!("// header\n{FILE}");
+
+        // when
+        let (text, line) =
+            apply_suggestion(&moved, LineRange::single(4), "}", "} // main").unwrap();
+
+        // then
+        assert!(text.ends_with("    run(x);\r\n} // main"));
+        assert_eq!(line, 5);
+    }
+
+    #[test]
+    fn should_conflict_when_lines_changed() {
+        assert!(matches!(
+            apply_suggestion(
+                FILE,
+                LineRange::single(2),
+                "    let x = 2;",
+                "    let y = 2;"
+            ),
+            Err(TuicrError::SuggestionConflict(_))
+        ));
+    }
+
+    #[test]
+    fn should_delete_lines_for_empty_replacement() {
+        let (text, _) = apply_suggestion(FILE, LineRange::single(3), "    run(x);", "").unwrap();
+        assert_eq!(text, "fn main() {\r\n    let x = 1;\r\n}");
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use super::diff_types::{DiffFile, LineOrigin};
+
+/// Markers for unfinished work that usually deserve a review comment
+pub const TODO_MARKERS: &[&str] = &["TODO", "FIXME", "XXX"];
+
+/// A marker on a line added by the diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoMarker {
+    pub path: PathBuf,
+    /// Line number in the new file
+    pub line: u32,
+    /// The whole line, trimmed
+    pub text: String,
+}
+
+/// Byte ranges of the markers in `text`. Markers must stand alone, so
+/// `TODOS` or `todo_list` do not count.
+pub fn marker_ranges(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let is_word = |idx: usize| {
+        bytes
+            .get(idx)
+            .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_')
+    };
+    let mut ranges = Vec::new();
+    for marker in TODO_MARKERS {
+        for (start, _) in text.match_indices(marker) {
+            let end = start + marker.len();
+            if (start == 0 || !is_word(start - 1)) && !is_word(end) {
+                ranges.push(start..end);
+            }
+        }
+    }
+    ranges.sort_by_key(|range| range.start);
+    ranges
+}
+
+/// Markers the diff introduces: added lines holding a marker, unless the
+/// same line is also deleted from the file (it was only moved or reindented)
+pub fn new_todo_markers(files: &[DiffFile]) -> Vec<TodoMarker> {
+    let mut markers = Vec::new();
+    for file in files {
+        let lines = || file.hunks.iter().flat_map(|hunk| &hunk.lines);
+        let deleted: HashSet<&str> = lines()
+            .filter(|line| line.origin == LineOrigin::Deletion)
+            .map(|line| line.content.trim())
+            .collect();
+        for line in lines() {
+            let text = line.content.trim();
+            if line.origin == LineOrigin::Addition
+                && !deleted.contains(text)
+                && !marker_ranges(text).is_empty()
+                && let Some(lineno) = line.new_lineno
+            {
+                markers.push(TodoMarker {
+                    path: file.display_path().clone(),
+                    line: lineno,
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+    markers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DiffLine;
+    use crate::model::diff_types::test_diffs::{hunk, modified_file, numbered_line};
+
+    fn line(origin: LineOrigin, content: &str, new_lineno: u32) -> DiffLine {
+        numbered_line(origin, content, None, Some(new_lineno))
+    }
+
+    #[test]
+    fn should_find_standalone_markers() {
+        assert_eq!(
+            marker_ranges("// TODO: fix, FIXME(ada) and XXX"),
+            vec![3..7, 14..19, 29..32]
+        );
+        assert!(marker_ranges("let TODOS = todo_list(); // XXXL").is_empty());
+    }
+
+    #[test]
+    fn should_list_markers_added_by_the_diff() {
+        // given
+        let files = vec![modified_file(
+            "src/lib.rs",
+            vec![hunk(vec![
+                line(LineOrigin::Deletion, "// TODO: old", 0),
+                line(LineOrigin::Addition, "    // TODO: old", 1),
+                line(LineOrigin::Addition, "// FIXME handle errors", 2),
+                line(LineOrigin::Context, "// TODO: untouched", 3),
+            ])],
+        )];
+
+        // when
+        let markers = new_todo_markers(&files);
+
+        // then
+        assert_eq!(
+            markers,
+            vec![TodoMarker {
+                path: PathBuf::from("src/lib.rs"),
+                line: 2,
+                text: "// FIXME handle errors".to_string(),
+            }]
+        );
+    }
+}
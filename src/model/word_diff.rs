@@ -0,0 +1,336 @@
+use std::ops::Range;
+
+use super::diff_types::{DiffHunk, MINIFIED_LINE_LEN};
+
+/// Kind of change for a run of text in a word diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordChange {
+    Equal,
+    Added,
+    Removed,
+}
+
+/// A run of text sharing the same change kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordSegment {
+    pub change: WordChange,
+    pub text: String,
+}
+
+/// Above this many token pairs the LCS table gets too large to be worth it,
+/// and the differing middle is reported as a whole replacement instead.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Split text into alternating runs of word characters and whitespace so
+/// that joining the tokens reproduces the original text.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev_ws: Option<bool> = None;
+    for (idx, ch) in text.char_indices() {
+        let is_ws = ch.is_whitespace();
+        if let Some(prev) = prev_ws
+            && prev != is_ws
+        {
+            tokens.push(&text[start..idx]);
+            start = idx;
+        }
+        prev_ws = Some(is_ws);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Split code into identifier runs, whitespace runs and single punctuation
+/// characters, so minified sources without whitespace still diff finely.
+fn tokenize_code(text: &str) -> Vec<&str> {
+    let class = |ch: char| {
+        if ch.is_alphanumeric() || ch == '_' {
+            0
+        } else if ch.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev_class: Option<u8> = None;
+    for (idx, ch) in text.char_indices() {
+        let current = class(ch);
+        if let Some(prev) = prev_class
+            && (prev != current || current == 2)
+        {
+            tokens.push(&text[start..idx]);
+            start = idx;
+        }
+        prev_class = Some(current);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn push_segment(segments: &mut Vec<WordSegment>, change: WordChange, text: &str) {
+    if let Some(last) = segments.last_mut()
+        && last.change == change
+    {
+        last.text.push_str(text);
+        return;
+    }
+    segments.push(WordSegment {
+        change,
+        text: text.to_string(),
+    });
+}
+
+/// Compute a word-level diff between two texts.
+///
+/// Unchanged words are reported as `Equal`, and the rest as `Removed` (only in
+/// `old`) or `Added` (only in `new`). Adjacent tokens of the same kind are
+/// merged into a single segment.
+pub fn diff_words(old: &str, new: &str) -> Vec<WordSegment> {
+    diff_tokens(&tokenize(old), &tokenize(new))
+}
+
+/// Compute a fine-grained diff between two lines of code.
+///
+/// Like `diff_words`, but punctuation is compared character by character so
+/// that long lines without whitespace (minified sources) split into small
+/// changed chunks.
+pub fn diff_code_chunks(old: &str, new: &str) -> Vec<WordSegment> {
+    diff_tokens(&tokenize_code(old), &tokenize_code(new))
+}
+
+fn diff_tokens(old_tokens: &[&str], new_tokens: &[&str]) -> Vec<WordSegment> {
+    let mut segments = Vec::new();
+
+    // Common prefix and suffix need no LCS, which keeps long lines with a
+    // small change (e.g. minified sources) cheap to diff
+    let prefix = old_tokens
+        .iter()
+        .zip(new_tokens)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_tokens[prefix..]
+        .iter()
+        .rev()
+        .zip(new_tokens[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_tokens[prefix..old_tokens.len() - suffix];
+    let new_mid = &new_tokens[prefix..new_tokens.len() - suffix];
+
+    for token in &old_tokens[..prefix] {
+        push_segment(&mut segments, WordChange::Equal, token);
+    }
+    diff_middle(&mut segments, old_mid, new_mid);
+    for token in &old_tokens[old_tokens.len() - suffix..] {
+        push_segment(&mut segments, WordChange::Equal, token);
+    }
+
+    segments
+}
+
+fn diff_middle(segments: &mut Vec<WordSegment>, old_tokens: &[&str], new_tokens: &[&str]) {
+    let n = old_tokens.len();
+    let m = new_tokens.len();
+
+    if n.saturating_mul(m) > MAX_LCS_CELLS {
+        for token in old_tokens {
+            push_segment(segments, WordChange::Removed, token);
+        }
+        for token in new_tokens {
+            push_segment(segments, WordChange::Added, token);
+        }
+        return;
+    }
+
+    // lcs[i][j] = length of the LCS of old_tokens[i..] and new_tokens[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_tokens[i] == new_tokens[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_tokens[i] == new_tokens[j] {
+            push_segment(segments, WordChange::Equal, old_tokens[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            push_segment(segments, WordChange::Removed, old_tokens[i]);
+            i += 1;
+        } else {
+            push_segment(segments, WordChange::Added, new_tokens[j]);
+            j += 1;
+        }
+    }
+    for token in &old_tokens[i..] {
+        push_segment(segments, WordChange::Removed, token);
+    }
+    for token in &new_tokens[j..] {
+        push_segment(segments, WordChange::Added, token);
+    }
+}
+
+/// Byte ranges of the changed text in one line
+pub type ChangedRanges = Vec<Range<usize>>;
+
+/// Byte ranges of the changed text in two versions of a line, for the old
+/// and the new line respectively.
+///
+/// Returns None when the lines only share whitespace: emphasizing the whole
+/// line adds nothing over the regular diff coloring.
+pub fn intraline_ranges(old: &str, new: &str) -> Option<(ChangedRanges, ChangedRanges)> {
+    let mut old_ranges = ChangedRanges::new();
+    let mut new_ranges = ChangedRanges::new();
+    let (mut old_pos, mut new_pos) = (0, 0);
+    let mut shared = false;
+    for segment in diff_code_chunks(old, new) {
+        let len = segment.text.len();
+        match segment.change {
+            WordChange::Equal => {
+                shared |= !segment.text.trim().is_empty();
+                old_pos += len;
+                new_pos += len;
+            }
+            WordChange::Removed => {
+                old_ranges.push(old_pos..old_pos + len);
+                old_pos += len;
+            }
+            WordChange::Added => {
+                new_ranges.push(new_pos..new_pos + len);
+                new_pos += len;
+            }
+        }
+    }
+    shared.then_some((old_ranges, new_ranges))
+}
+
+/// Fill `changed_ranges` of the paired deletion/addition lines of a hunk.
+///
+/// Minified lines are skipped, they are already shown as change chunks.
+pub fn mark_intraline_changes(hunk: &mut DiffHunk) {
+    for (del_idx, add_idx) in hunk.change_pairs() {
+        let lines = &mut hunk.lines;
+        let (old, new) = (&lines[del_idx].content, &lines[add_idx].content);
+        if old.len() > MINIFIED_LINE_LEN || new.len() > MINIFIED_LINE_LEN {
+            continue;
+        }
+        if let Some((old_ranges, new_ranges)) = intraline_ranges(old, new) {
+            lines[del_idx].changed_ranges = old_ranges;
+            lines[add_idx].changed_ranges = new_ranges;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(segments: &[WordSegment]) -> String {
+        segments
+            .iter()
+            .map(|s| match s.change {
+                WordChange::Equal => s.text.clone(),
+                WordChange::Added => format!("{{+{}+}}", s.text),
+                WordChange::Removed => format!("[-{}-]", s.text),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_report_identical_text_as_equal() {
+        // given
+        let text = "the quick brown fox";
+
+        // when
+        let segments = diff_words(text, text);
+
+        // then
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].change, WordChange::Equal);
+        assert_eq!(segments[0].text, text);
+    }
+
+    #[test]
+    fn should_mark_replaced_word() {
+        // given
+        let old = "the quick brown fox";
+        let new = "the slow brown fox";
+
+        // when
+        let segments = diff_words(old, new);
+
+        // then
+        assert_eq!(render(&segments), "the [-quick-]{+slow+} brown fox");
+    }
+
+    #[test]
+    fn should_mark_appended_words() {
+        // given
+        let old = "hello";
+        let new = "hello world";
+
+        // when
+        let segments = diff_words(old, new);
+
+        // then
+        assert_eq!(render(&segments), "hello{+ world+}");
+    }
+
+    #[test]
+    fn should_split_minified_code_into_small_chunks() {
+        // given
+        let old = "var a=1;function f(){return a}";
+        let new = "var a=2;function f(){return a}";
+
+        // when
+        let segments = diff_code_chunks(old, new);
+
+        // then
+        assert_eq!(render(&segments), "var a=[-1-]{+2+};function f(){return a}");
+    }
+
+    #[test]
+    fn should_handle_empty_sides() {
+        // when
+        let added = diff_words("", "new text");
+        let removed = diff_words("old text", "");
+
+        // then
+        assert_eq!(render(&added), "{+new text+}");
+        assert_eq!(render(&removed), "[-old text-]");
+    }
+
+    #[test]
+    fn should_locate_changed_words_in_both_lines() {
+        // given
+        let old = "let total = price * 2;";
+        let new = "let total = cost * 2;";
+
+        // when
+        let (old_ranges, new_ranges) = intraline_ranges(old, new).unwrap();
+
+        // then
+        assert_eq!(old_ranges, vec![12..17]);
+        assert_eq!(&old[12..17], "price");
+        assert_eq!(new_ranges, vec![12..16]);
+        assert_eq!(&new[12..16], "cost");
+    }
+
+    #[test]
+    fn should_skip_lines_with_nothing_in_common() {
+        assert_eq!(intraline_ranges("foo()", "  bar;"), None);
+    }
+}
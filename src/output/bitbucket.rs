@@ -0,0 +1,353 @@
+use serde_json::{Value, json};
+
+use crate::error::{Result, TuicrError};
+use crate::model::{
+    DiffFile, ExportPayload, LineOrigin, LineSide, PendingExport, PublishedContent, ReviewSession,
+    content_fingerprint,
+};
+
+use super::credentials::Forge;
+use super::markdown::{
+    ExportOptions, collect_comments, comment_body, expand_issue_refs, with_anchor_note,
+};
+
+/// The pull request a review is posted to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitbucketTarget {
+    /// bitbucket.org
+    Cloud {
+        workspace: String,
+        repo: String,
+        pr: u64,
+    },
+    /// Self-hosted Bitbucket Server / Data Center
+    Server {
+        base_url: String,
+        project: String,
+        repo: String,
+        pr: u64,
+    },
+}
+
+impl BitbucketTarget {
+    /// Parse a pull request URL as shown in the browser, e.g.
+    /// `https://bitbucket.org/team/app/pull-requests/12` or
+    /// `https://git.example.com/projects/APP/repos/app/pull-requests/12/overview`
+    pub fn from_pr_url(url: &str) -> Result<Self> {
+        let invalid = || TuicrError::Parse(format!("not a Bitbucket pull request URL: {url}"));
+        let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+        let (host, path) = rest.split_once('/').ok_or_else(invalid)?;
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let pr_idx = segments
+            .iter()
+            .position(|s| *s == "pull-requests")
+            .ok_or_else(invalid)?;
+        let pr = segments
+            .get(pr_idx + 1)
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(invalid)?;
+
+        match &segments[..pr_idx] {
+            [workspace, repo] if host == "bitbucket.org" => Ok(Self::Cloud {
+                workspace: workspace.to_string(),
+                repo: repo.to_string(),
+                pr,
+            }),
+            [prefix @ .., "projects", project, "repos", repo] => Ok(Self::Server {
+                base_url: format!("{scheme}://{host}")
+                    + &prefix.iter().map(|s| format!("/{s}")).collect::<String>(),
+                project: project.to_string(),
+                repo: repo.to_string(),
+                pr,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Host whose credentials are used, e.g. for `git credential`
+    pub fn host(&self) -> &str {
+        match self {
+            Self::Cloud { .. } => "bitbucket.org",
+            Self::Server { base_url, .. } => {
+                let rest = base_url
+                    .split_once("://")
+                    .map_or(base_url.as_str(), |(_, r)| r);
+                rest.split('/').next().unwrap_or(rest)
+            }
+        }
+    }
+
+    /// REST endpoint that creates pull request comments
+    pub fn comments_endpoint(&self) -> String {
+        match self {
+            Self::Cloud {
+                workspace,
+                repo,
+                pr,
+            } => format!(
+                "https://api.bitbucket.org/2.0/repositories/{workspace}/{repo}/pullrequests/{pr}/comments"
+            ),
+            Self::Server {
+                base_url,
+                project,
+                repo,
+                pr,
+            } => format!(
+                "{base_url}/rest/api/1.0/projects/{project}/repos/{repo}/pull-requests/{pr}/comments"
+            ),
+        }
+    }
+
+    /// Request body for one comment. `path` is None for the general comment
+    /// and `line` is None for file comments.
+    fn payload(
+        &self,
+        text: String,
+        path: Option<&str>,
+        line: Option<(u32, LineSide)>,
+        diff_files: &[DiffFile],
+    ) -> Value {
+        match self {
+            Self::Cloud { .. } => {
+                let mut body = json!({ "content": { "raw": text } });
+                if let Some(path) = path {
+                    let mut inline = json!({ "path": path });
+                    match line {
+                        Some((line, LineSide::Old)) => inline["from"] = json!(line),
+                        Some((line, LineSide::New)) => inline["to"] = json!(line),
+                        None => {}
+                    }
+                    body["inline"] = inline;
+                }
+                body
+            }
+            Self::Server { .. } => {
+                let mut body = json!({ "text": text });
+                if let Some(path) = path {
+                    let mut anchor = json!({ "path": path });
+                    if let Some((line, side)) = line {
+                        let (line_type, file_type) = match side {
+                            LineSide::Old => ("REMOVED", "FROM"),
+                            LineSide::New if is_added_line(diff_files, path, line) => {
+                                ("ADDED", "TO")
+                            }
+                            LineSide::New => ("CONTEXT", "TO"),
+                        };
+                        anchor["line"] = json!(line);
+                        anchor["lineType"] = json!(line_type);
+                        anchor["fileType"] = json!(file_type);
+                    }
+                    body["anchor"] = anchor;
+                }
+                body
+            }
+        }
+    }
+}
+
+/// Build one comment request per review comment, plus one for the session
+/// notes if there are any. Comments already published to this pull request
+/// in their current form are skipped.
+pub fn bitbucket_payloads(
+    session: &ReviewSession,
+    target: &BitbucketTarget,
+    options: &ExportOptions,
+) -> Result<Vec<ExportPayload>> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let endpoint = target.comments_endpoint();
+    let mut payloads = Vec::new();
+    if let Some(notes) = &session.session_notes
+        && !session.notes_published_to(&endpoint)
+    {
+        let body = target.payload(
+            with_anchor_note(session, expand_issue_refs(options, notes)),
+            None,
+            None,
+            options.diff_files,
+        );
+        payloads.push(ExportPayload {
+            publishes: vec![PublishedContent {
+                comment_id: None,
+                fingerprint: content_fingerprint(notes),
+            }],
+            body: body.to_string(),
+        });
+    }
+    for entry in collect_comments(session) {
+        if entry.comment.is_published_to(&endpoint) {
+            continue;
+        }
+        // Bitbucket anchors a comment to a single line: use the last one
+        let line = entry
+            .line_range
+            .map(|range| (range.end, entry.comment.side.unwrap_or(LineSide::New)));
+        let body = target.payload(
+            comment_body(entry.comment, options),
+            Some(&entry.file),
+            line,
+            options.diff_files,
+        );
+        payloads.push(ExportPayload {
+            publishes: vec![PublishedContent {
+                comment_id: Some(entry.comment.id.clone()),
+                fingerprint: entry.comment.fingerprint(),
+            }],
+            body: body.to_string(),
+        });
+    }
+    Ok(payloads)
+}
+
+/// Everything needed to post the review's unpublished comments to a
+/// Bitbucket pull request; send it with `delivery::deliver`
+pub fn bitbucket_export(
+    session: &ReviewSession,
+    target: &BitbucketTarget,
+    options: &ExportOptions,
+) -> Result<PendingExport> {
+    let payloads = bitbucket_payloads(session, target, options)?;
+    Ok(PendingExport::new(
+        Forge::Bitbucket,
+        target.host(),
+        target.comments_endpoint(),
+        payloads,
+    ))
+}
+
+fn is_added_line(diff_files: &[DiffFile], path: &str, line: u32) -> bool {
+    diff_files
+        .iter()
+        .filter(|file| file.display_path().to_string_lossy() == path)
+        .flat_map(|file| &file.hunks)
+        .flat_map(|hunk| &hunk.lines)
+        .any(|l| l.new_lineno == Some(line) && l.origin == LineOrigin::Addition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, CommentType, FileStatus, SessionDiffSource};
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_parse_cloud_pr_url() {
+        // when
+        let target =
+            BitbucketTarget::from_pr_url("https://bitbucket.org/team/app/pull-requests/12/diff")
+                .unwrap();
+
+        // then
+        assert_eq!(
+            target.comments_endpoint(),
+            "https://api.bitbucket.org/2.0/repositories/team/app/pullrequests/12/comments"
+        );
+    }
+
+    #[test]
+    fn should_parse_server_pr_url_with_context_path() {
+        // when
+        let target = BitbucketTarget::from_pr_url(
+            "https://git.example.com/bitbucket/projects/APP/repos/app/pull-requests/7/overview",
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(target.host(), "git.example.com");
+        assert_eq!(
+            target.comments_endpoint(),
+            "https://git.example.com/bitbucket/rest/api/1.0/projects/APP/repos/app/pull-requests/7/comments"
+        );
+    }
+
+    #[test]
+    fn should_reject_non_pr_url() {
+        assert!(BitbucketTarget::from_pr_url("https://bitbucket.org/team/app").is_err());
+    }
+
+    #[test]
+    fn should_anchor_comments_to_lines() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        session.get_file_mut(&path).unwrap().add_line_comment(
+            3,
+            Comment::new("Typo".to_string(), CommentType::Issue, Some(LineSide::Old)),
+        );
+        let cloud = BitbucketTarget::Cloud {
+            workspace: "team".to_string(),
+            repo: "app".to_string(),
+            pr: 1,
+        };
+
+        // when
+        let payloads = bitbucket_payloads(&session, &cloud, &ExportOptions::default()).unwrap();
+
+        // then
+        let bodies: Vec<Value> = payloads
+            .iter()
+            .map(|p| serde_json::from_str(&p.body).unwrap())
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![json!({
+                "content": { "raw": "**ISSUE** Typo" },
+                "inline": { "path": "src/lib.rs", "from": 3 },
+            })]
+        );
+    }
+
+    #[test]
+    fn should_only_send_new_or_changed_comments() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_line_comment(1, Comment::new("One".to_string(), CommentType::Note, None));
+        review.add_line_comment(2, Comment::new("Two".to_string(), CommentType::Note, None));
+        let cloud = BitbucketTarget::Cloud {
+            workspace: "team".to_string(),
+            repo: "app".to_string(),
+            pr: 1,
+        };
+        let endpoint = cloud.comments_endpoint();
+        for payload in bitbucket_payloads(&session, &cloud, &ExportOptions::default()).unwrap() {
+            for content in &payload.publishes {
+                session.mark_published(
+                    &endpoint,
+                    content.comment_id.as_deref(),
+                    &content.fingerprint,
+                );
+            }
+        }
+        assert!(!session.get_file_mut(&path).unwrap().line_comments[&1][0].is_draft());
+
+        // when
+        session
+            .get_file_mut(&path)
+            .unwrap()
+            .line_comments
+            .get_mut(&2)
+            .unwrap()[0]
+            .content = "Two, edited".to_string();
+        let payloads = bitbucket_payloads(&session, &cloud, &ExportOptions::default()).unwrap();
+
+        // then
+        assert_eq!(payloads.len(), 1);
+        assert!(payloads[0].body.contains("Two, edited"));
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+
+use crate::error::{Result, TuicrError};
+
+/// Forges that tuicr talks to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    Bitbucket,
+    GitHub,
+    GitLab,
+}
+
+impl Forge {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bitbucket => "bitbucket",
+            Self::GitHub => "github",
+            Self::GitLab => "gitlab",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bitbucket" => Some(Self::Bitbucket),
+            "github" => Some(Self::GitHub),
+            "gitlab" => Some(Self::GitLab),
+            _ => None,
+        }
+    }
+
+    /// Prefix of the environment variables holding credentials
+    fn env_prefix(self) -> &'static str {
+        match self {
+            Self::Bitbucket => "BITBUCKET",
+            Self::GitHub => "GITHUB",
+            Self::GitLab => "GITLAB",
+        }
+    }
+}
+
+/// A secret used to authenticate against a forge
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// Access token sent as a bearer token
+    Token(String),
+    /// Username and password (or app password) sent with basic auth
+    Basic { username: String, password: String },
+}
+
+// Never print secrets, e.g. in debug logs
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token(_) => f.write_str("Token(***)"),
+            Self::Basic { username, .. } => write!(f, "Basic({username}:***)"),
+        }
+    }
+}
+
+impl Credential {
+    /// Value of the `Authorization` header
+    pub fn authorization_header(&self) -> String {
+        match self {
+            Self::Token(token) => format!("Bearer {token}"),
+            Self::Basic { username, password } => {
+                format!("Basic {}", BASE64.encode(format!("{username}:{password}")))
+            }
+        }
+    }
+}
+
+/// Where a credential was found, reported when authentication fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialSource {
+    Env(String),
+    GitConfig(String),
+    GhCli,
+    GitCredential,
+    Keyring,
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(var) => write!(f, "${var}"),
+            Self::GitConfig(key) => write!(f, "git config {key}"),
+            Self::GhCli => f.write_str("gh auth token"),
+            Self::GitCredential => f.write_str("git credential"),
+            Self::Keyring => f.write_str("the system keyring"),
+        }
+    }
+}
+
+/// Find credentials for `host` on `forge`, trying in order:
+///
+/// 1. environment: `<FORGE>_TOKEN`, or `<FORGE>_USERNAME` with
+///    `<FORGE>_PASSWORD` / `<FORGE>_APP_PASSWORD`
+/// 2. git config: `tuicr.<forge>.token`
+/// 3. for GitHub, `gh auth token --hostname <host>`
+/// 4. `git credential fill` for `https://<host>`
+/// 5. the system keyring, service `tuicr`, account `<host>`
+pub fn resolve_credential(forge: Forge, host: &str) -> Result<(Credential, CredentialSource)> {
+    let lookups: [&dyn Fn() -> Option<(Credential, CredentialSource)>; 5] = [
+        &|| from_env(forge, |var| std::env::var(var).ok()),
+        &|| from_git_config(forge),
+        &|| {
+            (forge == Forge::GitHub)
+                .then(|| from_gh_cli(host))
+                .flatten()
+        },
+        &|| from_git_credential(host),
+        &|| from_keyring(host),
+    ];
+    lookups.iter().find_map(|lookup| lookup()).ok_or_else(|| {
+        let prefix = forge.env_prefix();
+        let gh = if forge == Forge::GitHub {
+            ", log in with gh auth login"
+        } else {
+            ""
+        };
+        TuicrError::ForgeAuth(format!(
+            "no {} credentials for {host}: set {prefix}_TOKEN (or {prefix}_USERNAME and \
+             {prefix}_PASSWORD), git config tuicr.{}.token{gh}, a git credential helper entry, \
+             or a keyring entry (service tuicr, account {host})",
+            forge.name(),
+            forge.name(),
+        ))
+    })
+}
+
+/// Add the credential source to authentication errors, so the user knows
+/// which secret was rejected
+pub fn explain_auth_failure(error: TuicrError, source: &CredentialSource) -> TuicrError {
+    match error {
+        TuicrError::ForgeAuth(msg) => {
+            TuicrError::ForgeAuth(format!("{msg} (credentials from {source})"))
+        }
+        other => other,
+    }
+}
+
+fn from_env(
+    forge: Forge,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<(Credential, CredentialSource)> {
+    let prefix = forge.env_prefix();
+    let token_var = format!("{prefix}_TOKEN");
+    if let Some(token) = var(&token_var).filter(|t| !t.is_empty()) {
+        return Some((Credential::Token(token), CredentialSource::Env(token_var)));
+    }
+    let user_var = format!("{prefix}_USERNAME");
+    let username = var(&user_var)?;
+    ["PASSWORD", "APP_PASSWORD"].iter().find_map(|suffix| {
+        let password_var = format!("{prefix}_{suffix}");
+        var(&password_var).map(|password| {
+            (
+                Credential::Basic {
+                    username: username.clone(),
+                    password,
+                },
+                CredentialSource::Env(format!("{user_var}/{password_var}")),
+            )
+        })
+    })
+}
+
+fn from_git_config(forge: Forge) -> Option<(Credential, CredentialSource)> {
+    let key = format!("tuicr.{}.token", forge.name());
+    let config = git2::Repository::discover(".")
+        .and_then(|repo| repo.config())
+        .or_else(|_| git2::Config::open_default())
+        .ok()?;
+    let token = config.get_string(&key).ok().filter(|t| !t.is_empty())?;
+    Some((Credential::Token(token), CredentialSource::GitConfig(key)))
+}
+
+fn from_gh_cli(host: &str) -> Option<(Credential, CredentialSource)> {
+    let output = Command::new("gh")
+        .args(["auth", "token", "--hostname", host])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!token.is_empty()).then_some((Credential::Token(token), CredentialSource::GhCli))
+}
+
+fn from_git_credential(host: &str) -> Option<(Credential, CredentialSource)> {
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        // Never prompt on the terminal the TUI is drawing on
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "true")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={host}\n\n").as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let (username, password) = parse_git_credential(&String::from_utf8_lossy(&output.stdout))?;
+    Some((
+        Credential::Basic { username, password },
+        CredentialSource::GitCredential,
+    ))
+}
+
+/// Extract username and password from `git credential fill` output
+fn parse_git_credential(output: &str) -> Option<(String, String)> {
+    let field = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    Some((field("username")?, field("password")?))
+}
+
+fn from_keyring(host: &str) -> Option<(Credential, CredentialSource)> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security")
+            .args(["find-generic-password", "-s", "tuicr", "-a", host, "-w"])
+            .stderr(Stdio::null())
+            .output()
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", "tuicr", "account", host])
+            .stderr(Stdio::null())
+            .output()
+    }
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!token.is_empty()).then_some((Credential::Token(token), CredentialSource::Keyring))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_prefer_token_over_basic_auth_in_env() {
+        // given
+        let vars = |var: &str| match var {
+            "BITBUCKET_TOKEN" => Some("tok".to_string()),
+            "BITBUCKET_USERNAME" => Some("me".to_string()),
+            "BITBUCKET_PASSWORD" => Some("pw".to_string()),
+            _ => None,
+        };
+
+        // when
+        let (credential, source) = from_env(Forge::Bitbucket, vars).unwrap();
+
+        // then
+        assert_eq!(credential.authorization_header(), "Bearer tok");
+        assert_eq!(source.to_string(), "$BITBUCKET_TOKEN");
+    }
+
+    #[test]
+    fn should_use_app_password_with_username() {
+        // given
+        let vars = |var: &str| match var {
+            "BITBUCKET_USERNAME" => Some("me".to_string()),
+            "BITBUCKET_APP_PASSWORD" => Some("pw".to_string()),
+            _ => None,
+        };
+
+        // when
+        let (credential, _) = from_env(Forge::Bitbucket, vars).unwrap();
+
+        // then
+        assert_eq!(credential.authorization_header(), "Basic bWU6cHc=");
+    }
+
+    #[test]
+    fn should_parse_git_credential_output() {
+        assert_eq!(
+            parse_git_credential("protocol=https\nhost=x\nusername=me\npassword=pw\n"),
+            Some(("me".to_string(), "pw".to_string()))
+        );
+        assert_eq!(parse_git_credential("protocol=https\nhost=x\n"), None);
+    }
+
+    #[test]
+    fn should_hide_secrets_in_debug_output() {
+        let credential = Credential::Basic {
+            username: "me".to_string(),
+            password: "secret".to_string(),
+        };
+        assert_eq!(format!("{credential:?}"), "Basic(me:***)");
+    }
+}
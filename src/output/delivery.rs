@@ -0,0 +1,53 @@
+use chrono::Utc;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{ExportPayload, PendingExport, ReviewSession};
+
+use super::credentials::{Forge, explain_auth_failure, resolve_credential};
+use super::http::post_json;
+
+impl PendingExport {
+    pub fn new(forge: Forge, host: &str, endpoint: String, payloads: Vec<ExportPayload>) -> Self {
+        Self {
+            forge: forge.name().to_string(),
+            host: host.to_string(),
+            endpoint,
+            payloads,
+            queued_at: Utc::now(),
+        }
+    }
+}
+
+/// POST the remaining payloads of an export in order, dropping each one and
+/// marking its comment as published as soon as the forge accepts it.
+///
+/// Returns how many were posted. On failure the export still holds exactly
+/// the payloads that were not delivered, so it can be queued and retried
+/// without duplicating comments.
+pub fn deliver(export: &mut PendingExport, session: &mut ReviewSession) -> Result<usize> {
+    let forge = Forge::from_name(&export.forge)
+        .ok_or_else(|| TuicrError::Forge(format!("unknown forge '{}'", export.forge)))?;
+    let (credential, source) = resolve_credential(forge, &export.host)?;
+    let authorization = credential.authorization_header();
+
+    let mut posted = 0;
+    while let Some(payload) = export.payloads.first() {
+        post_json(&export.endpoint, Some(&authorization), &payload.body).map_err(|e| match e {
+            TuicrError::Forge(msg) => TuicrError::Forge(format!(
+                "{msg} ({posted} posted, {} left)",
+                export.payloads.len()
+            )),
+            other => explain_auth_failure(other, &source),
+        })?;
+        let payload = export.payloads.remove(0);
+        for content in &payload.publishes {
+            session.mark_published(
+                &export.endpoint,
+                content.comment_id.as_deref(),
+                &content.fingerprint,
+            );
+        }
+        posted += payload.publishes.len();
+    }
+    Ok(posted)
+}
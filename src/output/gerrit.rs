@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{CommentType, LineSide, ReviewSession};
+
+use super::markdown::{ExportOptions, collect_comments, comment_body, with_anchor_note};
+
+/// Line span of a comment covering several lines. The range ends at
+/// character 0 of the line after the last one so that the whole last line is
+/// highlighted without knowing its length.
+#[derive(Debug, Serialize)]
+struct GerritRange {
+    start_line: u32,
+    start_character: u32,
+    end_line: u32,
+    end_character: u32,
+}
+
+/// One comment of a `CommentInput` list
+#[derive(Debug, Serialize)]
+struct GerritComment {
+    /// Absent for file comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<GerritRange>,
+    /// `PARENT` for comments on the old side; the revision itself otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    side: Option<&'static str>,
+    message: String,
+    /// Issues and suggestions need an answer; notes and praise do not
+    unresolved: bool,
+}
+
+/// A `ReviewInput`, as the body of `POST /changes/{id}/revisions/{rev}/review`
+/// or the input of `ssh <host> gerrit review --json <change>,<patchset>`
+#[derive(Debug, Serialize)]
+struct GerritReview {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    message: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<&'static str, i8>,
+    comments: BTreeMap<String, Vec<GerritComment>>,
+}
+
+/// Code-Review vote for the comments that are still open: -1 when an issue
+/// is left, +1 when everything is praise, and no vote otherwise
+fn code_review_vote(types: &[CommentType]) -> Option<i8> {
+    if types.contains(&CommentType::Issue) {
+        Some(-1)
+    } else if !types.is_empty() && types.iter().all(|t| *t == CommentType::Praise) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Export the review as Gerrit `ReviewInput` JSON: session notes become the
+/// review message, comments are keyed by path with their line (or range) and
+/// side, and the comment types decide the Code-Review label
+pub fn generate_gerrit_json(session: &ReviewSession, options: &ExportOptions) -> Result<String> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let mut comments: BTreeMap<String, Vec<GerritComment>> = BTreeMap::new();
+    let mut open_types = Vec::new();
+    for entry in collect_comments(session) {
+        let comment_type = entry.comment.comment_type;
+        if !entry.comment.resolved {
+            open_types.push(comment_type);
+        }
+        let (line, range) = match entry.line_range {
+            None => (None, None),
+            Some(range) if range.is_single() => (Some(range.end), None),
+            Some(range) => (
+                Some(range.end),
+                Some(GerritRange {
+                    start_line: range.start,
+                    start_character: 0,
+                    end_line: range.end + 1,
+                    end_character: 0,
+                }),
+            ),
+        };
+        let side = (entry.line_range.is_some() && entry.comment.side == Some(LineSide::Old))
+            .then_some("PARENT");
+        comments.entry(entry.file).or_default().push(GerritComment {
+            line,
+            range,
+            side,
+            message: comment_body(entry.comment, options),
+            unresolved: !entry.comment.resolved
+                && matches!(comment_type, CommentType::Issue | CommentType::Suggestion),
+        });
+    }
+
+    let mut labels = BTreeMap::new();
+    if let Some(vote) = code_review_vote(&open_types) {
+        labels.insert("Code-Review", vote);
+    }
+    let review = GerritReview {
+        message: with_anchor_note(session, session.session_notes.clone().unwrap_or_default()),
+        labels,
+        comments,
+    };
+    Ok(serde_json::to_string_pretty(&review)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, FileStatus, LineRange, SessionDiffSource};
+    use serde_json::{Value, json};
+    use std::path::PathBuf;
+
+    fn session() -> ReviewSession {
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(PathBuf::from("src/lib.rs"), FileStatus::Modified);
+        session
+    }
+
+    #[test]
+    fn should_key_comments_by_path_and_vote_on_issues() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = session();
+        session.session_notes = Some("Looks close".to_string());
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_file_comment(Comment::new(
+            "Needs tests".to_string(),
+            CommentType::Issue,
+            None,
+        ));
+        review.add_line_comment(
+            12,
+            Comment::new_with_range(
+                "Dead code".to_string(),
+                CommentType::Note,
+                Some(LineSide::Old),
+                LineRange::new(10, 12),
+            ),
+        );
+
+        // when
+        let json = generate_gerrit_json(&session, &ExportOptions::default()).unwrap();
+
+        // then
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["message"], "Looks close");
+        assert_eq!(value["labels"], json!({ "Code-Review": -1 }));
+        let comments = value["comments"]["src/lib.rs"].as_array().unwrap();
+        assert_eq!(
+            comments[0],
+            json!({ "message": "**ISSUE** Needs tests", "unresolved": true })
+        );
+        assert_eq!(comments[1]["line"], 12);
+        assert_eq!(comments[1]["side"], "PARENT");
+        assert_eq!(comments[1]["range"]["start_line"], 10);
+        assert_eq!(comments[1]["unresolved"], false);
+    }
+
+    #[test]
+    fn should_map_comment_types_to_code_review_votes() {
+        assert_eq!(code_review_vote(&[CommentType::Praise]), Some(1));
+        assert_eq!(
+            code_review_vote(&[CommentType::Praise, CommentType::Issue]),
+            Some(-1)
+        );
+        assert_eq!(code_review_vote(&[CommentType::Suggestion]), None);
+        assert_eq!(code_review_vote(&[]), None);
+    }
+}
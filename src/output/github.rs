@@ -0,0 +1,388 @@
+use serde_json::json;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{
+    DiffFile, ExportPayload, LineOrigin, LineSide, PendingExport, PublishedContent, ReviewSession,
+    content_fingerprint,
+};
+
+use super::credentials::Forge;
+use super::markdown::{
+    ExportOptions, collect_comments, comment_body, expand_issue_refs, with_anchor_note,
+};
+
+/// The pull request a review is posted to, on github.com or GitHub Enterprise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubTarget {
+    host: String,
+    owner: String,
+    repo: String,
+    pr: u64,
+}
+
+impl GitHubTarget {
+    /// Parse a pull request URL as shown in the browser, e.g.
+    /// `https://github.com/owner/repo/pull/12/files`
+    pub fn from_pr_url(url: &str) -> Result<Self> {
+        let invalid = || TuicrError::Parse(format!("not a GitHub pull request URL: {url}"));
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+        match segments[..] {
+            [host, owner, repo, "pull", pr, ..] => Ok(Self {
+                host: host.to_string(),
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                pr: pr.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Host whose credentials are used
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// REST endpoint that creates pull request reviews
+    pub fn reviews_endpoint(&self) -> String {
+        let api = if self.host == "github.com" {
+            "https://api.github.com".to_string()
+        } else {
+            format!("https://{}/api/v3", self.host)
+        };
+        format!(
+            "{api}/repos/{}/{}/pulls/{}/reviews",
+            self.owner, self.repo, self.pr
+        )
+    }
+}
+
+/// Position of a line in the pull request diff of `path`, as GitHub's review
+/// API counts it: 1 is the line below the first hunk header, and the headers
+/// of later hunks count as lines too.
+///
+/// Computed from the local diff, which must match the pull request's.
+fn diff_position(diff_files: &[DiffFile], path: &str, line: u32, side: LineSide) -> Option<u32> {
+    let file = diff_files
+        .iter()
+        .find(|file| file.display_path().to_string_lossy() == path)?;
+    let mut position = 0;
+    for (idx, hunk) in file.hunks.iter().enumerate() {
+        if idx > 0 {
+            position += 1;
+        }
+        for diff_line in &hunk.lines {
+            position += 1;
+            let matches = match side {
+                LineSide::New => {
+                    diff_line.origin != LineOrigin::Deletion && diff_line.new_lineno == Some(line)
+                }
+                LineSide::Old => {
+                    diff_line.origin != LineOrigin::Addition && diff_line.old_lineno == Some(line)
+                }
+            };
+            if matches {
+                return Some(position);
+            }
+        }
+    }
+    None
+}
+
+/// Build the review request: line comments become inline comments at their
+/// diff position, while session notes, file comments and line comments
+/// outside the pull request diff go into the review body. Comments already
+/// published to this pull request in their current form are left out.
+///
+/// Returns the payload (None when there is nothing new to post) and the
+/// anchors of the line comments that could not be mapped to the diff.
+pub fn github_review(
+    session: &ReviewSession,
+    target: &GitHubTarget,
+    options: &ExportOptions,
+) -> Result<(Option<ExportPayload>, Vec<String>)> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let endpoint = target.reviews_endpoint();
+    let mut publishes = Vec::new();
+    let mut general = Vec::new();
+    let mut inline = Vec::new();
+    let mut unmapped = Vec::new();
+    if let Some(notes) = &session.session_notes
+        && !session.notes_published_to(&endpoint)
+    {
+        general.push(expand_issue_refs(options, notes));
+        publishes.push(PublishedContent {
+            comment_id: None,
+            fingerprint: content_fingerprint(notes),
+        });
+    }
+    for entry in collect_comments(session) {
+        if entry.comment.is_published_to(&endpoint) {
+            continue;
+        }
+        let body = comment_body(entry.comment, options);
+        match entry.line_range {
+            None => general.push(format!("`{}`: {body}", entry.file)),
+            Some(range) => {
+                // Review comments sit on a single line: use the last one.
+                // Multi-line suggestions must cover their whole range, which
+                // takes line numbers instead of a diff position.
+                let side = entry.comment.side.unwrap_or(LineSide::New);
+                let position = diff_position(options.diff_files, &entry.file, range.end, side);
+                let spans_range = entry.comment.suggestion().is_some() && !range.is_single();
+                match position {
+                    Some(_)
+                        if spans_range
+                            && diff_position(
+                                options.diff_files,
+                                &entry.file,
+                                range.start,
+                                side,
+                            )
+                            .is_some() =>
+                    {
+                        let side = match side {
+                            LineSide::Old => "LEFT",
+                            LineSide::New => "RIGHT",
+                        };
+                        inline.push(json!({
+                            "path": entry.file,
+                            "start_line": range.start,
+                            "start_side": side,
+                            "line": range.end,
+                            "side": side,
+                            "body": body,
+                        }));
+                    }
+                    Some(position) if !spans_range => inline.push(json!({
+                        "path": entry.file,
+                        "position": position,
+                        "body": body,
+                    })),
+                    _ => {
+                        let anchor = if range.is_single() {
+                            format!("{}:{}", entry.file, range.start)
+                        } else {
+                            format!("{}:{}-{}", entry.file, range.start, range.end)
+                        };
+                        general.push(format!("`{anchor}`: {body}"));
+                        unmapped.push(anchor);
+                    }
+                }
+            }
+        }
+        publishes.push(PublishedContent {
+            comment_id: Some(entry.comment.id.clone()),
+            fingerprint: entry.comment.fingerprint(),
+        });
+    }
+
+    if publishes.is_empty() {
+        return Ok((None, unmapped));
+    }
+    let body = json!({
+        "event": "COMMENT",
+        "body": with_anchor_note(session, general.join("\n\n")),
+        "comments": inline,
+    });
+    Ok((
+        Some(ExportPayload {
+            publishes,
+            body: body.to_string(),
+        }),
+        unmapped,
+    ))
+}
+
+/// Everything needed to post the review's unpublished comments as a GitHub
+/// pull request review; send it with `delivery::deliver`. Also returns the
+/// anchors of line comments that are not on the pull request diff.
+pub fn github_export(
+    session: &ReviewSession,
+    target: &GitHubTarget,
+    options: &ExportOptions,
+) -> Result<(PendingExport, Vec<String>)> {
+    let (payload, unmapped) = github_review(session, target, options)?;
+    let export = PendingExport::new(
+        Forge::GitHub,
+        target.host(),
+        target.reviews_endpoint(),
+        payload.into_iter().collect(),
+    );
+    Ok((export, unmapped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::{hunk_at, modified_file, numbered_line};
+    use crate::model::{Comment, CommentType, DiffLine, FileStatus, LineRange, SessionDiffSource};
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    fn line(origin: LineOrigin, old: Option<u32>, new: Option<u32>) -> DiffLine {
+        numbered_line(origin, "", old, new)
+    }
+
+    fn diff_files() -> Vec<DiffFile> {
+        vec![modified_file(
+            "src/lib.rs",
+            vec![
+                hunk_at(
+                    1,
+                    1,
+                    vec![
+                        line(LineOrigin::Context, Some(1), Some(1)),
+                        line(LineOrigin::Deletion, Some(2), None),
+                        line(LineOrigin::Addition, None, Some(2)),
+                    ],
+                ),
+                hunk_at(
+                    20,
+                    20,
+                    vec![
+                        line(LineOrigin::Context, Some(20), Some(20)),
+                        line(LineOrigin::Addition, None, Some(21)),
+                    ],
+                ),
+            ],
+        )]
+    }
+
+    fn target() -> GitHubTarget {
+        GitHubTarget::from_pr_url("https://github.com/o/r/pull/7").unwrap()
+    }
+
+    #[test]
+    fn should_parse_pr_urls() {
+        // when
+        let cloud = GitHubTarget::from_pr_url("https://github.com/o/r/pull/7/files").unwrap();
+        let enterprise = GitHubTarget::from_pr_url("https://git.corp.com/o/r/pull/3").unwrap();
+
+        // then
+        assert_eq!(
+            cloud.reviews_endpoint(),
+            "https://api.github.com/repos/o/r/pulls/7/reviews"
+        );
+        assert_eq!(enterprise.host(), "git.corp.com");
+        assert_eq!(
+            enterprise.reviews_endpoint(),
+            "https://git.corp.com/api/v3/repos/o/r/pulls/3/reviews"
+        );
+        assert!(GitHubTarget::from_pr_url("https://github.com/o/r/issues/7").is_err());
+    }
+
+    #[test]
+    fn should_count_positions_across_hunks() {
+        let files = diff_files();
+        assert_eq!(
+            diff_position(&files, "src/lib.rs", 2, LineSide::Old),
+            Some(2)
+        );
+        assert_eq!(
+            diff_position(&files, "src/lib.rs", 2, LineSide::New),
+            Some(3)
+        );
+        // The second hunk header takes position 4
+        assert_eq!(
+            diff_position(&files, "src/lib.rs", 21, LineSide::New),
+            Some(6)
+        );
+        assert_eq!(diff_position(&files, "src/lib.rs", 10, LineSide::New), None);
+    }
+
+    #[test]
+    fn should_put_unmapped_and_file_comments_in_review_body() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_file_comment(Comment::new("Split".to_string(), CommentType::Note, None));
+        review.add_line_comment(
+            21,
+            Comment::new("Typo".to_string(), CommentType::Issue, None),
+        );
+        review.add_line_comment(
+            12,
+            Comment::new_with_range(
+                "Gone".to_string(),
+                CommentType::Note,
+                None,
+                LineRange::new(10, 12),
+            ),
+        );
+        let files = diff_files();
+        let options = ExportOptions {
+            diff_files: &files,
+            ..ExportOptions::default()
+        };
+
+        // when
+        let (payload, unmapped) = github_review(&session, &target(), &options).unwrap();
+
+        // then
+        assert_eq!(unmapped, vec!["src/lib.rs:10-12"]);
+        let body: Value = serde_json::from_str(&payload.unwrap().body).unwrap();
+        assert_eq!(
+            body["body"],
+            "`src/lib.rs`: **NOTE** Split\n\n`src/lib.rs:10-12`: **NOTE** Gone"
+        );
+        assert_eq!(
+            body["comments"],
+            json!([{ "path": "src/lib.rs", "position": 6, "body": "**ISSUE** Typo" }])
+        );
+    }
+
+    #[test]
+    fn should_anchor_multi_line_suggestions_to_their_range() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        session.get_file_mut(&path).unwrap().add_line_comment(
+            2,
+            Comment::new_with_range(
+                "```suggestion\nfn main() {}\n```".to_string(),
+                CommentType::Suggestion,
+                Some(LineSide::New),
+                LineRange::new(1, 2),
+            ),
+        );
+        let files = diff_files();
+        let options = ExportOptions {
+            diff_files: &files,
+            ..ExportOptions::default()
+        };
+
+        // when
+        let (payload, _) = github_review(&session, &target(), &options).unwrap();
+
+        // then
+        let body: Value = serde_json::from_str(&payload.unwrap().body).unwrap();
+        assert_eq!(
+            body["comments"],
+            json!([{
+                "path": "src/lib.rs",
+                "start_line": 1,
+                "start_side": "RIGHT",
+                "line": 2,
+                "side": "RIGHT",
+                "body": "**SUGGESTION**\n\n```suggestion\nfn main() {}\n```",
+            }])
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use std::path::Path;
+
+use serde_json::{Value, json};
+
+use crate::error::{Result, TuicrError};
+use crate::model::{
+    DiffFile, ExportPayload, LineOrigin, LineSide, PendingExport, PublishedContent, ReviewSession,
+    content_fingerprint,
+};
+
+use super::credentials::Forge;
+use super::markdown::{
+    ExportOptions, collect_comments, comment_body, expand_issue_refs, with_anchor_note,
+};
+
+/// The merge request a review is posted to, on gitlab.com or a self-hosted
+/// instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitLabTarget {
+    scheme: String,
+    host: String,
+    /// Namespace and project, e.g. `group/subgroup/app`
+    project: String,
+    mr: u64,
+}
+
+impl GitLabTarget {
+    /// Parse a merge request URL as shown in the browser, e.g.
+    /// `https://gitlab.com/group/app/-/merge_requests/12/diffs`
+    pub fn from_mr_url(url: &str) -> Result<Self> {
+        let invalid = || TuicrError::Parse(format!("not a GitLab merge request URL: {url}"));
+        let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+        let (host, path) = rest.split_once('/').ok_or_else(invalid)?;
+        let (project, mr_path) = path.split_once("/-/merge_requests/").ok_or_else(invalid)?;
+        let mr = mr_path
+            .split('/')
+            .next()
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(invalid)?;
+        let project = project.trim_matches('/');
+        if project.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            project: project.to_string(),
+            mr,
+        })
+    }
+
+    /// Host whose credentials are used
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// REST endpoint that creates merge request discussions
+    pub fn discussions_endpoint(&self) -> String {
+        format!(
+            "{}://{}/api/v4/projects/{}/merge_requests/{}/discussions",
+            self.scheme,
+            self.host,
+            self.project.replace('/', "%2F"),
+            self.mr
+        )
+    }
+}
+
+/// The commits GitLab positions a diff comment against: the merge base, the
+/// target branch tip and the source branch tip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRefs {
+    pub base_sha: String,
+    pub start_sha: String,
+    pub head_sha: String,
+}
+
+impl DiffRefs {
+    /// Resolve the refs from the local repository: `HEAD` stands for the
+    /// merge request's source branch and the default branch of the remote
+    /// pointing at the target project for its target branch. The local
+    /// checkout must match what was pushed.
+    pub fn resolve(repo_path: &Path, target: &GitLabTarget) -> Result<Self> {
+        let repo = git2::Repository::discover(repo_path)
+            .map_err(|_| TuicrError::Forge("GitLab export needs a git repository".to_string()))?;
+        let remote = target_remote(&repo, target)?;
+        let head = repo.head()?.peel_to_commit()?.id();
+        let start = [
+            format!("refs/remotes/{remote}/HEAD"),
+            format!("refs/remotes/{remote}/main"),
+            format!("refs/remotes/{remote}/master"),
+        ]
+        .iter()
+        .find_map(|name| repo.find_reference(name).ok()?.resolve().ok()?.target())
+        .ok_or_else(|| {
+            TuicrError::Forge(format!(
+                "cannot tell the target branch: fetch {remote} or run git remote set-head {remote} --auto"
+            ))
+        })?;
+        let base = repo.merge_base(head, start)?;
+        Ok(Self {
+            base_sha: base.to_string(),
+            start_sha: start.to_string(),
+            head_sha: head.to_string(),
+        })
+    }
+}
+
+/// Name of the remote whose URL points at the target project, or `origin`
+fn target_remote(repo: &git2::Repository, target: &GitLabTarget) -> Result<String> {
+    let names = repo.remotes()?;
+    let matching = names.iter().flatten().find(|name| {
+        repo.find_remote(name)
+            .ok()
+            .and_then(|remote| remote.url().map(str::to_string))
+            .is_some_and(|url| {
+                let url = url.trim_end_matches(".git");
+                url.contains(&target.host) && url.ends_with(&target.project)
+            })
+    });
+    match matching {
+        Some(name) => Ok(name.to_string()),
+        None if names.iter().flatten().any(|name| name == "origin") => Ok("origin".to_string()),
+        None => Err(TuicrError::Forge(format!(
+            "no git remote points at {}/{}",
+            target.host, target.project
+        ))),
+    }
+}
+
+/// Old and new line numbers of a line in the local diff of `path`, as GitLab
+/// positions it: added lines only have a new line, removed lines only an old
+/// one and context lines both. None when the line is not in the diff.
+fn diff_lines<'a>(
+    diff_files: &'a [DiffFile],
+    path: &str,
+    line: u32,
+    side: LineSide,
+) -> Option<(&'a DiffFile, Option<u32>, Option<u32>)> {
+    let file = diff_files
+        .iter()
+        .find(|file| file.display_path().to_string_lossy() == path)?;
+    let diff_line = file.hunks.iter().flat_map(|hunk| &hunk.lines).find(|l| {
+        let lineno = match side {
+            LineSide::Old => l.old_lineno,
+            LineSide::New => l.new_lineno,
+        };
+        lineno == Some(line)
+            && match side {
+                LineSide::Old => l.origin != LineOrigin::Addition,
+                LineSide::New => l.origin != LineOrigin::Deletion,
+            }
+    })?;
+    Some((file, diff_line.old_lineno, diff_line.new_lineno))
+}
+
+/// Build one discussion request per review comment, plus one for the session
+/// notes if there are any. Line comments are positioned on the diff; file
+/// comments and line comments outside the diff start general discussions
+/// naming their anchor. Comments already published to this merge request in
+/// their current form are skipped.
+///
+/// Returns the payloads and the anchors of the line comments that could not
+/// be positioned.
+pub fn gitlab_discussions(
+    session: &ReviewSession,
+    target: &GitLabTarget,
+    refs: &DiffRefs,
+    options: &ExportOptions,
+) -> Result<(Vec<ExportPayload>, Vec<String>)> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let endpoint = target.discussions_endpoint();
+    let mut payloads = Vec::new();
+    let mut unmapped = Vec::new();
+    if let Some(notes) = &session.session_notes
+        && !session.notes_published_to(&endpoint)
+    {
+        payloads.push(ExportPayload {
+            publishes: vec![PublishedContent {
+                comment_id: None,
+                fingerprint: content_fingerprint(notes),
+            }],
+            body: json!({ "body": with_anchor_note(session, expand_issue_refs(options, notes)) })
+                .to_string(),
+        });
+    }
+    for entry in collect_comments(session) {
+        if entry.comment.is_published_to(&endpoint) {
+            continue;
+        }
+        let text = comment_body(entry.comment, options);
+        let body = match entry.line_range {
+            None => json!({ "body": format!("`{}`: {text}", entry.file) }),
+            Some(range) => {
+                // A discussion sits on a single line: use the last one
+                let side = entry.comment.side.unwrap_or(LineSide::New);
+                match diff_lines(options.diff_files, &entry.file, range.end, side) {
+                    Some((file, old_line, new_line)) => json!({
+                        "body": text,
+                        "position": position(refs, file, old_line, new_line),
+                    }),
+                    None => {
+                        let anchor = if range.is_single() {
+                            format!("{}:{}", entry.file, range.start)
+                        } else {
+                            format!("{}:{}-{}", entry.file, range.start, range.end)
+                        };
+                        let body = json!({ "body": format!("`{anchor}`: {text}") });
+                        unmapped.push(anchor);
+                        body
+                    }
+                }
+            }
+        };
+        payloads.push(ExportPayload {
+            publishes: vec![PublishedContent {
+                comment_id: Some(entry.comment.id.clone()),
+                fingerprint: entry.comment.fingerprint(),
+            }],
+            body: body.to_string(),
+        });
+    }
+    Ok((payloads, unmapped))
+}
+
+/// The `position` of a diff discussion
+fn position(
+    refs: &DiffRefs,
+    file: &DiffFile,
+    old_line: Option<u32>,
+    new_line: Option<u32>,
+) -> Value {
+    let new_path = file.display_path().to_string_lossy().replace('\\', "/");
+    let old_path = file.old_path.as_ref().map_or_else(
+        || new_path.clone(),
+        |path| path.to_string_lossy().replace('\\', "/"),
+    );
+    let mut position = json!({
+        "position_type": "text",
+        "base_sha": refs.base_sha,
+        "start_sha": refs.start_sha,
+        "head_sha": refs.head_sha,
+        "old_path": old_path,
+        "new_path": new_path,
+    });
+    if let Some(line) = old_line {
+        position["old_line"] = json!(line);
+    }
+    if let Some(line) = new_line {
+        position["new_line"] = json!(line);
+    }
+    position
+}
+
+/// Everything needed to post the review's unpublished comments as GitLab
+/// merge request discussions; send it with `delivery::deliver`. Also returns
+/// the anchors of line comments that are not on the merge request diff.
+pub fn gitlab_export(
+    session: &ReviewSession,
+    target: &GitLabTarget,
+    options: &ExportOptions,
+) -> Result<(PendingExport, Vec<String>)> {
+    let refs = DiffRefs::resolve(&session.repo_path, target)?;
+    let (payloads, unmapped) = gitlab_discussions(session, target, &refs, options)?;
+    let export = PendingExport::new(
+        Forge::GitLab,
+        target.host(),
+        target.discussions_endpoint(),
+        payloads,
+    );
+    Ok((export, unmapped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::{hunk, modified_file, numbered_line};
+    use crate::model::{Comment, CommentType, DiffLine, FileStatus, LineRange, SessionDiffSource};
+    use std::path::PathBuf;
+
+    fn line(origin: LineOrigin, old: Option<u32>, new: Option<u32>) -> DiffLine {
+        numbered_line(origin, "", old, new)
+    }
+
+    fn diff_files() -> Vec<DiffFile> {
+        vec![DiffFile {
+            old_path: Some(PathBuf::from("src/old.rs")),
+            status: FileStatus::Renamed,
+            ..modified_file(
+                "src/lib.rs",
+                vec![hunk(vec![
+                    line(LineOrigin::Context, Some(1), Some(1)),
+                    line(LineOrigin::Deletion, Some(2), None),
+                    line(LineOrigin::Addition, None, Some(2)),
+                ])],
+            )
+        }]
+    }
+
+    fn refs() -> DiffRefs {
+        DiffRefs {
+            base_sha: "b".to_string(),
+            start_sha: "s".to_string(),
+            head_sha: "h".to_string(),
+        }
+    }
+
+    fn target() -> GitLabTarget {
+        GitLabTarget::from_mr_url("https://gitlab.com/group/app/-/merge_requests/7").unwrap()
+    }
+
+    #[test]
+    fn should_parse_mr_urls() {
+        // when
+        let cloud =
+            GitLabTarget::from_mr_url("https://gitlab.com/group/sub/app/-/merge_requests/12/diffs")
+                .unwrap();
+
+        // then
+        assert_eq!(cloud.host(), "gitlab.com");
+        assert_eq!(
+            cloud.discussions_endpoint(),
+            "https://gitlab.com/api/v4/projects/group%2Fsub%2Fapp/merge_requests/12/discussions"
+        );
+        assert!(GitLabTarget::from_mr_url("https://gitlab.com/group/app/-/issues/3").is_err());
+        assert!(GitLabTarget::from_mr_url("https://gitlab.com/-/merge_requests/3").is_err());
+    }
+
+    #[test]
+    fn should_position_line_comments_and_report_unmapped_ones() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Renamed);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_line_comment(
+            1,
+            Comment::new("Context".to_string(), CommentType::Note, None),
+        );
+        review.add_line_comment(
+            2,
+            Comment::new(
+                "Removed".to_string(),
+                CommentType::Issue,
+                Some(LineSide::Old),
+            ),
+        );
+        review.add_line_comment(
+            9,
+            Comment::new_with_range(
+                "Gone".to_string(),
+                CommentType::Note,
+                None,
+                LineRange::new(8, 9),
+            ),
+        );
+        let files = diff_files();
+        let options = ExportOptions {
+            diff_files: &files,
+            ..ExportOptions::default()
+        };
+
+        // when
+        let (payloads, unmapped) =
+            gitlab_discussions(&session, &target(), &refs(), &options).unwrap();
+
+        // then
+        assert_eq!(unmapped, vec!["src/lib.rs:8-9"]);
+        let bodies: Vec<Value> = payloads
+            .iter()
+            .map(|p| serde_json::from_str(&p.body).unwrap())
+            .collect();
+        let positions: Vec<&Value> = bodies.iter().map(|b| &b["position"]).collect();
+        assert!(positions.contains(&&json!({
+            "position_type": "text",
+            "base_sha": "b",
+            "start_sha": "s",
+            "head_sha": "h",
+            "old_path": "src/old.rs",
+            "new_path": "src/lib.rs",
+            "old_line": 1,
+            "new_line": 1,
+        })));
+        assert!(
+            positions
+                .iter()
+                .any(|p| p["old_line"] == 2 && p.get("new_line").is_none())
+        );
+        assert!(
+            bodies
+                .iter()
+                .any(|b| b["body"] == "`src/lib.rs:8-9`: **NOTE** Gone"
+                    && b.get("position").is_none())
+        );
+    }
+
+    #[test]
+    fn should_resolve_diff_refs_from_the_target_remote() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let base = repo
+            .commit(Some("HEAD"), &signature, &signature, "base", &tree, &[])
+            .unwrap();
+        let base_commit = repo.find_commit(base).unwrap();
+        let main = repo
+            .commit(None, &signature, &signature, "main", &tree, &[&base_commit])
+            .unwrap();
+        let head = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "feature",
+                &tree,
+                &[&base_commit],
+            )
+            .unwrap();
+        repo.remote("upstream", "git@gitlab.com:group/app.git")
+            .unwrap();
+        repo.reference("refs/remotes/upstream/main", main, false, "test")
+            .unwrap();
+
+        // when
+        let refs = DiffRefs::resolve(dir.path(), &target()).unwrap();
+
+        // then
+        assert_eq!(
+            refs,
+            DiffRefs {
+                base_sha: base.to_string(),
+                start_sha: main.to_string(),
+                head_sha: head.to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use crate::app::{App, DiffSource};
+use crate::error::{Result, TuicrError};
+use crate::model::{IssueLinker, ReviewSession, SessionDiffSource};
+use crate::persistence::{load_latest_session_for_context, load_session};
+use crate::vcs::detect_vcs;
+
+use super::{
+    ExportFormat, ExportOptions, generate_export_content, generate_gerrit_json,
+    generate_json_export, generate_phabricator_json, generate_rdjson_export, generate_sarif_export,
+};
+
+/// Export a saved review without starting the TUI: the session file at
+/// `session_path`, else the latest review of `range`, else the latest review
+/// of the working tree of the current repository.
+pub fn export_saved_review(
+    format: ExportFormat,
+    range: Option<&str>,
+    session_path: Option<&Path>,
+) -> Result<String> {
+    let session = load_saved_review(range, session_path)?;
+
+    let issue_linker = IssueLinker::from_env();
+    let options = ExportOptions {
+        issue_links: Some(&issue_linker),
+        ..ExportOptions::default()
+    };
+    match format {
+        ExportFormat::Markdown => {
+            let diff_source = match &session.commit_range {
+                Some(ids) => DiffSource::CommitRange(ids.clone()),
+                None => DiffSource::WorkingTree,
+            };
+            generate_export_content(&session, &diff_source, &options)
+        }
+        ExportFormat::Json => generate_json_export(&session, &options),
+        ExportFormat::Phabricator => generate_phabricator_json(&session, &options),
+        ExportFormat::Gerrit => generate_gerrit_json(&session, &options),
+        ExportFormat::Sarif => generate_sarif_export(&session, &options),
+        ExportFormat::Rdjson => generate_rdjson_export(&session, &options),
+    }
+}
+
+/// Check a saved review (found like `export_saved_review` finds it) for
+/// files left unreviewed and unresolved issue comments, e.g. from a pre-push
+/// hook. Returns one line per problem; an empty list means the review is done.
+pub fn check_saved_review(range: Option<&str>, session_path: Option<&Path>) -> Result<Vec<String>> {
+    let session = load_saved_review(range, session_path)?;
+    let mut problems: Vec<String> = session
+        .unreviewed_files()
+        .into_iter()
+        .map(|path| format!("unreviewed file: {}", path.display()))
+        .collect();
+    problems.extend(
+        session
+            .open_issues()
+            .into_iter()
+            .map(|(path, line)| match line {
+                Some(line) => format!("open issue: {}:{line}", path.display()),
+                None => format!("open issue: {}", path.display()),
+            }),
+    );
+    Ok(problems)
+}
+
+fn load_saved_review(range: Option<&str>, session_path: Option<&Path>) -> Result<ReviewSession> {
+    match session_path {
+        Some(path) => load_session(&path.to_path_buf()),
+        None => find_saved_session(range),
+    }
+}
+
+fn find_saved_session(range: Option<&str>) -> Result<ReviewSession> {
+    let vcs = detect_vcs()?;
+    let info = vcs.info();
+    let found = match range {
+        Some(range) => {
+            let ids = vcs.resolve_commit_range(range)?;
+            let newest = ids.last().ok_or(TuicrError::NoChanges)?;
+            load_latest_session_for_context(
+                &info.root_path,
+                info.session_branch(),
+                newest,
+                SessionDiffSource::CommitRange,
+                Some(ids.as_slice()),
+            )?
+        }
+        None => App::load_latest_working_tree_session(info)?,
+    };
+    found
+        .map(|(_path, session)| session)
+        .ok_or(TuicrError::NoSavedReview)
+}
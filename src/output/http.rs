@@ -0,0 +1,142 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::error::{Result, TuicrError};
+use crate::vcs::command_timeout;
+
+/// Longest wait for the connection to a forge
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POST a JSON body and return the response body.
+///
+/// Requests go through `curl` so that no TLS stack has to be linked in. The
+/// request is passed as a curl config on stdin, which keeps credentials out of
+/// the process list.
+pub fn post_json(url: &str, authorization: Option<&str>, body: &str) -> Result<String> {
+    let mut config = format!(
+        "url = {}\nrequest = \"POST\"\nheader = \"Content-Type: application/json\"\n",
+        curl_quote(url)
+    );
+    if let Some(auth) = authorization {
+        config.push_str(&format!(
+            "header = {}\n",
+            curl_quote(&format!("Authorization: {auth}"))
+        ));
+    }
+    config.push_str(&format!(
+        "data-binary = {}\nsilent\nshow-error\nwrite-out = \"\\n%{{http_code}}\"\n",
+        curl_quote(body)
+    ));
+    config.push_str(&timeout_config(command_timeout()));
+
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| TuicrError::Forge(format!("failed to run curl: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let msg = format!("POST {url}: {}", stderr.trim());
+        return Err(if output.status.code().is_some_and(is_network_failure) {
+            TuicrError::ForgeUnreachable(msg)
+        } else {
+            TuicrError::Forge(msg)
+        });
+    }
+    // write-out appends the HTTP status code on its own line
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    match status.trim().parse::<u16>() {
+        Ok(code) if code < 400 => Ok(response.to_string()),
+        Ok(code @ (401 | 403)) => Err(TuicrError::ForgeAuth(format!(
+            "POST {url} returned HTTP {code}"
+        ))),
+        Ok(code) => Err(TuicrError::Forge(format!(
+            "POST {url} returned HTTP {code}: {}",
+            response.trim()
+        ))),
+        Err(_) => Err(TuicrError::Forge(format!(
+            "POST {url}: unexpected curl output"
+        ))),
+    }
+}
+
+/// curl options giving up on a stalled forge after `timeout` (none when
+/// zero), so a post never freezes the UI. curl then exits with 28, a
+/// network failure.
+fn timeout_config(timeout: Duration) -> String {
+    if timeout.is_zero() {
+        return String::new();
+    }
+    let connect = timeout.min(CONNECT_TIMEOUT);
+    format!(
+        "connect-timeout = {}\nmax-time = {}\n",
+        connect.as_secs(),
+        timeout.as_secs()
+    )
+}
+
+/// curl exit codes for failures to reach the server at all (DNS, connect,
+/// timeout, TLS handshake, dropped connection), as opposed to bad requests
+fn is_network_failure(code: i32) -> bool {
+    matches!(code, 5 | 6 | 7 | 28 | 35 | 52 | 55 | 56)
+}
+
+/// Quote a value for a curl config file
+fn curl_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_treat_connection_errors_as_network_failures() {
+        assert!(is_network_failure(7));
+        assert!(is_network_failure(28));
+        assert!(!is_network_failure(22));
+    }
+
+    #[test]
+    fn should_limit_requests_to_the_command_timeout() {
+        assert_eq!(
+            timeout_config(Duration::from_secs(60)),
+            "connect-timeout = 10\nmax-time = 60\n"
+        );
+        assert_eq!(
+            timeout_config(Duration::from_secs(5)),
+            "connect-timeout = 5\nmax-time = 5\n"
+        );
+        assert_eq!(timeout_config(Duration::ZERO), "");
+    }
+
+    #[test]
+    fn should_escape_curl_config_values() {
+        assert_eq!(
+            curl_quote("{\"a\":\"b\\\\c\"}\n"),
+            r#""{\"a\":\"b\\\\c\"}\n""#
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use serde::Serialize;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{LineSide, ReviewAnchor, ReviewSession};
+
+use super::markdown::{ExportOptions, collect_comments, expand_issue_refs};
+
+/// One comment with its location, for scripts consuming a review
+#[derive(Debug, Serialize)]
+struct JsonComment {
+    file: String,
+    /// Absent for file comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    side: Option<LineSide>,
+    #[serde(rename = "type")]
+    comment_type: String,
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<String>,
+    resolved: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    replies: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonReview {
+    repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    base_commit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commits: Option<Vec<String>>,
+    /// The revisions the line numbers refer to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anchor: Option<ReviewAnchor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    comments: Vec<JsonComment>,
+}
+
+/// Export the review as plain JSON: where it was made, the session notes and
+/// every comment sorted by file and line
+pub fn generate_json_export(session: &ReviewSession, options: &ExportOptions) -> Result<String> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let comments = collect_comments(session)
+        .into_iter()
+        .map(|entry| {
+            let comment = entry.comment;
+            JsonComment {
+                file: entry.file,
+                start_line: entry.line_range.map(|range| range.start),
+                end_line: entry.line_range.map(|range| range.end),
+                side: entry.line_range.map(|_| comment.side.unwrap_or_default()),
+                comment_type: comment.comment_type.as_str().to_lowercase(),
+                content: expand_issue_refs(options, &comment.content_without_suggestion()),
+                suggestion: comment.suggestion(),
+                resolved: comment.resolved,
+                assignee: comment.assignee.clone(),
+                replies: comment
+                    .replies
+                    .iter()
+                    .map(|reply| expand_issue_refs(options, &reply.content))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let review = JsonReview {
+        repository: session.repo_path.display().to_string(),
+        branch: session.branch_name.clone(),
+        base_commit: session.base_commit.clone(),
+        commits: session.commit_range.clone(),
+        anchor: session.anchor.clone(),
+        notes: session.session_notes.clone(),
+        comments,
+    };
+    Ok(serde_json::to_string_pretty(&review)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, CommentType, FileStatus, LineRange, SessionDiffSource};
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_export_comments_with_their_location() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            Some("main".to_string()),
+            SessionDiffSource::WorkingTree,
+        );
+        session.session_notes = Some("Looks close".to_string());
+        session.add_file(path.clone(), FileStatus::Modified);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_file_comment(Comment::new(
+            "Needs tests".to_string(),
+            CommentType::Issue,
+            None,
+        ));
+        review.add_line_comment(
+            12,
+            Comment::new_with_range(
+                "Dead code".to_string(),
+                CommentType::Note,
+                Some(LineSide::Old),
+                LineRange::new(10, 12),
+            ),
+        );
+
+        // when
+        let json = generate_json_export(&session, &ExportOptions::default()).unwrap();
+
+        // then
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["branch"], "main");
+        assert_eq!(value["notes"], "Looks close");
+        let comments = value["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0]["type"], "issue");
+        assert!(comments[0].get("start_line").is_none());
+        assert_eq!(comments[1]["start_line"], 10);
+        assert_eq!(comments[1]["end_line"], 12);
+        assert_eq!(comments[1]["side"], "old");
+        assert_eq!(comments[1]["content"], "Dead code");
+    }
+
+    #[test]
+    fn should_export_the_revisions_reviewed() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.anchor = Some(ReviewAnchor {
+            base: "abc123".to_string(),
+            head: Some("def456".to_string()),
+        });
+        session.add_file(path.clone(), FileStatus::Modified);
+        session
+            .get_file_mut(&path)
+            .unwrap()
+            .add_file_comment(Comment::new(
+                "Needs tests".to_string(),
+                CommentType::Issue,
+                None,
+            ));
+
+        // when
+        let json = generate_json_export(&session, &ExportOptions::default()).unwrap();
+
+        // then
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["anchor"]["base"], "abc123");
+        assert_eq!(value["anchor"]["head"], "def456");
+    }
+
+    #[test]
+    fn should_refuse_sessions_without_comments() {
+        let session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        assert!(matches!(
+            generate_json_export(&session, &ExportOptions::default()),
+            Err(TuicrError::NoComments)
+        ));
+    }
+}
@@ -1,52 +1,206 @@
 use std::fmt::Write;
 use std::io::Write as IoWrite;
+use std::path::PathBuf;
 
 use arboard::Clipboard;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
 use crate::app::DiffSource;
 use crate::error::{Result, TuicrError};
-use crate::model::{LineRange, LineSide, ReviewSession};
+use crate::model::{
+    Comment, DiffFile, IssueLinker, LineOrigin, LineRange, LineSide, ReviewSession,
+};
+
+/// Settings that change what goes into an export
+#[derive(Default)]
+pub struct ExportOptions<'a> {
+    /// Current diff, used to embed context snippets
+    pub diff_files: &'a [DiffFile],
+    /// Lines of diff context to include around each line comment (0 = none)
+    pub context_lines: usize,
+    /// Expands issue references in comment text to links
+    pub issue_links: Option<&'a IssueLinker>,
+}
+
+pub(crate) fn expand_issue_refs(options: &ExportOptions, text: &str) -> String {
+    match options.issue_links {
+        Some(linker) => linker.expand(text),
+        None => text.to_string(),
+    }
+}
+
+/// Comment text for forges: type in bold, then the content, a suggested
+/// change as a ```suggestion block, and any replies as quotes
+/// The revisions the review's line numbers refer to, for a line of the export
+pub(crate) fn anchor_note(session: &ReviewSession) -> Option<String> {
+    let anchor = session.anchor.as_ref()?;
+    Some(format!("Line numbers refer to {anchor}"))
+}
+
+/// `text` followed by the [`anchor_note`] of `session`
+pub(crate) fn with_anchor_note(session: &ReviewSession, text: String) -> String {
+    match anchor_note(session) {
+        Some(note) if text.is_empty() => note,
+        Some(note) => format!("{text}\n\n{note}"),
+        None => text,
+    }
+}
+
+pub(crate) fn comment_body(comment: &Comment, options: &ExportOptions) -> String {
+    let mut body = format!("**{}**", comment.comment_type.as_str());
+    let text = comment.content_without_suggestion();
+    if !text.is_empty() {
+        body.push_str(&format!(" {}", expand_issue_refs(options, &text)));
+    }
+    if let Some(replacement) = comment.suggestion() {
+        body.push_str(&format!("\n\n```suggestion\n{replacement}\n```"));
+    }
+    for reply in &comment.replies {
+        body.push_str(&format!(
+            "\n\n> {}",
+            expand_issue_refs(options, &reply.content)
+        ));
+    }
+    body
+}
+
+/// A comment flattened for export together with its location
+pub(crate) struct CommentEntry<'a> {
+    pub file: String,
+    /// None for file-level comments
+    pub line_range: Option<LineRange>,
+    pub comment: &'a Comment,
+}
+
+/// Flatten every comment of the session in the session's `comment_sort`
+/// order. File order sorts by file path and then line, with file comments
+/// before the line comments of their file; the other orders keep file order
+/// among equals.
+pub(crate) fn collect_comments(session: &ReviewSession) -> Vec<CommentEntry<'_>> {
+    let mut all_comments: Vec<CommentEntry> = Vec::new();
+
+    // Sort files by path for consistent output
+    let mut files: Vec<_> = session.files.iter().collect();
+    files.sort_by_key(|(path, _)| path.to_string_lossy().to_string());
+
+    for (path, review) in files {
+        let path_str = path.display().to_string();
+
+        // File comments (no line number)
+        for comment in &review.file_comments {
+            all_comments.push(CommentEntry {
+                file: path_str.clone(),
+                line_range: None,
+                comment,
+            });
+        }
+
+        // Line comments (with line number, sorted)
+        let mut line_comments: Vec<_> = review.line_comments.iter().collect();
+        line_comments.sort_by_key(|(line, _)| *line);
+
+        for (line, comments) in line_comments {
+            for comment in comments {
+                // Use comment's line_range if available, otherwise use the key line
+                let line_range = comment
+                    .line_range
+                    .or_else(|| Some(LineRange::single(*line)));
+                all_comments.push(CommentEntry {
+                    file: path_str.clone(),
+                    line_range,
+                    comment,
+                });
+            }
+        }
+    }
 
-/// (file_path, line_range, side, comment_type, content)
-type CommentEntry<'a> = (
-    String,
-    Option<LineRange>,
-    Option<LineSide>,
-    &'a str,
-    &'a str,
-);
+    all_comments.sort_by(|a, b| session.comment_sort.compare(a.comment, b.comment));
+    all_comments
+}
 
 /// Generate markdown content from the review session.
 /// Returns the markdown string or an error if there are no comments.
 pub fn generate_export_content(
     session: &ReviewSession,
     diff_source: &DiffSource,
+    options: &ExportOptions,
 ) -> Result<String> {
     if !session.has_comments() {
         return Err(TuicrError::NoComments);
     }
-    Ok(generate_markdown(session, diff_source))
+    Ok(generate_markdown(session, diff_source, options))
 }
 
-pub fn export_to_clipboard(session: &ReviewSession, diff_source: &DiffSource) -> Result<String> {
-    let content = generate_export_content(session, diff_source)?;
+/// Largest text copied to the clipboard in one go. OSC 52 sends it base64
+/// encoded, and many terminals and tmux drop sequences beyond ~100 KB.
+pub const CLIPBOARD_LIMIT_BYTES: usize = 64 * 1024;
+
+/// Copy arbitrary text to the clipboard, returning a status message that
+/// starts with `what`. Text over `CLIPBOARD_LIMIT_BYTES` is refused rather
+/// than risking a silently truncated copy.
+pub fn copy_to_clipboard(content: &str, what: &str) -> Result<String> {
+    if content.len() > CLIPBOARD_LIMIT_BYTES {
+        return Err(TuicrError::ClipboardTooLarge(content.len()));
+    }
 
     // Prefer OSC 52 in tmux/SSH where arboard may silently fail
     if should_prefer_osc52() {
-        copy_osc52(&content)?;
-        return Ok("Review copied to clipboard (via terminal)".to_string());
+        copy_osc52(content)?;
+        return Ok(format!("{what} copied to clipboard (via terminal)"));
     }
 
     // Try arboard (system clipboard) first, fall back to OSC 52 for SSH/remote sessions
-    match Clipboard::new().and_then(|mut cb| cb.set_text(&content)) {
-        Ok(_) => Ok("Review copied to clipboard".to_string()),
+    match Clipboard::new().and_then(|mut cb| cb.set_text(content)) {
+        Ok(_) => Ok(format!("{what} copied to clipboard")),
         Err(_) => {
             // Fall back to OSC 52 escape sequence (works over SSH)
-            copy_osc52(&content)?;
-            Ok("Review copied to clipboard (via terminal)".to_string())
+            copy_osc52(content)?;
+            Ok(format!("{what} copied to clipboard (via terminal)"))
+        }
+    }
+}
+
+/// Split text into chunks of at most `max_bytes`, breaking between lines
+/// unless a single line is longer than a chunk
+pub fn split_into_chunks(content: &str, max_bytes: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for mut line in content.split_inclusive('\n') {
+        while !line.is_empty() {
+            if current.len() + line.len() <= max_bytes {
+                current.push_str(line);
+                break;
+            }
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+                continue;
+            }
+            let mut cut = max_bytes;
+            while !line.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            if cut == 0 {
+                cut = line.chars().next().map_or(1, char::len_utf8);
+            }
+            chunks.push(line[..cut].to_string());
+            line = &line[cut..];
         }
     }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Write an export to a new file in the temp directory, returning its path
+pub fn write_export_file(content: &str, extension: &str) -> Result<PathBuf> {
+    let name = format!(
+        "tuicr-review-{}.{extension}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, content)?;
+    Ok(path)
 }
 
 /// Returns true if we should prefer OSC 52 over the system clipboard.
@@ -76,7 +230,11 @@ fn write_osc52<W: IoWrite>(writer: &mut W, text: &str) -> Result<()> {
     Ok(())
 }
 
-fn generate_markdown(session: &ReviewSession, diff_source: &DiffSource) -> String {
+fn generate_markdown(
+    session: &ReviewSession,
+    diff_source: &DiffSource,
+    options: &ExportOptions,
+) -> String {
     let mut md = String::new();
 
     // Intro for agents
@@ -89,6 +247,10 @@ fn generate_markdown(session: &ReviewSession, diff_source: &DiffSource) -> Strin
     // Include commit range info if reviewing commits
     match diff_source {
         DiffSource::WorkingTree => {}
+        DiffSource::Ref(base) => {
+            let _ = writeln!(md, "Reviewing changes against: {base}");
+            let _ = writeln!(md);
+        }
         DiffSource::CommitRange(commits) => {
             if commits.len() == 1 {
                 let _ = writeln!(
@@ -103,6 +265,10 @@ fn generate_markdown(session: &ReviewSession, diff_source: &DiffSource) -> Strin
             let _ = writeln!(md);
         }
     }
+    if let Some(note) = anchor_note(session) {
+        let _ = writeln!(md, "{note}");
+        let _ = writeln!(md);
+    }
 
     let _ = writeln!(
         md,
@@ -117,50 +283,13 @@ fn generate_markdown(session: &ReviewSession, diff_source: &DiffSource) -> Strin
     }
 
     // Collect all comments into a flat list
-    let mut all_comments: Vec<CommentEntry> = Vec::new();
-
-    // Sort files by path for consistent output
-    let mut files: Vec<_> = session.files.iter().collect();
-    files.sort_by_key(|(path, _)| path.to_string_lossy().to_string());
-
-    for (path, review) in files {
-        let path_str = path.display().to_string();
-
-        // File comments (no line number)
-        for comment in &review.file_comments {
-            all_comments.push((
-                path_str.clone(),
-                None,
-                None,
-                comment.comment_type.as_str(),
-                &comment.content,
-            ));
-        }
-
-        // Line comments (with line number, sorted)
-        let mut line_comments: Vec<_> = review.line_comments.iter().collect();
-        line_comments.sort_by_key(|(line, _)| *line);
-
-        for (line, comments) in line_comments {
-            for comment in comments {
-                // Use comment's line_range if available, otherwise use the key line
-                let line_range = comment
-                    .line_range
-                    .or_else(|| Some(LineRange::single(*line)));
-                all_comments.push((
-                    path_str.clone(),
-                    line_range,
-                    comment.side,
-                    comment.comment_type.as_str(),
-                    &comment.content,
-                ));
-            }
-        }
-    }
+    let all_comments = collect_comments(session);
 
     // Output numbered list
-    for (i, (file, line_range, side, comment_type, content)) in all_comments.iter().enumerate() {
-        let location = match (line_range, side) {
+    for (i, entry) in all_comments.iter().enumerate() {
+        let file = &entry.file;
+        let comment = entry.comment;
+        let location = match (entry.line_range, comment.side) {
             // Range on deleted side (old lines)
             (Some(range), Some(LineSide::Old)) if range.is_single() => {
                 format!("`{}:~{}`", file, range.start)
@@ -178,24 +307,103 @@ fn generate_markdown(session: &ReviewSession, diff_source: &DiffSource) -> Strin
             // File comment
             (None, _) => format!("`{file}`"),
         };
+        let resolved = if comment.resolved { " (resolved)" } else { "" };
+        let assignee = comment
+            .assignee
+            .as_ref()
+            .map(|name| format!(" (assigned to @{name})"))
+            .unwrap_or_default();
         let _ = writeln!(
             md,
-            "{}. **[{}]** {} - {}",
+            "{}. **[{}]** {} - {}{}{}",
             i + 1,
-            comment_type,
+            comment.comment_type.as_str(),
             location,
-            content
+            expand_issue_refs(options, &comment.content_without_suggestion()),
+            resolved,
+            assignee
         );
+        if let Some(replacement) = comment.suggestion() {
+            let _ = writeln!(md, "   ```suggestion");
+            for line in replacement.lines() {
+                let _ = writeln!(md, "   {line}");
+            }
+            let _ = writeln!(md, "   ```");
+        }
+        for reply in &comment.replies {
+            let _ = writeln!(
+                md,
+                "   - Reply: {}",
+                expand_issue_refs(options, &reply.content)
+            );
+        }
+        if let Some(range) = entry.line_range
+            && options.context_lines > 0
+            && let Some(snippet) = context_snippet(
+                options.diff_files,
+                file,
+                range,
+                comment.side.unwrap_or(LineSide::New),
+                options.context_lines,
+            )
+        {
+            let _ = writeln!(md, "   ```diff");
+            for line in snippet {
+                let _ = writeln!(md, "   {line}");
+            }
+            let _ = writeln!(md, "   ```");
+        }
     }
 
     md
 }
 
+/// Diff lines around a commented range, prefixed with their origin marker.
+/// Returns None when the file or line is not part of the current diff.
+fn context_snippet(
+    diff_files: &[DiffFile],
+    file: &str,
+    range: LineRange,
+    side: LineSide,
+    context: usize,
+) -> Option<Vec<String>> {
+    let diff_file = diff_files
+        .iter()
+        .find(|f| f.display_path().to_string_lossy() == file)?;
+    let lines: Vec<_> = diff_file.hunks.iter().flat_map(|h| &h.lines).collect();
+    let lineno = |idx: usize| match side {
+        LineSide::New => lines[idx].new_lineno,
+        LineSide::Old => lines[idx].old_lineno,
+    };
+    let first = (0..lines.len()).find(|&i| lineno(i) == Some(range.start))?;
+    let last = (first..lines.len())
+        .rev()
+        .find(|&i| lineno(i) == Some(range.end))
+        .unwrap_or(first);
+
+    let start = first.saturating_sub(context);
+    let end = (last + context).min(lines.len() - 1);
+    Some(
+        lines[start..=end]
+            .iter()
+            .map(|line| {
+                let prefix = match line.origin {
+                    LineOrigin::Addition => '+',
+                    LineOrigin::Deletion => '-',
+                    LineOrigin::Context => ' ',
+                };
+                format!("{prefix}{}", line.content)
+            })
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Comment, CommentType, FileStatus, LineRange, LineSide, SessionDiffSource};
-    use std::path::PathBuf;
+    use crate::model::{
+        Comment, CommentType, FileStatus, LineRange, LineSide, ReviewAnchor, SessionDiffSource,
+    };
 
     fn create_test_session() -> ReviewSession {
         let mut session = ReviewSession::new(
@@ -234,7 +442,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("I reviewed your code and have the following comments"));
@@ -247,6 +455,26 @@ mod tests {
         assert!(markdown.contains("Magic number"));
     }
 
+    #[test]
+    fn should_name_the_revisions_reviewed() {
+        // given
+        let mut session = create_test_session();
+        session.anchor = Some(ReviewAnchor {
+            base: "abc1234def".to_string(),
+            head: None,
+        });
+
+        // when
+        let markdown = generate_markdown(
+            &session,
+            &DiffSource::WorkingTree,
+            &ExportOptions::default(),
+        );
+
+        // then
+        assert!(markdown.contains("Line numbers refer to base abc1234def, head working tree"));
+    }
+
     #[test]
     fn should_number_comments_sequentially() {
         // given
@@ -254,7 +482,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         // Should have 2 numbered comments
@@ -262,6 +490,81 @@ mod tests {
         assert!(markdown.contains("2. **[ISSUE]**"));
     }
 
+    #[test]
+    fn should_export_replies_and_resolved_state() {
+        // given
+        let mut session = create_test_session();
+        let review = session.get_file_mut(&PathBuf::from("src/main.rs")).unwrap();
+        let comment = &mut review.line_comments.get_mut(&42).unwrap()[0];
+        comment.resolved = true;
+        comment.assignee = Some("alice".to_string());
+        comment.replies.push(crate::model::CommentReply::new(
+            "Fixed in next commit".to_string(),
+        ));
+
+        // when
+        let markdown = generate_markdown(
+            &session,
+            &DiffSource::WorkingTree,
+            &ExportOptions::default(),
+        );
+
+        // then
+        assert!(
+            markdown.contains("Magic number should be a constant (resolved) (assigned to @alice)")
+        );
+        assert!(markdown.contains("   - Reply: Fixed in next commit"));
+    }
+
+    #[test]
+    fn should_export_suggestions_as_suggestion_blocks() {
+        // given
+        let mut session = create_test_session();
+        let review = session.get_file_mut(&PathBuf::from("src/main.rs")).unwrap();
+        let comment = &mut review.line_comments.get_mut(&42).unwrap()[0];
+        comment.comment_type = CommentType::Suggestion;
+        comment.content = "Name it\n```suggestion\nconst LIMIT: u32 = 42;\n```".to_string();
+
+        // when
+        let markdown = generate_markdown(
+            &session,
+            &DiffSource::WorkingTree,
+            &ExportOptions::default(),
+        );
+        let body = comment_body(
+            &session.files[&PathBuf::from("src/main.rs")].line_comments[&42][0],
+            &ExportOptions::default(),
+        );
+
+        // then
+        assert!(markdown.contains(
+            "`src/main.rs:42` - Name it\n   ```suggestion\n   const LIMIT: u32 = 42;\n   ```\n"
+        ));
+        assert_eq!(
+            body,
+            "**SUGGESTION** Name it\n\n```suggestion\nconst LIMIT: u32 = 42;\n```"
+        );
+    }
+
+    #[test]
+    fn should_link_issue_refs_in_export() {
+        // given
+        let mut session = create_test_session();
+        let review = session.get_file_mut(&PathBuf::from("src/main.rs")).unwrap();
+        review.line_comments.get_mut(&42).unwrap()[0].content = "See #7".to_string();
+        let linker = IssueLinker::parse("#=https://example.com/issues/{id}");
+        let options = ExportOptions {
+            issue_links: Some(&linker),
+            ..ExportOptions::default()
+        };
+
+        // when
+        let markdown = generate_markdown(&session, &DiffSource::WorkingTree, &options);
+
+        // then
+        assert!(markdown.contains("See [#7](https://example.com/issues/7)"));
+    }
+
     #[test]
     fn should_fail_export_when_no_comments() {
         // given
@@ -274,13 +577,41 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let result = export_to_clipboard(&session, &diff_source);
+        let result = generate_export_content(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), TuicrError::NoComments));
     }
 
+    #[test]
+    fn should_split_chunks_between_lines() {
+        // when
+        let chunks = split_into_chunks("aaa\nbb\ncccc\n", 8);
+
+        // then
+        assert_eq!(chunks, vec!["aaa\nbb\n", "cccc\n"]);
+    }
+
+    #[test]
+    fn should_cut_lines_longer_than_a_chunk_at_char_boundaries() {
+        // when
+        let chunks = split_into_chunks("ééé\nx", 3);
+
+        // then
+        assert_eq!(chunks.concat(), "ééé\nx");
+        assert!(chunks.iter().all(|c| c.len() <= 3));
+    }
+
+    #[test]
+    fn should_refuse_to_copy_oversized_text() {
+        // when
+        let result = copy_to_clipboard(&"x".repeat(CLIPBOARD_LIMIT_BYTES + 1), "Review");
+
+        // then
+        assert!(matches!(result, Err(TuicrError::ClipboardTooLarge(_))));
+    }
+
     #[test]
     fn should_generate_export_content_with_comments() {
         // given
@@ -288,7 +619,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let result = generate_export_content(&session, &diff_source);
+        let result = generate_export_content(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(result.is_ok());
@@ -310,7 +641,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let result = generate_export_content(&session, &diff_source);
+        let result = generate_export_content(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(result.is_err());
@@ -327,7 +658,7 @@ mod tests {
         ]);
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("Reviewing commits: abc1234, def4567"));
@@ -340,7 +671,7 @@ mod tests {
         let diff_source = DiffSource::CommitRange(vec!["abc1234567890".to_string()]);
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("Reviewing commit: abc1234"));
@@ -401,7 +732,7 @@ mod tests {
         // given - simulate what would be copied during export
         let session = create_test_session();
         let diff_source = DiffSource::WorkingTree;
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
         let mut buffer: Vec<u8> = Vec::new();
 
         // when
@@ -443,7 +774,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("`src/main.rs:42`"));
@@ -476,7 +807,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("`src/main.rs:10-15`"));
@@ -509,7 +840,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("`src/main.rs:~20-~25`"));
@@ -541,7 +872,7 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("`src/main.rs:~30`"));
@@ -573,9 +904,48 @@ mod tests {
         let diff_source = DiffSource::WorkingTree;
 
         // when
-        let markdown = generate_markdown(&session, &diff_source);
+        let markdown = generate_markdown(&session, &diff_source, &ExportOptions::default());
 
         // then
         assert!(markdown.contains("`src/main.rs:50`"));
     }
+
+    #[test]
+    fn should_embed_diff_context_around_line_comments() {
+        // given
+        use crate::model::LineOrigin;
+        use crate::model::diff_types::test_diffs::{hunk_at, modified_file, numbered_line};
+        let session = create_test_session();
+        let line =
+            |origin, content, new_lineno| numbered_line(origin, content, None, Some(new_lineno));
+        let diff_files = vec![modified_file(
+            "src/main.rs",
+            vec![hunk_at(
+                40,
+                40,
+                vec![
+                    line(LineOrigin::Context, "fn run() {", 40),
+                    line(LineOrigin::Context, "    let a = 1;", 41),
+                    line(LineOrigin::Addition, "    let timeout = 30;", 42),
+                    line(LineOrigin::Context, "}", 43),
+                ],
+            )],
+        )];
+        let options = ExportOptions {
+            diff_files: &diff_files,
+            context_lines: 1,
+            ..ExportOptions::default()
+        };
+
+        // when
+        let markdown = generate_markdown(&session, &DiffSource::WorkingTree, &options);
+
+        // then
+        assert!(
+            markdown.contains(
+                "   ```diff\n        let a = 1;\n   +    let timeout = 30;\n    }\n   ```"
+            )
+        );
+        assert!(!markdown.contains("fn run()"));
+    }
 }
@@ -1,3 +1,67 @@
+pub mod bitbucket;
+pub mod credentials;
+pub mod delivery;
+pub mod gerrit;
+pub mod github;
+pub mod gitlab;
+mod headless;
+mod http;
+pub mod json;
 pub mod markdown;
+mod patch;
+pub mod phabricator;
+pub mod rdjson;
+pub mod sarif;
+pub mod webhook;
 
-pub use markdown::{export_to_clipboard, generate_export_content};
+pub use gerrit::generate_gerrit_json;
+pub use headless::{check_saved_review, export_saved_review};
+pub use json::generate_json_export;
+pub use markdown::{
+    CLIPBOARD_LIMIT_BYTES, ExportOptions, copy_to_clipboard, generate_export_content,
+    split_into_chunks, write_export_file,
+};
+pub use patch::selection_patch;
+pub use phabricator::generate_phabricator_json;
+pub use rdjson::generate_rdjson_export;
+pub use sarif::generate_sarif_export;
+
+/// Formats a review can be exported in with `:export <format>` or
+/// `tuicr export --format <format>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    /// Comments and their locations as plain JSON
+    Json,
+    /// Differential inline-comment JSON
+    Phabricator,
+    /// Gerrit `ReviewInput` JSON
+    Gerrit,
+    /// SARIF 2.1.0 log for CI annotations and code scanning
+    Sarif,
+    /// Reviewdog Diagnostic Format for `reviewdog -f=rdjson`
+    Rdjson,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            "phab" | "phabricator" => Some(Self::Phabricator),
+            "gerrit" => Some(Self::Gerrit),
+            "sarif" => Some(Self::Sarif),
+            "rdjson" => Some(Self::Rdjson),
+            _ => None,
+        }
+    }
+
+    /// File extension used when the export is written to a file
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json | Self::Phabricator | Self::Gerrit | Self::Rdjson => "json",
+            Self::Sarif => "sarif",
+        }
+    }
+}
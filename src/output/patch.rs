@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use crate::model::{DiffFile, FileStatus, LineOrigin};
+
+/// Build a unified diff holding only the chosen changes of `file`, in the
+/// format `git apply` accepts. `selected(hunk_idx, line_idx)` picks the
+/// changed lines to keep: unpicked additions are dropped and unpicked
+/// deletions stay as context, like `git add -p` editing a hunk. Returns
+/// `None` when no changed line is picked.
+pub fn selection_patch(file: &DiffFile, selected: impl Fn(usize, usize) -> bool) -> Option<String> {
+    let mut hunks = String::new();
+    let mut all_picked = true;
+    // Lines the picked changes add to the file so far, to place later hunks
+    let mut delta: i64 = 0;
+
+    for (hunk_idx, hunk) in file.hunks.iter().enumerate() {
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0u32, 0u32);
+        let mut picked_any = false;
+        for (line_idx, line) in hunk.lines.iter().enumerate() {
+            let picked = line.origin != LineOrigin::Context && selected(hunk_idx, line_idx);
+            let prefix = match (line.origin, picked) {
+                (LineOrigin::Context, _) | (LineOrigin::Deletion, false) => ' ',
+                (LineOrigin::Addition, false) => {
+                    all_picked = false;
+                    continue;
+                }
+                (LineOrigin::Addition, true) => '+',
+                (LineOrigin::Deletion, true) => '-',
+            };
+            if line.origin == LineOrigin::Deletion && !picked {
+                all_picked = false;
+            }
+            picked_any |= picked;
+            if prefix != '+' {
+                old_count += 1;
+            }
+            if prefix != '-' {
+                new_count += 1;
+            }
+            let cr = if line.crlf { "\r" } else { "" };
+            let _ = writeln!(body, "{prefix}{}{cr}", line.content);
+            if line.no_newline_at_eof {
+                body.push_str("\\ No newline at end of file\n");
+            }
+        }
+        if !picked_any {
+            continue;
+        }
+
+        // An empty side starts at the line before the hunk, as git writes it
+        let old_start = i64::from(hunk.old_start);
+        let new_start = match (old_count, new_count) {
+            (0, _) => old_start + 1 + delta,
+            (_, 0) => old_start - 1 + delta,
+            _ => old_start + delta,
+        };
+        let _ = writeln!(
+            hunks,
+            "@@ -{},{old_count} +{},{new_count} @@",
+            hunk.old_start,
+            new_start.max(0)
+        );
+        hunks.push_str(&body);
+        delta += i64::from(new_count) - i64::from(old_count);
+    }
+    if hunks.is_empty() {
+        return None;
+    }
+
+    let new_path = file.display_path().to_string_lossy().replace('\\', "/");
+    let old_path = file
+        .old_path
+        .as_ref()
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|| new_path.clone());
+    // A partly staged deletion leaves the file in place
+    let deleted = file.status == FileStatus::Deleted && all_picked;
+    let (from, to) = match file.status {
+        FileStatus::Added => ("/dev/null".to_string(), format!("b/{new_path}")),
+        _ if deleted => (format!("a/{old_path}"), "/dev/null".to_string()),
+        _ => (format!("a/{old_path}"), format!("b/{new_path}")),
+    };
+    let to_path = if deleted { &old_path } else { &new_path };
+    let mut patch = format!("diff --git a/{old_path} b/{to_path}\n");
+    if file.status == FileStatus::Added {
+        patch.push_str("new file mode 100644\n");
+    } else if deleted {
+        patch.push_str("deleted file mode 100644\n");
+    }
+    let _ = write!(patch, "--- {from}\n+++ {to}\n{hunks}");
+    Some(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::{self, hunk_at, line};
+
+    fn modified_file() -> DiffFile {
+        use LineOrigin::*;
+        test_diffs::modified_file(
+            "src/lib.rs",
+            vec![
+                hunk_at(
+                    1,
+                    1,
+                    vec![
+                        line(Context, "a"),
+                        line(Addition, "b"),
+                        line(Addition, "c"),
+                        line(Context, "d"),
+                    ],
+                ),
+                hunk_at(10, 12, vec![line(Deletion, "x"), line(Context, "y")]),
+            ],
+        )
+    }
+
+    #[test]
+    fn should_build_a_patch_of_one_hunk() {
+        let patch = selection_patch(&modified_file(), |hunk_idx, _| hunk_idx == 1).unwrap();
+        assert_eq!(
+            patch,
+            "diff --git a/src/lib.rs b/src/lib.rs\n\
+             --- a/src/lib.rs\n\
+             +++ b/src/lib.rs\n\
+             @@ -10,2 +10,1 @@\n\
+             -x\n \
+             y\n"
+        );
+    }
+
+    #[test]
+    fn should_keep_only_picked_lines_and_shift_later_hunks() {
+        // given
+        let file = modified_file();
+
+        // when
+        let patch = selection_patch(&file, |hunk_idx, line_idx| {
+            (hunk_idx, line_idx) == (0, 2) || hunk_idx == 1
+        })
+        .unwrap();
+
+        // then
+        assert!(patch.contains("@@ -1,2 +1,3 @@\n a\n+c\n d\n@@ -10,2 +11,1 @@\n"));
+        assert!(selection_patch(&file, |_, _| false).is_none());
+    }
+}
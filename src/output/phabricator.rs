@@ -0,0 +1,113 @@
+use serde::Serialize;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{LineSide, ReviewSession};
+
+use super::markdown::{ExportOptions, anchor_note, collect_comments, comment_body};
+
+/// One inline comment, with the parameters of Conduit's
+/// `differential.createinline`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhabricatorInline {
+    file_path: String,
+    /// false when the comment is on the old (left) side of the diff
+    is_new_file: bool,
+    line_number: u32,
+    /// Number of lines after `line_number` that the comment also covers
+    line_length: u32,
+    content: String,
+}
+
+/// A review in the shape Differential expects: a top-level comment (session
+/// notes and file comments) plus inline comments
+#[derive(Debug, Serialize)]
+struct PhabricatorReview {
+    comment: String,
+    inlines: Vec<PhabricatorInline>,
+}
+
+/// Export the review as Differential inline-comment JSON, e.g. to feed
+/// `differential.createinline` via `arc call-conduit`
+pub fn generate_phabricator_json(
+    session: &ReviewSession,
+    options: &ExportOptions,
+) -> Result<String> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let mut general = Vec::new();
+    if let Some(notes) = &session.session_notes {
+        general.push(notes.clone());
+    }
+    let mut inlines = Vec::new();
+    for entry in collect_comments(session) {
+        let content = comment_body(entry.comment, options);
+        match entry.line_range {
+            Some(range) => inlines.push(PhabricatorInline {
+                file_path: entry.file,
+                is_new_file: entry.comment.side != Some(LineSide::Old),
+                line_number: range.start,
+                line_length: range.end - range.start,
+                content,
+            }),
+            None => general.push(format!("`{}`: {content}", entry.file)),
+        }
+    }
+
+    general.extend(anchor_note(session));
+    let review = PhabricatorReview {
+        comment: general.join("\n\n"),
+        inlines,
+    };
+    Ok(serde_json::to_string_pretty(&review)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, CommentType, FileStatus, LineRange, SessionDiffSource};
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_export_inline_and_file_comments() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_file_comment(Comment::new(
+            "Needs tests".to_string(),
+            CommentType::Issue,
+            None,
+        ));
+        review.add_line_comment(
+            12,
+            Comment::new_with_range(
+                "Dead code".to_string(),
+                CommentType::Note,
+                Some(LineSide::Old),
+                LineRange::new(10, 12),
+            ),
+        );
+
+        // when
+        let json = generate_phabricator_json(&session, &ExportOptions::default()).unwrap();
+
+        // then
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["comment"], "`src/lib.rs`: **ISSUE** Needs tests");
+        let inline = &value["inlines"][0];
+        assert_eq!(inline["filePath"], "src/lib.rs");
+        assert_eq!(inline["isNewFile"], false);
+        assert_eq!(inline["lineNumber"], 10);
+        assert_eq!(inline["lineLength"], 2);
+        assert_eq!(inline["content"], "**NOTE** Dead code");
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{CommentType, LineRange, LineSide, ReviewSession};
+
+use super::markdown::{ExportOptions, collect_comments, comment_body};
+
+#[derive(Debug, Serialize)]
+struct Source {
+    name: &'static str,
+    url: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Position {
+    line: u32,
+}
+
+/// Whole lines `start..=end`; without columns reviewdog covers them entirely
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+impl From<LineRange> for Range {
+    fn from(range: LineRange) -> Self {
+        Self {
+            start: Position { line: range.start },
+            end: Position { line: range.end },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Location {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<Range>,
+}
+
+#[derive(Debug, Serialize)]
+struct Code {
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Suggestion {
+    range: Range,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    message: String,
+    location: Location,
+    severity: &'static str,
+    code: Code,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<Suggestion>,
+}
+
+/// A result in the Reviewdog Diagnostic Format, read with `reviewdog -f=rdjson`
+#[derive(Debug, Serialize)]
+struct DiagnosticResult {
+    source: Source,
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn severity(comment_type: CommentType) -> &'static str {
+    match comment_type {
+        CommentType::Issue => "ERROR",
+        CommentType::Suggestion => "WARNING",
+        CommentType::Note | CommentType::Praise => "INFO",
+    }
+}
+
+/// Export the unresolved comments as Reviewdog Diagnostic Format JSON, to
+/// pipe into `reviewdog -f=rdjson` for PR annotations in CI.
+///
+/// Comments on the old side of the diff have no range, since reviewdog
+/// places diagnostics on lines of the new version; suggested changes become
+/// reviewdog suggestions.
+pub fn generate_rdjson_export(session: &ReviewSession, options: &ExportOptions) -> Result<String> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let diagnostics = collect_comments(session)
+        .into_iter()
+        .filter(|entry| !entry.comment.resolved)
+        .map(|entry| {
+            let comment = entry.comment;
+            let range = entry
+                .line_range
+                .filter(|_| comment.side != Some(LineSide::Old))
+                .map(Range::from);
+            let suggestions = match (range, comment.suggestion()) {
+                (Some(range), Some(text)) => vec![Suggestion { range, text }],
+                _ => Vec::new(),
+            };
+            Diagnostic {
+                message: comment_body(comment, options),
+                location: Location {
+                    path: entry.file,
+                    range,
+                },
+                severity: severity(comment.comment_type),
+                code: Code {
+                    value: comment.comment_type.as_str().to_lowercase(),
+                },
+                suggestions,
+            }
+        })
+        .collect();
+
+    let result = DiagnosticResult {
+        source: Source {
+            name: "tuicr",
+            url: env!("CARGO_PKG_REPOSITORY"),
+        },
+        diagnostics,
+    };
+    Ok(serde_json::to_string_pretty(&result)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, FileStatus, SessionDiffSource};
+    use serde_json::{Value, json};
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_export_unresolved_comments_as_diagnostics() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_line_comment(
+            4,
+            Comment::new_with_range(
+                "Use a constant\n\n```suggestion\nlet x = MAX;\n```".to_string(),
+                CommentType::Suggestion,
+                Some(LineSide::New),
+                LineRange::new(3, 4),
+            ),
+        );
+        let mut resolved = Comment::new("Fixed".to_string(), CommentType::Issue, None);
+        resolved.resolved = true;
+        review.add_file_comment(resolved);
+
+        // when
+        let rdjson = generate_rdjson_export(&session, &ExportOptions::default()).unwrap();
+
+        // then
+        let value: Value = serde_json::from_str(&rdjson).unwrap();
+        assert_eq!(value["source"]["name"], "tuicr");
+        let diagnostics = value["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic["severity"], "WARNING");
+        assert_eq!(diagnostic["code"]["value"], "suggestion");
+        let range = json!({ "start": { "line": 3 }, "end": { "line": 4 } });
+        assert_eq!(
+            diagnostic["location"],
+            json!({ "path": "src/lib.rs", "range": range })
+        );
+        assert_eq!(
+            diagnostic["suggestions"][0],
+            json!({ "range": range, "text": "let x = MAX;" })
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use serde::Serialize;
+
+use crate::error::{Result, TuicrError};
+use crate::model::{CommentType, LineRange, LineSide, ReviewAnchor, ReviewSession};
+
+use super::markdown::{ExportOptions, collect_comments, comment_body, expand_issue_refs};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Comment types in the order their rules are listed
+const RULE_TYPES: [CommentType; 4] = [
+    CommentType::Issue,
+    CommentType::Suggestion,
+    CommentType::Note,
+    CommentType::Praise,
+];
+
+#[derive(Debug, Serialize)]
+struct Message {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    markdown: Option<String>,
+}
+
+impl Message {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            markdown: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+    default_configuration: RuleConfiguration,
+}
+
+#[derive(Debug, Serialize)]
+struct RuleConfiguration {
+    level: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: u32,
+    end_line: u32,
+}
+
+impl From<LineRange> for Region {
+    fn from(range: LineRange) -> Self {
+        Self {
+            start_line: range.start,
+            end_line: range.end,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    deleted_region: Region,
+    inserted_content: InsertedContent,
+}
+
+#[derive(Debug, Serialize)]
+struct InsertedContent {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+    artifact_location: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+    description: Message,
+    artifact_changes: Vec<ArtifactChange>,
+}
+
+/// Marks a resolved comment, which annotation tools then hide
+#[derive(Debug, Serialize)]
+struct Suppression {
+    kind: &'static str,
+    status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<Fix>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressions: Vec<Suppression>,
+}
+
+#[derive(Debug, Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<RunProperties>,
+}
+
+/// Property bag of the run: the revisions the result regions refer to
+#[derive(Debug, Serialize)]
+struct RunProperties {
+    revisions: ReviewAnchor,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+fn rule_id(comment_type: CommentType) -> &'static str {
+    match comment_type {
+        CommentType::Issue => "issue",
+        CommentType::Suggestion => "suggestion",
+        CommentType::Note => "note",
+        CommentType::Praise => "praise",
+    }
+}
+
+/// SARIF level of a comment type: issues fail the check, suggestions warn
+fn level(comment_type: CommentType) -> &'static str {
+    match comment_type {
+        CommentType::Issue => "error",
+        CommentType::Suggestion => "warning",
+        CommentType::Note | CommentType::Praise => "note",
+    }
+}
+
+/// Export the review as a SARIF 2.1.0 log for CI annotations and code
+/// scanning dashboards: each comment is a result whose rule is its type.
+///
+/// Only comments on the new side of the diff get a line region, since the
+/// lines of the old side do not exist in the scanned tree.
+pub fn generate_sarif_export(session: &ReviewSession, options: &ExportOptions) -> Result<String> {
+    if !session.has_comments() {
+        return Err(TuicrError::NoComments);
+    }
+
+    let results = collect_comments(session)
+        .into_iter()
+        .map(|entry| {
+            let comment = entry.comment;
+            let region = entry
+                .line_range
+                .filter(|_| comment.side != Some(LineSide::Old))
+                .map(Region::from);
+            let fixes = match (region, comment.suggestion()) {
+                (Some(region), Some(replacement)) => vec![Fix {
+                    description: Message::text("Apply the suggested change"),
+                    artifact_changes: vec![ArtifactChange {
+                        artifact_location: ArtifactLocation {
+                            uri: entry.file.clone(),
+                        },
+                        replacements: vec![Replacement {
+                            deleted_region: region,
+                            inserted_content: InsertedContent {
+                                text: format!("{replacement}\n"),
+                            },
+                        }],
+                    }],
+                }],
+                _ => Vec::new(),
+            };
+            let suppressions = if comment.resolved {
+                vec![Suppression {
+                    kind: "external",
+                    status: "accepted",
+                }]
+            } else {
+                Vec::new()
+            };
+            SarifResult {
+                rule_id: rule_id(comment.comment_type),
+                level: level(comment.comment_type),
+                message: Message {
+                    text: expand_issue_refs(options, &comment.content_without_suggestion()),
+                    markdown: Some(comment_body(comment, options)),
+                },
+                locations: vec![Location {
+                    physical_location: PhysicalLocation {
+                        artifact_location: ArtifactLocation { uri: entry.file },
+                        region,
+                    },
+                }],
+                fixes,
+                suppressions,
+            }
+        })
+        .collect();
+
+    let rules = RULE_TYPES
+        .iter()
+        .map(|&comment_type| Rule {
+            id: rule_id(comment_type),
+            short_description: Message::text(format!(
+                "Review comment of type {}",
+                comment_type.as_str().to_lowercase()
+            )),
+            default_configuration: RuleConfiguration {
+                level: level(comment_type),
+            },
+        })
+        .collect();
+
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "tuicr",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: env!("CARGO_PKG_REPOSITORY"),
+                    rules,
+                },
+            },
+            results,
+            properties: session
+                .anchor
+                .clone()
+                .map(|revisions| RunProperties { revisions }),
+        }],
+    };
+    Ok(serde_json::to_string_pretty(&log)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, FileStatus, SessionDiffSource};
+    use serde_json::{Value, json};
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_export_comments_as_sarif_results() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        let review = session.get_file_mut(&path).unwrap();
+        review.add_line_comment(
+            7,
+            Comment::new_with_range(
+                "Off by one".to_string(),
+                CommentType::Issue,
+                Some(LineSide::New),
+                LineRange::new(5, 7),
+            ),
+        );
+        let mut old_side = Comment::new(
+            "Was fine".to_string(),
+            CommentType::Note,
+            Some(LineSide::Old),
+        );
+        old_side.resolved = true;
+        review.add_line_comment(3, old_side);
+
+        // when
+        let sarif = generate_sarif_export(&session, &ExportOptions::default()).unwrap();
+
+        // then
+        let value: Value = serde_json::from_str(&sarif).unwrap();
+        assert_eq!(value["version"], "2.1.0");
+        let run = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "issue");
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        let old = &results[0];
+        assert_eq!(old["ruleId"], "note");
+        assert!(old["locations"][0]["physicalLocation"]["region"].is_null());
+        assert_eq!(old["suppressions"][0]["kind"], "external");
+        let issue = &results[1];
+        assert_eq!(issue["level"], "error");
+        assert_eq!(issue["message"]["text"], "Off by one");
+        assert_eq!(
+            issue["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "src/lib.rs" },
+                "region": { "startLine": 5, "endLine": 7 }
+            })
+        );
+    }
+}
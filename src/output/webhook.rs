@@ -0,0 +1,247 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::model::{Comment, LineSide, ReviewSession};
+
+use super::http::post_json;
+
+/// How long the sender waits for more events after the first of a batch
+const BATCH_WINDOW: Duration = Duration::from_secs(2);
+/// Events posted in one request at most
+const BATCH_MAX: usize = 50;
+
+/// Where comment events are posted: `tuicr.webhook.url` and
+/// `tuicr.webhook.authorization` from git config, or the
+/// `TUICR_WEBHOOK_URL` and `TUICR_WEBHOOK_AUTHORIZATION` environment
+/// variables, which take precedence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Value of the `Authorization` header, e.g. `Bearer <token>`
+    pub authorization: Option<String>,
+}
+
+impl WebhookConfig {
+    /// The webhook configured for the repository at `repo_path`, if any
+    pub fn load(repo_path: &Path) -> Option<Self> {
+        let config = git2::Repository::discover(repo_path)
+            .and_then(|repo| repo.config())
+            .or_else(|_| git2::Config::open_default())
+            .ok();
+        let lookup = |env: &str, key: &str| {
+            std::env::var(env)
+                .ok()
+                .or_else(|| config.as_ref()?.get_string(key).ok())
+                .filter(|value| !value.is_empty())
+        };
+        Some(Self {
+            url: lookup("TUICR_WEBHOOK_URL", "tuicr.webhook.url")?,
+            authorization: lookup("TUICR_WEBHOOK_AUTHORIZATION", "tuicr.webhook.authorization"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEventKind {
+    Created,
+    /// Edited, replied to, resolved or reopened
+    Updated,
+}
+
+/// A comment as posted to the webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    event: WebhookEventKind,
+    id: String,
+    file: String,
+    /// Absent for file comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    side: Option<LineSide>,
+    #[serde(rename = "type")]
+    comment_type: String,
+    content: String,
+    resolved: bool,
+    replies: usize,
+    at: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    /// The event for comment `id` of file `path` as it is in the session
+    pub fn find(
+        session: &ReviewSession,
+        path: &PathBuf,
+        id: &str,
+        event: WebhookEventKind,
+    ) -> Option<Self> {
+        let review = session.files.get(path)?;
+        let (line, comment) = review
+            .file_comments
+            .iter()
+            .map(|comment| (None, comment))
+            .chain(review.line_comments.iter().flat_map(|(line, comments)| {
+                comments.iter().map(move |comment| (Some(*line), comment))
+            }))
+            .find(|(_, comment)| comment.id == id)?;
+        Some(Self::new(event, path, line, comment))
+    }
+
+    fn new(event: WebhookEventKind, path: &Path, line: Option<u32>, comment: &Comment) -> Self {
+        Self {
+            event,
+            id: comment.id.clone(),
+            file: path.display().to_string(),
+            start_line: line.map(|line| comment.line_range.map_or(line, |range| range.start)),
+            end_line: line,
+            side: line.map(|_| comment.side.unwrap_or_default()),
+            comment_type: comment.comment_type.as_str().to_lowercase(),
+            content: comment.content.clone(),
+            resolved: comment.resolved,
+            replies: comment.replies.len(),
+            at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookBatch<'a> {
+    repository: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<&'a str>,
+    events: &'a [WebhookEvent],
+}
+
+/// Posts comment events to a webhook from a background thread, batching the
+/// events of a few seconds into one request so editing a comment in quick
+/// succession does not flood the receiver.
+///
+/// Events still waiting for their batch when tuicr exits are not sent.
+pub struct WebhookSender {
+    events: Sender<WebhookEvent>,
+    failures: Receiver<String>,
+}
+
+impl WebhookSender {
+    pub fn spawn(config: WebhookConfig, repository: String, branch: Option<String>) -> Self {
+        let (events, received) = mpsc::channel();
+        let (failed, failures) = mpsc::channel();
+        thread::spawn(move || {
+            while let Some(batch) = next_batch(&received, BATCH_WINDOW, BATCH_MAX) {
+                let body = WebhookBatch {
+                    repository: &repository,
+                    branch: branch.as_deref(),
+                    events: &batch,
+                };
+                let Ok(body) = serde_json::to_string(&body) else {
+                    continue;
+                };
+                if let Err(e) = post_json(&config.url, config.authorization.as_deref(), &body)
+                    && failed.send(e.to_string()).is_err()
+                {
+                    break;
+                }
+            }
+        });
+        Self { events, failures }
+    }
+
+    pub fn send(&self, event: WebhookEvent) {
+        // The thread only stops once this sender is dropped
+        let _ = self.events.send(event);
+    }
+
+    /// The error of the latest failed post since the last call, if any
+    pub fn last_failure(&self) -> Option<String> {
+        self.failures.try_iter().last()
+    }
+}
+
+/// Wait for an event, then collect more until `window` has passed since it
+/// or `max` events are in the batch. `None` once the channel is closed and
+/// drained.
+fn next_batch<T>(events: &Receiver<T>, window: Duration, max: usize) -> Option<Vec<T>> {
+    let first = events.recv().ok()?;
+    let deadline = Instant::now() + window;
+    let mut batch = vec![first];
+    while batch.len() < max {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match events.recv_timeout(left) {
+            Ok(event) => batch.push(event),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Some(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CommentType, FileStatus, LineRange, SessionDiffSource};
+
+    #[test]
+    fn should_describe_comments_with_their_location() {
+        // given
+        let path = PathBuf::from("src/lib.rs");
+        let mut session = ReviewSession::new(
+            PathBuf::from("/tmp/repo"),
+            "abc123".to_string(),
+            None,
+            SessionDiffSource::WorkingTree,
+        );
+        session.add_file(path.clone(), FileStatus::Modified);
+        let comment = Comment::new_with_range(
+            "Off by one".to_string(),
+            CommentType::Issue,
+            Some(LineSide::New),
+            LineRange::new(4, 6),
+        );
+        let id = comment.id.clone();
+        session
+            .get_file_mut(&path)
+            .unwrap()
+            .add_line_comment(6, comment);
+
+        // when
+        let event = WebhookEvent::find(&session, &path, &id, WebhookEventKind::Created).unwrap();
+
+        // then
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], "created");
+        assert_eq!(value["file"], "src/lib.rs");
+        assert_eq!(value["start_line"], 4);
+        assert_eq!(value["end_line"], 6);
+        assert_eq!(value["type"], "issue");
+        assert!(
+            WebhookEvent::find(&session, &path, "missing", WebhookEventKind::Updated).is_none()
+        );
+    }
+
+    #[test]
+    fn should_batch_events_up_to_the_limit() {
+        // given
+        let (sender, events) = mpsc::channel();
+        for n in 0..5 {
+            sender.send(n).unwrap();
+        }
+        drop(sender);
+
+        // when
+        let first = next_batch(&events, Duration::from_secs(1), 3);
+        let second = next_batch(&events, Duration::from_secs(1), 3);
+        let done = next_batch(&events, Duration::from_secs(1), 3);
+
+        // then
+        assert_eq!(first, Some(vec![0, 1, 2]));
+        assert_eq!(second, Some(vec![3, 4]));
+        assert_eq!(done, None);
+    }
+}
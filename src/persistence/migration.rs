@@ -0,0 +1,114 @@
+//! Upgrading sessions saved by older versions of tuicr to the current
+//! format. Migrations work on the raw JSON, before it is read into a
+//! `ReviewSession`, so fields that were renamed or reshaped are carried
+//! over instead of falling back to their defaults.
+
+use serde_json::{Map, Value};
+
+use crate::error::{Result, TuicrError};
+use crate::model::review::SESSION_VERSION;
+
+/// Format of sessions saved without a version
+const UNVERSIONED: &str = "1.0";
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// Each format with the migration that upgrades sessions of the formats
+/// before it, oldest first. A format that adds or changes fields gets an
+/// entry (and becomes `SESSION_VERSION`) even when its migration has
+/// nothing to convert: older versions refuse sessions of formats they do
+/// not know, instead of dropping the new fields when they save.
+const MIGRATIONS: &[(&str, Migration)] = &[
+    // Sessions used to be of the working tree only, with no branch
+    ("1.2", |session| {
+        session
+            .entry("diff_source")
+            .or_insert_with(|| Value::String("working_tree".to_string()));
+    }),
+    // Hunks can be reviewed one at a time; whole files keep their flag
+    ("1.3", |_| {}),
+    // Lines passed over are recorded for `:set dimviewed`
+    ("1.4", |_| {}),
+    // The revisions reviewed are recorded for exports
+    ("1.5", |_| {}),
+    // Line comments keep the lines around them to follow them when they move
+    ("1.6", |_| {}),
+];
+
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Bring `session`, as read from a session file, up to `SESSION_VERSION`.
+/// Fails on sessions saved by a newer version of tuicr.
+pub fn migrate(session: &mut Value) -> Result<()> {
+    let corrupted = |reason: String| TuicrError::CorruptedSession(reason);
+    let session = session
+        .as_object_mut()
+        .ok_or_else(|| corrupted("not a JSON object".to_string()))?;
+    let version = session
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or(UNVERSIONED)
+        .to_string();
+    let found =
+        parse_version(&version).ok_or_else(|| corrupted(format!("unknown format {version}")))?;
+    if parse_version(SESSION_VERSION).is_some_and(|current| found > current) {
+        return Err(TuicrError::SessionTooNew(version));
+    }
+
+    for (format, upgrade) in MIGRATIONS {
+        if parse_version(format).is_some_and(|format| found < format) {
+            upgrade(session);
+        }
+    }
+    session.insert(
+        "version".to_string(),
+        Value::String(SESSION_VERSION.to_string()),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_end_the_migrations_at_the_current_format() {
+        // then
+        assert_eq!(MIGRATIONS.last().unwrap().0, SESSION_VERSION);
+        assert!(
+            MIGRATIONS
+                .windows(2)
+                .all(|pair| parse_version(pair[0].0) < parse_version(pair[1].0))
+        );
+    }
+
+    #[test]
+    fn should_upgrade_unversioned_sessions() {
+        // given
+        let mut session = json!({ "id": "abc", "files": {} });
+
+        // when
+        migrate(&mut session).unwrap();
+
+        // then
+        assert_eq!(session["version"], SESSION_VERSION);
+        assert_eq!(session["diff_source"], "working_tree");
+    }
+
+    #[test]
+    fn should_refuse_sessions_of_a_newer_format() {
+        // given
+        let mut session = json!({ "version": "99.0", "files": {} });
+
+        // when
+        let result = migrate(&mut session);
+
+        // then
+        assert!(matches!(result, Err(TuicrError::SessionTooNew(v)) if v == "99.0"));
+        assert_eq!(session["version"], "99.0");
+    }
+}
@@ -1,3 +1,7 @@
+mod migration;
 pub mod storage;
 
-pub use storage::{load_latest_session_for_context, save_session};
+pub use storage::{
+    archive_session, autosave_session, discard_autosave, load_autosave,
+    load_latest_session_for_context, load_session, prune_sessions, save_session,
+};
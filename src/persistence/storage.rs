@@ -1,9 +1,15 @@
 use directories::ProjectDirs;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
+use super::migration::migrate;
 use crate::error::{Result, TuicrError};
+use crate::hash::fnv1a_64;
 use crate::model::ReviewSession;
 use crate::model::review::SessionDiffSource;
 
@@ -106,18 +112,6 @@ fn sanitize_filename_component(value: &str) -> String {
     }
 }
 
-fn fnv1a_64(bytes: &[u8]) -> u64 {
-    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
-    const PRIME: u64 = 0x100000001b3;
-
-    let mut hash = OFFSET_BASIS;
-    for byte in bytes {
-        hash ^= u64::from(*byte);
-        hash = hash.wrapping_mul(PRIME);
-    }
-    hash
-}
-
 fn repo_path_fingerprint(repo_path: &Path) -> String {
     let normalized = normalize_repo_path(repo_path);
     let hash = fnv1a_64(normalized.as_bytes());
@@ -174,11 +168,137 @@ pub fn save_session(session: &ReviewSession) -> Result<PathBuf> {
     Ok(path)
 }
 
+fn get_archive_dir() -> Result<PathBuf> {
+    Ok(get_reviews_dir()?.join("archive"))
+}
+
+fn get_autosave_dir() -> Result<PathBuf> {
+    Ok(get_reviews_dir()?.join("autosave"))
+}
+
+/// Journal file autosaving the review of a repository, branch and diff
+/// source, so a new session of the same context finds what a crashed one
+/// left behind
+fn autosave_path(session: &ReviewSession) -> Result<PathBuf> {
+    let filename = session_filename(session);
+    let context: Vec<&str> = filename.split('_').collect();
+    let context = context[..context.len() - 3].join("_");
+    Ok(get_autosave_dir()?.join(format!("{context}.json")))
+}
+
+/// Write `session` to its autosave journal, replacing the previous one in
+/// a single step so a crash mid-write keeps the older journal
+pub fn autosave_session(session: &ReviewSession) -> Result<PathBuf> {
+    let path = autosave_path(session)?;
+    fs::create_dir_all(get_autosave_dir()?)?;
+    let partial = path.with_extension("json.tmp");
+    fs::write(&partial, serde_json::to_string_pretty(session)?)?;
+    fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Delete the autosave journal of `session`'s context, once it is saved or
+/// its changes deliberately dropped
+pub fn discard_autosave(session: &ReviewSession) -> Result<()> {
+    match fs::remove_file(autosave_path(session)?) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The session autosaved for `session`'s context by a run that did not
+/// exit normally, if it reviews the same diff and is newer than the last
+/// explicit save of that session
+pub fn load_autosave(session: &ReviewSession) -> Result<Option<ReviewSession>> {
+    let path = autosave_path(session)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let journal = load_session(&path)?;
+    let same_diff = normalize_repo_path(&journal.repo_path)
+        == normalize_repo_path(&session.repo_path)
+        && journal.branch_name == session.branch_name
+        && journal.diff_source == session.diff_source
+        && journal.commit_range == session.commit_range;
+    if !same_diff {
+        return Ok(None);
+    }
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let saved = get_reviews_dir()?.join(session_filename(&journal));
+    if modified(&saved) >= modified(&path) {
+        return Ok(None);
+    }
+    Ok(Some(journal))
+}
+
+/// Compress a session file into the `archive` folder of the reviews
+/// directory, out of reach of `load_latest_session_for_context`.
+/// `load_session` still reads the archived copy.
+pub fn archive_session(path: &Path) -> Result<PathBuf> {
+    let archive_dir = get_archive_dir()?;
+    fs::create_dir_all(&archive_dir)?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| TuicrError::Io(std::io::Error::other("Not a session file")))?;
+    let mut archived_name = file_name.to_os_string();
+    archived_name.push(".gz");
+    let archived = archive_dir.join(archived_name);
+
+    let mut encoder = GzEncoder::new(fs::File::create(&archived)?, Compression::best());
+    encoder.write_all(&fs::read(path)?)?;
+    encoder.finish()?;
+    fs::remove_file(path)?;
+    Ok(archived)
+}
+
+/// Delete saved and archived sessions last written more than `older_than`
+/// ago. Returns how many were deleted.
+pub fn prune_sessions(older_than: Duration) -> Result<usize> {
+    let now = SystemTime::now();
+    let mut pruned = 0;
+    for dir in [get_reviews_dir()?, get_archive_dir()?, get_autosave_dir()?] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.ends_with(".json") && !name.ends_with(".json.gz") {
+                continue;
+            }
+            let expired = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > older_than);
+            if expired {
+                fs::remove_file(&path)?;
+                pruned += 1;
+            }
+        }
+    }
+    Ok(pruned)
+}
+
+fn read_session_file(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut contents = String::new();
+        GzDecoder::new(fs::File::open(path)?).read_to_string(&mut contents)?;
+        Ok(contents)
+    } else {
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
 pub fn load_session(path: &PathBuf) -> Result<ReviewSession> {
-    let contents = fs::read_to_string(path)?;
-    let session: ReviewSession =
-        serde_json::from_str(&contents).map_err(|e| TuicrError::CorruptedSession(e.to_string()))?;
-    Ok(session)
+    let contents = read_session_file(path)?;
+    let corrupted = |e: serde_json::Error| TuicrError::CorruptedSession(e.to_string());
+    let mut session = serde_json::from_str(&contents).map_err(corrupted)?;
+    migrate(&mut session)?;
+    serde_json::from_value(session).map_err(corrupted)
 }
 
 pub fn load_latest_session_for_context(
@@ -263,49 +383,153 @@ pub fn load_latest_session_for_context(
             .then_with(|| a.file_name().cmp(&b.file_name()))
     });
 
+    let context = SessionContext {
+        repo_path: current_repo_path,
+        branch_name,
+        head_commit,
+        diff_source,
+        commit_range,
+    };
     let mut legacy_candidate = None;
 
     for entry in session_files {
         let path = entry.path();
-        let Ok(session) = load_session(&path) else {
-            continue;
+        let session = match load_session(&path) {
+            Ok(session) => session,
+            // Starting over would hide the newer review from the user, but
+            // only when it is a review of what is being opened
+            Err(e @ TuicrError::SessionTooNew(_)) => {
+                let header = read_session_file(&path)
+                    .ok()
+                    .and_then(|contents| serde_json::from_str::<SessionHeader>(&contents).ok());
+                if header.is_some_and(|header| context.matches(&header) == ContextMatch::Exact) {
+                    return Err(e);
+                }
+                continue;
+            }
+            Err(_) => continue,
+        };
+        let header = SessionHeader {
+            repo_path: session.repo_path.clone(),
+            branch_name: session.branch_name.clone(),
+            base_commit: session.base_commit.clone(),
+            diff_source: session.diff_source,
+            commit_range: session.commit_range.clone(),
         };
 
-        if normalize_repo_path(&session.repo_path) != current_repo_path {
-            continue;
+        match context.matches(&header) {
+            ContextMatch::Exact => return Ok(Some((path, session))),
+            ContextMatch::Legacy if legacy_candidate.is_none() => {
+                legacy_candidate = Some((path, session));
+            }
+            ContextMatch::Legacy | ContextMatch::None => {}
         }
+    }
 
-        if session.diff_source != diff_source {
-            continue;
+    Ok(legacy_candidate)
+}
+
+/// The fields of a saved session that tell what it is a review of. They are
+/// read on their own from sessions too new to load.
+#[derive(serde::Deserialize)]
+struct SessionHeader {
+    repo_path: PathBuf,
+    #[serde(default)]
+    branch_name: Option<String>,
+    base_commit: String,
+    #[serde(default)]
+    diff_source: SessionDiffSource,
+    #[serde(default)]
+    commit_range: Option<Vec<String>>,
+}
+
+/// What [`load_latest_session_for_context`] is looking for
+struct SessionContext<'a> {
+    repo_path: String,
+    branch_name: Option<&'a str>,
+    head_commit: &'a str,
+    diff_source: SessionDiffSource,
+    commit_range: Option<&'a [String]>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ContextMatch {
+    Exact,
+    /// A session saved without a branch at the same commit, from before
+    /// sessions were keyed by branch
+    Legacy,
+    None,
+}
+
+impl SessionContext<'_> {
+    fn matches(&self, session: &SessionHeader) -> ContextMatch {
+        if normalize_repo_path(&session.repo_path) != self.repo_path
+            || session.diff_source != self.diff_source
+        {
+            return ContextMatch::None;
         }
 
-        if diff_source == SessionDiffSource::CommitRange
-            && let Some(expected_range) = commit_range
+        if self.diff_source == SessionDiffSource::CommitRange
+            && let Some(expected_range) = self.commit_range
             && session.commit_range.as_deref() != Some(expected_range)
         {
-            continue;
+            return ContextMatch::None;
         }
 
         let session_branch = session.branch_name.as_deref();
-        if session_branch == branch_name {
-            if branch_name.is_none() && session.base_commit != head_commit {
-                continue;
+        if session_branch == self.branch_name {
+            if self.branch_name.is_none() && session.base_commit != self.head_commit {
+                return ContextMatch::None;
             }
-
-            return Ok(Some((path, session)));
+            return ContextMatch::Exact;
         }
 
-        let eligible_legacy = branch_name.is_some()
-            && legacy_candidate.is_none()
-            && commit_range.is_none()
+        let legacy = self.branch_name.is_some()
+            && self.commit_range.is_none()
             && session_branch.is_none()
-            && session.base_commit == head_commit;
-        if eligible_legacy {
-            legacy_candidate = Some((path, session));
+            && session.base_commit == self.head_commit;
+        if legacy {
+            ContextMatch::Legacy
+        } else {
+            ContextMatch::None
         }
     }
+}
 
-    Ok(legacy_candidate)
+#[cfg(test)]
+static TEST_LOCK: std::sync::OnceLock<std::sync::Mutex<()>> = std::sync::OnceLock::new();
+
+/// Points the session store at a fresh directory until dropped. Tests that
+/// touch the store hold it, so they never see each other's sessions.
+#[cfg(test)]
+pub(crate) struct TestReviewsDirGuard<'a> {
+    _lock: std::sync::MutexGuard<'a, ()>,
+    pub(crate) path: PathBuf,
+}
+
+#[cfg(test)]
+impl Drop for TestReviewsDirGuard<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            std::env::remove_var("TUICR_REVIEWS_DIR");
+        }
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn with_test_reviews_dir() -> TestReviewsDirGuard<'static> {
+    let lock = TEST_LOCK
+        .get_or_init(|| std::sync::Mutex::new(()))
+        .lock()
+        .unwrap();
+    let path = std::env::temp_dir().join(format!("tuicr-reviews-test-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&path).unwrap();
+    unsafe {
+        std::env::set_var("TUICR_REVIEWS_DIR", path.as_os_str());
+    }
+
+    TestReviewsDirGuard { _lock: lock, path }
 }
 
 #[cfg(test)]
@@ -319,7 +543,6 @@ mod tests {
     use super::*;
     use crate::model::FileStatus;
     use std::path::PathBuf;
-    use std::sync::{Mutex, OnceLock};
     use std::time::Duration;
 
     const TEST_MTIME_RETRIES: usize = 40;
@@ -336,34 +559,6 @@ mod tests {
         session
     }
 
-    static TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
-
-    struct TestReviewsDirGuard<'a> {
-        _lock: std::sync::MutexGuard<'a, ()>,
-        path: PathBuf,
-    }
-
-    impl Drop for TestReviewsDirGuard<'_> {
-        fn drop(&mut self) {
-            unsafe {
-                std::env::remove_var("TUICR_REVIEWS_DIR");
-            }
-            let _ = fs::remove_dir_all(&self.path);
-        }
-    }
-
-    fn with_test_reviews_dir() -> TestReviewsDirGuard<'static> {
-        let lock = TEST_LOCK.get_or_init(|| Mutex::new(())).lock().unwrap();
-        let path =
-            std::env::temp_dir().join(format!("tuicr-reviews-test-{}", uuid::Uuid::new_v4()));
-        fs::create_dir_all(&path).unwrap();
-        unsafe {
-            std::env::set_var("TUICR_REVIEWS_DIR", path.as_os_str());
-        }
-
-        TestReviewsDirGuard { _lock: lock, path }
-    }
-
     fn create_session(
         repo_path: PathBuf,
         base_commit: &str,
@@ -455,6 +650,76 @@ mod tests {
         let _ = delete_session(&path);
     }
 
+    #[test]
+    fn should_not_load_archived_sessions() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let session = create_test_session();
+        let path = save_session(&session).unwrap();
+
+        // when
+        let archived = archive_session(&path).unwrap();
+
+        // then
+        assert!(!path.exists());
+        assert_eq!(load_session(&archived).unwrap().id, session.id);
+        let found = load_latest_session_for_context(
+            &session.repo_path,
+            Some("main"),
+            "abc1234def",
+            SessionDiffSource::WorkingTree,
+            None,
+        )
+        .unwrap();
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn should_recover_an_autosave_newer_than_the_saved_session() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let session = create_test_session();
+        let saved = save_session(&session).unwrap();
+        let journal = autosave_session(&session).unwrap();
+        ensure_newer_mtime(&journal, &saved);
+        let fresh = create_test_session();
+
+        // when
+        let recovered = load_autosave(&fresh).unwrap();
+
+        // then
+        assert_eq!(recovered.unwrap().id, session.id);
+        ensure_newer_mtime(&saved, &journal);
+        assert!(load_autosave(&fresh).unwrap().is_none());
+        discard_autosave(&fresh).unwrap();
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn should_prune_only_old_sessions() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let recent = save_session(&create_test_session()).unwrap();
+        let mut old_session = create_test_session();
+        old_session.branch_name = Some("old".to_string());
+        let old = archive_session(&save_session(&old_session).unwrap()).unwrap();
+        let month_ago = SystemTime::now() - Duration::from_secs(31 * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(month_ago)
+            .unwrap();
+
+        // when
+        let pruned = prune_sessions(Duration::from_secs(30 * 24 * 60 * 60)).unwrap();
+
+        // then
+        assert_eq!(pruned, 1);
+        assert!(!old.exists());
+        assert!(recent.exists());
+    }
+
     #[test]
     fn should_sanitize_branch_name_in_filename() {
         let session = create_session(
@@ -532,6 +797,51 @@ mod tests {
         assert!(loaded.is_some());
     }
 
+    #[test]
+    fn should_only_refuse_newer_sessions_of_the_requested_branch() {
+        // given
+        let _guard = with_test_reviews_dir();
+        let repo_path = std::env::temp_dir().join(format!("tuicr-repo-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&repo_path).unwrap();
+        let main = create_session(
+            repo_path.clone(),
+            "commit-1",
+            Some("main"),
+            SessionDiffSource::WorkingTree,
+            None,
+        );
+        let main_path = save_session(&main).unwrap();
+        let feature = create_session(
+            repo_path.clone(),
+            "commit-2",
+            Some("feature"),
+            SessionDiffSource::WorkingTree,
+            None,
+        );
+        let feature_path = save_session(&feature).unwrap();
+        let mut value = serde_json::to_value(&feature).unwrap();
+        value["version"] = serde_json::Value::String("99.0".to_string());
+        fs::write(&feature_path, serde_json::to_string(&value).unwrap()).unwrap();
+        ensure_newer_mtime(&feature_path, &main_path);
+        let load = |branch| {
+            load_latest_session_for_context(
+                &repo_path,
+                Some(branch),
+                "commit-3",
+                SessionDiffSource::WorkingTree,
+                None,
+            )
+        };
+
+        // when
+        let main_result = load("main");
+        let feature_result = load("feature");
+
+        // then
+        assert_eq!(main_result.unwrap().unwrap().0, main_path);
+        assert!(matches!(feature_result, Err(TuicrError::SessionTooNew(v)) if v == "99.0"));
+    }
+
     #[test]
     fn should_load_session_with_underscore_branch_name() {
         let _guard = with_test_reviews_dir();
@@ -1,9 +1,19 @@
+mod palette;
+
 use ratatui::style::{Color, Modifier, Style};
 use std::path::Path;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 
-use crate::model::diff_types::LineOrigin;
+use crate::model::diff_types::{DiffFile, DiffLine, LineOrigin, MINIFIED_LINE_LEN};
+use crate::model::{WordChange, WordSegment, diff_code_chunks};
+
+/// Unchanged characters kept on each side of a changed chunk in minified lines
+const CHUNK_CONTEXT: usize = 40;
+
+/// Hunks longer than this are left unhighlighted. A new file is a single
+/// hunk, and highlighting a huge generated one would stall loading the diff.
+pub const MAX_HIGHLIGHT_LINES: usize = 20_000;
 
 /// Helper to highlight lines of code from a diff
 pub struct SyntaxHighlighter {
@@ -13,6 +23,8 @@ pub struct SyntaxHighlighter {
     pub add_bg: Color,
     /// Background color for deleted lines
     pub del_bg: Color,
+    /// Whether code is highlighted at all; off for the monochrome theme
+    colored: bool,
 }
 
 impl Default for SyntaxHighlighter {
@@ -31,20 +43,31 @@ impl SyntaxHighlighter {
         let syntax_set = SyntaxSet::load_defaults_newlines();
         let theme_set = ThemeSet::load_defaults();
 
-        // Try the requested theme, fall back to defaults
-        let theme = theme_set
-            .themes
-            .get(syntect_theme)
-            .or_else(|| theme_set.themes.get("base16-eighties.dark"))
-            .or_else(|| theme_set.themes.get("base16-ocean.dark"))
-            .cloned()
-            .unwrap_or_default();
+        // Try the requested theme (bundled or built-in palette), fall back to defaults
+        let theme = palette::palette_theme(syntect_theme).unwrap_or_else(|| {
+            theme_set
+                .themes
+                .get(syntect_theme)
+                .or_else(|| theme_set.themes.get("base16-eighties.dark"))
+                .or_else(|| theme_set.themes.get("base16-ocean.dark"))
+                .cloned()
+                .unwrap_or_default()
+        });
 
         Self {
             syntax_set,
             theme,
             add_bg,
             del_bg,
+            colored: true,
+        }
+    }
+
+    /// A highlighter that leaves code in the style of its diff line
+    pub fn without_colors() -> Self {
+        Self {
+            colored: false,
+            ..Self::new("base16-eighties.dark", Color::Reset, Color::Reset)
         }
     }
 
@@ -54,11 +77,46 @@ impl SyntaxHighlighter {
         &self,
         file_path: &Path,
         lines: &[String],
+    ) -> Option<Vec<Vec<(Style, String)>>> {
+        self.highlight_lines_as(file_path, None, lines)
+    }
+
+    /// Like `highlight_file_lines`, but `language` (a syntax name or file
+    /// extension, see `language_name`) replaces detection from the path
+    pub fn highlight_lines_as(
+        &self,
+        file_path: &Path,
+        language: Option<&str>,
+        lines: &[String],
+    ) -> Option<Vec<Vec<(Style, String)>>> {
+        if lines.len() > MAX_HIGHLIGHT_LINES {
+            return None;
+        }
+        self.highlight_any_size(file_path, language, lines)
+    }
+
+    /// Like `highlight_lines_as`, without the `MAX_HIGHLIGHT_LINES` limit
+    fn highlight_any_size(
+        &self,
+        file_path: &Path,
+        language: Option<&str>,
+        lines: &[String],
     ) -> Option<Vec<Vec<(Style, String)>>> {
         use syntect::easy::HighlightLines;
 
+        if !self.colored {
+            return None;
+        }
+        // Highlighting minified content is slow and unreadable anyway
+        if lines.iter().any(|l| l.len() > MINIFIED_LINE_LEN) {
+            return None;
+        }
+
         // Get syntax definition
-        let syntax = self.get_syntax(file_path)?;
+        let syntax = match language {
+            Some(language) => self.syntax_set.find_syntax_by_token(language),
+            None => self.get_syntax(file_path, lines.first().map(String::as_str)),
+        }?;
 
         // Create highlighter
         let mut highlighter = HighlightLines::new(syntax, &self.theme);
@@ -108,8 +166,14 @@ impl SyntaxHighlighter {
         Some(result)
     }
 
-    /// Get syntax definition from file path
-    fn get_syntax(&self, file_path: &Path) -> Option<&syntect::parsing::SyntaxReference> {
+    /// Get syntax definition from file path, or from the first line (a
+    /// shebang like `#!/usr/bin/env python`) for new scripts without an
+    /// extension
+    fn get_syntax(
+        &self,
+        file_path: &Path,
+        first_line: Option<&str>,
+    ) -> Option<&syntect::parsing::SyntaxReference> {
         // Try by extension first
         if let Some(ext) = file_path.extension().and_then(|e| e.to_str())
             && let Some(syntax) = self.syntax_set.find_syntax_by_extension(ext)
@@ -124,7 +188,15 @@ impl SyntaxHighlighter {
             return Some(syntax);
         }
 
-        None
+        first_line.and_then(|line| self.syntax_set.find_syntax_by_first_line(line))
+    }
+
+    /// Name of the syntax picked by a language name or file extension given
+    /// by the user, e.g. "Rust" for `rust` or `rs`
+    pub fn language_name(&self, language: &str) -> Option<&str> {
+        self.syntax_set
+            .find_syntax_by_token(language)
+            .map(|syntax| syntax.name.as_str())
     }
 
     /// Apply diff background colors to highlighted spans based on line origin
@@ -144,4 +216,241 @@ impl SyntaxHighlighter {
             .map(|(style, text)| (style.bg(bg_color), text))
             .collect()
     }
+
+    /// Recompute the highlighted spans of an already parsed file, e.g. after
+    /// switching themes, optionally in a language other than detected.
+    ///
+    /// A file with more diff lines than `max_lines` is rendered plain; with
+    /// `None` it is highlighted whatever its size.
+    pub fn rehighlight_file(
+        &self,
+        file: &mut DiffFile,
+        language: Option<&str>,
+        max_lines: Option<usize>,
+    ) {
+        let path = file.display_path().clone();
+        let too_big = max_lines.is_some_and(|max| file.line_count() > max);
+        for hunk in &mut file.hunks {
+            let contents: Vec<String> = hunk.lines.iter().map(|l| l.content.clone()).collect();
+            let highlighted = match max_lines {
+                _ if too_big => None,
+                Some(_) => self.highlight_lines_as(&path, language, &contents),
+                None => self.highlight_any_size(&path, language, &contents),
+            };
+            for (idx, line) in hunk.lines.iter_mut().enumerate() {
+                line.highlighted_spans = highlighted
+                    .as_ref()
+                    .and_then(|all| all.get(idx))
+                    .map(|spans| self.apply_diff_background(spans.clone(), line.origin));
+            }
+            self.apply_minified_chunks(&mut hunk.lines);
+        }
+    }
+
+    /// Replace the spans of paired minified deletion/addition lines with
+    /// intra-line change chunks.
+    ///
+    /// Changed chunks are emphasized and long unchanged stretches between
+    /// them are elided, so a one-character change in a bundle stays readable.
+    pub fn apply_minified_chunks(&self, lines: &mut [DiffLine]) {
+        let mut idx = 0;
+        while idx < lines.len() {
+            if lines[idx].origin != LineOrigin::Deletion {
+                idx += 1;
+                continue;
+            }
+            let del_start = idx;
+            while idx < lines.len() && lines[idx].origin == LineOrigin::Deletion {
+                idx += 1;
+            }
+            let add_start = idx;
+            while idx < lines.len() && lines[idx].origin == LineOrigin::Addition {
+                idx += 1;
+            }
+
+            let pairs = (add_start - del_start).min(idx - add_start);
+            for offset in 0..pairs {
+                let (del_idx, add_idx) = (del_start + offset, add_start + offset);
+                if lines[del_idx].content.len() <= MINIFIED_LINE_LEN
+                    && lines[add_idx].content.len() <= MINIFIED_LINE_LEN
+                {
+                    continue;
+                }
+                let segments = diff_code_chunks(&lines[del_idx].content, &lines[add_idx].content);
+                lines[del_idx].highlighted_spans =
+                    Some(chunk_spans(&segments, WordChange::Removed, self.del_bg));
+                lines[add_idx].highlighted_spans =
+                    Some(chunk_spans(&segments, WordChange::Added, self.add_bg));
+            }
+        }
+    }
+}
+
+/// Build the spans for one side of a chunked line, eliding unchanged text
+/// further than `CHUNK_CONTEXT` characters from any change
+fn chunk_spans(segments: &[WordSegment], side: WordChange, bg: Color) -> Vec<(Style, String)> {
+    let base = Style::default().bg(bg);
+    let changed = base.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+    let elided = base.add_modifier(Modifier::DIM);
+
+    let visible: Vec<&WordSegment> = segments
+        .iter()
+        .filter(|s| s.change == WordChange::Equal || s.change == side)
+        .collect();
+
+    let mut spans = Vec::new();
+    for (idx, segment) in visible.iter().enumerate() {
+        if segment.change != WordChange::Equal {
+            spans.push((changed, segment.text.clone()));
+            continue;
+        }
+        let chars: Vec<char> = segment.text.chars().collect();
+        let keep_head = if idx > 0 { CHUNK_CONTEXT } else { 0 };
+        let keep_tail = if idx + 1 < visible.len() {
+            CHUNK_CONTEXT
+        } else {
+            0
+        };
+        if chars.len() <= keep_head + keep_tail + 1 {
+            spans.push((base, segment.text.clone()));
+            continue;
+        }
+        if keep_head > 0 {
+            spans.push((base, chars[..keep_head].iter().collect()));
+        }
+        let hidden = chars.len() - keep_head - keep_tail;
+        spans.push((elided, format!(" …{hidden} chars… ")));
+        if keep_tail > 0 {
+            spans.push((base, chars[chars.len() - keep_tail..].iter().collect()));
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::diff_types::test_diffs::{added_file, line};
+
+    fn diff_line(origin: LineOrigin, content: String) -> DiffLine {
+        line(origin, &content)
+    }
+
+    #[test]
+    fn should_elide_unchanged_text_in_minified_lines() {
+        // given
+        let prefix = "a;".repeat(MINIFIED_LINE_LEN);
+        let mut lines = vec![
+            diff_line(LineOrigin::Deletion, format!("{prefix}x=1;")),
+            diff_line(LineOrigin::Addition, format!("{prefix}x=2;")),
+        ];
+        let highlighter = SyntaxHighlighter::default();
+
+        // when
+        highlighter.apply_minified_chunks(&mut lines);
+
+        // then
+        let added: String = lines[1]
+            .highlighted_spans
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect();
+        assert!(added.starts_with(" …"));
+        assert!(added.ends_with("x=2;"));
+        assert!(added.len() < 100);
+    }
+
+    #[test]
+    fn should_leave_short_lines_untouched() {
+        // given
+        let mut lines = vec![
+            diff_line(LineOrigin::Deletion, "let a = 1;".to_string()),
+            diff_line(LineOrigin::Addition, "let a = 2;".to_string()),
+        ];
+        let highlighter = SyntaxHighlighter::default();
+
+        // when
+        highlighter.apply_minified_chunks(&mut lines);
+
+        // then
+        assert!(lines.iter().all(|l| l.highlighted_spans.is_none()));
+    }
+
+    #[test]
+    fn should_detect_scripts_without_extension_by_shebang() {
+        // given
+        let highlighter = SyntaxHighlighter::default();
+        let lines = vec!["#!/usr/bin/env python3".to_string(), "x = 1".to_string()];
+
+        // when
+        let syntax = highlighter.get_syntax(Path::new("bin/deploy"), Some(&lines[0]));
+
+        // then
+        assert_eq!(syntax.unwrap().name, "Python");
+        assert!(
+            highlighter
+                .highlight_file_lines(Path::new("bin/deploy"), &lines)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn should_highlight_in_a_forced_language() {
+        // given
+        let highlighter = SyntaxHighlighter::default();
+        let lines = vec!["fn main() {}".to_string()];
+
+        // when
+        let detected = highlighter.highlight_file_lines(Path::new("templates/main.j2"), &lines);
+        let forced =
+            highlighter.highlight_lines_as(Path::new("templates/main.j2"), Some("rust"), &lines);
+
+        // then
+        assert!(detected.is_none());
+        assert!(forced.is_some());
+        assert_eq!(highlighter.language_name("rs"), Some("Rust"));
+        assert_eq!(highlighter.language_name("RUST"), Some("Rust"));
+        assert_eq!(highlighter.language_name("klingon"), None);
+    }
+
+    #[test]
+    fn should_skip_highlighting_huge_files() {
+        let highlighter = SyntaxHighlighter::default();
+        let lines = vec!["let x = 1;".to_string(); MAX_HIGHLIGHT_LINES + 1];
+        assert!(
+            highlighter
+                .highlight_file_lines(Path::new("gen.rs"), &lines)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn should_render_files_over_the_line_limit_plain_unless_forced() {
+        // given
+        let highlighter = SyntaxHighlighter::default();
+        let mut file = added_file("gen.rs", 1, &["let x = 1;"; 3]);
+
+        // when
+        highlighter.rehighlight_file(&mut file, None, Some(2));
+        let limited = file.hunks[0].lines[0].highlighted_spans.clone();
+        highlighter.rehighlight_file(&mut file, None, None);
+        let forced = file.hunks[0].lines[0].highlighted_spans.clone();
+
+        // then
+        assert!(limited.is_none());
+        assert!(forced.is_some());
+    }
+
+    #[test]
+    fn should_not_highlight_without_colors() {
+        let highlighter = SyntaxHighlighter::without_colors();
+        let lines = vec!["let x = 1;".to_string()];
+        assert!(
+            highlighter
+                .highlight_file_lines(Path::new("lib.rs"), &lines)
+                .is_none()
+        );
+    }
 }
@@ -0,0 +1,160 @@
+use std::str::FromStr;
+
+use syntect::highlighting::{
+    Color, FontStyle, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings,
+};
+
+/// Colors for a syntect theme built in code, for color schemes that syntect
+/// does not bundle
+struct SyntaxPalette {
+    name: &'static str,
+    background: u32,
+    foreground: u32,
+    comment: u32,
+    keyword: u32,
+    string: u32,
+    number: u32,
+    function: u32,
+    type_name: u32,
+    constant: u32,
+    operator: u32,
+}
+
+const GRUVBOX: SyntaxPalette = SyntaxPalette {
+    name: "tuicr-gruvbox",
+    background: 0x282828,
+    foreground: 0xebdbb2,
+    comment: 0x928374,
+    keyword: 0xfb4934,
+    string: 0xb8bb26,
+    number: 0xd3869b,
+    function: 0xfabd2f,
+    type_name: 0x83a598,
+    constant: 0xfe8019,
+    operator: 0x8ec07c,
+};
+
+const CATPPUCCIN: SyntaxPalette = SyntaxPalette {
+    name: "tuicr-catppuccin",
+    background: 0x1e1e2e,
+    foreground: 0xcdd6f4,
+    comment: 0x6c7086,
+    keyword: 0xcba6f7,
+    string: 0xa6e3a1,
+    number: 0xfab387,
+    function: 0x89b4fa,
+    type_name: 0xf9e2af,
+    constant: 0xfab387,
+    operator: 0x89dceb,
+};
+
+const NORD: SyntaxPalette = SyntaxPalette {
+    name: "tuicr-nord",
+    background: 0x2e3440,
+    foreground: 0xd8dee9,
+    comment: 0x616e88,
+    keyword: 0x81a1c1,
+    string: 0xa3be8c,
+    number: 0xb48ead,
+    function: 0x88c0d0,
+    type_name: 0x8fbcbb,
+    constant: 0xd08770,
+    operator: 0x81a1c1,
+};
+
+const HIGH_CONTRAST: SyntaxPalette = SyntaxPalette {
+    name: "tuicr-high-contrast",
+    background: 0x000000,
+    foreground: 0xffffff,
+    comment: 0xc8c8c8,
+    keyword: 0xffff00,
+    string: 0x00ff00,
+    number: 0xff80ff,
+    function: 0x00ffff,
+    type_name: 0x80c8ff,
+    constant: 0xffa000,
+    operator: 0xffffff,
+};
+
+const PALETTES: &[SyntaxPalette] = &[GRUVBOX, CATPPUCCIN, NORD, HIGH_CONTRAST];
+
+fn rgb(hex: u32) -> Color {
+    Color {
+        r: (hex >> 16) as u8,
+        g: (hex >> 8) as u8,
+        b: hex as u8,
+        a: 0xff,
+    }
+}
+
+fn item(scope: &str, color: u32, font_style: Option<FontStyle>) -> ThemeItem {
+    ThemeItem {
+        scope: ScopeSelectors::from_str(scope).expect("valid scope selector"),
+        style: StyleModifier {
+            foreground: Some(rgb(color)),
+            background: None,
+            font_style,
+        },
+    }
+}
+
+/// Build the syntect theme for one of the built-in palettes, if `name` is one
+pub fn palette_theme(name: &str) -> Option<Theme> {
+    let palette = PALETTES.iter().find(|p| p.name == name)?;
+    Some(Theme {
+        name: Some(palette.name.to_string()),
+        author: None,
+        settings: ThemeSettings {
+            foreground: Some(rgb(palette.foreground)),
+            background: Some(rgb(palette.background)),
+            ..ThemeSettings::default()
+        },
+        scopes: vec![
+            item("comment", palette.comment, Some(FontStyle::ITALIC)),
+            item("keyword, storage", palette.keyword, None),
+            item("string", palette.string, None),
+            item("constant.numeric", palette.number, None),
+            item(
+                "constant.language, constant.character, support.constant",
+                palette.constant,
+                None,
+            ),
+            item(
+                "entity.name.function, support.function, meta.function-call",
+                palette.function,
+                None,
+            ),
+            item(
+                "entity.name.type, entity.name.class, support.type, storage.type",
+                palette.type_name,
+                None,
+            ),
+            item(
+                "keyword.operator, punctuation.accessor",
+                palette.operator,
+                None,
+            ),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_every_palette_theme() {
+        for palette in PALETTES {
+            // when
+            let theme = palette_theme(palette.name);
+
+            // then
+            assert!(theme.is_some_and(|t| !t.scopes.is_empty()));
+        }
+    }
+
+    #[test]
+    fn should_not_build_unknown_palette() {
+        assert!(palette_theme("base16-ocean.dark").is_none());
+    }
+}
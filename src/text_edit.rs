@@ -1,7 +1,15 @@
 //! UTF-8 aware text editing utilities.
 //!
 //! Provides cursor movement and text manipulation functions that correctly
-//! handle multi-byte UTF-8 characters (CJK, emoji, etc.).
+//! handle multi-byte UTF-8 characters (CJK, emoji, etc.), and the multi-line
+//! [`TextEditor`] built on them.
+
+use std::fs::File;
+use std::process::{Command, Stdio};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::error::{Result, TuicrError};
 
 /// Find the byte position of the previous character boundary.
 /// Returns 0 if already at the start.
@@ -79,6 +87,330 @@ pub fn delete_word_before(buffer: &mut String, cursor: usize) -> usize {
     pos
 }
 
+fn line_start(buffer: &str, cursor: usize) -> usize {
+    let cursor = cursor.min(buffer.len());
+    match buffer[..cursor].rfind('\n') {
+        Some(pos) => pos + 1,
+        None => 0,
+    }
+}
+
+fn line_end(buffer: &str, cursor: usize) -> usize {
+    let cursor = cursor.min(buffer.len());
+    match buffer[cursor..].find('\n') {
+        Some(pos) => cursor + pos,
+        None => buffer.len(),
+    }
+}
+
+fn word_left(buffer: &str, cursor: usize) -> usize {
+    let cursor = cursor.min(buffer.len());
+    if cursor == 0 {
+        return 0;
+    }
+    let before = &buffer[..cursor];
+    let mut idx = 0;
+    let mut found_word = false;
+    for (pos, ch) in before.char_indices().rev() {
+        if !ch.is_whitespace() {
+            idx = pos;
+            found_word = true;
+            break;
+        }
+    }
+
+    if !found_word {
+        return 0;
+    }
+
+    for (pos, ch) in before[..idx].char_indices().rev() {
+        if ch.is_whitespace() {
+            return pos + ch.len_utf8();
+        }
+        idx = pos;
+    }
+
+    idx
+}
+
+fn word_right(buffer: &str, cursor: usize) -> usize {
+    let cursor = cursor.min(buffer.len());
+    if cursor >= buffer.len() {
+        return buffer.len();
+    }
+
+    let mut chars = buffer[cursor..].char_indices();
+    if let Some((_, ch)) = chars.next()
+        && ch.is_whitespace()
+    {
+        for (pos, ch) in buffer[cursor..].char_indices() {
+            if !ch.is_whitespace() {
+                return cursor + pos;
+            }
+        }
+        return buffer.len();
+    }
+
+    let mut word_end = buffer.len();
+    for (pos, ch) in buffer[cursor..].char_indices() {
+        if ch.is_whitespace() {
+            word_end = cursor + pos;
+            break;
+        }
+    }
+
+    if word_end >= buffer.len() {
+        return buffer.len();
+    }
+
+    for (pos, ch) in buffer[word_end..].char_indices() {
+        if !ch.is_whitespace() {
+            return word_end + pos;
+        }
+    }
+
+    buffer.len()
+}
+
+/// Byte range of a row of a [`TextEditor`] as displayed: a line of the text,
+/// or the part of one that fits the wrap width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisualRow {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Multi-line text input: `\n` separated lines with a cursor at a byte
+/// position. Lines wider than the wrap width are soft wrapped, and moving up
+/// or down keeps the display column the cursor started from.
+#[derive(Debug, Clone, Default)]
+pub struct TextEditor {
+    text: String,
+    cursor: usize,
+    /// Display width rows wrap at, 0 for no wrapping
+    wrap_width: usize,
+    /// Display column kept across consecutive vertical moves
+    goal_column: Option<usize>,
+}
+
+impl TextEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the text, with the cursor at its end
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.set_cursor(self.text.len());
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+    }
+
+    /// Move the cursor to byte `pos`, clamped to the text and to the start
+    /// of the character it falls in
+    pub fn set_cursor(&mut self, pos: usize) {
+        let mut pos = pos.min(self.text.len());
+        while !self.text.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        self.cursor = pos;
+        self.goal_column = None;
+    }
+
+    /// Set the display width rows wrap at, 0 for no wrapping
+    pub fn set_wrap_width(&mut self, width: usize) {
+        self.wrap_width = width;
+    }
+
+    pub fn insert(&mut self, ch: char) {
+        self.text.insert(self.cursor, ch);
+        self.set_cursor(self.cursor + ch.len_utf8());
+    }
+
+    pub fn delete_char(&mut self) {
+        let cursor = delete_char_before(&mut self.text, self.cursor);
+        self.set_cursor(cursor);
+    }
+
+    pub fn delete_word(&mut self) {
+        let cursor = delete_word_before(&mut self.text, self.cursor);
+        self.set_cursor(cursor);
+    }
+
+    pub fn move_left(&mut self) {
+        self.set_cursor(prev_char_boundary(&self.text, self.cursor));
+    }
+
+    pub fn move_right(&mut self) {
+        self.set_cursor(next_char_boundary(&self.text, self.cursor));
+    }
+
+    pub fn move_line_start(&mut self) {
+        self.set_cursor(line_start(&self.text, self.cursor));
+    }
+
+    pub fn move_line_end(&mut self) {
+        self.set_cursor(line_end(&self.text, self.cursor));
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.set_cursor(word_left(&self.text, self.cursor));
+    }
+
+    pub fn move_word_right(&mut self) {
+        self.set_cursor(word_right(&self.text, self.cursor));
+    }
+
+    pub fn move_up(&mut self) {
+        self.move_vertically(true);
+    }
+
+    pub fn move_down(&mut self) {
+        self.move_vertically(false);
+    }
+
+    /// The rows of the text as displayed at the wrap width
+    pub fn rows(&self) -> Vec<VisualRow> {
+        visual_rows(&self.text, self.wrap_width)
+    }
+
+    /// Row index and display column of the cursor
+    pub fn cursor_position(&self) -> (usize, usize) {
+        let rows = self.rows();
+        let row = cursor_row(&rows, self.cursor);
+        (row, self.text[rows[row].start..self.cursor].width())
+    }
+
+    /// Move to the row above or below, as close to the goal column as its
+    /// text allows. Stays put on the first or last row.
+    fn move_vertically(&mut self, up: bool) {
+        let rows = self.rows();
+        let row = cursor_row(&rows, self.cursor);
+        let goal = self
+            .goal_column
+            .unwrap_or_else(|| self.text[rows[row].start..self.cursor].width());
+        let target = if up {
+            row.checked_sub(1)
+        } else {
+            Some(row + 1).filter(|&target| target < rows.len())
+        };
+        let Some(target) = target else {
+            return;
+        };
+
+        let VisualRow { start, end } = rows[target];
+        // The end of a wrapped row is the start of the next one, where the
+        // cursor would be shown on the next row
+        let wrapped = rows.get(target + 1).is_some_and(|next| next.start == end);
+        let last = if wrapped {
+            prev_char_boundary(&self.text, end)
+        } else {
+            end
+        };
+        let mut pos = start;
+        let mut width = 0;
+        for (idx, ch) in self.text[start..last].char_indices() {
+            width += ch.width().unwrap_or(0);
+            if width > goal {
+                break;
+            }
+            pos = start + idx + ch.len_utf8();
+        }
+        self.cursor = pos;
+        self.goal_column = Some(goal);
+    }
+}
+
+/// Let the user edit `text` in `$VISUAL` or `$EDITOR` (`vi` if neither is
+/// set) through a temporary Markdown file, and return the saved text without
+/// its final newline. The caller hands the terminal over first; the editor
+/// draws on the controlling terminal even when stdout is redirected.
+pub fn edit_externally(text: &str) -> Result<String> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| {
+            std::env::var(var)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        })
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let path = std::env::temp_dir().join(format!("tuicr-comment-{}.md", uuid::Uuid::new_v4()));
+    std::fs::write(&path, text)?;
+    let tty = File::options().write(true).open("/dev/tty");
+    let status = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .stdout(tty.map_or_else(|_| Stdio::inherit(), Stdio::from))
+        .status();
+    let edited = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&path).map_err(TuicrError::from),
+        Ok(status) => Err(TuicrError::Editor(format!(
+            "{program} exited with {status}"
+        ))),
+        Err(e) => Err(TuicrError::Editor(format!("could not run {program}: {e}"))),
+    };
+    let _ = std::fs::remove_file(&path);
+    Ok(edited?.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Index of the row the cursor at byte `cursor` is shown on: at a wrap the
+/// cursor belongs to the row that starts there
+fn cursor_row(rows: &[VisualRow], cursor: usize) -> usize {
+    rows.iter()
+        .rposition(|row| row.start <= cursor)
+        .unwrap_or(0)
+}
+
+/// Split `text` into its lines, wrapping those wider than `width` display
+/// columns after the last whitespace that fits, or within a word that does
+/// not fit at all. A `width` of 0 does not wrap.
+fn visual_rows(text: &str, width: usize) -> Vec<VisualRow> {
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+    for line in text.split('\n') {
+        let line_end = line_start + line.len();
+        let mut start = line_start;
+        if width > 0 {
+            let mut row_width = 0;
+            // Byte after the last whitespace of the current row
+            let mut break_at = None;
+            for (idx, ch) in line.char_indices() {
+                let pos = line_start + idx;
+                let ch_width = ch.width().unwrap_or(0);
+                while row_width + ch_width > width && pos > start {
+                    let end = break_at.take().unwrap_or(pos);
+                    rows.push(VisualRow { start, end });
+                    row_width = text[end..pos].width();
+                    start = end;
+                }
+                row_width += ch_width;
+                if ch.is_whitespace() {
+                    break_at = Some(pos + ch.len_utf8());
+                }
+            }
+        }
+        rows.push(VisualRow {
+            start,
+            end: line_end,
+        });
+        line_start = line_end + 1;
+    }
+    rows
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,4 +686,63 @@ mod tests {
         assert_eq!(s, "");
         assert_eq!(cursor, 0);
     }
+
+    #[test]
+    fn should_wrap_rows_at_whitespace() {
+        // given
+        let mut editor = TextEditor::default();
+        editor.set_wrap_width(10);
+
+        // when
+        editor.set_text("one two three\nsupercalifragilistic");
+
+        // then
+        let rows: Vec<&str> = editor
+            .rows()
+            .iter()
+            .map(|row| &editor.text()[row.start..row.end])
+            .collect();
+        assert_eq!(rows, vec!["one two ", "three", "supercalif", "ragilistic"]);
+        assert_eq!(editor.cursor_position(), (3, 10));
+    }
+
+    #[test]
+    fn should_keep_goal_column_when_moving_between_lines() {
+        // given
+        let mut editor = TextEditor::default();
+        editor.set_text("first line\nab\nthird line");
+        editor.set_cursor(7); // after "first l"
+
+        // when
+        editor.move_down();
+        let on_short_line = editor.cursor_position();
+        editor.move_down();
+        let on_third_line = editor.cursor_position();
+        editor.move_down();
+
+        // then
+        assert_eq!(on_short_line, (1, 2));
+        assert_eq!(on_third_line, (2, 7));
+        assert_eq!(editor.cursor_position(), (2, 7));
+    }
+
+    #[test]
+    fn should_move_between_wrapped_rows_of_a_line() {
+        // given
+        let mut editor = TextEditor::default();
+        editor.set_wrap_width(6);
+        editor.set_text("hello world");
+
+        // when
+        editor.move_up();
+        let up = editor.cursor_position();
+        editor.move_line_end();
+        editor.move_up();
+        editor.move_down();
+
+        // then
+        assert_eq!(up, (0, 5)); // before the space that ends the wrapped row
+        assert_eq!(editor.cursor_position(), (1, 5));
+        assert_eq!(editor.cursor(), editor.text().len());
+    }
 }
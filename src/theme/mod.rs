@@ -6,6 +6,7 @@ use std::sync::OnceLock;
 
 use ratatui::style::Color;
 
+use crate::output::ExportFormat;
 use crate::syntax::SyntaxHighlighter;
 
 /// Complete color theme for the application
@@ -13,6 +14,12 @@ pub struct Theme {
     /// Cached syntax highlighter (lazily initialized)
     highlighter: OnceLock<SyntaxHighlighter>,
 
+    /// Name used to select this theme (`--theme`, `:theme`)
+    pub name: &'static str,
+    /// Every color is the terminal default; styles tell things apart with
+    /// bold, underline and reverse video instead, and code is not highlighted
+    pub monochrome: bool,
+
     // Base colors
     pub bg_highlight: Color,
     pub fg_primary: Color,
@@ -56,6 +63,10 @@ pub struct Theme {
     pub border_unfocused: Color,
     pub status_bar_bg: Color,
     pub cursor_color: Color,
+    /// Background of the diff cursor's line (`:set cursorline`)
+    pub cursor_line_bg: Color,
+    /// Background of the diff cursor's code column (`:set cursorcolumn`)
+    pub cursor_column_bg: Color,
 
     // Mode indicator colors
     pub mode_fg: Color,
@@ -73,6 +84,8 @@ impl Theme {
     pub fn dark() -> Self {
         Self {
             highlighter: OnceLock::new(),
+            name: "dark",
+            monochrome: false,
 
             // Base colors
             bg_highlight: Color::Rgb(70, 70, 70),
@@ -117,6 +130,8 @@ impl Theme {
             border_unfocused: Color::Rgb(110, 110, 110),
             status_bar_bg: Color::Rgb(30, 30, 30),
             cursor_color: Color::Rgb(255, 210, 90),
+            cursor_line_bg: Color::Rgb(40, 40, 40),
+            cursor_column_bg: Color::Rgb(55, 55, 55),
 
             // Mode indicator colors
             mode_fg: Color::Black,
@@ -128,6 +143,8 @@ impl Theme {
     pub fn light() -> Self {
         Self {
             highlighter: OnceLock::new(),
+            name: "light",
+            monochrome: false,
 
             // Base colors - dark text on light background
             bg_highlight: Color::Rgb(200, 200, 220),
@@ -173,12 +190,411 @@ impl Theme {
             border_unfocused: Color::Rgb(100, 100, 100),
             status_bar_bg: Color::Rgb(210, 210, 220),
             cursor_color: Color::Rgb(140, 80, 0),
+            cursor_line_bg: Color::Rgb(238, 238, 238),
+            cursor_column_bg: Color::Rgb(222, 222, 222),
 
             // Mode indicator colors
             mode_fg: Color::White,
             mode_bg: Color::Rgb(0, 80, 160),
         }
     }
+
+    /// Create the Gruvbox (dark, medium contrast) theme
+    pub fn gruvbox() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "gruvbox",
+            monochrome: false,
+
+            // Base colors
+            bg_highlight: Color::Rgb(80, 73, 69),
+            fg_primary: Color::Rgb(235, 219, 178),
+            fg_secondary: Color::Rgb(213, 196, 161),
+            fg_dim: Color::Rgb(168, 153, 132),
+
+            // Diff colors
+            diff_add: Color::Rgb(184, 187, 38),
+            diff_add_bg: Color::Rgb(50, 54, 26),
+            diff_del: Color::Rgb(251, 73, 52),
+            diff_del_bg: Color::Rgb(60, 31, 30),
+            diff_context: Color::Rgb(213, 196, 161),
+            diff_hunk_header: Color::Rgb(131, 165, 152),
+            expanded_context_fg: Color::Rgb(146, 131, 116),
+
+            // Syntax highlighting diff backgrounds
+            syntax_add_bg: Color::Rgb(43, 46, 24),
+            syntax_del_bg: Color::Rgb(54, 28, 27),
+
+            // Syntect theme for syntax highlighting
+            syntect_theme: "tuicr-gruvbox",
+
+            // File status colors
+            file_added: Color::Rgb(184, 187, 38),
+            file_modified: Color::Rgb(250, 189, 47),
+            file_deleted: Color::Rgb(251, 73, 52),
+            file_renamed: Color::Rgb(211, 134, 155),
+
+            // Review status colors
+            reviewed: Color::Rgb(184, 187, 38),
+            pending: Color::Rgb(250, 189, 47),
+
+            // Comment type colors
+            comment_note: Color::Rgb(131, 165, 152),
+            comment_suggestion: Color::Rgb(142, 192, 124),
+            comment_issue: Color::Rgb(251, 73, 52),
+            comment_praise: Color::Rgb(184, 187, 38),
+
+            // UI element colors
+            border_focused: Color::Rgb(250, 189, 47),
+            border_unfocused: Color::Rgb(102, 92, 84),
+            status_bar_bg: Color::Rgb(60, 56, 54),
+            cursor_color: Color::Rgb(254, 128, 25),
+            cursor_line_bg: Color::Rgb(60, 56, 54),
+            cursor_column_bg: Color::Rgb(80, 73, 69),
+
+            // Mode indicator colors
+            mode_fg: Color::Rgb(40, 40, 40),
+            mode_bg: Color::Rgb(250, 189, 47),
+        }
+    }
+
+    /// Create the Catppuccin (Mocha flavor) theme
+    pub fn catppuccin() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "catppuccin",
+            monochrome: false,
+
+            // Base colors
+            bg_highlight: Color::Rgb(69, 71, 90),
+            fg_primary: Color::Rgb(205, 214, 244),
+            fg_secondary: Color::Rgb(186, 194, 222),
+            fg_dim: Color::Rgb(166, 173, 200),
+
+            // Diff colors
+            diff_add: Color::Rgb(166, 227, 161),
+            diff_add_bg: Color::Rgb(43, 59, 52),
+            diff_del: Color::Rgb(243, 139, 168),
+            diff_del_bg: Color::Rgb(69, 41, 58),
+            diff_context: Color::Rgb(186, 194, 222),
+            diff_hunk_header: Color::Rgb(137, 180, 250),
+            expanded_context_fg: Color::Rgb(127, 132, 156),
+
+            // Syntax highlighting diff backgrounds
+            syntax_add_bg: Color::Rgb(37, 51, 41),
+            syntax_del_bg: Color::Rgb(58, 36, 49),
+
+            // Syntect theme for syntax highlighting
+            syntect_theme: "tuicr-catppuccin",
+
+            // File status colors
+            file_added: Color::Rgb(166, 227, 161),
+            file_modified: Color::Rgb(249, 226, 175),
+            file_deleted: Color::Rgb(243, 139, 168),
+            file_renamed: Color::Rgb(245, 194, 231),
+
+            // Review status colors
+            reviewed: Color::Rgb(166, 227, 161),
+            pending: Color::Rgb(249, 226, 175),
+
+            // Comment type colors
+            comment_note: Color::Rgb(137, 180, 250),
+            comment_suggestion: Color::Rgb(148, 226, 213),
+            comment_issue: Color::Rgb(243, 139, 168),
+            comment_praise: Color::Rgb(166, 227, 161),
+
+            // UI element colors
+            border_focused: Color::Rgb(203, 166, 247),
+            border_unfocused: Color::Rgb(88, 91, 112),
+            status_bar_bg: Color::Rgb(24, 24, 37),
+            cursor_color: Color::Rgb(245, 224, 220),
+            cursor_line_bg: Color::Rgb(49, 50, 68),
+            cursor_column_bg: Color::Rgb(69, 71, 90),
+
+            // Mode indicator colors
+            mode_fg: Color::Rgb(30, 30, 46),
+            mode_bg: Color::Rgb(203, 166, 247),
+        }
+    }
+
+    /// Create the Solarized dark theme
+    pub fn solarized_dark() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "solarized-dark",
+            monochrome: false,
+
+            // Base colors
+            bg_highlight: Color::Rgb(7, 54, 66),
+            fg_primary: Color::Rgb(147, 161, 161),
+            fg_secondary: Color::Rgb(131, 148, 150),
+            fg_dim: Color::Rgb(101, 123, 131),
+
+            // Diff colors
+            diff_add: Color::Rgb(133, 153, 0),
+            diff_add_bg: Color::Rgb(15, 58, 42),
+            diff_del: Color::Rgb(220, 50, 47),
+            diff_del_bg: Color::Rgb(59, 33, 48),
+            diff_context: Color::Rgb(131, 148, 150),
+            diff_hunk_header: Color::Rgb(38, 139, 210),
+            expanded_context_fg: Color::Rgb(88, 110, 117),
+
+            // Syntax highlighting diff backgrounds
+            syntax_add_bg: Color::Rgb(10, 53, 48),
+            syntax_del_bg: Color::Rgb(47, 37, 52),
+
+            // Syntect theme for syntax highlighting
+            syntect_theme: "Solarized (dark)",
+
+            // File status colors
+            file_added: Color::Rgb(133, 153, 0),
+            file_modified: Color::Rgb(181, 137, 0),
+            file_deleted: Color::Rgb(220, 50, 47),
+            file_renamed: Color::Rgb(211, 54, 130),
+
+            // Review status colors
+            reviewed: Color::Rgb(133, 153, 0),
+            pending: Color::Rgb(181, 137, 0),
+
+            // Comment type colors
+            comment_note: Color::Rgb(38, 139, 210),
+            comment_suggestion: Color::Rgb(42, 161, 152),
+            comment_issue: Color::Rgb(220, 50, 47),
+            comment_praise: Color::Rgb(133, 153, 0),
+
+            // UI element colors
+            border_focused: Color::Rgb(38, 139, 210),
+            border_unfocused: Color::Rgb(88, 110, 117),
+            status_bar_bg: Color::Rgb(7, 54, 66),
+            cursor_color: Color::Rgb(203, 75, 22),
+            cursor_line_bg: Color::Rgb(7, 54, 66),
+            cursor_column_bg: Color::Rgb(20, 70, 84),
+
+            // Mode indicator colors
+            mode_fg: Color::Rgb(0, 43, 54),
+            mode_bg: Color::Rgb(38, 139, 210),
+        }
+    }
+
+    /// Create the Solarized light theme
+    pub fn solarized_light() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "solarized-light",
+            monochrome: false,
+
+            // Base colors
+            bg_highlight: Color::Rgb(238, 232, 213),
+            fg_primary: Color::Rgb(7, 54, 66),
+            fg_secondary: Color::Rgb(88, 110, 117),
+            fg_dim: Color::Rgb(101, 123, 131),
+
+            // Diff colors
+            diff_add: Color::Rgb(95, 111, 0),
+            diff_add_bg: Color::Rgb(230, 236, 198),
+            diff_del: Color::Rgb(176, 37, 34),
+            diff_del_bg: Color::Rgb(247, 220, 207),
+            diff_context: Color::Rgb(88, 110, 117),
+            diff_hunk_header: Color::Rgb(31, 111, 168),
+            expanded_context_fg: Color::Rgb(147, 161, 161),
+
+            // Syntax highlighting diff backgrounds
+            syntax_add_bg: Color::Rgb(237, 240, 208),
+            syntax_del_bg: Color::Rgb(250, 229, 220),
+
+            // Syntect theme for syntax highlighting
+            syntect_theme: "Solarized (light)",
+
+            // File status colors
+            file_added: Color::Rgb(95, 111, 0),
+            file_modified: Color::Rgb(148, 111, 0),
+            file_deleted: Color::Rgb(176, 37, 34),
+            file_renamed: Color::Rgb(176, 42, 106),
+
+            // Review status colors
+            reviewed: Color::Rgb(95, 111, 0),
+            pending: Color::Rgb(148, 111, 0),
+
+            // Comment type colors
+            comment_note: Color::Rgb(31, 111, 168),
+            comment_suggestion: Color::Rgb(29, 127, 119),
+            comment_issue: Color::Rgb(176, 37, 34),
+            comment_praise: Color::Rgb(95, 111, 0),
+
+            // UI element colors
+            border_focused: Color::Rgb(38, 139, 210),
+            border_unfocused: Color::Rgb(147, 161, 161),
+            status_bar_bg: Color::Rgb(238, 232, 213),
+            cursor_color: Color::Rgb(203, 75, 22),
+            cursor_line_bg: Color::Rgb(238, 232, 213),
+            cursor_column_bg: Color::Rgb(224, 218, 199),
+
+            // Mode indicator colors
+            mode_fg: Color::Rgb(253, 246, 227),
+            mode_bg: Color::Rgb(38, 139, 210),
+        }
+    }
+
+    /// Create the Nord theme
+    pub fn nord() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "nord",
+            monochrome: false,
+
+            // Base colors
+            bg_highlight: Color::Rgb(67, 76, 94),
+            fg_primary: Color::Rgb(236, 239, 244),
+            fg_secondary: Color::Rgb(229, 233, 240),
+            fg_dim: Color::Rgb(160, 168, 183),
+
+            // Diff colors
+            diff_add: Color::Rgb(163, 190, 140),
+            diff_add_bg: Color::Rgb(53, 66, 55),
+            diff_del: Color::Rgb(191, 97, 106),
+            diff_del_bg: Color::Rgb(74, 51, 57),
+            diff_context: Color::Rgb(216, 222, 233),
+            diff_hunk_header: Color::Rgb(136, 192, 208),
+            expanded_context_fg: Color::Rgb(123, 132, 150),
+
+            // Syntax highlighting diff backgrounds
+            syntax_add_bg: Color::Rgb(51, 62, 58),
+            syntax_del_bg: Color::Rgb(67, 50, 58),
+
+            // Syntect theme for syntax highlighting
+            syntect_theme: "tuicr-nord",
+
+            // File status colors
+            file_added: Color::Rgb(163, 190, 140),
+            file_modified: Color::Rgb(235, 203, 139),
+            file_deleted: Color::Rgb(191, 97, 106),
+            file_renamed: Color::Rgb(180, 142, 173),
+
+            // Review status colors
+            reviewed: Color::Rgb(163, 190, 140),
+            pending: Color::Rgb(235, 203, 139),
+
+            // Comment type colors
+            comment_note: Color::Rgb(129, 161, 193),
+            comment_suggestion: Color::Rgb(143, 188, 187),
+            comment_issue: Color::Rgb(191, 97, 106),
+            comment_praise: Color::Rgb(163, 190, 140),
+
+            // UI element colors
+            border_focused: Color::Rgb(136, 192, 208),
+            border_unfocused: Color::Rgb(76, 86, 106),
+            status_bar_bg: Color::Rgb(59, 66, 82),
+            cursor_color: Color::Rgb(235, 203, 139),
+            cursor_line_bg: Color::Rgb(59, 66, 82),
+            cursor_column_bg: Color::Rgb(67, 76, 94),
+
+            // Mode indicator colors
+            mode_fg: Color::Rgb(46, 52, 64),
+            mode_bg: Color::Rgb(136, 192, 208),
+        }
+    }
+}
+
+impl Theme {
+    /// Create the high-contrast theme: pure, saturated colors on black
+    pub fn high_contrast() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "high-contrast",
+            monochrome: false,
+
+            // Base colors
+            bg_highlight: Color::Rgb(0, 0, 160),
+            fg_primary: Color::Rgb(255, 255, 255),
+            fg_secondary: Color::Rgb(255, 255, 255),
+            fg_dim: Color::Rgb(200, 200, 200),
+
+            // Diff colors
+            diff_add: Color::Rgb(0, 255, 0),
+            diff_add_bg: Color::Rgb(0, 40, 0),
+            diff_del: Color::Rgb(255, 90, 90),
+            diff_del_bg: Color::Rgb(60, 0, 0),
+            diff_context: Color::Rgb(255, 255, 255),
+            diff_hunk_header: Color::Rgb(0, 255, 255),
+            expanded_context_fg: Color::Rgb(200, 200, 200),
+
+            // Syntax highlighting diff backgrounds
+            syntax_add_bg: Color::Rgb(0, 40, 0),
+            syntax_del_bg: Color::Rgb(60, 0, 0),
+
+            // Syntect theme for syntax highlighting
+            syntect_theme: "tuicr-high-contrast",
+
+            // File status colors
+            file_added: Color::Rgb(0, 255, 0),
+            file_modified: Color::Rgb(255, 255, 0),
+            file_deleted: Color::Rgb(255, 90, 90),
+            file_renamed: Color::Rgb(255, 128, 255),
+
+            // Review status colors
+            reviewed: Color::Rgb(0, 255, 0),
+            pending: Color::Rgb(255, 255, 0),
+
+            // Comment type colors
+            comment_note: Color::Rgb(0, 255, 255),
+            comment_suggestion: Color::Rgb(128, 200, 255),
+            comment_issue: Color::Rgb(255, 90, 90),
+            comment_praise: Color::Rgb(0, 255, 0),
+
+            // UI element colors
+            border_focused: Color::Rgb(255, 255, 0),
+            border_unfocused: Color::Rgb(200, 200, 200),
+            status_bar_bg: Color::Rgb(0, 0, 0),
+            cursor_color: Color::Rgb(255, 255, 0),
+            cursor_line_bg: Color::Rgb(48, 48, 48),
+            cursor_column_bg: Color::Rgb(48, 48, 48),
+
+            // Mode indicator colors
+            mode_fg: Color::Rgb(0, 0, 0),
+            mode_bg: Color::Rgb(255, 255, 0),
+        }
+    }
+
+    /// Create the monochrome theme for terminals without reliable colors
+    pub fn monochrome() -> Self {
+        Self {
+            highlighter: OnceLock::new(),
+            name: "monochrome",
+            monochrome: true,
+            bg_highlight: Color::Reset,
+            fg_primary: Color::Reset,
+            fg_secondary: Color::Reset,
+            fg_dim: Color::Reset,
+            diff_add: Color::Reset,
+            diff_add_bg: Color::Reset,
+            diff_del: Color::Reset,
+            diff_del_bg: Color::Reset,
+            diff_context: Color::Reset,
+            diff_hunk_header: Color::Reset,
+            expanded_context_fg: Color::Reset,
+            syntax_add_bg: Color::Reset,
+            syntax_del_bg: Color::Reset,
+            syntect_theme: "",
+            file_added: Color::Reset,
+            file_modified: Color::Reset,
+            file_deleted: Color::Reset,
+            file_renamed: Color::Reset,
+            reviewed: Color::Reset,
+            pending: Color::Reset,
+            comment_note: Color::Reset,
+            comment_suggestion: Color::Reset,
+            comment_issue: Color::Reset,
+            comment_praise: Color::Reset,
+            border_focused: Color::Reset,
+            border_unfocused: Color::Reset,
+            status_bar_bg: Color::Reset,
+            cursor_color: Color::Reset,
+            cursor_line_bg: Color::Reset,
+            cursor_column_bg: Color::Reset,
+            mode_fg: Color::Reset,
+            mode_bg: Color::Reset,
+        }
+    }
 }
 
 /// Theme selection from CLI argument
@@ -187,6 +603,13 @@ pub enum ThemeArg {
     #[default]
     Dark,
     Light,
+    Gruvbox,
+    Catppuccin,
+    SolarizedDark,
+    SolarizedLight,
+    Nord,
+    HighContrast,
+    Monochrome,
 }
 
 /// CLI arguments parsed from command line
@@ -195,16 +618,82 @@ pub struct CliArgs {
     pub theme: ThemeArg,
     /// Output to stdout instead of clipboard when exporting
     pub output_to_stdout: bool,
+    /// `tuicr keymap`: print the keymap as Markdown and exit
+    pub print_keymap: bool,
+    /// Browse the session without being able to edit or save it
+    pub read_only: bool,
+    /// Load this session file instead of the latest one for the repository
+    pub session_path: Option<std::path::PathBuf>,
+    /// `tuicr -`: review a unified diff read from stdin
+    pub stdin_diff: bool,
+    /// `--range main..feature`: review these commits instead of the working tree
+    pub range: Option<String>,
+    /// `tuicr export`: print the saved review in this format and exit
+    pub export_format: Option<ExportFormat>,
+    /// `--watch`: reload the diff whenever working tree files change
+    pub watch: bool,
+    /// `--screen-reader`: start in screen reader mode
+    pub screen_reader: bool,
+    /// `--reduce-motion`: redraw only after input or changes
+    pub reduce_motion: bool,
+    /// `--highlight-max <LINES>`: render bigger files without highlighting
+    pub highlight_max_lines: Option<usize>,
+    /// `tuicr.contextLines`: lines of code quoted around exported comments
+    pub export_context_lines: Option<usize>,
+    /// `tuicr.vcsTimeout`: give up hg and jj commands and forge requests
+    /// running longer than this
+    pub vcs_timeout: Option<std::time::Duration>,
+    /// `tuicr.quitTimeout`: window for the second press of the force quit key
+    pub quit_timeout_ms: Option<u64>,
+    /// `tuicr.autosave`: seconds between autosaves of unsaved changes
+    pub autosave_secs: Option<u64>,
+    /// `tuicr check`: fail unless the saved review is complete
+    pub check: bool,
+    /// `tuicr sessions prune`: delete sessions older than this and exit
+    pub prune_older_than: Option<std::time::Duration>,
 }
 
 impl ThemeArg {
-    pub fn from_str(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "dark" => Some(Self::Dark),
-            "light" => Some(Self::Light),
-            _ => None,
+    /// All built-in themes, in picker order
+    pub const ALL: &'static [ThemeArg] = &[
+        ThemeArg::Dark,
+        ThemeArg::Light,
+        ThemeArg::Gruvbox,
+        ThemeArg::Catppuccin,
+        ThemeArg::SolarizedDark,
+        ThemeArg::SolarizedLight,
+        ThemeArg::Nord,
+        ThemeArg::HighContrast,
+        ThemeArg::Monochrome,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::Gruvbox => "gruvbox",
+            Self::Catppuccin => "catppuccin",
+            Self::SolarizedDark => "solarized-dark",
+            Self::SolarizedLight => "solarized-light",
+            Self::Nord => "nord",
+            Self::HighContrast => "high-contrast",
+            Self::Monochrome => "monochrome",
         }
     }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        let s = s.to_lowercase();
+        Self::ALL.iter().copied().find(|arg| arg.name() == s)
+    }
+
+    /// Comma-separated theme names for help and error messages
+    pub fn names() -> String {
+        Self::ALL
+            .iter()
+            .map(|arg| arg.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
 /// Resolve a theme based on the CLI argument
@@ -212,6 +701,13 @@ pub fn resolve_theme(arg: ThemeArg) -> Theme {
     match arg {
         ThemeArg::Dark => Theme::dark(),
         ThemeArg::Light => Theme::light(),
+        ThemeArg::Gruvbox => Theme::gruvbox(),
+        ThemeArg::Catppuccin => Theme::catppuccin(),
+        ThemeArg::SolarizedDark => Theme::solarized_dark(),
+        ThemeArg::SolarizedLight => Theme::solarized_light(),
+        ThemeArg::Nord => Theme::nord(),
+        ThemeArg::HighContrast => Theme::high_contrast(),
+        ThemeArg::Monochrome => Theme::monochrome(),
     }
 }
 
@@ -219,11 +715,85 @@ impl Theme {
     /// Get the syntax highlighter for this theme (lazily initialized, cached)
     pub fn syntax_highlighter(&self) -> &SyntaxHighlighter {
         self.highlighter.get_or_init(|| {
-            SyntaxHighlighter::new(self.syntect_theme, self.syntax_add_bg, self.syntax_del_bg)
+            if self.monochrome {
+                SyntaxHighlighter::without_colors()
+            } else {
+                SyntaxHighlighter::new(self.syntect_theme, self.syntax_add_bg, self.syntax_del_bg)
+            }
         })
     }
 }
 
+/// Seed the CLI arguments with the `tuicr.*` settings of the repository's
+/// git config (`.git/config`, then the global and system gitconfig), so that
+/// flags given on the command line override them:
+///
+/// - `tuicr.theme`: theme name, like `--theme`
+/// - `tuicr.contextLines`: lines of code quoted around exported comments,
+///   like `:set context=N`
+/// - `tuicr.exportTarget`: `clipboard` (the default) or `stdout`, like
+///   `--clipboard` and `--stdout`
+/// - `tuicr.vcsTimeout`: seconds an hg or jj command or a forge request may
+///   run, 0 for no limit
+/// - `tuicr.quitTimeout`: milliseconds to press the force quit key (Ctrl+C)
+///   again to quit, 0 to quit on the first press, like `:set quittimeout=N`
+/// - `tuicr.autosave`: seconds between autosaves of unsaved changes, 0 to
+///   turn autosave off
+fn apply_git_config_defaults(cli_args: &mut CliArgs) {
+    let Ok(config) = git2::Repository::discover(".")
+        .and_then(|repo| repo.config())
+        .or_else(|_| git2::Config::open_default())
+    else {
+        return;
+    };
+    let get = |key: &str| config.get_string(key).ok().filter(|v| !v.is_empty());
+
+    if let Some(value) = get("tuicr.theme") {
+        match ThemeArg::from_str(&value) {
+            Some(theme) => cli_args.theme = theme,
+            None => eprintln!(
+                "Warning: Unknown theme '{value}' in git config tuicr.theme. Valid options: {}",
+                ThemeArg::names()
+            ),
+        }
+    }
+    if let Some(value) = get("tuicr.contextLines") {
+        match value.parse() {
+            Ok(lines) => cli_args.export_context_lines = Some(lines),
+            Err(_) => eprintln!("Warning: git config tuicr.contextLines must be a number of lines"),
+        }
+    }
+    if let Some(value) = get("tuicr.exportTarget") {
+        match value.as_str() {
+            "clipboard" => cli_args.output_to_stdout = false,
+            "stdout" => cli_args.output_to_stdout = true,
+            _ => eprintln!(
+                "Warning: Unknown export target '{value}' in git config tuicr.exportTarget. Valid options: clipboard, stdout"
+            ),
+        }
+    }
+    if let Some(value) = get("tuicr.vcsTimeout") {
+        match value.parse() {
+            Ok(secs) => cli_args.vcs_timeout = Some(std::time::Duration::from_secs(secs)),
+            Err(_) => eprintln!("Warning: git config tuicr.vcsTimeout must be a number of seconds"),
+        }
+    }
+    if let Some(value) = get("tuicr.quitTimeout") {
+        match value.parse() {
+            Ok(ms) => cli_args.quit_timeout_ms = Some(ms),
+            Err(_) => {
+                eprintln!("Warning: git config tuicr.quitTimeout must be a number of milliseconds")
+            }
+        }
+    }
+    if let Some(value) = get("tuicr.autosave") {
+        match value.parse() {
+            Ok(secs) => cli_args.autosave_secs = Some(secs),
+            Err(_) => eprintln!("Warning: git config tuicr.autosave must be a number of seconds"),
+        }
+    }
+}
+
 /// Print help message and exit
 fn print_help() -> ! {
     let name = std::env::args()
@@ -238,11 +808,40 @@ fn print_help() -> ! {
         "tuicr - Review AI-generated diffs like a GitHub pull request
 
 Usage: {name} [OPTIONS]
+       git diff | {name} [OPTIONS] -
+       {name} keymap [--format md]
+       {name} export [--format md|json|sarif|rdjson] [--range <RANGE>] [--session <FILE>]
+       {name} check [--range <RANGE>] [--session <FILE>]
+       {name} sessions prune --older-than <AGE>
+
+Commands:
+  keymap           Print every key binding per mode as Markdown
+  export           Print the saved review of this repository to stdout
+                   without starting the TUI (exits 1 if it has no comments)
+  check            Exit 1 if the saved review has unreviewed files or
+                   unresolved issues, e.g. in a pre-push hook or CI job
+  sessions prune   Delete saved and archived sessions older than <AGE>
+                   (e.g. 30d, 2w, 12h)
+  -                Review a unified diff read from stdin
 
 Options:
   --theme <THEME>  Color theme to use [default: dark]
-                   Valid values: dark, light
+                   Valid values: dark, light, gruvbox, catppuccin,
+                   solarized-dark, solarized-light, nord,
+                   high-contrast, monochrome
   --stdout         Output to stdout instead of clipboard when exporting
+  --clipboard      Copy exports to the clipboard, even with
+                   tuicr.exportTarget = stdout
+  --range <RANGE>  Review a commit range instead of the working tree
+                   (git: main..feature, jj: a revset, hg: a revset or a..b)
+  --session <FILE> Load a saved session file (e.g. a teammate's review)
+  --read-only      Browse the session with editing and saving disabled
+  --watch          Reload the diff whenever working tree files change
+  --screen-reader  Spell out diff and comment markers in a single column
+  --reduce-motion  Redraw only after input or changes (e.g. over slow SSH)
+  --highlight-max <LINES>
+                   Skip syntax highlighting of files with more diff lines
+                   (default 10000, 0 for no limit)
   -h, --help       Print this help message
 
 Press ? in the application for keybinding help."
@@ -250,6 +849,20 @@ Press ? in the application for keybinding help."
     std::process::exit(0);
 }
 
+/// Parse an age like `30d`, `2w`, `12h` or `45m`
+fn parse_age(value: &str) -> Option<std::time::Duration> {
+    let unit = value.chars().last()?;
+    let count: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(std::time::Duration::from_secs(count.checked_mul(seconds)?))
+}
+
 /// Parse CLI arguments from command line
 ///
 /// We use a handrolled argument parser instead of clap to keep binary size
@@ -258,6 +871,77 @@ Press ? in the application for keybinding help."
 pub fn parse_cli_args() -> CliArgs {
     let args: Vec<String> = std::env::args().collect();
     let mut cli_args = CliArgs::default();
+    apply_git_config_defaults(&mut cli_args);
+
+    let format = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--format=").map(str::to_string))
+        });
+
+    // Handle the keymap subcommand
+    if args.get(1).is_some_and(|arg| arg == "keymap") {
+        cli_args.print_keymap = true;
+        if let Some(format) = &format
+            && format != "md"
+            && format != "markdown"
+        {
+            eprintln!("Error: Unknown keymap format '{format}'. Valid options: md");
+            std::process::exit(2);
+        }
+    }
+
+    // Handle the export subcommand
+    if args.get(1).is_some_and(|arg| arg == "export") {
+        let format = format.as_deref().unwrap_or("md");
+        match ExportFormat::from_name(format) {
+            Some(
+                format @ (ExportFormat::Markdown
+                | ExportFormat::Json
+                | ExportFormat::Sarif
+                | ExportFormat::Rdjson),
+            ) => {
+                cli_args.export_format = Some(format);
+            }
+            _ => {
+                eprintln!(
+                    "Error: Unknown export format '{format}'. Valid options: md, json, sarif, rdjson"
+                );
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Handle the check subcommand
+    if args.get(1).is_some_and(|arg| arg == "check") {
+        cli_args.check = true;
+    }
+
+    // Handle the sessions prune subcommand
+    if args.get(1).is_some_and(|arg| arg == "sessions") {
+        if args.get(2).is_none_or(|arg| arg != "prune") {
+            eprintln!("Error: Unknown sessions command. Valid options: prune");
+            std::process::exit(2);
+        }
+        let age = args
+            .iter()
+            .position(|arg| arg == "--older-than")
+            .and_then(|i| args.get(i + 1).cloned())
+            .or_else(|| {
+                args.iter()
+                    .find_map(|arg| arg.strip_prefix("--older-than=").map(str::to_string))
+            });
+        match age.as_deref().and_then(parse_age) {
+            Some(age) => cli_args.prune_older_than = Some(age),
+            None => {
+                eprintln!("Error: sessions prune requires --older-than <AGE> (e.g. 30d, 2w, 12h)");
+                std::process::exit(2);
+            }
+        }
+    }
 
     for i in 0..args.len() {
         // Handle --help / -h
@@ -265,29 +949,94 @@ pub fn parse_cli_args() -> CliArgs {
             print_help();
         }
 
-        // Handle --stdout
+        // Handle --stdout / --clipboard (the last one wins)
         if args[i] == "--stdout" {
             cli_args.output_to_stdout = true;
         }
+        if args[i] == "--clipboard" {
+            cli_args.output_to_stdout = false;
+        }
+
+        // Handle `-` (diff on stdin)
+        if i > 0 && args[i] == "-" {
+            cli_args.stdin_diff = true;
+        }
+
+        // Handle --read-only
+        if args[i] == "--read-only" {
+            cli_args.read_only = true;
+        }
+
+        // Handle --watch
+        if args[i] == "--watch" {
+            cli_args.watch = true;
+        }
+
+        // Handle --screen-reader
+        if args[i] == "--screen-reader" {
+            cli_args.screen_reader = true;
+        }
+
+        // Handle --reduce-motion
+        if args[i] == "--reduce-motion" {
+            cli_args.reduce_motion = true;
+        }
+
+        // Handle --highlight-max value / --highlight-max=value
+        let highlight_max = if args[i] == "--highlight-max" {
+            Some(args.get(i + 1).map(String::as_str).unwrap_or(""))
+        } else {
+            args[i].strip_prefix("--highlight-max=")
+        };
+        if let Some(value) = highlight_max {
+            match value.parse() {
+                Ok(lines) => cli_args.highlight_max_lines = Some(lines),
+                Err(_) => eprintln!("Warning: --highlight-max requires a number of lines"),
+            }
+        }
+
+        // Handle --range value / --range=value
+        if args[i] == "--range" {
+            match args.get(i + 1) {
+                Some(value) => cli_args.range = Some(value.clone()),
+                None => eprintln!("Warning: --range requires a range (e.g. main..feature)"),
+            }
+        }
+        if let Some(value) = args[i].strip_prefix("--range=") {
+            cli_args.range = Some(value.to_string());
+        }
+
+        // Handle --session value / --session=value
+        if args[i] == "--session" {
+            match args.get(i + 1) {
+                Some(value) => cli_args.session_path = Some(value.into()),
+                None => eprintln!("Warning: --session requires a file path"),
+            }
+        }
+        if let Some(value) = args[i].strip_prefix("--session=") {
+            cli_args.session_path = Some(value.into());
+        }
 
         // Handle --theme value
         if args[i] == "--theme" {
             if let Some(value) = args.get(i + 1) {
                 cli_args.theme = ThemeArg::from_str(value).unwrap_or_else(|| {
                     eprintln!(
-                        "Warning: Unknown theme '{value}', using dark. Valid options: dark, light"
+                        "Warning: Unknown theme '{value}', using dark. Valid options: {}",
+                        ThemeArg::names()
                     );
                     ThemeArg::Dark
                 });
             } else {
-                eprintln!("Warning: --theme requires a value (dark, light)");
+                eprintln!("Warning: --theme requires a value ({})", ThemeArg::names());
             }
         }
         // Handle --theme=value
         if let Some(value) = args[i].strip_prefix("--theme=") {
             cli_args.theme = ThemeArg::from_str(value).unwrap_or_else(|| {
                 eprintln!(
-                    "Warning: Unknown theme '{value}', using dark. Valid options: dark, light"
+                    "Warning: Unknown theme '{value}', using dark. Valid options: {}",
+                    ThemeArg::names()
                 );
                 ThemeArg::Dark
             });
@@ -1,28 +1,52 @@
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
-    style::Style,
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
 };
 use unicode_width::UnicodeWidthStr;
 
-use crate::app::{App, DiffViewMode, FileTreeItem, FocusedPanel, GapId, InputMode};
-use crate::model::{LineOrigin, LineRange, LineSide};
+use crate::app::{
+    AnnotatedLine, App, CommentInputKind, DiffSource, DiffViewMode, FileFilter, FileTreeItem,
+    FocusedPanel, GapId, InputMode,
+};
+use crate::model::review::FileReview;
+use crate::model::todo_markers::marker_ranges;
+use crate::model::{DiffFile, DiffLine, LineOrigin, LineRange, LineSide, SearchPattern};
+use crate::text_edit::TextEditor;
 use crate::theme::Theme;
-use crate::ui::{comment_panel, help_popup, status_bar, styles};
+use crate::ui::blame_gutter::{BLAME_GUTTER_WIDTH, blame_gutter_text, blank_blame_gutter};
+use crate::ui::indent_guides::add_indent_guides;
+use crate::ui::{comment_panel, error_view, help_popup, status_bar, styles, text_popup};
 use crate::vcs::git::calculate_gap;
 
+/// Below this size the panels cannot be drawn legibly
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+/// Below this width, side panels are stacked above and below the diff
+const STACKED_LAYOUT_WIDTH: u16 = 100;
+
 pub fn render(frame: &mut Frame, app: &mut App) {
     // Special handling for commit selection mode
     if app.input_mode == InputMode::CommitSelect {
         render_commit_select(frame, app);
         return;
     }
+    if app.input_mode == InputMode::RefSelect {
+        render_ref_select(frame, app);
+        return;
+    }
 
     // Clear cursor position before rendering (will be set if in Comment mode)
     app.comment_cursor_screen_pos = None;
 
+    let area = frame.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        render_too_small(frame, app, area);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
@@ -41,11 +65,30 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         help_popup::render_help(frame, app);
     }
 
+    if app.input_mode == InputMode::Popup {
+        text_popup::render_text_popup(frame, app);
+    }
+
+    if app.input_mode == InputMode::ThemePicker {
+        text_popup::render_theme_picker(frame, app);
+    }
+
     // Comment input is now rendered inline in the diff view
 
     // Render confirm dialog if in confirm mode
     if app.input_mode == InputMode::Confirm {
-        comment_panel::render_confirm_dialog(frame, app, "Copy review to clipboard?");
+        comment_panel::render_confirm_dialog(frame, app, &app.confirm_message());
+    }
+
+    if app.input_mode == InputMode::Error {
+        error_view::render_error_popup(frame, app);
+    }
+
+    if app.dim_when_unfocused && !app.terminal_focused {
+        let area = frame.area();
+        frame
+            .buffer_mut()
+            .set_style(area, Style::default().add_modifier(Modifier::DIM));
     }
 
     // Position terminal cursor for IME when in Comment mode
@@ -221,6 +264,109 @@ fn render_commit_select(frame: &mut Frame, app: &mut App) {
     frame.render_widget(footer, chunks[2]);
 }
 
+fn render_ref_select(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Header
+            Constraint::Min(0),    // Ref list
+            Constraint::Length(1), // Footer hints
+        ])
+        .split(area);
+
+    let header = Paragraph::new(" Select a base to compare the working tree against ")
+        .style(styles::header_style(&app.theme))
+        .block(Block::default());
+    frame.render_widget(header, chunks[0]);
+
+    let block = Block::default()
+        .title(" Branches and Tags ")
+        .borders(Borders::ALL)
+        .border_style(styles::border_style(&app.theme, true));
+
+    let inner = block.inner(chunks[1]);
+    frame.render_widget(block, chunks[1]);
+
+    app.ref_list_viewport_height = inner.height as usize;
+
+    let current_base = match &app.diff_source {
+        DiffSource::Ref(base) => Some(base.as_str()),
+        _ => None,
+    };
+    let name_width = app
+        .ref_list
+        .iter()
+        .map(|r| r.name.width())
+        .max()
+        .unwrap_or(0)
+        .min(40);
+
+    // Format: > remote  origin/main      abc1234 Commit summary
+    let lines: Vec<Line> = app
+        .ref_list
+        .iter()
+        .enumerate()
+        .skip(app.ref_list_scroll_offset)
+        .take(inner.height as usize)
+        .map(|(i, info)| {
+            let is_cursor = i == app.ref_list_cursor;
+            let pointer = if is_cursor { ">" } else { " " };
+            let style = if is_cursor {
+                styles::selected_style(&app.theme)
+            } else {
+                Style::default()
+            };
+            let current = if current_base == Some(info.name.as_str()) {
+                " (current)"
+            } else {
+                ""
+            };
+            let name = truncate_str(&info.name, name_width);
+            let padding = " ".repeat(name_width.saturating_sub(name.width()));
+            Line::from(vec![
+                Span::styled(format!("{pointer} "), style),
+                Span::styled(
+                    format!("{:<7}", info.kind.label()),
+                    Style::default().fg(app.theme.fg_secondary),
+                ),
+                Span::styled(format!("{name}{padding} "), style),
+                Span::styled(
+                    format!("{} ", info.short_id),
+                    styles::hash_style(&app.theme),
+                ),
+                Span::styled(
+                    truncate_str(&info.summary, 50),
+                    Style::default().fg(app.theme.fg_secondary),
+                ),
+                Span::styled(current, styles::reviewed_style(&app.theme)),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+
+    let theme = &app.theme;
+    let mode_span = Span::styled(" REFS ", styles::mode_style(theme));
+    let hints_span = Span::styled(
+        " j/k:navigate  Enter:compare  Esc:cancel",
+        Style::default().fg(theme.fg_secondary),
+    );
+
+    let (message_span, message_width) = status_bar::build_message_span(app.message.as_ref(), theme);
+    let spans = status_bar::build_right_aligned_spans(
+        vec![mode_span, hints_span],
+        message_span,
+        message_width,
+        chunks[2].width as usize,
+    );
+
+    let footer = Paragraph::new(Line::from(spans))
+        .style(styles::status_bar_style(theme))
+        .block(Block::default());
+    frame.render_widget(footer, chunks[2]);
+}
+
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -229,13 +375,69 @@ fn truncate_str(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Shown instead of the UI when the terminal is smaller than the minimum
+fn render_too_small(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.file_list_area = None;
+    app.file_list_state.path_echo = None;
+    app.diff_area = None;
+    app.comments_area = None;
+
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            styles::header_style(&app.theme),
+        )),
+        Line::from(format!("{}x{}", area.width, area.height)),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Enlarge to at least {MIN_WIDTH}x{MIN_HEIGHT}"),
+            styles::dim_style(&app.theme),
+        )),
+    ];
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let message_area = Rect {
+        y: area.y + top,
+        height: area.height - top,
+        ..area
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .alignment(ratatui::layout::Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        message_area,
+    );
+}
+
 fn render_main_content(frame: &mut Frame, app: &mut App, area: Rect) {
+    // Narrow terminals get the side panels above and below the diff
+    let (direction, list_share, comments_share) = if area.width < STACKED_LAYOUT_WIDTH {
+        (Direction::Vertical, 30, 30)
+    } else {
+        (Direction::Horizontal, 20, 25)
+    };
+
+    let area = if app.show_comments_panel {
+        let chunks = Layout::default()
+            .direction(direction)
+            .constraints([
+                Constraint::Percentage(100 - comments_share),
+                Constraint::Percentage(comments_share),
+            ])
+            .split(area);
+        app.comments_area = Some(chunks[1]);
+        comment_panel::render_comments_panel(frame, app, chunks[1]);
+        chunks[0]
+    } else {
+        app.comments_area = None;
+        area
+    };
+
     if app.show_file_list {
         let chunks = Layout::default()
-            .direction(Direction::Horizontal)
+            .direction(direction)
             .constraints([
-                Constraint::Percentage(20), // File list
-                Constraint::Percentage(80), // Diff view
+                Constraint::Percentage(list_share),       // File list
+                Constraint::Percentage(100 - list_share), // Diff view
             ])
             .split(area);
 
@@ -246,6 +448,7 @@ fn render_main_content(frame: &mut Frame, app: &mut App, area: Rect) {
         render_diff_view(frame, app, chunks[1]);
     } else {
         app.file_list_area = None;
+        app.file_list_state.path_echo = None;
         app.diff_area = Some(area);
 
         render_diff_view(frame, app, area);
@@ -264,36 +467,60 @@ fn render_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
         .border_style(styles::border_style(&app.theme, focused));
 
     let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+    render_file_filter_tabs(frame, app, chunks[0]);
+    let list_area = chunks[1];
+
     let visible_items = app.build_visible_items();
 
-    let max_content_width = visible_items
+    // Where each row's name starts and where the row ends
+    let row_extents: Vec<(usize, usize)> = visible_items
         .iter()
         .map(|item| match item {
-            FileTreeItem::Directory { path, depth, .. } => {
+            FileTreeItem::Directory {
+                path,
+                depth,
+                expanded,
+            } => {
                 let dir_name = Path::new(path)
                     .file_name()
                     .and_then(|n| n.to_str())
                     .unwrap_or(path);
-                depth * 2 + 2 + dir_name.width() + 1
+                let rollup_width = directory_rollup_spans(app, path, *expanded)
+                    .iter()
+                    .map(|span| span.content.width())
+                    .sum::<usize>();
+                let name_start = depth * 2 + 2;
+                (name_start, name_start + dir_name.width() + 1 + rollup_width)
             }
-            FileTreeItem::File { file_idx, depth } => {
+            FileTreeItem::File {
+                file_idx,
+                depth,
+                pinned,
+            } => {
                 let file = &app.diff_files[*file_idx];
-                let filename = file
-                    .display_path()
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("?");
-                depth * 2 + 3 + 3 + filename.width()
+                let name = file_list_name(file.display_path(), *pinned);
+                let owner_width = app
+                    .code_owners
+                    .label(file.display_path())
+                    .map_or(0, |owner| owner.width() + 1);
+                let name_start = depth * 2 + 3 + 3;
+                (name_start, name_start + name.width() + owner_width)
             }
         })
-        .max()
-        .unwrap_or(0);
+        .collect();
+    let max_content_width = row_extents.iter().map(|(_, end)| *end).max().unwrap_or(0);
 
-    app.file_list_state.viewport_width = inner.width as usize;
-    app.file_list_state.viewport_height = inner.height as usize;
+    app.file_list_state.viewport_width = list_area.width as usize;
+    app.file_list_state.viewport_height = list_area.height as usize;
     app.file_list_state.max_content_width = max_content_width;
+    app.file_list_state.top = list_area.y;
 
-    let max_scroll_x = max_content_width.saturating_sub(inner.width as usize);
+    let max_scroll_x = max_content_width.saturating_sub(list_area.width as usize);
     if app.file_list_state.scroll_x > max_scroll_x {
         app.file_list_state.scroll_x = max_scroll_x;
     }
@@ -321,11 +548,33 @@ fn render_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
 
     let selected_idx = app.file_list_state.selected();
 
+    // Echo the full path of a cut-off entry under the mouse, or of the
+    // selected one while the list has focus
+    let echo_idx = app
+        .file_list_state
+        .hovered()
+        .or((app.focused_panel == FocusedPanel::FileList).then_some(selected_idx));
+    app.file_list_state.path_echo = echo_idx.and_then(|idx| {
+        let (name_start, end) = *row_extents.get(idx)?;
+        let cut_off = scroll_x > name_start || end > scroll_x + list_area.width as usize;
+        if !cut_off {
+            return None;
+        }
+        Some(match &visible_items[idx] {
+            FileTreeItem::Directory { path, .. } => format!("{path}/"),
+            FileTreeItem::File { file_idx, .. } => app.diff_files[*file_idx]
+                .display_path()
+                .display()
+                .to_string(),
+        })
+    });
+
     let items: Vec<ListItem> = visible_items
         .iter()
         .enumerate()
         .map(|(i, item)| {
             let is_selected = i == selected_idx;
+            let in_selection = app.is_tree_item_in_visual_selection(i);
 
             match item {
                 FileTreeItem::Directory {
@@ -342,39 +591,66 @@ fn render_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
 
                     let style = if is_selected {
                         styles::selected_style(&app.theme).add_modifier(Modifier::UNDERLINED)
+                    } else if in_selection {
+                        styles::visual_selection_style(&app.theme)
                     } else {
                         Style::default()
                     };
 
-                    let line = Line::from(vec![
+                    let mut spans = vec![
                         Span::styled(indent, Style::default()),
                         Span::styled(format!("{icon} "), styles::dir_icon_style(&app.theme)),
                         Span::styled(format!("{dir_name}/"), style),
-                    ]);
+                    ];
+                    spans.extend(directory_rollup_spans(app, path, *expanded));
+                    let line = Line::from(spans);
 
                     ListItem::new(apply_horizontal_scroll(line, scroll_x))
                 }
-                FileTreeItem::File { file_idx, depth } => {
+                FileTreeItem::File {
+                    file_idx,
+                    depth,
+                    pinned,
+                } => {
                     let file = &app.diff_files[*file_idx];
                     let path = file.display_path();
-                    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
+                    let mut filename = file_list_name(path, *pinned);
+                    if let Some(old) = file.renamed_from() {
+                        filename = format!("{} → {filename}", old.display());
+                    }
                     let status = file.status.as_char();
                     let is_reviewed = app.session.is_file_reviewed(path);
-                    let review_mark = if is_reviewed { "✓" } else { " " };
+                    let is_skipped = app.session.is_file_skipped(path);
+                    let is_partly_reviewed = app.session.is_file_partly_reviewed(path);
+                    let review_mark = if is_skipped {
+                        "-"
+                    } else if is_reviewed {
+                        "✓"
+                    } else if is_partly_reviewed {
+                        "~"
+                    } else {
+                        " "
+                    };
 
                     let indent = "  ".repeat(*depth);
 
                     let style = if is_selected {
                         styles::selected_style(&app.theme).add_modifier(Modifier::UNDERLINED)
+                    } else if in_selection {
+                        styles::visual_selection_style(&app.theme)
+                    } else if is_skipped {
+                        styles::dim_style(&app.theme)
                     } else {
                         Style::default()
                     };
 
-                    let line = Line::from(vec![
+                    let mut spans = vec![
                         Span::styled(indent, Style::default()),
                         Span::styled(
                             format!("[{review_mark}]"),
-                            if is_reviewed {
+                            if is_skipped {
+                                styles::dim_style(&app.theme)
+                            } else if is_reviewed {
                                 styles::reviewed_style(&app.theme)
                             } else {
                                 styles::pending_style(&app.theme)
@@ -384,8 +660,18 @@ fn render_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
                             format!(" {status} "),
                             styles::file_status_style(&app.theme, status),
                         ),
-                        Span::styled(filename.to_string(), style),
-                    ]);
+                        Span::styled(filename, style),
+                    ];
+                    if app.staged_paths.contains(path) {
+                        spans.push(Span::styled(" +", styles::staged_style(&app.theme)));
+                    }
+                    if let Some(owner) = app.code_owners.label(path) {
+                        spans.push(Span::styled(
+                            format!(" {owner}"),
+                            styles::dim_style(&app.theme),
+                        ));
+                    }
+                    let line = Line::from(spans);
 
                     ListItem::new(apply_horizontal_scroll(line, scroll_x))
                 }
@@ -393,9 +679,120 @@ fn render_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
         })
         .collect();
 
-    let list = List::new(items).block(block);
+    let list = List::new(items);
+
+    frame.render_stateful_widget(list, list_area, &mut app.file_list_state.list_state);
+}
+
+/// Filter tabs above the file list; the active tab is highlighted
+fn render_file_filter_tabs(frame: &mut Frame, app: &App, area: Rect) {
+    let mut spans = Vec::new();
+    for (idx, filter) in FileFilter::ALL.iter().enumerate() {
+        if idx > 0 {
+            spans.push(Span::styled("│", styles::dim_style(&app.theme)));
+        }
+        let style = if *filter == app.file_filter {
+            styles::selected_style(&app.theme).add_modifier(Modifier::BOLD)
+        } else {
+            styles::dim_style(&app.theme)
+        };
+        spans.push(Span::styled(filter.tab_label(), style));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Hunk header row followed by the hunk's position and line counts, like
+/// "hunk 2/7 +3 -1"; while its file is partly reviewed, hunks reviewed on
+/// their own carry a checkmark
+fn hunk_header_line(
+    app: &App,
+    file: &DiffFile,
+    hunk_idx: usize,
+    indicator: &'static str,
+) -> Line<'static> {
+    let path = file.display_path();
+    let hunk = &file.hunks[hunk_idx];
+    let (additions, deletions) = hunk.line_stats();
+    let position = format!(" hunk {}/{}", hunk_idx + 1, file.hunks.len());
+    let mut spans = vec![
+        Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
+        Span::styled(
+            hunk.header.to_string(),
+            styles::diff_hunk_header_style(&app.theme),
+        ),
+    ];
+    if app.screen_reader {
+        spans.push(Span::styled(
+            format!("{position}, {additions} added, {deletions} removed"),
+            styles::dim_style(&app.theme),
+        ));
+    } else {
+        spans.extend([
+            Span::styled(position, styles::dim_style(&app.theme)),
+            Span::styled(format!(" +{additions}"), styles::stat_add_style(&app.theme)),
+            Span::styled(format!(" -{deletions}"), styles::stat_del_style(&app.theme)),
+        ]);
+    }
+    if app.session.is_file_partly_reviewed(path)
+        && app.session.is_hunk_reviewed(path, &hunk.fingerprint())
+    {
+        let mark = if app.screen_reader {
+            " reviewed"
+        } else {
+            " ✓"
+        };
+        spans.push(Span::styled(mark, styles::reviewed_style(&app.theme)));
+    }
+    Line::from(spans)
+}
+
+/// Header annotation for EOF newline, BOM and encoding changes
+fn file_indicator_label(file: &DiffFile) -> String {
+    file.indicators()
+        .iter()
+        .map(|indicator| format!("⚠ {indicator} "))
+        .collect()
+}
 
-    frame.render_stateful_widget(list, area, &mut app.file_list_state.list_state);
+/// Tree entries show the file name; pinned entries show the full path with a
+/// pin marker since they sit outside their directory.
+fn file_list_name(path: &std::path::Path, pinned: bool) -> String {
+    if pinned {
+        format!("⚑ {}", path.display())
+    } else {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("?")
+            .to_string()
+    }
+}
+
+/// Summary shown after a collapsed directory: "+12 -3 •2" where the last
+/// figure is the number of unreviewed files inside it.
+fn directory_rollup_spans(app: &App, path: &str, expanded: bool) -> Vec<Span<'static>> {
+    if expanded {
+        return Vec::new();
+    }
+    let rollup = app.directory_rollup(path);
+    let mut spans = vec![
+        Span::styled(
+            format!(" +{}", rollup.additions),
+            styles::stat_add_style(&app.theme),
+        ),
+        Span::styled(
+            format!(" -{}", rollup.deletions),
+            styles::stat_del_style(&app.theme),
+        ),
+    ];
+    if rollup.unreviewed > 0 {
+        spans.push(Span::styled(
+            format!(" •{}", rollup.unreviewed),
+            styles::pending_style(&app.theme),
+        ));
+    } else {
+        spans.push(Span::styled(" ✓", styles::reviewed_style(&app.theme)));
+    }
+    spans
 }
 
 fn render_diff_view(frame: &mut Frame, app: &mut App, area: Rect) {
@@ -418,6 +815,14 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
 
     // Update viewport height for scroll calculations
     app.diff_state.viewport_height = inner.height as usize;
+    // Comment input rows follow the cursor indicator and the box border
+    app.comment_editor.set_wrap_width(
+        (inner.width as usize).saturating_sub(1 + comment_panel::COMMENT_INPUT_BORDER_WIDTH),
+    );
+    if app.blame_gutter {
+        app.load_visible_blame();
+    }
+    let now = chrono::Utc::now();
 
     // Build all diff lines for infinite scroll
     // Track line index to mark the current line (cursor position)
@@ -429,6 +834,7 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
     // Store the logical line index and column where the cursor should be
     let mut comment_cursor_logical_line: Option<usize> = None;
     let mut comment_cursor_column: u16 = 0;
+    let decorations = ContentDecorations::new(app);
 
     for (file_idx, file) in app.diff_files.iter().enumerate() {
         let path = file.display_path();
@@ -438,17 +844,36 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
         // File header
         let indicator = cursor_indicator_spaced(line_idx, current_line_idx);
 
-        // Add checkmark if reviewed (using same character as file list)
-        let review_mark = if is_reviewed { "✓ " } else { "" };
+        if app.screen_reader {
+            // Words instead of the rule, checkmark and status letter
+            let mut header = format!("file {} {}", file.status.label(), file.path_label());
+            if is_reviewed {
+                header.push_str(", reviewed");
+            }
+            for indicator in file.indicators() {
+                header.push_str(&format!(", {indicator}"));
+            }
+            lines.push(Line::from(vec![
+                Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
+                Span::styled(header, styles::file_header_style(&app.theme)),
+            ]));
+        } else {
+            // Add checkmark if reviewed (using same character as file list)
+            let review_mark = if is_reviewed { "✓ " } else { "" };
 
-        lines.push(Line::from(vec![
-            Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
-            Span::styled(
-                format!("═══ {}{} [{}] ", review_mark, path.display(), status),
-                styles::file_header_style(&app.theme),
-            ),
-            Span::styled("═".repeat(40), styles::file_header_style(&app.theme)),
-        ]));
+            lines.push(Line::from(vec![
+                Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
+                Span::styled(
+                    format!("═══ {}{} [{}] ", review_mark, file.path_label(), status),
+                    styles::file_header_style(&app.theme),
+                ),
+                Span::styled(
+                    file_indicator_label(file),
+                    styles::pending_style(&app.theme),
+                ),
+                Span::styled("═".repeat(40), styles::file_header_style(&app.theme)),
+            ]));
+        }
         line_idx += 1;
 
         // If file is reviewed, skip rendering the body (fold it away)
@@ -473,10 +898,9 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                     let (input_lines, cursor_info) = comment_panel::format_comment_input_lines(
                         &app.theme,
                         app.comment_type,
-                        &app.comment_buffer,
-                        app.comment_cursor,
+                        &app.comment_editor,
                         None,
-                        true,
+                        CommentInputKind::Edit,
                         app.supports_keyboard_enhancement,
                     );
                     // Track cursor position: logical line = current line_idx + cursor offset within input
@@ -499,9 +923,10 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                 } else {
                     let comment_lines = comment_panel::format_comment_lines(
                         &app.theme,
-                        comment.comment_type,
-                        &comment.content,
+                        comment,
                         None,
+                        app.session.has_published(),
+                        app.screen_reader,
                     );
                     for mut comment_line in comment_lines {
                         let indicator = cursor_indicator(line_idx, current_line_idx);
@@ -524,10 +949,9 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             let (input_lines, cursor_info) = comment_panel::format_comment_input_lines(
                 &app.theme,
                 app.comment_type,
-                &app.comment_buffer,
-                app.comment_cursor,
+                &app.comment_editor,
                 None,
-                false,
+                app.comment_input_kind(),
                 app.supports_keyboard_enhancement,
             );
             // Track cursor position
@@ -549,7 +973,10 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             let indicator = cursor_indicator_spaced(line_idx, current_line_idx);
             lines.push(Line::from(vec![
                 Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
-                Span::styled("(binary file)", styles::dim_style(&app.theme)),
+                Span::styled(
+                    file.binary_sizes.placeholder(),
+                    styles::dim_style(&app.theme),
+                ),
             ]));
             line_idx += 1;
         } else if file.hunks.is_empty() {
@@ -589,26 +1016,39 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                         if let Some(expanded_lines) = app.expanded_content.get(&gap_id) {
                             for expanded_line in expanded_lines {
                                 let indicator = cursor_indicator(line_idx, current_line_idx);
-                                let line_num = expanded_line
-                                    .new_lineno
-                                    .map(|n| format!("{n:>4} "))
-                                    .unwrap_or_else(|| "     ".to_string());
+                                let line_num = format!(
+                                    "{} ",
+                                    line_number_label(
+                                        app,
+                                        expanded_line.new_lineno,
+                                        line_idx,
+                                        current_line_idx
+                                    )
+                                );
 
-                                let line_spans = vec![
+                                let expanded_style = styles::expanded_context_style(&app.theme);
+                                let mut line_spans = vec![
                                     Span::styled(
                                         indicator,
                                         styles::current_line_indicator_style(&app.theme),
                                     ),
-                                    Span::styled(
-                                        line_num,
-                                        styles::expanded_context_style(&app.theme),
-                                    ),
-                                    Span::styled("  ", styles::expanded_context_style(&app.theme)),
-                                    Span::styled(
-                                        expanded_line.content.clone(),
-                                        styles::expanded_context_style(&app.theme),
-                                    ),
+                                    Span::styled(line_num, expanded_style),
+                                    Span::styled("  ", expanded_style),
                                 ];
+                                if let Some(gutter) =
+                                    blame_gutter_span(app, path, expanded_line.new_lineno, now)
+                                {
+                                    line_spans.insert(1, gutter);
+                                }
+                                line_spans.extend(
+                                    plain_content_spans(
+                                        &expanded_line.content,
+                                        expanded_style,
+                                        decorations,
+                                    )
+                                    .into_iter()
+                                    .map(|(style, text)| Span::styled(text, style)),
+                                );
                                 lines.push(Line::from(line_spans));
                                 line_idx += 1;
                             }
@@ -632,13 +1072,7 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
 
                 // Hunk header
                 let indicator = cursor_indicator_spaced(line_idx, current_line_idx);
-                lines.push(Line::from(vec![
-                    Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
-                    Span::styled(
-                        hunk.header.to_string(),
-                        styles::diff_hunk_header_style(&app.theme),
-                    ),
-                ]));
+                lines.push(hunk_header_line(app, file, hunk_idx, indicator));
                 line_idx += 1;
 
                 // Diff lines
@@ -648,6 +1082,11 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                         LineOrigin::Deletion => ("-", styles::diff_del_style(&app.theme)),
                         LineOrigin::Context => (" ", styles::diff_context_style(&app.theme)),
                     };
+                    let prefix = match (diff_line.origin, app.screen_reader) {
+                        (LineOrigin::Addition, true) => "added:",
+                        (LineOrigin::Deletion, true) => "removed:",
+                        _ => prefix,
+                    };
 
                     // Check if this line is in visual selection
                     let is_in_visual_selection = {
@@ -671,21 +1110,15 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                         base_style
                     };
 
-                    let line_num_str = match diff_line.origin {
-                        LineOrigin::Addition => diff_line
-                            .new_lineno
-                            .map(|n| format!("{n:>4} "))
-                            .unwrap_or_else(|| "     ".to_string()),
-                        LineOrigin::Deletion => diff_line
-                            .old_lineno
-                            .map(|n| format!("{n:>4} "))
-                            .unwrap_or_else(|| "     ".to_string()),
-                        _ => diff_line
-                            .new_lineno
-                            .or(diff_line.old_lineno)
-                            .map(|n| format!("{n:>4} "))
-                            .unwrap_or_else(|| "     ".to_string()),
+                    let lineno = match diff_line.origin {
+                        LineOrigin::Addition => diff_line.new_lineno,
+                        LineOrigin::Deletion => diff_line.old_lineno,
+                        _ => diff_line.new_lineno.or(diff_line.old_lineno),
                     };
+                    let line_num_str = format!(
+                        "{} ",
+                        line_number_label(app, lineno, line_idx, current_line_idx)
+                    );
 
                     let indicator = cursor_indicator(line_idx, current_line_idx);
 
@@ -702,22 +1135,35 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                         Span::styled(line_num_str, line_num_style),
                         Span::styled(format!("{prefix} "), style),
                     ];
+                    let blame_lineno = match diff_line.origin {
+                        LineOrigin::Deletion => None,
+                        _ => diff_line.new_lineno,
+                    };
+                    if let Some(gutter) = blame_gutter_span(app, path, blame_lineno, now) {
+                        line_spans.insert(1, gutter);
+                    }
 
                     // Add content spans
-                    if let Some(ref highlighted) = diff_line.highlighted_spans {
+                    if let Some(highlighted) = content_spans(diff_line, base_style, decorations) {
                         // Use syntax-highlighted spans
                         for (span_style, span_text) in highlighted {
                             let final_style = if is_in_visual_selection {
                                 span_style.patch(styles::visual_selection_style(&app.theme))
                             } else {
-                                *span_style
+                                span_style
                             };
-                            line_spans.push(Span::styled(span_text.clone(), final_style));
+                            line_spans.push(Span::styled(span_text, final_style));
                         }
                     } else {
                         // Fall back to default diff styling
                         line_spans.push(Span::styled(diff_line.content.clone(), style));
                     }
+                    if diff_line.no_newline_at_eof {
+                        line_spans.push(Span::styled(
+                            " \\ no newline at end of file",
+                            styles::pending_style(&app.theme).add_modifier(Modifier::ITALIC),
+                        ));
+                    }
 
                     lines.push(Line::from(line_spans));
                     line_idx += 1;
@@ -747,10 +1193,9 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                                             comment_panel::format_comment_input_lines(
                                                 &app.theme,
                                                 app.comment_type,
-                                                &app.comment_buffer,
-                                                app.comment_cursor,
+                                                &app.comment_editor,
                                                 line_range,
-                                                true,
+                                                CommentInputKind::Edit,
                                                 app.supports_keyboard_enhancement,
                                             );
                                         comment_cursor_logical_line =
@@ -778,9 +1223,10 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                                             .or_else(|| Some(LineRange::single(old_ln)));
                                         let comment_lines = comment_panel::format_comment_lines(
                                             &app.theme,
-                                            comment.comment_type,
-                                            &comment.content,
+                                            comment,
                                             line_range,
+                                            app.session.has_published(),
+                                            app.screen_reader,
                                         );
                                         for mut comment_line in comment_lines {
                                             let is_current = line_idx == current_line_idx;
@@ -812,10 +1258,9 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                                 comment_panel::format_comment_input_lines(
                                     &app.theme,
                                     app.comment_type,
-                                    &app.comment_buffer,
-                                    app.comment_cursor,
+                                    &app.comment_editor,
                                     line_range,
-                                    false,
+                                    app.comment_input_kind(),
                                     app.supports_keyboard_enhancement,
                                 );
                             comment_cursor_logical_line = Some(line_idx + cursor_info.line_offset);
@@ -860,10 +1305,9 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                                             comment_panel::format_comment_input_lines(
                                                 &app.theme,
                                                 app.comment_type,
-                                                &app.comment_buffer,
-                                                app.comment_cursor,
+                                                &app.comment_editor,
                                                 line_range,
-                                                true,
+                                                CommentInputKind::Edit,
                                                 app.supports_keyboard_enhancement,
                                             );
                                         comment_cursor_logical_line =
@@ -891,9 +1335,10 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                                             .or_else(|| Some(LineRange::single(new_ln)));
                                         let comment_lines = comment_panel::format_comment_lines(
                                             &app.theme,
-                                            comment.comment_type,
-                                            &comment.content,
+                                            comment,
                                             line_range,
+                                            app.session.has_published(),
+                                            app.screen_reader,
                                         );
                                         for mut comment_line in comment_lines {
                                             let indicator =
@@ -925,10 +1370,9 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                                 comment_panel::format_comment_input_lines(
                                     &app.theme,
                                     app.comment_type,
-                                    &app.comment_buffer,
-                                    app.comment_cursor,
+                                    &app.comment_editor,
                                     line_range,
-                                    false,
+                                    app.comment_input_kind(),
                                     app.supports_keyboard_enhancement,
                                 );
                             comment_cursor_logical_line = Some(line_idx + cursor_info.line_offset);
@@ -986,7 +1430,8 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
     // Calculate how many logical lines actually fit in the viewport when wrapped
     let viewport_width = inner.width as usize;
     let viewport_height = inner.height as usize;
-    app.diff_state.visible_line_count = if app.diff_state.wrap_lines && viewport_width > 0 {
+    let wrap_lines = app.wrap_enabled();
+    app.diff_state.visible_line_count = if wrap_lines && viewport_width > 0 {
         let mut visual_rows_used = 0;
         let mut logical_lines_visible = 0;
         for &width in &line_widths {
@@ -1011,12 +1456,12 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
     if app.diff_state.scroll_x > max_scroll_x {
         app.diff_state.scroll_x = max_scroll_x;
     }
-    if app.diff_state.wrap_lines {
+    if wrap_lines {
         app.diff_state.scroll_x = 0;
     }
 
     let scroll_x = app.diff_state.scroll_x;
-    let visible_lines: Vec<Line> = if app.diff_state.wrap_lines {
+    let visible_lines: Vec<Line> = if wrap_lines {
         visible_lines_unscrolled
     } else {
         visible_lines_unscrolled
@@ -1025,12 +1470,31 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             .collect()
     };
 
+    let mut visible_lines = visible_lines;
+    if app.dim_viewed {
+        dim_viewed_lines(app, &mut visible_lines);
+    }
+    if app.cursor_line_highlight {
+        highlight_cursor_line(app, &mut visible_lines, inner.width as usize);
+    }
+
     let mut diff = Paragraph::new(visible_lines);
-    if app.diff_state.wrap_lines {
+    if wrap_lines {
         diff = diff.wrap(Wrap { trim: false });
     }
     frame.render_widget(diff, inner);
 
+    if app.cursor_column_highlight
+        && let Some((col, _)) = app.cursor_indent_column()
+    {
+        let offset = if app.blame_gutter {
+            8 + BLAME_GUTTER_WIDTH
+        } else {
+            8
+        };
+        highlight_cursor_column(frame, app, inner, &[offset], col);
+    }
+
     // Calculate screen position for comment cursor if in Comment mode
     if let Some(cursor_logical_line) = comment_cursor_logical_line {
         let scroll_offset = app.diff_state.scroll_offset;
@@ -1048,7 +1512,7 @@ fn render_unified_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             let mut visual_row: u16 = 0;
             let viewport_width = inner.width as usize;
 
-            if app.diff_state.wrap_lines && viewport_width > 0 {
+            if wrap_lines && viewport_width > 0 {
                 // Calculate how many visual rows the lines before cursor take
                 // Note: line_widths is indexed from 0 and corresponds to visible lines
                 // (i.e., line_widths[0] is the first visible line after scroll)
@@ -1087,11 +1551,43 @@ struct SideBySideContext<'a> {
     comment_input_mode: bool,
     comment_line: Option<(u32, LineSide)>,
     comment_type: crate::model::CommentType,
-    comment_buffer: &'a str,
-    comment_cursor: usize,
+    comment_editor: &'a TextEditor,
     comment_line_range: Option<LineRange>,
     editing_comment_id: Option<&'a str>,
+    comment_input_kind: CommentInputKind,
     supports_keyboard_enhancement: bool,
+    show_drafts: bool,
+    decorations: ContentDecorations<'a>,
+    relative_line_numbers: bool,
+    /// Review of the file being rendered, to dim its viewed lines
+    /// (`:set dimviewed`)
+    viewed: Option<&'a FileReview>,
+    /// Whether the hunk being rendered is marked reviewed, dimming all of it
+    hunk_viewed: bool,
+}
+
+impl SideBySideContext<'_> {
+    /// Dim `line`, the row of `diff_lines`, when they are all viewed
+    fn dim_if_viewed(&self, line: &mut Line, diff_lines: &[&DiffLine]) {
+        let viewed = self.hunk_viewed
+            || self.viewed.is_some_and(|review| {
+                diff_lines.iter().all(|diff_line| {
+                    review.is_line_viewed(diff_line.old_lineno, diff_line.new_lineno)
+                })
+            });
+        if viewed {
+            line.style = line.style.patch(styles::viewed_line_style());
+        }
+    }
+
+    fn line_number_label(&self, lineno: Option<u32>, line_idx: usize) -> String {
+        format_line_number(
+            lineno,
+            line_idx,
+            self.current_line_idx,
+            self.relative_line_numbers,
+        )
+    }
 }
 
 /// Get cursor indicator (single character for inline content)
@@ -1125,6 +1621,10 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
 
     // Update viewport height for scroll calculations
     app.diff_state.viewport_height = inner.height as usize;
+    // Comment input rows follow the cursor indicator and the box border
+    app.comment_editor.set_wrap_width(
+        (inner.width as usize).saturating_sub(1 + comment_panel::COMMENT_INPUT_BORDER_WIDTH),
+    );
 
     // Calculate column widths (split the area in half)
     // Layout: indicator(1) + linenum(4) + space(1) + prefix(1) + content + " │ "(3) + linenum(4) + space(1) + prefix(1) + content
@@ -1135,18 +1635,23 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
     // Determine if we're in line comment mode (not file-level)
     let comment_input_mode = app.input_mode == InputMode::Comment && !app.comment_is_file_level;
 
-    let ctx = SideBySideContext {
+    let mut ctx = SideBySideContext {
         theme: &app.theme,
         content_width,
         current_line_idx: app.diff_state.cursor_line,
         comment_input_mode,
         comment_line: app.comment_line,
         comment_type: app.comment_type,
-        comment_buffer: &app.comment_buffer,
-        comment_cursor: app.comment_cursor,
+        comment_editor: &app.comment_editor,
         comment_line_range: app.comment_line_range.map(|(r, _)| r),
         editing_comment_id: app.editing_comment_id.as_deref(),
+        comment_input_kind: app.comment_input_kind(),
         supports_keyboard_enhancement: app.supports_keyboard_enhancement,
+        show_drafts: app.session.has_published(),
+        decorations: ContentDecorations::new(app),
+        relative_line_numbers: app.relative_line_numbers,
+        viewed: None,
+        hunk_viewed: false,
     };
 
     // Build all diff lines for side-by-side view
@@ -1170,9 +1675,13 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
         lines.push(Line::from(vec![
             Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
             Span::styled(
-                format!("═══ {}{} [{}] ", review_mark, path.display(), status),
+                format!("═══ {}{} [{}] ", review_mark, file.path_label(), status),
                 styles::file_header_style(&app.theme),
             ),
+            Span::styled(
+                file_indicator_label(file),
+                styles::pending_style(&app.theme),
+            ),
             Span::styled("═".repeat(40), styles::file_header_style(&app.theme)),
         ]));
         line_idx += 1;
@@ -1181,6 +1690,10 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
         if is_reviewed {
             continue;
         }
+        ctx.viewed = app
+            .dim_viewed
+            .then(|| app.session.files.get(path))
+            .flatten();
 
         // Check if we're editing/adding a file-level comment for this file
         let is_file_comment_mode = app.input_mode == InputMode::Comment
@@ -1199,10 +1712,9 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                     let (input_lines, cursor_info) = comment_panel::format_comment_input_lines(
                         &app.theme,
                         app.comment_type,
-                        &app.comment_buffer,
-                        app.comment_cursor,
+                        &app.comment_editor,
                         None,
-                        true,
+                        CommentInputKind::Edit,
                         app.supports_keyboard_enhancement,
                     );
                     comment_cursor_logical_line = Some(line_idx + cursor_info.line_offset);
@@ -1223,9 +1735,10 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                 } else {
                     let comment_lines = comment_panel::format_comment_lines(
                         &app.theme,
-                        comment.comment_type,
-                        &comment.content,
+                        comment,
                         None,
+                        app.session.has_published(),
+                        app.screen_reader,
                     );
                     for mut comment_line in comment_lines {
                         let indicator = cursor_indicator(line_idx, ctx.current_line_idx);
@@ -1248,10 +1761,9 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             let (input_lines, cursor_info) = comment_panel::format_comment_input_lines(
                 &app.theme,
                 app.comment_type,
-                &app.comment_buffer,
-                app.comment_cursor,
+                &app.comment_editor,
                 None,
-                false,
+                app.comment_input_kind(),
                 app.supports_keyboard_enhancement,
             );
             comment_cursor_logical_line = Some(line_idx + cursor_info.line_offset);
@@ -1272,7 +1784,10 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             let indicator = cursor_indicator_spaced(line_idx, ctx.current_line_idx);
             lines.push(Line::from(vec![
                 Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
-                Span::styled("(binary file)", styles::dim_style(&app.theme)),
+                Span::styled(
+                    file.binary_sizes.placeholder(),
+                    styles::dim_style(&app.theme),
+                ),
             ]));
             line_idx += 1;
         } else if file.hunks.is_empty() {
@@ -1311,37 +1826,42 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
                         if let Some(expanded_lines) = app.expanded_content.get(&gap_id) {
                             for expanded_line in expanded_lines {
                                 let indicator = cursor_indicator(line_idx, ctx.current_line_idx);
-                                let line_num = expanded_line
-                                    .new_lineno
-                                    .map(|n| format!("{n:>4} "))
-                                    .unwrap_or_else(|| "     ".to_string());
+                                let line_num = format!(
+                                    "{} ",
+                                    line_number_label(
+                                        app,
+                                        expanded_line.new_lineno,
+                                        line_idx,
+                                        ctx.current_line_idx
+                                    )
+                                );
 
                                 // In side-by-side, show context on both sides
-                                let line_spans = vec![
+                                let expanded_style = styles::expanded_context_style(&app.theme);
+                                let content = truncate_or_pad_spans(
+                                    &plain_content_spans(
+                                        &expanded_line.content,
+                                        expanded_style,
+                                        ctx.decorations,
+                                    ),
+                                    ctx.content_width,
+                                    expanded_style,
+                                );
+                                let mut line_spans = vec![
                                     Span::styled(
                                         indicator,
                                         styles::current_line_indicator_style(&app.theme),
                                     ),
-                                    Span::styled(
-                                        line_num.clone(),
-                                        styles::expanded_context_style(&app.theme),
-                                    ),
-                                    Span::styled("  ", styles::expanded_context_style(&app.theme)),
-                                    Span::styled(
-                                        truncate_or_pad(&expanded_line.content, ctx.content_width),
-                                        styles::expanded_context_style(&app.theme),
-                                    ),
-                                    Span::styled(" │ ", styles::dim_style(&app.theme)),
-                                    Span::styled(
-                                        line_num,
-                                        styles::expanded_context_style(&app.theme),
-                                    ),
-                                    Span::styled("  ", styles::expanded_context_style(&app.theme)),
-                                    Span::styled(
-                                        truncate_or_pad(&expanded_line.content, ctx.content_width),
-                                        styles::expanded_context_style(&app.theme),
-                                    ),
+                                    Span::styled(line_num.clone(), expanded_style),
+                                    Span::styled("  ", expanded_style),
                                 ];
+                                line_spans.extend(content.clone());
+                                line_spans.extend([
+                                    Span::styled(" │ ", styles::dim_style(&app.theme)),
+                                    Span::styled(line_num, expanded_style),
+                                    Span::styled("  ", expanded_style),
+                                ]);
+                                line_spans.extend(content);
                                 lines.push(Line::from(line_spans));
                                 line_idx += 1;
                             }
@@ -1365,16 +1885,12 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
 
                 // Hunk header
                 let indicator = cursor_indicator_spaced(line_idx, ctx.current_line_idx);
-                lines.push(Line::from(vec![
-                    Span::styled(indicator, styles::current_line_indicator_style(&app.theme)),
-                    Span::styled(
-                        hunk.header.to_string(),
-                        styles::diff_hunk_header_style(&app.theme),
-                    ),
-                ]));
+                lines.push(hunk_header_line(app, file, hunk_idx, indicator));
                 line_idx += 1;
 
                 // Process diff lines in side-by-side format
+                ctx.hunk_viewed =
+                    app.dim_viewed && app.session.is_hunk_reviewed(path, &hunk.fingerprint());
                 let (new_line_idx, cursor_info) = render_hunk_lines_side_by_side(
                     &hunk.lines,
                     &line_comments,
@@ -1424,7 +1940,8 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
     // Calculate how many logical lines actually fit in the viewport when wrapped
     let viewport_width = inner.width as usize;
     let viewport_height = inner.height as usize;
-    app.diff_state.visible_line_count = if app.diff_state.wrap_lines && viewport_width > 0 {
+    let wrap_lines = app.wrap_enabled();
+    app.diff_state.visible_line_count = if wrap_lines && viewport_width > 0 {
         let mut visual_rows_used = 0;
         let mut logical_lines_visible = 0;
         for &width in &line_widths {
@@ -1449,12 +1966,12 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
     if app.diff_state.scroll_x > max_scroll_x {
         app.diff_state.scroll_x = max_scroll_x;
     }
-    if app.diff_state.wrap_lines {
+    if wrap_lines {
         app.diff_state.scroll_x = 0;
     }
 
     let scroll_x = app.diff_state.scroll_x;
-    let visible_lines: Vec<Line> = if app.diff_state.wrap_lines {
+    let visible_lines: Vec<Line> = if wrap_lines {
         visible_lines_unscrolled
     } else {
         visible_lines_unscrolled
@@ -1463,12 +1980,31 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             .collect()
     };
 
+    let mut visible_lines = visible_lines;
+    if app.cursor_line_highlight {
+        highlight_cursor_line(app, &mut visible_lines, inner.width as usize);
+    }
+
     let mut diff = Paragraph::new(visible_lines);
-    if app.diff_state.wrap_lines {
+    if wrap_lines {
         diff = diff.wrap(Wrap { trim: false });
     }
     frame.render_widget(diff, inner);
 
+    if app.cursor_column_highlight
+        && let Some((col, origin)) = app.cursor_indent_column()
+    {
+        // Deleted lines are on the left, added lines on the right
+        let left = 7;
+        let right = left + content_width + 9;
+        let offsets: &[usize] = match origin {
+            LineOrigin::Deletion => &[left],
+            LineOrigin::Addition => &[right],
+            LineOrigin::Context => &[left, right],
+        };
+        highlight_cursor_column(frame, app, inner, offsets, col);
+    }
+
     // Calculate screen position for comment cursor if in Comment mode
     if let Some(cursor_logical_line) = comment_cursor_logical_line {
         let scroll_offset = app.diff_state.scroll_offset;
@@ -1484,7 +2020,7 @@ fn render_side_by_side_diff(frame: &mut Frame, app: &mut App, area: Rect) {
             let mut visual_row: u16 = 0;
             let viewport_width = inner.width as usize;
 
-            if app.diff_state.wrap_lines && viewport_width > 0 {
+            if wrap_lines && viewport_width > 0 {
                 for i in 0..logical_offset {
                     if i < line_widths.len() {
                         let width = line_widths[i];
@@ -1584,11 +2120,7 @@ fn render_context_line_side_by_side(
     mut line_idx: usize,
     lines: &mut Vec<Line>,
 ) -> (usize, Option<(usize, u16)>) {
-    let line_num = diff_line
-        .old_lineno
-        .or(diff_line.new_lineno)
-        .map(|n| format!("{n:>4}"))
-        .unwrap_or_else(|| "    ".to_string());
+    let line_num = ctx.line_number_label(diff_line.old_lineno.or(diff_line.new_lineno), line_idx);
 
     let indicator = cursor_indicator(line_idx, ctx.current_line_idx);
 
@@ -1599,12 +2131,10 @@ fn render_context_line_side_by_side(
     ];
 
     // Left side content - use syntax highlighting if available
-    if let Some(ref highlighted) = diff_line.highlighted_spans {
-        let content_spans = truncate_or_pad_spans(
-            highlighted,
-            ctx.content_width,
-            styles::diff_context_style(ctx.theme),
-        );
+    let context_style = styles::diff_context_style(ctx.theme);
+    let highlighted = content_spans(diff_line, context_style, ctx.decorations);
+    if let Some(ref highlighted) = highlighted {
+        let content_spans = truncate_or_pad_spans(highlighted, ctx.content_width, context_style);
         spans.extend(content_spans);
     } else {
         let content = truncate_or_pad(&diff_line.content, ctx.content_width);
@@ -1623,19 +2153,17 @@ fn render_context_line_side_by_side(
     ));
 
     // Right side content - use same highlighting
-    if let Some(ref highlighted) = diff_line.highlighted_spans {
-        let content_spans = truncate_or_pad_spans(
-            highlighted,
-            ctx.content_width,
-            styles::diff_context_style(ctx.theme),
-        );
+    if let Some(ref highlighted) = highlighted {
+        let content_spans = truncate_or_pad_spans(highlighted, ctx.content_width, context_style);
         spans.extend(content_spans);
     } else {
         let content = truncate_or_pad(&diff_line.content, ctx.content_width);
         spans.push(Span::styled(content, styles::diff_context_style(ctx.theme)));
     }
 
-    lines.push(Line::from(spans));
+    let mut line = Line::from(spans);
+    ctx.dim_if_viewed(&mut line, &[diff_line]);
+    lines.push(line);
     line_idx += 1;
 
     // Add comments if any
@@ -1690,7 +2218,7 @@ fn render_deletion_addition_pair_side_by_side(
         // Left side (deletion)
         if offset < del_count {
             let del_line = &hunk_lines[start_idx + offset];
-            add_deletion_spans(ctx.theme, &mut spans, del_line, ctx.content_width);
+            add_deletion_spans(ctx, &mut spans, del_line, line_idx);
         } else {
             add_empty_column_spans(&mut spans, ctx.content_width);
         }
@@ -1700,12 +2228,20 @@ fn render_deletion_addition_pair_side_by_side(
         // Right side (addition)
         if offset < add_count {
             let add_line = &hunk_lines[add_start + offset];
-            add_addition_spans(ctx.theme, &mut spans, add_line, ctx.content_width);
+            add_addition_spans(ctx, &mut spans, add_line, line_idx);
         } else {
             add_empty_column_spans(&mut spans, ctx.content_width);
         }
 
-        lines.push(Line::from(spans));
+        let row: Vec<&DiffLine> = [start_idx + offset, add_start + offset]
+            .into_iter()
+            .zip([offset < del_count, offset < add_count])
+            .filter(|(_, present)| *present)
+            .map(|(idx, _)| &hunk_lines[idx])
+            .collect();
+        let mut line = Line::from(spans);
+        ctx.dim_if_viewed(&mut line, &row);
+        lines.push(line);
         line_idx += 1;
 
         // Add comments for deletion
@@ -1767,9 +2303,11 @@ fn render_standalone_addition_side_by_side(
     )];
     add_empty_column_spans(&mut spans, ctx.content_width);
     spans.push(Span::styled(" │ ", styles::dim_style(ctx.theme)));
-    add_addition_spans(ctx.theme, &mut spans, diff_line, ctx.content_width);
+    add_addition_spans(ctx, &mut spans, diff_line, line_idx);
 
-    lines.push(Line::from(spans));
+    let mut line = Line::from(spans);
+    ctx.dim_if_viewed(&mut line, &[diff_line]);
+    lines.push(line);
     line_idx += 1;
 
     // Add comments if any
@@ -1786,15 +2324,13 @@ fn render_standalone_addition_side_by_side(
 
 /// Add deletion line spans to the spans vector
 fn add_deletion_spans(
-    theme: &Theme,
+    ctx: &SideBySideContext,
     spans: &mut Vec<Span>,
-    diff_line: &crate::model::DiffLine,
-    content_width: usize,
+    diff_line: &DiffLine,
+    line_idx: usize,
 ) {
-    let line_num = diff_line
-        .old_lineno
-        .map(|n| format!("{n:>4}"))
-        .unwrap_or_else(|| "    ".to_string());
+    let (theme, content_width) = (ctx.theme, ctx.content_width);
+    let line_num = ctx.line_number_label(diff_line.old_lineno, line_idx);
 
     spans.push(Span::styled(
         format!("{line_num} "),
@@ -1803,10 +2339,13 @@ fn add_deletion_spans(
     spans.push(Span::styled("-".to_string(), styles::diff_del_style(theme)));
 
     // Use syntax highlighting if available
-    if let Some(ref highlighted) = diff_line.highlighted_spans {
-        let content_spans =
-            truncate_or_pad_spans(highlighted, content_width, styles::diff_del_style(theme));
-        spans.extend(content_spans);
+    let base_style = styles::diff_del_style(theme);
+    if let Some(highlighted) = content_spans(diff_line, base_style, ctx.decorations) {
+        spans.extend(truncate_or_pad_spans(
+            &highlighted,
+            content_width,
+            base_style,
+        ));
     } else {
         // Fall back to plain text
         let content = truncate_or_pad(&diff_line.content, content_width);
@@ -1816,15 +2355,13 @@ fn add_deletion_spans(
 
 /// Add addition line spans to the spans vector
 fn add_addition_spans(
-    theme: &Theme,
+    ctx: &SideBySideContext,
     spans: &mut Vec<Span>,
-    diff_line: &crate::model::DiffLine,
-    content_width: usize,
+    diff_line: &DiffLine,
+    line_idx: usize,
 ) {
-    let line_num = diff_line
-        .new_lineno
-        .map(|n| format!("{n:>4}"))
-        .unwrap_or_else(|| "    ".to_string());
+    let (theme, content_width) = (ctx.theme, ctx.content_width);
+    let line_num = ctx.line_number_label(diff_line.new_lineno, line_idx);
 
     spans.push(Span::styled(
         format!("{line_num} "),
@@ -1833,10 +2370,13 @@ fn add_addition_spans(
     spans.push(Span::styled("+".to_string(), styles::diff_add_style(theme)));
 
     // Use syntax highlighting if available
-    if let Some(ref highlighted) = diff_line.highlighted_spans {
-        let content_spans =
-            truncate_or_pad_spans(highlighted, content_width, styles::diff_add_style(theme));
-        spans.extend(content_spans);
+    let base_style = styles::diff_add_style(theme);
+    if let Some(highlighted) = content_spans(diff_line, base_style, ctx.decorations) {
+        spans.extend(truncate_or_pad_spans(
+            &highlighted,
+            content_width,
+            base_style,
+        ));
     } else {
         // Fall back to plain text
         let content = truncate_or_pad(&diff_line.content, content_width);
@@ -1885,10 +2425,9 @@ fn add_comments_to_line(
                     let (input_lines, cursor_info) = comment_panel::format_comment_input_lines(
                         ctx.theme,
                         ctx.comment_type,
-                        ctx.comment_buffer,
-                        ctx.comment_cursor,
+                        ctx.comment_editor,
                         line_range,
-                        true,
+                        CommentInputKind::Edit,
                         ctx.supports_keyboard_enhancement,
                     );
                     cursor_info_out =
@@ -1912,9 +2451,11 @@ fn add_comments_to_line(
                         .or_else(|| Some(LineRange::single(line_num)));
                     let comment_lines = comment_panel::format_comment_lines(
                         ctx.theme,
-                        comment.comment_type,
-                        &comment.content,
+                        comment,
                         line_range,
+                        ctx.show_drafts,
+                        // Screen reader mode keeps to the unified view
+                        false,
                     );
                     for mut comment_line in comment_lines {
                         let indicator = cursor_indicator(line_idx, ctx.current_line_idx);
@@ -1941,10 +2482,9 @@ fn add_comments_to_line(
         let (input_lines, cursor_info) = comment_panel::format_comment_input_lines(
             ctx.theme,
             ctx.comment_type,
-            ctx.comment_buffer,
-            ctx.comment_cursor,
+            ctx.comment_editor,
             line_range,
-            false,
+            ctx.comment_input_kind,
             ctx.supports_keyboard_enhancement,
         );
         cursor_info_out = Some((line_idx + cursor_info.line_offset, 1 + cursor_info.column));
@@ -1973,6 +2513,157 @@ fn truncate_or_pad(s: &str, width: usize) -> String {
     }
 }
 
+/// Decorations drawn over the content of code lines
+#[derive(Clone, Copy)]
+struct ContentDecorations<'a> {
+    /// Longest line that gets intraline emphasis, 0 when it is off
+    intraline_max_len: usize,
+    /// Columns between indent guides, 0 when they are off
+    indent_width: usize,
+    guide_style: Style,
+    /// Patched over TODO/FIXME/XXX markers on added lines
+    marker_style: Style,
+    /// Search whose matches are highlighted
+    search: Option<&'a SearchPattern>,
+    search_style: Style,
+}
+
+impl<'a> ContentDecorations<'a> {
+    fn new(app: &'a App) -> Self {
+        Self {
+            intraline_max_len: app.intraline_max_len(),
+            indent_width: app.indent_guide_width(),
+            guide_style: styles::dim_style(&app.theme),
+            marker_style: styles::todo_marker_style(&app.theme),
+            search: app.highlighted_search(),
+            search_style: styles::search_match_style(&app.theme),
+        }
+    }
+}
+
+/// Content spans of a code line: its syntax highlighting, with intraline
+/// changes emphasized, new TODO markers and search matches picked out and
+/// indent guides drawn as `decorations` ask. None when there is nothing to add to `base_style`.
+fn content_spans(
+    diff_line: &DiffLine,
+    base_style: Style,
+    decorations: ContentDecorations<'_>,
+) -> Option<Vec<(Style, String)>> {
+    let mut spans = intraline_spans(diff_line, base_style, decorations.intraline_max_len);
+    if diff_line.origin == LineOrigin::Addition {
+        let markers = marker_ranges(&diff_line.content);
+        if !markers.is_empty() {
+            let highlighted =
+                spans.unwrap_or_else(|| vec![(base_style, diff_line.content.clone())]);
+            spans = Some(restyle_ranges(
+                highlighted,
+                &markers,
+                diff_line.content.len(),
+                |style| style.patch(decorations.marker_style),
+            ));
+        }
+    }
+    if let Some(search) = decorations.search {
+        let matches = search.find_ranges(&diff_line.content);
+        if !matches.is_empty() {
+            let highlighted =
+                spans.unwrap_or_else(|| vec![(base_style, diff_line.content.clone())]);
+            spans = Some(restyle_ranges(
+                highlighted,
+                &matches,
+                diff_line.content.len(),
+                |style| style.patch(decorations.search_style),
+            ));
+        }
+    }
+    if decorations.indent_width == 0 || !diff_line.content.starts_with([' ', '\t']) {
+        return spans;
+    }
+    let spans = spans.unwrap_or_else(|| vec![(base_style, diff_line.content.clone())]);
+    Some(add_indent_guides(
+        spans,
+        decorations.indent_width,
+        decorations.guide_style,
+    ))
+}
+
+/// Text in a single style, with search matches picked out and indent
+/// guides when they are on
+fn plain_content_spans(
+    text: &str,
+    style: Style,
+    decorations: ContentDecorations<'_>,
+) -> Vec<(Style, String)> {
+    let mut spans = vec![(style, text.to_string())];
+    if let Some(search) = decorations.search {
+        spans = restyle_ranges(spans, &search.find_ranges(text), text.len(), |style| {
+            style.patch(decorations.search_style)
+        });
+    }
+    add_indent_guides(spans, decorations.indent_width, decorations.guide_style)
+}
+
+/// Syntax highlighting of a changed line, with the intraline changes
+/// emphasized when the line is at most `intraline_max_len` characters long
+fn intraline_spans(
+    diff_line: &DiffLine,
+    base_style: Style,
+    intraline_max_len: usize,
+) -> Option<Vec<(Style, String)>> {
+    let emphasize = !diff_line.changed_ranges.is_empty()
+        && diff_line.content.chars().count() <= intraline_max_len;
+    if !emphasize {
+        return diff_line.highlighted_spans.clone();
+    }
+    let spans = diff_line
+        .highlighted_spans
+        .clone()
+        .unwrap_or_else(|| vec![(base_style, diff_line.content.clone())]);
+    Some(restyle_ranges(
+        spans,
+        &diff_line.changed_ranges,
+        diff_line.content.len(),
+        styles::intraline_change_style,
+    ))
+}
+
+/// Restyle the parts of `spans` that fall within `ranges`, byte ranges of
+/// the line content. Spans that do not spell out the `content_len` bytes of
+/// the content exactly (e.g. elided minified lines) are left alone.
+fn restyle_ranges(
+    spans: Vec<(Style, String)>,
+    ranges: &[std::ops::Range<usize>],
+    content_len: usize,
+    restyle: impl Fn(Style) -> Style,
+) -> Vec<(Style, String)> {
+    if spans.iter().map(|(_, text)| text.len()).sum::<usize>() != content_len {
+        return spans;
+    }
+
+    let mut result = Vec::new();
+    let mut pos = 0;
+    for (style, text) in spans {
+        let end = pos + text.len();
+        let mut cuts: Vec<usize> = ranges
+            .iter()
+            .flat_map(|r| [r.start, r.end])
+            .filter(|&cut| cut > pos && cut < end)
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        cuts.push(end);
+        let mut start = pos;
+        for cut in cuts {
+            let in_range = ranges.iter().any(|r| r.contains(&start));
+            let piece_style = if in_range { restyle(style) } else { style };
+            result.push((piece_style, text[start - pos..cut - pos].to_string()));
+            start = cut;
+        }
+        pos = end;
+    }
+    result
+}
+
 /// Truncate or pad highlighted spans to a specific display width
 /// Uses unicode width to properly handle wide characters (CJK, emoji, etc.)
 /// Returns a vector of spans that fits exactly within the width
@@ -2041,11 +2732,147 @@ fn truncate_or_pad_spans(
 }
 
 /// Apply horizontal scroll to a line while preserving the first span (cursor indicator)
+/// Gutter line number of a diff line, four columns wide
+fn line_number_label(
+    app: &App,
+    lineno: Option<u32>,
+    line_idx: usize,
+    current_line_idx: usize,
+) -> String {
+    format_line_number(
+        lineno,
+        line_idx,
+        current_line_idx,
+        app.relative_line_numbers,
+    )
+}
+
+/// With relative numbers (`:set rnu`) every line but the cursor line shows
+/// its distance to the cursor, in `j`/`k` presses
+fn format_line_number(
+    lineno: Option<u32>,
+    line_idx: usize,
+    current_line_idx: usize,
+    relative: bool,
+) -> String {
+    match lineno {
+        Some(_) if relative && line_idx != current_line_idx => {
+            format!("{:>4}", line_idx.abs_diff(current_line_idx))
+        }
+        Some(n) => format!("{n:>4}"),
+        None => "    ".to_string(),
+    }
+}
+
+/// Blame gutter for a unified diff line, blank for lines without blame
+/// (deleted lines, files that could not be blamed). None when it is off.
+fn blame_gutter_span(
+    app: &App,
+    path: &std::path::Path,
+    new_lineno: Option<u32>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<Span<'static>> {
+    if !app.blame_gutter {
+        return None;
+    }
+    let text = new_lineno
+        .and_then(|lineno| app.blame_line(path, lineno))
+        .map(|blame| blame_gutter_text(blame, now))
+        .unwrap_or_else(blank_blame_gutter);
+    Some(Span::styled(text, styles::dim_style(&app.theme)))
+}
+
+/// Dim the rows of diff lines the cursor passed over and of hunks marked
+/// reviewed (`:set dimviewed`)
+fn dim_viewed_lines(app: &App, visible_lines: &mut [Line]) {
+    let mut reviewed_hunks = std::collections::HashMap::new();
+    for (row, line) in visible_lines.iter_mut().enumerate() {
+        let Some(AnnotatedLine::DiffLine {
+            file_idx,
+            hunk_idx,
+            old_lineno,
+            new_lineno,
+            ..
+        }) = app.line_annotations.get(app.diff_state.scroll_offset + row)
+        else {
+            continue;
+        };
+        let Some(file) = app.diff_files.get(*file_idx) else {
+            continue;
+        };
+        let path = file.display_path();
+        let hunk_reviewed = *reviewed_hunks
+            .entry((*file_idx, *hunk_idx))
+            .or_insert_with(|| {
+                file.hunks
+                    .get(*hunk_idx)
+                    .is_some_and(|hunk| app.session.is_hunk_reviewed(path, &hunk.fingerprint()))
+            });
+        let viewed = hunk_reviewed
+            || app
+                .session
+                .files
+                .get(path)
+                .is_some_and(|review| review.is_line_viewed(*old_lineno, *new_lineno));
+        if viewed {
+            line.style = line.style.patch(styles::viewed_line_style());
+        }
+    }
+}
+
+/// Give the cursor line the cursor line background, padded so that it
+/// spans the full width of the diff (`:set cursorline`)
+fn highlight_cursor_line(app: &App, visible_lines: &mut [Line], width: usize) {
+    let Some(line) = app
+        .diff_state
+        .cursor_line
+        .checked_sub(app.diff_state.scroll_offset)
+        .and_then(|row| visible_lines.get_mut(row))
+    else {
+        return;
+    };
+    let line_width = line.width();
+    let padding = if line_width == 0 {
+        width
+    } else {
+        (width - line_width % width.max(1)) % width.max(1)
+    };
+    line.push_span(Span::raw(" ".repeat(padding)));
+    line.style = line.style.patch(styles::cursor_line_style(&app.theme));
+}
+
+/// Highlight `col` of the code that starts `offsets` cells into each row of
+/// the diff, on every row (`:set cursorcolumn`)
+fn highlight_cursor_column(
+    frame: &mut Frame,
+    app: &App,
+    inner: Rect,
+    offsets: &[usize],
+    col: usize,
+) {
+    for offset in offsets {
+        // The cursor indicator stays put while the rest scrolls horizontally
+        let Some(x) = (offset - 1 + col)
+            .checked_sub(app.diff_state.scroll_x)
+            .map(|x| x + 1)
+            .filter(|&x| x < inner.width as usize)
+        else {
+            continue;
+        };
+        for y in inner.top()..inner.bottom() {
+            if let Some(cell) = frame.buffer_mut().cell_mut((inner.x + x as u16, y)) {
+                cell.set_style(styles::cursor_column_style(&app.theme));
+            }
+        }
+    }
+}
+
 fn apply_horizontal_scroll(line: Line, scroll_x: usize) -> Line {
     if scroll_x == 0 || line.spans.is_empty() {
         return line;
     }
 
+    let line_style = line.style;
     let mut spans: Vec<Span> = line.spans.into_iter().collect();
 
     // Preserve the first span (indicator)
@@ -2072,5 +2899,5 @@ fn apply_horizontal_scroll(line: Line, scroll_x: usize) -> Line {
         }
     }
 
-    Line::from(new_spans)
+    Line::from(new_spans).style(line_style)
 }
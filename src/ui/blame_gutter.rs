@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+
+use crate::vcs::BlameLine;
+
+const ID_WIDTH: usize = 8;
+const AUTHOR_WIDTH: usize = 12;
+const AGE_WIDTH: usize = 4;
+
+/// Columns taken by the blame gutter, including its trailing space
+pub const BLAME_GUTTER_WIDTH: usize = ID_WIDTH + 1 + AUTHOR_WIDTH + 1 + AGE_WIDTH + 1;
+
+/// Blame gutter text for a line: short hash, author and age. `None` is a
+/// line that is not committed yet.
+pub fn blame_gutter_text(blame: Option<&BlameLine>, now: DateTime<Utc>) -> String {
+    match blame {
+        Some(blame) => format!(
+            "{:<ID_WIDTH$} {:<AUTHOR_WIDTH$} {:>AGE_WIDTH$} ",
+            truncate(&blame.short_id, ID_WIDTH),
+            truncate(&blame.author, AUTHOR_WIDTH),
+            format_age(now, blame.time),
+        ),
+        None => format!(
+            "{:<ID_WIDTH$} {:<AUTHOR_WIDTH$} {:>AGE_WIDTH$} ",
+            "", "uncommitted", ""
+        ),
+    }
+}
+
+/// Blank gutter for lines without blame (deleted lines, unsupported VCS)
+pub fn blank_blame_gutter() -> String {
+    " ".repeat(BLAME_GUTTER_WIDTH)
+}
+
+/// Compact age like `5m`, `3h`, `2d`, `6w`, `4mo` or `2y`
+fn format_age(now: DateTime<Utc>, time: DateTime<Utc>) -> String {
+    let age = now.signed_duration_since(time);
+    let days = age.num_days();
+    if age.num_hours() < 1 {
+        format!("{}m", age.num_minutes().max(0))
+    } else if days < 1 {
+        format!("{}h", age.num_hours())
+    } else if days < 14 {
+        format!("{days}d")
+    } else if days < 60 {
+        format!("{}w", days / 7)
+    } else if days < 365 {
+        format!("{}mo", days / 30)
+    } else {
+        format!("{}y", days / 365)
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(width - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn should_format_hash_author_and_age() {
+        // given
+        let now = Utc::now();
+        let blame = BlameLine {
+            short_id: "1a2b3c4".to_string(),
+            author: "Augusta Ada King".to_string(),
+            time: now - Duration::days(3),
+        };
+
+        // when
+        let text = blame_gutter_text(Some(&blame), now);
+
+        // then
+        assert_eq!(text, "1a2b3c4  Augusta Ada…   3d ");
+        assert_eq!(text.chars().count(), BLAME_GUTTER_WIDTH);
+        assert_eq!(
+            blame_gutter_text(None, now).chars().count(),
+            BLAME_GUTTER_WIDTH
+        );
+    }
+
+    #[test]
+    fn should_use_coarser_units_for_older_lines() {
+        let now = Utc::now();
+        assert_eq!(format_age(now, now - Duration::minutes(5)), "5m");
+        assert_eq!(format_age(now, now - Duration::hours(5)), "5h");
+        assert_eq!(format_age(now, now - Duration::days(30)), "4w");
+        assert_eq!(format_age(now, now - Duration::days(200)), "6mo");
+        assert_eq!(format_age(now, now - Duration::days(800)), "2y");
+    }
+}
@@ -3,13 +3,14 @@ use ratatui::{
     layout::{Constraint, Flex, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
-use unicode_width::UnicodeWidthStr;
 
-use crate::app::App;
-use crate::model::{CommentType, LineRange};
+use crate::app::{App, CommentInputKind, FocusedPanel};
+use crate::model::{Comment, CommentLine, CommentSort, CommentType, LineRange, find_issue_refs};
+use crate::text_edit::TextEditor;
 use crate::theme::Theme;
+use crate::ui::markdown::{MarkdownRenderer, MarkdownSpan};
 use crate::ui::styles;
 
 /// Information about where the cursor should be positioned within comment input
@@ -24,6 +25,9 @@ pub struct CommentCursorInfo {
     pub column: u16,
 }
 
+/// Display width of the border in front of each row of the comment input
+pub const COMMENT_INPUT_BORDER_WIDTH: usize = 7;
+
 /// Format a comment input as multiple lines with a box border for inline editing.
 /// This mimics the normal comment display but shows it's being edited.
 ///
@@ -32,10 +36,9 @@ pub struct CommentCursorInfo {
 pub fn format_comment_input_lines(
     theme: &Theme,
     comment_type: CommentType,
-    buffer: &str,
-    cursor_pos: usize,
+    editor: &TextEditor,
     line_range: Option<LineRange>,
-    is_editing: bool,
+    kind: CommentInputKind,
     supports_keyboard_enhancement: bool,
 ) -> (Vec<Line<'static>>, CommentCursorInfo) {
     let type_style = styles::comment_type_style(theme, comment_type);
@@ -44,24 +47,24 @@ pub fn format_comment_input_lines(
         .fg(theme.cursor_color)
         .add_modifier(Modifier::UNDERLINED);
 
-    let action = if is_editing { "Edit" } else { "Add" };
+    let action = kind.label();
     let line_info = match line_range {
         Some(range) if range.is_single() => format!("L{} ", range.start),
         Some(range) => format!("L{}-L{} ", range.start, range.end),
         None => String::new(),
     };
 
-    let newline_hint = if supports_keyboard_enhancement {
-        "Shift-Enter"
+    let save_hint = if supports_keyboard_enhancement {
+        "Ctrl-Enter"
     } else {
-        "Ctrl-J"
+        "Ctrl-S"
     };
 
     let mut result = Vec::new();
     // Track cursor position: line offset within result, column (display width)
     // Default to first content line (index 1) with cursor at start of content (after border)
     let border_prefix = "     │ ";
-    let border_width = border_prefix.width() as u16;
+    let border_width = COMMENT_INPUT_BORDER_WIDTH as u16;
     let mut cursor_line_offset: usize = 1; // First content line (after header)
     let mut cursor_column: u16 = border_width; // After the border prefix
 
@@ -72,13 +75,13 @@ pub fn format_comment_input_lines(
         Span::styled(format!("[{}] ", comment_type.as_str()), type_style),
         Span::styled(line_info, styles::dim_style(theme)),
         Span::styled(
-            format!("(Tab:type Enter:save {}:newline Esc:cancel)", newline_hint),
+            format!("(Tab:type Enter:newline {}:save Esc:cancel)", save_hint),
             styles::dim_style(theme),
         ),
     ]));
 
     // Content lines with cursor
-    if buffer.is_empty() {
+    if editor.is_empty() {
         // Show placeholder with cursor at start
         result.push(Line::from(vec![
             Span::styled(border_prefix, border_style),
@@ -88,31 +91,22 @@ pub fn format_comment_input_lines(
         // cursor_line_offset is already 1 (first content line)
         // cursor_column is already border_width (cursor at start of content)
     } else {
-        // Split buffer into lines and render with cursor
-        let buffer_lines: Vec<&str> = buffer.split('\n').collect();
-        let mut char_offset = 0;
-
-        for (line_idx, text) in buffer_lines.iter().enumerate() {
-            let line_start = char_offset;
-            let line_end = char_offset + text.len();
-
-            // Check if cursor is on this line
-            let cursor_on_this_line = cursor_pos >= line_start
-                && (cursor_pos <= line_end
-                    || (line_idx == buffer_lines.len() - 1 && cursor_pos == buffer.len()));
+        // One line per row of the editor, soft wrapped at its wrap width
+        let buffer = editor.text();
+        let (cursor_row, column) = editor.cursor_position();
 
+        for (row_idx, row) in editor.rows().iter().enumerate() {
+            let text = &buffer[row.start..row.end];
             let mut line_spans = vec![Span::styled(border_prefix, border_style)];
 
-            if cursor_on_this_line {
-                let cursor_pos_in_line = cursor_pos - line_start;
-                let cursor_pos_in_line = cursor_pos_in_line.min(text.len());
-                let (before_cursor, after_cursor) = text.split_at(cursor_pos_in_line);
+            if row_idx == cursor_row {
+                let (before_cursor, after_cursor) = text.split_at(editor.cursor() - row.start);
 
                 // Track cursor position for IME
-                // line_offset: header (1) + current content line index
-                cursor_line_offset = 1 + line_idx;
+                // line_offset: header (1) + current content row index
+                cursor_line_offset = 1 + row_idx;
                 // column: border width + display width of text before cursor
-                cursor_column = border_width + before_cursor.width() as u16;
+                cursor_column = border_width + column as u16;
 
                 if after_cursor.is_empty() {
                     line_spans.push(Span::raw(before_cursor.to_string()));
@@ -130,9 +124,6 @@ pub fn format_comment_input_lines(
             }
 
             result.push(Line::from(line_spans));
-
-            // Account for newline character (except for last line)
-            char_offset = line_end + 1;
         }
     }
 
@@ -150,50 +141,275 @@ pub fn format_comment_input_lines(
     (result, cursor_info)
 }
 
-/// Format a comment as multiple lines with a box border (themed version)
+/// Format a comment as multiple lines with a box border (themed version).
+/// `show_draft` flags comments whose current content has not been published.
+/// `textual` swaps the box drawing for words, for screen readers.
 pub fn format_comment_lines(
     theme: &Theme,
-    comment_type: CommentType,
-    content: &str,
+    comment: &Comment,
     line_range: Option<LineRange>,
+    show_draft: bool,
+    textual: bool,
 ) -> Vec<Line<'static>> {
+    let comment_type = comment.comment_type;
     let type_style = styles::comment_type_style(theme, comment_type);
     let border_style = styles::comment_border_style(theme, comment_type);
+    let content_style = if comment.resolved {
+        styles::dim_style(theme)
+    } else {
+        Style::default()
+    };
 
     let line_info = match line_range {
         Some(range) if range.is_single() => format!("L{} ", range.start),
         Some(range) => format!("L{}-L{} ", range.start, range.end),
         None => String::new(),
     };
-    let content_lines: Vec<&str> = content.split('\n').collect();
+    let resolved_info = if comment.resolved { "resolved " } else { "" };
+    let orphaned_info = if comment.orphaned { "orphaned " } else { "" };
+    let assignee_info = match &comment.assignee {
+        Some(name) => format!("@{name} "),
+        None => String::new(),
+    };
+    let draft_info = if show_draft && comment.is_draft() {
+        "draft "
+    } else {
+        ""
+    };
 
+    let (top, side, reply_mark, bottom) = if textual {
+        (
+            "     comment ",
+            "       ",
+            "reply: ",
+            "     end of comment".to_string(),
+        )
+    } else {
+        (
+            "     ╭─ ",
+            "     │ ",
+            "↳ ",
+            "     ╰".to_string() + &"─".repeat(38),
+        )
+    };
     let mut result = Vec::new();
 
     // Top border with type label
     result.push(Line::from(vec![
-        Span::styled("     ╭─ ", border_style),
+        Span::styled(top, border_style),
         Span::styled(format!("[{}] ", comment_type.as_str()), type_style),
         Span::styled(line_info, styles::dim_style(theme)),
-        Span::styled("─".repeat(30), border_style),
+        Span::styled(resolved_info, styles::reviewed_style(theme)),
+        Span::styled(orphaned_info, styles::pending_style(theme)),
+        Span::styled(assignee_info, styles::hash_style(theme)),
+        Span::styled(draft_info, styles::pending_style(theme)),
+        Span::styled(
+            if textual {
+                String::new()
+            } else {
+                "─".repeat(30)
+            },
+            border_style,
+        ),
     ]));
 
-    // Content lines
-    for line in &content_lines {
-        result.push(Line::from(vec![
-            Span::styled("     │ ", border_style),
-            Span::raw(line.to_string()),
-        ]));
+    // Content lines as markdown, with a suggestion shown as a mini-diff
+    let code_style = styles::comment_code_style(theme).patch(content_style);
+    let mut markdown = MarkdownRenderer::default();
+    for line in comment.display_content() {
+        let mut spans = vec![Span::styled(side, border_style)];
+        match line {
+            CommentLine::Text(text) => {
+                result.push(Line::from(spans));
+                let rendered = markdown.line(text, content_style, code_style);
+                push_markdown(result.last_mut().unwrap(), rendered, theme);
+            }
+            CommentLine::Removed(text) => {
+                spans.push(Span::styled(
+                    format!("- {text}"),
+                    styles::diff_del_style(theme),
+                ));
+                result.push(Line::from(spans));
+            }
+            CommentLine::Added(text) => {
+                spans.push(Span::styled(
+                    format!("+ {text}"),
+                    styles::diff_add_style(theme),
+                ));
+                result.push(Line::from(spans));
+            }
+        }
+    }
+
+    // Replies, each prefixed with an arrow on its first line
+    for reply in &comment.replies {
+        let mut markdown = MarkdownRenderer::default();
+        for (idx, line) in reply.content.split('\n').enumerate() {
+            let prefix = if idx == 0 { reply_mark } else { "  " };
+            result.push(Line::from(vec![
+                Span::styled(side, border_style),
+                Span::styled(prefix, styles::dim_style(theme)),
+            ]));
+            let rendered = markdown.line(line, content_style, code_style);
+            push_markdown(result.last_mut().unwrap(), rendered, theme);
+        }
     }
 
     // Bottom border
-    result.push(Line::from(vec![Span::styled(
-        "     ╰".to_string() + &"─".repeat(38),
-        border_style,
-    )]));
+    result.push(Line::from(vec![Span::styled(bottom, border_style)]));
 
     result
 }
 
+/// Append rendered markdown to `line`, highlighting issue references outside
+/// of code
+fn push_markdown(line: &mut Line<'static>, spans: Vec<MarkdownSpan>, theme: &Theme) {
+    for span in spans {
+        if span.literal {
+            line.push_span(Span::styled(span.text, span.style));
+        } else {
+            push_with_issue_refs(line, &span.text, span.style, theme);
+        }
+    }
+}
+
+/// Append `text` to `line`, highlighting issue references such as `PROJ-123`
+fn push_with_issue_refs(line: &mut Line<'static>, text: &str, style: Style, theme: &Theme) {
+    let issue_style = styles::hash_style(theme).add_modifier(Modifier::UNDERLINED);
+    let mut last = 0;
+    for issue in find_issue_refs(text) {
+        line.push_span(Span::styled(text[last..issue.start].to_string(), style));
+        line.push_span(Span::styled(
+            text[issue.start..issue.end].to_string(),
+            issue_style,
+        ));
+        last = issue.end;
+    }
+    line.push_span(Span::styled(text[last..].to_string(), style));
+}
+
+/// Render the comments sidebar listing every comment in the review. In file
+/// order the comments are grouped under a header row per file.
+pub fn render_comments_panel(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    let focused = app.focused_panel == FocusedPanel::Comments;
+    let entries = app.comment_panel_entries();
+    let show_drafts = app.session.has_published();
+    let grouped = app.session.comment_sort == CommentSort::File;
+
+    let title = match &app.comments_assignee_filter {
+        Some(name) => format!(" Comments @{name} ({}) ", entries.len()),
+        None => format!(" Comments ({}) ", entries.len()),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(styles::border_style(theme, focused));
+
+    let comment_items = entries.iter().map(|entry| {
+        let comment = &entry.comment;
+        let location = match (grouped, entry.line) {
+            (true, Some(line)) => format!("L{line}"),
+            (true, None) => "file".to_string(),
+            (false, Some(line)) => format!("{}:{line}", entry.path.display()),
+            (false, None) => entry.path.display().to_string(),
+        };
+        let text_style = if comment.resolved {
+            styles::dim_style(theme)
+        } else {
+            Style::default()
+        };
+        let status = if comment.resolved { "✓ " } else { "" };
+        let draft = if show_drafts && comment.is_draft() {
+            "✎ "
+        } else {
+            ""
+        };
+        let orphaned = if comment.orphaned { "? " } else { "" };
+        let replies = if comment.replies.is_empty() {
+            String::new()
+        } else {
+            format!(" (+{})", comment.replies.len())
+        };
+        let preview = comment.content.lines().next().unwrap_or("");
+        let preview = MarkdownRenderer::default()
+            .line(preview, text_style, styles::comment_code_style(theme))
+            .into_iter()
+            .map(|span| Span::styled(span.text, span.style));
+        ListItem::new(vec![
+            Line::from(vec![
+                Span::styled(status, styles::reviewed_style(theme)),
+                Span::styled(draft, styles::pending_style(theme)),
+                Span::styled(orphaned, styles::pending_style(theme)),
+                Span::styled(
+                    format!("[{}] ", comment.comment_type.as_str()),
+                    styles::comment_type_style(theme, comment.comment_type),
+                ),
+                Span::styled(location, styles::dim_style(theme)),
+                Span::styled(
+                    comment
+                        .assignee
+                        .as_ref()
+                        .map(|name| format!(" @{name}"))
+                        .unwrap_or_default(),
+                    styles::hash_style(theme),
+                ),
+            ]),
+            Line::from(
+                std::iter::once(Span::styled("  ", text_style))
+                    .chain(preview)
+                    .chain(std::iter::once(Span::styled(
+                        replies,
+                        styles::dim_style(theme),
+                    )))
+                    .collect::<Vec<_>>(),
+            ),
+        ])
+    });
+
+    let selected = app.comments_panel_state.selected();
+    let mut selected_row = 0;
+    // First row to keep in view for the selection: its file header, if any
+    let mut selected_top = 0;
+    let mut items = Vec::new();
+    for (idx, (entry, item)) in entries.iter().zip(comment_items).enumerate() {
+        let starts_group = idx == 0 || entries[idx - 1].path != entry.path;
+        if grouped && starts_group {
+            let count = entries[idx..]
+                .iter()
+                .take_while(|other| other.path == entry.path)
+                .count();
+            items.push(ListItem::new(Line::from(vec![
+                Span::styled(
+                    entry.path.display().to_string(),
+                    styles::file_header_style(theme),
+                ),
+                Span::styled(format!(" ({count})"), styles::dim_style(theme)),
+            ])));
+        }
+        if idx == selected {
+            selected_top = if grouped && starts_group {
+                items.len() - 1
+            } else {
+                items.len()
+            };
+            selected_row = items.len();
+        }
+        items.push(item);
+    }
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(styles::selected_style(theme));
+
+    let list_state = &mut app.comments_panel_state.list_state;
+    list_state.select(Some(selected_row));
+    if list_state.offset() > selected_top {
+        *list_state.offset_mut() = selected_top;
+    }
+    frame.render_stateful_widget(list, area, list_state);
+}
+
 pub fn render_confirm_dialog(frame: &mut Frame, app: &App, message: &str) {
     let theme = &app.theme;
     let area = centered_rect(50, 20, frame.area());
@@ -208,19 +424,24 @@ pub fn render_confirm_dialog(frame: &mut Frame, app: &App, message: &str) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    let mut choices = vec![Span::raw("  ")];
+    for (key, label) in app.confirm_choices() {
+        choices.push(Span::styled(
+            *key,
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        choices.push(Span::raw(*label));
+    }
     let lines = vec![
         Line::from(""),
         Line::from(Span::raw(message)),
         Line::from(""),
-        Line::from(vec![
-            Span::styled("  [Y]", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw("es    "),
-            Span::styled("[N]", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw("o"),
-        ]),
+        Line::from(choices),
     ];
 
-    let paragraph = Paragraph::new(lines).alignment(ratatui::layout::Alignment::Center);
+    let paragraph = Paragraph::new(lines)
+        .alignment(ratatui::layout::Alignment::Center)
+        .wrap(Wrap { trim: true });
     frame.render_widget(paragraph, inner);
 }
 
@@ -241,14 +462,27 @@ mod tests {
         Theme::default()
     }
 
+    fn editor(text: &str, cursor: usize) -> TextEditor {
+        let mut editor = TextEditor::default();
+        editor.set_text(text);
+        editor.set_cursor(cursor);
+        editor
+    }
+
     #[test]
     fn should_return_cursor_at_start_for_empty_buffer() {
         // given
         let theme = test_theme();
 
         // when
-        let (lines, cursor_info) =
-            format_comment_input_lines(&theme, CommentType::Note, "", 0, None, false, false);
+        let (lines, cursor_info) = format_comment_input_lines(
+            &theme,
+            CommentType::Note,
+            &TextEditor::default(),
+            None,
+            CommentInputKind::Add,
+            false,
+        );
 
         // then
         assert_eq!(lines.len(), 3); // header + content + footer
@@ -267,10 +501,9 @@ mod tests {
         let (_, cursor_info) = format_comment_input_lines(
             &theme,
             CommentType::Note,
-            buffer,
-            cursor_pos,
+            &editor(buffer, cursor_pos),
             None,
-            false,
+            CommentInputKind::Add,
             false,
         );
 
@@ -290,10 +523,9 @@ mod tests {
         let (_, cursor_info) = format_comment_input_lines(
             &theme,
             CommentType::Note,
-            buffer,
-            cursor_pos,
+            &editor(buffer, cursor_pos),
             None,
-            false,
+            CommentInputKind::Add,
             false,
         );
 
@@ -314,10 +546,9 @@ mod tests {
         let (_, cursor_info) = format_comment_input_lines(
             &theme,
             CommentType::Note,
-            buffer,
-            cursor_pos,
+            &editor(buffer, cursor_pos),
             None,
-            false,
+            CommentInputKind::Add,
             false,
         );
 
@@ -337,10 +568,9 @@ mod tests {
         let (lines, cursor_info) = format_comment_input_lines(
             &theme,
             CommentType::Note,
-            buffer,
-            cursor_pos,
+            &editor(buffer, cursor_pos),
             None,
-            false,
+            CommentInputKind::Add,
             false,
         );
 
@@ -361,10 +591,9 @@ mod tests {
         let (_, cursor_info) = format_comment_input_lines(
             &theme,
             CommentType::Note,
-            buffer,
-            cursor_pos,
+            &editor(buffer, cursor_pos),
             None,
-            false,
+            CommentInputKind::Add,
             false,
         );
 
@@ -373,4 +602,22 @@ mod tests {
         // "a" = 1 display width, "좋" = 2 display width, total = 3
         assert_eq!(cursor_info.column, 7 + 3);
     }
+
+    #[test]
+    fn should_spell_out_comment_boxes_with_the_same_rows() {
+        // given
+        let theme = test_theme();
+        let comment = Comment::new("Check bounds".to_string(), CommentType::Issue, None);
+
+        // when
+        let boxed = format_comment_lines(&theme, &comment, None, false, false);
+        let textual = format_comment_lines(&theme, &comment, None, false, true);
+
+        // then
+        let text: Vec<String> = textual.iter().map(|line| line.to_string()).collect();
+        assert_eq!(textual.len(), boxed.len());
+        assert_eq!(text[0].trim_end(), "     comment [ISSUE]");
+        assert_eq!(text[1], "       Check bounds");
+        assert_eq!(text[2], "     end of comment");
+    }
 }
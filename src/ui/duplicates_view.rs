@@ -0,0 +1,34 @@
+use ratatui::text::{Line, Span};
+
+use crate::model::duplicates::{BlockLocation, Duplication};
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render possible duplications, each as the two locations it links and a
+/// preview of the first shared line
+pub fn duplicate_lines(theme: &Theme, duplications: &[Duplication]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for (idx, duplication) in duplications.iter().enumerate() {
+        if idx > 0 {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(vec![
+            Span::styled(location(&duplication.first), styles::header_style(theme)),
+            Span::styled("  ≈  ", styles::dim_style(theme)),
+            Span::styled(location(&duplication.second), styles::header_style(theme)),
+            Span::styled(
+                format!("  ({} lines)", duplication.lines),
+                styles::dim_style(theme),
+            ),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("    {}", duplication.preview),
+            styles::diff_context_style(theme),
+        )));
+    }
+    lines
+}
+
+fn location(block: &BlockLocation) -> String {
+    format!("{}:{}-{}", block.path.display(), block.start, block.end)
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::App;
+use crate::model::CommentType;
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render the popup of a failed operation: what failed, the error and its
+/// causes, and the keys to retry, open the error log or continue
+pub fn render_error_popup(frame: &mut Frame, app: &App) {
+    let Some(report) = &app.error_report else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = centered_rect(60, 40, frame.area());
+
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(" {} ", report.context))
+        .borders(Borders::ALL)
+        .border_style(styles::comment_border_style(theme, CommentType::Issue));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let mut lines = vec![Line::from("")];
+    for (idx, error) in report.chain.iter().enumerate() {
+        for (row, text) in error.lines().enumerate() {
+            let prefix = match (idx, row) {
+                (0, _) | (_, 1..) => "  ",
+                _ => "  caused by: ",
+            };
+            let style = if idx == 0 {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(prefix, styles::dim_style(theme)),
+                Span::styled(text.to_string(), style),
+            ]));
+        }
+    }
+    lines.push(Line::from(""));
+
+    let key = |key: &'static str| Span::styled(key, Style::default().add_modifier(Modifier::BOLD));
+    let mut choices = vec![Span::raw("  ")];
+    if report.retry.is_some() {
+        choices.extend([key("[R]"), Span::raw("etry    ")]);
+    }
+    choices.extend([
+        key("[L]"),
+        Span::raw("og    "),
+        key("[Enter]"),
+        Span::raw(" continue"),
+    ]);
+    lines.push(Line::from(choices));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// Render the errors of this run, newest first, each with its time
+pub fn error_log_lines(theme: &Theme, log: &[(DateTime<Local>, String)]) -> Vec<Line<'static>> {
+    log.iter()
+        .rev()
+        .map(|(at, error)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{}  ", at.format("%H:%M:%S")),
+                    styles::dim_style(theme),
+                ),
+                Span::raw(error.clone()),
+            ])
+        })
+        .collect()
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
@@ -72,12 +72,19 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Jump to prev/next hunk"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  %         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Jump to matching bracket"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  /         ",
                 Style::default().add_modifier(Modifier::BOLD),
             ),
-            Span::raw("Search within diff"),
+            Span::raw("Search within diff (regex, smart-case)"),
         ]),
         Line::from(vec![
             Span::styled(
@@ -114,6 +121,34 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Toggle file list visibility"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  ;c        ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Show/focus comments panel"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  ;s        ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Reselect commits (keeps checkboxes)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  ;w        ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Cycle ignored whitespace (:set iw)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    ;p         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Review progress and time per file (:stats)"),
+        ]),
         Line::from(""),
         Line::from(Span::styled(
             "File Tree",
@@ -148,6 +183,13 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Collapse all directories"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  1-7       ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Filter files: All/Unrev/Cmnt/Add/Del/Mine/Filt"),
+        ]),
         Line::from(""),
         Line::from(Span::styled(
             "Review Actions",
@@ -161,6 +203,48 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Toggle file reviewed"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  a         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Mark reviewed, jump to next unreviewed"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    m          ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle hunk reviewed (file follows its hunks)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  A         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Reply to the comment at cursor"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  p         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Pin/unpin file to top of file list"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  x         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Skip file (excluded from progress)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  s         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Stage hunk (file in file list) for commit"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  c         ",
@@ -189,6 +273,20 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Delete comment at cursor"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  u         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Undo comment/reviewed change"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Ctrl-R    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Redo"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  y         ",
@@ -196,6 +294,13 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Yank (copy) review to clipboard"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  Y         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Copy visible diff lines as plain text"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  v/V       ",
@@ -223,6 +328,34 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Create comment for selected range"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  s         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Suggest a replacement for the lines"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  S         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Stage the selected lines for commit"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  y         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Copy selected lines as plain text"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  r/x/p/f   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("In the file list: mark reviewed/skipped/pinned/filtered"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  Esc/v/V   ",
@@ -231,6 +364,54 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             Span::raw("Cancel visual selection"),
         ]),
         Line::from(""),
+        Line::from(Span::styled(
+            "Comments Panel",
+            Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(
+                "  j/k       ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Select comment"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Enter     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Jump to comment in diff"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  r/c       ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Reply to comment"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  R         ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Resolve/reopen comment"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  dd        ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Delete comment"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Esc       ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Return focus to diff"),
+        ]),
+        Line::from(""),
         Line::from(Span::styled(
             "Comment Mode",
             Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
@@ -248,14 +429,35 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
                 "  Ctrl-S    ",
                 Style::default().add_modifier(Modifier::BOLD),
             ),
-            Span::raw("Save comment"),
+            Span::raw("Save comment (also Ctrl-Enter)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Enter     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Insert newline"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Arrows    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Move cursor, up/down by wrapped row"),
         ]),
         Line::from(vec![
             Span::styled(
-                "  Ctrl-A/E  ",
+                "  Home/End  ",
                 Style::default().add_modifier(Modifier::BOLD),
             ),
-            Span::raw("Line start/end"),
+            Span::raw("Line start/end (also Ctrl-A)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Ctrl-E    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Edit in $EDITOR"),
         ]),
         Line::from(vec![
             Span::styled(
@@ -298,6 +500,13 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Reload diff files"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  :edit     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Edit comment at cursor in $EDITOR"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  :clip     ",
@@ -305,6 +514,62 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Copy review to clipboard"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  :export F ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Copy review as md, json, phab, gerrit, sarif or rdjson"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :export F PATH ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Write the export to a file"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :export bitbucket URL ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Post comments to a Bitbucket PR"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    :export github URL  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Post comments as a GitHub PR review"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "      :export gitlab URL   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Post comments as GitLab MR discussions"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :retry-export ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Resend forge exports queued while offline"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :errors   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("List the errors of this session"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :copy     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Copy visible diff lines as text"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  :set wrap ",
@@ -319,6 +584,279 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Toggle line wrap in diff view"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set context=N ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Embed N diff lines around comments in exports"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :theme    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Pick a theme with live preview"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :theme <name> ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Switch theme"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set hideeol! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle hiding LF/CRLF-only changes"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set hiderenames! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle hiding lines that only rename"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set newcode! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle hiding moved/pre-existing code"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set autoreload! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle reloading the diff on file changes"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set intraline! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle changed-word highlighting"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set intralinemax=N ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("No word highlighting past N chars"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    :set highlightmax=N  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("No syntax highlighting past N diff lines"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set indentguides! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle indent guides"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set indentwidth=N ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Indent guide every N columns"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set cursorline! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle cursor line highlight"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set dimviewed! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle dimming lines already viewed"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set cursorcolumn! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle cursor column highlight"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set rnu! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle relative line numbers"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set screenreader! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle screen reader mode"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set reducemotion! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle reduced motion"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :noh      ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Clear search highlight"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set blame! ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Toggle blame gutter (unified view)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set iw   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Cycle ignored whitespace (noiw: show all)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set timeoutlen=N ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Multi-key timeout in ms (0 = off)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set quittimeout=N ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Ctrl-C double-press window in ms (0 = single)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :map [text] ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("List key bindings, optionally filtered"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set history ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Record review actions in the session"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :history  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Show recorded review actions"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :ccomments /pat ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Search comments; n/N step through them"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :todos    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("List TODO/FIXME/XXX markers the diff adds"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    :lang NAME  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Highlight the file as NAME (:lang auto undoes)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "      :highlight!   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Highlight the file despite highlightmax"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    :stats     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Show review progress, comments and time per file"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :sort S   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Sort comments: file/severity/created/resolved"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :dups     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("List blocks possibly copied between files"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :assign NAME ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Assign the comment to a person"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :unassign ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Clear the comment's assignee"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :assignee NAME ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Only list comments assigned to NAME"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :apply    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Apply the suggestion to the file"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :unapply  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Undo the last applied suggestion"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :set dimunfocused ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Dim UI when terminal loses focus"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  :diff     ",
@@ -326,6 +864,34 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Toggle unified/side-by-side diff view"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  :worddiff ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Word diff of current file (prose)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :columns  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Column-aligned view of CSV/TSV diff"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :image    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Block-art preview of current image"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :structdiff ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Key-path diff of JSON/YAML file"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  :commits  ",
@@ -333,6 +899,20 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Select commits to review"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  :base     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Pick branch/tag to diff against"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :base <ref> ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Diff working tree against <ref>"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  :clear    ",
@@ -340,6 +920,20 @@ pub fn render_help(frame: &mut Frame, app: &mut App) {
             ),
             Span::raw("Clear all comments"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  :archive  ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Archive the finished review"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  :dump     ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("Copy the diff view as plain text"),
+        ]),
         Line::from(vec![
             Span::styled(
                 "  :q        ",
@@ -0,0 +1,34 @@
+use chrono::Local;
+use ratatui::text::{Line, Span};
+
+use crate::model::review::HistoryEntry;
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render the session audit trail, one line per recorded action, oldest first
+pub fn history_lines(theme: &Theme, history: &[HistoryEntry]) -> Vec<Line<'static>> {
+    history
+        .iter()
+        .map(|entry| {
+            let location = match entry.line {
+                Some(line) => format!("{}:{line}", entry.path.display()),
+                None => entry.path.display().to_string(),
+            };
+            Line::from(vec![
+                Span::styled(
+                    entry
+                        .at
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S  ")
+                        .to_string(),
+                    styles::dim_style(theme),
+                ),
+                Span::styled(
+                    format!("{:<18}", entry.action.label()),
+                    styles::hash_style(theme),
+                ),
+                Span::raw(location),
+            ])
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use image::{DynamicImage, Rgba, imageops::FilterType};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+/// Pixels with less opacity than this are left to the terminal background
+const MIN_ALPHA: u8 = 128;
+
+/// Render `image` as half-block characters, two pixel rows per line, scaled
+/// down to fit `max_width` columns by `max_height` lines
+pub fn image_lines(image: &DynamicImage, max_width: u32, max_height: u32) -> Vec<Line<'static>> {
+    let image = image
+        .resize(max_width, max_height * 2, FilterType::Triangle)
+        .to_rgba8();
+    let color = |pixel: &Rgba<u8>| {
+        let [r, g, b, a] = pixel.0;
+        (a >= MIN_ALPHA).then_some(Color::Rgb(r, g, b))
+    };
+
+    (0..image.height())
+        .step_by(2)
+        .map(|y| {
+            let spans: Vec<Span> = (0..image.width())
+                .map(|x| {
+                    let top = color(image.get_pixel(x, y));
+                    let bottom = (y + 1 < image.height())
+                        .then(|| color(image.get_pixel(x, y + 1)))
+                        .flatten();
+                    match (top, bottom) {
+                        (Some(top), Some(bottom)) => {
+                            Span::styled("▀", Style::default().fg(top).bg(bottom))
+                        }
+                        (Some(top), None) => Span::styled("▀", Style::default().fg(top)),
+                        (None, Some(bottom)) => Span::styled("▄", Style::default().fg(bottom)),
+                        (None, None) => Span::raw(" "),
+                    }
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
@@ -0,0 +1,122 @@
+use ratatui::style::Style;
+
+/// Character drawn at each indent level
+const GUIDE: char = '│';
+
+/// Draw indent guides in the leading whitespace of a code line: a guide at
+/// every `width` columns, in the style of the whitespace patched with
+/// `guide`, so diff backgrounds and emphasis carry through.
+///
+/// Leading tabs are expanded to the next multiple of `width`, so the guides
+/// line up whether a file indents with tabs or spaces.
+pub fn add_indent_guides(
+    spans: Vec<(Style, String)>,
+    width: usize,
+    guide: Style,
+) -> Vec<(Style, String)> {
+    if width == 0 {
+        return spans;
+    }
+
+    let mut result: Vec<(Style, String)> = Vec::with_capacity(spans.len() + 2);
+    let mut push = |style: Style, text: &str| match result.last_mut() {
+        Some((last_style, last_text)) if *last_style == style => last_text.push_str(text),
+        _ => result.push((style, text.to_string())),
+    };
+    let mut col = 0;
+    let mut in_indent = true;
+    for (style, text) in &spans {
+        if !in_indent {
+            push(*style, text);
+            continue;
+        }
+        for (idx, ch) in text.char_indices() {
+            let cells = match ch {
+                ' ' => 1,
+                '\t' => width - col % width,
+                _ => {
+                    in_indent = false;
+                    push(*style, &text[idx..]);
+                    break;
+                }
+            };
+            for _ in 0..cells {
+                if col % width == 0 {
+                    push(style.patch(guide), GUIDE.encode_utf8(&mut [0; 4]));
+                } else {
+                    push(*style, " ");
+                }
+                col += 1;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Modifier};
+
+    fn guide() -> Style {
+        Style::default().fg(Color::DarkGray)
+    }
+
+    #[test]
+    fn should_draw_a_guide_per_indent_level() {
+        // given
+        let code = Style::default().fg(Color::Green);
+        let spans = vec![(code, "        let x = 1;".to_string())];
+
+        // when
+        let result = add_indent_guides(spans, 4, guide());
+
+        // then
+        assert_eq!(
+            result,
+            vec![
+                (code.patch(guide()), "│".to_string()),
+                (code, "   ".to_string()),
+                (code.patch(guide()), "│".to_string()),
+                (code, "   let x = 1;".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_expand_tabs_to_indent_levels() {
+        // given
+        let spans = vec![(Style::default(), "\t\tx".to_string())];
+
+        // when
+        let result = add_indent_guides(spans, 2, guide());
+
+        // then
+        let text: String = result.iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(text, "│ │ x");
+    }
+
+    #[test]
+    fn should_keep_styles_of_split_highlighted_spans() {
+        // given
+        let changed = Style::default().add_modifier(Modifier::REVERSED);
+        let spans = vec![
+            (Style::default(), "  ".to_string()),
+            (changed, "  y".to_string()),
+        ];
+
+        // when
+        let result = add_indent_guides(spans, 2, guide());
+
+        // then
+        assert_eq!(
+            result,
+            vec![
+                (guide(), "│".to_string()),
+                (Style::default(), " ".to_string()),
+                (changed.patch(guide()), "│".to_string()),
+                (changed, " y".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthStr;
+
+use crate::input::keymap::ModeKeymap;
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render the keymap as one section per mode, keys left and action right
+pub fn keymap_lines(theme: &Theme, keymap: &[ModeKeymap]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+
+    for mode in keymap.iter().filter(|m| !m.bindings.is_empty()) {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            mode.mode.to_string(),
+            styles::header_style(theme),
+        )));
+
+        let keys: Vec<String> = mode.bindings.iter().map(|b| b.keys.join(" ")).collect();
+        let width = keys.iter().map(|k| k.width()).max().unwrap_or(0);
+        for (keys, binding) in keys.iter().zip(&mode.bindings) {
+            let padding = " ".repeat(width.saturating_sub(keys.width()));
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {keys}{padding}  "), styles::hash_style(theme)),
+                Span::raw(binding.action.clone()),
+            ]));
+        }
+    }
+
+    lines
+}
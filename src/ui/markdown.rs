@@ -0,0 +1,212 @@
+use ratatui::style::{Modifier, Style};
+
+/// A run of rendered comment text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownSpan {
+    pub text: String,
+    pub style: Style,
+    /// Code, which is shown as written (no issue reference highlighting)
+    pub literal: bool,
+}
+
+/// Renders the basic markdown of comments line by line: `**bold**`,
+/// `*italics*`, `` `inline code` ``, fenced code blocks, headings and `-`/`*`
+/// list items. Keeps track of fenced blocks across the lines of one text.
+#[derive(Debug, Default)]
+pub struct MarkdownRenderer {
+    in_code_block: bool,
+}
+
+impl MarkdownRenderer {
+    /// Spans of the next line of the text, in `base` style with markup
+    /// applied, and code in `code` style
+    pub fn line(&mut self, text: &str, base: Style, code: Style) -> Vec<MarkdownSpan> {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("```") {
+            self.in_code_block = !self.in_code_block;
+            return vec![span(text, code.add_modifier(Modifier::DIM), true)];
+        }
+        if self.in_code_block {
+            return vec![span(text, code, true)];
+        }
+
+        let indent = &text[..text.len() - trimmed.len()];
+        if let Some(heading) = trimmed
+            .strip_prefix("# ")
+            .or_else(|| trimmed.strip_prefix("## "))
+            .or_else(|| trimmed.strip_prefix("### "))
+        {
+            return inline_spans(heading, base.add_modifier(Modifier::BOLD), code);
+        }
+        if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+        {
+            let mut spans = vec![span(&format!("{indent}• "), base, false)];
+            spans.extend(inline_spans(item, base, code));
+            return spans;
+        }
+        inline_spans(text, base, code)
+    }
+}
+
+fn span(text: &str, style: Style, literal: bool) -> MarkdownSpan {
+    MarkdownSpan {
+        text: text.to_string(),
+        style,
+        literal,
+    }
+}
+
+/// Spans of inline markup: emphasis markers only count when they are closed
+/// later on the line, and `_` only at word boundaries, so `snake_case` and a
+/// lone `*` stay as written
+fn inline_spans(text: &str, base: Style, code: Style) -> Vec<MarkdownSpan> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut bold = false;
+    let mut italic = false;
+    let style = |bold: bool, italic: bool| {
+        let mut style = base;
+        if bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        style
+    };
+    let flush = |current: &mut String, spans: &mut Vec<MarkdownSpan>, style: Style| {
+        if !current.is_empty() {
+            spans.push(span(current, style, false));
+            current.clear();
+        }
+    };
+
+    let mut idx = 0;
+    while idx < text.len() {
+        let rest = &text[idx..];
+        let ch = rest.chars().next().unwrap_or_default();
+        let prev = text[..idx].chars().next_back();
+
+        if ch == '\\'
+            && let Some(next) = rest[1..].chars().next()
+            && next.is_ascii_punctuation()
+        {
+            current.push(next);
+            idx += 1 + next.len_utf8();
+            continue;
+        }
+        if ch == '`'
+            && let Some(end) = rest[1..].find('`')
+        {
+            flush(&mut current, &mut spans, style(bold, italic));
+            spans.push(span(&rest[1..=end], code, true));
+            idx += end + 2;
+            continue;
+        }
+        let marker = if rest.starts_with("**") || rest.starts_with("__") {
+            &rest[..2]
+        } else if ch == '*' || ch == '_' {
+            &rest[..1]
+        } else {
+            ""
+        };
+        let word_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+        let is_strong = marker.len() == 2;
+        let open = if is_strong { bold } else { italic };
+        let toggles = !marker.is_empty()
+            && if open {
+                !marker.starts_with('_') || word_boundary(rest[marker.len()..].chars().next())
+            } else {
+                (!marker.starts_with('_') || word_boundary(prev))
+                    && rest[marker.len()..]
+                        .chars()
+                        .next()
+                        .is_some_and(|c| !c.is_whitespace())
+                    && rest[marker.len()..].contains(marker)
+            };
+        if toggles {
+            flush(&mut current, &mut spans, style(bold, italic));
+            if is_strong {
+                bold = !bold;
+            } else {
+                italic = !italic;
+            }
+            idx += marker.len();
+            continue;
+        }
+        current.push(ch);
+        idx += ch.len_utf8();
+    }
+    flush(&mut current, &mut spans, style(bold, italic));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn texts(spans: &[MarkdownSpan]) -> Vec<(&str, bool, bool, bool)> {
+        spans
+            .iter()
+            .map(|s| {
+                (
+                    s.text.as_str(),
+                    s.style.add_modifier.contains(Modifier::BOLD),
+                    s.style.add_modifier.contains(Modifier::ITALIC),
+                    s.literal,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_render_inline_markup() {
+        // given
+        let mut renderer = MarkdownRenderer::default();
+        let code = Style::default().fg(Color::Cyan);
+
+        // when
+        let spans = renderer.line(
+            "Use **one** *call* to `parse_args` in snake_case, 2 * 3",
+            Style::default(),
+            code,
+        );
+
+        // then
+        assert_eq!(
+            texts(&spans),
+            vec![
+                ("Use ", false, false, false),
+                ("one", true, false, false),
+                (" ", false, false, false),
+                ("call", false, true, false),
+                (" to ", false, false, false),
+                ("parse_args", false, false, true),
+                (" in snake_case, 2 * 3", false, false, false),
+            ]
+        );
+        assert_eq!(spans[5].style, code);
+    }
+
+    #[test]
+    fn should_render_lists_and_code_blocks() {
+        // given
+        let mut renderer = MarkdownRenderer::default();
+        let base = Style::default();
+        let code = Style::default().fg(Color::Cyan);
+
+        // when
+        let lines: Vec<Vec<MarkdownSpan>> = ["- first", "```", "let *x* = 1;", "```", "done"]
+            .iter()
+            .map(|line| renderer.line(line, base, code))
+            .collect();
+
+        // then
+        assert_eq!(texts(&lines[0])[0], ("• ", false, false, false));
+        assert_eq!(texts(&lines[2]), vec![("let *x* = 1;", false, false, true)]);
+        assert_eq!(texts(&lines[4]), vec![("done", false, false, false)]);
+    }
+}
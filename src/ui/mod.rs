@@ -1,9 +1,23 @@
 pub mod app_layout;
+pub mod blame_gutter;
 pub mod comment_panel;
 pub mod diff_view;
+pub mod duplicates_view;
+pub mod error_view;
 pub mod file_list;
 pub mod help_popup;
+pub mod history_view;
+pub mod image_view;
+pub mod indent_guides;
+pub mod keymap_view;
+pub mod markdown;
+pub mod stats_view;
 pub mod status_bar;
+pub mod structural_view;
 pub mod styles;
+pub mod table_view;
+pub mod text_popup;
+pub mod todo_view;
+pub mod word_diff_view;
 
 pub use app_layout::render;
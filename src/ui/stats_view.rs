@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use ratatui::text::{Line, Span};
+
+use crate::model::ReviewStats;
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render review progress, diff size, comments by type and time per file
+pub fn stats_lines(theme: &Theme, stats: &ReviewStats) -> Vec<Line<'static>> {
+    let heading =
+        |text: &str| Line::from(Span::styled(text.to_string(), styles::header_style(theme)));
+    let label = |text: String| Span::styled(format!("  {text:<24}"), styles::dim_style(theme));
+
+    let mut progress = format!("{} / {}", stats.files_reviewed, stats.files_total);
+    if stats.files_skipped > 0 {
+        progress.push_str(&format!(" ({} skipped)", stats.files_skipped));
+    }
+    let mut lines = vec![
+        heading("Progress"),
+        Line::from(vec![
+            label("Files reviewed".to_string()),
+            Span::styled(progress, styles::reviewed_style(theme)),
+        ]),
+        Line::from(vec![
+            label("Lines".to_string()),
+            Span::styled(
+                format!("+{}", stats.lines_added),
+                styles::stat_add_style(theme),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!("-{}", stats.lines_removed),
+                styles::stat_del_style(theme),
+            ),
+        ]),
+        Line::default(),
+        heading("Comments"),
+    ];
+    for (comment_type, count, resolved) in &stats.comments {
+        let mut text = count.to_string();
+        if *resolved > 0 {
+            text.push_str(&format!(" ({resolved} resolved)"));
+        }
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<24}", comment_type.as_str()),
+                styles::comment_type_style(theme, *comment_type),
+            ),
+            Span::raw(text),
+        ]));
+    }
+
+    lines.push(Line::default());
+    lines.push(heading(&format!(
+        "Time spent ({})",
+        format_duration(stats.total_time())
+    )));
+    if stats.time_per_file.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No time recorded yet",
+            styles::dim_style(theme),
+        )));
+    }
+    for (path, time) in &stats.time_per_file {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:>8}  ", format_duration(*time)),
+                styles::hash_style(theme),
+            ),
+            Span::raw(path.display().to_string()),
+        ]));
+    }
+    lines
+}
+
+/// `1h 05m`, `4m 20s` or `35s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
@@ -17,15 +17,14 @@ pub fn build_message_span(message: Option<&Message>, theme: &Theme) -> (Span<'st
             MessageType::Warning => (Color::Black, theme.pending),
             MessageType::Error => (Color::White, theme.comment_issue),
         };
+        let style = if theme.monochrome {
+            Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD)
+        };
         let content = format!(" {} ", msg.content);
         let width = content.len();
-        (
-            Span::styled(
-                content,
-                Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD),
-            ),
-            width,
-        )
+        (Span::styled(content, style), width)
     } else {
         (Span::raw(""), 0)
     }
@@ -59,6 +58,7 @@ pub fn render_header(frame: &mut Frame, app: &App, area: Rect) {
     // Show diff source info
     let source_info = match &app.diff_source {
         DiffSource::WorkingTree => String::new(),
+        DiffSource::Ref(base) => format!("[vs {base}] "),
         DiffSource::CommitRange(commits) => {
             if commits.len() == 1 {
                 format!("[commit {}] ", &commits[0][..7.min(commits[0].len())])
@@ -68,21 +68,32 @@ pub fn render_header(frame: &mut Frame, app: &App, area: Rect) {
         }
     };
 
-    let progress = format!("{}/{} reviewed ", app.reviewed_count(), app.file_count());
+    let progress = format!(
+        "{}/{} reviewed ",
+        app.reviewed_count(),
+        app.reviewable_count()
+    );
 
     let title_span = Span::styled(title, styles::header_style(theme));
     let vcs_span = Span::styled(vcs_info, Style::default().fg(theme.fg_secondary));
     let source_span = Span::styled(source_info, Style::default().fg(theme.diff_hunk_header));
     let progress_span = Span::styled(
         progress,
-        if app.reviewed_count() == app.file_count() {
+        if app.reviewed_count() == app.reviewable_count() {
             styles::reviewed_style(theme)
         } else {
             styles::pending_style(theme)
         },
     );
 
-    let line = Line::from(vec![title_span, vcs_span, source_span, progress_span]);
+    let mut spans = vec![title_span, vcs_span, source_span, progress_span];
+    if app.diff_stale {
+        spans.push(Span::styled(
+            " ⟳ diff is stale — :e to reload ",
+            styles::pending_style(theme).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let line = Line::from(spans);
 
     let header = Paragraph::new(line)
         .style(styles::status_bar_style(theme))
@@ -120,6 +131,13 @@ pub fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
             InputMode::Help => " HELP ".to_string(),
             InputMode::Confirm => " CONFIRM ".to_string(),
             InputMode::CommitSelect => " SELECT ".to_string(),
+            InputMode::Popup => " VIEW ".to_string(),
+            InputMode::ThemePicker => " THEME ".to_string(),
+            InputMode::RefSelect => " REFS ".to_string(),
+            InputMode::Error => " ERROR ".to_string(),
+            InputMode::VisualSelect if app.file_visual_anchor.is_some() => {
+                " VISUAL FILES ".to_string()
+            }
             InputMode::VisualSelect => {
                 if let Some((range, _)) = app.get_visual_selection() {
                     if range.is_single() {
@@ -147,21 +165,71 @@ pub fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
             InputMode::CommitSelect => {
                 " j/k:navigate  Space:select  Enter:confirm  Esc:back  q:quit "
             }
-            InputMode::VisualSelect => " j/k:extend  c/Enter:comment  Esc/V:cancel ",
+            InputMode::VisualSelect if app.file_visual_anchor.is_some() => {
+                " j/k:extend  r:reviewed  x:skip  p:pin  Esc/V:cancel "
+            }
+            InputMode::VisualSelect => " j/k:extend  c/Enter:comment  y:copy  Esc/V:cancel ",
+            InputMode::Popup => " j/k/h/l:scroll  q/Esc:close ",
+            InputMode::ThemePicker => " j/k:preview  Enter:apply  Esc:cancel ",
+            InputMode::RefSelect => " j/k:navigate  Enter:compare  Esc:cancel ",
+            InputMode::Error => " r:retry  l:log  Enter/Esc:continue ",
         };
         let hints_span = Span::styled(hints, Style::default().fg(theme.fg_secondary));
 
-        let dirty_indicator = if app.dirty {
+        let dirty_indicator = if app.read_only {
+            Span::styled(" [read-only] ", Style::default().fg(theme.fg_secondary))
+        } else if app.dirty {
             Span::styled(" [modified] ", Style::default().fg(theme.pending))
         } else {
             Span::raw("")
         };
 
-        vec![mode_span, hints_span, dirty_indicator]
+        // Like vim's showcmd: echo a pending multi-key prefix
+        let pending_span = match &app.pending_keys {
+            Some(keys) => Span::styled(
+                format!(" {keys}… "),
+                Style::default()
+                    .fg(theme.fg_primary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            None => Span::raw(""),
+        };
+
+        // Which hunk of its file the cursor is in
+        let hunk_span = match app.hunk_at_cursor() {
+            Some((file_idx, hunk_idx)) if app.input_mode == InputMode::Normal => Span::styled(
+                format!(
+                    " hunk {}/{} ",
+                    hunk_idx + 1,
+                    app.diff_files[file_idx].hunks.len()
+                ),
+                Style::default().fg(theme.fg_secondary),
+            ),
+            _ => Span::raw(""),
+        };
+
+        vec![
+            mode_span,
+            hints_span,
+            dirty_indicator,
+            pending_span,
+            hunk_span,
+        ]
     };
 
-    // Build message span and create right-aligned layout
-    let (message_span, message_width) = build_message_span(app.message.as_ref(), theme);
+    // Build message span and create right-aligned layout; without a message,
+    // echo the full path of a cut-off file list entry
+    let (message_span, message_width) = match (&app.message, &app.file_list_state.path_echo) {
+        (None, Some(path)) if app.input_mode == InputMode::Normal => {
+            let content = format!(" {path} ");
+            let width = content.len();
+            (
+                Span::styled(content, Style::default().fg(theme.fg_secondary)),
+                width,
+            )
+        }
+        _ => build_message_span(app.message.as_ref(), theme),
+    };
     let total_width = area.width as usize;
     let spans = build_right_aligned_spans(left_spans, message_span, message_width, total_width);
 
@@ -0,0 +1,35 @@
+use ratatui::text::{Line, Span};
+
+use crate::model::StructuralChange;
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render a key-path change list, one line per changed leaf
+pub fn structural_diff_lines(theme: &Theme, changes: &[StructuralChange]) -> Vec<Line<'static>> {
+    if changes.is_empty() {
+        return vec![Line::from(Span::styled(
+            "No structural changes (only formatting or key order differs)",
+            styles::dim_style(theme),
+        ))];
+    }
+
+    changes
+        .iter()
+        .map(|change| match change {
+            StructuralChange::Added { path, value } => Line::from(vec![
+                Span::styled(format!("+ {path}: "), styles::stat_add_style(theme)),
+                Span::raw(value.clone()),
+            ]),
+            StructuralChange::Removed { path, value } => Line::from(vec![
+                Span::styled(format!("- {path}: "), styles::stat_del_style(theme)),
+                Span::raw(value.clone()),
+            ]),
+            StructuralChange::Changed { path, old, new } => Line::from(vec![
+                Span::styled(format!("~ {path}: "), styles::pending_style(theme)),
+                Span::styled(old.clone(), styles::stat_del_style(theme)),
+                Span::styled(" → ", styles::dim_style(theme)),
+                Span::styled(new.clone(), styles::stat_add_style(theme)),
+            ]),
+        })
+        .collect()
+}
@@ -2,6 +2,15 @@ use ratatui::style::{Modifier, Style};
 
 use crate::theme::Theme;
 
+/// `modifier` under the monochrome theme, where it stands in for color
+fn mono(theme: &Theme, modifier: Modifier) -> Modifier {
+    if theme.monochrome {
+        modifier
+    } else {
+        Modifier::empty()
+    }
+}
+
 pub fn header_style(theme: &Theme) -> Style {
     Style::default()
         .fg(theme.fg_primary)
@@ -9,19 +18,62 @@ pub fn header_style(theme: &Theme) -> Style {
 }
 
 pub fn selected_style(theme: &Theme) -> Style {
-    Style::default().bg(theme.bg_highlight).fg(theme.fg_primary)
+    Style::default()
+        .bg(theme.bg_highlight)
+        .fg(theme.fg_primary)
+        .add_modifier(mono(theme, Modifier::REVERSED))
 }
 
 pub fn dim_style(theme: &Theme) -> Style {
-    Style::default().fg(theme.fg_dim)
+    Style::default()
+        .fg(theme.fg_dim)
+        .add_modifier(mono(theme, Modifier::DIM))
 }
 
 pub fn diff_add_style(theme: &Theme) -> Style {
-    Style::default().fg(theme.diff_add).bg(theme.diff_add_bg)
+    Style::default()
+        .fg(theme.diff_add)
+        .bg(theme.diff_add_bg)
+        .add_modifier(mono(theme, Modifier::BOLD))
 }
 
 pub fn diff_del_style(theme: &Theme) -> Style {
-    Style::default().fg(theme.diff_del).bg(theme.diff_del_bg)
+    Style::default()
+        .fg(theme.diff_del)
+        .bg(theme.diff_del_bg)
+        .add_modifier(mono(theme, Modifier::DIM))
+}
+
+/// Addition count style for summaries (no background)
+pub fn stat_add_style(theme: &Theme) -> Style {
+    Style::default()
+        .fg(theme.diff_add)
+        .add_modifier(mono(theme, Modifier::BOLD))
+}
+
+/// Deletion count style for summaries (no background)
+pub fn stat_del_style(theme: &Theme) -> Style {
+    Style::default().fg(theme.diff_del)
+}
+
+/// Emphasis for the changed words within a changed line
+pub fn intraline_change_style(style: Style) -> Style {
+    style.add_modifier(Modifier::BOLD | Modifier::REVERSED)
+}
+
+/// Matches of the last search in the diff
+pub fn search_match_style(theme: &Theme) -> Style {
+    Style::default()
+        .fg(theme.mode_fg)
+        .bg(theme.mode_bg)
+        .add_modifier(mono(theme, Modifier::REVERSED))
+}
+
+/// TODO/FIXME/XXX markers added by the diff
+pub fn todo_marker_style(theme: &Theme) -> Style {
+    Style::default()
+        .fg(theme.comment_issue)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
 }
 
 pub fn diff_context_style(theme: &Theme) -> Style {
@@ -29,7 +81,9 @@ pub fn diff_context_style(theme: &Theme) -> Style {
 }
 
 pub fn expanded_context_style(theme: &Theme) -> Style {
-    Style::default().fg(theme.expanded_context_fg)
+    Style::default()
+        .fg(theme.expanded_context_fg)
+        .add_modifier(mono(theme, Modifier::DIM))
 }
 
 pub fn diff_hunk_header_style(theme: &Theme) -> Style {
@@ -52,9 +106,18 @@ pub fn pending_style(theme: &Theme) -> Style {
     Style::default().fg(theme.pending)
 }
 
+/// Marker of files with changes staged for the next commit
+pub fn staged_style(theme: &Theme) -> Style {
+    Style::default()
+        .fg(theme.diff_add)
+        .add_modifier(mono(theme, Modifier::BOLD))
+}
+
 pub fn border_style(theme: &Theme, focused: bool) -> Style {
     if focused {
-        Style::default().fg(theme.border_focused)
+        Style::default()
+            .fg(theme.border_focused)
+            .add_modifier(mono(theme, Modifier::BOLD))
     } else {
         Style::default().fg(theme.border_unfocused)
     }
@@ -64,13 +127,14 @@ pub fn status_bar_style(theme: &Theme) -> Style {
     Style::default()
         .bg(theme.status_bar_bg)
         .fg(theme.fg_primary)
+        .add_modifier(mono(theme, Modifier::REVERSED))
 }
 
 pub fn mode_style(theme: &Theme) -> Style {
     Style::default()
         .fg(theme.mode_fg)
         .bg(theme.mode_bg)
-        .add_modifier(Modifier::BOLD)
+        .add_modifier(Modifier::BOLD | mono(theme, Modifier::REVERSED))
 }
 
 pub fn file_status_style(theme: &Theme, status: char) -> Style {
@@ -85,13 +149,22 @@ pub fn file_status_style(theme: &Theme, status: char) -> Style {
 }
 
 pub fn current_line_indicator_style(theme: &Theme) -> Style {
-    Style::default().fg(theme.border_focused)
+    Style::default()
+        .fg(theme.border_focused)
+        .add_modifier(mono(theme, Modifier::BOLD))
 }
 
 pub fn hash_style(theme: &Theme) -> Style {
     Style::default().fg(theme.cursor_color)
 }
 
+/// Inline code and code blocks in comments
+pub fn comment_code_style(theme: &Theme) -> Style {
+    Style::default()
+        .fg(theme.diff_hunk_header)
+        .bg(theme.bg_highlight)
+}
+
 pub fn dir_icon_style(theme: &Theme) -> Style {
     Style::default().fg(theme.diff_hunk_header)
 }
@@ -118,6 +191,25 @@ pub fn comment_border_style(theme: &Theme, comment_type: crate::model::CommentTy
     Style::default().fg(color)
 }
 
+/// Diff lines already viewed (`:set dimviewed`), dimmed whatever their color
+pub fn viewed_line_style() -> Style {
+    Style::default().add_modifier(Modifier::DIM)
+}
+
+pub fn cursor_line_style(theme: &Theme) -> Style {
+    Style::default()
+        .bg(theme.cursor_line_bg)
+        .add_modifier(mono(theme, Modifier::UNDERLINED))
+}
+
+pub fn cursor_column_style(theme: &Theme) -> Style {
+    Style::default()
+        .bg(theme.cursor_column_bg)
+        .add_modifier(mono(theme, Modifier::REVERSED))
+}
+
 pub fn visual_selection_style(theme: &Theme) -> Style {
-    Style::default().bg(theme.bg_highlight)
+    Style::default()
+        .bg(theme.bg_highlight)
+        .add_modifier(mono(theme, Modifier::REVERSED))
 }
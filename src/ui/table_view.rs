@@ -0,0 +1,184 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::model::{DiffFile, LineOrigin};
+use crate::theme::Theme;
+use crate::ui::styles;
+
+const CELL_SEPARATOR: &str = " │ ";
+
+/// Build a column-aligned rendering of a delimited data file, one block per
+/// hunk.
+///
+/// Cells are padded to the widest value of their column within the hunk so
+/// that columns line up across removed, added and context rows. When a
+/// removed row is followed by an added row, the cells that differ between the
+/// two are emphasized.
+pub fn table_diff_lines(theme: &Theme, file: &DiffFile, delimiter: char) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+
+    for hunk in &file.hunks {
+        lines.push(Line::from(Span::styled(
+            hunk.header.clone(),
+            styles::diff_hunk_header_style(theme),
+        )));
+
+        let rows: Vec<Vec<String>> = hunk
+            .lines
+            .iter()
+            .map(|l| split_record(&l.content, delimiter))
+            .collect();
+        let widths = column_widths(&rows);
+
+        let mut idx = 0;
+        while idx < hunk.lines.len() {
+            if hunk.lines[idx].origin != LineOrigin::Deletion {
+                lines.push(render_row(
+                    theme,
+                    hunk.lines[idx].origin,
+                    &rows[idx],
+                    &widths,
+                    None,
+                ));
+                idx += 1;
+                continue;
+            }
+
+            // Pair a run of deletions with the run of additions that follows
+            let del_start = idx;
+            while idx < hunk.lines.len() && hunk.lines[idx].origin == LineOrigin::Deletion {
+                idx += 1;
+            }
+            let add_start = idx;
+            while idx < hunk.lines.len() && hunk.lines[idx].origin == LineOrigin::Addition {
+                idx += 1;
+            }
+            let deletions = &rows[del_start..add_start];
+            let additions = &rows[add_start..idx];
+
+            for (i, row) in deletions.iter().enumerate() {
+                let counterpart = additions.get(i).map(Vec::as_slice);
+                lines.push(render_row(
+                    theme,
+                    LineOrigin::Deletion,
+                    row,
+                    &widths,
+                    counterpart,
+                ));
+            }
+            for (i, row) in additions.iter().enumerate() {
+                let counterpart = deletions.get(i).map(Vec::as_slice);
+                lines.push(render_row(
+                    theme,
+                    LineOrigin::Addition,
+                    row,
+                    &widths,
+                    counterpart,
+                ));
+            }
+        }
+
+        lines.push(Line::from(""));
+    }
+
+    lines
+}
+
+fn render_row(
+    theme: &Theme,
+    origin: LineOrigin,
+    cells: &[String],
+    widths: &[usize],
+    counterpart: Option<&[String]>,
+) -> Line<'static> {
+    let (prefix, style) = match origin {
+        LineOrigin::Addition => ("+ ", styles::stat_add_style(theme)),
+        LineOrigin::Deletion => ("- ", styles::stat_del_style(theme)),
+        LineOrigin::Context => ("  ", styles::diff_context_style(theme)),
+    };
+    let changed_style = style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+
+    let mut spans = vec![Span::styled(prefix, style)];
+    for (col, cell) in cells.iter().enumerate() {
+        if col > 0 {
+            spans.push(Span::styled(CELL_SEPARATOR, styles::dim_style(theme)));
+        }
+        let changed = counterpart.is_some_and(|other| other.get(col) != Some(cell));
+        let width = widths.get(col).copied().unwrap_or(0);
+        let padding = " ".repeat(width.saturating_sub(cell.width()));
+        spans.push(Span::styled(
+            cell.clone(),
+            if changed { changed_style } else { style },
+        ));
+        spans.push(Span::styled(padding, Style::default()));
+    }
+    Line::from(spans)
+}
+
+fn column_widths(rows: &[Vec<String>]) -> Vec<usize> {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        for (col, cell) in row.iter().enumerate() {
+            if col >= widths.len() {
+                widths.push(0);
+            }
+            widths[col] = widths[col].max(cell.width());
+        }
+    }
+    widths
+}
+
+/// Split a delimited record into cells, honoring double-quoted fields
+fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => cells.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    cells.push(current);
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_quoted_fields() {
+        // when
+        let cells = split_record(r#"1,"Doe, Jane","say ""hi""",x"#, ',');
+
+        // then
+        assert_eq!(cells, vec!["1", "Doe, Jane", "say \"hi\"", "x"]);
+    }
+
+    #[test]
+    fn should_compute_widest_cell_per_column() {
+        // given
+        let rows = vec![
+            split_record("id\tname", '\t'),
+            split_record("100\tal", '\t'),
+            split_record("7\tbeatrice\textra", '\t'),
+        ];
+
+        // when
+        let widths = column_widths(&rows);
+
+        // then
+        assert_eq!(widths, vec![3, 8, 5]);
+    }
+}
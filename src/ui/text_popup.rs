@@ -0,0 +1,134 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::App;
+use crate::theme::ThemeArg;
+use crate::ui::styles;
+
+/// Render the generic read-only popup (word diff, lists, reports)
+pub fn render_text_popup(frame: &mut Frame, app: &mut App) {
+    let theme = &app.theme;
+    let Some(popup) = app.text_popup.as_mut() else {
+        return;
+    };
+    let area = centered_rect(80, 80, frame.area());
+
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(" {} - Esc/q to close ", popup.title))
+        .borders(Borders::ALL)
+        .border_style(styles::border_style(theme, true));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Wrapped lines can span several rows, so count rendered rows
+    let width = inner.width.max(1) as usize;
+    let total_rows: usize = if popup.wrap {
+        popup
+            .lines
+            .iter()
+            .map(|line| line.width().div_ceil(width).max(1))
+            .sum()
+    } else {
+        popup.lines.len()
+    };
+    popup.total_lines = total_rows;
+    popup.viewport_height = inner.height as usize;
+    let max_offset = total_rows.saturating_sub(popup.viewport_height);
+    popup.scroll_offset = popup.scroll_offset.min(max_offset);
+
+    let can_scroll_up = popup.scroll_offset > 0;
+    let can_scroll_down = popup.scroll_offset < max_offset;
+
+    let paragraph = if popup.wrap {
+        Paragraph::new(popup.lines.clone())
+            .wrap(Wrap { trim: false })
+            .scroll((popup.scroll_offset as u16, 0))
+    } else {
+        let max_width = popup.lines.iter().map(Line::width).max().unwrap_or(0);
+        popup.scroll_x = popup.scroll_x.min(max_width.saturating_sub(width));
+        Paragraph::new(popup.lines.clone())
+            .scroll((popup.scroll_offset as u16, popup.scroll_x as u16))
+    };
+    frame.render_widget(paragraph, inner);
+
+    let indicator_style = Style::default().fg(Color::DarkGray);
+
+    if can_scroll_up {
+        let up_indicator = Paragraph::new(Line::from(Span::styled("▲ more", indicator_style)));
+        let up_area = Rect {
+            x: inner.x + inner.width.saturating_sub(8),
+            y: inner.y,
+            width: 7,
+            height: 1,
+        };
+        frame.render_widget(up_indicator, up_area);
+    }
+
+    if can_scroll_down {
+        let down_indicator = Paragraph::new(Line::from(Span::styled("▼ more", indicator_style)));
+        let down_area = Rect {
+            x: inner.x + inner.width.saturating_sub(8),
+            y: inner.y + inner.height.saturating_sub(1),
+            width: 7,
+            height: 1,
+        };
+        frame.render_widget(down_indicator, down_area);
+    }
+}
+
+/// Render the theme picker as a small list over the (previewed) diff
+pub fn render_theme_picker(frame: &mut Frame, app: &App) {
+    let Some(picker) = app.theme_picker.as_ref() else {
+        return;
+    };
+    let theme = &app.theme;
+    let names: Vec<&str> = ThemeArg::ALL.iter().map(|t| t.name()).collect();
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0) as u16 + 8;
+    let height = names.len() as u16 + 2;
+
+    let screen = frame.area();
+    let area = Rect {
+        x: screen.x + screen.width.saturating_sub(width) / 2,
+        y: screen.y + screen.height.saturating_sub(height) / 2,
+        width: width.max(18).min(screen.width),
+        height: height.min(screen.height),
+    };
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = names
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            if idx == picker.selected {
+                Line::from(Span::styled(
+                    format!("▸ {name}"),
+                    styles::selected_style(theme),
+                ))
+            } else {
+                Line::from(format!("  {name}"))
+            }
+        })
+        .collect();
+
+    let block = Block::default()
+        .title(" Theme ")
+        .borders(Borders::ALL)
+        .border_style(styles::border_style(theme, true));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
@@ -0,0 +1,40 @@
+use ratatui::text::{Line, Span};
+
+use crate::model::TodoMarker;
+use crate::model::todo_markers::marker_ranges;
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Render the TODO markers added by the diff, one line per marker, with the
+/// markers themselves picked out
+pub fn todo_lines(theme: &Theme, markers: &[TodoMarker]) -> Vec<Line<'static>> {
+    let width = markers
+        .iter()
+        .map(|marker| location(marker).chars().count())
+        .max()
+        .unwrap_or(0);
+    markers
+        .iter()
+        .map(|marker| {
+            let mut spans = vec![Span::styled(
+                format!("{:<width$}  ", location(marker)),
+                styles::dim_style(theme),
+            )];
+            let mut pos = 0;
+            for range in marker_ranges(&marker.text) {
+                spans.push(Span::raw(marker.text[pos..range.start].to_string()));
+                spans.push(Span::styled(
+                    marker.text[range.clone()].to_string(),
+                    styles::todo_marker_style(theme),
+                ));
+                pos = range.end;
+            }
+            spans.push(Span::raw(marker.text[pos..].to_string()));
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn location(marker: &TodoMarker) -> String {
+    format!("{}:{}", marker.path.display(), marker.line)
+}
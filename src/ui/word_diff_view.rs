@@ -0,0 +1,100 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::model::{DiffFile, LineOrigin, WordChange, diff_words};
+use crate::theme::Theme;
+use crate::ui::styles;
+
+/// Build the reflowed word diff of a file, one block per hunk.
+///
+/// Within a hunk, consecutive non-blank lines are joined into paragraphs on
+/// both sides, then the paragraphs are compared word by word. Removed words
+/// are struck through and added words are highlighted.
+pub fn word_diff_lines(theme: &Theme, file: &DiffFile) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let removed_style = styles::stat_del_style(theme).add_modifier(Modifier::CROSSED_OUT);
+    let added_style = styles::stat_add_style(theme).add_modifier(Modifier::BOLD);
+
+    for hunk in &file.hunks {
+        lines.push(Line::from(Span::styled(
+            hunk.header.clone(),
+            styles::diff_hunk_header_style(theme),
+        )));
+
+        let old_text = reflow(
+            hunk.lines
+                .iter()
+                .filter(|l| l.origin != LineOrigin::Addition)
+                .map(|l| l.content.as_str()),
+        );
+        let new_text = reflow(
+            hunk.lines
+                .iter()
+                .filter(|l| l.origin != LineOrigin::Deletion)
+                .map(|l| l.content.as_str()),
+        );
+
+        let mut spans = Vec::new();
+        for segment in diff_words(&old_text, &new_text) {
+            let style = match segment.change {
+                WordChange::Equal => Style::default(),
+                WordChange::Added => added_style,
+                WordChange::Removed => removed_style,
+            };
+            // Paragraph breaks become separate lines
+            for (idx, part) in segment.text.split('\n').enumerate() {
+                if idx > 0 {
+                    lines.push(Line::from(std::mem::take(&mut spans)));
+                }
+                if !part.is_empty() {
+                    spans.push(Span::styled(part.to_string(), style));
+                }
+            }
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::from(""));
+    }
+
+    lines
+}
+
+/// Join lines into paragraphs separated by a blank line ("\n\n")
+fn reflow<'a>(lines: impl Iterator<Item = &'a str>) -> String {
+    let mut text = String::new();
+    let mut paragraph_open = false;
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if paragraph_open {
+                text.push_str("\n\n");
+                paragraph_open = false;
+            }
+            continue;
+        }
+        if paragraph_open {
+            text.push(' ');
+        }
+        text.push_str(trimmed);
+        paragraph_open = true;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_join_wrapped_lines_into_paragraphs() {
+        // given
+        let lines = ["First line", "continues here.", "", "  Second paragraph."];
+
+        // when
+        let text = reflow(lines.into_iter());
+
+        // then
+        assert_eq!(text, "First line continues here.\n\nSecond paragraph.");
+    }
+}
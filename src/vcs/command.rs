@@ -0,0 +1,128 @@
+//! Running VCS subprocesses (hg, jj) without letting a hung command freeze
+//! the TUI: commands get no stdin, so they fail instead of prompting, are
+//! killed after a timeout, and can be aborted by the user.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, TuicrError};
+
+/// Seconds a command may run before it is killed, 0 for no limit
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT.as_secs());
+/// Tells whether the user asked to abort the running command
+static CANCEL_CHECK: OnceLock<fn() -> bool> = OnceLock::new();
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Longest wait between checks of a running command
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Kill VCS commands that run longer than `timeout` (zero for no limit)
+pub fn set_command_timeout(timeout: Duration) {
+    TIMEOUT_SECS.store(timeout.as_secs(), Ordering::Relaxed);
+}
+
+/// The limit set with [`set_command_timeout`], zero for none. Forge
+/// requests share it.
+pub fn command_timeout() -> Duration {
+    Duration::from_secs(TIMEOUT_SECS.load(Ordering::Relaxed))
+}
+
+/// Register how to tell that the user asked to abort a running command,
+/// checked while waiting for it. Only the first registration counts.
+pub fn set_cancel_check(check: fn() -> bool) {
+    let _ = CANCEL_CHECK.set(check);
+}
+
+/// Run `program` with `args` (in `dir` if given) like [`Command::output`],
+/// but fail once it exceeds the timeout or the user cancels it
+pub(super) fn run_output(program: &str, dir: Option<&Path>, args: &[&str]) -> Result<Output> {
+    let mut command = Command::new(program);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // jj runs git for fetches and pushes, which must not prompt either
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .spawn()
+        .map_err(|e| TuicrError::VcsCommand(format!("Failed to run {program}: {e}")))?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let timeout = Duration::from_secs(TIMEOUT_SECS.load(Ordering::Relaxed));
+    let started = Instant::now();
+    let mut interval = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let abort = if !timeout.is_zero() && started.elapsed() >= timeout {
+            Some(format!("timed out after {}s", timeout.as_secs()))
+        } else if CANCEL_CHECK.get().is_some_and(|cancelled| cancelled()) {
+            Some("was cancelled".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = abort {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TuicrError::VcsCommand(format!(
+                "{program} {} {reason}",
+                args.join(" ")
+            )));
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Drain a pipe on its own thread, so a command writing more than the pipe
+/// buffer holds does not block before it exits
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_collect_the_output_of_a_command() {
+        // when
+        let output = run_output("sh", None, &["-c", "echo out; echo err >&2"]).unwrap();
+
+        // then
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn should_not_give_commands_a_stdin_to_wait_on() {
+        // when
+        let output = run_output("sh", None, &["-c", "cat; echo done"]).unwrap();
+
+        // then
+        assert_eq!(output.stdout, b"done\n");
+    }
+}
@@ -6,7 +6,10 @@
 use std::path::PathBuf;
 
 use crate::error::{Result, TuicrError};
-use crate::model::{DiffFile, DiffHunk, DiffLine, FileStatus, LineOrigin};
+use crate::model::{
+    DiffFile, DiffHunk, DiffLine, FileStatus, LineOrigin, has_minified_lines,
+    mark_intraline_changes,
+};
 use crate::syntax::SyntaxHighlighter;
 
 /// Diff format variants for different VCS tools.
@@ -25,7 +28,8 @@ pub fn parse_unified_diff(
     highlighter: &SyntaxHighlighter,
 ) -> Result<Vec<DiffFile>> {
     let mut files: Vec<DiffFile> = Vec::new();
-    let mut lines = diff_text.lines().peekable();
+    // Split on LF only so content lines keep their CR and CRLF endings can be detected
+    let mut lines = diff_text.split_terminator('\n').peekable();
 
     let header_prefix = match format {
         DiffFormat::Hg => "diff ",
@@ -45,6 +49,8 @@ pub fn parse_unified_diff(
                     status,
                     hunks: Vec::new(),
                     is_binary: true,
+                    binary_sizes: Default::default(),
+                    minified: false,
                 });
                 continue;
             }
@@ -71,8 +77,10 @@ pub fn parse_unified_diff(
                 old_path,
                 new_path,
                 status,
+                minified: has_minified_lines(&hunks),
                 hunks,
                 is_binary: false,
+                binary_sizes: Default::default(),
             });
         }
     }
@@ -96,7 +104,8 @@ where
     let mut status = FileStatus::Modified;
 
     // Parse --- and +++ lines and metadata
-    while let Some(line) = lines.peek() {
+    while let Some(raw) = lines.peek() {
+        let line = raw.trim_end_matches('\r');
         if line.starts_with("---") {
             let path_str = line.trim_start_matches("--- ").trim_start_matches("a/");
             if path_str != "/dev/null" {
@@ -180,7 +189,7 @@ fn parse_hunk<'a, I>(
 where
     I: Iterator<Item = &'a str>,
 {
-    let header_line = lines.next()?;
+    let header_line = lines.next()?.trim_end_matches('\r');
 
     // Parse @@ -old_start,old_count +new_start,new_count @@
     let (old_start, old_count, new_start, new_count) = parse_hunk_header(header_line)?;
@@ -188,6 +197,8 @@ where
     let mut line_contents: Vec<String> = Vec::new();
     let mut line_origins: Vec<LineOrigin> = Vec::new();
     let mut line_numbers: Vec<(Option<u32>, Option<u32>)> = Vec::new();
+    let mut no_newline: Vec<bool> = Vec::new();
+    let mut line_crlf: Vec<bool> = Vec::new();
 
     let mut old_lineno = old_start;
     let mut new_lineno = new_start;
@@ -201,7 +212,10 @@ where
         let line = lines.next().unwrap();
 
         if line.starts_with('\\') {
-            // "\ No newline at end of file" - skip
+            // "\ No newline at end of file" applies to the preceding line
+            if let Some(last) = no_newline.last_mut() {
+                *last = true;
+            }
             continue;
         }
 
@@ -227,21 +241,28 @@ where
             old_lineno += 1;
             new_lineno += 1;
             (LineOrigin::Context, stripped, Some(old_ln), Some(new_ln))
-        } else if line.is_empty() {
+        } else if line.is_empty() || line == "\r" {
             // Empty line in diff (context line with no content after space)
             let old_ln = old_lineno;
             let new_ln = new_lineno;
             old_lineno += 1;
             new_lineno += 1;
-            (LineOrigin::Context, "", Some(old_ln), Some(new_ln))
+            (LineOrigin::Context, line, Some(old_ln), Some(new_ln))
         } else {
             // Unknown format, skip
             continue;
         };
 
+        let (content, crlf) = match content.strip_suffix('\r') {
+            Some(stripped) => (stripped, true),
+            None => (content, false),
+        };
+
         line_contents.push(content.to_string());
         line_origins.push(origin);
         line_numbers.push((old_ln, new_ln));
+        no_newline.push(false);
+        line_crlf.push(crlf);
     }
 
     // Apply syntax highlighting if we have a file path
@@ -265,17 +286,24 @@ where
             old_lineno,
             new_lineno,
             highlighted_spans,
+            no_newline_at_eof: no_newline[idx],
+            crlf: line_crlf[idx],
+            changed_ranges: Vec::new(),
         });
     }
 
-    Some(DiffHunk {
+    highlighter.apply_minified_chunks(&mut diff_lines);
+
+    let mut hunk = DiffHunk {
         header: header_line.to_string(),
         lines: diff_lines,
         old_start,
         old_count,
         new_start,
         new_count,
-    })
+    };
+    mark_intraline_changes(&mut hunk);
+    Some(hunk)
 }
 
 fn parse_hunk_header(line: &str) -> Option<(u32, u32, u32, u32)> {
@@ -635,6 +663,31 @@ copy to dest.rs
         assert_eq!(result[0].hunks[0].lines.len(), 2);
     }
 
+    #[test]
+    fn hg_should_flag_line_before_no_newline_marker() {
+        let diff = r#"diff -r abc123 eof.rs
+--- a/eof.rs
++++ b/eof.rs
+@@ -1,2 +1,2 @@
+ first
+-old
+\ No newline at end of file
++new
+"#;
+
+        let result =
+            parse_unified_diff(diff, DiffFormat::Hg, &SyntaxHighlighter::default()).unwrap();
+        let lines = &result[0].hunks[0].lines;
+
+        assert!(!lines[0].no_newline_at_eof);
+        assert!(lines[1].no_newline_at_eof);
+        assert!(!lines[2].no_newline_at_eof);
+        assert_eq!(
+            result[0].indicators(),
+            vec![crate::model::diff_types::FileIndicator::NewlineAtEofAdded]
+        );
+    }
+
     #[test]
     fn hg_should_parse_line_numbers_correctly() {
         let diff = r#"diff -r abc123 nums.rs
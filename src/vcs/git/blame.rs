@@ -0,0 +1,122 @@
+use chrono::{TimeZone, Utc};
+use git2::{Blame, BlameOptions, Oid, Repository};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Result, TuicrError};
+use crate::vcs::traits::BlameLine;
+
+/// Blame each line of a file at `revision`, or of the working tree file
+/// (uncommitted lines are `None`) when there is no revision.
+pub fn annotate_file(
+    repo: &Repository,
+    file_path: &Path,
+    revision: Option<&str>,
+) -> Result<Vec<Option<BlameLine>>> {
+    let mut opts = BlameOptions::new();
+    match revision {
+        Some(revision) => {
+            let commit = repo.revparse_single(revision)?.peel_to_commit()?;
+            opts.newest_commit(commit.id());
+            Ok(blame_lines(
+                repo,
+                &repo.blame_file(file_path, Some(&mut opts))?,
+            ))
+        }
+        None => {
+            let workdir = repo.workdir().ok_or(TuicrError::NotARepository)?;
+            let content = std::fs::read(workdir.join(file_path))?;
+            match repo.blame_file(file_path, Some(&mut opts)) {
+                Ok(committed) => Ok(blame_lines(repo, &committed.blame_buffer(&content)?)),
+                // Not in HEAD yet: nothing of it is committed
+                Err(_) => {
+                    let line_count = String::from_utf8_lossy(&content).lines().count();
+                    Ok(vec![None; line_count])
+                }
+            }
+        }
+    }
+}
+
+/// Expand blame hunks to one entry per line. Authors come from the commits:
+/// hunks of a buffer blame carry no signatures.
+fn blame_lines(repo: &Repository, blame: &Blame) -> Vec<Option<BlameLine>> {
+    let mut commits: HashMap<Oid, Option<BlameLine>> = HashMap::new();
+    let mut lines = Vec::new();
+    for hunk in blame.iter() {
+        let oid = hunk.final_commit_id();
+        let line = if oid.is_zero() {
+            None
+        } else {
+            commits
+                .entry(oid)
+                .or_insert_with(|| {
+                    let commit = repo.find_commit(oid).ok()?;
+                    let id = oid.to_string();
+                    Some(BlameLine {
+                        short_id: id[..7.min(id.len())].to_string(),
+                        author: commit.author().name().unwrap_or("Unknown").to_string(),
+                        time: Utc
+                            .timestamp_opt(commit.time().seconds(), 0)
+                            .single()
+                            .unwrap_or_else(Utc::now),
+                    })
+                })
+                .clone()
+        };
+        lines.extend(std::iter::repeat_n(line, hunk.lines_in_hunk()));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    fn commit_file(repo: &Repository, name: &str, content: &str) {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Ada", "ada@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "add", &tree, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn should_blame_committed_lines_and_leave_new_ones_empty() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "lib.rs", "one\ntwo\n");
+        std::fs::write(dir.path().join("lib.rs"), "one\nnew\ntwo\n").unwrap();
+
+        // when
+        let lines = annotate_file(&repo, Path::new("lib.rs"), None).unwrap();
+
+        // then
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].as_ref().unwrap().author, "Ada");
+        assert_eq!(lines[0].as_ref().unwrap().short_id.len(), 7);
+        assert!(lines[1].is_none());
+        assert_eq!(lines[2], lines[0]);
+    }
+
+    #[test]
+    fn should_leave_untracked_files_empty() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "lib.rs", "one\n");
+        std::fs::write(dir.path().join("new.rs"), "a\nb\n").unwrap();
+
+        // when
+        let lines = annotate_file(&repo, Path::new("new.rs"), None).unwrap();
+
+        // then
+        assert_eq!(lines, vec![None, None]);
+    }
+}
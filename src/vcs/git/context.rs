@@ -44,6 +44,9 @@ pub fn fetch_context_lines(
                 old_lineno: Some(line_num),
                 new_lineno: Some(line_num),
                 highlighted_spans: None,
+                no_newline_at_eof: false,
+                crlf: false,
+                changed_ranges: Vec::new(),
             });
         }
     }
@@ -1,22 +1,49 @@
-use git2::{Delta, Diff, DiffOptions, Repository};
+use git2::{Delta, Diff, DiffFindOptions, DiffOptions, Repository};
 use std::path::PathBuf;
 
 use crate::error::{Result, TuicrError};
-use crate::model::{DiffFile, DiffHunk, DiffLine, FileStatus, LineOrigin};
+use crate::model::{
+    BinarySizes, DiffFile, DiffHunk, DiffLine, FileStatus, LineOrigin, has_minified_lines,
+    mark_intraline_changes,
+};
 use crate::syntax::SyntaxHighlighter;
+use crate::vcs::traits::WhitespaceMode;
 
 pub fn get_working_tree_diff(
     repo: &Repository,
+    whitespace: WhitespaceMode,
     highlighter: &SyntaxHighlighter,
 ) -> Result<Vec<DiffFile>> {
     let head = repo.head()?.peel_to_tree()?;
 
-    let mut opts = DiffOptions::new();
+    let mut opts = diff_options(whitespace);
+    opts.include_untracked(true);
+    opts.show_untracked_content(true);
+    opts.recurse_untracked_dirs(true);
+
+    let mut diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut opts))?;
+    find_renames(&mut diff)?;
+
+    parse_diff(&diff, highlighter)
+}
+
+/// Get the diff of the working tree (including untracked files) against `base`,
+/// which may be any revision git understands (branch, tag, remote branch, ...).
+pub fn get_ref_diff(
+    repo: &Repository,
+    base: &str,
+    whitespace: WhitespaceMode,
+    highlighter: &SyntaxHighlighter,
+) -> Result<Vec<DiffFile>> {
+    let base_tree = repo.revparse_single(base)?.peel_to_tree()?;
+
+    let mut opts = diff_options(whitespace);
     opts.include_untracked(true);
     opts.show_untracked_content(true);
     opts.recurse_untracked_dirs(true);
 
-    let diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut opts))?;
+    let mut diff = repo.diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut opts))?;
+    find_renames(&mut diff)?;
 
     parse_diff(&diff, highlighter)
 }
@@ -27,6 +54,7 @@ pub fn get_working_tree_diff(
 pub fn get_commit_range_diff(
     repo: &Repository,
     commit_ids: &[String],
+    whitespace: WhitespaceMode,
     highlighter: &SyntaxHighlighter,
 ) -> Result<Vec<DiffFile>> {
     if commit_ids.is_empty() {
@@ -50,11 +78,40 @@ pub fn get_commit_range_diff(
 
     let new_tree = newest_commit.tree()?;
 
-    let diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), None)?;
+    let mut opts = diff_options(whitespace);
+    let mut diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(&mut opts))?;
+    find_renames(&mut diff)?;
 
     parse_diff(&diff, highlighter)
 }
 
+fn diff_options(whitespace: WhitespaceMode) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    match whitespace {
+        WhitespaceMode::Show => {}
+        WhitespaceMode::IgnoreTrailing => {
+            opts.ignore_whitespace_eol(true);
+        }
+        WhitespaceMode::IgnoreAll => {
+            opts.ignore_whitespace(true);
+        }
+        WhitespaceMode::IgnoreBlankLines => {
+            opts.ignore_blank_lines(true);
+        }
+    }
+    opts
+}
+
+/// Pair removed files with the added (or untracked) ones they were renamed
+/// to, and added files with the modified ones they were copied from, like
+/// `git diff -M -C`, so those files only show what changed in their content
+fn find_renames(diff: &mut Diff) -> Result<()> {
+    let mut opts = DiffFindOptions::new();
+    opts.renames(true).copies(true).for_untracked(true);
+    diff.find_similar(Some(&mut opts))?;
+    Ok(())
+}
+
 fn parse_diff(diff: &Diff, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
     let mut files: Vec<DiffFile> = Vec::new();
 
@@ -81,12 +138,23 @@ fn parse_diff(diff: &Diff, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFi
             parse_hunks(diff, delta_idx, file_path, highlighter)?
         };
 
+        let binary_sizes = if is_binary {
+            BinarySizes {
+                old: (status != FileStatus::Added).then(|| delta.old_file().size()),
+                new: (status != FileStatus::Deleted).then(|| delta.new_file().size()),
+            }
+        } else {
+            BinarySizes::default()
+        };
+
         files.push(DiffFile {
             old_path,
             new_path,
             status,
+            minified: has_minified_lines(&hunks),
             hunks,
             is_binary,
+            binary_sizes,
         });
     }
 
@@ -122,6 +190,9 @@ fn parse_hunks(
             // First, collect all line content for syntax highlighting
             let mut line_contents: Vec<String> = Vec::new();
             let mut line_origins: Vec<LineOrigin> = Vec::new();
+            let mut line_numbers: Vec<(Option<u32>, Option<u32>)> = Vec::new();
+            let mut no_newline: Vec<bool> = Vec::new();
+            let mut line_crlf: Vec<bool> = Vec::new();
 
             for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
                 let line = patch.line_in_hunk(hunk_idx, line_idx)?;
@@ -130,10 +201,19 @@ fn parse_hunks(
                     '+' => LineOrigin::Addition,
                     '-' => LineOrigin::Deletion,
                     ' ' => LineOrigin::Context,
+                    // "\ No newline at end of file" markers apply to the preceding line
+                    '=' | '>' | '<' => {
+                        if let Some(last) = no_newline.last_mut() {
+                            *last = true;
+                        }
+                        continue;
+                    }
                     _ => LineOrigin::Context,
                 };
 
-                let content = String::from_utf8_lossy(line.content())
+                let raw = line.content();
+                let crlf = raw.ends_with(b"\r\n") || raw.ends_with(b"\r");
+                let content = String::from_utf8_lossy(raw)
                     .trim_end_matches('\n')
                     .trim_end_matches('\r')
                     .replace('\t', "    ")
@@ -141,6 +221,9 @@ fn parse_hunks(
 
                 line_contents.push(content);
                 line_origins.push(origin);
+                line_numbers.push((line.old_lineno(), line.new_lineno()));
+                no_newline.push(false);
+                line_crlf.push(crlf);
             }
 
             // Apply syntax highlighting if we have a file path
@@ -151,12 +234,9 @@ fn parse_hunks(
             };
 
             // Now create DiffLines with syntax highlighting applied
-            for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
-                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
-                let old_lineno = line.old_lineno();
-                let new_lineno = line.new_lineno();
-                let content = line_contents[line_idx].clone();
+            for (line_idx, content) in line_contents.into_iter().enumerate() {
                 let origin = line_origins[line_idx];
+                let (old_lineno, new_lineno) = line_numbers[line_idx];
 
                 // Get highlighted spans and apply diff background
                 let highlighted_spans = if let Some(ref all_highlighted) = highlighted_lines {
@@ -173,17 +253,24 @@ fn parse_hunks(
                     old_lineno,
                     new_lineno,
                     highlighted_spans,
+                    no_newline_at_eof: no_newline[line_idx],
+                    crlf: line_crlf[line_idx],
+                    changed_ranges: Vec::new(),
                 });
             }
 
-            hunks.push(DiffHunk {
+            highlighter.apply_minified_chunks(&mut lines);
+
+            let mut hunk = DiffHunk {
                 header,
                 lines,
                 old_start,
                 old_count,
                 new_start,
                 new_count,
-            });
+            };
+            mark_intraline_changes(&mut hunk);
+            hunks.push(hunk);
         }
     }
 
@@ -210,4 +297,69 @@ mod tests {
         // then
         assert!(matches!(result, Err(TuicrError::NoChanges)));
     }
+
+    #[test]
+    fn should_leave_out_ignored_whitespace_changes() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("lib.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "add", &tree, &[])
+            .unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn a() {}   \nfn  b() {}\n").unwrap();
+        let highlighter = SyntaxHighlighter::default();
+
+        // when
+        let trailing = get_working_tree_diff(&repo, WhitespaceMode::IgnoreTrailing, &highlighter);
+        let all = get_working_tree_diff(&repo, WhitespaceMode::IgnoreAll, &highlighter);
+
+        // then
+        let changed: Vec<String> = trailing.unwrap()[0].hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.origin == LineOrigin::Addition)
+            .map(|line| line.content.clone())
+            .collect();
+        assert_eq!(changed, vec!["fn  b() {}"]);
+        assert!(all.map_or(true, |files| files.iter().all(|file| file.hunks.is_empty())));
+    }
+
+    #[test]
+    fn should_show_only_the_content_changes_of_renamed_files() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let content: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+        std::fs::write(dir.path().join("old.rs"), &content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("old.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "add", &tree, &[])
+            .unwrap();
+        std::fs::remove_file(dir.path().join("old.rs")).unwrap();
+        let edited = content.replace("line 10\n", "line ten\n");
+        std::fs::write(dir.path().join("new.rs"), edited).unwrap();
+        let highlighter = SyntaxHighlighter::default();
+
+        // when
+        let files = get_working_tree_diff(&repo, WhitespaceMode::Show, &highlighter).unwrap();
+
+        // then
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].status, FileStatus::Renamed);
+        assert_eq!(files[0].old_path, Some(PathBuf::from("old.rs")));
+        assert_eq!(files[0].new_path, Some(PathBuf::from("new.rs")));
+        let changed: Vec<&str> = files[0].hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.origin != LineOrigin::Context)
+            .map(|line| line.content.as_str())
+            .collect();
+        assert_eq!(changed, vec!["line 10", "line ten"]);
+    }
 }
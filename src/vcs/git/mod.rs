@@ -1,24 +1,27 @@
+pub mod blame;
 pub mod context;
 pub mod diff;
 pub mod repository;
+pub mod stage;
 
 use git2::Repository;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::error::{Result, TuicrError};
-use crate::model::{DiffFile, DiffLine, FileStatus};
+use crate::model::{DiffFile, DiffLine, FileStatus, ReviewAnchor};
 use crate::syntax::SyntaxHighlighter;
 
-use super::traits::{CommitInfo, VcsBackend, VcsInfo, VcsType};
+use super::traits::{BlameLine, CommitInfo, RefInfo, VcsBackend, VcsInfo, VcsType, WhitespaceMode};
 
 // Re-export commonly used functions
 pub use context::{calculate_gap, fetch_context_lines};
-pub use diff::{get_commit_range_diff, get_working_tree_diff};
+pub use diff::{get_commit_range_diff, get_ref_diff, get_working_tree_diff};
 
 /// Git backend implementation using git2 library
 pub struct GitBackend {
     repo: Repository,
     info: VcsInfo,
+    whitespace: WhitespaceMode,
 }
 
 impl GitBackend {
@@ -32,29 +35,38 @@ impl GitBackend {
             .ok_or(TuicrError::NotARepository)?
             .to_path_buf();
 
-        let head_commit = repo
-            .head()
-            .ok()
-            .and_then(|h| h.peel_to_commit().ok())
-            .map(|c| c.id().to_string())
-            .unwrap_or_else(|| "HEAD".to_string());
-
-        let branch_name = repo.head().ok().and_then(|h| {
-            if h.is_branch() {
-                h.shorthand().map(|s| s.to_string())
-            } else {
-                None
-            }
-        });
-
-        let info = VcsInfo {
-            root_path,
-            head_commit,
-            branch_name,
-            vcs_type: VcsType::Git,
-        };
-
-        Ok(Self { repo, info })
+        let info = read_info(&repo, root_path);
+
+        Ok(Self {
+            repo,
+            info,
+            whitespace: WhitespaceMode::Show,
+        })
+    }
+}
+
+/// Head commit and branch of `repo`
+fn read_info(repo: &Repository, root_path: PathBuf) -> VcsInfo {
+    let head_commit = repo
+        .head()
+        .ok()
+        .and_then(|h| h.peel_to_commit().ok())
+        .map(|c| c.id().to_string())
+        .unwrap_or_else(|| "HEAD".to_string());
+
+    let branch_name = repo.head().ok().and_then(|h| {
+        if h.is_branch() {
+            h.shorthand().map(|s| s.to_string())
+        } else {
+            None
+        }
+    });
+
+    VcsInfo {
+        root_path,
+        head_commit,
+        branch_name,
+        vcs_type: VcsType::Git,
     }
 }
 
@@ -63,8 +75,12 @@ impl VcsBackend for GitBackend {
         &self.info
     }
 
+    fn current_info(&self) -> Result<VcsInfo> {
+        Ok(read_info(&self.repo, self.info.root_path.clone()))
+    }
+
     fn get_working_tree_diff(&self, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
-        get_working_tree_diff(&self.repo, highlighter)
+        get_working_tree_diff(&self.repo, self.whitespace, highlighter)
     }
 
     fn fetch_context_lines(
@@ -91,11 +107,74 @@ impl VcsBackend for GitBackend {
             .collect())
     }
 
+    fn state_fingerprint(&self) -> Option<u64> {
+        repository::state_fingerprint(&self.repo).ok()
+    }
+
+    fn list_refs(&self) -> Result<Vec<RefInfo>> {
+        repository::list_refs(&self.repo)
+    }
+
+    fn list_authors(&self) -> Result<Vec<String>> {
+        repository::list_authors(&self.repo)
+    }
+
+    fn annotate_file(
+        &self,
+        file_path: &Path,
+        revision: Option<&str>,
+    ) -> Result<Vec<Option<BlameLine>>> {
+        blame::annotate_file(&self.repo, file_path, revision)
+    }
+
+    fn get_ref_diff(&self, base: &str, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
+        get_ref_diff(&self.repo, base, self.whitespace, highlighter)
+    }
+
+    fn resolve_commit_range(&self, range: &str) -> Result<Vec<String>> {
+        repository::resolve_commit_range(&self.repo, range)
+    }
+
     fn get_commit_range_diff(
         &self,
         commit_ids: &[String],
         highlighter: &SyntaxHighlighter,
     ) -> Result<Vec<DiffFile>> {
-        get_commit_range_diff(&self.repo, commit_ids, highlighter)
+        get_commit_range_diff(&self.repo, commit_ids, self.whitespace, highlighter)
+    }
+
+    fn file_at_revision(&self, rev: &str, path: &Path) -> Result<Vec<u8>> {
+        repository::file_at_revision(&self.repo, rev, path)
+    }
+
+    fn working_tree_anchor(&self) -> Result<ReviewAnchor> {
+        Ok(ReviewAnchor {
+            base: repository::resolve_revision(&self.repo, "HEAD")?,
+            head: None,
+        })
+    }
+
+    fn ref_anchor(&self, base: &str) -> Result<ReviewAnchor> {
+        Ok(ReviewAnchor {
+            base: repository::resolve_revision(&self.repo, base)?,
+            head: None,
+        })
+    }
+
+    fn commit_range_anchor(&self, commit_ids: &[String]) -> Result<ReviewAnchor> {
+        repository::commit_range_anchor(&self.repo, commit_ids)
+    }
+
+    fn set_whitespace_mode(&mut self, mode: WhitespaceMode) -> Result<()> {
+        self.whitespace = mode;
+        Ok(())
+    }
+
+    fn stage_patch(&self, patch: &str) -> Result<()> {
+        stage::stage_patch(&self.repo, patch)
+    }
+
+    fn staged_paths(&self) -> Result<Vec<PathBuf>> {
+        stage::staged_paths(&self.repo)
     }
 }
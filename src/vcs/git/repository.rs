@@ -1,7 +1,13 @@
 use chrono::{DateTime, TimeZone, Utc};
-use git2::Repository;
+use git2::{Oid, Repository, Sort, StatusOptions};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
 
-use crate::error::Result;
+use crate::error::{Result, TuicrError};
+use crate::hash::fnv1a_64;
+use crate::model::ReviewAnchor;
+use crate::vcs::traits::{RefInfo, RefKind};
 
 #[derive(Debug, Clone)]
 pub struct CommitInfo {
@@ -45,3 +51,232 @@ pub fn get_recent_commits(
 
     Ok(commits)
 }
+
+/// List local branches, remote branches and tags, each group sorted by name
+pub fn list_refs(repo: &Repository) -> Result<Vec<RefInfo>> {
+    let mut refs = Vec::new();
+    for reference in repo.references()? {
+        let reference = reference?;
+        let kind = if reference.is_branch() {
+            RefKind::Branch
+        } else if reference.is_remote() {
+            RefKind::RemoteBranch
+        } else if reference.is_tag() {
+            RefKind::Tag
+        } else {
+            continue;
+        };
+        let Some(name) = reference.shorthand() else {
+            continue;
+        };
+        // Symbolic refs like origin/HEAD duplicate another entry
+        if kind == RefKind::RemoteBranch && name.ends_with("/HEAD") {
+            continue;
+        }
+        let Ok(commit) = reference.peel_to_commit() else {
+            continue;
+        };
+        let id = commit.id().to_string();
+        refs.push(RefInfo {
+            name: name.to_string(),
+            kind,
+            short_id: id[..7.min(id.len())].to_string(),
+            summary: commit.summary().unwrap_or("(no message)").to_string(),
+        });
+    }
+    refs.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+    Ok(refs)
+}
+
+/// How many commits back from HEAD to look for authors
+const AUTHOR_SCAN_LIMIT: usize = 1000;
+
+/// Authors of recent commits on HEAD, sorted by commit count (descending)
+pub fn list_authors(repo: &Repository) -> Result<Vec<String>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for oid in revwalk.take(AUTHOR_SCAN_LIMIT) {
+        let commit = repo.find_commit(oid?)?;
+        if let Some(name) = commit.author().name() {
+            *counts.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    let mut authors: Vec<(String, usize)> = counts.into_iter().collect();
+    authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(authors.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Commits of a range like `git log main..feature`, oldest first: those
+/// reachable from the end but not from the start, either of which defaults
+/// to HEAD when left out. A single revision is just that commit.
+pub fn resolve_commit_range(repo: &Repository, range: &str) -> Result<Vec<String>> {
+    let resolve = |rev: &str| -> Result<Oid> {
+        let rev = if rev.is_empty() { "HEAD" } else { rev };
+        Ok(repo.revparse_single(rev)?.peel_to_commit()?.id())
+    };
+    if range.contains("...") {
+        return Err(TuicrError::UnsupportedOperation(
+            "Symmetric ranges (a...b) are not supported; use a..b".into(),
+        ));
+    }
+    let Some((start, end)) = range.split_once("..") else {
+        return Ok(vec![resolve(range)?.to_string()]);
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    revwalk.push(resolve(end)?)?;
+    revwalk.hide(resolve(start)?)?;
+    revwalk
+        .map(|oid| Ok(oid?.to_string()))
+        .collect::<Result<Vec<_>>>()
+}
+
+/// Git's id of the empty tree, the old side of the diff of a root commit
+const EMPTY_TREE_ID: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// The commit `rev` names, like `git rev-parse <rev>^{commit}`
+pub fn resolve_revision(repo: &Repository, rev: &str) -> Result<String> {
+    Ok(repo
+        .revparse_single(rev)?
+        .peel_to_commit()?
+        .id()
+        .to_string())
+}
+
+/// Content of `path` in the tree of commit `rev`
+pub fn file_at_revision(repo: &Repository, rev: &str, path: &Path) -> Result<Vec<u8>> {
+    let tree = repo.revparse_single(rev)?.peel_to_commit()?.tree()?;
+    let blob = tree.get_path(path)?.to_object(repo)?.peel_to_blob()?;
+    Ok(blob.content().to_vec())
+}
+
+/// The revisions the diff of `commit_ids` (oldest first) compares: the first
+/// parent of the oldest commit, or the empty tree, and the newest commit
+pub fn commit_range_anchor(repo: &Repository, commit_ids: &[String]) -> Result<ReviewAnchor> {
+    let (Some(oldest), Some(newest)) = (commit_ids.first(), commit_ids.last()) else {
+        return Err(TuicrError::NoChanges);
+    };
+    let oldest = repo.find_commit(Oid::from_str(oldest)?)?;
+    let base = match oldest.parent_ids().next() {
+        Some(parent) => parent.to_string(),
+        None => EMPTY_TREE_ID.to_string(),
+    };
+    Ok(ReviewAnchor {
+        base,
+        head: Some(newest.clone()),
+    })
+}
+
+/// Hash HEAD, the index and the size/mtime of every changed or untracked file.
+///
+/// This does not read file contents, so it is cheap enough to poll, but any
+/// edit, stage, commit or checkout changes the result.
+pub fn state_fingerprint(repo: &Repository) -> Result<u64> {
+    let mut bytes = Vec::new();
+
+    if let Ok(head) = repo.head() {
+        // The branch name too, so switching to a branch at the same commit
+        // counts as a change
+        bytes.extend_from_slice(head.name_bytes());
+        if let Some(target) = head.target() {
+            bytes.extend_from_slice(target.as_bytes());
+        }
+    }
+    if let Ok(meta) = std::fs::metadata(repo.path().join("index")) {
+        push_metadata(&mut bytes, &meta);
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let workdir = repo.workdir();
+    for entry in repo.statuses(Some(&mut opts))?.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.extend_from_slice(&entry.status().bits().to_le_bytes());
+        if let Some(meta) = workdir.and_then(|dir| std::fs::metadata(dir.join(path)).ok()) {
+            push_metadata(&mut bytes, &meta);
+        }
+    }
+
+    Ok(fnv1a_64(&bytes))
+}
+
+fn push_metadata(bytes: &mut Vec<u8>, meta: &std::fs::Metadata) {
+    bytes.extend_from_slice(&meta.len().to_le_bytes());
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    bytes.extend_from_slice(&modified.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    fn commit(repo: &Repository, message: &str) -> String {
+        let signature = Signature::now("Ada", "ada@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    #[test]
+    fn should_resolve_ranges_oldest_first() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let base = commit(&repo, "base");
+        let first = commit(&repo, "first");
+        let second = commit(&repo, "second");
+
+        // then
+        assert_eq!(
+            resolve_commit_range(&repo, &format!("{base}..HEAD")).unwrap(),
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(
+            resolve_commit_range(&repo, &format!("{base}..")).unwrap(),
+            vec![first.clone(), second]
+        );
+        assert_eq!(resolve_commit_range(&repo, "HEAD~1").unwrap(), vec![first]);
+    }
+
+    #[test]
+    fn should_anchor_commit_ranges_to_the_parent_of_the_oldest_commit() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let root = commit(&repo, "root");
+        let first = commit(&repo, "first");
+        let second = commit(&repo, "second");
+
+        // when
+        let anchor = commit_range_anchor(&repo, &[first, second.clone()]).unwrap();
+        let root_anchor = commit_range_anchor(&repo, std::slice::from_ref(&root)).unwrap();
+
+        // then
+        assert_eq!(anchor.base, root);
+        assert_eq!(anchor.head, Some(second));
+        assert_eq!(root_anchor.base, EMPTY_TREE_ID);
+        assert_eq!(resolve_revision(&repo, "HEAD~2").unwrap(), root);
+    }
+}
@@ -0,0 +1,151 @@
+use git2::{ApplyLocation, Diff, DiffOptions, Repository, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, TuicrError};
+
+/// Apply a unified diff against HEAD to the index, leaving the working tree
+/// alone. Changes staged earlier move the lines of the file in the index, so
+/// the hunks are shifted to match first.
+pub fn stage_patch(repo: &Repository, patch: &str) -> Result<()> {
+    let diff = Diff::from_buffer(patch.as_bytes())?;
+    let old_path = diff
+        .deltas()
+        .next()
+        .and_then(|delta| delta.old_file().path().map(Path::to_path_buf));
+    let patch = match old_path {
+        Some(path) => shift_hunks(patch, &staged_hunks(repo, &path)?),
+        None => patch.to_string(),
+    };
+    let diff = Diff::from_buffer(patch.as_bytes())?;
+    repo.apply(&diff, ApplyLocation::Index, None)
+        .map_err(|e| TuicrError::VcsCommand(format!("Patch does not apply to the index: {e}")))
+}
+
+/// Hunks of HEAD to index for one file, as (old start, old lines, new lines)
+fn staged_hunks(repo: &Repository, path: &Path) -> Result<Vec<(u32, u32, u32)>> {
+    let head = repo.head()?.peel_to_tree()?;
+    let mut opts = DiffOptions::new();
+    opts.pathspec(path)
+        .disable_pathspec_match(true)
+        .context_lines(0);
+    let diff = repo.diff_tree_to_index(Some(&head), None, Some(&mut opts))?;
+    let mut hunks = Vec::new();
+    diff.foreach(
+        &mut |_, _| true,
+        None,
+        Some(&mut |_, hunk| {
+            hunks.push((hunk.old_start(), hunk.old_lines(), hunk.new_lines()));
+            true
+        }),
+        None,
+    )?;
+    Ok(hunks)
+}
+
+/// Move both starts of each hunk header of `patch` by the lines that
+/// `staged` hunks ending before it add or remove
+fn shift_hunks(patch: &str, staged: &[(u32, u32, u32)]) -> String {
+    let mut shifted = String::with_capacity(patch.len());
+    for line in patch.split_inclusive('\n') {
+        let Some((old, new, rest)) = parse_hunk_header(line) else {
+            shifted.push_str(line);
+            continue;
+        };
+        let offset: i64 = staged
+            .iter()
+            .filter(|(old_start, old_lines, _)| old_start + old_lines <= old.0)
+            .map(|(_, old_lines, new_lines)| i64::from(*new_lines) - i64::from(*old_lines))
+            .sum();
+        let moved = |start: u32| (i64::from(start) + offset).max(0);
+        shifted.push_str(&format!(
+            "@@ -{},{} +{},{} @@{rest}",
+            moved(old.0),
+            old.1,
+            moved(new.0),
+            new.1
+        ));
+    }
+    shifted
+}
+
+/// A start line and line count of a hunk header
+type HunkRange = (u32, u32);
+
+/// The ranges of a `@@ -a,b +c,d @@` line and what follows them
+fn parse_hunk_header(line: &str) -> Option<(HunkRange, HunkRange, &str)> {
+    let (ranges, rest) = line.strip_prefix("@@ -")?.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |range: &str| -> Option<HunkRange> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    Some((range(old)?, range(new)?, rest))
+}
+
+/// Paths whose index entry differs from HEAD
+pub fn staged_paths(repo: &Repository) -> Result<Vec<PathBuf>> {
+    let staged = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE;
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false);
+    let statuses = repo.statuses(Some(&mut opts))?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| entry.status().intersects(staged))
+        .filter_map(|entry| entry.path().map(PathBuf::from))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::selection_patch;
+    use crate::syntax::SyntaxHighlighter;
+    use crate::vcs::git::get_working_tree_diff;
+    use crate::vcs::traits::WhitespaceMode;
+    use std::path::Path;
+
+    #[test]
+    fn should_stage_hunks_one_at_a_time() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let original: Vec<String> = (1..=20).map(|n| format!("line {n}")).collect();
+        std::fs::write(dir.path().join("lib.txt"), original.join("\n") + "\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.txt")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "add", &tree, &[])
+            .unwrap();
+        let mut changed = original.clone();
+        changed.insert(1, "inserted".to_string());
+        changed[18] = "edited".to_string();
+        let content = changed.join("\n") + "\n";
+        std::fs::write(dir.path().join("lib.txt"), &content).unwrap();
+        let files =
+            get_working_tree_diff(&repo, WhitespaceMode::Show, &SyntaxHighlighter::default())
+                .unwrap();
+        assert_eq!(files[0].hunks.len(), 2);
+
+        // when
+        let first = selection_patch(&files[0], |hunk_idx, _| hunk_idx == 0).unwrap();
+        stage_patch(&repo, &first).unwrap();
+        let staged_after_first = staged_paths(&repo).unwrap();
+        let second = selection_patch(&files[0], |hunk_idx, _| hunk_idx == 1).unwrap();
+        stage_patch(&repo, &second).unwrap();
+
+        // then
+        assert_eq!(staged_after_first, vec![PathBuf::from("lib.txt")]);
+        let index = repo.index().unwrap();
+        let entry = index.get_path(Path::new("lib.txt"), 0).unwrap();
+        let blob = repo.find_blob(entry.id).unwrap();
+        assert_eq!(std::str::from_utf8(blob.content()).unwrap(), content);
+        assert!(stage_patch(&repo, &second).is_err());
+    }
+}
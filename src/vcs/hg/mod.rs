@@ -1,17 +1,19 @@
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use chrono::{TimeZone, Utc};
 
 use crate::error::{Result, TuicrError};
-use crate::model::{DiffFile, DiffLine, FileStatus, LineOrigin};
+use crate::hash::fnv1a_64;
+use crate::model::{DiffFile, DiffLine, FileStatus, LineOrigin, ReviewAnchor};
 use crate::syntax::SyntaxHighlighter;
+use crate::vcs::command::run_output;
 use crate::vcs::diff_parser::{self, DiffFormat};
-use crate::vcs::traits::{CommitInfo, VcsBackend, VcsInfo, VcsType};
+use crate::vcs::traits::{BlameLine, CommitInfo, VcsBackend, VcsInfo, VcsType, WhitespaceMode};
 
 /// Mercurial backend implementation using hg CLI commands
 pub struct HgBackend {
     info: VcsInfo,
+    whitespace: WhitespaceMode,
 }
 
 impl HgBackend {
@@ -19,10 +21,7 @@ impl HgBackend {
     pub fn discover() -> Result<Self> {
         // Use `hg root` to find the repository root
         // This handles being called from subdirectories
-        let root_output = Command::new("hg")
-            .args(["root"])
-            .output()
-            .map_err(|e| TuicrError::VcsCommand(format!("Failed to run hg: {}", e)))?;
+        let root_output = run_output("hg", None, &["root"])?;
 
         if !root_output.status.success() {
             return Err(TuicrError::NotARepository);
@@ -38,23 +37,56 @@ impl HgBackend {
         // Canonicalize to resolve symlinks (e.g., /var -> /private/var on macOS)
         let root_path = root_path.canonicalize().unwrap_or(root_path);
 
-        // Get current revision info
-        let head_commit = run_hg_command(&root_path, &["id", "-i"])
-            .map(|s| s.trim().trim_end_matches('+').to_string())
-            .unwrap_or_else(|_| "unknown".to_string());
+        let info = read_info(root_path);
 
-        let branch_name = run_hg_command(&root_path, &["branch"])
-            .ok()
-            .map(|s| s.trim().to_string());
+        Ok(Self {
+            info,
+            whitespace: WhitespaceMode::Show,
+        })
+    }
+}
 
-        let info = VcsInfo {
-            root_path,
-            head_commit,
-            branch_name,
-            vcs_type: VcsType::Mercurial,
+impl HgBackend {
+    /// `args` of an `hg diff` command plus the flags of the whitespace mode
+    fn diff_args<'a>(&self, args: &[&'a str]) -> Vec<&'a str> {
+        let flag = match self.whitespace {
+            WhitespaceMode::Show => None,
+            WhitespaceMode::IgnoreTrailing => Some("--ignore-space-at-eol"),
+            WhitespaceMode::IgnoreAll => Some("--ignore-all-space"),
+            WhitespaceMode::IgnoreBlankLines => Some("--ignore-blank-lines"),
         };
+        args.iter().copied().chain(flag).collect()
+    }
 
-        Ok(Self { info })
+    /// The full node id of the first revision `revset` names
+    fn first_node(&self, revset: &str) -> Result<String> {
+        let output = run_hg_command(
+            &self.info.root_path,
+            &["log", "-r", revset, "--template", "{node}\\n"],
+        )?;
+        output
+            .lines()
+            .next()
+            .map(str::to_string)
+            .ok_or(TuicrError::NoChanges)
+    }
+}
+
+/// Current revision and branch of the repository at `root_path`
+fn read_info(root_path: PathBuf) -> VcsInfo {
+    let head_commit = run_hg_command(&root_path, &["id", "-i"])
+        .map(|s| s.trim().trim_end_matches('+').to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let branch_name = run_hg_command(&root_path, &["branch"])
+        .ok()
+        .map(|s| s.trim().to_string());
+
+    VcsInfo {
+        root_path,
+        head_commit,
+        branch_name,
+        vcs_type: VcsType::Mercurial,
     }
 }
 
@@ -63,9 +95,13 @@ impl VcsBackend for HgBackend {
         &self.info
     }
 
+    fn current_info(&self) -> Result<VcsInfo> {
+        Ok(read_info(self.info.root_path.clone()))
+    }
+
     fn get_working_tree_diff(&self, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
         // Get unified diff output from hg
-        let diff_output = run_hg_command(&self.info.root_path, &["diff"])?;
+        let diff_output = run_hg_command(&self.info.root_path, &self.diff_args(&["diff"]))?;
 
         if diff_output.trim().is_empty() {
             return Err(TuicrError::NoChanges);
@@ -74,6 +110,13 @@ impl VcsBackend for HgBackend {
         diff_parser::parse_unified_diff(&diff_output, DiffFormat::Hg, highlighter)
     }
 
+    fn state_fingerprint(&self) -> Option<u64> {
+        // The working copy parent plus the raw diff; no highlighting involved
+        let parent = run_hg_command(&self.info.root_path, &["id", "-i"]).ok()?;
+        let diff = run_hg_command(&self.info.root_path, &["diff"]).ok()?;
+        Some(fnv1a_64(format!("{parent}{diff}").as_bytes()))
+    }
+
     fn fetch_context_lines(
         &self,
         file_path: &Path,
@@ -112,6 +155,9 @@ impl VcsBackend for HgBackend {
                     old_lineno: Some(line_num),
                     new_lineno: Some(line_num),
                     highlighted_spans: None,
+                    no_newline_at_eof: false,
+                    crlf: false,
+                    changed_ranges: Vec::new(),
                 });
             }
         }
@@ -123,6 +169,9 @@ impl VcsBackend for HgBackend {
         // Use hg log with a template to get structured output
         // Template fields separated by \x00, records separated by \x01
         //
+        // Like git's history of HEAD, only ancestors of the working directory
+        // parent are listed, not other heads or branches.
+        //
         // hg log doesn't have a --skip option, so we fetch offset+limit commits
         // and skip the first `offset` in Rust code
         let fetch_count = offset + limit;
@@ -132,6 +181,8 @@ impl VcsBackend for HgBackend {
             &self.info.root_path,
             &[
                 "log",
+                "-r",
+                "reverse(::.)",
                 "-l",
                 &fetch_count.to_string(),
                 "--template",
@@ -176,6 +227,77 @@ impl VcsBackend for HgBackend {
         Ok(commits.into_iter().skip(offset).collect())
     }
 
+    fn annotate_file(
+        &self,
+        file_path: &Path,
+        revision: Option<&str>,
+    ) -> Result<Vec<Option<BlameLine>>> {
+        // Template fields separated by \x00, lines separated by \x01.
+        // Working directory changes get the all-f "wdir" node.
+        let template = "{lines % '{node|short}\\x00{user|person}\\x00{date|hgdate}\\x01'}";
+        let revision = revision.map_or_else(|| "wdir()".to_string(), |rev| rev.to_string());
+        let output = run_hg_command(
+            &self.info.root_path,
+            &[
+                "annotate",
+                "-r",
+                &revision,
+                "-T",
+                template,
+                &file_path.to_string_lossy(),
+            ],
+        )?;
+
+        Ok(output
+            .split('\x01')
+            .filter(|record| !record.trim().is_empty())
+            .map(|record| {
+                let parts: Vec<&str> = record.trim().split('\x00').collect();
+                if parts.len() < 3 || parts[0].starts_with("ffffffffffff") {
+                    return None;
+                }
+                let time = parts[2]
+                    .split_whitespace()
+                    .next()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+                    .unwrap_or_else(Utc::now);
+                Some(BlameLine {
+                    short_id: parts[0].to_string(),
+                    author: parts[1].to_string(),
+                    time,
+                })
+            })
+            .collect())
+    }
+
+    fn resolve_commit_range(&self, range: &str) -> Result<Vec<String>> {
+        // Revsets have no `a..b`; spell it the way git means it
+        let revset = match range.split_once("..") {
+            Some((start, end)) => {
+                let start = if start.is_empty() { "." } else { start };
+                let end = if end.is_empty() { "." } else { end };
+                format!("only({end}, {start})")
+            }
+            None => range.to_string(),
+        };
+        let output = run_hg_command(
+            &self.info.root_path,
+            &[
+                "log",
+                "-r",
+                &format!("sort({revset}, rev)"),
+                "--template",
+                "{node}\\n",
+            ],
+        )?;
+        let ids: Vec<String> = output.lines().map(str::to_string).collect();
+        if ids.is_empty() {
+            return Err(TuicrError::VcsCommand(format!("No commits in {range}")));
+        }
+        Ok(ids)
+    }
+
     fn get_commit_range_diff(
         &self,
         commit_ids: &[String],
@@ -226,7 +348,7 @@ impl VcsBackend for HgBackend {
 
         let diff_output = run_hg_command(
             &self.info.root_path,
-            &["diff", "-r", &from_rev, "-r", newest_short],
+            &self.diff_args(&["diff", "-r", &from_rev, "-r", newest_short]),
         )?;
 
         if diff_output.trim().is_empty() {
@@ -235,15 +357,56 @@ impl VcsBackend for HgBackend {
 
         diff_parser::parse_unified_diff(&diff_output, DiffFormat::Hg, highlighter)
     }
+
+    fn file_at_revision(&self, rev: &str, path: &Path) -> Result<Vec<u8>> {
+        let path = path.to_string_lossy();
+        run_hg_command_bytes(&self.info.root_path, &["cat", "-r", rev, &path])
+    }
+
+    fn working_tree_anchor(&self) -> Result<ReviewAnchor> {
+        Ok(ReviewAnchor {
+            base: self.first_node(".")?,
+            head: None,
+        })
+    }
+
+    fn commit_range_anchor(&self, commit_ids: &[String]) -> Result<ReviewAnchor> {
+        let (Some(oldest), Some(newest)) = (commit_ids.first(), commit_ids.last()) else {
+            return Err(TuicrError::NoChanges);
+        };
+        // A root commit has no parent; hg names the empty revision "null"
+        let base = match self.first_node(&format!("parents({oldest})")) {
+            Ok(node) => node,
+            Err(TuicrError::NoChanges) => "null".to_string(),
+            Err(e) => return Err(e),
+        };
+        Ok(ReviewAnchor {
+            base,
+            head: Some(newest.clone()),
+        })
+    }
+
+    fn set_whitespace_mode(&mut self, mode: WhitespaceMode) -> Result<()> {
+        self.whitespace = mode;
+        Ok(())
+    }
+
+    fn stage_patch(&self, _patch: &str) -> Result<()> {
+        Err(TuicrError::UnsupportedOperation(
+            "Mercurial has no staging area (see `hg commit --interactive`)".into(),
+        ))
+    }
 }
 
 /// Run an hg command and return its stdout
 fn run_hg_command(root: &Path, args: &[&str]) -> Result<String> {
-    let output = Command::new("hg")
-        .current_dir(root)
-        .args(args)
-        .output()
-        .map_err(|e| TuicrError::VcsCommand(format!("Failed to run hg: {}", e)))?;
+    let stdout = run_hg_command_bytes(root, args)?;
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like `run_hg_command`, for output that may not be text
+fn run_hg_command_bytes(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = run_output("hg", Some(root), args)?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -254,13 +417,14 @@ fn run_hg_command(root: &Path, args: &[&str]) -> Result<String> {
         )));
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    Ok(output.stdout)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
+    use std::process::Command;
 
     /// Check if hg command is available
     fn hg_available() -> bool {
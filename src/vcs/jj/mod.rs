@@ -1,19 +1,22 @@
 //! Jujutsu (jj) backend implementation using CLI commands.
 
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use chrono::{DateTime, Utc};
 
 use crate::error::{Result, TuicrError};
-use crate::model::{DiffFile, DiffLine, FileStatus, LineOrigin};
+use crate::hash::fnv1a_64;
+use crate::model::{DiffFile, DiffLine, FileStatus, LineOrigin, ReviewAnchor};
 use crate::syntax::SyntaxHighlighter;
+use crate::vcs::command::run_output;
 use crate::vcs::diff_parser::{self, DiffFormat};
-use crate::vcs::traits::{CommitInfo, VcsBackend, VcsInfo, VcsType};
+use crate::vcs::traits::{BlameLine, CommitInfo, VcsBackend, VcsInfo, VcsType, WhitespaceMode};
 
 /// Jujutsu backend implementation using jj CLI commands
 pub struct JjBackend {
     info: VcsInfo,
+    /// Only `Show` and `IgnoreAll`: `jj diff` has no flags for the others
+    whitespace: WhitespaceMode,
 }
 
 impl JjBackend {
@@ -21,10 +24,7 @@ impl JjBackend {
     pub fn discover() -> Result<Self> {
         // Use `jj root` to find the repository root
         // This handles being called from subdirectories
-        let root_output = Command::new("jj")
-            .args(["root"])
-            .output()
-            .map_err(|e| TuicrError::VcsCommand(format!("Failed to run jj: {}", e)))?;
+        let root_output = run_output("jj", None, &["root"])?;
 
         if !root_output.status.success() {
             return Err(TuicrError::NotARepository);
@@ -40,58 +40,94 @@ impl JjBackend {
         // Canonicalize to resolve symlinks (e.g., /var -> /private/var on macOS)
         let root_path = root_path.canonicalize().unwrap_or(root_path);
 
-        // Get current change id (jj uses change IDs rather than commit hashes)
-        let head_commit = run_jj_command(
-            &root_path,
-            &["log", "-r", "@", "--no-graph", "-T", "change_id.short()"],
-        )
-        .map(|s| s.trim().to_string())
-        .unwrap_or_else(|_| "unknown".to_string());
+        let info = read_info(root_path);
+
+        Ok(Self {
+            info,
+            whitespace: WhitespaceMode::Show,
+        })
+    }
+}
+
+impl JjBackend {
+    /// `args` of a `jj diff` command plus the flag of the whitespace mode
+    fn diff_args<'a>(&self, args: &[&'a str]) -> Vec<&'a str> {
+        let flag = (self.whitespace == WhitespaceMode::IgnoreAll).then_some("--ignore-all-space");
+        args.iter().copied().chain(flag).collect()
+    }
+
+    /// The change id of the first change `revset` names
+    fn first_change_id(&self, revset: &str) -> Result<String> {
+        let output = run_jj_command(
+            &self.info.root_path,
+            &[
+                "log",
+                "-r",
+                revset,
+                "--no-graph",
+                "-T",
+                r#"change_id ++ "\n""#,
+            ],
+        )?;
+        output
+            .lines()
+            .next()
+            .map(str::to_string)
+            .ok_or(TuicrError::NoChanges)
+    }
+}
 
-        // jj doesn't have branches in the traditional sense, but we can show the bookmark if set
-        // First check if @ has a bookmark directly, otherwise find the closest ancestor bookmark
-        let branch_name = run_jj_command(
+/// Current change and nearest bookmark of the repository at `root_path`
+fn read_info(root_path: PathBuf) -> VcsInfo {
+    // Get current change id (jj uses change IDs rather than commit hashes)
+    let head_commit = run_jj_command(
+        &root_path,
+        &["log", "-r", "@", "--no-graph", "-T", "change_id.short()"],
+    )
+    .map(|s| s.trim().to_string())
+    .unwrap_or_else(|_| "unknown".to_string());
+
+    // jj doesn't have branches in the traditional sense, but we can show the bookmark if set
+    // First check if @ has a bookmark directly, otherwise find the closest ancestor bookmark
+    let branch_name = run_jj_command(
+        &root_path,
+        &["log", "-r", "@", "--no-graph", "-T", "bookmarks"],
+    )
+    .ok()
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+    .or_else(|| {
+        // Find the closest bookmark in ancestors using heads(::@ & bookmarks())
+        run_jj_command(
             &root_path,
-            &["log", "-r", "@", "--no-graph", "-T", "bookmarks"],
+            &[
+                "log",
+                "-r",
+                "heads(::@ & bookmarks())",
+                "--no-graph",
+                "-T",
+                "bookmarks",
+                "--limit",
+                "1",
+            ],
         )
         .ok()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
-        .or_else(|| {
-            // Find the closest bookmark in ancestors using heads(::@ & bookmarks())
-            run_jj_command(
-                &root_path,
-                &[
-                    "log",
-                    "-r",
-                    "heads(::@ & bookmarks())",
-                    "--no-graph",
-                    "-T",
-                    "bookmarks",
-                    "--limit",
-                    "1",
-                ],
-            )
-            .ok()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-        })
-        // Extract first local bookmark (filter out remote tracking like "name@upstream")
-        .map(|s| {
-            s.split_whitespace()
-                .find(|b| !b.contains('@'))
-                .unwrap_or_else(|| s.split_whitespace().next().unwrap_or(&s))
-                .to_string()
-        });
-
-        let info = VcsInfo {
-            root_path,
-            head_commit,
-            branch_name,
-            vcs_type: VcsType::Jujutsu,
-        };
-
-        Ok(Self { info })
+    })
+    // Extract first local bookmark (filter out remote tracking like "name@upstream")
+    .map(|s| {
+        s.split_whitespace()
+            .find(|b| !b.contains('@'))
+            .unwrap_or_else(|| s.split_whitespace().next().unwrap_or(&s))
+            .to_string()
+    });
+
+    VcsInfo {
+        root_path,
+        head_commit,
+        branch_name,
+        vcs_type: VcsType::Jujutsu,
     }
 }
 
@@ -100,9 +136,14 @@ impl VcsBackend for JjBackend {
         &self.info
     }
 
+    fn current_info(&self) -> Result<VcsInfo> {
+        Ok(read_info(self.info.root_path.clone()))
+    }
+
     fn get_working_tree_diff(&self, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
         // Get unified diff output from jj using --git format
-        let diff_output = run_jj_command(&self.info.root_path, &["diff", "--git"])?;
+        let diff_output =
+            run_jj_command(&self.info.root_path, &self.diff_args(&["diff", "--git"]))?;
 
         if diff_output.trim().is_empty() {
             return Err(TuicrError::NoChanges);
@@ -111,6 +152,13 @@ impl VcsBackend for JjBackend {
         diff_parser::parse_unified_diff(&diff_output, DiffFormat::GitStyle, highlighter)
     }
 
+    fn state_fingerprint(&self) -> Option<u64> {
+        // jj snapshots the working copy on every command, so the raw diff
+        // reflects edits as well as new commits
+        let diff = run_jj_command(&self.info.root_path, &["diff", "--git"]).ok()?;
+        Some(fnv1a_64(diff.as_bytes()))
+    }
+
     fn fetch_context_lines(
         &self,
         file_path: &Path,
@@ -149,6 +197,9 @@ impl VcsBackend for JjBackend {
                     old_lineno: Some(line_num),
                     new_lineno: Some(line_num),
                     highlighted_spans: None,
+                    no_newline_at_eof: false,
+                    crlf: false,
+                    changed_ranges: Vec::new(),
                 });
             }
         }
@@ -159,18 +210,20 @@ impl VcsBackend for JjBackend {
     fn get_recent_commits(&self, offset: usize, limit: usize) -> Result<Vec<CommitInfo>> {
         // Use jj log with a template to get structured output
         // Template fields separated by \x00, records separated by \x01
-        // Note: jj uses change_id for identifying changes, commit_id for the underlying git commit
+        // Note: changes are listed by change_id, which survives rewrites of
+        // the underlying git commit (commit_id), e.g. by amending `@`. The
+        // root commit has nothing to diff against and is left out.
         //
         // jj log doesn't have a --skip option, so we fetch offset+limit commits
         // and skip the first `offset` in Rust code
         let fetch_count = offset + limit;
-        let template = r#"commit_id ++ "\x00" ++ commit_id.short() ++ "\x00" ++ description.first_line() ++ "\x00" ++ author.email() ++ "\x00" ++ committer.timestamp() ++ "\x01""#;
+        let template = r#"change_id ++ "\x00" ++ change_id.short() ++ "\x00" ++ description.first_line() ++ "\x00" ++ author.email() ++ "\x00" ++ committer.timestamp() ++ "\x01""#;
         let output = run_jj_command(
             &self.info.root_path,
             &[
                 "log",
                 "-r",
-                "::@",
+                "::@ ~ root()",
                 "--limit",
                 &fetch_count.to_string(),
                 "--no-graph",
@@ -213,6 +266,68 @@ impl VcsBackend for JjBackend {
         Ok(commits.into_iter().skip(offset).collect())
     }
 
+    fn annotate_file(
+        &self,
+        file_path: &Path,
+        revision: Option<&str>,
+    ) -> Result<Vec<Option<BlameLine>>> {
+        // One record per line; lines changed in the working copy commit are
+        // what git would call uncommitted, so they get an empty id
+        let template = r#"if(commit.current_working_copy(), "", commit.commit_id().short()) ++ "\x00" ++ commit.author().name() ++ "\x00" ++ commit.author().timestamp() ++ "\x01""#;
+        let output = run_jj_command(
+            &self.info.root_path,
+            &[
+                "file",
+                "annotate",
+                "-r",
+                revision.unwrap_or("@"),
+                "-T",
+                template,
+                &file_path.to_string_lossy(),
+            ],
+        )?;
+
+        Ok(output
+            .split('\x01')
+            .filter(|record| !record.trim().is_empty())
+            .map(|record| {
+                let parts: Vec<&str> = record.trim().split('\x00').collect();
+                if parts.len() < 3 || parts[0].is_empty() {
+                    return None;
+                }
+                let time = DateTime::parse_from_rfc3339(parts[2])
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now());
+                Some(BlameLine {
+                    short_id: parts[0].to_string(),
+                    author: parts[1].to_string(),
+                    time,
+                })
+            })
+            .collect())
+    }
+
+    fn resolve_commit_range(&self, range: &str) -> Result<Vec<String>> {
+        // Any revset works, `main..feature` included
+        let output = run_jj_command(
+            &self.info.root_path,
+            &[
+                "log",
+                "-r",
+                range,
+                "--reversed",
+                "--no-graph",
+                "-T",
+                r#"change_id ++ "\n""#,
+            ],
+        )?;
+        let ids: Vec<String> = output.lines().map(str::to_string).collect();
+        if ids.is_empty() {
+            return Err(TuicrError::VcsCommand(format!("No commits in {range}")));
+        }
+        Ok(ids)
+    }
+
     fn get_commit_range_diff(
         &self,
         commit_ids: &[String],
@@ -222,7 +337,7 @@ impl VcsBackend for JjBackend {
             return Err(TuicrError::NoChanges);
         }
 
-        // commit_ids are ordered from oldest to newest
+        // commit_ids are change ids ordered from oldest to newest
         let oldest = &commit_ids[0];
         let newest = commit_ids.last().unwrap();
 
@@ -230,14 +345,14 @@ impl VcsBackend for JjBackend {
         // In jj, we use {commit}- to get the parent(s)
         let diff_output = run_jj_command(
             &self.info.root_path,
-            &[
+            &self.diff_args(&[
                 "diff",
                 "--from",
                 &format!("{}-", oldest),
                 "--to",
                 newest,
                 "--git",
-            ],
+            ]),
         )?;
 
         if diff_output.trim().is_empty() {
@@ -246,15 +361,59 @@ impl VcsBackend for JjBackend {
 
         diff_parser::parse_unified_diff(&diff_output, DiffFormat::GitStyle, highlighter)
     }
+
+    fn file_at_revision(&self, rev: &str, path: &Path) -> Result<Vec<u8>> {
+        let path = path.to_string_lossy();
+        run_jj_command_bytes(&self.info.root_path, &["file", "show", "-r", rev, &path])
+    }
+
+    fn working_tree_anchor(&self) -> Result<ReviewAnchor> {
+        // The working copy is a change too, so it gets an id of its own
+        Ok(ReviewAnchor {
+            base: self.first_change_id("@-")?,
+            head: Some(self.first_change_id("@")?),
+        })
+    }
+
+    fn commit_range_anchor(&self, commit_ids: &[String]) -> Result<ReviewAnchor> {
+        let (Some(oldest), Some(newest)) = (commit_ids.first(), commit_ids.last()) else {
+            return Err(TuicrError::NoChanges);
+        };
+        Ok(ReviewAnchor {
+            base: self.first_change_id(&format!("{oldest}-"))?,
+            head: Some(newest.clone()),
+        })
+    }
+
+    fn set_whitespace_mode(&mut self, mode: WhitespaceMode) -> Result<()> {
+        match mode {
+            WhitespaceMode::Show | WhitespaceMode::IgnoreAll => {
+                self.whitespace = mode;
+                Ok(())
+            }
+            _ => Err(TuicrError::UnsupportedOperation(format!(
+                "jj diff cannot diff while {}",
+                mode.label()
+            ))),
+        }
+    }
+
+    fn stage_patch(&self, _patch: &str) -> Result<()> {
+        Err(TuicrError::UnsupportedOperation(
+            "jj has no staging area: the working copy is already a commit (see `jj split`)".into(),
+        ))
+    }
 }
 
 /// Run a jj command and return its stdout
 fn run_jj_command(root: &Path, args: &[&str]) -> Result<String> {
-    let output = Command::new("jj")
-        .current_dir(root)
-        .args(args)
-        .output()
-        .map_err(|e| TuicrError::VcsCommand(format!("Failed to run jj: {}", e)))?;
+    let stdout = run_jj_command_bytes(root, args)?;
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like `run_jj_command`, for output that may not be text
+fn run_jj_command_bytes(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = run_output("jj", Some(root), args)?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -265,13 +424,14 @@ fn run_jj_command(root: &Path, args: &[&str]) -> Result<String> {
         )));
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    Ok(output.stdout)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
+    use std::process::Command;
 
     /// Check if jj command is available
     fn jj_available() -> bool {
@@ -506,6 +666,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_jj_lists_changes_by_change_id() {
+        let Some(temp) = setup_test_repo_with_commits() else {
+            eprintln!("Skipping test: jj command not available");
+            return;
+        };
+
+        let backend =
+            JjBackend::from_path(temp.path().to_path_buf()).expect("Failed to create jj backend");
+
+        let commits = backend
+            .get_recent_commits(0, 10)
+            .expect("Failed to get commits");
+        let working_copy = run_jj_command(
+            temp.path(),
+            &["log", "-r", "@", "--no-graph", "-T", "change_id"],
+        )
+        .expect("Failed to get change id");
+        let range = backend
+            .resolve_commit_range("::@ ~ root()")
+            .expect("Failed to resolve range");
+
+        assert_eq!(commits[0].id, working_copy.trim());
+        assert_eq!(range.len(), commits.len());
+        assert_eq!(range.last(), Some(&commits[0].id));
+    }
+
     #[test]
     fn test_jj_get_commit_range_diff() {
         let Some(temp) = setup_test_repo_with_commits() else {
@@ -11,16 +11,22 @@
 //! are Git-backed and contain a `.git` directory. If jj detection fails, Git
 //! is tried next, then Mercurial.
 
+mod command;
 mod diff_parser;
 pub mod git;
 mod hg;
 mod jj;
+mod stdin;
 mod traits;
+mod watch;
 
+pub use command::{command_timeout, set_cancel_check, set_command_timeout};
 pub use git::GitBackend;
 pub use hg::HgBackend;
 pub use jj::JjBackend;
-pub use traits::{CommitInfo, VcsBackend, VcsInfo};
+pub use stdin::StdinBackend;
+pub use traits::{BlameLine, CommitInfo, RefInfo, VcsBackend, VcsInfo, VcsType, WhitespaceMode};
+pub use watch::FileWatcher;
 
 use crate::error::{Result, TuicrError};
 
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, TuicrError};
+use crate::hash::fnv1a_64;
+use crate::model::{DiffFile, DiffLine, FileStatus};
+use crate::syntax::SyntaxHighlighter;
+use crate::vcs::diff_parser::{self, DiffFormat};
+use crate::vcs::traits::{VcsBackend, VcsInfo, VcsType};
+
+/// Backend for a unified diff read from stdin (`git diff | tuicr -`).
+///
+/// There is no repository behind the diff, so the session is keyed by the
+/// hash of the diff text: piping the same diff again resumes its review.
+pub struct StdinBackend {
+    info: VcsInfo,
+    diff_text: String,
+}
+
+impl StdinBackend {
+    pub fn new(diff_text: String) -> Self {
+        let hash = format!("{:016x}", fnv1a_64(diff_text.as_bytes()));
+        let info = VcsInfo {
+            root_path: PathBuf::from(format!("stdin-{hash}")),
+            head_commit: hash,
+            branch_name: None,
+            vcs_type: VcsType::Stdin,
+        };
+        Self { info, diff_text }
+    }
+
+    /// Read the whole diff from stdin
+    pub fn from_stdin() -> Result<Self> {
+        let diff_text = std::io::read_to_string(std::io::stdin())?;
+        Ok(Self::new(diff_text))
+    }
+}
+
+impl VcsBackend for StdinBackend {
+    fn info(&self) -> &VcsInfo {
+        &self.info
+    }
+
+    fn get_working_tree_diff(&self, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
+        // `git diff` and `jj diff --git` output, or plain `hg diff`
+        let format = if self.diff_text.lines().any(|l| l.starts_with("diff --git ")) {
+            DiffFormat::GitStyle
+        } else {
+            DiffFormat::Hg
+        };
+        let files = diff_parser::parse_unified_diff(&self.diff_text, format, highlighter)?;
+        if files.is_empty() {
+            return Err(TuicrError::NoChanges);
+        }
+        Ok(files)
+    }
+
+    fn fetch_context_lines(
+        &self,
+        _file_path: &Path,
+        _file_status: FileStatus,
+        _start_line: u32,
+        _end_line: u32,
+    ) -> Result<Vec<DiffLine>> {
+        Err(TuicrError::UnsupportedOperation(
+            "No context beyond the diff when reading from stdin".into(),
+        ))
+    }
+
+    fn stage_patch(&self, _patch: &str) -> Result<()> {
+        Err(TuicrError::UnsupportedOperation(
+            "No repository to stage in when reading from stdin".into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = concat!(
+        "diff --git a/src/lib.rs b/src/lib.rs\n",
+        "index 1111111..2222222 100644\n",
+        "--- a/src/lib.rs\n",
+        "+++ b/src/lib.rs\n",
+        "@@ -1,2 +1,2 @@\n",
+        " fn main() {\n",
+        "-    old();\n",
+        "+    new();\n",
+    );
+
+    #[test]
+    fn should_parse_piped_git_diff() {
+        // given
+        let backend = StdinBackend::new(DIFF.to_string());
+
+        // when
+        let files = backend
+            .get_working_tree_diff(&SyntaxHighlighter::default())
+            .unwrap();
+
+        // then
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].display_path(), &PathBuf::from("src/lib.rs"));
+        assert_eq!(files[0].hunks[0].lines.len(), 3);
+    }
+
+    #[test]
+    fn should_key_session_by_diff_content() {
+        // given
+        let first = StdinBackend::new(DIFF.to_string());
+        let same = StdinBackend::new(DIFF.to_string());
+        let other = StdinBackend::new(DIFF.replace("new", "newer"));
+
+        // then
+        assert_eq!(first.info().root_path, same.info().root_path);
+        assert_eq!(first.info().head_commit, same.info().head_commit);
+        assert_ne!(first.info().head_commit, other.info().head_commit);
+    }
+
+    #[test]
+    fn should_report_no_changes_for_empty_input() {
+        let backend = StdinBackend::new(String::new());
+        assert!(matches!(
+            backend.get_working_tree_diff(&SyntaxHighlighter::default()),
+            Err(TuicrError::NoChanges)
+        ));
+    }
+
+    #[test]
+    fn should_refuse_to_touch_files_without_a_working_tree() {
+        // given
+        let backend = StdinBackend::new(DIFF.to_string());
+
+        // then
+        assert!(matches!(
+            backend.info().working_tree_path(Path::new("src/lib.rs")),
+            Err(TuicrError::UnsupportedOperation(_))
+        ));
+        assert!(matches!(
+            backend.stage_patch(DIFF),
+            Err(TuicrError::UnsupportedOperation(_))
+        ));
+    }
+}
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use std::path::{Path, PathBuf};
 
 use crate::error::Result;
-use crate::model::{DiffFile, DiffLine, FileStatus};
+use crate::model::{DiffFile, DiffLine, FileStatus, ReviewAnchor};
 use crate::syntax::SyntaxHighlighter;
 
 /// Information about the VCS type
@@ -11,6 +11,8 @@ pub enum VcsType {
     Git,
     Mercurial,
     Jujutsu,
+    /// A diff piped in on stdin, without a repository
+    Stdin,
 }
 
 impl std::fmt::Display for VcsType {
@@ -19,6 +21,7 @@ impl std::fmt::Display for VcsType {
             VcsType::Git => write!(f, "git"),
             VcsType::Mercurial => write!(f, "hg"),
             VcsType::Jujutsu => write!(f, "jj"),
+            VcsType::Stdin => write!(f, "stdin"),
         }
     }
 }
@@ -33,6 +36,45 @@ pub struct VcsInfo {
     pub vcs_type: VcsType,
 }
 
+impl VcsInfo {
+    /// The branch reviews are saved under. jj changes are reviewed one by
+    /// one rather than per bookmark, so theirs go by change id (the
+    /// `head_commit`) like reviews of a detached head.
+    pub fn session_branch(&self) -> Option<&str> {
+        match self.vcs_type {
+            VcsType::Jujutsu => None,
+            _ => self.branch_name.as_deref(),
+        }
+    }
+
+    /// Where `path`, relative to the root, is in the working tree. A diff
+    /// read from stdin has none to read or change files in.
+    pub fn working_tree_path(&self, path: &Path) -> Result<PathBuf> {
+        match self.vcs_type {
+            VcsType::Stdin => Err(crate::error::TuicrError::UnsupportedOperation(
+                "No working tree behind a diff read from stdin".into(),
+            )),
+            _ => Ok(self.root_path.join(path)),
+        }
+    }
+
+    /// The bookmark jj reviews were saved under before they went by change
+    /// id, to pick up reviews saved back then
+    pub fn legacy_session_branch(&self) -> Option<&str> {
+        match self.vcs_type {
+            VcsType::Jujutsu => self.branch_name.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether `other` is reviewed in the same session as this: on the same
+    /// branch, or at the same commit (change for jj) without one
+    pub fn same_review(&self, other: &VcsInfo) -> bool {
+        self.session_branch() == other.session_branch()
+            && (self.session_branch().is_some() || self.head_commit == other.head_commit)
+    }
+}
+
 /// Commit information for commit selection UI
 #[derive(Debug, Clone)]
 pub struct CommitInfo {
@@ -43,11 +85,89 @@ pub struct CommitInfo {
     pub time: DateTime<Utc>,
 }
 
+/// Who last changed a line, as shown in the blame gutter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub short_id: String,
+    pub author: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Which whitespace changes diffs leave out, cycled with `:set iw`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhitespaceMode {
+    #[default]
+    Show,
+    /// Ignore whitespace at the end of lines
+    IgnoreTrailing,
+    /// Ignore all whitespace differences within lines
+    IgnoreAll,
+    /// Ignore added or removed blank lines
+    IgnoreBlankLines,
+}
+
+impl WhitespaceMode {
+    /// Modes in cycling order
+    pub const ALL: &'static [WhitespaceMode] = &[
+        WhitespaceMode::Show,
+        WhitespaceMode::IgnoreTrailing,
+        WhitespaceMode::IgnoreAll,
+        WhitespaceMode::IgnoreBlankLines,
+    ];
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Show => "showing all whitespace changes",
+            Self::IgnoreTrailing => "ignoring trailing whitespace",
+            Self::IgnoreAll => "ignoring all whitespace",
+            Self::IgnoreBlankLines => "ignoring blank lines",
+        }
+    }
+}
+
+/// Kind of named ref offered as a comparison base
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RefKind {
+    Branch,
+    RemoteBranch,
+    Tag,
+}
+
+impl RefKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RefKind::Branch => "branch",
+            RefKind::RemoteBranch => "remote",
+            RefKind::Tag => "tag",
+        }
+    }
+}
+
+/// Named ref information for the ref picker UI
+#[derive(Debug, Clone)]
+pub struct RefInfo {
+    pub name: String,
+    pub kind: RefKind,
+    pub short_id: String,
+    pub summary: String,
+}
+
 /// Trait for VCS backend implementations
 pub trait VcsBackend: Send {
     /// Get repository information
     fn info(&self) -> &VcsInfo;
 
+    /// Repository information read afresh, e.g. after a branch switch.
+    /// Defaults to the information read when the backend was created.
+    fn current_info(&self) -> Result<VcsInfo> {
+        Ok(self.info().clone())
+    }
+
     /// Get the working tree diff (uncommitted changes)
     fn get_working_tree_diff(&self, highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>>;
 
@@ -67,6 +187,57 @@ pub trait VcsBackend: Send {
         Ok(Vec::new())
     }
 
+    /// Cheap fingerprint of the repository state (HEAD, index and working tree
+    /// files), used to notice that the loaded diff went stale.
+    /// Returns None if not supported (default).
+    fn state_fingerprint(&self) -> Option<u64> {
+        None
+    }
+
+    /// List branches, remote branches and tags, grouped by kind.
+    /// Returns empty vec if not supported (default).
+    fn list_refs(&self) -> Result<Vec<RefInfo>> {
+        Ok(Vec::new())
+    }
+
+    /// Commit authors, most active first (like `git shortlog -sn`).
+    /// Returns empty vec if not supported (default).
+    fn list_authors(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Who last changed each line of a file, indexed by line number - 1;
+    /// `None` for lines not committed yet. Annotates the file at `revision`,
+    /// or the working tree file when there is none.
+    /// Returns error if not supported (default).
+    fn annotate_file(
+        &self,
+        _file_path: &Path,
+        _revision: Option<&str>,
+    ) -> Result<Vec<Option<BlameLine>>> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Blame not supported for this VCS".into(),
+        ))
+    }
+
+    /// Get the diff of the working tree against the given ref.
+    /// Returns error if not supported (default).
+    fn get_ref_diff(&self, _base: &str, _highlighter: &SyntaxHighlighter) -> Result<Vec<DiffFile>> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Diff against a ref not supported for this VCS".into(),
+        ))
+    }
+
+    /// Commit ids of a range expression, oldest first, for
+    /// `get_commit_range_diff`. The syntax is the VCS's own (`main..feature`
+    /// for git, a revset for jj and hg).
+    /// Returns error if not supported (default).
+    fn resolve_commit_range(&self, _range: &str) -> Result<Vec<String>> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Commit ranges not supported for this VCS".into(),
+        ))
+    }
+
     /// Get diff for a commit range.
     /// Returns error if not supported (default).
     fn get_commit_range_diff(
@@ -78,6 +249,67 @@ pub trait VcsBackend: Send {
             "Commit range diff not supported for this VCS".into(),
         ))
     }
+
+    /// The revisions `get_working_tree_diff` compares, recorded with the
+    /// review so exports say what its line numbers refer to.
+    /// Returns error if not supported (default).
+    fn working_tree_anchor(&self) -> Result<ReviewAnchor> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Revision ids not supported for this VCS".into(),
+        ))
+    }
+
+    /// The revisions `get_ref_diff` compares for `base`.
+    /// Returns error if not supported (default).
+    fn ref_anchor(&self, _base: &str) -> Result<ReviewAnchor> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Revision ids not supported for this VCS".into(),
+        ))
+    }
+
+    /// The revisions `get_commit_range_diff` compares for `commit_ids`.
+    /// Returns error if not supported (default).
+    fn commit_range_anchor(&self, _commit_ids: &[String]) -> Result<ReviewAnchor> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Revision ids not supported for this VCS".into(),
+        ))
+    }
+
+    /// Content of `path` as of revision `rev`, e.g. to preview an image.
+    /// Returns error if not supported (default).
+    fn file_at_revision(&self, _rev: &str, _path: &Path) -> Result<Vec<u8>> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Reading files at a revision not supported for this VCS".into(),
+        ))
+    }
+
+    /// Leave the whitespace changes of `mode` out of the diffs returned from
+    /// now on. Returns error if the mode is not supported (default: only
+    /// `WhitespaceMode::Show` is).
+    fn set_whitespace_mode(&mut self, mode: WhitespaceMode) -> Result<()> {
+        if mode == WhitespaceMode::Show {
+            return Ok(());
+        }
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Ignoring whitespace not supported for this VCS".into(),
+        ))
+    }
+
+    /// Stage the changes of a unified diff (see `output::selection_patch`)
+    /// for the next commit, like `git add -p`.
+    /// Returns error if not supported (default).
+    fn stage_patch(&self, _patch: &str) -> Result<()> {
+        Err(crate::error::TuicrError::UnsupportedOperation(
+            "Staging not supported for this VCS".into(),
+        ))
+    }
+
+    /// Paths, relative to the repository root, with changes staged for the
+    /// next commit.
+    /// Returns empty vec if not supported (default).
+    fn staged_paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
 }
 
 #[cfg(test)]
@@ -136,6 +368,28 @@ mod tests {
         assert!(info.branch_name.is_none());
     }
 
+    #[test]
+    fn should_review_jj_changes_one_by_one() {
+        // given
+        let info = |vcs_type, head: &str| VcsInfo {
+            root_path: PathBuf::from("/repo"),
+            head_commit: head.to_string(),
+            branch_name: Some("main".to_string()),
+            vcs_type,
+        };
+
+        // then
+        assert!(info(VcsType::Git, "abc").same_review(&info(VcsType::Git, "def")));
+        assert!(!info(VcsType::Jujutsu, "abc").same_review(&info(VcsType::Jujutsu, "def")));
+        assert!(info(VcsType::Jujutsu, "abc").same_review(&info(VcsType::Jujutsu, "abc")));
+        assert_eq!(info(VcsType::Jujutsu, "abc").session_branch(), None);
+        assert_eq!(
+            info(VcsType::Jujutsu, "abc").legacy_session_branch(),
+            Some("main")
+        );
+        assert_eq!(info(VcsType::Git, "abc").legacy_session_branch(), None);
+    }
+
     #[test]
     fn commit_info_clone() {
         let commit = CommitInfo {
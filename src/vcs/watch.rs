@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::hash::Fnv1a64;
+
+/// How often the watcher thread looks at the working tree
+const WATCH_INTERVAL: Duration = Duration::from_millis(750);
+
+/// Watches a working tree from a background thread and signals when any
+/// file in it is created, removed or modified.
+///
+/// Changes are noticed from file sizes and modification times. VCS metadata
+/// directories (`.git`, `.hg`, `.jj`) and other hidden directories count as
+/// one entry each, so commits and staging are noticed through them without
+/// reading their contents. In a git working tree, paths matched by the
+/// ignore rules are skipped; elsewhere, build directories tagged with
+/// `CACHEDIR.TAG` (like cargo's `target`) are.
+pub struct FileWatcher {
+    changes: Receiver<()>,
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    pub fn spawn(root: PathBuf) -> Self {
+        let (sender, changes) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        thread::spawn(move || {
+            let repo = git2::Repository::open(&root).ok();
+            let mut last = tree_stamp(&root, repo.as_ref());
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(WATCH_INTERVAL);
+                let stamp = tree_stamp(&root, repo.as_ref());
+                if stamp != last {
+                    last = stamp;
+                    if sender.send(()).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Self { changes, stop }
+    }
+
+    /// Whether the tree changed since the last call
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Hash of the path, size and modification time of every watched entry
+fn tree_stamp(root: &Path, repo: Option<&git2::Repository>) -> u64 {
+    let mut hasher = Fnv1a64::default();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<_> = entries.flatten().collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if is_ignored(repo, root, &path, meta.is_dir()) {
+                continue;
+            }
+            hasher.write(path.as_os_str().as_encoded_bytes());
+            hasher.write(&meta.len().to_le_bytes());
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            hasher.write(&modified.to_le_bytes());
+
+            let hidden = entry.file_name().as_encoded_bytes().starts_with(b".");
+            if meta.is_dir() && !hidden {
+                dirs.push(path);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+fn is_ignored(repo: Option<&git2::Repository>, root: &Path, path: &Path, is_dir: bool) -> bool {
+    let Some(repo) = repo else {
+        return is_dir && path.join("CACHEDIR.TAG").exists();
+    };
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    // libgit2 reports its own metadata directory as ignored, but commits and
+    // staging are noticed through it
+    if relative == Path::new(".git") {
+        return false;
+    }
+    // A trailing slash lets directory-only patterns like `target/` match
+    let relative = if is_dir {
+        relative.join("")
+    } else {
+        relative.to_path_buf()
+    };
+    repo.is_path_ignored(&relative).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_notice_changed_files_but_not_build_output() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("target/CACHEDIR.TAG"), "").unwrap();
+        let before = tree_stamp(dir.path(), None);
+
+        // when
+        std::fs::write(dir.path().join("target/out.o"), "object").unwrap();
+        let after_build = tree_stamp(dir.path(), None);
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() { b() }\n").unwrap();
+        let after_edit = tree_stamp(dir.path(), None);
+
+        // then
+        assert_eq!(before, after_build);
+        assert_ne!(after_build, after_edit);
+    }
+
+    #[test]
+    fn should_skip_paths_ignored_by_git() {
+        // given
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "build/\n*.log\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("build")).unwrap();
+        std::fs::write(dir.path().join("main.c"), "int main;\n").unwrap();
+        let before = tree_stamp(dir.path(), Some(&repo));
+
+        // when
+        std::fs::write(dir.path().join("build/main.o"), "object").unwrap();
+        std::fs::write(dir.path().join("debug.log"), "trace").unwrap();
+        let after_build = tree_stamp(dir.path(), Some(&repo));
+        std::fs::write(dir.path().join("main.c"), "int main(void);\n").unwrap();
+        let after_edit = tree_stamp(dir.path(), Some(&repo));
+
+        // then
+        assert_eq!(before, after_build);
+        assert_ne!(after_build, after_edit);
+        assert!(!is_ignored(
+            Some(&repo),
+            dir.path(),
+            &dir.path().join(".git"),
+            true
+        ));
+    }
+}